@@ -7,10 +7,11 @@
 
 use std::cmp;
 use std::convert::TryInto;
-use std::num::Wrapping;
 
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
 use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::checksum::PcmMd5;
+use symphonia_core::codecs::state::DecoderState;
 use symphonia_core::codecs::{
     CodecDescriptor, CodecParameters, VerificationCheck, CODEC_TYPE_FLAC,
 };
@@ -20,13 +21,11 @@ use symphonia_core::formats::Packet;
 use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr};
 use symphonia_core::support_codec;
 use symphonia_core::units::TimeBase;
-use symphonia_core::util::bits::sign_extend_leq32_to_i32;
 use symphonia_utils_xiph::flac::metadata::StreamInfo;
 
 use log::{debug, log_enabled, warn};
 
 use super::frame::*;
-use super::validate::Validator;
 
 fn decorrelate_left_side(left: &[i32], side: &mut [i32]) {
     for (s, l) in side.iter_mut().zip(left) {
@@ -84,7 +83,7 @@ fn decorrelate_right_side(right: &[i32], side: &mut [i32]) {
 pub struct FlacDecoder {
     params: CodecParameters,
     is_validating: bool,
-    validator: Validator,
+    validator: PcmMd5,
     buf: AudioBuffer<i32>,
 }
 
@@ -237,14 +236,33 @@ impl Decoder for FlacDecoder {
         // No state is stored between packets, therefore do nothing.
     }
 
+    fn save_state(&self) -> Option<DecoderState> {
+        // No state is stored between packets. Note that the state of the MD5 validator, if
+        // verification is enabled, is not saved.
+        Some(DecoderState::new(CODEC_TYPE_FLAC, Box::new([])))
+    }
+
+    fn restore_state(&mut self, state: &DecoderState) -> Result<()> {
+        if state.codec != CODEC_TYPE_FLAC || !state.data.is_empty() {
+            return decode_error("flac: invalid decoder state");
+        }
+        Ok(())
+    }
+
     fn codec_params(&self) -> &CodecParameters {
         &self.params
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
         if let Err(e) = self.decode_inner(packet) {
             self.buf.clear();
-            Err(e)
+            Err(e.into_packet_error())
         }
         else {
             Ok(self.buf.as_audio_buffer_ref())
@@ -366,7 +384,7 @@ fn samples_shl(shift: u32, buf: &mut [i32]) {
 }
 
 fn decode_constant<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Result<()> {
-    let const_sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(bps)?, bps);
+    let const_sample = bs.read_bits_leq32_signed(bps)?;
 
     for sample in buf.iter_mut() {
         *sample = const_sample;
@@ -377,7 +395,7 @@ fn decode_constant<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Res
 
 fn decode_verbatim<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Result<()> {
     for sample in buf.iter_mut() {
-        *sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(bps)?, bps);
+        *sample = bs.read_bits_leq32_signed(bps)?;
     }
 
     Ok(())
@@ -420,13 +438,13 @@ fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i3
     }
 
     // QLP coefficients bit shift [-16, 15].
-    let qlp_coeff_shift = sign_extend_leq32_to_i32(bs.read_bits_leq32(5)?, 5);
+    let qlp_coeff_shift = bs.read_bits_leq32_signed(5)?;
 
     if qlp_coeff_shift >= 0 {
         let mut qlp_coeffs = [0i32; 32];
 
         for c in qlp_coeffs.iter_mut().rev().take(order as usize) {
-            *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
+            *c = bs.read_bits_leq32_signed(qlp_precision)?;
         }
 
         decode_residual(bs, order, buf)?;
@@ -556,7 +574,7 @@ fn decode_rice_partition<B: ReadBitsLtr>(
 
         // Read each binary encoded residual and store in buffer.
         for sample in buf.iter_mut() {
-            *sample = sign_extend_leq32_to_i32(bs.read_bits_leq32(residual_bits)?, residual_bits);
+            *sample = bs.read_bits_leq32_signed(residual_bits)?;
         }
     }
 
@@ -606,7 +624,28 @@ fn verify_rice_signed_to_i32() {
     assert_eq!(rice_signed_to_i32(9), -5);
     assert_eq!(rice_signed_to_i32(10), 5);
 
-    assert_eq!(rice_signed_to_i32(u32::max_value()), -2_147_483_648);
+    assert_eq!(rice_signed_to_i32(u32::MAX), -2_147_483_648);
+}
+
+/// Reconstructs a sample from its residual, and the prediction of a predictor.
+///
+/// Predictors accumulate in 64-bits, and for streams of up-to 32 bits per sample, the prediction
+/// may not fit in 32-bits even though the residual and the reconstructed sample always do.
+/// Therefore, the sample is reconstructed with wrapping 32-bit arithmetic, which yields the exact
+/// sample for all legal streams. If the `checked-arithmetic` feature is enabled, a reconstructed
+/// sample that does not fit in 32-bits panics instead.
+#[inline(always)]
+fn reconstruct(residual: i32, predicted: i64) -> i32 {
+    #[cfg(feature = "checked-arithmetic")]
+    {
+        let sample = i64::from(residual) + predicted;
+        assert!(
+            sample >= i64::from(i32::MIN) && sample <= i64::from(i32::MAX),
+            "flac: reconstructed sample overflowed"
+        );
+    }
+
+    residual.wrapping_add(predicted as i32)
 }
 
 fn fixed_predict(order: u32, buf: &mut [i32]) {
@@ -623,35 +662,35 @@ fn fixed_predict(order: u32, buf: &mut [i32]) {
         // s(i) = 1*s(i),
         1 => {
             for i in 1..buf.len() {
-                buf[i] += buf[i - 1];
+                buf[i] = reconstruct(buf[i], i64::from(buf[i - 1]));
             }
         }
         // A 2nd order predictor uses the polynomial: s(i) = 2*s(i-1) - 1*s(i-2).
         2 => {
             for i in 2..buf.len() {
-                let a = Wrapping(-1) * Wrapping(i64::from(buf[i - 2]));
-                let b = Wrapping(2) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b).0 as i32;
+                let a = -i64::from(buf[i - 2]);
+                let b = 2 * i64::from(buf[i - 1]);
+                buf[i] = reconstruct(buf[i], a + b);
             }
         }
         // A 3rd order predictor uses the polynomial: s(i) = 3*s(i-1) - 3*s(i-2) + 1*s(i-3).
         3 => {
             for i in 3..buf.len() {
-                let a = Wrapping(1) * Wrapping(i64::from(buf[i - 3]));
-                let b = Wrapping(-3) * Wrapping(i64::from(buf[i - 2]));
-                let c = Wrapping(3) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c).0 as i32;
+                let a = i64::from(buf[i - 3]);
+                let b = -3 * i64::from(buf[i - 2]);
+                let c = 3 * i64::from(buf[i - 1]);
+                buf[i] = reconstruct(buf[i], a + b + c);
             }
         }
         // A 4th order predictor uses the polynomial:
         // s(i) = 4*s(i-1) - 6*s(i-2) + 4*s(i-3) - 1*s(i-4).
         4 => {
             for i in 4..buf.len() {
-                let a = Wrapping(-1) * Wrapping(i64::from(buf[i - 4]));
-                let b = Wrapping(4) * Wrapping(i64::from(buf[i - 3]));
-                let c = Wrapping(-6) * Wrapping(i64::from(buf[i - 2]));
-                let d = Wrapping(4) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c + d).0 as i32;
+                let a = -i64::from(buf[i - 4]);
+                let b = 4 * i64::from(buf[i - 3]);
+                let c = -6 * i64::from(buf[i - 2]);
+                let d = 4 * i64::from(buf[i - 1]);
+                buf[i] = reconstruct(buf[i], a + b + c + d);
             }
         }
         _ => unreachable!(),
@@ -680,7 +719,7 @@ fn lpc_predict<const N: usize>(order: usize, coeffs: &[i32; N], coeff_shift: u32
             .map(|(&c, &sample)| c as i64 * sample as i64)
             .sum::<i64>();
 
-        buf[i] += (predicted >> coeff_shift) as i32;
+        buf[i] = reconstruct(buf[i], predicted >> coeff_shift);
     }
 
     // If the pre-fill operation filled the entire sample buffer, return immediately.
@@ -696,6 +735,66 @@ fn lpc_predict<const N: usize>(order: usize, coeffs: &[i32; N], coeff_shift: u32
             .map(|(&c, &s)| i64::from(c) * i64::from(s))
             .sum::<i64>();
 
-        buf[i] += (predicted >> coeff_shift) as i32;
+        buf[i] = reconstruct(buf[i], predicted >> coeff_shift);
     }
 }
+
+/// Generates extreme, but legal, 32-bit samples, and the residuals of a predictor for them. The
+/// predictions exceed 32-bits, but the residuals do not.
+#[cfg(test)]
+fn extreme_residuals(order: usize, predict: impl Fn(&[i64]) -> i64) -> (Vec<i32>, Vec<i32>) {
+    use std::convert::TryFrom;
+
+    const Q: i32 = 1 << 30;
+
+    let samples = [0, Q, i32::MAX, i32::MAX, Q, 0, -Q, i32::MIN, i32::MIN, -Q, 0];
+
+    let residuals = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            if i < order {
+                return s;
+            }
+
+            let history: Vec<i64> = samples[i - order..i].iter().map(|&s| i64::from(s)).collect();
+
+            i32::try_from(i64::from(s) - predict(&history)).unwrap()
+        })
+        .collect();
+
+    (samples.to_vec(), residuals)
+}
+
+#[test]
+fn verify_fixed_predict_extreme() {
+    // The polynomials of the fixed predictors, applied to the previous samples in order.
+    let polynomials: [&[i64]; 4] = [&[1], &[-1, 2], &[1, -3, 3], &[-1, 4, -6, 4]];
+
+    for (i, &polynomial) in polynomials.iter().enumerate() {
+        let order = i + 1;
+
+        let (samples, mut buf) = extreme_residuals(order, |history| {
+            history.iter().zip(polynomial).map(|(&s, &c)| c * s).sum()
+        });
+
+        fixed_predict(order as u32, &mut buf);
+        assert_eq!(buf, samples, "order {}", order);
+    }
+}
+
+#[test]
+fn verify_lpc_predict_extreme() {
+    // The 4th order fixed predictor, as a high-precision linear predictor.
+    let mut coeffs = [0i32; 32];
+    coeffs[28..].copy_from_slice(&[-1 << 11, 4 << 11, -6 << 11, 4 << 11]);
+
+    let shift = 11;
+
+    let (samples, mut buf) = extreme_residuals(4, |history| {
+        history.iter().zip(&coeffs[28..]).map(|(&s, &c)| i64::from(c) * s).sum::<i64>() >> shift
+    });
+
+    lpc_predict(4, &coeffs, shift, &mut buf);
+    assert_eq!(buf, samples);
+}
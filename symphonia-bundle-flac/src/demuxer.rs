@@ -373,3 +373,70 @@ fn read_stream_info_block<B: ReadBytes + FiniteStream>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+    use symphonia_core::codecs::{Decoder, DecoderOptions};
+    use symphonia_core::export::{ExportSink, FlacSink};
+    use symphonia_core::formats::FormatReader;
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::FlacReader;
+    use crate::FlacDecoder;
+
+    #[test]
+    fn verify_flac_sink_round_trip() {
+        let spec = SignalSpec::new(44_100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        // A buffer of 24-bit samples, spanning more than one FLAC block.
+        let mut buf = AudioBuffer::<i32>::new(5_000, spec);
+        buf.render_reserved(Some(5_000));
+
+        for (i, s) in buf.chan_mut(0).iter_mut().enumerate() {
+            *s = ((i as i32 * 7_919) % 0x80_0000 - 0x40_0000) << 8;
+        }
+        for (i, s) in buf.chan_mut(1).iter_mut().enumerate() {
+            *s = -(i as i32) << 8;
+        }
+
+        let mut sink = FlacSink::new(Cursor::new(Vec::new()), 24);
+        sink.begin(&spec).unwrap();
+        sink.write(&buf).unwrap();
+        sink.write(&buf).unwrap();
+        sink.finish().unwrap();
+
+        let data = sink.into_inner().into_inner();
+
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+        let mut reader = FlacReader::try_new(mss, &Default::default()).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.sample_rate, Some(44_100));
+        assert_eq!(params.bits_per_sample, Some(24));
+        assert_eq!(params.n_frames, Some(10_000));
+
+        let options = DecoderOptions { verify: true, ..Default::default() };
+        let mut decoder = FlacDecoder::try_new(params, &options).unwrap();
+
+        let mut decoded = vec![Vec::new(); 2];
+
+        while let Ok(packet) = reader.next_packet() {
+            let decoded_buf = decoder.decode(&packet).unwrap();
+
+            let mut out = decoded_buf.make_equivalent::<i32>();
+            decoded_buf.convert(&mut out);
+
+            decoded[0].extend_from_slice(out.chan(0));
+            decoded[1].extend_from_slice(out.chan(1));
+        }
+
+        for (ch, decoded) in decoded.iter().enumerate() {
+            assert_eq!(decoded, &[buf.chan(ch), buf.chan(ch)].concat());
+        }
+
+        assert_eq!(decoder.finalize().verify_ok, Some(true));
+    }
+}
@@ -18,7 +18,6 @@ mod decoder;
 mod demuxer;
 mod frame;
 mod parser;
-mod validate;
 
 pub use decoder::FlacDecoder;
 pub use demuxer::FlacReader;
@@ -39,14 +39,10 @@ impl<const N: usize> MovingAverage<N> {
             // If greater-than N values were pushed, then all samples must be averaged.
             self.samples.iter().sum::<usize>() / N
         }
-        else if self.count > 0 {
-            // If less-than N values were pushed, then only the first 0..N samples need to be
-            // averaged.
-            self.samples.iter().take(self.count).sum::<usize>() / self.count
-        }
         else {
-            // No samples.
-            0
+            // If less-than N values were pushed, then only the first 0..N samples need to be
+            // averaged. If no samples were pushed, the average is 0.
+            self.samples.iter().take(self.count).sum::<usize>().checked_div(self.count).unwrap_or(0)
         }
     }
 }
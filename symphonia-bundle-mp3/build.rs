@@ -0,0 +1,293 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generates the lookup tables used by the decoders.
+//!
+//! Floating point arithmetic is not yet permitted in a `const fn` for the minimum supported Rust
+//! version, so the tables are computed here and written to `OUT_DIR` as array expressions. Each
+//! table is then included into the source file that documents it. This avoids initializing the
+//! tables at runtime, and the cost of a `lazy_static` dereference on every access.
+
+// Justification: Some loops are better expressed without a range loop.
+#![allow(clippy::needless_range_loop)]
+
+use std::env;
+use std::f64;
+use std::fs;
+use std::path::Path;
+
+/// The number of fractional bits of a `Fixed`. Must match `Fixed::FRAC_BITS`.
+const FIXED_FRAC_BITS: u32 = 24;
+
+/// The range of layer 3 requantization exponents. Must match `REQUANTIZE_POW2_MIN_EXP` and
+/// `REQUANTIZE_POW2_MAX_EXP`.
+const REQUANTIZE_POW2_MIN_EXP: i32 = -326;
+const REQUANTIZE_POW2_MAX_EXP: i32 = 45;
+
+/// Converts a floating point number into the nearest `Fixed`. Must match `Fixed::from_f64`.
+fn to_fixed(value: f64) -> i32 {
+    let scaled = (value * f64::from(1u32 << FIXED_FRAC_BITS)).round();
+    scaled.max(f64::from(i32::MIN)).min(f64::from(i32::MAX)) as i32
+}
+
+/// Formats a `f32` such that it is parsed back into exactly the same value.
+fn fmt_f32(value: f32) -> String {
+    format!("{:?}", value)
+}
+
+/// Formats a `f32` as the nearest `Fixed`.
+fn fmt_fixed(value: f32) -> String {
+    format!("Fixed({})", to_fixed(f64::from(value)))
+}
+
+/// Formats a list of elements as an array expression.
+fn fmt_array<I: IntoIterator<Item = String>>(elems: I) -> String {
+    format!("[{}]", elems.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+/// Formats a list of pairs of `f32`s as an array expression of tuples.
+fn fmt_pairs(pairs: &[(f32, f32)], fmt: fn(f32) -> String) -> String {
+    fmt_array(pairs.iter().map(|&(a, b)| format!("({}, {})", fmt(a), fmt(b))))
+}
+
+/// Layer 1 dequantization factors. See `FACTOR` in `layer1/mod.rs`.
+fn layer1_factor() -> [f32; 16] {
+    let mut factor = [0f32; 16];
+
+    for (i, factor) in factor.iter_mut().enumerate().skip(2) {
+        let a = 1 << i;
+        let b = 1 << (i - 1);
+
+        *factor = (a as f32 / (a - 1) as f32) * (b as f32).recip();
+    }
+
+    factor
+}
+
+/// See `INTENSITY_STEREO_RATIOS_MPEG2` in `layer3/stereo.rs`.
+fn intensity_stereo_ratios_mpeg2() -> [[(f32, f32); 32]; 2] {
+    let is_scale: [f64; 2] = [1.0 / f64::sqrt(f64::consts::SQRT_2), f64::consts::FRAC_1_SQRT_2];
+
+    let mut ratios = [[(0.0, 0.0); 32]; 2];
+
+    for (i, is_pos) in (0..32).enumerate() {
+        if is_pos & 1 != 0 {
+            // Odd case.
+            ratios[0][i] = (is_scale[0].powf(f64::from(is_pos + 1) / 2.0) as f32, 1.0);
+            ratios[1][i] = (is_scale[1].powf(f64::from(is_pos + 1) / 2.0) as f32, 1.0);
+        }
+        else {
+            // Even & zero case.
+            ratios[0][i] = (1.0, is_scale[0].powf(f64::from(is_pos) / 2.0) as f32);
+            ratios[1][i] = (1.0, is_scale[1].powf(f64::from(is_pos) / 2.0) as f32);
+        }
+    }
+
+    ratios
+}
+
+/// See `INTENSITY_STEREO_RATIOS_MPEG1` in `layer3/stereo.rs`.
+fn intensity_stereo_ratios_mpeg1() -> [(f32, f32); 7] {
+    const PI_12: f64 = f64::consts::PI / 12.0;
+
+    let mut ratios = [(0.0, 0.0); 7];
+
+    for (is_pos, ratio) in ratios.iter_mut().enumerate() {
+        let is_ratio = (PI_12 * is_pos as f64).tan();
+        *ratio = ((is_ratio / (1.0 + is_ratio)) as f32, (1.0 / (1.0 + is_ratio)) as f32);
+    }
+
+    ratios[6] = (1.0, 0.0);
+
+    ratios
+}
+
+/// See `IMDCT_WINDOWS` in `layer3/hybrid_synthesis.rs`.
+fn imdct_windows() -> [[f32; 36]; 4] {
+    const PI_36: f64 = f64::consts::PI / 36.0;
+    const PI_12: f64 = f64::consts::PI / 12.0;
+
+    let mut windows = [[0f32; 36]; 4];
+
+    // Window for Long blocks.
+    for i in 0..36 {
+        windows[0][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
+    }
+
+    // Window for Start blocks (indicies 30..36 implictly 0.0).
+    for i in 0..18 {
+        windows[1][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
+    }
+    for i in 18..24 {
+        windows[1][i] = 1.0;
+    }
+    for i in 24..30 {
+        windows[1][i] = (PI_12 * ((i - 18) as f64 + 0.5)).sin() as f32;
+    }
+
+    // Window for Short blocks.
+    for i in 0..12 {
+        windows[2][i] = (PI_12 * (i as f64 + 0.5)).sin() as f32;
+    }
+
+    // Window for End blocks (indicies 0..6 implicitly 0.0).
+    for i in 6..12 {
+        windows[3][i] = (PI_12 * ((i - 6) as f64 + 0.5)).sin() as f32;
+    }
+    for i in 12..18 {
+        windows[3][i] = 1.0;
+    }
+    for i in 18..36 {
+        windows[3][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
+    }
+
+    windows
+}
+
+/// See `IMDCT_HALF_COS_12` in `layer3/hybrid_synthesis.rs`.
+fn imdct_half_cos_12() -> [[f32; 6]; 6] {
+    const PI_24: f64 = f64::consts::PI / 24.0;
+
+    let mut cos = [[0f32; 6]; 6];
+
+    for (i, cos_i) in cos.iter_mut().enumerate() {
+        for (k, cos_ik) in cos_i.iter_mut().enumerate() {
+            // Only compute the middle half of the cosine lookup table (i offset by 3).
+            let n = (2 * (i + 3) + (12 / 2) + 1) * (2 * k + 1);
+            *cos_ik = (PI_24 * n as f64).cos() as f32;
+        }
+    }
+
+    cos
+}
+
+/// See `ANTIALIAS_CS_CA` in `layer3/hybrid_synthesis.rs`.
+fn antialias_cs_ca() -> ([f32; 8], [f32; 8]) {
+    const C: [f64; 8] = [-0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037];
+
+    let mut cs = [0f32; 8];
+    let mut ca = [0f32; 8];
+
+    for i in 0..8 {
+        let sqrt = f64::sqrt(1.0 + (C[i] * C[i]));
+        cs[i] = (1.0 / sqrt) as f32;
+        ca[i] = (C[i] / sqrt) as f32;
+    }
+
+    (cs, ca)
+}
+
+/// See `REQUANTIZE_POW43` in `layer3/requantize.rs`.
+fn requantize_pow43() -> Vec<f32> {
+    (0..8207).map(|i| f32::powf(i as f32, 4.0 / 3.0)).collect()
+}
+
+/// See `REQUANTIZE_POW2` in `layer3/requantize.rs`.
+fn requantize_pow2() -> Vec<f32> {
+    (REQUANTIZE_POW2_MIN_EXP..=REQUANTIZE_POW2_MAX_EXP)
+        .map(|exp| f64::powf(2.0, 0.25 * f64::from(exp)) as f32)
+        .collect()
+}
+
+/// See `REQUANTIZE_POW43_FIXED` in `layer3/requantize.rs`.
+fn requantize_pow43_fixed() -> Vec<u32> {
+    let mut pow43 = vec![0u32; 8207];
+
+    for (i, pow43) in pow43.iter_mut().enumerate().skip(1) {
+        let value = f64::powf(i as f64, 4.0 / 3.0);
+
+        let mut exp = value.log2().floor() as i32 + 1;
+        let mut mantissa = (value * f64::powi(2.0, 27 - exp)).round() as u32;
+
+        // Rounding may carry the mantissa out of range.
+        if mantissa >= 1 << 27 {
+            mantissa >>= 1;
+            exp += 1;
+        }
+
+        *pow43 = (mantissa << 5) | exp as u32;
+    }
+
+    pow43
+}
+
+fn write_table(out_dir: &Path, name: &str, table: String) {
+    fs::write(out_dir.join(name), table).expect("failed to write lookup table");
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR is not set");
+    let out_dir = Path::new(&out_dir);
+
+    // Layer 1 tables.
+    write_table(
+        out_dir,
+        "layer1_factor.rs",
+        fmt_array(layer1_factor().iter().map(|&v| fmt_f32(v))),
+    );
+
+    // Layer 3 stereo tables.
+    let mpeg2 = intensity_stereo_ratios_mpeg2();
+    let mpeg1 = intensity_stereo_ratios_mpeg1();
+
+    write_table(
+        out_dir,
+        "intensity_stereo_ratios_mpeg2.rs",
+        fmt_array(mpeg2.iter().map(|ratios| fmt_pairs(ratios, fmt_f32))),
+    );
+    write_table(
+        out_dir,
+        "intensity_stereo_ratios_mpeg2_fixed.rs",
+        fmt_array(mpeg2.iter().map(|ratios| fmt_pairs(ratios, fmt_fixed))),
+    );
+    write_table(out_dir, "intensity_stereo_ratios_mpeg1.rs", fmt_pairs(&mpeg1, fmt_f32));
+    write_table(out_dir, "intensity_stereo_ratios_mpeg1_fixed.rs", fmt_pairs(&mpeg1, fmt_fixed));
+
+    // Layer 3 hybrid synthesis tables.
+    let windows = imdct_windows();
+    let cos12 = imdct_half_cos_12();
+    let (cs, ca) = antialias_cs_ca();
+
+    for &(suffix, fmt) in [("", fmt_f32 as fn(f32) -> String), ("_fixed", fmt_fixed)].iter() {
+        let fmt_row = |row: &[f32]| fmt_array(row.iter().map(|&v| fmt(v)));
+
+        write_table(
+            out_dir,
+            &format!("imdct_windows{}.rs", suffix),
+            fmt_array(windows.iter().map(|window| fmt_row(window))),
+        );
+        write_table(
+            out_dir,
+            &format!("imdct_half_cos_12{}.rs", suffix),
+            fmt_array(cos12.iter().map(|cos| fmt_row(cos))),
+        );
+        write_table(
+            out_dir,
+            &format!("antialias_cs_ca{}.rs", suffix),
+            format!("({}, {})", fmt_row(&cs), fmt_row(&ca)),
+        );
+    }
+
+    // Layer 3 requantization tables.
+    write_table(
+        out_dir,
+        "requantize_pow43.rs",
+        fmt_array(requantize_pow43().into_iter().map(fmt_f32)),
+    );
+    write_table(
+        out_dir,
+        "requantize_pow2.rs",
+        fmt_array(requantize_pow2().into_iter().map(fmt_f32)),
+    );
+    write_table(
+        out_dir,
+        "requantize_pow43_fixed.rs",
+        fmt_array(requantize_pow43_fixed().into_iter().map(|v| v.to_string())),
+    );
+}
@@ -5,11 +5,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::audio::{AudioBuffer, Channels, Layout, SignalSpec};
+use symphonia_core::audio::{Channels, Layout, SignalSpec};
 use symphonia_core::codecs::{CodecType, CODEC_TYPE_MP1, CODEC_TYPE_MP2, CODEC_TYPE_MP3};
-use symphonia_core::errors::Result;
 
-use symphonia_core::io::BufReader;
+use crate::header::MPEG_HEADER_LEN;
+
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+use symphonia_core::{audio::AudioBuffer, errors::Result, io::BufReader, sample::Sample};
+
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+use crate::synthesis::FromSynthesized;
 
 /// The MPEG audio version.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -86,26 +91,37 @@ pub enum Emphasis {
     Fifty15,
     /// CCIT J.17
     CcitJ17,
+    /// Reserved, decoded as no emphasis.
+    Reserved,
 }
 
 /// A MPEG 1, 2, or 2.5 audio frame header.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct FrameHeader {
+    /// The MPEG audio version.
     pub version: MpegVersion,
+    /// The MPEG audio layer.
     pub layer: MpegLayer,
+    /// The bit-rate in bits per second.
     pub bitrate: u32,
+    /// The sample rate in Hz.
     pub sample_rate: u32,
+    /// The index of the sample rate in the list of all sample rates supported by MPEG 1, 2, and
+    /// 2.5 audio, in descending order of version.
     pub sample_rate_idx: usize,
+    /// The channel mode.
     pub channel_mode: ChannelMode,
-    #[allow(dead_code)]
+    /// The emphasis applied during encoding.
     pub emphasis: Emphasis,
-    #[allow(dead_code)]
+    /// The audio is copyrighted.
     pub is_copyrighted: bool,
-    #[allow(dead_code)]
+    /// The audio is an original, not a copy.
     pub is_original: bool,
-    #[allow(dead_code)]
+    /// The frame contains an additional padding slot.
     pub has_padding: bool,
+    /// The frame header is followed by a CRC16 checksum.
     pub has_crc: bool,
+    /// The length of the frame in bytes, excluding the header.
     pub frame_size: usize,
 }
 
@@ -118,7 +134,6 @@ impl FrameHeader {
 
     /// Returns true if this a MPEG2.5 frame, false otherwise.
     #[inline(always)]
-    #[allow(dead_code)]
     pub fn is_mpeg2p5(&self) -> bool {
         self.version == MpegVersion::Mpeg2p5
     }
@@ -133,7 +148,6 @@ impl FrameHeader {
     }
 
     /// Returns a signal specification for the frame.
-    #[allow(dead_code)]
     pub fn spec(&self) -> SignalSpec {
         let layout = match self.n_channels() {
             1 => Layout::Mono,
@@ -153,6 +167,12 @@ impl FrameHeader {
         }
     }
 
+    /// Returns the total length of the frame in bytes, including the header.
+    #[inline(always)]
+    pub fn frame_len(&self) -> usize {
+        MPEG_HEADER_LEN + self.frame_size
+    }
+
     /// Returns the number of granules in the frame.
     #[inline(always)]
     pub fn n_granules(&self) -> usize {
@@ -169,7 +189,6 @@ impl FrameHeader {
     }
 
     /// Returns true if Intensity Stereo encoding is used, false otherwise.
-    #[allow(dead_code)]
     #[inline(always)]
     pub fn is_intensity_stereo(&self) -> bool {
         match self.channel_mode {
@@ -191,11 +210,16 @@ impl FrameHeader {
     }
 }
 
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
 pub trait Layer {
-    fn decode(
+    /// Decodes the audio frame described by `header` into `out`, converting the synthesized
+    /// samples into the output sample format.
+    fn decode<O>(
         &mut self,
         reader: &mut BufReader<'_>,
         header: &FrameHeader,
-        out: &mut AudioBuffer<f32>,
-    ) -> Result<()>;
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<f32>;
 }
@@ -5,12 +5,22 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal};
+use std::time::Duration;
+
+use symphonia_core::audio::SignalSpec;
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Layout, Signal};
+#[cfg(feature = "mp3")]
+use symphonia_core::codecs::budget::DecodeBudget;
+use symphonia_core::codecs::state::DecoderState;
+use symphonia_core::codecs::DualMonoPolicy;
+use symphonia_core::codecs::FinalizeResult;
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CodecType};
-use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::codecs::{DecodeWarning, DecodeWarningKind, Decoder, DecoderOptions};
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
-use symphonia_core::io::FiniteStream;
+use symphonia_core::io::{BufReader, FiniteStream};
+use symphonia_core::meta::Limit;
+use symphonia_core::sample::{Sample, SampleFormat};
 use symphonia_core::support_codec;
 
 #[cfg(feature = "mp1")]
@@ -29,6 +39,10 @@ use crate::layer2;
 #[cfg(feature = "mp3")]
 use crate::layer3;
 
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+use crate::synthesis::FromSynthesized;
+
 enum State {
     #[cfg(feature = "mp1")]
     Layer1(layer1::Layer1),
@@ -39,79 +53,608 @@ enum State {
 }
 
 impl State {
-    fn new(codec: CodecType) -> Self {
+    fn new(codec: CodecType, options: &MpaDecoderOptions) -> Self {
+        let dual_mono = options.dual_mono;
+
         match codec {
             #[cfg(feature = "mp1")]
-            CODEC_TYPE_MP1 => State::Layer1(layer1::Layer1::new()),
+            CODEC_TYPE_MP1 => State::Layer1(layer1::Layer1 { dual_mono, ..layer1::Layer1::new() }),
             #[cfg(feature = "mp2")]
-            CODEC_TYPE_MP2 => State::Layer2(layer2::Layer2::new()),
+            CODEC_TYPE_MP2 => State::Layer2(layer2::Layer2 { dual_mono, ..layer2::Layer2::new() }),
             #[cfg(feature = "mp3")]
-            CODEC_TYPE_MP3 => State::Layer3(Box::new(layer3::Layer3::new())),
+            CODEC_TYPE_MP3 => {
+                let mut layer = Box::new(layer3::Layer3::new());
+                layer.dual_mono = dual_mono;
+                layer.budget = options.layer3_budget();
+                State::Layer3(layer)
+            }
             _ => unreachable!(),
         }
     }
+
+    /// Resets the state to that of a newly instantiated decoder, without reallocating.
+    fn reset(&mut self) {
+        match self {
+            #[cfg(feature = "mp1")]
+            State::Layer1(layer) => layer.reset(),
+            #[cfg(feature = "mp2")]
+            State::Layer2(layer) => layer.reset(),
+            #[cfg(feature = "mp3")]
+            State::Layer3(layer) => layer.reset(),
+        }
+    }
+
+    /// Gets the warnings raised while decoding the last frame.
+    fn warnings(&self) -> &[DecodeWarning] {
+        match self {
+            #[cfg(feature = "mp3")]
+            State::Layer3(layer) => &layer.warnings,
+            #[allow(unreachable_patterns)]
+            _ => &[],
+        }
+    }
+
+    /// Gets the ancillary data collected while decoding the last frame.
+    fn ancillary(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "mp1")]
+            State::Layer1(layer) => &layer.ancillary,
+            #[cfg(feature = "mp2")]
+            State::Layer2(layer) => &layer.ancillary,
+            #[cfg(feature = "mp3")]
+            State::Layer3(layer) => &layer.ancillary,
+        }
+    }
+
+    /// Appends the state of the decoder to `buf`.
+    fn save(&self, buf: &mut Vec<u8>) {
+        match self {
+            #[cfg(feature = "mp1")]
+            State::Layer1(layer) => layer.save_state(buf),
+            #[cfg(feature = "mp2")]
+            State::Layer2(layer) => layer.save_state(buf),
+            #[cfg(feature = "mp3")]
+            State::Layer3(layer) => layer.save_state(buf),
+        }
+    }
+
+    /// Restores a state previously saved by `save` from `reader`.
+    fn restore(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        match self {
+            #[cfg(feature = "mp1")]
+            State::Layer1(layer) => layer.restore_state(reader),
+            #[cfg(feature = "mp2")]
+            State::Layer2(layer) => layer.restore_state(reader),
+            #[cfg(feature = "mp3")]
+            State::Layer3(layer) => layer.restore_state(reader),
+        }
+    }
 }
 
-/// MPEG1 and MPEG2 audio layer 1, 2, and 3 decoder.
-pub struct MpaDecoder {
-    params: CodecParameters,
-    state: State,
-    buf: AudioBuffer<f32>,
+/// Validates that `state` was saved by a decoder for `codec`, and then calls `restore` to restore
+/// the decoder specific data. The data must be fully consumed by `restore`.
+fn restore_state<F>(codec: CodecType, state: &DecoderState, restore: F) -> Result<()>
+where
+    F: FnOnce(&mut BufReader<'_>) -> Result<()>,
+{
+    if state.codec != codec {
+        return decode_error("mpa: decoder state is for a different codec");
+    }
+
+    let mut reader = BufReader::new(&state.data);
+
+    restore(&mut reader)?;
+
+    if reader.bytes_available() != 0 {
+        return decode_error("mpa: invalid decoder state length");
+    }
+
+    Ok(())
 }
 
-impl MpaDecoder {
-    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+/// Reads the frame header from `reader`, and prepares `buf` to receive the decoded audio frame. If
+/// `mono` is true, `buf` receives a single channel regardless of the channel mode of the frame.
+fn begin_frame<S: Sample>(
+    reader: &mut BufReader<'_>,
+    buf: &mut AudioBuffer<S>,
+    mono: bool,
+) -> Result<FrameHeader> {
+    let header = header::read_frame_header(reader)?;
+
+    // The packet should be the size stated in the header.
+    if header.frame_size != reader.bytes_available() as usize {
+        return decode_error("mpa: invalid packet length");
+    }
+
+    let spec = if mono {
+        SignalSpec::new_with_layout(header.sample_rate, Layout::Mono)
+    }
+    else {
+        header.spec()
+    };
+
+    // The audio buffer can only be created after the first frame is decoded.
+    if buf.is_unused() {
+        *buf = AudioBuffer::new(1152, spec);
+    }
+    else {
+        // Ensure the packet contains an audio frame with the same signal specification as the
+        // buffer.
+        //
+        // TODO: Is it worth it to support changing signal specifications?
+        if buf.spec() != &spec {
+            return decode_error("mpa: invalid audio buffer signal spec for packet");
+        }
+    }
+
+    // Clear the audio buffer.
+    buf.clear();
+
+    Ok(header)
+}
+
+/// The action taken by a decoder when the CRC of a frame does not match the frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CrcPolicy {
+    /// Do not verify the CRC.
+    Ignore,
+    /// Report the mismatch as a warning, and output the frame as decoded.
+    Warn,
+    /// Report the mismatch as a warning, and output silence in place of the frame.
+    Mute,
+    /// Report the mismatch as a warning, and output no audio for the frame.
+    Skip,
+    /// Return a decode error.
+    Error,
+}
+
+impl Default for CrcPolicy {
+    fn default() -> Self {
+        CrcPolicy::Warn
+    }
+}
+
+/// Options for instantiating a `MpaDecoder` or `Mp3FixedDecoder`.
+///
+/// Decoders instantiated with `Decoder::try_new` derive these options from the generic
+/// `DecoderOptions`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MpaDecoderOptions {
+    /// The action taken when the CRC of a layer 3 frame that is protected by one does not match.
+    /// Regardless of the policy, a CRC mismatch is an error in strict mode unless the CRC is
+    /// ignored. Default: `CrcPolicy::Warn`.
+    pub crc_policy: CrcPolicy,
+    /// Return an error if a frame violates the specification, instead of concealing the violation.
+    /// Default: `false`.
+    pub strict: bool,
+    /// Trim the decoded audio of each packet as indicated by the packet's `trim_start` and
+    /// `trim_end`. Demuxers only request trimming when gapless playback is enabled. Default: `true`.
+    pub enable_gapless: bool,
+    /// Downmix the decoded audio to a single channel. Default: `false`.
+    pub downmix_to_mono: bool,
+    /// The policy for outputting dual mono frames. Default: `DualMonoPolicy::Both`.
+    pub dual_mono: DualMonoPolicy,
+    /// The sample format of the decoded audio. `MpaDecoder` supports `F32`, `S16`, and `S32`.
+    /// `Mp3FixedDecoder` must be instantiated with the same sample format as its type parameter.
+    /// Default: `F32`.
+    pub sample_format: SampleFormat,
+    /// The maximum number of spectral samples Huffman decoded per layer 3 frame. Frames exceeding
+    /// the limit are rejected with a `LimitError`. The default never rejects a valid frame.
+    /// Default: `Limit::Default`.
+    pub limit_decode_iterations: Limit,
+    /// The maximum time spent decoding a single layer 3 frame. Frames exceeding the limit are
+    /// rejected with a `LimitError`. Default: `None`.
+    pub limit_decode_time: Option<Duration>,
+}
+
+impl Default for MpaDecoderOptions {
+    fn default() -> Self {
+        MpaDecoderOptions {
+            crc_policy: CrcPolicy::Warn,
+            strict: false,
+            enable_gapless: true,
+            downmix_to_mono: false,
+            dual_mono: DualMonoPolicy::Both,
+            sample_format: SampleFormat::F32,
+            limit_decode_iterations: Limit::Default,
+            limit_decode_time: None,
+        }
+    }
+}
+
+impl MpaDecoderOptions {
+    /// Derives the options from the generic decoder options for a decoder outputting samples of
+    /// type `S`.
+    fn from_generic<S: Sample>(options: &DecoderOptions) -> Self {
+        MpaDecoderOptions {
+            strict: options.strict,
+            downmix_to_mono: options.downmix_to_mono,
+            dual_mono: options.dual_mono,
+            sample_format: S::FORMAT,
+            limit_decode_iterations: options.limit_decode_iterations,
+            limit_decode_time: options.limit_decode_time,
+            ..Default::default()
+        }
+    }
+
+    /// Gets the decode budget of a layer 3 frame.
+    #[cfg(feature = "mp3")]
+    fn layer3_budget(&self) -> DecodeBudget {
+        DecodeBudget::with_limits(
+            self.limit_decode_iterations.limit_or_default(layer3::MAX_HUFFMAN_SAMPLES),
+            self.limit_decode_time,
+        )
+    }
+
+    /// Checks that the options are valid for a decoder outputting samples of type `S`.
+    #[cfg(feature = "fixed-point")]
+    fn validate<S: Sample>(&self) -> Result<()> {
+        if self.sample_format != S::FORMAT {
+            return unsupported_error("mpa: sample format does not match the decoder");
+        }
+        Ok(())
+    }
+}
+
+/// Gets the codec parameters of the decoded audio given the codec parameters of the stream.
+fn output_params(params: &CodecParameters, options: &MpaDecoderOptions) -> CodecParameters {
+    let mut params = params.clone();
+
+    if options.downmix_to_mono && params.channels.is_some() {
+        params.channels = Some(Layout::Mono.into_channels());
+    }
+
+    params
+}
+
+/// Returns an error for the first violation of the specification found while decoding a frame.
+/// Used in strict mode.
+fn check_strict(header: &FrameHeader, warnings: &[DecodeWarning]) -> Result<()> {
+    if header.emphasis == Emphasis::Reserved {
+        return decode_error("mpa: reserved emphasis");
+    }
+
+    // Missing data is not a violation since it is expected when decoding starts in the middle of
+    // a stream.
+    match warnings.iter().find(|warning| warning.kind != DecodeWarningKind::MissingData) {
+        Some(warning) => decode_error(warning.message),
+        None => Ok(()),
+    }
+}
+
+/// Statistics describing the health of the stream decoded by a `MpaDecoder` or
+/// `Mp3FixedDecoder`.
+///
+/// Statistics are accumulated over the lifetime of the decoder, and are not cleared when the
+/// decoder is reset.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MpaDecoderStats {
+    /// The number of frames decoded successfully.
+    pub frames_decoded: u64,
+    /// The number of frames that failed to decode.
+    pub frames_failed: u64,
+    /// The number of layer 3 frames with an invalid CRC. These frames are still decoded.
+    pub crc_failures: u64,
+    /// The number of layer 3 frames where the bit reservoir could not be used, and the decoder had
+    /// to resynchronize to the bitstream. This occurs when decoding starts in the middle of a
+    /// stream, or after a corrupt frame.
+    pub resyncs: u64,
+    /// The number of layer 3 granule channels where the count1 partition overran its length.
+    pub count1_overruns: u64,
+    /// The minimum bitrate, in bits per second, of all frames decoded successfully.
+    pub min_bitrate: Option<u32>,
+    /// The maximum bitrate, in bits per second, of all frames decoded successfully.
+    pub max_bitrate: Option<u32>,
+    /// The number of bytes of main data the last layer 3 frame reused from the bit reservoir.
+    pub reservoir_used: usize,
+    /// The maximum number of bytes of main data any layer 3 frame reused from the bit reservoir.
+    pub max_reservoir_used: usize,
+    /// The sum of the bitrates of all frames decoded successfully.
+    bitrate_sum: u64,
+}
+
+impl MpaDecoderStats {
+    /// Gets the average bitrate, in bits per second, of all frames decoded successfully.
+    pub fn avg_bitrate(&self) -> Option<u32> {
+        match self.frames_decoded {
+            0 => None,
+            n => Some((self.bitrate_sum / n) as u32),
+        }
+    }
+
+    /// Records a frame that was decoded successfully.
+    fn record_frame(&mut self, header: &FrameHeader) {
+        self.frames_decoded += 1;
+        self.bitrate_sum += u64::from(header.bitrate);
+        self.min_bitrate =
+            Some(self.min_bitrate.map_or(header.bitrate, |min| min.min(header.bitrate)));
+        self.max_bitrate =
+            Some(self.max_bitrate.map_or(header.bitrate, |max| max.max(header.bitrate)));
+    }
+
+    /// Records the outcome of decoding a layer 3 frame.
+    #[cfg(feature = "mp3")]
+    fn record_layer3<S: layer3::Layer3Sample>(&mut self, layer: &layer3::Layer3<S>) {
+        if layer.resynced {
+            self.resyncs += 1;
+        }
+
+        self.count1_overruns += layer.count1_overruns() as u64;
+        self.reservoir_used = layer.resevoir_used;
+        self.max_reservoir_used = self.max_reservoir_used.max(layer.resevoir_used);
+    }
+
+    /// Verifies the header, and the CRC unless ignored by `crc_policy`, of a layer 3 frame, and
+    /// records a warning for each problem. Returns true if the CRC did not match.
+    #[cfg(feature = "mp3")]
+    fn check_layer3_frame(
+        &mut self,
+        frame: &[u8],
+        header: &FrameHeader,
+        crc_policy: CrcPolicy,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> bool {
+        if header.emphasis == Emphasis::Reserved {
+            // The emphasis field is the last 2 bits of the frame header.
+            warnings.push(
+                DecodeWarning::new(DecodeWarningKind::MalformedBitstream, "mpa: reserved emphasis")
+                    .at(30),
+            );
+        }
+
+        if crc_policy == CrcPolicy::Ignore || layer3::is_crc_valid(frame, header) {
+            return false;
+        }
+
+        self.crc_failures += 1;
+
+        // The CRC immediately follows the frame header.
+        warnings.push(
+            DecodeWarning::new(DecodeWarningKind::MalformedBitstream, "mpa: frame crc mismatch")
+                .at(32),
+        );
+
+        true
+    }
+}
+
+/// Conceals the decoded audio of a frame that failed CRC verification as directed by `policy`.
+#[cfg(feature = "mp3")]
+fn conceal_crc_failure<S: Sample>(buf: &mut AudioBuffer<S>, policy: CrcPolicy) {
+    match policy {
+        CrcPolicy::Mute => {
+            let n_frames = buf.frames();
+            buf.clear();
+            buf.render_silence(Some(n_frames));
+        }
+        CrcPolicy::Skip => buf.clear(),
+        _ => (),
+    }
+}
+
+/// A PCM sample format that `MpaDecoder` can decode into.
+trait MpaOutputSample: Sample + FromSynthesized<f32> {}
+
+impl MpaOutputSample for f32 {}
+
+impl MpaOutputSample for i16 {}
+
+impl MpaOutputSample for i32 {}
+
+/// The audio buffer of a `MpaDecoder` in the sample format selected by its options.
+enum OutputBuffer {
+    F32(AudioBuffer<f32>),
+    S16(AudioBuffer<i16>),
+    S32(AudioBuffer<i32>),
+}
+
+macro_rules! impl_output_buffer_func {
+    ($var:expr, $buf:ident, $expr:expr) => {
+        match $var {
+            OutputBuffer::F32($buf) => $expr,
+            OutputBuffer::S16($buf) => $expr,
+            OutputBuffer::S32($buf) => $expr,
+        }
+    };
+}
+
+impl OutputBuffer {
+    /// Instantiates an unused audio buffer for samples of `format`.
+    fn new(format: SampleFormat) -> Result<Self> {
+        match format {
+            SampleFormat::F32 => Ok(OutputBuffer::F32(AudioBuffer::unused())),
+            SampleFormat::S16 => Ok(OutputBuffer::S16(AudioBuffer::unused())),
+            SampleFormat::S32 => Ok(OutputBuffer::S32(AudioBuffer::unused())),
+            _ => unsupported_error("mpa: unsupported sample format"),
+        }
+    }
+
+    fn clear(&mut self) {
+        impl_output_buffer_func!(self, buf, buf.clear())
+    }
+
+    fn as_audio_buffer_ref(&self) -> AudioBufferRef<'_> {
+        impl_output_buffer_func!(self, buf, buf.as_audio_buffer_ref())
+    }
+}
+
+/// Decodes a packet into `buf`, calling `on_granule` for each granule. See
+/// [`MpaDecoder::decode_granules`].
+fn decode_granules<S, F>(
+    state: &mut State,
+    stats: &mut MpaDecoderStats,
+    options: &MpaDecoderOptions,
+    packet: &Packet,
+    buf: &mut AudioBuffer<S>,
+    on_granule: &mut F,
+) -> Result<()>
+where
+    S: MpaOutputSample,
+    AudioBuffer<S>: AsAudioBufferRef,
+    F: FnMut(AudioBufferRef<'_>),
+{
+    #[cfg(feature = "mp3")]
+    #[allow(irrefutable_let_patterns)]
+    if let State::Layer3(layer) = state {
+        layer.warnings.clear();
+
         let mut reader = packet.as_buf_reader();
 
-        let header = header::read_frame_header(&mut reader)?;
+        let header = begin_frame(&mut reader, buf, options.downmix_to_mono)?;
 
-        // The packet should be the size stated in the header.
-        if header.frame_size != reader.bytes_available() as usize {
-            return decode_error("mpa: invalid packet length");
+        if header.layer != MpegLayer::Layer3 {
+            return decode_error("mpa: invalid mpeg audio layer");
         }
 
-        // The audio buffer can only be created after the first frame is decoded.
-        if self.buf.is_unused() {
-            self.buf = AudioBuffer::new(1152, header.spec());
+        let crc_policy = options.crc_policy;
+
+        let crc_failed =
+            stats.check_layer3_frame(packet.buf(), &header, crc_policy, &mut layer.warnings);
+
+        if crc_failed && crc_policy == CrcPolicy::Error {
+            return decode_error("mpa: frame crc mismatch");
         }
-        else {
-            // Ensure the packet contains an audio frame with the same signal specification as the
-            // buffer.
-            //
-            // TODO: Is it worth it to support changing signal specifications?
-            if self.buf.spec() != &header.spec() {
-                return decode_error("mpa: invalid audio buffer signal spec for packet");
-            }
+
+        let result = layer.read_frame(&mut reader, &header);
+
+        stats.record_layer3(layer);
+
+        result?;
+
+        if options.strict {
+            check_strict(&header, &layer.warnings)?;
         }
 
-        // Clear the audio buffer.
-        self.buf.clear();
+        stats.record_frame(&header);
 
-        // Choose the decode step based on the MPEG layer and the current codec type.
-        match &mut self.state {
-            #[cfg(feature = "mp1")]
-            State::Layer1(layer) if header.layer == MpegLayer::Layer1 => {
-                layer.decode(&mut reader, &header, &mut self.buf)?;
+        // The range of audio frames of the whole frame that remain after trimming.
+        let keep_start = packet.trim_start() as usize;
+        let keep_end = (576 * header.n_granules()).saturating_sub(packet.trim_end() as usize);
+
+        for gr in 0..header.n_granules() {
+            buf.clear();
+
+            layer.decode_granule(&header, gr, buf)?;
+
+            if crc_failed {
+                conceal_crc_failure(buf, crc_policy);
             }
-            #[cfg(feature = "mp2")]
-            State::Layer2(layer) if header.layer == MpegLayer::Layer2 => {
-                layer.decode(&mut reader, &header, &mut self.buf)?;
+
+            if options.enable_gapless {
+                let start = 576 * gr;
+                let end = start + 576;
+
+                buf.trim(keep_start.clamp(start, end) - start, end - keep_end.clamp(start, end));
             }
-            #[cfg(feature = "mp3")]
-            State::Layer3(layer) if header.layer == MpegLayer::Layer3 => {
-                layer.decode(&mut reader, &header, &mut self.buf)?;
+
+            on_granule(buf.as_audio_buffer_ref());
+        }
+
+        return Ok(());
+    }
+
+    decode_frame(state, stats, options, packet, buf)?;
+
+    on_granule(buf.as_audio_buffer_ref());
+
+    Ok(())
+}
+
+/// Decodes a packet into `buf`.
+fn decode_frame<S: MpaOutputSample>(
+    state: &mut State,
+    stats: &mut MpaDecoderStats,
+    options: &MpaDecoderOptions,
+    packet: &Packet,
+    buf: &mut AudioBuffer<S>,
+) -> Result<()> {
+    // Warnings only apply to the last packet decoded.
+    #[cfg(feature = "mp3")]
+    #[allow(irrefutable_let_patterns)]
+    if let State::Layer3(layer) = state {
+        layer.warnings.clear();
+    }
+
+    // An empty packet contains no audio.
+    if packet.buf().is_empty() {
+        buf.clear();
+        return Ok(());
+    }
+
+    let mut reader = packet.as_buf_reader();
+
+    let header = begin_frame(&mut reader, buf, options.downmix_to_mono)?;
+
+    // Choose the decode step based on the MPEG layer and the current codec type.
+    match state {
+        #[cfg(feature = "mp1")]
+        State::Layer1(layer) if header.layer == MpegLayer::Layer1 => {
+            layer.decode(&mut reader, &header, buf)?;
+        }
+        #[cfg(feature = "mp2")]
+        State::Layer2(layer) if header.layer == MpegLayer::Layer2 => {
+            layer.decode(&mut reader, &header, buf)?;
+        }
+        #[cfg(feature = "mp3")]
+        State::Layer3(layer) if header.layer == MpegLayer::Layer3 => {
+            let crc_policy = options.crc_policy;
+
+            let crc_failed =
+                stats.check_layer3_frame(packet.buf(), &header, crc_policy, &mut layer.warnings);
+
+            if crc_failed && crc_policy == CrcPolicy::Error {
+                return decode_error("mpa: frame crc mismatch");
+            }
+
+            // The frame is always decoded, even if it will be concealed, since it may
+            // contribute main data to the bit reservoir of the following frames.
+            let result = layer.decode(&mut reader, &header, buf);
+
+            stats.record_layer3(layer);
+
+            result?;
+
+            if crc_failed {
+                conceal_crc_failure(buf, crc_policy);
             }
-            _ => return decode_error("mpa: invalid mpeg audio layer"),
         }
+        _ => return decode_error("mpa: invalid mpeg audio layer"),
+    }
 
-        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+    if options.strict {
+        check_strict(&header, state.warnings())?;
+    }
 
-        Ok(())
+    stats.record_frame(&header);
+
+    if options.enable_gapless {
+        buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
     }
+
+    Ok(())
 }
 
-impl Decoder for MpaDecoder {
-    fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+/// MPEG1 and MPEG2 audio layer 1, 2, and 3 decoder.
+///
+/// By default, the decoded audio is output as `f32` samples. Applications that require `i16` or
+/// `i32` samples may select them with `MpaDecoderOptions::sample_format`, in which case the output
+/// of the polyphase synthesis filterbank is scaled and clamped directly into the output buffer.
+/// This avoids a separate conversion pass and intermediate buffer, and produces the same samples as
+/// converting the `f32` output with `symphonia-core`.
+pub struct MpaDecoder {
+    params: CodecParameters,
+    state: State,
+    buf: OutputBuffer,
+    stats: MpaDecoderStats,
+    options: MpaDecoderOptions,
+}
+
+impl MpaDecoder {
+    /// Instantiates the decoder with options specific to this decoder.
+    pub fn try_new_with_options(
+        params: &CodecParameters,
+        options: &MpaDecoderOptions,
+    ) -> Result<Self> {
         // This decoder only supports MP1, MP2, and MP3.
         match params.codec {
             #[cfg(feature = "mp1")]
@@ -123,10 +666,83 @@ impl Decoder for MpaDecoder {
             _ => return unsupported_error("mpa: invalid codec type"),
         }
 
+        let buf = OutputBuffer::new(options.sample_format)?;
+
         // Create decoder state.
-        let state = State::new(params.codec);
+        let state = State::new(params.codec, options);
+
+        Ok(MpaDecoder {
+            params: output_params(params, options),
+            state,
+            buf,
+            stats: Default::default(),
+            options: *options,
+        })
+    }
+
+    /// Gets the statistics of the stream decoded so far.
+    pub fn stats(&self) -> &MpaDecoderStats {
+        &self.stats
+    }
+
+    /// Clears the statistics of the stream decoded so far.
+    pub fn clear_stats(&mut self) {
+        self.stats = Default::default();
+    }
 
-        Ok(MpaDecoder { params: params.clone(), state, buf: AudioBuffer::unused() })
+    /// Gets the ancillary data collected while decoding the last frame. Ancillary data are the
+    /// whole bytes following the audio data of a frame, and may carry application specific data
+    /// (e.g., DAB programme associated data). The ancillary data is byte-aligned, so a partial
+    /// byte following the audio data is not included.
+    ///
+    /// For layers 1 and 2, this is the ancillary data of the last decoded frame. For layer 3, the
+    /// ancillary data of a frame ends where the main data of the next frame begins, therefore this
+    /// is the ancillary data of the frame that preceded the last decoded frame. The ancillary data
+    /// of the final frame of a stream is never returned.
+    pub fn ancillary_data(&self) -> &[u8] {
+        self.state.ancillary()
+    }
+
+    /// Decodes a packet, and calls `on_granule` with the decoded audio of each granule as soon as
+    /// it is synthesized, instead of once the entire frame is decoded.
+    ///
+    /// An MPEG1 layer 3 frame contains two granules of 576 audio frames each, while an MPEG2 or
+    /// MPEG2.5 layer 3 frame contains a single granule. Therefore, for MPEG1 layer 3, this halves
+    /// the latency of the decoder. For layers 1 and 2, `on_granule` is called once with the entire
+    /// frame.
+    ///
+    /// If an error occurs while decoding the second granule, `on_granule` will have already been
+    /// called with the first granule.
+    pub fn decode_granules<F>(&mut self, packet: &Packet, mut on_granule: F) -> Result<()>
+    where
+        F: FnMut(AudioBufferRef<'_>),
+    {
+        let MpaDecoder { state, buf, stats, options, .. } = self;
+
+        let result = impl_output_buffer_func!(
+            buf,
+            buf,
+            decode_granules(state, stats, options, packet, buf, &mut on_granule)
+        );
+
+        if result.is_err() {
+            self.buf.clear();
+            self.stats.frames_failed += 1;
+        }
+
+        result
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let MpaDecoder { state, buf, stats, options, .. } = self;
+
+        impl_output_buffer_func!(buf, buf, decode_frame(state, stats, options, packet, buf))
+    }
+}
+
+impl Decoder for MpaDecoder {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        Self::try_new_with_options(params, &MpaDecoderOptions::from_generic::<f32>(options))
     }
 
     fn supported_codecs() -> &'static [CodecDescriptor] {
@@ -146,13 +762,225 @@ impl Decoder for MpaDecoder {
 
     fn reset(&mut self) {
         // Fully reset the decoder state.
-        self.state = State::new(self.params.codec);
+        self.state.reset();
+    }
+
+    fn save_state(&self) -> Option<DecoderState> {
+        let mut data = Vec::new();
+        self.state.save(&mut data);
+        Some(DecoderState::new(self.params.codec, data.into_boxed_slice()))
+    }
+
+    fn restore_state(&mut self, state: &DecoderState) -> Result<()> {
+        // Restore into a new decoder state so that the current state is unchanged on error.
+        let mut new_state = State::new(self.params.codec, &self.options);
+
+        restore_state(self.params.codec, state, |reader| new_state.restore(reader))?;
+
+        self.state = new_state;
+        Ok(())
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            self.stats.frames_failed += 1;
+            Err(e.into_packet_error())
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+
+    fn last_warnings(&self) -> &[DecodeWarning] {
+        self.state.warnings()
+    }
+}
+
+/// A PCM sample format that `Mp3FixedDecoder` can decode into.
+///
+/// This trait is implemented for `i16` and `i32`.
+#[cfg(feature = "fixed-point")]
+pub trait FixedOutputSample: Sample + FromSynthesized<Fixed> + Send + Sync + 'static {}
+
+#[cfg(feature = "fixed-point")]
+impl FixedOutputSample for i16 {}
+
+#[cfg(feature = "fixed-point")]
+impl FixedOutputSample for i32 {}
+
+/// MPEG1 and MPEG2 audio layer 3 decoder using only integer arithmetic.
+///
+/// Requantization, joint stereo, hybrid synthesis, and polyphase synthesis are all computed in
+/// fixed-point, making this decoder suitable for targets without a floating point unit. The
+/// decoded audio is output as either `i16` or `i32` samples, as selected by `S`. Bitstream parsing
+/// is shared with `MpaDecoder`.
+///
+/// This decoder is not registered in the default codec registry. To use it, register it in
+/// place of `MpaDecoder`, or instantiate it directly.
+#[cfg(feature = "fixed-point")]
+pub struct Mp3FixedDecoder<S: FixedOutputSample = i32> {
+    params: CodecParameters,
+    layer: Box<layer3::Layer3<Fixed>>,
+    buf: AudioBuffer<S>,
+    stats: MpaDecoderStats,
+    options: MpaDecoderOptions,
+}
+
+#[cfg(feature = "fixed-point")]
+impl<S: FixedOutputSample> Mp3FixedDecoder<S> {
+    /// Instantiates the decoder with options specific to this decoder.
+    pub fn try_new_with_options(
+        params: &CodecParameters,
+        options: &MpaDecoderOptions,
+    ) -> Result<Self> {
+        // This decoder only supports MP3.
+        if params.codec != CODEC_TYPE_MP3 {
+            return unsupported_error("mpa: invalid codec type");
+        }
+
+        options.validate::<S>()?;
+
+        let mut layer = Box::new(layer3::Layer3::new());
+        layer.dual_mono = options.dual_mono;
+        layer.budget = options.layer3_budget();
+
+        Ok(Mp3FixedDecoder {
+            params: output_params(params, options),
+            layer,
+            buf: AudioBuffer::unused(),
+            stats: Default::default(),
+            options: *options,
+        })
+    }
+
+    /// Gets the statistics of the stream decoded so far.
+    pub fn stats(&self) -> &MpaDecoderStats {
+        &self.stats
+    }
+
+    /// Clears the statistics of the stream decoded so far.
+    pub fn clear_stats(&mut self) {
+        self.stats = Default::default();
+    }
+
+    /// Gets the ancillary data collected while decoding the last frame. Ancillary data are the
+    /// whole bytes following the audio data of a frame. The ancillary data of a frame ends where
+    /// the main data of the next frame begins, therefore this is the ancillary data of the frame
+    /// that preceded the last decoded frame. See [`MpaDecoder::ancillary_data`] for details.
+    pub fn ancillary_data(&self) -> &[u8] {
+        &self.layer.ancillary
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        // Warnings only apply to the last packet decoded.
+        self.layer.warnings.clear();
+
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(());
+        }
+
+        let mut reader = packet.as_buf_reader();
+
+        let header = begin_frame(&mut reader, &mut self.buf, self.options.downmix_to_mono)?;
+
+        if header.layer != MpegLayer::Layer3 {
+            return decode_error("mpa: invalid mpeg audio layer");
+        }
+
+        let crc_policy = self.options.crc_policy;
+
+        let crc_failed = self.stats.check_layer3_frame(
+            packet.buf(),
+            &header,
+            crc_policy,
+            &mut self.layer.warnings,
+        );
+
+        if crc_failed && crc_policy == CrcPolicy::Error {
+            return decode_error("mpa: frame crc mismatch");
+        }
+
+        let result = self.layer.decode_into(&mut reader, &header, &mut self.buf);
+
+        self.stats.record_layer3(&self.layer);
+
+        result?;
+
+        if crc_failed {
+            conceal_crc_failure(&mut self.buf, crc_policy);
+        }
+
+        if self.options.strict {
+            check_strict(&header, &self.layer.warnings)?;
+        }
+
+        self.stats.record_frame(&header);
+
+        if self.options.enable_gapless {
+            self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl<S> Decoder for Mp3FixedDecoder<S>
+where
+    S: FixedOutputSample,
+    AudioBuffer<S>: AsAudioBufferRef,
+{
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        Self::try_new_with_options(params, &MpaDecoderOptions::from_generic::<S>(options))
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_MP3, "mp3", "MPEG Audio Layer 3 (Fixed-Point)")]
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn reset(&mut self) {
+        // Fully reset the decoder state.
+        self.layer.reset();
+    }
+
+    fn save_state(&self) -> Option<DecoderState> {
+        let mut data = Vec::new();
+        self.layer.save_state(&mut data);
+        Some(DecoderState::new(self.params.codec, data.into_boxed_slice()))
+    }
+
+    fn restore_state(&mut self, state: &DecoderState) -> Result<()> {
+        // Restore into a new decoder state so that the current state is unchanged on error.
+        let mut layer = Box::new(layer3::Layer3::new());
+        layer.dual_mono = self.options.dual_mono;
+        layer.budget = self.options.layer3_budget();
+
+        restore_state(self.params.codec, state, |reader| layer.restore_state(reader))?;
+
+        self.layer = layer;
+        Ok(())
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
         if let Err(e) = self.decode_inner(packet) {
             self.buf.clear();
-            Err(e)
+            self.stats.frames_failed += 1;
+            Err(e.into_packet_error())
         }
         else {
             Ok(self.buf.as_audio_buffer_ref())
@@ -166,4 +994,511 @@ impl Decoder for MpaDecoder {
     fn last_decoded(&self) -> AudioBufferRef<'_> {
         self.buf.as_audio_buffer_ref()
     }
+
+    fn last_warnings(&self) -> &[DecodeWarning] {
+        &self.layer.warnings
+    }
+}
+
+/// The side_info fields of a granule in a test frame. All other fields are 0.
+#[cfg(all(test, feature = "mp3"))]
+#[derive(Default)]
+pub(crate) struct TestGranule {
+    pub part2_3_length: u32,
+    pub global_gain: u32,
+    pub scalefac_compress: u32,
+    pub count1table_select: bool,
+}
+
+/// Builds a MPEG1 layer 3, 128 kbps, 44.1 kHz, mono frame with the given side_info, followed by
+/// `main_data`. The remainder of the frame is zeroed.
+#[cfg(all(test, feature = "mp3"))]
+pub(crate) fn make_test_frame(
+    main_data_begin: u32,
+    scfsi: u32,
+    granules: [TestGranule; 2],
+    main_data: &[u8],
+) -> Vec<u8> {
+    use symphonia_core::io::{BitWriterLtr, WriteBitsLtr};
+
+    let mut bw = BitWriterLtr::new();
+
+    // Writing to a vector never fails.
+    let mut write = |bits: u32, bit_width: u32| bw.write_bits_leq32(bits, bit_width).unwrap();
+
+    write(main_data_begin, 9);
+    // private_bits
+    write(0, 5);
+    write(scfsi, 4);
+
+    for granule in granules.iter() {
+        write(granule.part2_3_length, 12);
+        // big_values
+        write(0, 9);
+        write(granule.global_gain, 8);
+        write(granule.scalefac_compress, 4);
+        // window_switching_flag, table_select, region0_count, region1_count, preflag, and
+        // scalefac_scale.
+        write(0, 1 + 15 + 4 + 3 + 1 + 1);
+        write(u32::from(granule.count1table_select), 1);
+    }
+
+    let mut frame = vec![0xff, 0xfb, 0x90, 0xc4];
+    frame.extend_from_slice(&bw.into_inner());
+    frame.extend_from_slice(main_data);
+    frame.resize(417, 0);
+    frame
+}
+
+#[cfg(test)]
+// Justification: The decoder state only has the layer 3 variant if mp1 and mp2 are disabled.
+#[allow(irrefutable_let_patterns)]
+mod tests {
+    use super::{check_strict, MpaDecoderStats};
+    use crate::common::Emphasis;
+    use crate::header::parse_frame_header;
+
+    use symphonia_core::codecs::{DecodeWarning, DecodeWarningKind};
+
+    #[cfg(feature = "mp3")]
+    use super::{make_test_frame, MpaDecoder, State, TestGranule};
+    #[cfg(feature = "mp3")]
+    use symphonia_core::codecs::{state::DecoderState, CODEC_TYPE_MP2};
+    #[cfg(feature = "mp3")]
+    use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_MP3};
+
+    #[test]
+    fn verify_stats_bitrate() {
+        let mut stats = MpaDecoderStats::default();
+        assert_eq!(stats.avg_bitrate(), None);
+
+        // MPEG1 layer 3 frames at 128, 320, and 32 kbps.
+        for word in [0xfffb_9044u32, 0xfffb_e044, 0xfffb_1044].iter() {
+            stats.record_frame(&parse_frame_header(*word).unwrap());
+        }
+
+        assert_eq!(stats.frames_decoded, 3);
+        assert_eq!(stats.min_bitrate, Some(32_000));
+        assert_eq!(stats.max_bitrate, Some(320_000));
+        assert_eq!(stats.avg_bitrate(), Some(160_000));
+    }
+
+    #[test]
+    fn verify_check_strict() {
+        let header = parse_frame_header(0xfffb_9044).unwrap();
+        let reserved = parse_frame_header(0xfffb_9046).unwrap();
+
+        assert_eq!(reserved.emphasis, Emphasis::Reserved);
+
+        assert!(check_strict(&header, &[]).is_ok());
+        assert!(check_strict(&reserved, &[]).is_err());
+
+        // Missing data is tolerated, but any other warning is a violation.
+        let missing = DecodeWarning::new(DecodeWarningKind::MissingData, "missing");
+        let malformed =
+            DecodeWarning::new(DecodeWarningKind::MalformedBitstream, "malformed").at(8);
+
+        assert!(check_strict(&header, &[missing]).is_ok());
+        assert!(check_strict(&header, &[missing, malformed]).is_err());
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_reset() {
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+        let initial = decoder.save_state().unwrap();
+
+        // Populate the bit resevoir and overlap with stale data.
+        if let State::Layer3(layer) = &mut decoder.state {
+            layer.resevoir.fill(&[0xaa; 100], 0).unwrap();
+            layer.overlap[1][31][17] = 1.0;
+            layer.ancillary.push(0xaa);
+        }
+
+        assert_ne!(decoder.save_state().unwrap(), initial);
+
+        decoder.reset();
+
+        assert_eq!(decoder.save_state().unwrap(), initial);
+        assert!(decoder.ancillary_data().is_empty());
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_save_restore_state() {
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        // Populate the bit resevoir and overlap with a recognizable pattern.
+        if let State::Layer3(layer) = &mut decoder.state {
+            let main_data: Vec<u8> = (0..100).collect();
+            layer.resevoir.fill(&main_data, 0).unwrap();
+            layer.resevoir.consume(40);
+
+            for (i, overlap) in layer.overlap.iter_mut().flatten().flatten().enumerate() {
+                *overlap = i as f32 * 0.5;
+            }
+        }
+
+        let state = DecoderState::from_bytes(&decoder.save_state().unwrap().to_bytes()).unwrap();
+
+        let mut resumed = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+        resumed.restore_state(&state).unwrap();
+
+        match (&decoder.state, &resumed.state) {
+            (State::Layer3(expected), State::Layer3(actual)) => {
+                assert_eq!(actual.resevoir.bytes_ref(), expected.resevoir.bytes_ref());
+                assert_eq!(actual.overlap, expected.overlap);
+            }
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+
+        assert_eq!(resumed.save_state().unwrap(), state);
+
+        // States for another codec, or with missing or extra data, are rejected.
+        let other = DecoderState { codec: CODEC_TYPE_MP2, ..state.clone() };
+        assert!(resumed.restore_state(&other).is_err());
+
+        let truncated =
+            DecoderState::new(CODEC_TYPE_MP3, state.data[..state.data.len() - 1].into());
+        assert!(resumed.restore_state(&truncated).is_err());
+
+        let extended = DecoderState::new(CODEC_TYPE_MP3, [&state.data[..], &[0]].concat().into());
+        assert!(resumed.restore_state(&extended).is_err());
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_decode_empty_packet() {
+        use symphonia_core::formats::Packet;
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        if let State::Layer3(layer) = &mut decoder.state {
+            layer.warnings.push(DecodeWarning::new(DecodeWarningKind::Concealed, "stale"));
+        }
+
+        // An empty packet decodes to no audio, and clears the warnings of the last packet.
+        let packet = Packet::new_from_slice(0, 1152, 1152, &[]);
+
+        assert_eq!(decoder.decode(&packet).unwrap().frames(), 0);
+        assert!(decoder.last_warnings().is_empty());
+        assert_eq!(decoder.stats().frames_failed, 0);
+    }
+
+    #[cfg(feature = "mp1")]
+    #[test]
+    fn verify_layer1_ancillary_data() {
+        use super::MpaDecoder;
+        use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_MP1};
+        use symphonia_core::formats::Packet;
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP1);
+
+        let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        // A 32 byte, 32 kbps, 44.1 kHz mono layer 1 frame. Every sub-band has a bit allocation of 0,
+        // so the audio data consists of 32 4-bit allocations. The remaining 12 bytes of the frame
+        // are ancillary data.
+        let ancillary = *b"ancillary!!!";
+
+        let mut frame = vec![0xff, 0xff, 0x10, 0xc0];
+        frame.extend_from_slice(&[0; 16]);
+        frame.extend_from_slice(&ancillary);
+
+        decoder.decode(&Packet::new_from_slice(0, 0, 384, &frame)).unwrap();
+        assert_eq!(decoder.ancillary_data(), &ancillary);
+
+        // The ancillary data is replaced by every frame.
+        frame.truncate(20);
+        frame.extend_from_slice(&[0; 12]);
+
+        decoder.decode(&Packet::new_from_slice(0, 384, 384, &frame)).unwrap();
+        assert_eq!(decoder.ancillary_data(), &[0; 12]);
+    }
+
+    #[cfg(feature = "mp1")]
+    #[test]
+    fn verify_decoder_options() {
+        use super::{MpaDecoder, MpaDecoderOptions};
+        use symphonia_core::audio::{AudioBufferRef, Signal};
+        use symphonia_core::codecs::{CodecParameters, Decoder, CODEC_TYPE_MP1};
+        use symphonia_core::formats::Packet;
+        use symphonia_core::sample::SampleFormat;
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP1);
+
+        // An empty 32 byte, 32 kbps, 44.1 kHz mono layer 1 frame.
+        let mut frame = vec![0xff, 0xff, 0x10, 0xc0];
+        frame.resize(32, 0);
+
+        let packet = Packet::new_trimmed_from_slice(0, 0, 384, 100, 0, &frame);
+
+        // The decoded audio is output in the selected sample format.
+        let options = MpaDecoderOptions { sample_format: SampleFormat::S16, ..Default::default() };
+
+        let mut decoder = MpaDecoder::try_new_with_options(&params, &options).unwrap();
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::S16(buf) => assert_eq!(buf.frames(), 284),
+            _ => panic!("unexpected sample format"),
+        }
+
+        // Trimming is not applied if gapless is disabled.
+        let options = MpaDecoderOptions { enable_gapless: false, ..Default::default() };
+
+        let mut decoder = MpaDecoder::try_new_with_options(&params, &options).unwrap();
+        assert_eq!(decoder.decode(&packet).unwrap().frames(), 384);
+
+        let options = MpaDecoderOptions { sample_format: SampleFormat::F64, ..Default::default() };
+        assert!(MpaDecoder::try_new_with_options(&params, &options).is_err());
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_crc_policy() {
+        use super::{CrcPolicy, MpaDecoderOptions};
+        use symphonia_core::formats::Packet;
+
+        // A MPEG1 layer 3, 128 kbps, 44.1 kHz frame protected by a CRC that does not match.
+        let mut frame = vec![0xff, 0xfa, 0x90, 0x44, 0x12, 0x34];
+        frame.resize(417, 0);
+
+        let header = parse_frame_header(0xfffa_9044).unwrap();
+        assert!(header.has_crc);
+
+        let mut stats = MpaDecoderStats::default();
+        let mut warnings = Vec::new();
+
+        assert!(!stats.check_layer3_frame(&frame, &header, CrcPolicy::Ignore, &mut warnings));
+        assert!(warnings.is_empty());
+
+        assert!(stats.check_layer3_frame(&frame, &header, CrcPolicy::Warn, &mut warnings));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(stats.crc_failures, 1);
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let packet = Packet::new_from_slice(0, 0, 1152, &frame);
+
+        let decode = |crc_policy| {
+            let options = MpaDecoderOptions { crc_policy, ..Default::default() };
+            let mut decoder = MpaDecoder::try_new_with_options(&params, &options).unwrap();
+            let frames = decoder.decode(&packet).map(|buf| buf.frames());
+            (frames.ok(), decoder.last_warnings().len())
+        };
+
+        assert_eq!(decode(CrcPolicy::Ignore), (Some(1152), 0));
+        assert_eq!(decode(CrcPolicy::Warn), (Some(1152), 1));
+        assert_eq!(decode(CrcPolicy::Mute), (Some(1152), 1));
+        assert_eq!(decode(CrcPolicy::Skip), (Some(0), 1));
+        assert_eq!(decode(CrcPolicy::Error).0, None);
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_conceal_granule() {
+        use symphonia_core::audio::{AudioBufferRef, Signal};
+        use symphonia_core::formats::Packet;
+
+        // Builds a MPEG1 layer 3, 128 kbps, 44.1 kHz, mono frame. The second granule codes 10
+        // quadruples of non-zero spectral samples with count1 table B, and shares the scale factors
+        // of the first granule. The first granule is corrupt if it has a part2_3_length shorter than
+        // its 74 bits of scale factors.
+        let frame = |corrupt: bool| {
+            let gr0 = if corrupt {
+                TestGranule { part2_3_length: 73, scalefac_compress: 15, ..Default::default() }
+            }
+            else {
+                Default::default()
+            };
+
+            let gr1 = TestGranule {
+                part2_3_length: 80,
+                global_gain: 210,
+                count1table_select: true,
+                ..Default::default()
+            };
+
+            // The 73 bits of main_data of the corrupt first granule are all ones, so its scale
+            // factors are non-zero.
+            let main_data: &[u8] = if corrupt {
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x80]
+            }
+            else {
+                &[]
+            };
+
+            make_test_frame(0, 0xf, [gr0, gr1], main_data)
+        };
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let decode = |frame: &[u8]| {
+            let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+            let samples = match decoder.decode(&Packet::new_from_slice(0, 0, 1152, frame)).unwrap()
+            {
+                AudioBufferRef::F32(buf) => buf.chan(0).to_vec(),
+                _ => unreachable!(),
+            };
+
+            (samples, decoder.last_warnings().to_vec())
+        };
+
+        let (expected, warnings) = decode(&frame(false));
+        assert!(warnings.is_empty());
+        assert!(expected[576..].iter().any(|&s| s != 0.0));
+
+        // The corrupt granule is concealed, and the second granule is decoded as if the first
+        // granule had no scale factors.
+        let (actual, warnings) = decode(&frame(true));
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, DecodeWarningKind::Concealed);
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_last_warnings() {
+        use symphonia_core::formats::Packet;
+
+        // Builds a MPEG1 layer 3, 128 kbps, 44.1 kHz, mono frame. In the first granule, the count1
+        // partition is coded with table B, where all zero bits decode to 8 bit quadruples. Since
+        // the part2_3_length of 100 bits is not a multiple of 8, the last quadruple overruns it.
+        let frame = |main_data_begin: u32| {
+            let gr0 =
+                TestGranule { part2_3_length: 100, count1table_select: true, ..Default::default() };
+
+            make_test_frame(main_data_begin, 0, [gr0, Default::default()], &[])
+        };
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        // The overrun is located at the start of the main data of the frame, after the header and
+        // side_info.
+        decoder.decode(&Packet::new_from_slice(0, 0, 1152, &frame(0))).unwrap();
+
+        let overrun =
+            DecodeWarning::new(DecodeWarningKind::MalformedBitstream, "mpa: count1 overrun");
+
+        assert_eq!(decoder.last_warnings(), &[overrun.at(8 * (4 + 17))]);
+
+        // The main data of the next frame begins in a previous frame that was never decoded.
+        decoder.reset();
+        decoder.decode(&Packet::new_from_slice(0, 1152, 1152, &frame(100))).unwrap();
+
+        let warnings = decoder.last_warnings();
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, DecodeWarningKind::MissingData);
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_decode_budget() {
+        use super::MpaDecoderOptions;
+        use symphonia_core::errors::Error;
+        use symphonia_core::formats::Packet;
+        use symphonia_core::meta::Limit;
+
+        // A MPEG1 layer 3, 128 kbps, 44.1 kHz, mono frame. In the first granule, part2_3_length is
+        // 100 bits, and the count1 partition is coded with table B. All zero bits decode to
+        // quadruples of non-zero spectral samples in table B.
+        let gr0 =
+            TestGranule { part2_3_length: 100, count1table_select: true, ..Default::default() };
+
+        let frame = make_test_frame(0, 0, [gr0, Default::default()], &[]);
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        let packet = Packet::new_from_slice(0, 0, 1152, &frame);
+
+        let decode = |limit_decode_iterations| {
+            let options = MpaDecoderOptions { limit_decode_iterations, ..Default::default() };
+            let mut decoder = MpaDecoder::try_new_with_options(&params, &options).unwrap();
+            decoder.decode(&packet).map(|buf| buf.frames())
+        };
+
+        assert_eq!(decode(Limit::Default).unwrap(), 1152);
+        assert_eq!(decode(Limit::None).unwrap(), 1152);
+        assert_eq!(decode(Limit::Maximum(576)).unwrap(), 1152);
+        assert!(matches!(decode(Limit::Maximum(8)), Err(Error::LimitError(_))));
+
+        // The limits of the generic decoder options are used.
+        let options =
+            DecoderOptions { limit_decode_iterations: Limit::Maximum(8), ..Default::default() };
+        let mut decoder = MpaDecoder::try_new(&params, &options).unwrap();
+        assert!(matches!(decoder.decode(&packet), Err(Error::LimitError(_))));
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn verify_decode_granules() {
+        use symphonia_core::audio::{AudioBufferRef, Signal};
+        use symphonia_core::formats::Packet;
+
+        // A MPEG1 layer 3, 128 kbps, 44.1 kHz, frame without any main data.
+        let mut frame = vec![0xff, 0xfb, 0x90, 0x44];
+        frame.resize(417, 0);
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_MP3);
+
+        // A decoder with a non-zero overlap so that the decoded audio is not silent.
+        let new_decoder = || {
+            let mut decoder = MpaDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+            if let State::Layer3(layer) = &mut decoder.state {
+                for (i, overlap) in layer.overlap.iter_mut().flatten().flatten().enumerate() {
+                    *overlap = ((i % 7) as f32 - 3.0) * 0.01;
+                }
+            }
+
+            decoder
+        };
+
+        let samples = |buf: AudioBufferRef<'_>| match buf {
+            AudioBufferRef::F32(buf) => buf.chan(0).to_vec(),
+            _ => unreachable!(),
+        };
+
+        for &(trim_start, trim_end) in &[(0, 0), (100, 0), (600, 50), (0, 700)] {
+            let packet = Packet::new_trimmed_from_slice(0, 0, 1152, trim_start, trim_end, &frame);
+
+            let expected = samples(new_decoder().decode(&packet).unwrap());
+            assert!(expected.iter().any(|&s| s != 0.0));
+
+            // Each granule is output separately, and the concatenation of the granules is the
+            // entire frame.
+            let mut granules = Vec::new();
+
+            new_decoder().decode_granules(&packet, |buf| granules.push(samples(buf))).unwrap();
+
+            assert_eq!(granules.len(), 2);
+            assert_eq!(
+                granules[0].len(),
+                576.min(1152 - trim_end as usize).saturating_sub(trim_start as usize)
+            );
+            assert_eq!(granules.concat(), expected);
+        }
+    }
 }
@@ -9,14 +9,23 @@ use symphonia_core::support_format;
 
 use symphonia_core::checksum::Crc16AnsiLe;
 use symphonia_core::codecs::CodecParameters;
-use symphonia_core::errors::{seek_error, Result, SeekErrorKind};
+use symphonia_core::dsp::emphasis;
+use symphonia_core::errors::{
+    end_of_stream_error, seek_error, unsupported_error, Error, Result, SeekErrorKind,
+};
+use symphonia_core::formats::cache::AnalysisCache;
 use symphonia_core::formats::prelude::*;
+use symphonia_core::formats::util::{SeekIndex, SeekSearchResult};
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_metadata::ape::{read_ape_items, ApeFooter, APE_FOOTER_LEN};
+use symphonia_metadata::id3v1::read_id3v1;
+use symphonia_metadata::id3v2::{read_id3v2_with_info, Id3v2TagInfo};
 
-use crate::common::{FrameHeader, MpegLayer};
+use crate::common::{ChannelMode, Emphasis, FrameHeader, MpegLayer};
 use crate::header::{self, MAX_MPEG_FRAME_SIZE, MPEG_HEADER_LEN};
+use crate::icy::{IcyQueue, IcySource};
 
 use std::io::{Seek, SeekFrom};
 
@@ -32,7 +41,79 @@ pub struct MpaReader {
     metadata: MetadataLog,
     options: FormatOptions,
     first_packet_pos: u64,
+    /// The position of the end of the audio data, excluding any trailing tags, if known.
+    audio_end_pos: Option<u64>,
     next_packet_ts: u64,
+    last_header: Option<FrameHeader>,
+    /// Seek index of frame positions relative to the first packet, built progressively.
+    index: SeekIndex,
+    /// The timestamp at, or after, which the next seek index entry will be added.
+    next_index_ts: u64,
+    /// The exact total number of frames in the stream, including delay and padding, if known.
+    exact_n_frames: Option<u64>,
+    /// True if all packets were read sequentially from the first packet.
+    is_contiguous: bool,
+    /// True if the timestamp of the next packet is exact, and not an estimate after a coarse seek.
+    is_ts_exact: bool,
+    /// The parameters of the stream if it was verified to be constant bitrate.
+    cbr: Option<CbrInfo>,
+    /// The queue of metadata read from ICY metadata blocks, if the stream contains them.
+    icy_queue: Option<IcyQueue>,
+    /// The table of contents of the Xing tag, if present. Only used by the parallel decoder.
+    #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+    xing_toc: Option<[u8; 100]>,
+}
+
+/// Options specific to `MpaReader`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MpaReaderOptions {
+    /// If the stream is an internet radio stream with interleaved ICY (SHOUTcast) metadata, the
+    /// number of audio bytes between metadata blocks as provided by the `icy-metaint` HTTP
+    /// response header. The metadata blocks are stripped from the stream, and their metadata is
+    /// pushed to the metadata queue of the reader. A stream with ICY metadata is not seekable.
+    /// Default: `None`.
+    pub icy_metaint: Option<u32>,
+}
+
+/// A complete MPEG audio frame, including the frame header, as read from the stream.
+#[derive(Clone, Debug)]
+pub struct MpaFrame {
+    /// The frame header.
+    pub header: FrameHeader,
+    /// The timestamp of the frame in audio frames, including the encoder delay.
+    pub ts: u64,
+    /// The duration of the frame in audio frames.
+    pub dur: u64,
+    /// The frame data, including the frame header.
+    pub data: Box<[u8]>,
+}
+
+/// An iterator over the MPEG frames read by a `MpaReader`.
+pub struct MpaFrames<'a> {
+    reader: &'a mut MpaReader,
+    is_done: bool,
+}
+
+impl Iterator for MpaFrames<'_> {
+    type Item = Result<MpaFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        match self.reader.next_frame() {
+            Ok(frame) => Some(Ok(frame)),
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.is_done = true;
+                None
+            }
+            Err(err) => {
+                self.is_done = true;
+                Some(Err(err))
+            }
+        }
+    }
 }
 
 impl QueryDescriptor for MpaReader {
@@ -51,7 +132,8 @@ impl QueryDescriptor for MpaReader {
                     &[0xff, 0xf7], // MPEG 2
                     &[0xff, 0xe6], // MPEG 2.5 with CRC
                     &[0xff, 0xe7], // MPEG 2.5
-                ]
+                ],
+                Instantiate::FormatWithCache(MpaReader::instantiate)
             ),
             // Layer 2
             support_format!(
@@ -66,7 +148,8 @@ impl QueryDescriptor for MpaReader {
                     &[0xff, 0xf5], // MPEG 2
                     &[0xff, 0xe4], // MPEG 2.5 with CRC
                     &[0xff, 0xe5], // MPEG 2.5
-                ]
+                ],
+                Instantiate::FormatWithCache(MpaReader::instantiate)
             ),
             // Layer 3
             support_format!(
@@ -81,124 +164,26 @@ impl QueryDescriptor for MpaReader {
                     &[0xff, 0xf3], // MPEG 2
                     &[0xff, 0xe2], // MPEG 2.5 with CRC
                     &[0xff, 0xe3], // MPEG 2.5
-                ]
+                ],
+                Instantiate::FormatWithCache(MpaReader::instantiate)
             ),
         ]
     }
 
-    fn score(_context: &[u8]) -> u8 {
-        255
+    fn score(context: &[u8]) -> u8 {
+        score_mpeg_frames(context)
     }
 }
 
 impl FormatReader for MpaReader {
-    fn try_new(mut source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
-        // Try to read the first MPEG frame.
-        let (header, packet) = read_mpeg_frame_strict(&mut source)?;
-
-        // Use the header to populate the codec parameters.
-        let mut params = CodecParameters::new();
-
-        params
-            .for_codec(header.codec())
-            .with_sample_rate(header.sample_rate)
-            .with_time_base(TimeBase::new(1, header.sample_rate))
-            .with_channels(header.channel_mode.channels());
-
-        // Check if there is a Xing/Info tag contained in the first frame.
-        if let Some(info_tag) = try_read_info_tag(&packet, &header) {
-            // The LAME tag contains ReplayGain and padding information.
-            let (delay, padding) = if let Some(lame_tag) = info_tag.lame {
-                params.with_delay(lame_tag.enc_delay).with_padding(lame_tag.enc_padding);
-
-                (lame_tag.enc_delay, lame_tag.enc_padding)
-            }
-            else {
-                (0, 0)
-            };
-
-            // The base Xing/Info tag may contain the number of frames.
-            if let Some(num_mpeg_frames) = info_tag.num_frames {
-                debug!("using xing header for duration");
-
-                let num_frames = u64::from(num_mpeg_frames) * header.duration();
-
-                // Adjust for gapless playback.
-                if options.enable_gapless {
-                    params.with_n_frames(num_frames - u64::from(delay) - u64::from(padding));
-                }
-                else {
-                    params.with_n_frames(num_frames);
-                }
-            }
-        }
-        else if let Some(vbri_tag) = try_read_vbri_tag(&packet, &header) {
-            debug!("using vbri header for duration");
-
-            let num_frames = u64::from(vbri_tag.num_mpeg_frames) * header.duration();
-
-            // Check if there is a VBRI tag.
-            params.with_n_frames(num_frames);
-        }
-        else {
-            // The first frame was not a Xing/Info header, rewind back to the start of the frame so
-            // that it may be decoded.
-            source.seek_buffered_rev(MPEG_HEADER_LEN + header.frame_size);
-
-            // Likely not a VBR file, so estimate the duration if seekable.
-            if source.is_seekable() {
-                info!("estimating duration from bitrate, may be inaccurate for vbr files");
-
-                if let Some(n_mpeg_frames) = estimate_num_mpeg_frames(&mut source) {
-                    params.with_n_frames(n_mpeg_frames * header.duration());
-                }
-            }
-        }
-
-        let first_packet_pos = source.pos();
-
-        Ok(MpaReader {
-            reader: source,
-            tracks: vec![Track::new(0, params)],
-            cues: Vec::new(),
-            metadata: Default::default(),
-            options: *options,
-            first_packet_pos,
-            next_packet_ts: 0,
-        })
+    fn try_new(source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
+        MpaReader::try_new_with_options(source, options, &Default::default())
     }
 
     fn next_packet(&mut self) -> Result<Packet> {
-        let (header, packet) = loop {
-            // Read the next MPEG frame.
-            let (header, packet) = read_mpeg_frame(&mut self.reader)?;
+        let frame = self.next_frame()?;
 
-            // Check if the packet contains a Xing, Info, or VBRI tag.
-            if is_maybe_info_tag(&packet, &header) {
-                if try_read_info_tag(&packet, &header).is_some() {
-                    // Discard the packet and tag since it was not at the start of the stream.
-                    warn!("found an unexpected xing tag, discarding");
-                    continue;
-                }
-            }
-            else if is_maybe_vbri_tag(&packet, &header)
-                && try_read_vbri_tag(&packet, &header).is_some()
-            {
-                // Discard the packet and tag since it was not at the start of the stream.
-                warn!("found an unexpected vbri tag, discarding");
-                continue;
-            }
-
-            break (header, packet);
-        };
-
-        // Each frame contains 1 or 2 granules with each granule being exactly 576 samples long.
-        let ts = self.next_packet_ts;
-        let duration = header.duration();
-
-        self.next_packet_ts += duration;
-
-        let mut packet = Packet::new_from_boxed_slice(0, ts, duration, packet.into_boxed_slice());
+        let mut packet = Packet::new_from_boxed_slice(0, frame.ts, frame.dur, frame.data);
 
         if self.options.enable_gapless {
             symphonia_core::formats::util::trim_packet(
@@ -212,6 +197,7 @@ impl FormatReader for MpaReader {
     }
 
     fn metadata(&mut self) -> Metadata<'_> {
+        self.push_icy_metadata();
         self.metadata.metadata()
     }
 
@@ -223,8 +209,20 @@ impl FormatReader for MpaReader {
         &self.tracks
     }
 
+    fn analysis_cache(&self) -> Option<AnalysisCache> {
+        let params = &self.tracks[0].codec_params;
+
+        Some(AnalysisCache {
+            byte_len: self.reader.byte_len(),
+            n_frames: self.exact_n_frames,
+            delay: params.delay,
+            padding: params.padding,
+            seek_points: self.index.points().to_vec(),
+            ..Default::default()
+        })
+    }
+
     fn seek(&mut self, mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
-        const MAX_REF_FRAMES: usize = 4;
         const REF_FRAMES_MASK: usize = MAX_REF_FRAMES - 1;
 
         // Get the timestamp of the desired audio frame.
@@ -265,6 +263,9 @@ impl FormatReader for MpaReader {
 
         debug!("seeking to ts={} (+{} delay = {})", desired_ts, delay, required_ts);
 
+        // After a seek, packets are no longer read sequentially.
+        self.is_contiguous = false;
+
         // Step 1
         //
         // In coarse seek mode, the underlying media source stream will be roughly seeked based on
@@ -273,8 +274,17 @@ impl FormatReader for MpaReader {
         //
         // In accurate seek mode, the underlying media source stream will not be seeked unless the
         // required timestamp is in the past, in which case the stream is seeked back to the start.
+        //
+        // In either mode, if the stream is constant bitrate, the media source stream will be seeked
+        // directly to the packet containing the required timestamp, less its reference frames.
+        let is_cbr_seeked = is_seekable && self.preseek_cbr(required_ts)?;
+
         match mode {
-            SeekMode::Coarse if is_seekable => self.preseek_coarse(required_ts, delay)?,
+            _ if is_cbr_seeked => (),
+            SeekMode::Coarse if is_seekable => {
+                self.preseek_coarse(required_ts, delay)?;
+                self.is_ts_exact = false;
+            }
             SeekMode::Accurate => self.preseek_accurate(required_ts)?,
             _ => (),
         };
@@ -382,6 +392,381 @@ impl FormatReader for MpaReader {
 }
 
 impl MpaReader {
+    /// Instantiates a new `MpaReader` with options specific to `MpaReader`.
+    pub fn try_new_with_options(
+        source: MediaSourceStream,
+        options: &FormatOptions,
+        mpa_options: &MpaReaderOptions,
+    ) -> Result<Self> {
+        MpaReader::try_new_inner(source, options, mpa_options, None)
+    }
+
+    /// Instantiates a new `MpaReader` that uses the results of a previous analysis of the media.
+    ///
+    /// If the cache is still valid for the media, the cached duration, seek index, and gapless
+    /// information are used instead of scanning the media to determine them. Otherwise, the cache
+    /// is ignored. See [`FormatReader::analysis_cache`].
+    pub fn try_new_with_analysis_cache(
+        source: MediaSourceStream,
+        options: &FormatOptions,
+        mpa_options: &MpaReaderOptions,
+        cache: &AnalysisCache,
+    ) -> Result<Self> {
+        MpaReader::try_new_inner(source, options, mpa_options, Some(cache))
+    }
+
+    /// Instantiates a new `MpaReader` for the `Probe`.
+    fn instantiate(
+        source: MediaSourceStream,
+        options: &FormatOptions,
+        cache: Option<&AnalysisCache>,
+    ) -> Result<Box<dyn FormatReader>> {
+        Ok(Box::new(MpaReader::try_new_inner(source, options, &Default::default(), cache)?))
+    }
+
+    fn try_new_inner(
+        source: MediaSourceStream,
+        options: &FormatOptions,
+        mpa_options: &MpaReaderOptions,
+        cache: Option<&AnalysisCache>,
+    ) -> Result<Self> {
+        // If the stream contains ICY metadata, strip it from the stream before reading it.
+        let (mut source, icy_queue) = match mpa_options.icy_metaint {
+            Some(metaint) if metaint > 0 => {
+                let queue: IcyQueue = Default::default();
+                let icy = IcySource::new(source, metaint, queue.clone());
+
+                (MediaSourceStream::new(Box::new(icy), Default::default()), Some(queue))
+            }
+            _ => (source, None),
+        };
+
+        // Use the results of a previous analysis if they are still valid for this media source.
+        let cache = cache.filter(|c| c.is_valid_for(source.byte_len()));
+
+        // If the stream was not probed, it may start with ID3v2 tags. Read them instead of relying
+        // on resynchronization to skip them.
+        let mut metadata: MetadataLog = Default::default();
+
+        // Encrypted audio would decode to noise.
+        if let Some(owner) = read_id3v2_tags(&mut source, &mut metadata)? {
+            warn!("audio is encrypted by {}", owner);
+            return unsupported_error("mpa: audio is encrypted");
+        }
+
+        // Tags appended to the end of the stream must not be mistaken for audio data.
+        let audio_end_pos = read_trailing_tags(&mut source, &mut metadata)?;
+
+        // Try to read the first MPEG frame.
+        let (header, packet) = read_mpeg_frame_strict(&mut source, None, resync_limit(options))?;
+
+        let mut exact_n_frames = None;
+
+        // Use the header to populate the codec parameters.
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(header.codec())
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_channels(header.channel_mode.channels())
+            .with_dual_mono(header.channel_mode == ChannelMode::DualMono);
+
+        match header.emphasis {
+            Emphasis::Fifty15 => params.with_emphasis(emphasis::Emphasis::Fifty15),
+            Emphasis::CcitJ17 => params.with_emphasis(emphasis::Emphasis::CcittJ17),
+            _ => &mut params,
+        };
+
+        // Check if there is a Xing/Info tag contained in the first frame.
+        let info_tag = try_read_info_tag(&packet, &header);
+
+        #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+        let xing_toc = info_tag.as_ref().and_then(|info_tag| info_tag.toc);
+
+        if let Some(info_tag) = info_tag {
+            // The LAME tag contains ReplayGain and padding information.
+            let (delay, padding) = if let Some(lame_tag) = info_tag.lame {
+                params.with_delay(lame_tag.enc_delay).with_padding(lame_tag.enc_padding);
+
+                (lame_tag.enc_delay, lame_tag.enc_padding)
+            }
+            else {
+                (0, 0)
+            };
+
+            // The base Xing/Info tag may contain the number of frames.
+            if let Some(num_mpeg_frames) = info_tag.num_frames {
+                debug!("using xing header for duration");
+
+                let num_frames = u64::from(num_mpeg_frames) * header.duration();
+
+                exact_n_frames = Some(num_frames);
+
+                // Adjust for gapless playback.
+                if options.enable_gapless {
+                    params.with_n_frames(num_frames - u64::from(delay) - u64::from(padding));
+                }
+                else {
+                    params.with_n_frames(num_frames);
+                }
+            }
+        }
+        else if let Some(vbri_tag) = try_read_vbri_tag(&packet, &header) {
+            debug!("using vbri header for duration");
+
+            let num_frames = u64::from(vbri_tag.num_mpeg_frames) * header.duration();
+
+            exact_n_frames = Some(num_frames);
+
+            // Check if there is a VBRI tag.
+            params.with_n_frames(num_frames);
+        }
+        else {
+            // The first frame was not a Xing/Info header, rewind back to the start of the frame so
+            // that it may be decoded.
+            source.seek_buffered_rev(header.frame_len());
+
+            // If the media was previously analyzed, use the cached gapless information and
+            // duration instead of estimating the duration.
+            if let Some(cache) = cache {
+                if let Some(delay) = cache.delay {
+                    params.with_delay(delay);
+                }
+                if let Some(padding) = cache.padding {
+                    params.with_padding(padding);
+                }
+
+                if let Some(num_frames) = cache.n_frames {
+                    debug!("using analysis cache for duration");
+
+                    exact_n_frames = Some(num_frames);
+
+                    // Adjust for gapless playback.
+                    if options.enable_gapless {
+                        let trim = cache.delay.unwrap_or(0) + cache.padding.unwrap_or(0);
+                        params.with_n_frames(num_frames.saturating_sub(u64::from(trim)));
+                    }
+                    else {
+                        params.with_n_frames(num_frames);
+                    }
+                }
+            }
+
+            // Likely not a VBR file, so estimate the duration if the length of the stream is known.
+            if params.n_frames.is_none() {
+                info!("estimating duration from bitrate, may be inaccurate for vbr files");
+
+                if let Some(n_mpeg_frames) = estimate_num_mpeg_frames(&mut source, audio_end_pos) {
+                    params.with_n_frames(n_mpeg_frames * header.duration());
+                }
+            }
+        }
+
+        let first_packet_pos = source.pos();
+
+        // If the stream is constant bitrate, any frame can be seeked to without a seek index.
+        let cbr = if source.is_seekable() { detect_cbr(&mut source, audio_end_pos)? } else { None };
+
+        // Populate the seek index with the cached seek points.
+        let mut index = SeekIndex::new();
+
+        if let Some(cache) = cache {
+            for point in &cache.seek_points {
+                index.insert(point.frame_ts, point.byte_offset, point.n_frames);
+            }
+        }
+
+        let next_index_ts = index.points().last().map_or(0, |point| {
+            point.frame_ts + index_interval(options, &header).max(u64::from(point.n_frames))
+        });
+
+        let mut reader = MpaReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata,
+            options: *options,
+            first_packet_pos,
+            audio_end_pos,
+            next_packet_ts: 0,
+            last_header: Some(header),
+            index,
+            next_index_ts,
+            exact_n_frames,
+            is_contiguous: true,
+            is_ts_exact: true,
+            cbr,
+            icy_queue,
+            #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+            xing_toc,
+        };
+
+        // If requested, and the exact duration is still unknown, read every frame in the stream to
+        // determine it. This also builds the complete seek index.
+        if options.enable_full_scan && exact_n_frames.is_none() && reader.reader.is_seekable() {
+            reader.scan(header)?;
+        }
+
+        Ok(reader)
+    }
+
+    /// Reads the next complete MPEG frame, without decoding it.
+    ///
+    /// Unlike a packet returned by `next_packet`, the frame is never trimmed for gapless playback
+    /// and its timestamp includes the encoder delay. The frame may be copied as-is into another
+    /// stream or container.
+    pub fn next_frame(&mut self) -> Result<MpaFrame> {
+        let (header, packet) = loop {
+            // Read the next MPEG frame, verifying it against the last frame read. The audio data
+            // ends where the trailing tags begin, and a frame extending into them is invalid.
+            let frame = match self.audio_end_pos {
+                Some(end_pos) if self.reader.pos() >= end_pos => end_of_stream_error(),
+                _ => read_mpeg_frame_strict(
+                    &mut self.reader,
+                    self.last_header.as_ref(),
+                    resync_limit(&self.options),
+                ),
+            };
+
+            let frame = match frame {
+                Ok(_)
+                    if self.audio_end_pos.map_or(false, |end_pos| self.reader.pos() > end_pos) =>
+                {
+                    end_of_stream_error()
+                }
+                frame => frame,
+            };
+
+            let (header, packet) = match frame {
+                Ok(frame) => frame,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    // If every packet was read, then the exact duration of the stream is now
+                    // known.
+                    if self.is_contiguous {
+                        self.exact_n_frames = Some(self.next_packet_ts);
+                    }
+                    return Err(Error::IoError(err));
+                }
+                Err(err) => return Err(err),
+            };
+
+            // Check if the packet contains a Xing, Info, or VBRI tag.
+            if is_maybe_info_tag(&packet, &header) {
+                if try_read_info_tag(&packet, &header).is_some() {
+                    // Discard the packet and tag since it was not at the start of the stream.
+                    warn!("found an unexpected xing tag, discarding");
+                    continue;
+                }
+            }
+            else if is_maybe_vbri_tag(&packet, &header)
+                && try_read_vbri_tag(&packet, &header).is_some()
+            {
+                // Discard the packet and tag since it was not at the start of the stream.
+                warn!("found an unexpected vbri tag, discarding");
+                continue;
+            }
+
+            break (header, packet);
+        };
+
+        self.last_header = Some(header);
+
+        // Each frame contains 1 or 2 granules with each granule being exactly 576 samples long.
+        let ts = self.next_packet_ts;
+        let duration = header.duration();
+
+        // Progressively build the seek index. This is only possible if the timestamp is exact.
+        if self.is_ts_exact && ts >= self.next_index_ts {
+            let pos = self.reader.pos() - packet.len() as u64;
+
+            self.index.insert(ts, pos - self.first_packet_pos, duration as u32);
+            self.next_index_ts = ts + index_interval(&self.options, &header).max(duration);
+        }
+
+        self.next_packet_ts += duration;
+
+        self.push_icy_metadata();
+
+        Ok(MpaFrame { header, ts, dur: duration, data: packet.into_boxed_slice() })
+    }
+
+    /// Gets the position of the first audio frame, and the position of the end of the audio data if
+    /// known.
+    #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+    pub(crate) fn audio_span(&self) -> (u64, Option<u64>) {
+        (self.first_packet_pos, self.audio_end_pos)
+    }
+
+    /// Gets the table of contents of the Xing tag, if present. Entry `i` is the position of the
+    /// frame `i` percent into the stream, in units of 1/256th of the stream length.
+    #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+    pub(crate) fn xing_toc(&self) -> Option<&[u8; 100]> {
+        self.xing_toc.as_ref()
+    }
+
+    /// Pushes any metadata read from ICY metadata blocks to the metadata log. Since the stream is
+    /// read ahead of the frames, the metadata may be pushed slightly before the audio it applies
+    /// to is read.
+    fn push_icy_metadata(&mut self) {
+        if let Some(queue) = &self.icy_queue {
+            if let Ok(mut queue) = queue.lock() {
+                for rev in queue.drain(..) {
+                    self.metadata.push(rev);
+                }
+            }
+        }
+    }
+
+    /// Gets an iterator over the remaining MPEG frames in the stream. See [`MpaReader::next_frame`]
+    /// for details. The iterator ends at the end of the stream, or after the first error.
+    pub fn frames(&mut self) -> MpaFrames<'_> {
+        MpaFrames { reader: self, is_done: false }
+    }
+
+    /// Reads every frame in the stream, without decoding, to determine the exact duration of the
+    /// stream and populate the seek index. Afterwards, the reader is returned to the first frame.
+    fn scan(&mut self, first_header: FrameHeader) -> Result<()> {
+        debug!("scanning stream for duration");
+
+        loop {
+            match self.next_frame() {
+                Ok(_) => (),
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    break
+                }
+                Err(err) => {
+                    // The stream may still be playable up-to the error, so do not fail.
+                    warn!("stopping scan due to error: {}", err);
+                    break;
+                }
+            }
+        }
+
+        self.reader.seek(SeekFrom::Start(self.first_packet_pos))?;
+
+        self.next_packet_ts = 0;
+        self.last_header = Some(first_header);
+        self.is_contiguous = true;
+        self.is_ts_exact = true;
+
+        // The exact duration is only known if the scan reached the end of the stream.
+        if let Some(num_frames) = self.exact_n_frames {
+            let params = &mut self.tracks[0].codec_params;
+
+            // Adjust for gapless playback.
+            if self.options.enable_gapless {
+                let trim = u64::from(params.delay.unwrap_or(0) + params.padding.unwrap_or(0));
+                params.with_n_frames(num_frames.saturating_sub(trim));
+            }
+            else {
+                params.with_n_frames(num_frames);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Seeks the media source stream to a byte position roughly where the packet with the required
     /// timestamp should be located.
     fn preseek_coarse(&mut self, required_ts: u64, delay: u64) -> Result<()> {
@@ -393,9 +778,9 @@ impl MpaReader {
             0
         };
 
-        // Get the total byte length of the stream. It is not possible to seek without this.
-        let total_byte_len = match self.reader.byte_len() {
-            Some(byte_len) => byte_len,
+        // Get the position of the end of the audio data. It is not possible to seek without this.
+        let audio_end_pos = match self.audio_end_pos {
+            Some(end_pos) => end_pos,
             None => return seek_error(SeekErrorKind::Unseekable),
         };
 
@@ -407,7 +792,7 @@ impl MpaReader {
         };
 
         // Calculate the total size of the audio data.
-        let audio_byte_len = total_byte_len - self.first_packet_pos;
+        let audio_byte_len = audio_end_pos - self.first_packet_pos;
 
         // Calculate, roughly, where the packet containing the required timestamp is in the media
         // source stream relative to the start of the audio data.
@@ -422,7 +807,11 @@ impl MpaReader {
         self.reader.seek(SeekFrom::Start(seek_pos))?;
 
         // Resync to the start of the next packet.
-        let (header, _) = read_mpeg_frame_strict(&mut self.reader)?;
+        let (header, _) = read_mpeg_frame_strict(
+            &mut self.reader,
+            self.last_header.as_ref(),
+            resync_limit(&self.options),
+        )?;
 
         // Calculate, roughly, the timestamp of the packet based on the byte position after resync.
         let seeked_pos = self.reader.pos();
@@ -439,9 +828,95 @@ impl MpaReader {
         Ok(())
     }
 
-    /// Seeks the media source stream back to the start of the first packet if the required
-    /// timestamp is in the past.
+    /// If the stream is constant bitrate, calculates the position of a packet sufficiently before the
+    /// packet containing the required timestamp, and seeks the media source stream to it. Returns
+    /// false, without changing the position of the stream, if the stream is not constant bitrate,
+    /// or the packet found at the calculated position is not where it was expected.
+    fn preseek_cbr(&mut self, required_ts: u64) -> Result<bool> {
+        let cbr = match self.cbr {
+            Some(cbr) => cbr,
+            None => return Ok(false),
+        };
+
+        // Leave room for the reference frames of the packet containing the required timestamp.
+        let n = (required_ts / cbr.frame_dur).saturating_sub(MAX_REF_FRAMES as u64 - 1);
+
+        // The actual position of a packet may precede the calculated position by up-to one padding
+        // slot. Therefore, seek one slot before the calculated position and then resync.
+        let seek_pos = self.first_packet_pos + cbr.frame_pos(n).saturating_sub(cbr.slot_len());
+
+        let old_pos = self.reader.pos();
+
+        self.reader.seek(SeekFrom::Start(seek_pos))?;
+
+        let landed = match read_mpeg_frame_strict(
+            &mut self.reader,
+            self.last_header.as_ref(),
+            resync_limit(&self.options),
+        ) {
+            Ok((header, packet)) if cbr.is_match(&header) => {
+                let pos = self.reader.pos() - packet.len() as u64;
+                Some((pos, cbr.frame_num(pos - self.first_packet_pos)))
+            }
+            _ => None,
+        };
+
+        // The stream is only constant bitrate at the landed position if the packet is where it is
+        // expected to be.
+        match landed {
+            Some((pos, n)) if cbr.is_aligned(n, pos - self.first_packet_pos) => {
+                debug!("using cbr seek to ts={} @ pos={}", n * cbr.frame_dur, pos);
+
+                self.reader.seek_buffered(pos);
+                self.next_packet_ts = n * cbr.frame_dur;
+                self.is_ts_exact = true;
+
+                Ok(true)
+            }
+            _ => {
+                debug!("cbr seek failed, stream may not be constant bitrate");
+
+                self.reader.seek(SeekFrom::Start(old_pos))?;
+
+                Ok(false)
+            }
+        }
+    }
+
+    /// Seeks the media source stream to the closest indexed packet that is sufficiently before the
+    /// required timestamp. If there is no such packet, seeks the media source stream back to the
+    /// start of the first packet if the required timestamp is in the past.
     fn preseek_accurate(&mut self, required_ts: u64) -> Result<()> {
+        // Leave room for the reference frames of the packet containing the required timestamp.
+        let margin = 4 * self.last_header.as_ref().map_or(0, |header| header.duration());
+
+        let point = match self.index.search(required_ts.saturating_sub(margin)) {
+            SeekSearchResult::Range(point, _) | SeekSearchResult::Lower(point) => Some(point),
+            _ => None,
+        };
+
+        if let Some(point) = point {
+            // Only seek if it would skip past packets, or the required timestamp is in the past.
+            let is_useful =
+                point.frame_ts > self.next_packet_ts || required_ts < self.next_packet_ts;
+
+            if is_useful && self.reader.is_seekable() {
+                let pos = self.first_packet_pos + point.byte_offset;
+
+                // The position seeked to must be exactly as requested.
+                if self.reader.seek(SeekFrom::Start(pos))? != pos {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+
+                debug!("using seek index entry ts={} @ pos={}", point.frame_ts, pos);
+
+                self.next_packet_ts = point.frame_ts;
+                self.is_ts_exact = true;
+
+                return Ok(());
+            }
+        }
+
         if required_ts < self.next_packet_ts {
             let seeked_pos = self.reader.seek(SeekFrom::Start(self.first_packet_pos))?;
 
@@ -453,17 +928,161 @@ impl MpaReader {
 
             // Successfuly seeked to the start of the stream, reset the next packet timestamp.
             self.next_packet_ts = 0;
+            self.is_ts_exact = true;
         }
 
         Ok(())
     }
 }
 
-/// Reads a MPEG frame and returns the header and buffer.
-fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
+/// The default maximum number of bytes skipped while resynchronizing to the next frame.
+const DEFAULT_RESYNC_LIMIT: usize = 1024 * 1024;
+
+/// Gets the maximum number of bytes that may be skipped while resynchronizing to the next frame.
+fn resync_limit(options: &FormatOptions) -> u64 {
+    options.limit_resync_bytes.limit_or_default(DEFAULT_RESYNC_LIMIT).map_or(u64::MAX, |l| l as u64)
+}
+
+/// The maximum number of reference frames that will be provided to the decoder after a seek.
+const MAX_REF_FRAMES: usize = 4;
+
+/// The parameters of a constant bitrate stream. The position of any frame in a constant bitrate
+/// stream can be calculated from these parameters.
+#[derive(Copy, Clone, Debug)]
+struct CbrInfo {
+    layer: MpegLayer,
+    bitrate: u32,
+    sample_rate: u32,
+    frame_dur: u64,
+}
+
+impl CbrInfo {
+    fn new(header: &FrameHeader) -> Option<Self> {
+        // The bitrate of a free format stream is unknown.
+        if header.bitrate == 0 {
+            return None;
+        }
+
+        Some(CbrInfo {
+            layer: header.layer,
+            bitrate: header.bitrate,
+            sample_rate: header.sample_rate,
+            frame_dur: header.duration(),
+        })
+    }
+
+    /// Check if the frame header belongs to the constant bitrate stream.
+    fn is_match(&self, header: &FrameHeader) -> bool {
+        header.layer == self.layer
+            && header.bitrate == self.bitrate
+            && header.sample_rate == self.sample_rate
+    }
+
+    /// Gets the length of a padding slot in bytes.
+    fn slot_len(&self) -> u64 {
+        if self.layer == MpegLayer::Layer1 {
+            4
+        }
+        else {
+            1
+        }
+    }
+
+    /// Gets the position of frame `n` relative to the first frame. Encoders pad frames such that
+    /// the average length of a frame is exactly the number of bits per frame divided by 8.
+    fn frame_pos(&self, n: u64) -> u64 {
+        let bits = u128::from(n) * u128::from(self.bitrate) * u128::from(self.frame_dur);
+        (bits / (8 * u128::from(self.sample_rate))) as u64
+    }
+
+    /// Gets the number of the frame nearest to the position `pos` relative to the first frame.
+    fn frame_num(&self, pos: u64) -> u64 {
+        let bits = u128::from(pos) * 8 * u128::from(self.sample_rate);
+        let bits_per_frame = u128::from(self.bitrate) * u128::from(self.frame_dur);
+        ((bits + bits_per_frame / 2) / bits_per_frame) as u64
+    }
+
+    /// Check if frame `n` is at, or within one padding slot of, the position `pos` relative to the
+    /// first frame.
+    fn is_aligned(&self, n: u64, pos: u64) -> bool {
+        let expected = self.frame_pos(n);
+        let delta = if pos > expected { pos - expected } else { expected - pos };
+        delta <= self.slot_len()
+    }
+}
+
+/// Checks if the first frames of the stream have the same bitrate and are padded to maintain
+/// that bitrate exactly. If so, the stream is assumed to be constant bitrate. The stream is
+/// returned to the position it was at upon entering this function.
+fn detect_cbr(
+    reader: &mut MediaSourceStream,
+    audio_end_pos: Option<u64>,
+) -> Result<Option<CbrInfo>> {
+    const MAX_FRAMES: u64 = 16;
+
+    let start_pos = reader.pos();
+
+    let mut cbr: Option<CbrInfo> = None;
+    let mut n = 0;
+
+    let is_cbr = loop {
+        if n >= MAX_FRAMES {
+            break true;
+        }
+
+        // The stream may end before all frames are read.
+        if audio_end_pos.map_or(false, |end_pos| reader.pos() >= end_pos) {
+            break n > 0;
+        }
+
+        let pos = reader.pos() - start_pos;
+
+        let header_val = match reader.read_be_u32() {
+            Ok(header_val) => header_val,
+            _ => break n > 0,
+        };
+
+        let header = match header::parse_frame_header(header_val) {
+            Ok(header) => header,
+            _ => break false,
+        };
+
+        let info = match cbr {
+            Some(info) => info,
+            None => match CbrInfo::new(&header) {
+                Some(info) => *cbr.get_or_insert(info),
+                None => break false,
+            },
+        };
+
+        if !info.is_match(&header) || !info.is_aligned(n, pos) {
+            break false;
+        }
+
+        if reader.ignore_bytes(header.frame_size as u64).is_err() {
+            break true;
+        }
+
+        n += 1;
+    };
+
+    reader.seek(SeekFrom::Start(start_pos))?;
+
+    Ok(if is_cbr { cbr } else { None })
+}
+
+/// Reads a MPEG frame and returns the header and buffer. Atmost `max_skip` bytes are skipped to
+/// find the frame header.
+fn read_mpeg_frame(
+    reader: &mut MediaSourceStream,
+    max_skip: u64,
+) -> Result<(FrameHeader, Vec<u8>)> {
+    let start = reader.pos();
+
     let (header, header_word) = loop {
         // Sync to the next frame header.
-        let sync = header::sync_frame(reader)?;
+        let skipped = reader.pos() - start;
+        let sync = header::sync_frame_within(reader, max_skip.saturating_sub(skipped))?;
 
         // Parse the frame header fully.
         if let Ok(header) = header::parse_frame_header(sync) {
@@ -474,7 +1093,7 @@ fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u
     };
 
     // Allocate frame buffer.
-    let mut packet = vec![0u8; MPEG_HEADER_LEN + header.frame_size];
+    let mut packet = vec![0u8; header.frame_len()];
     packet[0..MPEG_HEADER_LEN].copy_from_slice(&header_word.to_be_bytes());
 
     // Read the frame body.
@@ -484,32 +1103,78 @@ fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u
     Ok((header, packet))
 }
 
-/// Reads a MPEG frame and checks if the next frame begins after the packet.
-fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
+/// Reads a MPEG frame and verifies that it is not a false sync.
+///
+/// If a previous frame header is provided, a frame that immediately follows the previous frame
+/// and has a similar header is trusted outright. Otherwise, the candidate frame must be similar to
+/// the previous frame (if it was found by resynchronizing), and the frame header following the
+/// candidate frame must be similar to the candidate, if it can be read.
+///
+/// Atmost `max_skip` bytes of junk are skipped to find a valid frame, otherwise a `LimitError` is
+/// returned.
+pub(crate) fn read_mpeg_frame_strict(
+    reader: &mut MediaSourceStream,
+    prev: Option<&FrameHeader>,
+    max_skip: u64,
+) -> Result<(FrameHeader, Vec<u8>)> {
+    let origin = reader.pos();
+
     loop {
+        // Get the position before reading the frame.
+        let start = reader.pos();
+
         // Read the next MPEG frame.
-        let (header, packet) = read_mpeg_frame(reader)?;
+        let skipped = start.saturating_sub(origin);
+        let (header, packet) = read_mpeg_frame(reader, max_skip.saturating_sub(skipped))?;
 
         // Get the position before trying to read the next header.
         let pos = reader.pos();
 
+        // The position of the frame header.
+        let frame_pos = pos - packet.len() as u64;
+
+        // If no bytes were skipped to find the frame header, then the frame is contiguous with the
+        // previous frame.
+        let is_contiguous = frame_pos == start;
+
+        // Check if the frame header is consistent with the previous frame header.
+        let is_consistent = prev.map_or(true, |prev| is_frame_header_similar(prev, &header));
+
+        // A frame that directly follows a similar frame is very unlikely to be a false sync.
+        if prev.is_some() && is_contiguous && is_consistent {
+            break Ok((header, packet));
+        }
+
+        // Resynchronizing to a frame with different parameters than the previous frame is most
+        // likely a false sync since the stream parameters rarely change.
+        let mut is_valid = is_consistent || is_contiguous;
+
         // Read a sync word from the stream. If this read fails then the file may have ended and
         // this check cannot be performed.
-        if let Ok(sync) = header::read_frame_header_word_no_sync(reader) {
-            // If the stream is not synced to the next frame's sync word, or the next frame header
-            // is not parseable or similar to the current frame header, then reject the current
-            // packet since the stream likely synced to random data.
-            if !header::is_frame_header_word_synced(sync) || !is_frame_header_similar(&header, sync)
-            {
-                warn!("skipping junk at {} bytes", pos - packet.len() as u64);
-
-                // Seek back to the second byte of the rejected packet to prevent syncing to the
-                // same spot again.
-                reader.seek_buffered_rev(packet.len() + MPEG_HEADER_LEN - 1);
-                continue;
+        if is_valid {
+            if let Ok(sync) = header::read_frame_header_word_no_sync(reader) {
+                // If the stream is not synced to the next frame's sync word, or the next frame
+                // header is not parseable or similar to the current frame header, then reject the
+                // current packet since the stream likely synced to random data.
+                is_valid = header::is_frame_header_word_synced(sync)
+                    && is_frame_header_word_similar(&header, sync);
+            }
+            else {
+                // Without a following frame header, a frame with different parameters than the
+                // previous frame cannot be confirmed.
+                is_valid = is_consistent;
             }
         }
 
+        if !is_valid {
+            warn!("skipping junk at {} bytes", frame_pos);
+
+            // Seek back to the second byte of the rejected packet to prevent syncing to the same
+            // spot again.
+            reader.seek_buffered(frame_pos + 1);
+            continue;
+        }
+
         // Jump back to the position before the next header was read.
         reader.seek_buffered(pos);
 
@@ -518,18 +1183,65 @@ fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader
 }
 
 /// Check if a sync word parses to a frame header that is similar to the one provided.
-fn is_frame_header_similar(header: &FrameHeader, sync: u32) -> bool {
-    if let Ok(candidate) = header::parse_frame_header(sync) {
-        if header.version == candidate.version
-            && header.layer == candidate.layer
-            && header.sample_rate == candidate.sample_rate
-            && header.n_channels() == candidate.n_channels()
-        {
-            return true;
+fn is_frame_header_word_similar(header: &FrameHeader, sync: u32) -> bool {
+    match header::parse_frame_header(sync) {
+        Ok(candidate) => is_frame_header_similar(header, &candidate),
+        _ => false,
+    }
+}
+
+/// Check if two frame headers describe the same stream parameters.
+fn is_frame_header_similar(header: &FrameHeader, candidate: &FrameHeader) -> bool {
+    header.version == candidate.version
+        && header.layer == candidate.layer
+        && header.sample_rate == candidate.sample_rate
+        && header.n_channels() == candidate.n_channels()
+}
+
+/// The number of consecutive frames with consistent parameters required for a full probe score.
+const PROBE_SCORE_FRAMES: usize = 3;
+
+/// Scores a probe context buffer by the number of consecutive, consistent, frames it contains.
+///
+/// A frame header that is followed by garbage, or by a frame header with different parameters, is
+/// rejected with a score of 0. If the context ends before enough frames could be checked, the score
+/// is reduced proportionally.
+fn score_mpeg_frames(context: &[u8]) -> u8 {
+    let read_header_at = |pos: usize| -> Option<FrameHeader> {
+        let buf = context.get(pos..pos + MPEG_HEADER_LEN)?;
+        let sync = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+
+        if !header::is_frame_header_word_synced(sync) || !header::check_header(sync) {
+            return None;
+        }
+
+        header::parse_frame_header(sync).ok()
+    };
+
+    let first = match read_header_at(0) {
+        Some(header) => header,
+        None => return 0,
+    };
+
+    let mut pos = first.frame_len();
+    let mut n_frames = 1;
+
+    while n_frames < PROBE_SCORE_FRAMES && pos + MPEG_HEADER_LEN <= context.len() {
+        match read_header_at(pos) {
+            Some(header) if is_frame_header_similar(&first, &header) => {
+                pos += header.frame_len();
+                n_frames += 1;
+            }
+            _ => return 0,
         }
     }
 
-    false
+    ((255 * n_frames) / PROBE_SCORE_FRAMES) as u8
+}
+
+/// Gets the minimum number of frames between seek index entries.
+fn index_interval(options: &FormatOptions, header: &FrameHeader) -> u64 {
+    u64::from(options.seek_index_fill_rate) * u64::from(header.sample_rate)
 }
 
 #[derive(Default)]
@@ -557,8 +1269,198 @@ fn read_main_data_begin<B: ReadBytes>(reader: &mut B, header: &FrameHeader) -> R
     Ok(main_data_begin)
 }
 
+/// The length of an ID3v2 tag header, and footer.
+const ID3V2_HEADER_LEN: u64 = 10;
+
+/// The maximum number of ID3v2 tags linked by SEEK frames that will be followed.
+const MAX_ID3V2_SEEKS: usize = 8;
+
+/// Reads all consecutive ID3v2 tags at the current position of the stream, and any tags linked to
+/// them by SEEK frames, and pushes them to `metadata`. Tags that fail to parse are skipped. Returns
+/// the owner identifier of the audio encryption method if any tag signals that the audio is
+/// encrypted.
+///
+/// Linked tags can only be read if the stream is seekable. The stream is returned to the end of the
+/// consecutive tags afterwards.
+fn read_id3v2_tags(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<Option<String>> {
+    let mut encryption_owner = None;
+
+    while let Some(info) = read_id3v2_tag(reader, metadata)? {
+        let mut seek_offset = info.seek_offset;
+
+        encryption_owner = encryption_owner.or(info.encryption_owner);
+
+        if seek_offset.is_none() || !reader.is_seekable() {
+            continue;
+        }
+
+        let resume_pos = reader.pos();
+
+        for _ in 0..MAX_ID3V2_SEEKS {
+            let offset = match seek_offset {
+                Some(offset) => u64::from(offset),
+                None => break,
+            };
+
+            let tag_pos = reader.pos() + offset;
+
+            // The linked tag must be within the stream.
+            if reader.byte_len().map_or(false, |len| tag_pos + ID3V2_HEADER_LEN > len) {
+                warn!("id3v2 seek frame points past the end of the stream");
+                break;
+            }
+
+            reader.seek(SeekFrom::Start(tag_pos))?;
+
+            match read_id3v2_tag(reader, metadata) {
+                Ok(Some(info)) => {
+                    debug!("found linked id3v2 tag at {}", tag_pos);
+
+                    seek_offset = info.seek_offset;
+                    encryption_owner = encryption_owner.or(info.encryption_owner);
+                }
+                Ok(None) => {
+                    warn!("id3v2 seek frame does not point to a tag");
+                    break;
+                }
+                Err(err) => {
+                    warn!("skipping linked id3v2 tag: {}", err);
+                    break;
+                }
+            }
+        }
+
+        reader.seek(SeekFrom::Start(resume_pos))?;
+    }
+
+    Ok(encryption_owner)
+}
+
+/// Reads an ID3v2 tag at the current position of the stream, and pushes it to `metadata`. If the
+/// tag fails to parse, it is skipped. Returns `None` if there is no ID3v2 tag at the current
+/// position.
+fn read_id3v2_tag(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<Option<Id3v2TagInfo>> {
+    if reader.read_triple_bytes()? != *b"ID3" {
+        reader.seek_buffered_rev(3);
+        return Ok(None);
+    }
+
+    let mut header = [0; 7];
+    reader.read_buf_exact(&mut header)?;
+
+    reader.seek_buffered_rev(ID3V2_HEADER_LEN as usize);
+
+    // The version must not be 0xff, and each byte of the synchsafe tag size must have its most
+    // significant bit cleared. Otherwise, this is not an ID3v2 tag.
+    if header[0] == 0xff || header[3..].iter().any(|&byte| byte & 0x80 != 0) {
+        return Ok(None);
+    }
+
+    let size = header[3..].iter().fold(0, |size, &byte| (size << 7) | u64::from(byte));
+
+    // An ID3v2.4 tag may be followed by a footer.
+    let footer_len = if header[0] == 4 && header[2] & 0x10 != 0 { ID3V2_HEADER_LEN } else { 0 };
+
+    let mut scoped = ScopedStream::new(&mut *reader, ID3V2_HEADER_LEN + size + footer_len);
+
+    let mut builder = MetadataBuilder::new();
+
+    let info = match read_id3v2_with_info(&mut scoped, &mut builder) {
+        Ok(info) => {
+            metadata.push(builder.metadata());
+            info
+        }
+        Err(err) => {
+            warn!("skipping id3v2 tag: {}", err);
+            Default::default()
+        }
+    };
+
+    // Skip the remainder of the tag, if any.
+    scoped.ignore()?;
+
+    Ok(Some(info))
+}
+
+/// The length of an ID3v1 tag.
+const ID3V1_TAG_LEN: u64 = 128;
+
+/// Reads the ID3v1 and APE tags at the end of the stream, if any, and pushes them to `metadata`.
+/// Returns the position where the audio data ends, or `None` if the length of the stream is
+/// unknown. The stream is returned to its original position afterwards.
+///
+/// If the stream is not seekable, tags cannot be read, and the audio data is assumed to end at the
+/// end of the stream.
+fn read_trailing_tags(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<Option<u64>> {
+    let byte_len = match reader.byte_len() {
+        Some(byte_len) if reader.is_seekable() => byte_len,
+        Some(byte_len) => return Ok(Some(byte_len)),
+        None => return Ok(None),
+    };
+
+    let start_pos = reader.pos();
+
+    let mut end_pos = byte_len;
+
+    // An ID3v1 tag occupies the last 128 bytes of the stream.
+    if end_pos >= start_pos + ID3V1_TAG_LEN {
+        reader.seek(SeekFrom::Start(end_pos - ID3V1_TAG_LEN))?;
+
+        let mut builder = MetadataBuilder::new();
+
+        if read_id3v1(reader, &mut builder).is_ok() {
+            debug!("found id3v1 tag at end of stream");
+
+            metadata.push(builder.metadata());
+            end_pos -= ID3V1_TAG_LEN;
+        }
+    }
+
+    // An APE tag ends with a footer, and precedes the ID3v1 tag if both are present.
+    if end_pos >= start_pos + APE_FOOTER_LEN {
+        reader.seek(SeekFrom::Start(end_pos - APE_FOOTER_LEN))?;
+
+        if let Ok(footer) = ApeFooter::read(reader) {
+            if footer.tag_len() <= end_pos - start_pos {
+                debug!("found ape tag at end of stream");
+
+                reader.seek(SeekFrom::Start(end_pos - u64::from(footer.size)))?;
+
+                let mut builder = MetadataBuilder::new();
+
+                match read_ape_items(reader, &footer, &mut builder) {
+                    Ok(_) => metadata.push(builder.metadata()),
+                    Err(err) => warn!("skipping ape tag: {}", err),
+                }
+
+                end_pos -= footer.tag_len();
+            }
+        }
+    }
+
+    reader.seek(SeekFrom::Start(start_pos))?;
+
+    Ok(Some(end_pos))
+}
+
 /// Estimates the total number of MPEG frames in the media source stream.
-fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
+///
+/// If the first few frames have the same bitrate, the stream is assumed to be constant bitrate, and
+/// the number of frames is calculated from the length of the audio data and the bitrate of the first
+/// frame. Otherwise, the number of frames is estimated from the average length of those frames.
+fn estimate_num_mpeg_frames(
+    reader: &mut MediaSourceStream,
+    audio_end_pos: Option<u64>,
+) -> Option<u64> {
     const MAX_FRAMES: u32 = 16;
     const MAX_LEN: usize = 16 * 1024;
 
@@ -577,8 +1479,11 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
     let mut total_frame_len = 0;
     let mut total_frames = 0;
 
-    let total_len = match reader.byte_len() {
-        Some(len) => len - start_pos,
+    let mut first_header: Option<FrameHeader> = None;
+    let mut is_cbr = true;
+
+    let total_len = match audio_end_pos {
+        Some(end_pos) => end_pos.saturating_sub(start_pos),
         _ => return None,
     };
 
@@ -589,8 +1494,13 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
         // Parse the frame header.
         let header = break_on_err!(header::parse_frame_header(header_val));
 
+        match &first_header {
+            Some(first) => is_cbr &= header.bitrate == first.bitrate,
+            None => first_header = Some(header),
+        }
+
         // Tabulate the size.
-        total_frame_len += MPEG_HEADER_LEN + header.frame_size;
+        total_frame_len += header.frame_len();
         total_frames += 1;
 
         // Ignore the frame body.
@@ -607,7 +1517,17 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
     // Rewind back to the first frame seen upon entering this function.
     reader.seek_buffered_rev((reader.pos() - start_pos) as usize);
 
-    num_mpeg_frames
+    match first_header {
+        Some(first) if is_cbr => {
+            // The average length of a frame in a constant bitrate stream is exactly the number of
+            // bits per frame divided by 8. Padding is used to maintain this average.
+            let bits = u128::from(total_len) * 8 * u128::from(first.sample_rate);
+            let bits_per_frame = u128::from(first.bitrate) * u128::from(first.duration());
+
+            Some(((bits + bits_per_frame / 2) / bits_per_frame) as u64)
+        }
+        _ => num_mpeg_frames,
+    }
 }
 
 const XING_TAG_ID: [u8; 4] = *b"Xing";
@@ -921,3 +1841,589 @@ fn is_maybe_vbri_tag(buf: &[u8], header: &FrameHeader) -> bool {
     // The bytes preceeding the VBRI tag (mostly the side information) should be all 0.
     !buf[MPEG_HEADER_LEN..VBRI_TAG_OFFSET].iter().any(|&b| b != 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::meta::Limit;
+
+    /// MPEG1 layer 3, 128 kbps, 44.1 kHz, stereo, without CRC or padding (417 bytes).
+    const HEADER_44K: u32 = 0xfffb_9000;
+    /// MPEG1 layer 3, 128 kbps, 48 kHz, stereo, without CRC or padding (384 bytes).
+    const HEADER_48K: u32 = 0xfffb_9400;
+
+    fn push_frame(buf: &mut Vec<u8>, header: u32, len: usize) {
+        buf.extend_from_slice(&header.to_be_bytes());
+        buf.resize(buf.len() + len - MPEG_HEADER_LEN, 0);
+    }
+
+    #[test]
+    fn verify_probe_score() {
+        let mut buf = Vec::new();
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        // Three consistent frames.
+        assert_eq!(score_mpeg_frames(&buf), 255);
+
+        // The context ends after two consistent frames.
+        assert_eq!(score_mpeg_frames(&buf[..2 * 417 + 2]), 170);
+
+        // The second frame has different parameters.
+        buf[417 + 2] = 0x94;
+        assert_eq!(score_mpeg_frames(&buf), 0);
+
+        // The first frame is followed by junk.
+        buf[417..417 + 4].copy_from_slice(&[0; 4]);
+        assert_eq!(score_mpeg_frames(&buf), 0);
+    }
+
+    #[test]
+    fn verify_id3v2_tag_skipped() {
+        use symphonia_core::meta::StandardTagKey;
+
+        let mut buf = Vec::new();
+
+        // An ID3v2.4 tag with a title frame followed by padding. The tag size (0x81 = 129 bytes)
+        // is synchsafe and deliberately has a second byte to verify it is decoded correctly.
+        let title = b"\x03Title";
+        buf.extend_from_slice(b"ID3\x04\x00\x00\x00\x00\x01\x01");
+        buf.extend_from_slice(b"TIT2");
+        buf.extend_from_slice(&[0, 0, 0, title.len() as u8, 0, 0]);
+        buf.extend_from_slice(title);
+        buf.resize(10 + 129, 0);
+
+        // A false sync word in the tag body must not be found.
+        buf[100] = 0xff;
+        buf[101] = 0xfb;
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        let mut reader = MpaReader::try_new(mss, &Default::default()).unwrap();
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.data.len(), 417);
+        assert_eq!(reader.reader.pos(), 10 + 129 + 417);
+
+        let mut metadata = reader.metadata();
+        let rev = metadata.skip_to_latest().unwrap();
+        assert_eq!(rev.tags()[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(rev.tags()[0].value.to_string(), "Title");
+    }
+
+    #[test]
+    fn verify_id3v2_seek_and_aenc_frames() {
+        use symphonia_core::errors::Error;
+        use symphonia_core::meta::StandardTagKey;
+
+        // An ID3v2.4 tag with a seek frame pointing to a second tag after two frames.
+        let mut buf = b"ID3\x04\x00\x00\x00\x00\x00\x0e".to_vec();
+        buf.extend_from_slice(b"SEEK\x00\x00\x00\x04\x00\x00");
+        buf.extend_from_slice(&(2 * 417u32).to_be_bytes());
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        let title = b"\x03Title";
+        buf.extend_from_slice(b"ID3\x04\x00\x00\x00\x00\x00\x10");
+        buf.extend_from_slice(b"TIT2");
+        buf.extend_from_slice(&[0, 0, 0, title.len() as u8, 0, 0]);
+        buf.extend_from_slice(title);
+
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        let mss =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf.clone())), Default::default());
+
+        let mut reader = MpaReader::try_new(mss, &Default::default()).unwrap();
+
+        // The stream is returned to the end of the first tag.
+        assert_eq!(reader.first_packet_pos, 24);
+
+        let mut metadata = reader.metadata();
+        let rev = metadata.skip_to_latest().unwrap();
+        assert_eq!(rev.tags()[0].std_key, Some(StandardTagKey::TrackTitle));
+
+        // The second tag is skipped when reading packets.
+        for _ in 0..3 {
+            assert_eq!(reader.next_packet().unwrap().data.len(), 417);
+        }
+
+        // An audio encryption frame in the second tag.
+        let aenc = b"AENC\x00\x00\x00\x06\x00\x00own\x00\x00\x00";
+        buf.splice(24 + 2 * 417 + 10..24 + 2 * 417 + 26, aenc.iter().copied());
+
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        match MpaReader::try_new(mss, &Default::default()) {
+            Err(Error::Unsupported(msg)) => assert_eq!(msg, "mpa: audio is encrypted"),
+            _ => panic!("expected an unsupported error"),
+        }
+    }
+
+    #[test]
+    fn verify_icy_metadata_stripped() {
+        use super::MpaReaderOptions;
+        use symphonia_core::meta::StandardTagKey;
+
+        let mut audio = Vec::new();
+
+        for _ in 0..6 {
+            push_frame(&mut audio, HEADER_44K, 417);
+        }
+
+        // Insert a metadata block after every 1000 bytes of audio. The second block repeats the
+        // first.
+        let mut block = b"StreamTitle='One';".to_vec();
+        block.resize(32, 0);
+
+        let mut buf = Vec::new();
+
+        for (i, chunk) in audio.chunks(1000).enumerate() {
+            buf.extend_from_slice(chunk);
+
+            if chunk.len() == 1000 {
+                buf.push(2);
+                buf.extend_from_slice(&block);
+            }
+
+            // The stream ends in the middle of an interval.
+            assert!(i < 3);
+        }
+
+        let source = ReadOnlySource::new(std::io::Cursor::new(buf));
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+
+        let options = MpaReaderOptions { icy_metaint: Some(1000) };
+        let mut reader =
+            MpaReader::try_new_with_options(mss, &Default::default(), &options).unwrap();
+
+        for _ in 0..6 {
+            assert_eq!(reader.next_packet().unwrap().data.len(), 417);
+        }
+
+        assert!(reader.next_packet().is_err());
+
+        // The repeated metadata block is not pushed again.
+        let metadata = reader.metadata();
+        assert!(metadata.is_latest());
+
+        let rev = metadata.current().unwrap();
+        assert_eq!(rev.tags()[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(rev.tags()[0].value.to_string(), "One");
+    }
+
+    #[test]
+    fn verify_trailing_tags_excluded() {
+        use symphonia_core::meta::StandardTagKey;
+
+        let mut buf = Vec::new();
+
+        for _ in 0..20 {
+            push_frame(&mut buf, HEADER_44K, 417);
+        }
+
+        // An APEv2 tag with a header, and a binary item containing a valid MPEG frame.
+        let mut item = Vec::new();
+        push_frame(&mut item, HEADER_44K, 417);
+
+        let mut items = Vec::new();
+        items.extend_from_slice(&(item.len() as u32).to_le_bytes());
+        items.extend_from_slice(&2u32.to_le_bytes());
+        items.extend_from_slice(b"Frame\0");
+        items.extend_from_slice(&item);
+        items.extend_from_slice(&3u32.to_le_bytes());
+        items.extend_from_slice(&0u32.to_le_bytes());
+        items.extend_from_slice(b"Title\0Ape");
+
+        for flags in &[0xa000_0000u32, 0x8000_0000] {
+            buf.extend_from_slice(b"APETAGEX");
+            buf.extend_from_slice(&2000u32.to_le_bytes());
+            buf.extend_from_slice(&(items.len() as u32 + 32).to_le_bytes());
+            buf.extend_from_slice(&2u32.to_le_bytes());
+            buf.extend_from_slice(&flags.to_le_bytes());
+            buf.extend_from_slice(&[0; 8]);
+
+            // The items follow the header.
+            if *flags & 0x2000_0000 != 0 {
+                buf.extend_from_slice(&items);
+            }
+        }
+
+        // An ID3v1 tag.
+        let mut id3v1 = [0; 128];
+        id3v1[..3].copy_from_slice(b"TAG");
+        id3v1[3..8].copy_from_slice(b"Id3v1");
+        buf.extend_from_slice(&id3v1);
+
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        let mut reader = MpaReader::try_new(mss, &Default::default()).unwrap();
+
+        assert_eq!(reader.audio_end_pos, Some(20 * 417));
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(20 * 1152));
+
+        let mut num_packets = 0;
+
+        while reader.next_packet().is_ok() {
+            num_packets += 1;
+        }
+
+        assert_eq!(num_packets, 20);
+        assert_eq!(reader.exact_n_frames, Some(20 * 1152));
+
+        // The ID3v1 tag is read first, then the APE tag.
+        let mut metadata = reader.metadata();
+        assert_eq!(metadata.current().unwrap().tags()[0].value.to_string(), "Id3v1");
+
+        let rev = metadata.skip_to_latest().unwrap();
+        assert_eq!(rev.tags().len(), 2);
+        assert_eq!(rev.tags()[1].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(rev.tags()[1].value.to_string(), "Ape");
+    }
+
+    /// Makes a 128 kbps, 44.1 kHz, constant bitrate stream. Frames are padded as an encoder would
+    /// to maintain an average frame length of exactly 144 * 128000 / 44100 bytes.
+    fn make_cbr(n_frames: usize) -> Vec<u8> {
+        /// MPEG1 layer 3, 128 kbps, 44.1 kHz, stereo, without CRC, with padding (418 bytes).
+        const HEADER_44K_PADDED: u32 = HEADER_44K | 0x200;
+
+        let mut buf = Vec::new();
+        let mut rem = 0;
+
+        for _ in 0..n_frames {
+            rem += (144 * 128_000) % 44_100;
+
+            if rem >= 44_100 {
+                rem -= 44_100;
+                push_frame(&mut buf, HEADER_44K_PADDED, 418);
+            }
+            else {
+                push_frame(&mut buf, HEADER_44K, 417);
+            }
+        }
+
+        buf
+    }
+
+    #[test]
+    fn verify_cbr_duration() {
+        for &n_frames in &[3, 1000] {
+            let mss = MediaSourceStream::new(
+                Box::new(std::io::Cursor::new(make_cbr(n_frames))),
+                Default::default(),
+            );
+
+            let reader = MpaReader::try_new(mss, &Default::default()).unwrap();
+
+            assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(n_frames as u64 * 1152));
+        }
+    }
+
+    #[test]
+    fn verify_false_sync_rejected() {
+        let mut buf = Vec::new();
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+        // A false sync word with different stream parameters followed by a short run of junk.
+        push_frame(&mut buf, HEADER_48K, 100);
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        let mut mss =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        let (first, _) = read_mpeg_frame_strict(&mut mss, None, u64::MAX).unwrap();
+        let (second, _) = read_mpeg_frame_strict(&mut mss, Some(&first), u64::MAX).unwrap();
+        assert_eq!(mss.pos(), 2 * 417);
+
+        let (third, packet) = read_mpeg_frame_strict(&mut mss, Some(&second), u64::MAX).unwrap();
+        assert_eq!(third.sample_rate, 44_100);
+        assert_eq!(packet.len(), 417);
+        assert_eq!(mss.pos(), 3 * 417 + 100);
+
+        let (fourth, _) = read_mpeg_frame_strict(&mut mss, Some(&third), u64::MAX).unwrap();
+        assert_eq!(fourth.sample_rate, 44_100);
+        assert_eq!(mss.pos(), 4 * 417 + 100);
+    }
+
+    #[test]
+    fn verify_resync_limit() {
+        let mut buf = Vec::new();
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+        // A run of junk between frames.
+        buf.resize(buf.len() + 1000, 0);
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+
+        let read_third = |max_skip| {
+            let mut mss = MediaSourceStream::new(
+                Box::new(std::io::Cursor::new(buf.clone())),
+                Default::default(),
+            );
+
+            let (first, _) = read_mpeg_frame_strict(&mut mss, None, max_skip).unwrap();
+            let (second, _) = read_mpeg_frame_strict(&mut mss, Some(&first), max_skip).unwrap();
+            read_mpeg_frame_strict(&mut mss, Some(&second), max_skip).map(|_| mss.pos())
+        };
+
+        assert_eq!(read_third(u64::MAX).unwrap(), 3 * 417 + 1000);
+        assert_eq!(read_third(1000).unwrap(), 3 * 417 + 1000);
+        assert!(matches!(read_third(999), Err(Error::LimitError(_))));
+
+        // The limit is set by the format options.
+        let options =
+            FormatOptions { limit_resync_bytes: Limit::Maximum(999), ..Default::default() };
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+        let mut reader = MpaReader::try_new(mss, &options).unwrap();
+
+        assert!(reader.next_packet().is_ok());
+        assert!(reader.next_packet().is_ok());
+        assert!(matches!(reader.next_packet(), Err(Error::LimitError(_))));
+    }
+
+    #[test]
+    fn verify_parameter_change_accepted() {
+        let mut buf = Vec::new();
+
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_44K, 417);
+        push_frame(&mut buf, HEADER_48K, 384);
+        push_frame(&mut buf, HEADER_48K, 384);
+
+        let mut mss =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+
+        let (first, _) = read_mpeg_frame_strict(&mut mss, None, u64::MAX).unwrap();
+        let (second, _) = read_mpeg_frame_strict(&mut mss, Some(&first), u64::MAX).unwrap();
+        assert_eq!(second.sample_rate, 44_100);
+
+        // A contiguous frame with new parameters is accepted if the next frame agrees.
+        let (third, _) = read_mpeg_frame_strict(&mut mss, Some(&second), u64::MAX).unwrap();
+        assert_eq!(third.sample_rate, 48_000);
+        assert_eq!(mss.pos(), 2 * 417 + 384);
+    }
+
+    #[test]
+    fn verify_analysis_cache() {
+        let mut buf = Vec::new();
+
+        for _ in 0..200 {
+            push_frame(&mut buf, HEADER_44K, 417);
+        }
+
+        let mss = || {
+            let source = Box::new(std::io::Cursor::new(buf.clone()));
+            MediaSourceStream::new(source, Default::default())
+        };
+
+        let open = |cache: &AnalysisCache| {
+            MpaReader::try_new_with_analysis_cache(
+                mss(),
+                &Default::default(),
+                &Default::default(),
+                cache,
+            )
+            .unwrap()
+        };
+
+        let options = FormatOptions { seek_index_fill_rate: 1, ..Default::default() };
+
+        let mut reader = MpaReader::try_new(mss(), &options).unwrap();
+
+        // The exact duration is not known until all packets are read.
+        assert_eq!(reader.analysis_cache().unwrap().n_frames, None);
+
+        while reader.next_packet().is_ok() {}
+
+        let cache = reader.analysis_cache().unwrap();
+
+        assert_eq!(cache.byte_len, Some(200 * 417));
+        assert_eq!(cache.n_frames, Some(200 * 1152));
+
+        // A seek point is added at least every second.
+        let points: Vec<(u64, u64)> =
+            cache.seek_points.iter().map(|p| (p.frame_ts, p.byte_offset)).collect();
+        let expected: Vec<(u64, u64)> =
+            [0, 39, 78, 117, 156, 195].iter().map(|&frame| (frame * 1152, frame * 417)).collect();
+        assert_eq!(points, expected);
+
+        // The cached duration and seek index are used when reopening.
+        let cache = AnalysisCache::from_bytes(&cache.to_bytes()).unwrap();
+
+        let mut reader = open(&AnalysisCache { n_frames: Some(1234), ..cache.clone() });
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(1234));
+
+        let seeked = reader
+            .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 150 * 1152, track_id: 0 })
+            .unwrap();
+        assert_eq!(seeked.actual_ts, 150 * 1152);
+        assert_eq!(reader.next_packet().unwrap().ts, 150 * 1152);
+
+        // The cache is also used when the reader is instantiated by a probe.
+        let mut probe: symphonia_core::probe::Probe = Default::default();
+        probe.register_all::<MpaReader>();
+
+        let mut hint = symphonia_core::probe::Hint::new();
+        hint.with_analysis_cache(std::sync::Arc::new(AnalysisCache {
+            n_frames: Some(1234),
+            ..cache.clone()
+        }));
+
+        let probed = probe.format(&hint, mss(), &Default::default(), &Default::default()).unwrap();
+        assert_eq!(probed.format.tracks()[0].codec_params.n_frames, Some(1234));
+
+        // A cache for different media is ignored.
+        let reader = open(&AnalysisCache { byte_len: Some(1), n_frames: Some(1234), ..cache });
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(200 * 1152));
+    }
+
+    #[test]
+    fn verify_full_scan() {
+        /// MPEG1 layer 3, 64 kbps, 44.1 kHz, stereo (208 bytes).
+        const HEADER_44K_64K: u32 = 0xfffb_5000;
+        /// MPEG1 layer 3, 192 kbps, 44.1 kHz, stereo (626 bytes).
+        const HEADER_44K_192K: u32 = 0xfffb_b000;
+
+        // A VBR stream without a Xing or VBRI header.
+        let mut buf = Vec::new();
+
+        for _ in 0..100 {
+            push_frame(&mut buf, HEADER_44K, 417);
+        }
+        for _ in 0..100 {
+            push_frame(&mut buf, HEADER_44K_64K, 208);
+        }
+        for _ in 0..100 {
+            push_frame(&mut buf, HEADER_44K_192K, 626);
+        }
+
+        let options =
+            FormatOptions { enable_full_scan: true, seek_index_fill_rate: 1, ..Default::default() };
+
+        let source = Box::new(std::io::Cursor::new(buf));
+        let mut reader =
+            MpaReader::try_new(MediaSourceStream::new(source, Default::default()), &options)
+                .unwrap();
+
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(300 * 1152));
+
+        // A seek point is added at least every second throughout the entire stream.
+        let cache = reader.analysis_cache().unwrap();
+        assert_eq!(cache.n_frames, Some(300 * 1152));
+        assert_eq!(cache.seek_points.len(), 8);
+        assert_eq!(cache.seek_points[7].frame_ts, 273 * 1152);
+        assert_eq!(cache.seek_points[7].byte_offset, 100 * 417 + 100 * 208 + 73 * 626);
+
+        // After the scan, packets are read from the start of the stream.
+        assert_eq!(reader.next_packet().unwrap().ts, 0);
+
+        let seeked = reader
+            .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 250 * 1152, track_id: 0 })
+            .unwrap();
+        assert_eq!(seeked.actual_ts, 250 * 1152);
+        assert_eq!(reader.next_packet().unwrap().ts, 250 * 1152);
+    }
+
+    #[test]
+    fn verify_cbr_seek() {
+        let open = |buf: Vec<u8>| {
+            let source = Box::new(std::io::Cursor::new(buf));
+            MpaReader::try_new(
+                MediaSourceStream::new(source, Default::default()),
+                &Default::default(),
+            )
+            .unwrap()
+        };
+
+        let mut reader = open(make_cbr(1000));
+        assert!(reader.cbr.is_some());
+
+        // A coarse seek is exact, and neither seek requires a seek index.
+        let seeked = reader
+            .seek(SeekMode::Coarse, SeekTo::TimeStamp { ts: 700 * 1152 + 100, track_id: 0 })
+            .unwrap();
+        assert_eq!(seeked.actual_ts, 700 * 1152);
+        assert!(reader.is_ts_exact);
+        assert_eq!(reader.next_packet().unwrap().ts, 700 * 1152);
+
+        for &frame in &[500, 10, 999] {
+            let seeked = reader
+                .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: frame * 1152 + 100, track_id: 0 })
+                .unwrap();
+            assert_eq!(seeked.actual_ts, frame * 1152);
+            assert_eq!(reader.next_packet().unwrap().ts, frame * 1152);
+        }
+
+        assert!(reader.index.points().len() <= 1);
+
+        // Without padding, the average frame length drifts from the bitrate.
+        let mut buf = Vec::new();
+
+        for _ in 0..100 {
+            push_frame(&mut buf, HEADER_44K, 417);
+        }
+
+        assert!(open(buf).cbr.is_none());
+    }
+
+    #[test]
+    fn verify_emphasis() {
+        let open = |header: u32| {
+            let mut buf = Vec::new();
+
+            for _ in 0..3 {
+                push_frame(&mut buf, header, 417);
+            }
+
+            let source = Box::new(std::io::Cursor::new(buf));
+            let mss = MediaSourceStream::new(source, Default::default());
+            let reader = MpaReader::try_new(mss, &Default::default()).unwrap();
+            reader.tracks()[0].codec_params.emphasis
+        };
+
+        assert_eq!(open(HEADER_44K), None);
+        assert_eq!(open(HEADER_44K | 0x1), Some(emphasis::Emphasis::Fifty15));
+        assert_eq!(open(HEADER_44K | 0x3), Some(emphasis::Emphasis::CcittJ17));
+    }
+
+    #[test]
+    fn verify_frames() {
+        let mut buf = make_cbr(10);
+        let n_bytes = buf.len();
+
+        // Trailing garbage is not part of any frame.
+        buf.extend_from_slice(&[0xff, 0x00, 0x12]);
+
+        let mss =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf.clone())), Default::default());
+
+        let options = FormatOptions { enable_gapless: true, ..Default::default() };
+        let mut reader = MpaReader::try_new(mss, &options).unwrap();
+
+        let frames = reader.frames().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(frames.len(), 10);
+
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.ts, i as u64 * 1152);
+            assert_eq!(frame.dur, 1152);
+            assert_eq!(frame.data.len(), frame.header.frame_len());
+        }
+
+        // The frames are an exact copy of the stream.
+        assert_eq!(
+            frames.iter().flat_map(|frame| frame.data.iter().copied()).collect::<Vec<_>>(),
+            &buf[..n_bytes]
+        );
+
+        assert!(reader.frames().next().is_none());
+    }
+}
@@ -0,0 +1,177 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `fixed` module implements the fixed-point number type used by the integer-only decode path.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::lanes::Lanes;
+
+/// A signed Q7.24 fixed-point number.
+///
+/// With 24 fractional bits, the precision of a `Fixed` exceeds that required for 24-bit PCM output,
+/// while 7 integer bits provide ample headroom for the intermediate values of the IMDCT and
+/// polyphase synthesis. All arithmetic saturates instead of wrapping on overflow.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    /// The number of fractional bits.
+    pub const FRAC_BITS: u32 = 24;
+
+    /// Converts a floating point number into the nearest `Fixed`.
+    ///
+    /// This is only used to generate lookup tables, decoding never performs floating point
+    /// arithmetic.
+    pub fn from_f64(value: f64) -> Fixed {
+        let scaled = (value * f64::from(1u32 << Fixed::FRAC_BITS)).round();
+        Fixed(scaled.max(f64::from(i32::MIN)).min(f64::from(i32::MAX)) as i32)
+    }
+
+    /// Converts the `Fixed` into a floating point number.
+    #[allow(dead_code)]
+    pub fn to_f64(self) -> f64 {
+        f64::from(self.0) / f64::from(1u32 << Fixed::FRAC_BITS)
+    }
+}
+
+/// Converts a constant floating point number into the nearest `Fixed` at compile-time.
+macro_rules! fixed_const {
+    ($value:expr) => {{
+        const SCALE: f64 = (1u32 << $crate::fixed::Fixed::FRAC_BITS) as f64;
+        let value = $value as f64;
+        if value < 0.0 {
+            $crate::fixed::Fixed((value * SCALE - 0.5) as i32)
+        }
+        else {
+            $crate::fixed::Fixed((value * SCALE + 0.5) as i32)
+        }
+    }};
+}
+
+/// Converts a constant array of floating point numbers into an array of `Fixed` at compile-time.
+macro_rules! fixed_const_array {
+    ($values:expr, $len:literal) => {{
+        let values = $values;
+        let mut fixed = [$crate::fixed::Fixed(0); $len];
+        let mut i = 0;
+        while i < $len {
+            fixed[i] = fixed_const!(values[i]);
+            i += 1;
+        }
+        fixed
+    }};
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn mul(self, rhs: Fixed) -> Fixed {
+        // Multiply into a 64-bit intermediate, then round to nearest while removing the extra
+        // fractional bits.
+        let product = i64::from(self.0) * i64::from(rhs.0);
+        let rounded = (product + (1 << (Fixed::FRAC_BITS - 1))) >> Fixed::FRAC_BITS;
+        Fixed(rounded.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Fixed) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    #[inline(always)]
+    fn sub_assign(&mut self, rhs: Fixed) {
+        *self = *self - rhs;
+    }
+}
+
+impl Lanes for Fixed {
+    type Coeff = Fixed;
+
+    #[inline(always)]
+    fn half(self) -> Self {
+        Fixed(self.0 >> 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fixed;
+
+    #[test]
+    fn verify_fixed_conversions() {
+        const HALF: Fixed = fixed_const!(0.5);
+        const NEG_QUARTER: Fixed = fixed_const!(-0.25);
+        const TABLE: [Fixed; 3] = fixed_const_array!([1.0f32, -1.0, 0.75], 3);
+
+        assert_eq!(HALF, Fixed(1 << 23));
+        assert_eq!(NEG_QUARTER, Fixed(-(1 << 22)));
+        assert_eq!(TABLE, [Fixed(1 << 24), Fixed(-(1 << 24)), Fixed(3 << 22)]);
+
+        assert_eq!(Fixed::from_f64(0.5), HALF);
+        assert_eq!(Fixed::from_f64(-0.25), NEG_QUARTER);
+        assert_eq!(Fixed::from_f64(1000.0), Fixed(i32::MAX));
+        assert_eq!(Fixed(3 << 22).to_f64(), 0.75);
+    }
+
+    #[test]
+    fn verify_fixed_arithmetic() {
+        let a = Fixed::from_f64(1.5);
+        let b = Fixed::from_f64(-0.25);
+
+        assert_eq!(a + b, Fixed::from_f64(1.25));
+        assert_eq!(a - b, Fixed::from_f64(1.75));
+        assert_eq!(a * b, Fixed::from_f64(-0.375));
+        assert_eq!(-a, Fixed::from_f64(-1.5));
+
+        // Products round to nearest.
+        assert_eq!(Fixed(1) * Fixed::from_f64(0.5), Fixed(1));
+        assert_eq!(Fixed(1) * Fixed::from_f64(0.25), Fixed(0));
+
+        // All operations saturate.
+        let max = Fixed(i32::MAX);
+        let min = Fixed(i32::MIN);
+
+        assert_eq!(max + a, max);
+        assert_eq!(min - a, min);
+        assert_eq!(max * Fixed::from_f64(100.0), max);
+        assert_eq!(max * Fixed::from_f64(-100.0), min);
+        assert_eq!(-min, max);
+    }
+}
@@ -5,8 +5,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::errors::{decode_error, limit_error, unsupported_error, Result};
 use symphonia_core::io::ReadBytes;
+use symphonia_core::util::checked;
 
 use crate::common::*;
 
@@ -77,28 +78,36 @@ pub fn is_frame_header_word_synced(sync: u32) -> bool {
 /// Synchronize the provided reader to the end of the frame header, and return the frame header as
 /// as `u32`.
 pub fn sync_frame<B: ReadBytes>(reader: &mut B) -> Result<u32> {
-    let mut sync = 0u32;
+    sync_frame_within(reader, u64::MAX)
+}
 
-    loop {
-        // Synchronize stream to the next frame using the sync word. The MPEG audio frame header
-        // always starts at a byte boundary with 0xffe (11 consecutive 1 bits.) if supporting up to
-        // MPEG version 2.5.
-        while !is_frame_header_word_synced(sync) {
-            sync = (sync << 8) | u32::from(reader.read_u8()?);
-        }
+/// Synchronize the provided reader to the end of the frame header, skipping atmost `max_skip` bytes
+/// before the frame header, and return the frame header as a `u32`. If the frame header is not
+/// found within the limit, a `LimitError` is returned.
+pub fn sync_frame_within<B: ReadBytes>(reader: &mut B, max_skip: u64) -> Result<u32> {
+    let mut sync = 0u32;
 
-        // Random data can look like a sync word. Do a quick check to increase confidence that
-        // this is may be the start of a frame.
-        if check_header(sync) {
-            break;
+    // The frame header itself is not skipped.
+    let max_read = max_skip.saturating_add(MPEG_HEADER_LEN as u64);
+    let mut n_read = 0;
+
+    // Synchronize stream to the next frame using the sync word. The MPEG audio frame header always
+    // starts at a byte boundary with 0xffe (11 consecutive 1 bits.) if supporting up to MPEG
+    // version 2.5. Random data can look like a sync word. Do a quick check to increase confidence
+    // that this is may be the start of a frame.
+    while !(is_frame_header_word_synced(sync) && check_header(sync)) {
+        if n_read >= max_read {
+            return limit_error("mpa: frame header not found within the resync limit");
         }
 
         sync = (sync << 8) | u32::from(reader.read_u8()?);
+        n_read += 1;
     }
 
     Ok(sync)
 }
 
+/// Parse a MPEG audio frame header word, including the sync word, into a `FrameHeader`.
 pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     // The MPEG audio header is structured as follows:
     //
@@ -186,9 +195,10 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     }
 
     let emphasis = match header & 0x3 {
+        0b00 => Emphasis::None,
         0b01 => Emphasis::Fifty15,
-        0b11 => Emphasis::CcitJ17,
-        _ => Emphasis::None,
+        0b10 => Emphasis::Reserved,
+        _ => Emphasis::CcitJ17,
     };
 
     let is_copyrighted = header & 0x8 != 0x0;
@@ -216,7 +226,7 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     let frame_size_slots = (factor * bitrate / sample_rate) as usize + usize::from(has_padding);
 
     // Calculate the frame size in bytes, excluding the header.
-    let frame_size = (frame_size_slots * slot_size) - 4;
+    let frame_size = checked::sub(frame_size_slots * slot_size, 4, "mpa: invalid frame size")?;
 
     Ok(FrameHeader {
         version,
@@ -237,7 +247,6 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
 /// Synchronize the stream to the start of the next MPEG audio frame header, then read and return
 /// the frame header or an error.
 #[inline]
-#[allow(dead_code)]
 pub fn read_frame_header<B: ReadBytes>(reader: &mut B) -> Result<FrameHeader> {
     // Synchronize and parse the frame header.
     parse_frame_header(sync_frame(reader)?)
@@ -249,3 +258,45 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B) -> Result<FrameHeader> {
 pub fn read_frame_header_word_no_sync<B: ReadBytes>(reader: &mut B) -> Result<u32> {
     Ok(reader.read_be_u32()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    #[test]
+    fn verify_read_frame_header() {
+        // Junk, followed by a MPEG1 layer 3, 128 kbps, 44.1 kHz, joint stereo frame header with
+        // padding.
+        let buf = [0x00, 0xff, 0x12, 0xff, 0xfb, 0x92, 0x64];
+
+        let header = read_frame_header(&mut BufReader::new(&buf)).unwrap();
+
+        assert_eq!(header.version, MpegVersion::Mpeg1);
+        assert_eq!(header.layer, MpegLayer::Layer3);
+        assert_eq!(header.bitrate, 128_000);
+        assert_eq!(header.sample_rate, 44_100);
+        assert_eq!(
+            header.channel_mode,
+            ChannelMode::JointStereo(Mode::Layer3 { mid_side: true, intensity: false })
+        );
+        assert!(header.has_padding);
+        assert!(!header.has_crc);
+        assert_eq!(header.duration(), 1152);
+        assert_eq!(header.frame_len(), 418);
+
+        // MPEG2 layer 2, 64 kbps, 22.05 kHz, mono, with CRC.
+        let header = parse_frame_header(0xfff4_80c0).unwrap();
+
+        assert_eq!(header.version, MpegVersion::Mpeg2);
+        assert_eq!(header.layer, MpegLayer::Layer2);
+        assert_eq!(header.bitrate, 64_000);
+        assert_eq!(header.channel_mode, ChannelMode::Mono);
+        assert!(header.has_crc);
+        assert_eq!(header.duration(), 1152);
+        assert_eq!(header.frame_len(), 417);
+
+        // Free bit-rate.
+        assert!(parse_frame_header(0xfffb_0000).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Support for ICY (SHOUTcast) metadata interleaved in an internet radio stream.
+//!
+//! If an internet radio server is requested to send metadata (using the `Icy-MetaData: 1` HTTP
+//! request header), it responds with the `icy-metaint` HTTP response header, and inserts a metadata
+//! block after every `icy-metaint` bytes of audio data. A metadata block consists of a single byte
+//! length, in units of 16 bytes, followed by the metadata text padded with null bytes. The text is a
+//! list of `Key='Value';` pairs (e.g., `StreamTitle='Artist - Title';`).
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, MetadataRevision};
+use symphonia_metadata::icy::parse_icy_metadata;
+
+use log::warn;
+
+/// A queue of the metadata revisions read from ICY metadata blocks.
+pub type IcyQueue = Arc<Mutex<VecDeque<MetadataRevision>>>;
+
+/// An `IcySource` strips ICY metadata blocks from an internet radio stream, and pushes the
+/// metadata they contain to a queue.
+pub struct IcySource {
+    inner: MediaSourceStream,
+    /// The number of audio bytes between metadata blocks.
+    metaint: usize,
+    /// The number of audio bytes until the next metadata block.
+    remaining: usize,
+    /// The last non-empty metadata block.
+    last_block: Box<[u8]>,
+    queue: IcyQueue,
+}
+
+impl IcySource {
+    /// Instantiates a new `IcySource` for a stream with a metadata block after every `metaint`
+    /// bytes of audio data. The current position of the stream is assumed to be the start of the
+    /// stream body.
+    pub fn new(inner: MediaSourceStream, metaint: u32, queue: IcyQueue) -> Self {
+        let metaint = metaint as usize;
+
+        // The stream may have been read before. As long as the first metadata block was not read,
+        // the count of audio bytes can still be synchronized to the stream.
+        let pos = inner.pos() as usize;
+
+        let remaining = if pos <= metaint {
+            metaint - pos
+        }
+        else {
+            warn!("icy: stream was read past the first metadata block");
+            metaint
+        };
+
+        IcySource { inner, metaint, remaining, last_block: Box::new([]), queue }
+    }
+
+    /// Reads a metadata block, and pushes the metadata it contains to the queue if it changed.
+    fn read_metadata_block(&mut self) -> io::Result<()> {
+        let len = 16 * usize::from(self.inner.read_byte()?);
+
+        // An empty block indicates the metadata has not changed.
+        if len == 0 {
+            return Ok(());
+        }
+
+        let block = self.inner.read_boxed_slice_exact(len)?;
+
+        // Servers often repeat the same metadata in every block.
+        if block == self.last_block {
+            return Ok(());
+        }
+
+        let mut builder = MetadataBuilder::new();
+
+        for tag in parse_icy_metadata(&block) {
+            builder.add_tag(tag);
+        }
+
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.push_back(builder.metadata());
+        }
+
+        self.last_block = block;
+
+        Ok(())
+    }
+}
+
+impl MediaSource for IcySource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl io::Read for IcySource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            // The stream may end instead of the next metadata block.
+            match self.read_metadata_block() {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                result => result?,
+            }
+
+            self.remaining = self.metaint;
+        }
+
+        let len = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..len])?;
+
+        self.remaining -= n;
+
+        Ok(n)
+    }
+}
+
+impl io::Seek for IcySource {
+    fn seek(&mut self, _: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Other, "icy: stream is not seekable"))
+    }
+}
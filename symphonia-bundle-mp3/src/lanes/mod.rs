@@ -0,0 +1,43 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Lane types for writing DSP kernels once, and running them on either a single sample (`f32`), or
+//! on multiple samples in parallel (`F32x4`).
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// Only layer 3 processes multiple sub-bands in parallel.
+#[cfg(feature = "mp3")]
+pub mod x4;
+
+/// A type of lane(s) a kernel may operate on.
+pub trait Lanes:
+    Copy
+    + Default
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Mul<<Self as Lanes>::Coeff, Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+{
+    /// The scalar type of the coefficients all lanes may be multiplied by.
+    type Coeff: Copy;
+
+    /// Halves the value of all lanes.
+    fn half(self) -> Self;
+}
+
+impl Lanes for f32 {
+    type Coeff = f32;
+
+    #[inline(always)]
+    fn half(self) -> Self {
+        self * 0.5
+    }
+}
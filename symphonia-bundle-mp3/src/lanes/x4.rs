@@ -0,0 +1,119 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Four `f32` lanes, and helpers to move blocks of samples in and out of them.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use super::Lanes;
+
+/// Four `f32` lanes.
+///
+/// Since unsafe code, and thus explicit SIMD intrinsics, are not allowed, all operations are
+/// performed lane-wise on a plain fixed-size array. Whether these operations are vectorized is left
+/// to the optimizer, and is not guaranteed.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct F32x4(pub [f32; 4]);
+
+impl Lanes for F32x4 {
+    type Coeff = f32;
+
+    #[inline(always)]
+    fn half(self) -> Self {
+        self * 0.5
+    }
+}
+
+impl Add for F32x4 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        let (a, b) = (self.0, rhs.0);
+        F32x4([a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]])
+    }
+}
+
+impl Sub for F32x4 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        let (a, b) = (self.0, rhs.0);
+        F32x4([a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]])
+    }
+}
+
+impl Mul for F32x4 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (self.0, rhs.0);
+        F32x4([a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]])
+    }
+}
+
+impl Mul<f32> for F32x4 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: f32) -> Self {
+        let a = self.0;
+        F32x4([a[0] * rhs, a[1] * rhs, a[2] * rhs, a[3] * rhs])
+    }
+}
+
+impl Neg for F32x4 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn neg(self) -> Self {
+        let a = self.0;
+        F32x4([-a[0], -a[1], -a[2], -a[3]])
+    }
+}
+
+impl AddAssign for F32x4 {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for F32x4 {
+    #[inline(always)]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+/// Transposes 4 blocks of 18 samples into 18 vectors of 4 lanes, where lane `n` of each vector
+/// holds a sample from block `n`.
+#[inline(always)]
+pub fn gather_x4(blocks: [&[f32; 18]; 4]) -> [F32x4; 18] {
+    let mut out = [F32x4::default(); 18];
+
+    for (i, lanes) in out.iter_mut().enumerate() {
+        *lanes = F32x4([blocks[0][i], blocks[1][i], blocks[2][i], blocks[3][i]]);
+    }
+
+    out
+}
+
+/// The inverse of `gather_x4`.
+#[inline(always)]
+pub fn scatter_x4(src: &[F32x4; 18], blocks: [&mut [f32; 18]; 4]) {
+    let [b0, b1, b2, b3] = blocks;
+
+    for (i, lanes) in src.iter().enumerate() {
+        b0[i] = lanes.0[0];
+        b1[i] = lanes.0[1];
+        b2[i] = lanes.0[2];
+        b3[i] = lanes.0[3];
+    }
+}
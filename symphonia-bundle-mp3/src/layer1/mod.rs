@@ -6,45 +6,38 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::audio::{AudioBuffer, Signal};
+use symphonia_core::codecs::DualMonoPolicy;
 use symphonia_core::errors::{decode_error, Result};
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::io::{BitReaderLtr, BufReader, FiniteBitStream, ReadBitsLtr, ReadBytes};
+use symphonia_core::sample::Sample;
 use symphonia_core::util::bits::sign_extend_leq32_to_i32;
 
 use crate::common::*;
-use crate::layer12::LAYER12_SCALEFACTORS;
-use crate::synthesis;
-
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref FACTOR: [f32; 16] = {
-        let mut factor = [0f32; 16];
-
-        for (i, factor) in factor.iter_mut().enumerate().skip(2) {
-            // As per ISO/IEC 11172-3, given the nb-bit signed raw sample, val, dequantization is
-            // defined as follows.
-            //
-            // fractional = val / 2^(nb - 1)
-            // dequantized = (2^nb) / (2^nb - 1) * (fractional * 2^(-nb + 1))
-            //
-            // After combining, expanding, and simplifying the above equations, the complete
-            // calculation can be expressed as below.
-            //
-            // [(2^nb) / ((2^nb) - 1)] * 2^(-nb + 1) * (val + 1)
-            // -------------------------------------
-            //                 factor
-            //
-            // Therefore, dequantization can be reduced to a single multiplication and addition.
-            // This lookup table generator computes factor for nb-bits between 2..15, inclusive.
-            let a = 1 << i;
-            let b = 1 << (i - 1);
-
-            *factor = (a as f32 / (a - 1) as f32) * (b as f32).recip();
-        }
-
-        factor
-    };
-}
+use crate::layer12::{read_ancillary_data, LAYER12_SCALEFACTORS};
+use crate::synthesis::{self, FromSynthesized};
+
+/// Layer 1 dequantization factors, indexed by the number of bits, nb, of a raw sample.
+///
+/// As per ISO/IEC 11172-3, given the nb-bit signed raw sample, val, dequantization is defined as
+/// follows.
+///
+/// ```text
+/// fractional = val / 2^(nb - 1)
+/// dequantized = (2^nb) / (2^nb - 1) * (fractional * 2^(-nb + 1))
+/// ```
+///
+/// After combining, expanding, and simplifying the above equations, the complete calculation can
+/// be expressed as below.
+///
+/// ```text
+/// [(2^nb) / ((2^nb) - 1)] * 2^(-nb + 1) * (val + 1)
+/// -------------------------------------
+///                 factor
+/// ```
+///
+/// Therefore, dequantization can be reduced to a single multiplication and addition. This table
+/// contains factor for nb-bits between 2..15, inclusive. The table is generated by the build script.
+static FACTOR: [f32; 16] = include!(concat!(env!("OUT_DIR"), "/layer1_factor.rs"));
 
 /// Dequantize a sample, `raw`, of length `bits` bits.
 #[inline(always)]
@@ -61,25 +54,63 @@ fn dequantize(bits: u32, factor: f32, raw: u32) -> f32 {
 
 pub struct Layer1 {
     pub synthesis: [synthesis::SynthesisState; 2],
+    /// The ancillary data of the last decoded frame.
+    pub ancillary: Vec<u8>,
+    /// The policy for outputting dual mono frames.
+    pub dual_mono: DualMonoPolicy,
 }
 
 impl Layer1 {
     pub fn new() -> Self {
-        Self { synthesis: Default::default() }
+        Self {
+            synthesis: Default::default(),
+            ancillary: Vec::new(),
+            dual_mono: DualMonoPolicy::Both,
+        }
+    }
+
+    /// Resets the decoder to its initial state without reallocating.
+    pub fn reset(&mut self) {
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.reset();
+        }
+        self.ancillary.clear();
+    }
+
+    /// Appends the state of the decoder to `buf`.
+    pub fn save_state(&self, buf: &mut Vec<u8>) {
+        for synthesis in self.synthesis.iter() {
+            synthesis.save(buf);
+        }
+    }
+
+    /// Restores a state previously saved by `save_state` from `reader`.
+    pub fn restore_state(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.restore(reader)?;
+        }
+        Ok(())
     }
 }
 
 impl Layer for Layer1 {
-    fn decode(
+    fn decode<O>(
         &mut self,
         reader: &mut BufReader<'_>,
         header: &FrameHeader,
-        out: &mut AudioBuffer<f32>,
-    ) -> Result<()> {
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<f32>,
+    {
+        self.ancillary.clear();
+
         // Ignore the CRC.
         let _crc = if header.has_crc { Some(reader.read_be_u16()?) } else { None };
 
-        let mut bs = BitReaderLtr::new(reader.read_buf_bytes_available_ref());
+        let frame = reader.read_buf_bytes_available_ref();
+
+        let mut bs = BitReaderLtr::new(frame);
 
         let mut alloc = [[0; 32]; 2];
         let mut scalefacs = [[0.0; 32]; 2];
@@ -99,7 +130,7 @@ impl Layer for Layer1 {
 
         // Read bit allocations for each non-intensity coded sub-bands.
         for sb in 0..bound {
-            for chan in &mut alloc {
+            for chan in &mut alloc[..num_channels] {
                 let bits = bs.read_bits_leq32(4)? as u8;
 
                 if bits > 0xe {
@@ -111,7 +142,9 @@ impl Layer for Layer1 {
         }
 
         // Read bit allocations for the intensity coded sub-bands.
-        for sb in bound..32 {
+        let [alloc0, alloc1] = &mut alloc;
+
+        for (a0, a1) in alloc0[bound..].iter_mut().zip(&mut alloc1[bound..]) {
             let bits = bs.read_bits_leq32(4)? as u8;
 
             if bits > 0xe {
@@ -120,8 +153,8 @@ impl Layer for Layer1 {
 
             let ba = if bits != 0 { bits + 1 } else { 0 };
 
-            alloc[0][sb] = ba;
-            alloc[1][sb] = ba;
+            *a0 = ba;
+            *a1 = ba;
         }
 
         // Read scalefactors for each sub-band.
@@ -178,11 +211,27 @@ impl Layer for Layer1 {
             }
         }
 
+        // The remainder of the frame is ancillary data.
+        read_ancillary_data(frame, bs.bits_left(), &mut self.ancillary);
+
         // Each packet will yield 384 audio frames. After reserving frames, all steps must be
         // infalliable.
         out.render_reserved(Some(384));
 
-        for (ch, samples) in samples.iter().enumerate().take(num_channels) {
+        if header.channel_mode == ChannelMode::DualMono {
+            let (left, right) = samples.split_at_mut(1);
+            synthesis::select_dual_mono(&mut left[0], &mut right[0], self.dual_mono);
+        }
+
+        // If the output is mono, downmix stereo frames before synthesis.
+        let num_out_channels = out.spec().channels.count();
+
+        if num_out_channels < num_channels {
+            let (left, right) = samples.split_at_mut(1);
+            synthesis::downmix_to_mono(&mut left[0], &right[0]);
+        }
+
+        for (ch, samples) in samples.iter().enumerate().take(num_out_channels) {
             // Perform polyphase synthesis and generate PCM samples.
             synthesis::synthesis(&mut self.synthesis[ch], 12, samples, out.chan_mut(ch));
         }
@@ -5,6 +5,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+/// Appends the ancillary data of a layer 1 or 2 frame to `ancillary`. The ancillary data are the
+/// whole bytes of `frame` remaining after the audio data, of which there are `bits_left` bits.
+pub fn read_ancillary_data(frame: &[u8], bits_left: u64, ancillary: &mut Vec<u8>) {
+    let len = (bits_left >> 3) as usize;
+
+    ancillary.extend_from_slice(&frame[frame.len() - len..]);
+}
+
 /// Layer 1 and 2 shared scale factors. Derived from ISO/ISO 11172-3 Table 3-B.1.
 pub const LAYER12_SCALEFACTORS: [f32; 64] = [
     2.00000000000000,
@@ -6,13 +6,15 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::audio::{AudioBuffer, Signal};
+use symphonia_core::codecs::DualMonoPolicy;
 use symphonia_core::errors::Result;
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::io::{BitReaderLtr, BufReader, FiniteBitStream, ReadBitsLtr, ReadBytes};
+use symphonia_core::sample::Sample;
 use symphonia_core::util::bits::sign_extend_leq32_to_i32;
 
 use crate::common::{ChannelMode, FrameHeader, Layer, Mode};
-use crate::layer12::LAYER12_SCALEFACTORS;
-use crate::synthesis;
+use crate::layer12::{read_ancillary_data, LAYER12_SCALEFACTORS};
+use crate::synthesis::{self, FromSynthesized};
 
 struct QuantClass {
     /// C constant.
@@ -223,25 +225,63 @@ fn dequantize(bs: &mut BitReaderLtr<'_>, class: &QuantClass) -> Result<[f32; 3]>
 
 pub struct Layer2 {
     pub synthesis: [synthesis::SynthesisState; 2],
+    /// The ancillary data of the last decoded frame.
+    pub ancillary: Vec<u8>,
+    /// The policy for outputting dual mono frames.
+    pub dual_mono: DualMonoPolicy,
 }
 
 impl Layer2 {
     pub fn new() -> Self {
-        Self { synthesis: Default::default() }
+        Self {
+            synthesis: Default::default(),
+            ancillary: Vec::new(),
+            dual_mono: DualMonoPolicy::Both,
+        }
+    }
+
+    /// Resets the decoder to its initial state without reallocating.
+    pub fn reset(&mut self) {
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.reset();
+        }
+        self.ancillary.clear();
+    }
+
+    /// Appends the state of the decoder to `buf`.
+    pub fn save_state(&self, buf: &mut Vec<u8>) {
+        for synthesis in self.synthesis.iter() {
+            synthesis.save(buf);
+        }
+    }
+
+    /// Restores a state previously saved by `save_state` from `reader`.
+    pub fn restore_state(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.restore(reader)?;
+        }
+        Ok(())
     }
 }
 
 impl Layer for Layer2 {
-    fn decode(
+    fn decode<O>(
         &mut self,
         reader: &mut BufReader<'_>,
         header: &FrameHeader,
-        out: &mut AudioBuffer<f32>,
-    ) -> Result<()> {
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<f32>,
+    {
+        self.ancillary.clear();
+
         // Ignore the CRC.
         let _crc = if header.has_crc { Some(reader.read_be_u16()?) } else { None };
 
-        let mut bs = BitReaderLtr::new(reader.read_buf_bytes_available_ref());
+        let frame = reader.read_buf_bytes_available_ref();
+
+        let mut bs = BitReaderLtr::new(frame);
 
         let mut alloc = [[0; 32]; 2];
         let mut scfsi = [[0; 32]; 2];
@@ -273,13 +313,20 @@ impl Layer for Layer2 {
         }
 
         // Read the class index (allocation in the standard) for each intensity coded sub-band.
-        for sb in bound..sb_info.sblimit {
-            let nbal = find_sb_quant_info(sb_info, sb).nbal;
+        let [alloc0, alloc1] = &mut alloc;
+
+        let intensity_bands = alloc0[bound..sb_info.sblimit]
+            .iter_mut()
+            .zip(&mut alloc1[bound..sb_info.sblimit])
+            .enumerate();
+
+        for (i, (a0, a1)) in intensity_bands {
+            let nbal = find_sb_quant_info(sb_info, bound + i).nbal;
 
             let value = bs.read_bits_leq32(u32::from(nbal))? as u8;
 
-            alloc[0][sb] = value;
-            alloc[1][sb] = value;
+            *a0 = value;
+            *a1 = value;
         }
 
         // Read scale factor selection information.
@@ -373,11 +420,27 @@ impl Layer for Layer2 {
             }
         }
 
+        // The remainder of the frame is ancillary data.
+        read_ancillary_data(frame, bs.bits_left(), &mut self.ancillary);
+
         // Each packet will yield 1152 audio frames. After reserving frames, all steps must be
         // infalliable.
         out.render_reserved(Some(1152));
 
-        for (ch, samples) in samples.iter().enumerate().take(num_channels) {
+        if header.channel_mode == ChannelMode::DualMono {
+            let (left, right) = samples.split_at_mut(1);
+            synthesis::select_dual_mono(&mut left[0], &mut right[0], self.dual_mono);
+        }
+
+        // If the output is mono, downmix stereo frames before synthesis.
+        let num_out_channels = out.spec().channels.count();
+
+        if num_out_channels < num_channels {
+            let (left, right) = samples.split_at_mut(1);
+            synthesis::downmix_to_mono(&mut left[0], &right[0]);
+        }
+
+        for (ch, samples) in samples.iter().enumerate().take(num_out_channels) {
             // Perform polyphase synthesis and generate PCM samples.
             synthesis::synthesis(&mut self.synthesis[ch], 36, samples, out.chan_mut(ch));
         }
@@ -129,9 +129,8 @@ fn read_granule_channel_side_info<B: ReadBitsLtr>(
             // for region0. These bands are also always  [4, 4, 4, 4, 4, 4, 4, 4, 4, ...] regardless
             // of sample and thus sum to 36 samples.
             //
-            // In all cases, the region0_count is 36.
-            //
-            // TODO: This is not accurate for MPEG2.5 at 8kHz.
+            // In all cases, the region0_count is 36. MPEG2.5, where the bands at 8 kHz are twice as
+            // wide, is handled above.
             channel.region1_start = 36;
         }
         // If MPEG version 2 AND the block type is not Short...
@@ -299,8 +298,8 @@ pub(super) fn read_scale_factors_mpeg1<B: ReadBitsLtr>(
                 granule1[0].channels[ch].scalefacs[*start..*end]
                     .copy_from_slice(&granule0.channels[ch].scalefacs[*start..*end]);
             }
-            // Otherwise, read the scale factors from the bitstream. Since scale factors are already
-            // zeroed out by default, don't do anything if slen is 0.
+            // Otherwise, read the scale factors from the bitstream. Since scale factors are zeroed
+            // before being read, don't do anything if slen is 0.
             else if slen > 0 {
                 for sfb in *start..*end {
                     frame_data.granules[gr].channels[ch].scalefacs[sfb] =
@@ -409,7 +408,7 @@ pub(super) fn read_scale_factors_mpeg2<B: ReadBitsLtr>(
     for (&slen, &n_sfb) in slen_table.iter().zip(nsfb_table.iter()) {
         // If slen > 0, read n_sfb scale factors with each scale factor being slen bits long. If
         // slen == 0, but n_sfb > 0, then the those scale factors should be set to 0. Since all
-        // scalefacs are preinitialized to 0, this process may be skipped.
+        // scalefacs are zeroed before being read, this process may be skipped.
         if slen > 0 {
             for sfb in start..(start + n_sfb) {
                 channel.scalefacs[sfb] = bs.read_bits_leq32(slen)? as u8;
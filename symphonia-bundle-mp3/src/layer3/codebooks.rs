@@ -555,6 +555,11 @@ pub const CODEBOOK_LINBITS: [u32; 32] = [
     13,
 ];
 
+/// The maximum number of entries in a codebook table. The width of the first read of each codebook
+/// is chosen to minimize the expected number of reads per codeword within this size. Tables with
+/// codewords of up-to 10 bits decode every codeword with a single read.
+const MAX_TABLE_LEN: usize = 2048;
+
 fn mpeg_gen_value(i: u16, wrap: u16) -> u16 {
     ((i / wrap) << 4) | (i % wrap)
 }
@@ -575,10 +580,11 @@ lazy_static! {
             // Generate the codebook.
             let mut builder = CodebookBuilder::new(BitOrder::Verbatim);
 
-            // Decode a maximum of 8 bits per read.
+            // Decode a maximum of 8 bits per read after the first read.
             builder.bits_per_read(8);
 
-            *codebook = builder.make(table.codes, table.lens, &values).unwrap();
+            *codebook =
+                builder.make_optimal(table.codes, table.lens, &values, MAX_TABLE_LEN).unwrap();
         }
 
         codebooks
@@ -601,10 +607,11 @@ lazy_static! {
             // Generate the codebook.
             let mut builder = CodebookBuilder::new(BitOrder::Verbatim);
 
-            // Decode a maximum of 8 bits per read.
+            // Decode a maximum of 8 bits per read after the first read.
             builder.bits_per_read(8);
 
-            *codebook = builder.make(table.codes, table.lens, &values).unwrap();
+            *codebook =
+                builder.make_optimal(table.codes, table.lens, &values, MAX_TABLE_LEN).unwrap();
         }
 
         codebooks
@@ -9,148 +9,202 @@
 #![allow(clippy::needless_range_loop)]
 
 use crate::common::FrameHeader;
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+use crate::lanes::x4::{gather_x4, scatter_x4, F32x4};
+use crate::lanes::Lanes;
 
 use super::{common::*, GranuleChannel};
 
-use std::{convert::TryInto, f64};
+use std::convert::TryInto;
+use std::ops::Range;
+
+pub use imdct36::Imdct36Coeffs;
+
+/// Hybrid synthesesis IMDCT window coefficients for: Long, Start, Short, and End block, in that
+/// order.
+///
+/// For long blocks:
+///
+/// ```text
+/// W[ 0..36] = sin(PI/36.0 * (i + 0.5))
+/// ```
+///
+/// For start blocks:
+///
+/// ```text
+/// W[ 0..18] = sin(PI/36.0 * (i + 0.5))
+/// W[18..24] = 1.0
+/// W[24..30] = sin(PI/12.0 * ((i - 18) - 0.5))
+/// W[30..36] = 0.0
+/// ```
+///
+/// For short blocks (to be applied to each 12 sample window):
+///
+/// ```text
+/// W[ 0..12] = sin(PI/12.0 * (i + 0.5))
+/// W[12..36] = 0.0
+/// ```
+///
+/// For end blocks:
+///
+/// ```text
+/// W[ 0..6 ] = 0.0
+/// W[ 6..12] = sin(PI/12.0 * ((i - 6) + 0.5))
+/// W[12..18] = 1.0
+/// W[18..36] = sin(PI/36.0 * (i + 0.5))
+/// ```
+static IMDCT_WINDOWS: [[f32; 36]; 4] = include!(concat!(env!("OUT_DIR"), "/imdct_windows.rs"));
+
+/// Lookup table of cosine coefficients for half of a 12-point IMDCT.
+///
+/// This table is derived from the general expression:
+///
+/// ```text
+/// cos12[i][k] = cos(PI/24.0 * (2*i + 1 + N/2) * (2*k + 1))
+/// ```
+/// where:
+///     `N=12`, `i=N/4..3N/4`, and `k=0..N/2`.
+static IMDCT_HALF_COS_12: [[f32; 6]; 6] =
+    include!(concat!(env!("OUT_DIR"), "/imdct_half_cos_12.rs"));
+
+/// Pair of lookup tables, CS and CA, for alias reduction.
+///
+/// As per ISO/IEC 11172-3, CS and CA are calculated as follows:
+///
+/// ```text
+/// cs[i] =  1.0 / sqrt(1.0 + c[i]^2)
+/// ca[i] = c[i] / sqrt(1.0 + c[i]^2)
+/// ```
+///
+/// where:
+/// ```text
+/// c[i] = [ -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037 ]
+/// ```
+static ANTIALIAS_CS_CA: ([f32; 8], [f32; 8]) =
+    include!(concat!(env!("OUT_DIR"), "/antialias_cs_ca.rs"));
+
+/// Fixed-point version of `IMDCT_WINDOWS`.
+#[cfg(feature = "fixed-point")]
+static IMDCT_WINDOWS_FIXED: [[Fixed; 36]; 4] =
+    include!(concat!(env!("OUT_DIR"), "/imdct_windows_fixed.rs"));
+
+/// Fixed-point version of `IMDCT_HALF_COS_12`.
+#[cfg(feature = "fixed-point")]
+static IMDCT_HALF_COS_12_FIXED: [[Fixed; 6]; 6] =
+    include!(concat!(env!("OUT_DIR"), "/imdct_half_cos_12_fixed.rs"));
+
+/// Fixed-point version of `ANTIALIAS_CS_CA`.
+#[cfg(feature = "fixed-point")]
+static ANTIALIAS_CS_CA_FIXED: ([Fixed; 8], [Fixed; 8]) =
+    include!(concat!(env!("OUT_DIR"), "/antialias_cs_ca_fixed.rs"));
+
+/// A sample type that hybrid synthesis can be performed on.
+pub trait HybridSynthesisSample: Lanes<Coeff = Self> + Imdct36Coeffs {
+    /// Gets the IMDCT window coefficients. See `IMDCT_WINDOWS`.
+    fn imdct_windows() -> &'static [[Self; 36]; 4];
+
+    /// Gets the cosine coefficients for half of a 12-point IMDCT. See `IMDCT_HALF_COS_12`.
+    fn imdct_half_cos_12() -> &'static [[Self; 6]; 6];
+
+    /// Gets the alias reduction coefficients. See `ANTIALIAS_CS_CA`.
+    fn antialias_cs_ca() -> &'static ([Self; 8], [Self; 8]);
+
+    /// Performs the windowed 36-point IMDCT on each sub-band in `sub_bands`.
+    fn imdct36_sub_bands(
+        samples: &mut [Self; 576],
+        overlap: &mut [[Self; 18]; 32],
+        window: &[Self; 36],
+        sub_bands: Range<usize>,
+    ) {
+        imdct36_scalar(samples, overlap, window, sub_bands)
+    }
 
-use lazy_static::lazy_static;
+    /// Performs the windowed 12-point IMDCT on each of the 3 short windows of each sub-band in
+    /// `sub_bands`.
+    fn imdct12_sub_bands(
+        samples: &mut [Self; 576],
+        overlap: &mut [[Self; 18]; 32],
+        window: &[Self; 36],
+        sub_bands: Range<usize>,
+    ) {
+        imdct12_scalar(samples, overlap, window, sub_bands)
+    }
+}
 
-lazy_static! {
-    /// Hybrid synthesesis IMDCT window coefficients for: Long, Start, Short, and End block, in that
-    /// order.
-    ///
-    /// For long blocks:
-    ///
-    /// ```text
-    /// W[ 0..36] = sin(PI/36.0 * (i + 0.5))
-    /// ```
-    ///
-    /// For start blocks:
-    ///
-    /// ```text
-    /// W[ 0..18] = sin(PI/36.0 * (i + 0.5))
-    /// W[18..24] = 1.0
-    /// W[24..30] = sin(PI/12.0 * ((i - 18) - 0.5))
-    /// W[30..36] = 0.0
-    /// ```
-    ///
-    /// For short blocks (to be applied to each 12 sample window):
-    ///
-    /// ```text
-    /// W[ 0..12] = sin(PI/12.0 * (i + 0.5))
-    /// W[12..36] = 0.0
-    /// ```
-    ///
-    /// For end blocks:
-    ///
-    /// ```text
-    /// W[ 0..6 ] = 0.0
-    /// W[ 6..12] = sin(PI/12.0 * ((i - 6) + 0.5))
-    /// W[12..18] = 1.0
-    /// W[18..36] = sin(PI/36.0 * (i + 0.5))
-    /// ```
-    static ref IMDCT_WINDOWS: [[f32; 36]; 4] = {
-        const PI_36: f64 = f64::consts::PI / 36.0;
-        const PI_12: f64 = f64::consts::PI / 12.0;
-
-        let mut windows = [[0f32; 36]; 4];
-
-        // Window for Long blocks.
-        for i in 0..36 {
-            windows[0][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
-        }
+impl HybridSynthesisSample for f32 {
+    #[inline(always)]
+    fn imdct_windows() -> &'static [[Self; 36]; 4] {
+        &IMDCT_WINDOWS
+    }
 
-        // Window for Start blocks (indicies 30..36 implictly 0.0).
-        for i in 0..18 {
-            windows[1][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
-        }
-        for i in 18..24 {
-            windows[1][i] = 1.0;
-        }
-        for i in 24..30 {
-            windows[1][i] = (PI_12 * ((i - 18) as f64 + 0.5)).sin() as f32;
-        }
+    #[inline(always)]
+    fn imdct_half_cos_12() -> &'static [[Self; 6]; 6] {
+        &IMDCT_HALF_COS_12
+    }
 
-        // Window for Short blocks.
-        for i in 0..12 {
-            windows[2][i] = (PI_12 * (i as f64 + 0.5)).sin() as f32;
-        }
+    #[inline(always)]
+    fn antialias_cs_ca() -> &'static ([Self; 8], [Self; 8]) {
+        &ANTIALIAS_CS_CA
+    }
 
-        // Window for End blocks (indicies 0..6 implicitly 0.0).
-        for i in 6..12 {
-            windows[3][i] = (PI_12 * ((i - 6) as f64 + 0.5)).sin() as f32;
-        }
-        for i in 12..18 {
-            windows[3][i] = 1.0;
-        }
-        for i in 18..36 {
-            windows[3][i] = (PI_36 * (i as f64 + 0.5)).sin() as f32;
+    fn imdct36_sub_bands(
+        samples: &mut [Self; 576],
+        overlap: &mut [[Self; 18]; 32],
+        window: &[Self; 36],
+        sub_bands: Range<usize>,
+    ) {
+        // Process as many sub-bands as possible 4 at a time using the 4-lane IMDCT.
+        let x4_end = x4_end(&sub_bands);
+
+        for sb in (sub_bands.start..x4_end).step_by(4) {
+            imdct_x4(samples, overlap, sb, |x, overlap| imdct36::imdct36(x, window, overlap));
         }
 
-        windows
-   };
-}
-
-lazy_static! {
-    /// Lookup table of cosine coefficients for half of a 12-point IMDCT.
-    ///
-    /// This table is derived from the general expression:
-    ///
-    /// ```text
-    /// cos12[i][k] = cos(PI/24.0 * (2*i + 1 + N/2) * (2*k + 1))
-    /// ```
-    /// where:
-    ///     `N=12`, `i=N/4..3N/4`, and `k=0..N/2`.
-    static ref IMDCT_HALF_COS_12: [[f32; 6]; 6] = {
-        const PI_24: f64 = f64::consts::PI / 24.0;
-
-        let mut cos = [[0f32; 6]; 6];
+        imdct36_scalar(samples, overlap, window, x4_end..sub_bands.end)
+    }
 
-        for (i, cos_i) in cos.iter_mut().enumerate() {
-            for (k, cos_ik) in cos_i.iter_mut().enumerate() {
-                // Only compute the middle half of the cosine lookup table (i offset by 3).
-                let n = (2 * (i + 3) + (12 / 2) + 1) * (2 * k + 1);
-                *cos_ik = (PI_24 * n as f64).cos() as f32;
-            }
+    fn imdct12_sub_bands(
+        samples: &mut [Self; 576],
+        overlap: &mut [[Self; 18]; 32],
+        window: &[Self; 36],
+        sub_bands: Range<usize>,
+    ) {
+        // Process as many sub-bands as possible 4 at a time using the 4-lane IMDCT.
+        let x4_end = x4_end(&sub_bands);
+
+        for sb in (sub_bands.start..x4_end).step_by(4) {
+            imdct_x4(samples, overlap, sb, |x, overlap| imdct12_win(x, window, overlap));
         }
 
-        cos
-    };
+        imdct12_scalar(samples, overlap, window, x4_end..sub_bands.end)
+    }
 }
 
-lazy_static! {
-    /// Pair of lookup tables, CS and CA, for alias reduction.
-    ///
-    /// As per ISO/IEC 11172-3, CS and CA are calculated as follows:
-    ///
-    /// ```text
-    /// cs[i] =  1.0 / sqrt(1.0 + c[i]^2)
-    /// ca[i] = c[i] / sqrt(1.0 + c[i]^2)
-    /// ```
-    ///
-    /// where:
-    /// ```text
-    /// c[i] = [ -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037 ]
-    /// ```
-    static ref ANTIALIAS_CS_CA: ([f32; 8], [f32; 8]) = {
-        const C: [f64; 8] = [ -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037 ];
-
-        let mut cs = [0f32; 8];
-        let mut ca = [0f32; 8];
+#[cfg(feature = "fixed-point")]
+impl HybridSynthesisSample for Fixed {
+    #[inline(always)]
+    fn imdct_windows() -> &'static [[Self; 36]; 4] {
+        &IMDCT_WINDOWS_FIXED
+    }
 
-        for i in 0..8 {
-            let sqrt = f64::sqrt(1.0 + (C[i] * C[i]));
-            cs[i] = (1.0 / sqrt) as f32;
-            ca[i] = (C[i] / sqrt) as f32;
-        }
+    #[inline(always)]
+    fn imdct_half_cos_12() -> &'static [[Self; 6]; 6] {
+        &IMDCT_HALF_COS_12_FIXED
+    }
 
-        (cs, ca)
-    };
+    #[inline(always)]
+    fn antialias_cs_ca() -> &'static ([Self; 8], [Self; 8]) {
+        &ANTIALIAS_CS_CA_FIXED
+    }
 }
 
 /// Reorder samples that are part of short blocks into sub-band order.
-pub(super) fn reorder(header: &FrameHeader, channel: &mut GranuleChannel, buf: &mut [f32; 576]) {
+pub(super) fn reorder<S: Copy>(
+    header: &FrameHeader,
+    channel: &mut GranuleChannel,
+    buf: &mut [S; 576],
+) {
     // Only short blocks are reordered.
     if let BlockType::Short { is_mixed } = channel.block_type {
         // Every short block is split into 3 equally sized windows as illustrated below (e.g. for
@@ -170,6 +224,8 @@ pub(super) fn reorder(header: &FrameHeader, channel: &mut GranuleChannel, buf: &
         debug_assert!(channel.rzero <= 576);
 
         // In mixed blocks, only the short bands can be re-ordered. Determine the applicable bands.
+        // Note that for MPEG2.5 at 8 kHz, the short bands of a mixed block begin at sample 72,
+        // therefore the 2 sub-bands following the long block are not re-ordered.
         let bands = if is_mixed {
             let switch = SFB_MIXED_SWITCH_POINT[header.sample_rate_idx];
             &SFB_MIXED_BANDS[header.sample_rate_idx][switch..]
@@ -178,44 +234,67 @@ pub(super) fn reorder(header: &FrameHeader, channel: &mut GranuleChannel, buf: &
             &SFB_SHORT_BANDS[header.sample_rate_idx]
         };
 
-        let mut reorder_buf = [0f32; 576];
-
-        let start = bands[0];
-        let mut i = start;
+        let mut end = bands[0];
 
-        for (((s0, s1), s2), s3) in
-            bands.iter().zip(&bands[1..]).zip(&bands[2..]).zip(&bands[3..]).step_by(3)
-        {
+        for (s0, s3) in bands.iter().zip(&bands[3..]).step_by(3) {
             // Do not reorder short blocks that begin after the rzero partition boundary since
             // they're zeroed.
             if *s0 >= channel.rzero {
                 break;
             }
 
-            // The three short sample windows.
-            let win0 = &buf[*s0..*s1];
-            let win1 = &buf[*s1..*s2];
-            let win2 = &buf[*s2..*s3];
-
             // Interleave the three short sample windows.
-            for ((w0, w1), w2) in win0.iter().zip(win1).zip(win2) {
-                reorder_buf[i + 0] = *w0;
-                reorder_buf[i + 1] = *w1;
-                reorder_buf[i + 2] = *w2;
-                i += 3;
-            }
-        }
+            interleave_windows(&mut buf[*s0..*s3]);
 
-        // Copy reordered samples from the reorder buffer to the actual sample buffer.
-        buf[start..i].copy_from_slice(&reorder_buf[start..i]);
+            end = *s3;
+        }
 
         // After reordering, the start of the rzero partition may no longer be valid. Update it.
-        channel.rzero = channel.rzero.max(i);
+        channel.rzero = channel.rzero.max(end);
+    }
+}
+
+/// Interleaves, in-place, the three equally sized windows of a short scale factor band.
+///
+/// Interleaving is a transpose of the band from a 3 x `win_len` matrix to a `win_len` x 3 matrix.
+/// The transpose is performed by following each cycle of the permutation, and moving every sample
+/// of the cycle to its final position.
+fn interleave_windows<S: Copy>(band: &mut [S]) {
+    debug_assert!(band.len() % 3 == 0 && band.len() <= 576);
+
+    let win_len = band.len() / 3;
+
+    // A bitmap of the samples that were moved to their final position.
+    let mut moved = [0u64; 9];
+
+    // The first and last samples never move.
+    for start in 1..band.len().saturating_sub(1) {
+        if moved[start >> 6] & (1 << (start & 63)) != 0 {
+            continue;
+        }
+
+        let mut pos = start;
+        let mut sample = band[start];
+
+        loop {
+            // The sample at position `pos` is sample `pos % win_len` of window `pos / win_len`.
+            pos = 3 * (pos % win_len) + pos / win_len;
+
+            std::mem::swap(&mut sample, &mut band[pos]);
+            moved[pos >> 6] |= 1 << (pos & 63);
+
+            if pos == start {
+                break;
+            }
+        }
     }
 }
 
 /// Applies the anti-aliasing filter to sub-bands that are not part of short blocks.
-pub(super) fn antialias(channel: &mut GranuleChannel, samples: &mut [f32; 576]) {
+pub(super) fn antialias<S: HybridSynthesisSample>(
+    channel: &mut GranuleChannel,
+    samples: &mut [S; 576],
+) {
     // The maximum number of sub-bands to anti-alias depends on block type.
     let sb_limit = match channel.block_type {
         // Short blocks are never anti-aliased.
@@ -227,8 +306,7 @@ pub(super) fn antialias(channel: &mut GranuleChannel, samples: &mut [f32; 576])
         _ => 32,
     };
 
-    // Amortize the lazy_static fetch over the entire anti-aliasing operation.
-    let (cs, ca): &([f32; 8], [f32; 8]) = &ANTIALIAS_CS_CA;
+    let (cs, ca) = S::antialias_cs_ca();
 
     // The sub-band that intersects the start of the rzero partition. All sub-bands after this one
     // are zeroed and do-not need anti-aliasing.
@@ -276,11 +354,84 @@ pub(super) fn antialias(channel: &mut GranuleChannel, samples: &mut [f32; 576])
     }
 }
 
+/// Returns the end of the prefix of `sub_bands` that can be processed 4 sub-bands at a time.
+#[inline(always)]
+fn x4_end(sub_bands: &Range<usize>) -> usize {
+    sub_bands.start + ((sub_bands.end - sub_bands.start) & !3)
+}
+
+/// Applies the IMDCT kernel `imdct` to the 4 sub-bands starting at sub-band `sb`, in parallel.
+#[inline(always)]
+fn imdct_x4<F>(samples: &mut [f32; 576], overlap: &mut [[f32; 18]; 32], sb: usize, imdct: F)
+where
+    F: Fn(&mut [F32x4; 18], &mut [F32x4; 18]),
+{
+    let (s0, rest) = samples[18 * sb..18 * (sb + 4)].split_at_mut(18);
+    let (s1, rest) = rest.split_at_mut(18);
+    let (s2, s3) = rest.split_at_mut(18);
+
+    let sub_bands: [&mut [f32; 18]; 4] = [
+        s0.try_into().unwrap(),
+        s1.try_into().unwrap(),
+        s2.try_into().unwrap(),
+        s3.try_into().unwrap(),
+    ];
+
+    if let [o0, o1, o2, o3] = &mut overlap[sb..sb + 4] {
+        let mut x = gather_x4([&*sub_bands[0], &*sub_bands[1], &*sub_bands[2], &*sub_bands[3]]);
+        let mut x_overlap = gather_x4([&*o0, &*o1, &*o2, &*o3]);
+
+        imdct(&mut x, &mut x_overlap);
+
+        scatter_x4(&x, sub_bands);
+        scatter_x4(&x_overlap, [o0, o1, o2, o3]);
+    }
+}
+
+/// Performs the windowed 36-point IMDCT on each sub-band in `sub_bands`, one at a time.
+fn imdct36_scalar<S: HybridSynthesisSample>(
+    samples: &mut [S; 576],
+    overlap: &mut [[S; 18]; 32],
+    window: &[S; 36],
+    sub_bands: Range<usize>,
+) {
+    // For each sub-band (18 samples each) in the long block...
+    for sb in sub_bands {
+        let start = 18 * sb;
+
+        // Casting to a slice of a known-size lets the compiler elide bounds checks.
+        let sub_band: &mut [S; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
+
+        // Perform the 36-point on the entire sub-band.
+        imdct36::imdct36(sub_band, window, &mut overlap[sb]);
+    }
+}
+
+/// Performs the windowed 12-point IMDCT on each sub-band in `sub_bands`, one at a time.
+fn imdct12_scalar<S: HybridSynthesisSample>(
+    samples: &mut [S; 576],
+    overlap: &mut [[S; 18]; 32],
+    window: &[S; 36],
+    sub_bands: Range<usize>,
+) {
+    // For each sub-band (18 samples each) in the short block...
+    for sb in sub_bands {
+        let start = 18 * sb;
+
+        // Casting to a slice of a known-size lets the compiler elide bounds checks.
+        let sub_band: &mut [S; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
+
+        // Perform the 12-point IMDCT on each of the 3 short windows within the sub-band (6
+        // samples each).
+        imdct12_win(sub_band, window, &mut overlap[sb]);
+    }
+}
+
 /// Performs hybrid synthesis (IMDCT and windowing).
-pub(super) fn hybrid_synthesis(
+pub(super) fn hybrid_synthesis<S: HybridSynthesisSample>(
     channel: &GranuleChannel,
-    overlap: &mut [[f32; 18]; 32],
-    samples: &mut [f32; 576],
+    overlap: &mut [[S; 18]; 32],
+    samples: &mut [S; 576],
 ) {
     // The first sub-band after the rzero partition boundary is the sub-band limit. All sub-bands
     // past this are zeroed.
@@ -297,68 +448,51 @@ pub(super) fn hybrid_synthesis(
         _ => 32,
     };
 
+    let windows = S::imdct_windows();
+
     // If the split point is not 0, then some sub-bands need to be processed as long blocks using
     // the 36-point IMDCT.
     if sb_split > 0 {
         // Select the appropriate window given the block type.
-        let window: &[f32; 36] = match channel.block_type {
-            BlockType::Start => &IMDCT_WINDOWS[1],
-            BlockType::End => &IMDCT_WINDOWS[3],
-            _ => &IMDCT_WINDOWS[0],
+        let window = match channel.block_type {
+            BlockType::Start => &windows[1],
+            BlockType::End => &windows[3],
+            _ => &windows[0],
         };
 
-        let sb_long_end = sb_split.min(sb_limit);
-
-        // For each of the sub-bands (18 samples each) in the long block...
-        for sb in 0..sb_long_end {
-            let start = 18 * sb;
-
-            // Casting to a slice of a known-size lets the compiler elide bounds checks.
-            let sub_band: &mut [f32; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
-
-            // Perform the 36-point on the entire sub-band.
-            imdct36::imdct36(sub_band, window, &mut overlap[sb]);
-        }
+        S::imdct36_sub_bands(samples, overlap, window, 0..sb_split.min(sb_limit));
     }
 
     // If the split point is less-than 32, then some sub-bands need to be processed as short blocks
     // using the 12-point IMDCT on each of the three windows.
     if sb_split < 32 {
         // Select the short block window.
-        let window: &[f32; 36] = &IMDCT_WINDOWS[2];
-
-        let sb_short_begin = sb_split.min(sb_limit);
+        let window = &windows[2];
 
-        // For each of the sub-bands (18 samples each) in the short block...
-        for sb in sb_short_begin..sb_limit {
-            let start = 18 * sb;
-
-            // Casting to a slice of a known-size lets the compiler elide bounds checks.
-            let sub_band: &mut [f32; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
-
-            // Perform the 12-point IMDCT on each of the 3 short windows within the sub-band (6
-            // samples each).
-            imdct12_win(sub_band, window, &mut overlap[sb]);
-        }
+        S::imdct12_sub_bands(samples, overlap, window, sb_split.min(sb_limit)..sb_limit);
     }
 
     // Every sub-band after the the sub-band limit are zeroed, however, the overlap for that
     // sub-band may be non-zero. Therefore, copy it over.
     for sb in sb_limit..32 {
         let start = 18 * sb;
-        let sub_band: &mut [f32; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
+        let sub_band: &mut [S; 18] = (&mut samples[start..(start + 18)]).try_into().unwrap();
 
         sub_band.copy_from_slice(&overlap[sb]);
-        overlap[sb].fill(0.0);
+        overlap[sb].fill(S::default());
     }
 }
 
 /// Performs the 12-point IMDCT, and windowing for each of the 3 short windows of a short block, and
 /// then overlap-adds the result.
-fn imdct12_win(x: &mut [f32; 18], window: &[f32; 36], overlap: &mut [f32; 18]) {
-    let cos12: &[[f32; 6]; 6] = &IMDCT_HALF_COS_12;
+fn imdct12_win<T>(x: &mut [T; 18], window: &[T::Coeff; 36], overlap: &mut [T; 18])
+where
+    T: Lanes,
+    T::Coeff: HybridSynthesisSample,
+{
+    let cos12 = T::Coeff::imdct_half_cos_12();
 
-    let mut tmp = [0.0; 36];
+    let mut tmp = [T::default(); 36];
 
     for w in 0..3 {
         for i in 0..3 {
@@ -450,7 +584,7 @@ fn imdct12_win(x: &mut [f32; 18], window: &[f32; 36], overlap: &mut [f32; 18]) {
 }
 
 /// Inverts odd samples in odd sub-bands.
-pub fn frequency_inversion(samples: &mut [f32; 576]) {
+pub fn frequency_inversion<S: Lanes>(samples: &mut [S; 576]) {
     // There are 32 sub-bands spanning 576 samples:
     //
     //        0    18    36    54    72    90   108       558    576
@@ -481,10 +615,21 @@ pub fn frequency_inversion(samples: &mut [f32; 576]) {
 
 #[cfg(test)]
 mod tests {
-    use super::imdct12_win;
     use super::IMDCT_WINDOWS;
+    use super::{imdct12_win, interleave_windows};
+    #[cfg(feature = "fixed-point")]
+    use super::{HybridSynthesisSample, IMDCT_WINDOWS_FIXED};
+    #[cfg(feature = "fixed-point")]
+    use crate::fixed::Fixed;
+    use crate::lanes::x4::{gather_x4, F32x4};
     use std::f64;
 
+    const TEST_VECTOR: [f32; 18] = [
+        0.0976, 0.9321, 0.6138, 0.0857, 0.0433, 0.4855, 0.2144, 0.8488, //
+        0.6889, 0.2983, 0.1957, 0.7037, 0.0052, 0.0197, 0.3188, 0.5123, //
+        0.2994, 0.7157,
+    ];
+
     fn imdct12_analytical(x: &[f32; 6]) -> [f32; 12] {
         const PI_24: f64 = f64::consts::PI / 24.0;
 
@@ -502,56 +647,198 @@ mod tests {
         result
     }
 
+    /// Performs 3 analytical 12-point IMDCTs over the input, and then windows and overlaps the
+    /// results to generate the final result.
+    fn imdct12_win_analytical(x: &[f32; 18], window: &[f32; 36]) -> [f32; 36] {
+        let mut expected = [0f32; 36];
+
+        let mut x0 = [0f32; 6];
+        let mut x1 = [0f32; 6];
+        let mut x2 = [0f32; 6];
+
+        for i in 0..6 {
+            x0[i] = x[3 * i + 0];
+            x1[i] = x[3 * i + 1];
+            x2[i] = x[3 * i + 2];
+        }
+
+        let imdct0 = imdct12_analytical(&x0);
+        let imdct1 = imdct12_analytical(&x1);
+        let imdct2 = imdct12_analytical(&x2);
+
+        for i in 0..12 {
+            expected[6 + i] += imdct0[i] * window[i];
+            expected[12 + i] += imdct1[i] * window[i];
+            expected[18 + i] += imdct2[i] * window[i];
+        }
+
+        expected
+    }
+
     #[test]
-    fn verify_imdct12_win() {
-        const TEST_VECTOR: [f32; 18] = [
-            0.0976, 0.9321, 0.6138, 0.0857, 0.0433, 0.4855, 0.2144, 0.8488, //
-            0.6889, 0.2983, 0.1957, 0.7037, 0.0052, 0.0197, 0.3188, 0.5123, //
-            0.2994, 0.7157,
-        ];
+    fn verify_interleave_windows() {
+        for win_len in 1..=192 {
+            let mut band: Vec<usize> = (0..3 * win_len).collect();
+
+            interleave_windows(&mut band);
+
+            for (i, &sample) in band.iter().enumerate() {
+                assert_eq!(sample, (i % 3) * win_len + i / 3);
+            }
+        }
+    }
 
+    #[test]
+    fn verify_imdct12_win() {
         let window = &IMDCT_WINDOWS[2];
 
         let mut actual = TEST_VECTOR;
         let mut overlap = [0.0; 18];
         imdct12_win(&mut actual, window, &mut overlap);
 
-        // The following block performs 3 analytical 12-point IMDCTs over the test vector, and then
-        // windows and overlaps the results to generate the final result.
-        let expected = {
-            let mut expected = [0f32; 36];
+        let expected = imdct12_win_analytical(&TEST_VECTOR, window);
 
-            let mut x0 = [0f32; 6];
-            let mut x1 = [0f32; 6];
-            let mut x2 = [0f32; 6];
+        for i in 0..18 {
+            assert!((expected[i] - actual[i]).abs() < 0.00001);
+            assert!((expected[i + 18] - overlap[i]).abs() < 0.00001);
+        }
+    }
 
-            for i in 0..6 {
-                x0[i] = TEST_VECTOR[3 * i + 0];
-                x1[i] = TEST_VECTOR[3 * i + 1];
-                x2[i] = TEST_VECTOR[3 * i + 2];
-            }
+    #[test]
+    fn verify_imdct12_win_x4() {
+        let window = &IMDCT_WINDOWS[2];
 
-            let imdct0 = imdct12_analytical(&x0);
-            let imdct1 = imdct12_analytical(&x1);
-            let imdct2 = imdct12_analytical(&x2);
+        // Use a different input for each lane so that lanes cannot be mixed up.
+        let mut inputs = [TEST_VECTOR; 4];
 
-            for i in 0..12 {
-                expected[6 + i] += imdct0[i] * window[i];
-                expected[12 + i] += imdct1[i] * window[i];
-                expected[18 + i] += imdct2[i] * window[i];
+        for (lane, input) in inputs.iter_mut().enumerate() {
+            input.rotate_left(5 * lane);
+        }
+
+        let mut actual = gather_x4([&inputs[0], &inputs[1], &inputs[2], &inputs[3]]);
+        let mut overlap = [F32x4::default(); 18];
+        imdct12_win(&mut actual, window, &mut overlap);
+
+        for (lane, input) in inputs.iter().enumerate() {
+            let expected = imdct12_win_analytical(input, window);
+
+            for i in 0..18 {
+                assert!((expected[i] - actual[i].0[lane]).abs() < 0.00001);
+                assert!((expected[i + 18] - overlap[i].0[lane]).abs() < 0.00001);
             }
+        }
+    }
 
-            expected
-        };
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_imdct12_win_fixed() {
+        let window = &IMDCT_WINDOWS[2];
+
+        let mut actual = [Fixed(0); 18];
+        for (fixed, float) in actual.iter_mut().zip(&TEST_VECTOR) {
+            *fixed = Fixed::from_f64(f64::from(*float));
+        }
+
+        let mut overlap = [Fixed(0); 18];
+        imdct12_win(&mut actual, &IMDCT_WINDOWS_FIXED[2], &mut overlap);
+
+        let expected = imdct12_win_analytical(&TEST_VECTOR, window);
 
         for i in 0..18 {
-            assert!((expected[i] - actual[i]).abs() < 0.00001);
-            assert!((expected[i + 18] - overlap[i]).abs() < 0.00001);
+            assert!((f64::from(expected[i]) - actual[i].to_f64()).abs() < 0.00001);
+            assert!((f64::from(expected[i + 18]) - overlap[i].to_f64()).abs() < 0.00001);
+        }
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_antialias_cs_ca_fixed() {
+        let (cs, ca) = f32::antialias_cs_ca();
+        let (cs_fixed, ca_fixed) = Fixed::antialias_cs_ca();
+
+        for i in 0..8 {
+            assert!((f64::from(cs[i]) - cs_fixed[i].to_f64()).abs() < 0.0000001);
+            assert!((f64::from(ca[i]) - ca_fixed[i].to_f64()).abs() < 0.0000001);
         }
     }
 }
 
 mod imdct36 {
+    #[cfg(feature = "fixed-point")]
+    use crate::fixed::Fixed;
+    use crate::lanes::Lanes;
+
+    /// Coefficients used by the 36-point IMDCT.
+    pub trait Imdct36Coeffs: Sized + 'static {
+        /// Scale factors for the input samples of `dct_iv`. See `DCT_IV_SCALE`.
+        const DCT_IV_SCALE: [Self; 18];
+        /// Scale factors for the odd input samples of `sdct_ii_18`. See `SDCT_II_18_SCALE`.
+        const SDCT_II_18_SCALE: [Self; 9];
+        /// Constants used by `sdct_ii_9`. See `SDCT_II_9_D`.
+        const SDCT_II_9_D: [Self; 7];
+    }
+
+    impl Imdct36Coeffs for f32 {
+        const DCT_IV_SCALE: [f32; 18] = DCT_IV_SCALE;
+        const SDCT_II_18_SCALE: [f32; 9] = SDCT_II_18_SCALE;
+        const SDCT_II_9_D: [f32; 7] = SDCT_II_9_D;
+    }
+
+    #[cfg(feature = "fixed-point")]
+    impl Imdct36Coeffs for Fixed {
+        const DCT_IV_SCALE: [Fixed; 18] = fixed_const_array!(DCT_IV_SCALE, 18);
+        const SDCT_II_18_SCALE: [Fixed; 9] = fixed_const_array!(SDCT_II_18_SCALE, 9);
+        const SDCT_II_9_D: [Fixed; 7] = fixed_const_array!(SDCT_II_9_D, 7);
+    }
+
+    /// Scale factors for input samples. Computed from (16).
+    /// 2 * cos(PI * (2*m + 1) / (2*36)
+    const DCT_IV_SCALE: [f32; 18] = [
+        1.998_096_443_163_715_6, // m=0
+        1.982_889_722_747_620_8, // m=1
+        1.952_592_014_239_866_7, // m=2
+        1.907_433_901_496_453_9, // m=3
+        1.847_759_065_022_573_5, // m=4
+        1.774_021_666_356_443_4, // m=5
+        1.686_782_891_625_771_4, // m=6
+        1.586_706_680_582_470_6, // m=7
+        1.474_554_673_620_247_9, // m=8
+        1.351_180_415_231_320_7, // m=9
+        1.217_522_858_017_441_3, // m=10
+        1.074_599_216_693_647_8, // m=11
+        0.923_497_226_470_067_7, // m=12
+        0.765_366_864_730_179_7, // m=13
+        0.601_411_599_008_546_1, // m=14
+        0.432_879_227_876_205_8, // m=15
+        0.261_052_384_440_103_0, // m=16
+        0.087_238_774_730_672_0, // m=17
+    ];
+
+    /// Scale factors for odd input samples. Computed from (23).
+    /// 2 * cos(PI * (2*m + 1) / 36)
+    const SDCT_II_18_SCALE: [f32; 9] = [
+        1.992_389_396_183_491_1,  // m=0
+        1.931_851_652_578_136_6,  // m=1
+        1.812_615_574_073_299_9,  // m=2
+        1.638_304_088_577_983_6,  // m=3
+        std::f32::consts::SQRT_2, // m=4
+        1.147_152_872_702_092_3,  // m=5
+        0.845_236_523_481_398_9,  // m=6
+        0.517_638_090_205_041_9,  // m=7
+        0.174_311_485_495_316_3,  // m=8
+    ];
+
+    /// Constants for the 9-point SDCT-II.
+    const SDCT_II_9_D: [f32; 7] = [
+        -1.732_050_807_568_877_2, // -sqrt(3.0)
+        1.879_385_241_571_816_6,  // -2.0 * cos(8.0 * PI / 9.0)
+        -0.347_296_355_333_860_8, // -2.0 * cos(4.0 * PI / 9.0)
+        -1.532_088_886_237_956_0, // -2.0 * cos(2.0 * PI / 9.0)
+        -0.684_040_286_651_337_8, // -2.0 * sin(8.0 * PI / 9.0)
+        -1.969_615_506_024_416_0, // -2.0 * sin(4.0 * PI / 9.0)
+        -1.285_575_219_373_078_5, // -2.0 * sin(2.0 * PI / 9.0)
+    ];
+
     /// Performs an Inverse Modified Discrete Cosine Transform (IMDCT) transforming 18
     /// frequency-domain input samples, into 36 time-domain output samples.
     ///
@@ -563,8 +850,12 @@ mod imdct36 {
     /// Signal Processing, vol. 48, no. 10, pp. 990-994, 2001.
     ///
     /// https://ieeexplore.ieee.org/document/974789
-    pub fn imdct36(x: &mut [f32; 18], window: &[f32; 36], overlap: &mut [f32; 18]) {
-        let mut dct = [0f32; 18];
+    pub fn imdct36<T>(x: &mut [T; 18], window: &[T::Coeff; 36], overlap: &mut [T; 18])
+    where
+        T: Lanes,
+        T::Coeff: Imdct36Coeffs,
+    {
+        let mut dct = [T::default(); 18];
 
         dct_iv(x, &mut dct);
 
@@ -600,77 +891,52 @@ mod imdct36 {
     /// Continutation of `imdct36`.
     ///
     /// Step 2: Mapping N/2-point DCT-IV to N/2-point SDCT-II.
-    fn dct_iv(x: &[f32; 18], y: &mut [f32; 18]) {
-        // Scale factors for input samples. Computed from (16).
-        // 2 * cos(PI * (2*m + 1) / (2*36)
-        const SCALE: [f32; 18] = [
-            1.998_096_443_163_715_6, // m=0
-            1.982_889_722_747_620_8, // m=1
-            1.952_592_014_239_866_7, // m=2
-            1.907_433_901_496_453_9, // m=3
-            1.847_759_065_022_573_5, // m=4
-            1.774_021_666_356_443_4, // m=5
-            1.686_782_891_625_771_4, // m=6
-            1.586_706_680_582_470_6, // m=7
-            1.474_554_673_620_247_9, // m=8
-            1.351_180_415_231_320_7, // m=9
-            1.217_522_858_017_441_3, // m=10
-            1.074_599_216_693_647_8, // m=11
-            0.923_497_226_470_067_7, // m=12
-            0.765_366_864_730_179_7, // m=13
-            0.601_411_599_008_546_1, // m=14
-            0.432_879_227_876_205_8, // m=15
-            0.261_052_384_440_103_0, // m=16
-            0.087_238_774_730_672_0, // m=17
-        ];
+    fn dct_iv<T>(x: &[T; 18], y: &mut [T; 18])
+    where
+        T: Lanes,
+        T::Coeff: Imdct36Coeffs,
+    {
+        let scale = &T::Coeff::DCT_IV_SCALE;
 
         let samples = [
-            SCALE[0] * x[0],
-            SCALE[1] * x[1],
-            SCALE[2] * x[2],
-            SCALE[3] * x[3],
-            SCALE[4] * x[4],
-            SCALE[5] * x[5],
-            SCALE[6] * x[6],
-            SCALE[7] * x[7],
-            SCALE[8] * x[8],
-            SCALE[9] * x[9],
-            SCALE[10] * x[10],
-            SCALE[11] * x[11],
-            SCALE[12] * x[12],
-            SCALE[13] * x[13],
-            SCALE[14] * x[14],
-            SCALE[15] * x[15],
-            SCALE[16] * x[16],
-            SCALE[17] * x[17],
+            x[0] * scale[0],
+            x[1] * scale[1],
+            x[2] * scale[2],
+            x[3] * scale[3],
+            x[4] * scale[4],
+            x[5] * scale[5],
+            x[6] * scale[6],
+            x[7] * scale[7],
+            x[8] * scale[8],
+            x[9] * scale[9],
+            x[10] * scale[10],
+            x[11] * scale[11],
+            x[12] * scale[12],
+            x[13] * scale[13],
+            x[14] * scale[14],
+            x[15] * scale[15],
+            x[16] * scale[16],
+            x[17] * scale[17],
         ];
 
         sdct_ii_18(&samples, y);
 
-        y[0] /= 2.0;
+        y[0] = y[0].half();
         for i in 1..17 {
-            y[i] = (y[i] / 2.0) - y[i - 1];
+            y[i] = y[i].half() - y[i - 1];
         }
-        y[17] = (y[17] / 2.0) - y[16];
+        y[17] = y[17].half() - y[16];
     }
 
     /// Continutation of `imdct36`.
     ///
     /// Step 3: Decompose N/2-point SDCT-II into two N/4-point SDCT-IIs.
-    fn sdct_ii_18(x: &[f32; 18], y: &mut [f32; 18]) {
-        // Scale factors for odd input samples. Computed from (23).
-        // 2 * cos(PI * (2*m + 1) / 36)
-        const SCALE: [f32; 9] = [
-            1.992_389_396_183_491_1,  // m=0
-            1.931_851_652_578_136_6,  // m=1
-            1.812_615_574_073_299_9,  // m=2
-            1.638_304_088_577_983_6,  // m=3
-            std::f32::consts::SQRT_2, // m=4
-            1.147_152_872_702_092_3,  // m=5
-            0.845_236_523_481_398_9,  // m=6
-            0.517_638_090_205_041_9,  // m=7
-            0.174_311_485_495_316_3,  // m=8
-        ];
+    fn sdct_ii_18<T>(x: &[T; 18], y: &mut [T; 18])
+    where
+        T: Lanes,
+        T::Coeff: Imdct36Coeffs,
+    {
+        let scale = &T::Coeff::SDCT_II_18_SCALE;
 
         let even = [
             x[0] + x[18 - 1],
@@ -687,15 +953,15 @@ mod imdct36 {
         sdct_ii_9(&even, y);
 
         let odd = [
-            SCALE[0] * (x[0] - x[18 - 1]),
-            SCALE[1] * (x[1] - x[18 - 2]),
-            SCALE[2] * (x[2] - x[18 - 3]),
-            SCALE[3] * (x[3] - x[18 - 4]),
-            SCALE[4] * (x[4] - x[18 - 5]),
-            SCALE[5] * (x[5] - x[18 - 6]),
-            SCALE[6] * (x[6] - x[18 - 7]),
-            SCALE[7] * (x[7] - x[18 - 8]),
-            SCALE[8] * (x[8] - x[18 - 9]),
+            (x[0] - x[18 - 1]) * scale[0],
+            (x[1] - x[18 - 2]) * scale[1],
+            (x[2] - x[18 - 3]) * scale[2],
+            (x[3] - x[18 - 4]) * scale[3],
+            (x[4] - x[18 - 5]) * scale[4],
+            (x[5] - x[18 - 6]) * scale[5],
+            (x[6] - x[18 - 7]) * scale[6],
+            (x[7] - x[18 - 8]) * scale[7],
+            (x[8] - x[18 - 9]) * scale[8],
         ];
 
         sdct_ii_9(&odd, &mut y[1..]);
@@ -713,16 +979,12 @@ mod imdct36 {
     /// Continutation of `imdct36`.
     ///
     /// Step 4: Computation of 9-point (N/4) SDCT-II.
-    fn sdct_ii_9(x: &[f32; 9], y: &mut [f32]) {
-        const D: [f32; 7] = [
-            -1.732_050_807_568_877_2, // -sqrt(3.0)
-            1.879_385_241_571_816_6,  // -2.0 * cos(8.0 * PI / 9.0)
-            -0.347_296_355_333_860_8, // -2.0 * cos(4.0 * PI / 9.0)
-            -1.532_088_886_237_956_0, // -2.0 * cos(2.0 * PI / 9.0)
-            -0.684_040_286_651_337_8, // -2.0 * sin(8.0 * PI / 9.0)
-            -1.969_615_506_024_416_0, // -2.0 * sin(4.0 * PI / 9.0)
-            -1.285_575_219_373_078_5, // -2.0 * sin(2.0 * PI / 9.0)
-        ];
+    fn sdct_ii_9<T>(x: &[T; 9], y: &mut [T])
+    where
+        T: Lanes,
+        T::Coeff: Imdct36Coeffs,
+    {
+        let d = &T::Coeff::SDCT_II_9_D;
 
         let a01 = x[3] + x[5];
         let a02 = x[3] - x[5];
@@ -744,16 +1006,16 @@ mod imdct36 {
         let a17 = a04 + a08;
         let a18 = a02 - a08;
         let a19 = a02 + a04;
-        let a20 = 2.0 * x[4] - a05;
+        let a20 = (x[4] + x[4]) - a05;
 
-        let m1 = D[0] * a06;
-        let m2 = D[1] * a12;
-        let m3 = D[2] * a13;
-        let m4 = D[3] * a14;
-        let m5 = D[0] * a16;
-        let m6 = D[4] * a17;
-        let m7 = D[5] * a18; // Note: the cited paper has an error, a1 should be a18.
-        let m8 = D[6] * a19;
+        let m1 = a06 * d[0];
+        let m2 = a12 * d[1];
+        let m3 = a13 * d[2];
+        let m4 = a14 * d[3];
+        let m5 = a16 * d[0];
+        let m6 = a17 * d[4];
+        let m7 = a18 * d[5]; // Note: the cited paper has an error, a1 should be a18.
+        let m8 = a19 * d[6];
 
         let a21 = a20 + m2;
         let a22 = a20 - m2;
@@ -768,7 +1030,7 @@ mod imdct36 {
         y[6] = m5;
         y[8] = a22 - m3;
         y[10] = a25 - m7;
-        y[12] = a11 - 2.0 * a09;
+        y[12] = a11 - (a09 + a09);
         y[14] = a24 + m8;
         y[16] = a23 + m4;
     }
@@ -776,6 +1038,9 @@ mod imdct36 {
     #[cfg(test)]
     mod tests {
         use super::imdct36;
+        #[cfg(feature = "fixed-point")]
+        use crate::fixed::Fixed;
+        use crate::lanes::x4::{gather_x4, F32x4};
         use std::f64;
 
         fn imdct36_analytical(x: &[f32; 18]) -> [f32; 36] {
@@ -794,16 +1059,16 @@ mod imdct36 {
             result
         }
 
-        #[test]
-        fn verify_imdct36() {
-            const TEST_VECTOR: [f32; 18] = [
-                0.0976, 0.9321, 0.6138, 0.0857, 0.0433, 0.4855, 0.2144, 0.8488, //
-                0.6889, 0.2983, 0.1957, 0.7037, 0.0052, 0.0197, 0.3188, 0.5123, //
-                0.2994, 0.7157,
-            ];
+        const TEST_VECTOR: [f32; 18] = [
+            0.0976, 0.9321, 0.6138, 0.0857, 0.0433, 0.4855, 0.2144, 0.8488, //
+            0.6889, 0.2983, 0.1957, 0.7037, 0.0052, 0.0197, 0.3188, 0.5123, //
+            0.2994, 0.7157,
+        ];
 
-            const WINDOW: [f32; 36] = [1.0; 36];
+        const WINDOW: [f32; 36] = [1.0; 36];
 
+        #[test]
+        fn verify_imdct36() {
             let mut actual = TEST_VECTOR;
             let mut overlap = [0.0; 18];
             imdct36(&mut actual, &WINDOW, &mut overlap);
@@ -815,5 +1080,47 @@ mod imdct36 {
                 assert!((expected[i + 18] - overlap[i]).abs() < 0.00001);
             }
         }
+
+        #[test]
+        fn verify_imdct36_x4() {
+            // Use a different input for each lane so that lanes cannot be mixed up.
+            let mut inputs = [TEST_VECTOR; 4];
+
+            for (lane, input) in inputs.iter_mut().enumerate() {
+                input.rotate_left(5 * lane);
+            }
+
+            let mut actual = gather_x4([&inputs[0], &inputs[1], &inputs[2], &inputs[3]]);
+            let mut overlap = [F32x4::default(); 18];
+            imdct36(&mut actual, &WINDOW, &mut overlap);
+
+            for (lane, input) in inputs.iter().enumerate() {
+                let expected = imdct36_analytical(input);
+
+                for i in 0..18 {
+                    assert!((expected[i] - actual[i].0[lane]).abs() < 0.00001);
+                    assert!((expected[i + 18] - overlap[i].0[lane]).abs() < 0.00001);
+                }
+            }
+        }
+
+        #[cfg(feature = "fixed-point")]
+        #[test]
+        fn verify_imdct36_fixed() {
+            let mut actual = [Fixed(0); 18];
+            for (fixed, float) in actual.iter_mut().zip(&TEST_VECTOR) {
+                *fixed = Fixed::from_f64(f64::from(*float));
+            }
+
+            let mut overlap = [Fixed(0); 18];
+            imdct36(&mut actual, &[fixed_const!(1.0); 36], &mut overlap);
+
+            let expected = imdct36_analytical(&TEST_VECTOR);
+
+            for i in 0..18 {
+                assert!((f64::from(expected[i]) - actual[i].to_f64()).abs() < 0.00001);
+                assert!((f64::from(expected[i + 18]) - overlap[i].to_f64()).abs() < 0.00001);
+            }
+        }
     }
 }
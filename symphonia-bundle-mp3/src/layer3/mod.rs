@@ -8,8 +8,13 @@
 use std::fmt;
 
 use symphonia_core::audio::{AudioBuffer, Signal};
+use symphonia_core::checksum::Crc16Ansi;
+use symphonia_core::codecs::budget::DecodeBudget;
+use symphonia_core::codecs::{DecodeWarning, DecodeWarningKind, DualMonoPolicy};
 use symphonia_core::errors::{decode_error, Error, Result};
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::io::{BitReaderLtr, BufReader, FiniteBitStream, Monitor};
+use symphonia_core::io::{ReadBytes, SeekBits};
+use symphonia_core::sample::Sample;
 
 mod bitstream;
 mod codebooks;
@@ -18,12 +23,34 @@ mod hybrid_synthesis;
 mod requantize;
 mod stereo;
 
-use crate::{common::*, synthesis};
+use crate::common::*;
+use crate::state::{restore_samples, save_samples, StateSample};
+use crate::synthesis::{self, FromSynthesized, SynthesisSample};
 
 use common::BlockType;
+use hybrid_synthesis::HybridSynthesisSample;
+use requantize::RequantizeSample;
+use stereo::StereoSample;
 
 use log::warn;
 
+/// The maximum number of spectral samples Huffman decoded in a frame (2 granules of 2 channels).
+pub const MAX_HUFFMAN_SAMPLES: usize = 2 * 2 * 576;
+
+/// The maximum value of `main_data_begin`. It is a 9-bit field for MPEG1, and an 8-bit field for
+/// MPEG2 and MPEG2.5.
+const MAX_MAIN_DATA_BEGIN: usize = 511;
+
+/// The maximum length of the main data of a single frame. The largest layer 3 frames are 1441
+/// bytes long (a padded 320 kbps MPEG1 frame at 32 kHz, or a padded 160 kbps MPEG2.5 frame at
+/// 8 kHz). Excluding the 4 byte header, and the 9 byte side_info of a MPEG2.5 mono frame, leaves
+/// 1428 bytes of main data.
+const MAX_MAIN_DATA_LEN: usize = 1441 - 4 - 9;
+
+/// The capacity of the bit resevoir. It must hold the main data of one frame, in addition to the
+/// greatest amount of main data a frame may reuse from previous frames.
+const RESEVOIR_CAPACITY: usize = MAX_MAIN_DATA_BEGIN + MAX_MAIN_DATA_LEN;
+
 /// `BitResevoir` implements the bit resevoir mechanism for main_data. Since frames have a
 /// deterministic length based on the bit-rate, low-complexity portions of the audio may not need
 /// every byte allocated to the frame. The bit resevoir mechanism allows these unused portions of
@@ -36,7 +63,7 @@ pub struct BitResevoir {
 
 impl BitResevoir {
     pub fn new() -> Self {
-        BitResevoir { buf: vec![0u8; 2048].into_boxed_slice(), len: 0, consumed: 0 }
+        BitResevoir { buf: vec![0u8; RESEVOIR_CAPACITY].into_boxed_slice(), len: 0, consumed: 0 }
     }
 
     pub fn fill(&mut self, pkt_main_data: &[u8], main_data_begin: usize) -> Result<u32> {
@@ -44,19 +71,31 @@ impl BitResevoir {
 
         // The value `main_data_begin` indicates the number of bytes from the previous frame(s) to
         // reuse. It must always be less than or equal to maximum amount of bytes the resevoir can
-        // hold taking into account the additional data being added to the resevoir.
-        let main_data_end = main_data_begin + main_data_len;
-
-        if main_data_end > self.buf.len() {
-            return decode_error("mpa: invalid main_data length, will exceed resevoir buffer");
-        }
+        // hold taking into account the additional data being added to the resevoir. Both values
+        // are untrusted, therefore guard against overflow as well.
+        let main_data_end = match main_data_begin.checked_add(main_data_len) {
+            Some(end) if end <= self.buf.len() => end,
+            _ => return decode_error("mpa: invalid main_data length, will exceed resevoir buffer"),
+        };
 
         let unread = self.len - self.consumed;
 
-        // If the offset is less-than or equal to the amount of unread data in the resevoir, shift
-        // the re-used bytes to the beginning of the resevoir, then copy the main data of the
-        // current packet into the resevoir.
-        let underflow = if main_data_begin <= unread {
+        // If the offset is less-than or equal to the amount of data in the resevoir, shift the
+        // re-used bytes to the beginning of the resevoir, then copy the main data of the current
+        // packet into the resevoir.
+        //
+        // Normally, the re-used bytes were not read by the previous frame. However, some encoders
+        // produce streams where the main data of a frame overlaps the main data of the previous
+        // frame. Since the bytes read by the previous frame are retained in the resevoir, they may
+        // still be re-used.
+        let underflow = if main_data_begin <= self.len {
+            if main_data_begin > unread {
+                warn!(
+                    "mpa: main_data_begin overlaps previous frame by {} bytes",
+                    main_data_begin - unread
+                );
+            }
+
             // Shift all the re-used bytes as indicated by main_data_begin to the front of the
             // resevoir.
             self.buf.copy_within(self.len - main_data_begin..self.len, 0);
@@ -68,20 +107,20 @@ impl BitResevoir {
             0
         }
         else {
-            // Shift all the unread bytes to the front of the resevoir. Since this is an underflow
-            // condition, all unread bytes will be unconditionally reused.
-            self.buf.copy_within(self.len - unread..self.len, 0);
-
+            // Since this is an underflow condition, all buffered bytes will be unconditionally
+            // reused. They are already at the front of the resevoir.
+            //
             // If the offset is greater than the amount of data in the resevoir, then the stream is
             // malformed. This can occur if the decoder is starting in the middle of a stream. This
             // is particularly common with online radio streams. In this case, copy the main data
             // of the current packet into the resevoir, then return the number of bytes that are
             // missing.
-            self.buf[unread..unread + main_data_len].copy_from_slice(pkt_main_data);
-            self.len = unread + main_data_len;
+            self.buf[self.len..self.len + main_data_len].copy_from_slice(pkt_main_data);
 
             // The number of bytes that will be missing.
-            let underflow = (main_data_begin - unread) as u32;
+            let underflow = (main_data_begin - self.len) as u32;
+
+            self.len += main_data_len;
 
             warn!("mpa: invalid main_data_begin, underflow by {} bytes", underflow);
 
@@ -101,10 +140,46 @@ impl BitResevoir {
         &self.buf[self.consumed..self.len]
     }
 
+    /// Gets the bytes following the main data of the previous frame that will not be re-used by a
+    /// frame with the given `main_data_begin`. These bytes are the ancillary data of the previous
+    /// frame.
+    pub fn ancillary_ref(&self, main_data_begin: usize) -> &[u8] {
+        let end = self.len.saturating_sub(main_data_begin).max(self.consumed);
+        &self.buf[self.consumed..end]
+    }
+
     pub fn clear(&mut self) {
         self.len = 0;
         self.consumed = 0;
     }
+
+    /// Appends the bytes of the resevoir that may be re-used by the next frame to `buf`.
+    pub fn save(&self, buf: &mut Vec<u8>) {
+        // A frame can never re-use more bytes than `main_data_begin` can reference.
+        let start = self.len.saturating_sub(MAX_MAIN_DATA_BEGIN);
+        let unread = self.len - self.consumed.max(start);
+
+        buf.extend_from_slice(&((self.len - start) as u16).to_le_bytes());
+        buf.extend_from_slice(&(unread as u16).to_le_bytes());
+        buf.extend_from_slice(&self.buf[start..self.len]);
+    }
+
+    /// Restores a resevoir previously saved by `save` from `reader`.
+    pub fn restore(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        let len = usize::from(reader.read_u16()?);
+        let unread = usize::from(reader.read_u16()?);
+
+        if len > MAX_MAIN_DATA_BEGIN || unread > len {
+            return decode_error("mpa: invalid bit resevoir state");
+        }
+
+        reader.read_buf_exact(&mut self.buf[..len])?;
+
+        self.len = len;
+        self.consumed = len - unread;
+
+        Ok(())
+    }
 }
 
 /// `FrameData` contains the side_info and main_data portions of a MPEG audio frame.
@@ -251,31 +326,125 @@ impl fmt::Debug for GranuleChannel {
     }
 }
 
-pub struct Layer3 {
-    pub samples: [[[f32; 576]; 2]; 2],
-    pub overlap: [[[f32; 18]; 32]; 2],
-    pub synthesis: [synthesis::SynthesisState; 2],
+/// A sample type the layer 3 decoder may decode with.
+///
+/// This is implemented for `f32`, and, if the `fixed-point` feature is enabled, `Fixed`.
+pub trait Layer3Sample:
+    RequantizeSample + StereoSample + HybridSynthesisSample + SynthesisSample + StateSample
+{
+}
+
+impl<S> Layer3Sample for S where
+    S: RequantizeSample + StereoSample + HybridSynthesisSample + SynthesisSample + StateSample
+{
+}
+
+pub struct Layer3<S: Layer3Sample = f32> {
+    pub samples: [[[S; 576]; 2]; 2],
+    pub overlap: [[[S; 18]; 32]; 2],
+    pub synthesis: [synthesis::SynthesisState<S>; 2],
     pub resevoir: BitResevoir,
+    pub warnings: Vec<DecodeWarning>,
+    /// The number of bytes of main data the last frame reused from previous frames.
+    pub resevoir_used: usize,
+    /// True if the last frame could not use the bit resevoir, and the decoder had to resynchronize
+    /// to the bitstream.
+    pub resynced: bool,
+    /// The ancillary data of the frame preceding the last decoded frame.
+    pub ancillary: Vec<u8>,
+    /// The side_info and main_data of the frame being decoded. Every field that is used while
+    /// decoding a frame is overwritten by the frame first, so it does not need to be cleared
+    /// between frames.
+    frame_data: FrameData,
+    /// The policy for outputting dual mono frames.
+    pub dual_mono: DualMonoPolicy,
+    /// The budget of spectral samples decoded, and time spent, per frame.
+    pub budget: DecodeBudget,
 }
 
-impl Layer3 {
+impl<S: Layer3Sample> Layer3<S> {
     pub fn new() -> Self {
         Self {
-            samples: [[[0f32; 576]; 2]; 2],
-            overlap: [[[0f32; 18]; 32]; 2],
+            samples: [[[S::default(); 576]; 2]; 2],
+            overlap: [[[S::default(); 18]; 32]; 2],
             synthesis: Default::default(),
             resevoir: BitResevoir::new(),
+            warnings: Vec::new(),
+            resevoir_used: 0,
+            resynced: false,
+            ancillary: Vec::new(),
+            frame_data: Default::default(),
+            dual_mono: DualMonoPolicy::Both,
+            budget: Default::default(),
+        }
+    }
+
+    /// Resets the decoder to its initial state without reallocating. The bit resevoir, IMDCT
+    /// overlap, and polyphase synthesis states are cleared so that no audio from before the reset
+    /// bleeds into the audio decoded after it.
+    pub fn reset(&mut self) {
+        self.resevoir.clear();
+
+        for overlap in self.overlap.iter_mut().flatten() {
+            overlap.fill(S::default());
         }
+
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.reset();
+        }
+
+        self.warnings.clear();
+        self.resevoir_used = 0;
+        self.resynced = false;
+        self.ancillary.clear();
+    }
+
+    /// Appends the state of the decoder to `buf`. The state consists of the bit resevoir, the
+    /// IMDCT overlap, and the polyphase synthesis states. Everything else is rewritten by every
+    /// frame.
+    pub fn save_state(&self, buf: &mut Vec<u8>) {
+        buf.push(S::ID);
+
+        self.resevoir.save(buf);
+
+        for overlap in self.overlap.iter().flatten() {
+            save_samples(overlap, buf);
+        }
+
+        for synthesis in self.synthesis.iter() {
+            synthesis.save(buf);
+        }
+    }
+
+    /// Restores a state previously saved by `save_state` from `reader`.
+    pub fn restore_state(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        if reader.read_u8()? != S::ID {
+            return decode_error("mpa: decoder state has a different sample type");
+        }
+
+        self.resevoir.restore(reader)?;
+
+        for overlap in self.overlap.iter_mut().flatten() {
+            restore_samples(overlap, reader)?;
+        }
+
+        for synthesis in self.synthesis.iter_mut() {
+            synthesis.restore(reader)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of granule channels in the last frame where the count1 partition
+    /// overran the part3 length.
+    pub fn count1_overruns(&self) -> usize {
+        self.warnings.iter().filter(|warning| warning.message == requantize::COUNT1_OVERRUN).count()
     }
 
     /// Reads the main_data portion of a MPEG audio frame from a `BitStream` into `FrameData`.
-    fn read_main_data(
-        &mut self,
-        header: &FrameHeader,
-        underflow_bits: u32,
-        frame_data: &mut FrameData,
-    ) -> Result<usize> {
-        let main_data = self.resevoir.bytes_ref();
+    fn read_main_data(&mut self, header: &FrameHeader, underflow_bits: u32) -> Result<usize> {
+        let mut bs = BitReaderLtr::new(self.resevoir.bytes_ref());
+        let frame_data = &mut self.frame_data;
         let mut part2_3_begin = 0;
         let mut part2_3_skipped = 0;
 
@@ -286,9 +455,16 @@ impl Layer3 {
                 // Zero the samples in the granule channel(s) and sum the part2/3 bits that were
                 // skipped.
                 for ch in 0..header.n_channels() {
+                    let channel = &mut frame_data.granules[gr].channels[ch];
+
                     requantize::zero(&mut self.samples[gr][ch]);
-                    part2_3_skipped +=
-                        u32::from(frame_data.granules[gr].channels[ch].part2_3_length);
+                    channel.rzero = 0;
+
+                    // The second granule may reuse the scale factors of the first granule. Since
+                    // they were not read, they must not be those of the last frame.
+                    channel.scalefacs = [0; 39];
+
+                    part2_3_skipped += u32::from(channel.part2_3_length);
                 }
 
                 // Adjust the start position of the next granule in the buffer of main data that is
@@ -302,67 +478,57 @@ impl Layer3 {
             }
 
             for ch in 0..header.n_channels() {
-                let byte_index = part2_3_begin >> 3;
+                let n_warnings = self.warnings.len();
 
-                // Create a bit reader at the expected starting bit position.
-                let mut bs = if byte_index < main_data.len() {
-                    let mut bs = BitReaderLtr::new(&main_data[byte_index..]);
+                let result = read_granule_channel(
+                    &mut bs,
+                    part2_3_begin,
+                    header,
+                    gr,
+                    ch,
+                    frame_data,
+                    &mut self.samples[gr][ch],
+                    &mut self.warnings,
+                );
 
-                    let bit_index = part2_3_begin & 0x7;
+                // A corrupt granule channel does not invalidate the remainder of the frame since
+                // the side_info states where the next granule channel begins. Conceal the error by
+                // discarding the spectral samples of the damaged granule channel. Hybrid synthesis
+                // will then output the tail of the previous granule's overlap.
+                if let Err(err) = result {
+                    let msg = match err {
+                        Error::DecodeError(msg) => msg,
+                        Error::IoError(_) => "mpa: main_data overrun",
+                        _ => return Err(err),
+                    };
 
-                    if bit_index > 0 {
-                        bs.ignore_bits(bit_index as u32)?;
-                    }
+                    warn!("{}, discarding granule", msg);
 
-                    bs
-                }
-                else {
-                    return decode_error("mpa: invalid main_data offset");
-                };
+                    self.warnings.push(DecodeWarning::new(DecodeWarningKind::Concealed, msg));
+
+                    let channel = &mut frame_data.granules[gr].channels[ch];
 
-                // Read the scale factors (part2) and get the number of bits read.
-                let part2_len = if header.is_mpeg1() {
-                    bitstream::read_scale_factors_mpeg1(&mut bs, gr, ch, frame_data)
+                    requantize::zero(&mut self.samples[gr][ch]);
+                    channel.rzero = 0;
+
+                    // The scale factors may have been partially read before the error. Since the
+                    // second granule may reuse the scale factors of the first granule, zero them.
+                    channel.scalefacs = [0; 39];
                 }
-                else {
-                    bitstream::read_scale_factors_mpeg2(
-                        &mut bs,
-                        ch > 0 && header.is_intensity_stereo(),
-                        &mut frame_data.granules[gr].channels[ch],
-                    )
-                }?;
-
-                let part2_3_length = u32::from(frame_data.granules[gr].channels[ch].part2_3_length);
-
-                // The part2 length must be less than or equal to the part2_3_length.
-                if part2_len > part2_3_length {
-                    return decode_error("mpa: part2_3_length is not valid");
+
+                // Locate the warnings raised by the granule channel at its part2_3 data.
+                let offset = main_data_packet_offset(header, self.resevoir_used, part2_3_begin);
+
+                for warning in &mut self.warnings[n_warnings..] {
+                    warning.offset = offset;
                 }
 
-                // The Huffman code length (part3).
-                let part3_len = part2_3_length - part2_len;
+                // Charge the decoded spectral samples to the budget of the frame.
+                self.budget.consume(frame_data.granules[gr].channels[ch].rzero)?;
 
-                // Decode the Huffman coded spectral samples and get the starting index of the rzero
-                // partition.
-                let huffman_result = requantize::read_huffman_samples(
-                    &mut bs,
-                    &frame_data.granules[gr].channels[ch],
-                    part3_len,
-                    &mut self.samples[gr][ch],
-                );
+                let part2_3_length = frame_data.granules[gr].channels[ch].part2_3_length;
 
-                // Huffman decoding errors are returned as an IO error by the bit reader. IO errors
-                // are unrecoverable, which is not the case for huffman decoding errors. Convert the
-                // IO error to a decode error.
-                frame_data.granules[gr].channels[ch].rzero = match huffman_result {
-                    Ok(rzero) => rzero,
-                    Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::Other => {
-                        return decode_error("mpa: huffman decode overrun");
-                    }
-                    Err(err) => return Err(err),
-                };
-
-                part2_3_begin += part2_3_length as usize;
+                part2_3_begin += usize::from(part2_3_length);
             }
         }
 
@@ -370,17 +536,138 @@ impl Layer3 {
     }
 }
 
-impl Layer for Layer3 {
-    fn decode(
+/// Gets the offset, in bits, from the start of the packet to the bit at `pos` in the main data
+/// buffered in the bit resevoir, where the first `resevoir_used` bytes of the main data were
+/// reused from previous packets. Returns `None` if the bit belongs to a previous packet.
+fn main_data_packet_offset(header: &FrameHeader, resevoir_used: usize, pos: usize) -> Option<u64> {
+    let crc_len = if header.has_crc { 2 } else { 0 };
+    let main_data_start = 8 * (4 + crc_len + header.side_info_len());
+
+    pos.checked_sub(8 * resevoir_used).map(|pos| (main_data_start + pos) as u64)
+}
+
+/// Returns true if the CRC of the layer 3 audio frame `frame` is valid, or if the frame is not
+/// protected by a CRC.
+///
+/// The CRC covers the last two bytes of the frame header, and the side_info.
+pub fn is_crc_valid(frame: &[u8], header: &FrameHeader) -> bool {
+    if !header.has_crc {
+        return true;
+    }
+
+    let side_info_end = 6 + header.side_info_len();
+
+    // A frame too short to contain the side_info will fail to decode regardless.
+    if frame.len() < side_info_end {
+        return true;
+    }
+
+    let mut crc = Crc16Ansi::new(0xffff);
+    crc.process_buf_bytes(&frame[2..4]);
+    crc.process_buf_bytes(&frame[6..side_info_end]);
+
+    crc.crc() == u16::from_be_bytes([frame[4], frame[5]])
+}
+
+/// Reads the scale factors and Huffman coded spectral samples of a single granule channel starting
+/// at the bit position `part2_3_begin` of the main data read by `bs`.
+#[allow(clippy::too_many_arguments)]
+fn read_granule_channel<S: RequantizeSample>(
+    bs: &mut BitReaderLtr<'_>,
+    part2_3_begin: usize,
+    header: &FrameHeader,
+    gr: usize,
+    ch: usize,
+    frame_data: &mut FrameData,
+    samples: &mut [S; 576],
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<()> {
+    // Position the bit reader at the start of the granule channel. Since the side_info states
+    // where each granule channel begins, this also recovers from the previous granule channel being
+    // read past its end.
+    match bs.seek_bits(part2_3_begin as u64) {
+        Ok(_) if bs.bits_left() > 0 => (),
+        _ => return decode_error("mpa: invalid main_data offset"),
+    }
+
+    // Scale factors that are not transmitted are 0. Since `FrameData` is reused between frames,
+    // clear the scale factors of the last frame before reading those of this frame.
+    frame_data.granules[gr].channels[ch].scalefacs = [0; 39];
+
+    // Read the scale factors (part2) and get the number of bits read.
+    let part2_len = if header.is_mpeg1() {
+        bitstream::read_scale_factors_mpeg1(bs, gr, ch, frame_data)
+    }
+    else {
+        bitstream::read_scale_factors_mpeg2(
+            bs,
+            ch > 0 && header.is_intensity_stereo(),
+            &mut frame_data.granules[gr].channels[ch],
+        )
+    }?;
+
+    let part2_3_length = u32::from(frame_data.granules[gr].channels[ch].part2_3_length);
+
+    // The part2 length must be less than or equal to the part2_3_length.
+    if part2_len > part2_3_length {
+        return decode_error("mpa: part2_3_length is not valid");
+    }
+
+    // The Huffman code length (part3).
+    let part3_len = part2_3_length - part2_len;
+
+    // Decode the Huffman coded spectral samples and get the starting index of the rzero partition.
+    let huffman_result = requantize::read_huffman_samples(
+        bs,
+        &frame_data.granules[gr].channels[ch],
+        part3_len,
+        samples,
+        warnings,
+    );
+
+    // Huffman decoding errors are returned as an IO error by the bit reader. IO errors are
+    // unrecoverable, which is not the case for huffman decoding errors. Convert the IO error to a
+    // decode error.
+    frame_data.granules[gr].channels[ch].rzero = match huffman_result {
+        Ok(rzero) => rzero,
+        Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::Other => {
+            return decode_error("mpa: huffman decode overrun");
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok(())
+}
+
+impl<S: Layer3Sample> Layer3<S> {
+    /// Decodes the frame described by `header` from `reader` into `out`.
+    pub fn decode_into<O>(
         &mut self,
         reader: &mut BufReader<'_>,
         header: &FrameHeader,
-        out: &mut AudioBuffer<f32>,
-    ) -> Result<()> {
-        // Initialize an empty FrameData to store the side_info and main_data portions of the
-        // frame.
-        let mut frame_data: FrameData = Default::default();
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<S>,
+    {
+        self.read_frame(reader, header)?;
+
+        for gr in 0..header.n_granules() {
+            self.decode_granule(header, gr, out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the side information and main data of the frame described by `header` from `reader`.
+    /// Afterwards, each granule of the frame may be decoded with `decode_granule`.
+    pub fn read_frame(&mut self, reader: &mut BufReader<'_>, header: &FrameHeader) -> Result<()> {
+        self.resevoir_used = 0;
+        self.resynced = false;
+        self.ancillary.clear();
+        self.budget.begin();
 
+        // The CRC, if present, is verified by the caller using `is_crc_valid`.
         let _crc = if header.has_crc { Some(reader.read_be_u16()?) } else { None };
 
         let buf = reader.read_buf_bytes_available_ref();
@@ -388,24 +675,48 @@ impl Layer for Layer3 {
         let mut bs = BitReaderLtr::new(buf);
 
         // Read side_info into the frame data.
-        // TODO: Use a MonitorStream to compute the CRC.
-        let side_info_len = match bitstream::read_side_info(&mut bs, header, &mut frame_data) {
+        let side_info_len = match bitstream::read_side_info(&mut bs, header, &mut self.frame_data) {
             Ok(len) => len,
             Err(e) => {
                 // A failure in reading this packet will cause a discontinuity in the codec
                 // bitstream. Therefore, clear the bit reservoir since it will not be valid for the
                 // next packet.
                 self.resevoir.clear();
+                self.resynced = true;
                 return Err(e);
             }
         };
 
+        let main_data_begin = usize::from(self.frame_data.main_data_begin);
+
+        // The end of the ancillary data of a frame is only known once the main_data_begin of the
+        // next frame is known. Collect the ancillary data of the previous frame before the bytes
+        // are discarded from the resevoir.
+        self.ancillary.extend_from_slice(self.resevoir.ancillary_ref(main_data_begin));
+
         // Buffer main data into the bit resevoir.
-        let underflow =
-            self.resevoir.fill(&buf[side_info_len..], frame_data.main_data_begin as usize)?;
+        let underflow = match self.resevoir.fill(&buf[side_info_len..], main_data_begin) {
+            Ok(underflow) => underflow,
+            Err(e) => {
+                // As above, the bit reservoir is no longer valid for the next packet.
+                self.resevoir.clear();
+                self.resynced = true;
+                return Err(e);
+            }
+        };
+
+        self.resevoir_used = main_data_begin - underflow as usize;
+
+        if underflow > 0 {
+            self.resynced = true;
+            self.warnings.push(DecodeWarning::new(
+                DecodeWarningKind::MissingData,
+                "mpa: invalid main_data_begin, bit reservoir underflow",
+            ));
+        }
 
         // Read the main data (scale factors and spectral samples).
-        match self.read_main_data(header, 8 * underflow, &mut frame_data) {
+        match self.read_main_data(header, 8 * underflow) {
             Ok(len) => {
                 // Consume the bytes of main data read from the resevoir.
                 self.resevoir.consume(len);
@@ -414,65 +725,247 @@ impl Layer for Layer3 {
                 // The bit reservoir was likely filled with invalid data. Clear it for the next
                 // packet.
                 self.resevoir.clear();
+                self.resynced = true;
                 return Err(e);
             }
         }
 
-        for gr in 0..header.n_granules() {
-            let granule = &mut frame_data.granules[gr];
+        Ok(())
+    }
 
-            // Requantize all non-zero (big_values and count1 partition) spectral samples.
-            requantize::requantize(header, &granule.channels[0], &mut self.samples[gr][0]);
+    /// Decodes granule `gr` of the frame last read by `read_frame`, and appends the 576 decoded
+    /// audio frames to `out`.
+    pub fn decode_granule<O>(
+        &mut self,
+        header: &FrameHeader,
+        gr: usize,
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<S>,
+    {
+        let granule = &mut self.frame_data.granules[gr];
+
+        // Requantize all non-zero (big_values and count1 partition) spectral samples.
+        requantize::requantize(header, &granule.channels[0], &mut self.samples[gr][0]);
+
+        // If there is a second channel...
+        if header.channel_mode != ChannelMode::Mono {
+            // Requantize all non-zero spectral samples in the second channel.
+            requantize::requantize(header, &granule.channels[1], &mut self.samples[gr][1]);
+
+            // Apply joint stereo processing if it is used.
+            stereo::stereo(header, granule, &mut self.samples[gr])?;
+        }
 
-            // If there is a second channel...
-            if header.channel_mode != ChannelMode::Mono {
-                // Requantize all non-zero spectral samples in the second channel.
-                requantize::requantize(header, &granule.channels[1], &mut self.samples[gr][1]);
+        // Each granule will yield 576 samples. After reserving frames, all steps must be
+        // infalliable.
+        let start = out.frames();
+        out.render_reserved(Some(576));
+
+        // The next steps are independant of channel count.
+        for ch in 0..header.n_channels() {
+            // Reorder the spectral samples in short blocks into sub-band order.
+            hybrid_synthesis::reorder(header, &mut granule.channels[ch], &mut self.samples[gr][ch]);
+
+            // Apply the anti-aliasing filter to all block types other than short.
+            hybrid_synthesis::antialias(&mut granule.channels[ch], &mut self.samples[gr][ch]);
+
+            // Perform hybrid-synthesis (IMDCT and windowing). After this step, rzero is invalid
+            // due to the overlap-add operation.
+            hybrid_synthesis::hybrid_synthesis(
+                &granule.channels[ch],
+                &mut self.overlap[ch],
+                &mut self.samples[gr][ch],
+            );
+
+            // Invert every second sample in every second sub-band to negate the frequency
+            // inversion of the polyphase filterbank.
+            hybrid_synthesis::frequency_inversion(&mut self.samples[gr][ch]);
+        }
 
-                // Apply joint stereo processing if it is used.
-                stereo::stereo(header, granule, &mut self.samples[gr])?;
-            }
+        if header.channel_mode == ChannelMode::DualMono {
+            let (left, right) = self.samples[gr].split_at_mut(1);
+            synthesis::select_dual_mono(&mut left[0], &mut right[0], self.dual_mono);
+        }
 
-            // Each granule will yield 576 samples. After reserving frames, all steps must be
-            // infalliable.
-            out.render_reserved(Some(576));
+        // If the output is mono, downmix stereo granules after stereo processing and hybrid
+        // synthesis, but before polyphase synthesis.
+        let n_out_channels = out.spec().channels.count();
 
-            // The next steps are independant of channel count.
-            for ch in 0..header.n_channels() {
-                // Reorder the spectral samples in short blocks into sub-band order.
-                hybrid_synthesis::reorder(
-                    header,
-                    &mut granule.channels[ch],
-                    &mut self.samples[gr][ch],
-                );
+        if n_out_channels < header.n_channels() {
+            let (left, right) = self.samples[gr].split_at_mut(1);
+            synthesis::downmix_to_mono(&mut left[0], &right[0]);
+        }
 
-                // Apply the anti-aliasing filter to all block types other than short.
-                hybrid_synthesis::antialias(&mut granule.channels[ch], &mut self.samples[gr][ch]);
+        for ch in 0..n_out_channels {
+            // Perform polyphase synthesis and generate PCM samples.
+            let out_ch_samples = out.chan_mut(ch);
 
-                // Perform hybrid-synthesis (IMDCT and windowing). After this step, rzero is invalid
-                // due to the overlap-add operation.
-                hybrid_synthesis::hybrid_synthesis(
-                    &granule.channels[ch],
-                    &mut self.overlap[ch],
-                    &mut self.samples[gr][ch],
-                );
+            synthesis::synthesis(
+                &mut self.synthesis[ch],
+                18,
+                &self.samples[gr][ch],
+                &mut out_ch_samples[start..start + 576],
+            );
+        }
 
-                // Invert every second sample in every second sub-band to negate the frequency
-                // inversion of the polyphase filterbank.
-                hybrid_synthesis::frequency_inversion(&mut self.samples[gr][ch]);
+        Ok(())
+    }
+}
 
-                // Perform polyphase synthesis and generate PCM samples.
-                let out_ch_samples = out.chan_mut(ch);
+impl Layer for Layer3<f32> {
+    fn decode<O>(
+        &mut self,
+        reader: &mut BufReader<'_>,
+        header: &FrameHeader,
+        out: &mut AudioBuffer<O>,
+    ) -> Result<()>
+    where
+        O: Sample + FromSynthesized<f32>,
+    {
+        self.decode_into(reader, header, out)
+    }
+}
 
-                synthesis::synthesis(
-                    &mut self.synthesis[ch],
-                    18,
-                    &self.samples[gr][ch],
-                    &mut out_ch_samples[(gr * 576)..((gr + 1) * 576)],
-                );
+#[cfg(test)]
+mod tests {
+    use super::{is_crc_valid, BitResevoir};
+    use super::{MAX_MAIN_DATA_BEGIN, MAX_MAIN_DATA_LEN, RESEVOIR_CAPACITY};
+    use crate::header::parse_frame_header;
+
+    use symphonia_core::io::BufReader;
+
+    /// Bit-by-bit reference implementation of the MPEG audio CRC-16.
+    fn crc16_reference(bytes: &[u8]) -> u16 {
+        let mut crc = 0xffffu16;
+
+        for &byte in bytes {
+            for bit in (0..8).rev() {
+                let feedback = ((crc >> 15) ^ u16::from(byte >> bit)) & 1;
+                crc <<= 1;
+                if feedback != 0 {
+                    crc ^= 0x8005;
+                }
             }
         }
 
-        Ok(())
+        crc
+    }
+
+    #[test]
+    fn verify_is_crc_valid() {
+        // MPEG1 layer 3, 128 kbps, 44.1 kHz, joint stereo, protected by a CRC.
+        let header = parse_frame_header(0xfffa_9044).unwrap();
+        assert!(header.has_crc);
+
+        let mut frame = vec![0u8; header.frame_size];
+        frame[..4].copy_from_slice(&[0xff, 0xfa, 0x90, 0x44]);
+
+        for (i, byte) in frame[6..].iter_mut().enumerate() {
+            *byte = (i * 37 + 11) as u8;
+        }
+
+        let side_info_end = 6 + header.side_info_len();
+        let crc = crc16_reference(&[&frame[2..4], &frame[6..side_info_end]].concat());
+        frame[4..6].copy_from_slice(&crc.to_be_bytes());
+
+        assert!(is_crc_valid(&frame, &header));
+
+        // Corrupting the main_data does not invalidate the CRC.
+        frame[side_info_end] ^= 0x01;
+        assert!(is_crc_valid(&frame, &header));
+
+        // Corrupting the side_info, or the protected bits of the header, does.
+        frame[side_info_end - 1] ^= 0x01;
+        assert!(!is_crc_valid(&frame, &header));
+
+        frame[side_info_end - 1] ^= 0x01;
+        frame[3] ^= 0x04;
+        assert!(!is_crc_valid(&frame, &header));
+
+        // Frames without a CRC are always valid.
+        let header = parse_frame_header(0xfffb_9044).unwrap();
+        assert!(is_crc_valid(&frame, &header));
+    }
+
+    #[test]
+    fn verify_bit_resevoir() {
+        let mut resevoir = BitResevoir::new();
+
+        // Hostile lengths and offsets are rejected without panicking.
+        assert!(resevoir.fill(&[0; 16], usize::MAX).is_err());
+        assert!(resevoir.fill(&vec![0; RESEVOIR_CAPACITY + 1], 0).is_err());
+
+        // The largest frame: a padded 160 kbps MPEG2.5 mono frame at 8 kHz.
+        let header = parse_frame_header(0xffe3_eac0).unwrap();
+        assert_eq!(header.frame_size - header.side_info_len(), MAX_MAIN_DATA_LEN);
+
+        // The largest frame, re-using the most data, fits. Since the resevoir is empty, all the
+        // re-used bytes are missing.
+        let frame = vec![0; MAX_MAIN_DATA_LEN];
+        assert_eq!(resevoir.fill(&frame, MAX_MAIN_DATA_BEGIN).unwrap(), 511);
+
+        resevoir.clear();
+
+        // Re-use 4 of the unread bytes of the previous frame.
+        assert_eq!(resevoir.fill(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 0).unwrap(), 0);
+        resevoir.consume(6);
+        assert_eq!(resevoir.fill(&[11, 12], 4).unwrap(), 0);
+        assert_eq!(resevoir.bytes_ref(), &[7, 8, 9, 10, 11, 12]);
+
+        // Re-use bytes that were already read by the previous frame.
+        resevoir.consume(6);
+        assert_eq!(resevoir.fill(&[13], 3).unwrap(), 0);
+        assert_eq!(resevoir.bytes_ref(), &[10, 11, 12, 13]);
+
+        // Re-use more bytes than are buffered.
+        assert_eq!(resevoir.fill(&[14], 10).unwrap(), 6);
+        assert_eq!(resevoir.bytes_ref(), &[10, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn verify_bit_resevoir_ancillary_data() {
+        let mut resevoir = BitResevoir::new();
+
+        // Nothing has been buffered.
+        assert!(resevoir.ancillary_ref(0).is_empty());
+
+        // The bytes after the main data of the previous frame, but before the main data re-used by
+        // the next frame, are ancillary data.
+        resevoir.fill(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 0).unwrap();
+        resevoir.consume(4);
+        assert_eq!(resevoir.ancillary_ref(2), &[5, 6, 7, 8]);
+        assert_eq!(resevoir.ancillary_ref(0), &[5, 6, 7, 8, 9, 10]);
+
+        // If the next frame re-uses all unread bytes, or underflows, there is no ancillary data.
+        assert!(resevoir.ancillary_ref(6).is_empty());
+        assert!(resevoir.ancillary_ref(8).is_empty());
+        assert!(resevoir.ancillary_ref(20).is_empty());
+    }
+
+    #[test]
+    fn verify_bit_resevoir_save_restore() {
+        let mut resevoir = BitResevoir::new();
+
+        let frame: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        resevoir.fill(&frame, 0).unwrap();
+        resevoir.consume(900);
+
+        // Only the bytes that may be re-used by the next frame are saved.
+        let mut state = Vec::new();
+        resevoir.save(&mut state);
+        assert_eq!(state.len(), 4 + MAX_MAIN_DATA_BEGIN);
+
+        let mut restored = BitResevoir::new();
+        restored.restore(&mut BufReader::new(&state)).unwrap();
+        assert_eq!(restored.bytes_ref(), resevoir.bytes_ref());
+
+        resevoir.fill(&[1, 2, 3], MAX_MAIN_DATA_BEGIN).unwrap();
+        restored.fill(&[1, 2, 3], MAX_MAIN_DATA_BEGIN).unwrap();
+        assert_eq!(restored.bytes_ref(), resevoir.bytes_ref());
+
+        // More unread bytes than saved bytes is invalid.
+        assert!(restored.restore(&mut BufReader::new(&[1, 0, 2, 0, 0])).is_err());
     }
 }
@@ -5,8 +5,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use symphonia_core::codecs::{DecodeWarning, DecodeWarningKind::MalformedBitstream};
 use symphonia_core::errors::Result;
-use symphonia_core::io::ReadBitsLtr;
+use symphonia_core::io::{ReadBitsLtr, SeekBits};
 
 use crate::common::FrameHeader;
 
@@ -15,54 +16,181 @@ use super::{codebooks, common::*, GranuleChannel};
 use std::cmp::min;
 use std::{f32, f64};
 
-use lazy_static::lazy_static;
-
 use log::debug;
 
-lazy_static! {
-    /// Lookup table for computing x(i) = s(i)^(4/3) where s(i) is a decoded Huffman sample. The
-    /// value of s(i) is bound between 0..8207.
-    static ref REQUANTIZE_POW43: [f32; 8207] = {
-        // It is wasteful to initialize to 0.. however, Symphonia policy is to limit unsafe code to
-        // only symphonia-core.
-        //
-        // TODO: Implement generic lookup table initialization in the core library.
-        let mut pow43 = [0f32; 8207];
-        for (i, pow43) in pow43.iter_mut().enumerate() {
-            *pow43 = f32::powf(i as f32, 4.0 / 3.0);
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+
+/// Lookup table for computing x(i) = s(i)^(4/3) where s(i) is a decoded Huffman sample. The
+/// value of s(i) is bound between 0..8207.
+static REQUANTIZE_POW43: [f32; 8207] = include!(concat!(env!("OUT_DIR"), "/requantize_pow43.rs"));
+
+/// The minimum requantization exponent, 2^(0.25 * exponent). Occurs for a short block with the
+/// minimum global gain, maximum subblock gain, and maximum scale factor.
+const REQUANTIZE_POW2_MIN_EXP: i32 = -210 - 8 * 7 - (15 << 2);
+
+/// The maximum requantization exponent. Occurs for the maximum global gain, and a scale factor of
+/// 0.
+const REQUANTIZE_POW2_MAX_EXP: i32 = 255 - 210;
+
+/// Lookup table for computing 2^(0.25 * exponent) for all requantization exponents, starting at
+/// `REQUANTIZE_POW2_MIN_EXP`.
+// Justification: The generated table contains powers of 2 that are mathematical constants.
+#[allow(clippy::approx_constant)]
+static REQUANTIZE_POW2: [f32; (REQUANTIZE_POW2_MAX_EXP - REQUANTIZE_POW2_MIN_EXP + 1) as usize] =
+    include!(concat!(env!("OUT_DIR"), "/requantize_pow2.rs"));
+
+/// Lookup table for computing x(i) = s(i)^(4/3) in fixed-point, where s(i) is a decoded Huffman
+/// sample. The value of s(i) is bound between 0..8207.
+///
+/// Each entry packs a 27-bit mantissa, m, and a 5-bit exponent, e, as `(m << 5) | e`, such that
+/// `x(i) = m * 2^(e - 27)` where `2^26 <= m < 2^27`.
+#[cfg(feature = "fixed-point")]
+static REQUANTIZE_POW43_FIXED: [u32; 8207] =
+    include!(concat!(env!("OUT_DIR"), "/requantize_pow43_fixed.rs"));
+
+/// Lookup table of 2^(i/4) for i=0..4, in Q2.30 format.
+#[cfg(feature = "fixed-point")]
+const REQUANTIZE_ROOT_2_FIXED: [u64; 4] = [
+    (1.000_000_000_000_000_0 * (1u64 << 30) as f64 + 0.5) as u64,
+    (1.189_207_115_002_721_0 * (1u64 << 30) as f64 + 0.5) as u64,
+    (f64::consts::SQRT_2 * (1u64 << 30) as f64 + 0.5) as u64,
+    (1.681_792_830_507_429_0 * (1u64 << 30) as f64 + 0.5) as u64,
+];
+
+/// A spectral sample type that Huffman coded samples can be decoded into, and then requantized.
+pub trait RequantizeSample: Copy + Default {
+    /// The type of lookup table used to convert Huffman decoded samples into spectral samples.
+    type Lut: 'static;
+
+    /// Gets the lookup table.
+    fn lut() -> &'static Self::Lut;
+
+    /// Converts the magnitude, and sign, of a Huffman decoded sample into a spectral sample.
+    fn from_huffman(lut: &Self::Lut, magnitude: usize, negative: bool) -> Self;
+
+    /// Scales all spectral samples by 2^(0.25 * `exponent`).
+    fn requantize(samples: &mut [Self], exponent: i32);
+}
+
+impl RequantizeSample for f32 {
+    type Lut = [f32; 8207];
+
+    #[inline(always)]
+    fn lut() -> &'static Self::Lut {
+        &REQUANTIZE_POW43
+    }
+
+    /// The value of the Huffman decoded sample is raised to the (4/3)-rd power. This is not
+    /// actually part of the Huffman decoding process, but, by converting the integer sample to
+    /// floating point here we don't need to do pointless casting or use an extra buffer.
+    #[inline(always)]
+    fn from_huffman(lut: &Self::Lut, magnitude: usize, negative: bool) -> Self {
+        if negative {
+            -lut[magnitude]
+        }
+        else {
+            lut[magnitude]
+        }
+    }
+
+    fn requantize(samples: &mut [Self], exponent: i32) {
+        let pow2 = match REQUANTIZE_POW2.get((exponent - REQUANTIZE_POW2_MIN_EXP) as usize) {
+            Some(&pow2) => pow2,
+            // The exponent is always in range for a valid bitstream.
+            _ => f64::powf(2.0, 0.25 * f64::from(exponent)) as f32,
+        };
+
+        for sample in samples {
+            *sample *= pow2;
+        }
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl RequantizeSample for Fixed {
+    type Lut = ();
+
+    #[inline(always)]
+    fn lut() -> &'static Self::Lut {
+        &()
+    }
+
+    /// The signed Huffman decoded sample is stored as-is, it is only raised to the (4/3)-rd power
+    /// during requantization since the result will generally not fit in a `Fixed` before scaling.
+    #[inline(always)]
+    fn from_huffman(_: &Self::Lut, magnitude: usize, negative: bool) -> Self {
+        if negative {
+            Fixed(-(magnitude as i32))
+        }
+        else {
+            Fixed(magnitude as i32)
+        }
+    }
+
+    fn requantize(samples: &mut [Self], exponent: i32) {
+        let pow43_table: &[u32; 8207] = &REQUANTIZE_POW43_FIXED;
+
+        // Split 2^(0.25 * exponent) into 2^(exponent / 4) * 2^((exponent % 4) / 4), where the
+        // first term is a bit shift, and the second term is looked up.
+        let root = REQUANTIZE_ROOT_2_FIXED[(exponent & 0x3) as usize];
+        let exp_shift = exponent >> 2;
+
+        for sample in samples {
+            if sample.0 == 0 {
+                continue;
+            }
+
+            let pow43 = pow43_table[sample.0.unsigned_abs() as usize];
+
+            // The mantissa of s(i)^(4/3) multiplied by the root term, in Q27 format. This value
+            // is < 2^28.
+            let mantissa = (u64::from(pow43 >> 5) * root) >> 30;
+
+            // Shift the mantissa to obtain the final Q24 value.
+            let shift = (pow43 & 0x1f) as i32 + exp_shift - 27 + Fixed::FRAC_BITS as i32;
+
+            let value = if shift >= 0 {
+                (mantissa << shift.min(32)).min(i32::MAX as u64) as i32
+            }
+            else {
+                let shift = (-shift).min(63);
+                ((mantissa + (1 << (shift - 1))) >> shift) as i32
+            };
+
+            *sample = if sample.0 < 0 { Fixed(-value) } else { Fixed(value) };
         }
-        pow43
-    };
+    }
 }
 
+/// The message of the warning raised when the count1 partition overruns the part3 length.
+pub const COUNT1_OVERRUN: &str = "mpa: count1 overrun";
+
 /// Zero a sample buffer.
 #[inline(always)]
-pub(super) fn zero(buf: &mut [f32; 576]) {
-    buf.fill(0.0);
+pub(super) fn zero<S: RequantizeSample>(buf: &mut [S; 576]) {
+    buf.fill(S::default());
 }
 
 /// Reads the Huffman coded spectral samples for a given channel in a granule from a `BitStream`
 /// into a provided sample buffer. Returns the number of decoded samples (the starting index of the
-/// rzero partition).
+/// rzero partition). Recoverable bitstream errors are pushed onto `warnings`.
 ///
-/// Note, each spectral sample is raised to the (4/3)-rd power. This is not actually part of the
-/// Huffman decoding process, but, by converting the integer sample to floating point here we don't
-/// need to do pointless casting or use an extra buffer.
-pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
+/// Note, each spectral sample is converted using `RequantizeSample::from_huffman`.
+pub(super) fn read_huffman_samples<B: ReadBitsLtr + SeekBits, S: RequantizeSample>(
     bs: &mut B,
     channel: &GranuleChannel,
     part3_bits: u32,
-    buf: &mut [f32; 576],
+    buf: &mut [S; 576],
+    warnings: &mut Vec<DecodeWarning>,
 ) -> Result<usize> {
     // If there are no Huffman code bits, zero all samples and return immediately.
     if part3_bits == 0 {
-        buf.fill(0.0);
+        buf.fill(S::default());
         return Ok(0);
     }
 
-    // Dereference the POW43 table once per granule since there is a tiny overhead each time a
-    // lazy_static is dereferenced that should be amortized over as many samples as possible.
-    let pow43_table: &[f32; 8207] = &REQUANTIZE_POW43;
+    let lut = S::lut();
 
     let mut bits_read = 0;
     let mut i = 0;
@@ -99,9 +227,9 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
         // region.
         if codebook.is_empty() {
             while i < *region_end {
-                buf[i] = 0.0;
+                buf[i] = S::default();
                 i += 1;
-                buf[i] = 0.0;
+                buf[i] = S::default();
                 i += 1;
             }
             continue;
@@ -128,12 +256,12 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
                 }
 
                 // The next bit is the sign bit. If the sign bit is 1, then the sample should be
-                // negative. The value of the sample is raised to the (4/3) power.
-                buf[i] = (1.0 - 2.0 * bs.read_bit()? as f32) * pow43_table[x];
+                // negative.
+                buf[i] = S::from_huffman(lut, x, bs.read_bit()? != 0);
                 bits_read += 1;
             }
             else {
-                buf[i] = 0.0;
+                buf[i] = S::default();
             }
 
             i += 1;
@@ -145,11 +273,11 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
                     bits_read += linbits;
                 }
 
-                buf[i] = (1.0 - 2.0 * bs.read_bit()? as f32) * pow43_table[y];
+                buf[i] = S::from_huffman(lut, y, bs.read_bit()? != 0);
                 bits_read += 1;
             }
             else {
-                buf[i] = 0.0;
+                buf[i] = S::default();
             }
 
             i += 1;
@@ -165,7 +293,7 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
         //
         // For each 1-bit sample, if it is 0, then the dequantized sample value is 0 as well. If
         // the 1-bit sample is 1, then a sign bit is read. The dequantized sample is then either
-        // +/-1.0 depending on the sign bit.
+        // +/-1 depending on the sign bit.
 
         // Decode the next Huffman code.
         let (value, code_len) = bs.read_codebook(count1_codebook)?;
@@ -181,34 +309,34 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
 
         // Unpack the samples.
         if value & 0x1 != 0 {
-            buf[i + 3] = 1.0 - 2.0 * (signs & 1) as f32;
+            buf[i + 3] = S::from_huffman(lut, 1, signs & 1 != 0);
             signs >>= 1;
         }
         else {
-            buf[i + 3] = 0.0;
+            buf[i + 3] = S::default();
         }
 
         if value & 0x2 != 0 {
-            buf[i + 2] = 1.0 - 2.0 * (signs & 1) as f32;
+            buf[i + 2] = S::from_huffman(lut, 1, signs & 1 != 0);
             signs >>= 1;
         }
         else {
-            buf[i + 2] = 0.0;
+            buf[i + 2] = S::default();
         }
 
         if value & 0x4 != 0 {
-            buf[i + 1] = 1.0 - 2.0 * (signs & 1) as f32;
+            buf[i + 1] = S::from_huffman(lut, 1, signs & 1 != 0);
             signs >>= 1;
         }
         else {
-            buf[i + 1] = 0.0;
+            buf[i + 1] = S::default();
         }
 
         if value & 0x8 != 0 {
-            buf[i + 0] = 1.0 - 2.0 * (signs & 1) as f32;
+            buf[i + 0] = S::from_huffman(lut, 1, signs & 1 != 0);
         }
         else {
-            buf[i + 0] = 0.0;
+            buf[i + 0] = S::default();
         }
 
         i += 4;
@@ -221,27 +349,34 @@ pub(super) fn read_huffman_samples<B: ReadBitsLtr>(
     // Word on the street is that some encoders are poor at "stuffing" bits, resulting in part3_len
     // being ever so slightly too large. This causes the Huffman decode loop to decode the next few
     // bits as spectral samples. However, these bits are actually random data and are not real
-    // samples, therefore, undo them! The caller will be reponsible for re-aligning the bitstream
-    // reader. Candy Pop confirms this.
+    // samples, therefore, undo them, and rewind the bitstream to the end of part3. Candy Pop
+    // confirms this.
     else if bits_read > part3_bits && i > big_values_len {
         debug!("count1 overrun, malformed bitstream");
+        warnings.push(DecodeWarning::new(MalformedBitstream, COUNT1_OVERRUN));
+        bs.rewind_bits(u64::from(bits_read - part3_bits))?;
         i -= 4;
     }
     else if bits_read > part3_bits {
         // It seems that most other decoders don't undo overruns of the big values. We'll just print
         // a message for now.
         debug!("big_values overrun, malformed bitstream");
+        warnings.push(DecodeWarning::new(MalformedBitstream, "mpa: big_values overrun"));
     }
 
     // The final partition after the count1 partition is the rzero partition. Samples in this
     // partition are all 0.
-    buf[i..].fill(0.0);
+    buf[i..].fill(S::default());
 
     Ok(i)
 }
 
 /// Requantize long block samples in `buf`.
-fn requantize_long(channel: &GranuleChannel, bands: &[usize], buf: &mut [f32; 576]) {
+fn requantize_long<S: RequantizeSample>(
+    channel: &GranuleChannel,
+    bands: &[usize],
+    buf: &mut [S; 576],
+) {
     // For long blocks dequantization and scaling is governed by the following equation:
     //
     //                     xr(i) = s(i)^(4/3) * 2^(0.25*A) * 2^(-B)
@@ -278,28 +413,23 @@ fn requantize_long(channel: &GranuleChannel, bands: &[usize], buf: &mut [f32; 57
         // Calculate B.
         let b = i32::from((channel.scalefacs[i] + pre_emphasis) << scalefac_shift);
 
-        // Calculate 2^(0.25*A) * 2^(-B). This can be rewritten as 2^{ 0.25 * (A - 4 * B) }.
-        // Since scalefac_shift was multiplies by 4 above, the final equation becomes
-        // 2^{ 0.25 * (A - B) }.
-        let pow2ab = f64::powf(2.0, 0.25 * f64::from(a - b)) as f32;
-
         // Calculate the ending sample index for the scale-factor band, clamping it to the length of
         // the sample buffer.
         let band_end = min(*end, channel.rzero);
 
-        // The sample buffer contains s(i)^(4/3), now multiply in 2^(0.25*A) * 2^(-B) to get xr(i).
-        for sample in &mut buf[*start..band_end] {
-            *sample *= pow2ab;
-        }
+        // Multiply in 2^(0.25*A) * 2^(-B) to get xr(i). This can be rewritten as
+        // 2^{ 0.25 * (A - 4 * B) }. Since scalefac_shift was multiplies by 4 above, the final
+        // equation becomes 2^{ 0.25 * (A - B) }.
+        S::requantize(&mut buf[*start..band_end], a - b);
     }
 }
 
 /// Requantize short block samples in `buf` starting at scale-factor band `sfb_init`.
-fn requantize_short(
+fn requantize_short<S: RequantizeSample>(
     channel: &GranuleChannel,
     bands: &[usize],
     switch: usize,
-    buf: &mut [f32; 576],
+    buf: &mut [S; 576],
 ) {
     // For short blocks dequantization and scaling is governed by the following equation:
     //
@@ -341,25 +471,23 @@ fn requantize_short(
         // Calculate B.
         let b = i32::from(channel.scalefacs[switch + i] << scalefac_shift);
 
-        // Calculate 2^(0.25*A) * 2^(-B). This can be rewritten as 2^{ 0.25 * (A - 4 * B) }.
-        // Since scalefac_shift multiplies by 4 above, the final equation becomes
-        // 2^{ 0.25 * (A - B) }.
-        let pow2ab = f64::powf(2.0, 0.25 * f64::from(a[i % 3] - b)) as f32;
-
         // Clamp the ending sample index to the rzero sample index. Since samples starting from
         // rzero are 0, there is no point in requantizing them.
         let win_end = min(*end, channel.rzero);
 
-        // The sample buffer contains s(i)^(4/3), now multiply in 2^(0.25*A) * 2^(-B) to get
-        // xr(i).
-        for sample in &mut buf[*start..win_end] {
-            *sample *= pow2ab;
-        }
+        // Multiply in 2^(0.25*A) * 2^(-B) to get xr(i). This can be rewritten as
+        // 2^{ 0.25 * (A - 4 * B) }. Since scalefac_shift multiplies by 4 above, the final equation
+        // becomes 2^{ 0.25 * (A - B) }.
+        S::requantize(&mut buf[*start..win_end], a[i % 3] - b);
     }
 }
 
 /// Requantize samples in `buf` regardless of block type.
-pub(super) fn requantize(header: &FrameHeader, channel: &GranuleChannel, buf: &mut [f32; 576]) {
+pub(super) fn requantize<S: RequantizeSample>(
+    header: &FrameHeader,
+    channel: &GranuleChannel,
+    buf: &mut [S; 576],
+) {
     match channel.block_type {
         BlockType::Short { is_mixed: false } => {
             requantize_short(channel, &SFB_SHORT_BANDS[header.sample_rate_idx], 0, buf);
@@ -375,7 +503,9 @@ pub(super) fn requantize(header: &FrameHeader, channel: &GranuleChannel, buf: &m
             let bands = SFB_MIXED_BANDS[header.sample_rate_idx];
             let switch = SFB_MIXED_SWITCH_POINT[header.sample_rate_idx];
 
-            requantize_long(channel, &bands[..switch], buf);
+            // The long bands end where the short bands begin, so the band edge at the switch point
+            // is shared.
+            requantize_long(channel, &bands[..=switch], buf);
             requantize_short(channel, &bands[switch..], switch, buf);
         }
         _ => {
@@ -383,3 +513,140 @@ pub(super) fn requantize(header: &FrameHeader, channel: &GranuleChannel, buf: &m
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{requantize, RequantizeSample, REQUANTIZE_POW43};
+    use super::{REQUANTIZE_POW2_MAX_EXP, REQUANTIZE_POW2_MIN_EXP};
+    #[cfg(feature = "fixed-point")]
+    use crate::fixed::Fixed;
+    use crate::header::parse_frame_header;
+    use crate::layer3::common::*;
+    use crate::layer3::GranuleChannel;
+
+    #[test]
+    fn verify_pow43_table() {
+        // The generated table must round-trip exactly.
+        for (i, &pow43) in REQUANTIZE_POW43.iter().enumerate() {
+            assert_eq!(pow43, f32::powf(i as f32, 4.0 / 3.0));
+        }
+    }
+
+    #[test]
+    fn verify_pow2_table() {
+        // Requantizing with the table must be identical to computing the power directly.
+        for exponent in REQUANTIZE_POW2_MIN_EXP - 2..=REQUANTIZE_POW2_MAX_EXP + 2 {
+            let mut samples = [1.0f32];
+            f32::requantize(&mut samples, exponent);
+            assert_eq!(samples[0], f64::powf(2.0, 0.25 * f64::from(exponent)) as f32);
+        }
+    }
+
+    #[test]
+    fn verify_requantize() {
+        let lut = f32::lut();
+
+        let mut samples = [f32::from_huffman(lut, 8, false), f32::from_huffman(lut, 8, true)];
+        f32::requantize(&mut samples, -4);
+
+        // 8^(4/3) * 2^(-1) = 8
+        assert!((samples[0] - 8.0).abs() < 0.00001);
+        assert!((samples[1] + 8.0).abs() < 0.00001);
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_requantize_fixed() {
+        const MAGNITUDES: [usize; 8] = [0, 1, 2, 3, 15, 100, 1000, 8206];
+
+        for exponent in -250..=8 {
+            let mut float = [0f32; 16];
+            let mut fixed = [Fixed(0); 16];
+
+            for (i, &mag) in MAGNITUDES.iter().enumerate() {
+                float[2 * i] = f32::from_huffman(f32::lut(), mag, false);
+                float[2 * i + 1] = f32::from_huffman(f32::lut(), mag, true);
+                fixed[2 * i] = Fixed::from_huffman(Fixed::lut(), mag, false);
+                fixed[2 * i + 1] = Fixed::from_huffman(Fixed::lut(), mag, true);
+            }
+
+            f32::requantize(&mut float, exponent);
+            Fixed::requantize(&mut fixed, exponent);
+
+            for (&float, &fixed) in float.iter().zip(&fixed) {
+                let expected = Fixed::from_f64(f64::from(float));
+
+                if expected == Fixed(i32::MAX) || expected == Fixed(i32::MIN) {
+                    // Values that cannot be represented must saturate.
+                    assert!(fixed.0.unsigned_abs() >= i32::MAX as u32);
+                }
+                else {
+                    // Otherwise, allow for the rounding error of the float path.
+                    let tolerance = 2 + (f64::from(expected.0.unsigned_abs()) * 1e-6) as u32;
+                    assert!((expected.0 - fixed.0).unsigned_abs() <= tolerance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_requantize_mixed_bands() {
+        // MPEG1 at 44.1, 48, and 32 kHz, MPEG2 at 22.05, 24, and 16 kHz, and MPEG2.5 at 11.025,
+        // 12, and 8 kHz, layer 3 headers.
+        const HEADERS: [u32; 9] = [
+            0xfffb_9000,
+            0xfffb_9400,
+            0xfffb_9800,
+            0xfff3_9000,
+            0xfff3_9400,
+            0xfff3_9800,
+            0xffe3_9000,
+            0xffe3_9400,
+            0xffe3_9800,
+        ];
+
+        for (sample_rate_idx, &word) in HEADERS.iter().enumerate() {
+            let header = parse_frame_header(word).unwrap();
+            assert_eq!(header.sample_rate_idx, sample_rate_idx);
+
+            // A mixed block starts with the long bands spanning the first 36 samples (72 at 8 kHz),
+            // and continues with the short bands from the 4th onwards.
+            let n_long = if header.is_mpeg1() { 8 } else { 6 };
+            let split = SFB_LONG_BANDS[sample_rate_idx][n_long];
+
+            assert_eq!(split, SFB_SHORT_BANDS[sample_rate_idx][3 * 3]);
+            assert_eq!(split, if sample_rate_idx == 8 { 72 } else { 36 });
+
+            let mut channel = GranuleChannel {
+                block_type: BlockType::Short { is_mixed: true },
+                global_gain: 210,
+                scalefac_scale: true,
+                rzero: 576,
+                ..Default::default()
+            };
+
+            for (i, scalefac) in channel.scalefacs.iter_mut().enumerate() {
+                *scalefac = (i % 7) as u8 + 1;
+            }
+
+            let mut buf = [1.0f32; 576];
+            requantize(&header, &channel, &mut buf);
+
+            // With a global gain of 210, each sample is scaled by 2^-scalefac, where scalefac is
+            // the scale factor of the long band, or short band window, of the sample.
+            for (i, &sample) in buf.iter().enumerate() {
+                let sfi = if i < split {
+                    SFB_LONG_BANDS[sample_rate_idx].iter().rposition(|&start| start <= i).unwrap()
+                }
+                else {
+                    let win =
+                        SFB_SHORT_BANDS[sample_rate_idx].iter().rposition(|&start| start <= i);
+                    n_long + win.unwrap() - 3 * 3
+                };
+
+                let expected = (-f32::from(channel.scalefacs[sfi])).exp2();
+                assert_eq!(sample, expected, "sample {} at sample rate {}", i, sample_rate_idx);
+            }
+        }
+    }
+}
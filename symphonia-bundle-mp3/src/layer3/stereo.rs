@@ -8,13 +8,14 @@
 use symphonia_core::errors::{decode_error, Result};
 
 use crate::common::{ChannelMode, FrameHeader, Mode};
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+use crate::lanes::Lanes;
 
 use super::{common::*, Granule};
 
 use std::cmp::max;
-use std::{f32, f64};
-
-use lazy_static::lazy_static;
+use std::f32;
 
 /// The invalid intensity position for MPEG1 bitstreams.
 const INTENSITY_INV_POS_MPEG1: u8 = 7;
@@ -28,97 +29,108 @@ const INTENSITY_INV_POS_MPEG1: u8 = 7;
 /// to consider is_pos == 7 invalid for MPEG2 or 2.5.
 const INTENSITY_INV_POS_MPEG2: u8 = 31;
 
-lazy_static! {
-    /// (Left, right) channel coefficients for decoding intensity stereo in MPEG2 bitstreams.
-    ///
-    /// These coefficients are derived from section 2.4.3.2 of ISO/IEC 13818-3.
-    ///
-    /// As per the specification, for a given intensity position, is_pos (0 <= is_pos < 32), the
-    /// channel coefficients, k_l and k_r, may be calculated as per the table below:
-    ///
-    /// ```text
-    /// If...            | k_l                     | k_r
-    /// -----------------+-------------------------+-------------------
-    /// is_pos     == 0  | 1.0                     | 1.0
-    /// is_pos & 1 == 1  | i0 ^ [(is_pos + 1) / 2] | 1.0
-    /// is_pos & 1 == 0  | 1.0                     | i0 ^ (is_pos / 2)
-    /// ```
-    ///
-    /// The value of i0 is dependant on the least significant bit of scalefac_compress.
-    ///
-    /// ```text
-    /// scalefac_compress & 1 | i0
-    /// ----------------------+---------------------
-    /// 0                     | 1 / sqrt(sqrt(2.0))
-    /// 1                     | 1 / sqrt(2.0)
-    /// ```
-    ///
-    /// The first dimension of this table is indexed by scalefac_compress & 1 to select i0. The
-    /// second dimension is indexed by is_pos to obtain the channel coefficients. Note that
-    /// is_pos == 31 is considered an invalid position, but IS included in the table.
-    static ref INTENSITY_STEREO_RATIOS_MPEG2: [[(f32, f32); 32]; 2] = {
-        let is_scale: [f64; 2] = [
-            1.0 / f64::sqrt(f64::consts::SQRT_2),
-            f64::consts::FRAC_1_SQRT_2,
-        ];
-
-        let mut ratios = [[(0.0, 0.0); 32]; 2];
-
-        for (i, is_pos) in (0..32).enumerate() {
-            if is_pos & 1 != 0 {
-                // Odd case.
-                ratios[0][i] = (is_scale[0].powf(f64::from(is_pos + 1) / 2.0) as f32, 1.0);
-                ratios[1][i] = (is_scale[1].powf(f64::from(is_pos + 1) / 2.0) as f32, 1.0);
-            }
-            else {
-                // Even & zero case.
-                ratios[0][i] = (1.0, is_scale[0].powf(f64::from(is_pos) / 2.0) as f32);
-                ratios[1][i] = (1.0, is_scale[1].powf(f64::from(is_pos) / 2.0) as f32);
-            }
-        }
+/// (Left, right) channel coefficients for decoding intensity stereo in MPEG2 bitstreams.
+///
+/// These coefficients are derived from section 2.4.3.2 of ISO/IEC 13818-3.
+///
+/// As per the specification, for a given intensity position, is_pos (0 <= is_pos < 32), the
+/// channel coefficients, k_l and k_r, may be calculated as per the table below:
+///
+/// ```text
+/// If...            | k_l                     | k_r
+/// -----------------+-------------------------+-------------------
+/// is_pos     == 0  | 1.0                     | 1.0
+/// is_pos & 1 == 1  | i0 ^ [(is_pos + 1) / 2] | 1.0
+/// is_pos & 1 == 0  | 1.0                     | i0 ^ (is_pos / 2)
+/// ```
+///
+/// The value of i0 is dependant on the least significant bit of scalefac_compress.
+///
+/// ```text
+/// scalefac_compress & 1 | i0
+/// ----------------------+---------------------
+/// 0                     | 1 / sqrt(sqrt(2.0))
+/// 1                     | 1 / sqrt(2.0)
+/// ```
+///
+/// The first dimension of this table is indexed by scalefac_compress & 1 to select i0. The
+/// second dimension is indexed by is_pos to obtain the channel coefficients. Note that
+/// is_pos == 31 is considered an invalid position, but IS included in the table.
+static INTENSITY_STEREO_RATIOS_MPEG2: [[(f32, f32); 32]; 2] =
+    include!(concat!(env!("OUT_DIR"), "/intensity_stereo_ratios_mpeg2.rs"));
 
-        ratios
-    };
+/// (Left, right) channel coeffcients for decoding intensity stereo in MPEG1 bitstreams.
+///
+/// These coefficients are derived from section 2.4.3.4.9.3 of ISO/IEC 11172-3.
+///
+/// As per the specification, for a given intensity position, is_pos (0 <= is_pos < 7), a ratio,
+/// is_ratio, is calculated as follows:
+///
+/// ```text
+/// is_ratio = tan(is_pos * PI/12)
+/// ```
+///
+/// Then, the channel coefficients, k_l and k_r, are calculated as follows:
+///
+/// ```text
+/// k_l = is_ratio / (1 + is_ratio)
+/// k_r =        1 / (1 + is_ratio)
+/// ```
+///
+/// This table is indexed by is_pos. Note that is_pos == 7 is invalid and is NOT included in the
+/// table.
+static INTENSITY_STEREO_RATIOS_MPEG1: [(f32, f32); 7] =
+    include!(concat!(env!("OUT_DIR"), "/intensity_stereo_ratios_mpeg1.rs"));
+
+/// Fixed-point version of `INTENSITY_STEREO_RATIOS_MPEG2`.
+#[cfg(feature = "fixed-point")]
+static INTENSITY_STEREO_RATIOS_MPEG2_FIXED: [[(Fixed, Fixed); 32]; 2] =
+    include!(concat!(env!("OUT_DIR"), "/intensity_stereo_ratios_mpeg2_fixed.rs"));
+
+/// Fixed-point version of `INTENSITY_STEREO_RATIOS_MPEG1`.
+#[cfg(feature = "fixed-point")]
+static INTENSITY_STEREO_RATIOS_MPEG1_FIXED: [(Fixed, Fixed); 7] =
+    include!(concat!(env!("OUT_DIR"), "/intensity_stereo_ratios_mpeg1_fixed.rs"));
+
+/// A sample type that joint stereo decoding can be performed on.
+pub trait StereoSample: Lanes<Coeff = Self> + PartialEq + 'static {
+    /// The value of 1 / sqrt(2).
+    const FRAC_1_SQRT_2: Self;
+
+    /// Gets the intensity stereo ratios for MPEG1 bitstreams. See `INTENSITY_STEREO_RATIOS_MPEG1`.
+    fn intensity_ratios_mpeg1() -> &'static [(Self, Self); 7];
+
+    /// Gets the intensity stereo ratios for MPEG2 bitstreams. See `INTENSITY_STEREO_RATIOS_MPEG2`.
+    fn intensity_ratios_mpeg2() -> &'static [[(Self, Self); 32]; 2];
 }
 
-lazy_static! {
-    /// (Left, right) channel coeffcients for decoding intensity stereo in MPEG1 bitstreams.
-    ///
-    /// These coefficients are derived from section 2.4.3.4.9.3 of ISO/IEC 11172-3.
-    ///
-    /// As per the specification, for a given intensity position, is_pos (0 <= is_pos < 7), a ratio,
-    /// is_ratio, is calculated as follows:
-    ///
-    /// ```text
-    /// is_ratio = tan(is_pos * PI/12)
-    /// ```
-    ///
-    /// Then, the channel coefficients, k_l and k_r, are calculated as follows:
-    ///
-    /// ```text
-    /// k_l = is_ratio / (1 + is_ratio)
-    /// k_r =        1 / (1 + is_ratio)
-    /// ```
-    ///
-    /// This table is indexed by is_pos. Note that is_pos == 7 is invalid and is NOT included in the
-    /// table.
-    static ref INTENSITY_STEREO_RATIOS_MPEG1: [(f32, f32); 7] = {
-        const PI_12: f64 = f64::consts::PI / 12.0;
-
-        let mut ratios = [(0.0, 0.0); 7];
-
-        for (is_pos, ratio) in ratios.iter_mut().enumerate() {
-            let is_ratio = (PI_12 * is_pos as f64).tan();
-            *ratio = (
-                (is_ratio / (1.0 + is_ratio)) as f32,
-                (1.0 / (1.0 + is_ratio)) as f32
-            );
-        }
+impl StereoSample for f32 {
+    const FRAC_1_SQRT_2: f32 = f32::consts::FRAC_1_SQRT_2;
 
-        ratios[6] = (1.0, 0.0);
+    #[inline(always)]
+    fn intensity_ratios_mpeg1() -> &'static [(Self, Self); 7] {
+        &INTENSITY_STEREO_RATIOS_MPEG1
+    }
 
-        ratios
-    };
+    #[inline(always)]
+    fn intensity_ratios_mpeg2() -> &'static [[(Self, Self); 32]; 2] {
+        &INTENSITY_STEREO_RATIOS_MPEG2
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl StereoSample for Fixed {
+    const FRAC_1_SQRT_2: Fixed = fixed_const!(std::f64::consts::FRAC_1_SQRT_2);
+
+    #[inline(always)]
+    fn intensity_ratios_mpeg1() -> &'static [(Self, Self); 7] {
+        &INTENSITY_STEREO_RATIOS_MPEG1_FIXED
+    }
+
+    #[inline(always)]
+    fn intensity_ratios_mpeg2() -> &'static [[(Self, Self); 32]; 2] {
+        &INTENSITY_STEREO_RATIOS_MPEG2_FIXED
+    }
 }
 
 /// Decorrelates mid and side channels into left and right channels.
@@ -140,12 +152,12 @@ lazy_static! {
 /// In the bitstream, m[i] is transmitted in channel 0, while s[i] in channel 1. After decoding,
 /// the left channel replaces m[i] in channel 0, and the right channel replaces s[i] in channel
 /// 1.
-fn process_mid_side(mid: &mut [f32], side: &mut [f32]) {
+fn process_mid_side<S: StereoSample>(mid: &mut [S], side: &mut [S]) {
     debug_assert!(mid.len() == side.len());
 
     for (m, s) in mid.iter_mut().zip(side) {
-        let left = (*m + *s) * f32::consts::FRAC_1_SQRT_2;
-        let right = (*m - *s) * f32::consts::FRAC_1_SQRT_2;
+        let left = (*m + *s) * S::FRAC_1_SQRT_2;
+        let right = (*m - *s) * S::FRAC_1_SQRT_2;
         *m = left;
         *s = right;
     }
@@ -165,21 +177,21 @@ fn process_mid_side(mid: &mut [f32], side: &mut [f32]) {
 ///      l[i], and r[i] are the left and right channels, respectively.
 ///      ch0[i] is the intensity stereo coded signal found in channel 0.
 ///      k_l, and k_r are the left and right channel ratios, respectively.
-fn process_intensity(
+fn process_intensity<S: StereoSample>(
     intensity_pos: u8,
-    intensity_table: &[(f32, f32)],
+    intensity_table: &[(S, S)],
     intensity_max: u8,
     mid_side: bool,
-    ch0: &mut [f32],
-    ch1: &mut [f32],
+    ch0: &mut [S],
+    ch1: &mut [S],
 ) {
     if intensity_pos < intensity_max {
         let (ratio_l, ratio_r) = intensity_table[usize::from(intensity_pos)];
 
         for (l, r) in ch0.iter_mut().zip(ch1) {
             let is = *l;
-            *l = ratio_l * is;
-            *r = ratio_r * is;
+            *l = is * ratio_l;
+            *r = is * ratio_r;
         }
     }
     else if mid_side {
@@ -189,19 +201,19 @@ fn process_intensity(
 
 /// Determines if a band is zeroed.
 #[inline(always)]
-fn is_zero_band(band: &[f32]) -> bool {
-    !band.iter().any(|&x| x != 0.0)
+fn is_zero_band<S: StereoSample>(band: &[S]) -> bool {
+    !band.iter().any(|&x| x != S::default())
 }
 
 /// Decodes all intensity stereo coded bands within an entire long block and returns the intensity
 /// bound.
-fn process_intensity_long_block(
+fn process_intensity_long_block<S: StereoSample>(
     header: &FrameHeader,
     granule: &Granule,
     mid_side: bool,
     max_bound: usize,
-    ch0: &mut [f32; 576],
-    ch1: &mut [f32; 576],
+    ch0: &mut [S; 576],
+    ch1: &mut [S; 576],
 ) -> usize {
     // As per ISO/IEC 11172-3 and ISO/IEC 13818-3, for long blocks that have intensity stereo
     // coding enabled, all bands starting after the last non-zero band in channel 1 may be
@@ -214,11 +226,11 @@ fn process_intensity_long_block(
 
     // Select the intensity stereo ratios table.
     let (is_table, is_inv_pos) = if header.is_mpeg1() {
-        (&INTENSITY_STEREO_RATIOS_MPEG1[..], INTENSITY_INV_POS_MPEG1)
+        (&S::intensity_ratios_mpeg1()[..], INTENSITY_INV_POS_MPEG1)
     }
     else {
         let is_scale = granule.channels[1].scalefac_compress & 1;
-        (&INTENSITY_STEREO_RATIOS_MPEG2[usize::from(is_scale)][..], INTENSITY_INV_POS_MPEG2)
+        (&S::intensity_ratios_mpeg2()[usize::from(is_scale)][..], INTENSITY_INV_POS_MPEG2)
     };
 
     let bands = &SFB_LONG_BANDS[header.sample_rate_idx];
@@ -262,14 +274,14 @@ fn process_intensity_long_block(
 
 /// Decodes all intensity stereo coded bands within an entire short block and returns the intensity
 /// bound.
-fn process_intensity_short_block(
+fn process_intensity_short_block<S: StereoSample>(
     header: &FrameHeader,
     granule: &Granule,
     is_mixed: bool,
     mid_side: bool,
     max_bound: usize,
-    ch0: &mut [f32; 576],
-    ch1: &mut [f32; 576],
+    ch0: &mut [S; 576],
+    ch1: &mut [S; 576],
 ) -> usize {
     // For short, non-mixed, blocks, each band is composed of 3 windows (windows 0 thru 2). Windows
     // are interleaved in each band.
@@ -340,11 +352,11 @@ fn process_intensity_short_block(
 
     // Select the intensity stereo ratios table based on the bitstream version.
     let (is_table, is_inv_pos) = if header.is_mpeg1() {
-        (&INTENSITY_STEREO_RATIOS_MPEG1[..], INTENSITY_INV_POS_MPEG1)
+        (&S::intensity_ratios_mpeg1()[..], INTENSITY_INV_POS_MPEG1)
     }
     else {
         let is_scale = granule.channels[1].scalefac_compress & 1;
-        (&INTENSITY_STEREO_RATIOS_MPEG2[usize::from(is_scale)][..], INTENSITY_INV_POS_MPEG2)
+        (&S::intensity_ratios_mpeg2()[usize::from(is_scale)][..], INTENSITY_INV_POS_MPEG2)
     };
 
     // The intensity position for the final band (last three short windows) is not coded and is
@@ -482,10 +494,10 @@ fn process_intensity_short_block(
 }
 
 /// Perform joint stereo decoding on the channel pair.
-pub(super) fn stereo(
+pub(super) fn stereo<S: StereoSample>(
     header: &FrameHeader,
     granule: &mut Granule,
-    ch: &mut [[f32; 576]; 2],
+    ch: &mut [[S; 576]; 2],
 ) -> Result<()> {
     // Determine whether mid-side, and/or intensity stereo coding is used.
     let (mid_side, intensity) = match header.channel_mode {
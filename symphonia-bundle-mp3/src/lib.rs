@@ -18,13 +18,23 @@
 mod common;
 mod header;
 
-// Demuxer module.
+// Demuxer modules.
 mod demuxer;
+mod icy;
 
 // Decoder modules.
+#[cfg(feature = "fixed-point")]
+#[macro_use]
+mod fixed;
 #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
 mod decoder;
 #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+mod lanes;
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+mod parallel;
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+mod state;
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
 mod synthesis;
 
 // Shared layer 1 & 2 decoder support module.
@@ -39,9 +49,15 @@ mod layer2;
 #[cfg(feature = "mp3")]
 mod layer3;
 
+pub use common::{ChannelMode, Emphasis, FrameHeader, Mode, MpegLayer, MpegVersion};
+#[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
+pub use decoder::{CrcPolicy, MpaDecoder, MpaDecoderOptions, MpaDecoderStats};
+#[cfg(feature = "fixed-point")]
+pub use decoder::{FixedOutputSample, Mp3FixedDecoder};
+pub use demuxer::{MpaFrame, MpaFrames, MpaReader, MpaReaderOptions};
+pub use header::{parse_frame_header, read_frame_header, MAX_MPEG_FRAME_SIZE, MPEG_HEADER_LEN};
 #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
-pub use decoder::MpaDecoder;
-pub use demuxer::MpaReader;
+pub use parallel::{decode_parallel, ParallelDecodeOptions};
 
 // For SemVer compatibility in v0.5.x series.
 #[deprecated = "use `symphonia_bundle_mp3::MpaDecoder` instead"]
@@ -0,0 +1,475 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Offline decoding of an entire MPEG audio stream using multiple threads.
+//!
+//! The stream is partitioned into segments at frame boundaries. Since a layer 3 frame may use main
+//! data from up-to 511 bytes of previous frames (the bit reservoir), and the hybrid and polyphase
+//! synthesis carry state from one granule to the next, each segment is decoded starting a few
+//! frames before its first frame. The decoder state has fully converged by the first frame of the
+//! segment, and therefore the audio decoded from each segment is identical to the audio that would
+//! have been decoded sequentially.
+
+use std::io::{Cursor, Seek, SeekFrom};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use symphonia_core::audio::{AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecParameters, Decoder};
+use symphonia_core::errors::{unsupported_error, Error, Result};
+use symphonia_core::formats::{FormatReader, Packet};
+use symphonia_core::io::{MediaSourceStream, ReadBytes};
+use symphonia_core::sample::SampleFormat;
+
+use log::warn;
+
+use crate::common::FrameHeader;
+use crate::decoder::{MpaDecoder, MpaDecoderOptions};
+use crate::demuxer::{read_mpeg_frame_strict, MpaReader};
+use crate::header::MPEG_HEADER_LEN;
+
+/// The maximum number of bytes of main data a layer 3 frame may use from previous frames.
+const MAX_MAIN_DATA_BEGIN: usize = 511;
+
+/// The number of frames, with all their main data available, that are decoded before the first
+/// frame of a segment. The first such frame restores the IMDCT overlap, and the second restores
+/// the polyphase synthesis state (of MPEG2 and MPEG2.5 frames with a single granule).
+const NUM_CONVERGED_FRAMES: usize = 2;
+
+/// The minimum length of a segment in bytes. Shorter segments would spend a significant amount of
+/// time decoding frames that are discarded.
+const MIN_SEGMENT_LEN: u64 = 16 * 1024;
+
+/// The number of segments per thread. More segments than threads balances the work of each thread
+/// if some segments take longer to decode.
+const SEGMENTS_PER_THREAD: usize = 2;
+
+/// Options for [`decode_parallel`].
+#[derive(Copy, Clone, Debug)]
+pub struct ParallelDecodeOptions {
+    /// The number of threads used to decode. Default: `4`.
+    pub n_threads: usize,
+    /// Trim the encoder delay and padding from the decoded audio, if known. Default: `true`.
+    pub enable_gapless: bool,
+    /// The options of the decoder of each segment. Only `SampleFormat::F32` is supported. Default:
+    /// `MpaDecoderOptions::default()`.
+    pub decoder: MpaDecoderOptions,
+}
+
+impl Default for ParallelDecodeOptions {
+    fn default() -> Self {
+        ParallelDecodeOptions { n_threads: 4, enable_gapless: true, decoder: Default::default() }
+    }
+}
+
+/// A segment of the stream.
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    /// The position of the frame decoding starts at.
+    prime_pos: u64,
+    /// The position of the first frame of the segment.
+    start_pos: u64,
+    /// The position of the end of the segment.
+    end_pos: u64,
+}
+
+/// The audio decoded from a segment.
+struct DecodedSegment {
+    spec: Option<SignalSpec>,
+    planes: Vec<Vec<f32>>,
+}
+
+/// Decodes the entire MPEG audio stream in `data` using multiple threads, and returns the decoded
+/// audio.
+///
+/// The stream is partitioned using the table of contents of the Xing tag, if present, such that
+/// each segment has roughly the same duration. Otherwise, the stream is partitioned into segments
+/// of equal length. Frames that fail to decode are skipped, as they would be by a typical
+/// sequential decode loop.
+pub fn decode_parallel(
+    data: Arc<[u8]>,
+    options: &ParallelDecodeOptions,
+) -> Result<AudioBuffer<f32>> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(data.clone())), Default::default());
+
+    let reader = MpaReader::try_new(source, &Default::default())?;
+
+    let params = reader.tracks()[0].codec_params.clone();
+
+    if options.decoder.sample_format != SampleFormat::F32 {
+        return unsupported_error("mpa: parallel decoding only outputs f32 samples");
+    }
+
+    // The decoder is instantiated to validate the options, and to get the output parameters.
+    let out_params =
+        MpaDecoder::try_new_with_options(&params, &options.decoder)?.codec_params().clone();
+
+    let (first_pos, end_pos) = reader.audio_span();
+    let toc = reader.xing_toc().copied();
+
+    let mut source = Box::new(reader).into_inner();
+
+    let span_end = end_pos.unwrap_or(data.len() as u64);
+    let span_len = span_end.saturating_sub(first_pos);
+
+    let n_threads = options.n_threads.max(1);
+
+    let n_segments = if n_threads > 1 {
+        (n_threads * SEGMENTS_PER_THREAD).min((span_len / MIN_SEGMENT_LEN).max(1) as usize)
+    }
+    else {
+        1
+    };
+
+    // Find the segment boundaries.
+    let mut segments =
+        vec![Segment { prime_pos: first_pos, start_pos: first_pos, end_pos: u64::MAX }];
+
+    for k in 1..n_segments {
+        // The approximate position of the segment.
+        let pos = match &toc {
+            Some(toc) => {
+                let entry = u64::from(toc[100 * k / n_segments]);
+                first_pos + entry * span_len / 256
+            }
+            None => first_pos + span_len * k as u64 / n_segments as u64,
+        };
+
+        let (prime_pos, start_pos) = match find_boundary(&mut source, pos)? {
+            Some(boundary) => boundary,
+            None => break,
+        };
+
+        let last = segments.last_mut().unwrap();
+
+        // Positions from the table of contents are not guaranteed to be monotonic.
+        if start_pos <= last.start_pos || start_pos >= span_end {
+            continue;
+        }
+
+        last.end_pos = start_pos;
+
+        segments.push(Segment { prime_pos, start_pos, end_pos: u64::MAX });
+    }
+
+    // Audio data ends where the trailing tags begin.
+    if let Some(end_pos) = end_pos {
+        segments.last_mut().unwrap().end_pos = end_pos;
+    }
+
+    let decoded = decode_segments(data, &params, &options.decoder, &segments, n_threads)?;
+
+    // Stitch the decoded segments together.
+    let spec = decoded.iter().find_map(|segment| segment.spec).unwrap_or_else(|| {
+        SignalSpec::new(out_params.sample_rate.unwrap_or(0), out_params.channels.unwrap())
+    });
+
+    let n_frames: usize =
+        decoded.iter().map(|segment| segment.planes.first().map_or(0, |plane| plane.len())).sum();
+
+    let (trim_start, trim_end) = if options.enable_gapless {
+        (params.delay.unwrap_or(0) as usize, params.padding.unwrap_or(0) as usize)
+    }
+    else {
+        (0, 0)
+    };
+
+    let len = n_frames.saturating_sub(trim_start + trim_end);
+
+    let mut out = AudioBuffer::new(len as u64, spec);
+    out.render_reserved(Some(len));
+
+    for ch in 0..spec.channels.count() {
+        let samples = decoded
+            .iter()
+            .flat_map(|segment| segment.planes.get(ch).into_iter().flatten())
+            .skip(trim_start)
+            .take(len);
+
+        for (out, &sample) in out.chan_mut(ch).iter_mut().zip(samples) {
+            *out = sample;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Resynchronizes `source` at `pos`, and returns the position of the first frame found, and the
+/// position of the first frame the decoder state will have converged at if decoding starts at the
+/// first frame. Returns `None` if the end of the stream is reached first.
+fn find_boundary(source: &mut MediaSourceStream, pos: u64) -> Result<Option<(u64, u64)>> {
+    source.seek(SeekFrom::Start(pos))?;
+
+    let mut prime_pos = None;
+    let mut last_header: Option<FrameHeader> = None;
+
+    // The number of bytes of main data buffered by the frames before the current frame.
+    let mut buffered = 0;
+    let mut n_converged = 0;
+
+    loop {
+        let (header, packet) = match read_mpeg_frame_strict(source, last_header.as_ref(), u64::MAX)
+        {
+            Ok(frame) => frame,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(None)
+            }
+            Err(err) => return Err(err),
+        };
+
+        let frame_pos = source.pos() - packet.len() as u64;
+
+        // If all the main data the frame may use is buffered, the frame decodes correctly.
+        if buffered >= MAX_MAIN_DATA_BEGIN {
+            if n_converged == NUM_CONVERGED_FRAMES {
+                return Ok(prime_pos.map(|prime_pos| (prime_pos, frame_pos)));
+            }
+
+            n_converged += 1;
+        }
+
+        prime_pos.get_or_insert(frame_pos);
+
+        let overhead =
+            MPEG_HEADER_LEN + header.side_info_len() + if header.has_crc { 2 } else { 0 };
+
+        buffered += packet.len().saturating_sub(overhead);
+        last_header = Some(header);
+    }
+}
+
+/// Decodes `segments` using `n_threads` threads, and returns the decoded audio of each segment.
+fn decode_segments(
+    data: Arc<[u8]>,
+    params: &CodecParameters,
+    options: &MpaDecoderOptions,
+    segments: &[Segment],
+    n_threads: usize,
+) -> Result<Vec<DecodedSegment>> {
+    let segments: Arc<[Segment]> = segments.into();
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let (sender, receiver) = mpsc::channel();
+
+    let mut threads = Vec::new();
+
+    for _ in 0..n_threads.min(segments.len()) {
+        let data = data.clone();
+        let params = params.clone();
+        let options = *options;
+        let segments = segments.clone();
+        let next = next.clone();
+        let sender = sender.clone();
+
+        threads.push(thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+
+            if index >= segments.len() {
+                break;
+            }
+
+            let result = decode_segment(data.clone(), &params, &options, &segments[index]);
+            let failed = result.is_err();
+
+            // Stop if the segment failed to decode, or the receiver is gone.
+            if sender.send((index, result)).is_err() || failed {
+                break;
+            }
+        }));
+    }
+
+    drop(sender);
+
+    let mut decoded: Vec<Option<DecodedSegment>> = segments.iter().map(|_| None).collect();
+
+    for (index, result) in receiver.iter() {
+        decoded[index] = Some(result?);
+    }
+
+    for thread in threads {
+        if thread.join().is_err() {
+            return unsupported_error("mpa: decode thread panicked");
+        }
+    }
+
+    // Every segment was decoded if no error occured.
+    Ok(decoded.into_iter().map(|segment| segment.unwrap()).collect())
+}
+
+/// Decodes `segment` of the stream in `data`.
+fn decode_segment(
+    data: Arc<[u8]>,
+    params: &CodecParameters,
+    options: &MpaDecoderOptions,
+    segment: &Segment,
+) -> Result<DecodedSegment> {
+    let mut source = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+    source.seek(SeekFrom::Start(segment.prime_pos))?;
+
+    let mut decoder = MpaDecoder::try_new_with_options(params, options)?;
+
+    let mut decoded = DecodedSegment { spec: None, planes: Vec::new() };
+    let mut last_header: Option<FrameHeader> = None;
+
+    loop {
+        let (header, packet) =
+            match read_mpeg_frame_strict(&mut source, last_header.as_ref(), u64::MAX) {
+                Ok(frame) => frame,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    break
+                }
+                Err(err) => return Err(err),
+            };
+
+        // The segment ends at the first frame that does not end within it.
+        if source.pos() > segment.end_pos {
+            break;
+        }
+
+        let frame_pos = source.pos() - packet.len() as u64;
+
+        last_header = Some(header);
+
+        let packet = Packet::new_from_boxed_slice(0, 0, header.duration(), packet.into());
+
+        let buf = match decoder.decode(&packet) {
+            Ok(AudioBufferRef::F32(buf)) => buf,
+            Ok(_) => unreachable!(),
+            Err(Error::DecodeError(err)) => {
+                warn!("decode error: {}", err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Discard the audio decoded while the decoder state converges.
+        if frame_pos < segment.start_pos {
+            continue;
+        }
+
+        match decoded.spec {
+            None => {
+                decoded.spec = Some(*buf.spec());
+                decoded.planes = vec![Vec::new(); buf.spec().channels.count()];
+            }
+            Some(spec) if spec != *buf.spec() => {
+                return unsupported_error("mpa: signal specification changed");
+            }
+            _ => (),
+        }
+
+        for (ch, plane) in decoded.planes.iter_mut().enumerate() {
+            plane.extend_from_slice(buf.chan(ch));
+        }
+    }
+
+    Ok(decoded)
+}
+
+// The tests decode a layer 3 stream.
+#[cfg(all(test, feature = "mp3"))]
+mod tests {
+    use std::sync::Arc;
+
+    use symphonia_core::audio::{AudioBufferRef, Signal};
+    use symphonia_core::codecs::Decoder;
+    use symphonia_core::formats::{FormatOptions, FormatReader};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::{decode_parallel, ParallelDecodeOptions};
+    use crate::decoder::{make_test_frame, TestGranule};
+    use crate::{MpaDecoder, MpaReader};
+
+    /// Makes a stream of MPEG1 layer 3, 128 kbps, 44.1 kHz, mono frames that decode to non-silent
+    /// audio. Each frame has a different global gain.
+    fn make_stream(n_frames: usize, with_xing: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        if with_xing {
+            // A Xing tag with a table of contents that places the midpoint of the stream at the
+            // first quarter of the stream.
+            let mut frame = vec![0xff, 0xfb, 0x90, 0xc4];
+            frame.resize(4 + 17, 0);
+            frame.extend_from_slice(b"Xing");
+            frame.extend_from_slice(&5u32.to_be_bytes());
+            frame.extend_from_slice(&(n_frames as u32).to_be_bytes());
+            frame.extend((0..100u8).map(|i| {
+                if i < 50 {
+                    i
+                }
+                else {
+                    64 + 3 * (i - 50)
+                }
+            }));
+            frame.resize(417, 0);
+
+            buf.extend_from_slice(&frame);
+        }
+
+        for i in 0..n_frames {
+            // All zero bits decode to quadruples of non-zero spectral samples in count1 table B.
+            let granule = || TestGranule {
+                part2_3_length: 100,
+                global_gain: 120 + (i % 64) as u32,
+                count1table_select: true,
+                ..Default::default()
+            };
+
+            buf.extend_from_slice(&make_test_frame(0, 0, [granule(), granule()], &[]));
+        }
+
+        buf
+    }
+
+    /// Decodes the stream sequentially.
+    fn decode_sequential(data: &[u8]) -> Vec<f32> {
+        let mss = MediaSourceStream::new(
+            Box::new(std::io::Cursor::new(data.to_vec())),
+            Default::default(),
+        );
+
+        let options = FormatOptions { enable_gapless: false, ..Default::default() };
+        let mut reader = MpaReader::try_new(mss, &options).unwrap();
+
+        let mut decoder =
+            MpaDecoder::try_new(&reader.tracks()[0].codec_params, &Default::default()).unwrap();
+
+        let mut samples = Vec::new();
+
+        while let Ok(packet) = reader.next_packet() {
+            match decoder.decode(&packet).unwrap() {
+                AudioBufferRef::F32(buf) => samples.extend_from_slice(buf.chan(0)),
+                _ => unreachable!(),
+            }
+        }
+
+        samples
+    }
+
+    #[test]
+    fn verify_decode_parallel() {
+        for &with_xing in &[false, true] {
+            let data = make_stream(400, with_xing);
+
+            let expected = decode_sequential(&data);
+            assert_eq!(expected.len(), 400 * 1152);
+            assert!(expected.iter().any(|&s| s != 0.0));
+
+            let data: Arc<[u8]> = data.into();
+
+            for &n_threads in &[1, 3, 4] {
+                let options = ParallelDecodeOptions { n_threads, ..Default::default() };
+
+                let decoded = decode_parallel(data.clone(), &options).unwrap();
+
+                assert_eq!(decoded.spec().rate, 44_100);
+                assert_eq!(decoded.chan(0), &expected[..]);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `state` module provides support for saving and restoring the state of the decoders.
+//!
+//! All values are stored little-endian.
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::{BufReader, ReadBytes};
+
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+
+/// A sample type that can be saved in, and restored from, a decoder state.
+pub trait StateSample: Copy {
+    /// A unique identifier of the sample type. A state saved with one sample type can not be
+    /// restored with another.
+    #[cfg(feature = "mp3")]
+    const ID: u8;
+
+    /// Appends the sample to `buf`.
+    fn save(self, buf: &mut Vec<u8>);
+
+    /// Reads a sample from `reader`.
+    fn restore(reader: &mut BufReader<'_>) -> Result<Self>;
+}
+
+impl StateSample for f32 {
+    #[cfg(feature = "mp3")]
+    const ID: u8 = 0;
+
+    fn save(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn restore(reader: &mut BufReader<'_>) -> Result<Self> {
+        Ok(reader.read_f32()?)
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl StateSample for Fixed {
+    const ID: u8 = 1;
+
+    fn save(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0.to_le_bytes());
+    }
+
+    fn restore(reader: &mut BufReader<'_>) -> Result<Self> {
+        Ok(Fixed(reader.read_i32()?))
+    }
+}
+
+/// Appends `samples` to `buf`.
+pub fn save_samples<S: StateSample>(samples: &[S], buf: &mut Vec<u8>) {
+    for &sample in samples {
+        sample.save(buf);
+    }
+}
+
+/// Reads `samples` from `reader`.
+pub fn restore_samples<S: StateSample>(
+    samples: &mut [S],
+    reader: &mut BufReader<'_>,
+) -> Result<()> {
+    for sample in samples {
+        *sample = S::restore(reader)?;
+    }
+    Ok(())
+}
@@ -7,6 +7,16 @@
 
 //! The `synthesis` module implements the polyphase synthesis filterbank of the MPEG audio standard.
 
+use symphonia_core::codecs::DualMonoPolicy;
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+use crate::lanes::Lanes;
+use crate::state::{restore_samples, save_samples, StateSample};
+
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+
 /// Synthesis window D[i], defined in Table B.3 of ISO/IEC 11172-3.
 #[allow(clippy::unreadable_literal)]
 #[rustfmt::skip]
@@ -141,23 +151,249 @@ const SYNTHESIS_D: [f32; 512] = [
      0.000015259,  0.000015259,  0.000015259,  0.000015259,
 ];
 
+/// The following tables are pre-computed values of the the following equation, used by `dct32`:
+///
+/// ```text
+/// c[i] = 1.0 / [2.0 * cos((PI / N) * (2*i + 1))]    for i = 0..N/2
+/// ```
+///
+/// where N = [32, 16, 8, 4, 2], for DCT32_COS_16, DCT32_COS_8, DCT32_COS_4,
+/// and DCT32_COS_2, respectively.
+const DCT32_COS_16: [f32; 16] = [
+    0.500_602_998_235_196_3,  // i= 0
+    0.505_470_959_897_543_6,  // i= 1
+    0.515_447_309_922_624_6,  // i= 2
+    0.531_042_591_089_784_1,  // i= 3
+    0.553_103_896_034_444_5,  // i= 4
+    0.582_934_968_206_133_9,  // i= 5
+    0.622_504_123_035_664_8,  // i= 6
+    0.674_808_341_455_005_7,  // i= 7
+    0.744_536_271_002_298_6,  // i= 8
+    0.839_349_645_415_526_8,  // i= 9
+    0.972_568_237_861_960_8,  // i=10
+    1.169_439_933_432_884_7,  // i=11
+    1.484_164_616_314_166_2,  // i=12
+    2.057_781_009_953_410_8,  // i=13
+    3.407_608_418_468_719_0,  // i=14
+    10.190_008_123_548_032_9, // i=15
+];
+
+const DCT32_COS_8: [f32; 8] = [
+    0.502_419_286_188_155_7, // i=0
+    0.522_498_614_939_688_9, // i=1
+    0.566_944_034_816_357_7, // i=2
+    0.646_821_783_359_990_1, // i=3
+    0.788_154_623_451_250_2, // i=4
+    1.060_677_685_990_347_1, // i=5
+    1.722_447_098_238_334_2, // i=6
+    5.101_148_618_689_155_3, // i=7
+];
+
+const DCT32_COS_4: [f32; 4] = [
+    0.509_795_579_104_159_2, // i=0
+    0.601_344_886_935_045_3, // i=1
+    0.899_976_223_136_415_6, // i=2
+    2.562_915_447_741_505_5, // i=3
+];
+
+const DCT32_COS_2: [f32; 2] = [
+    0.541_196_100_146_197_0, // i=0
+    1.306_562_964_876_376_4, // i=1
+];
+
+const DCT32_COS_1: f32 = 0.707_106_781_186_547_5;
+
+/// A sample type the polyphase synthesis filterbank can operate on.
+pub trait SynthesisSample: Lanes<Coeff = Self> + 'static {
+    /// The coefficients of `DCT32_COS_16`.
+    const DCT32_COS_16: [Self; 16];
+    /// The coefficients of `DCT32_COS_8`.
+    const DCT32_COS_8: [Self; 8];
+    /// The coefficients of `DCT32_COS_4`.
+    const DCT32_COS_4: [Self; 4];
+    /// The coefficients of `DCT32_COS_2`.
+    const DCT32_COS_2: [Self; 2];
+    /// The coefficient `DCT32_COS_1`.
+    const DCT32_COS_1: Self;
+
+    /// Gets the synthesis window, `SYNTHESIS_D`.
+    fn synthesis_window() -> &'static [Self; 512];
+}
+
+impl SynthesisSample for f32 {
+    const DCT32_COS_16: [f32; 16] = DCT32_COS_16;
+    const DCT32_COS_8: [f32; 8] = DCT32_COS_8;
+    const DCT32_COS_4: [f32; 4] = DCT32_COS_4;
+    const DCT32_COS_2: [f32; 2] = DCT32_COS_2;
+    const DCT32_COS_1: f32 = DCT32_COS_1;
+
+    #[inline(always)]
+    fn synthesis_window() -> &'static [f32; 512] {
+        &SYNTHESIS_D
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+static SYNTHESIS_D_FIXED: [Fixed; 512] = fixed_const_array!(SYNTHESIS_D, 512);
+
+#[cfg(feature = "fixed-point")]
+impl SynthesisSample for Fixed {
+    const DCT32_COS_16: [Fixed; 16] = fixed_const_array!(DCT32_COS_16, 16);
+    const DCT32_COS_8: [Fixed; 8] = fixed_const_array!(DCT32_COS_8, 8);
+    const DCT32_COS_4: [Fixed; 4] = fixed_const_array!(DCT32_COS_4, 4);
+    const DCT32_COS_2: [Fixed; 2] = fixed_const_array!(DCT32_COS_2, 2);
+    const DCT32_COS_1: Fixed = fixed_const!(DCT32_COS_1);
+
+    #[inline(always)]
+    fn synthesis_window() -> &'static [Fixed; 512] {
+        &SYNTHESIS_D_FIXED
+    }
+}
+
+/// A PCM sample type the output of the polyphase synthesis filterbank can be converted into.
+pub trait FromSynthesized<S> {
+    /// Converts, and clamps, a synthesized sample into a PCM sample.
+    fn from_synthesized(sample: S) -> Self;
+}
+
+impl FromSynthesized<f32> for f32 {
+    #[inline(always)]
+    fn from_synthesized(sample: f32) -> Self {
+        sample.clamp(-1.0, 1.0)
+    }
+}
+
+impl FromSynthesized<f32> for i16 {
+    #[inline(always)]
+    fn from_synthesized(sample: f32) -> Self {
+        // Identical to the f32 to i16 sample conversion in symphonia-core.
+        (sample.clamp(-1.0, 1.0) * 32_768.0) as i16
+    }
+}
+
+impl FromSynthesized<f32> for i32 {
+    #[inline(always)]
+    fn from_synthesized(sample: f32) -> Self {
+        // Identical to the f32 to i32 sample conversion in symphonia-core.
+        (f64::from(sample.clamp(-1.0, 1.0)) * 2_147_483_648.0) as i32
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl FromSynthesized<Fixed> for i16 {
+    #[inline(always)]
+    fn from_synthesized(sample: Fixed) -> Self {
+        // Round to the nearest 16-bit sample by removing all but 15 fractional bits.
+        const SHIFT: u32 = Fixed::FRAC_BITS - 15;
+        let rounded = sample.0.saturating_add(1 << (SHIFT - 1)) >> SHIFT;
+        rounded.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl FromSynthesized<Fixed> for i32 {
+    #[inline(always)]
+    fn from_synthesized(sample: Fixed) -> Self {
+        // Scale to 31 fractional bits.
+        let scaled = i64::from(sample.0) << (31 - Fixed::FRAC_BITS);
+        scaled.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+    }
+}
+
 /// `SynthesisState` maintains the persistant state of sub-band synthesis.
-pub struct SynthesisState {
-    v_vec: [[f32; 64]; 16],
+pub struct SynthesisState<S = f32> {
+    v_vec: [[S; 64]; 16],
     v_front: usize,
 }
 
-impl Default for SynthesisState {
+impl<S: SynthesisSample> Default for SynthesisState<S> {
     fn default() -> Self {
-        SynthesisState { v_vec: [[0f32; 64]; 16], v_front: 0 }
+        SynthesisState { v_vec: [[S::default(); 64]; 16], v_front: 0 }
+    }
+}
+
+impl<S: SynthesisSample> SynthesisState<S> {
+    /// Clears the synthesis delay line.
+    pub fn reset(&mut self) {
+        for v in self.v_vec.iter_mut() {
+            v.fill(S::default());
+        }
+        self.v_front = 0;
+    }
+}
+
+impl<S: SynthesisSample + StateSample> SynthesisState<S> {
+    /// Appends the synthesis state to `buf`.
+    pub fn save(&self, buf: &mut Vec<u8>) {
+        buf.push(self.v_front as u8);
+
+        for v in self.v_vec.iter() {
+            save_samples(v, buf);
+        }
+    }
+
+    /// Restores a synthesis state previously saved by `save` from `reader`.
+    pub fn restore(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        let v_front = usize::from(reader.read_u8()?);
+
+        if v_front >= self.v_vec.len() {
+            return decode_error("mpa: invalid synthesis state");
+        }
+
+        self.v_front = v_front;
+
+        for v in self.v_vec.iter_mut() {
+            restore_samples(v, reader)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Downmixes the sub-band samples of a stereo frame to mono by averaging the sub-band samples of
+/// the right channel, `right`, into those of the left channel, `left`.
+///
+/// Since sub-band synthesis is linear, this is equivalent to averaging the synthesized PCM samples
+/// of both channels, but only requires one channel to be synthesized.
+pub fn downmix_to_mono<S: SynthesisSample>(left: &mut [S], right: &[S]) {
+    for (l, &r) in left.iter_mut().zip(right) {
+        *l = l.half() + r.half();
+    }
+}
+
+/// Applies `policy` to the sub-band samples of a dual mono frame, wherein `left` and `right` are
+/// the sub-band samples of the first and second programs, respectively.
+pub fn select_dual_mono<S: SynthesisSample>(
+    left: &mut [S],
+    right: &mut [S],
+    policy: DualMonoPolicy,
+) {
+    match policy {
+        DualMonoPolicy::Both => (),
+        DualMonoPolicy::Left => right.copy_from_slice(left),
+        DualMonoPolicy::Right => left.copy_from_slice(right),
+        DualMonoPolicy::Mix => {
+            downmix_to_mono(left, right);
+            right.copy_from_slice(left);
+        }
     }
 }
 
 /// Sub-band synthesis transforms 32 sub-band blocks containing 18 time-domain samples each into
 /// 18 blocks of 32 PCM audio samples.
-pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32], out: &mut [f32]) {
-    let mut s_vec = [0f32; 32];
-    let mut d_vec = [0f32; 32];
+pub fn synthesis<S, O>(
+    state: &mut SynthesisState<S>,
+    n_frames: usize,
+    in_samples: &[S],
+    out: &mut [O],
+) where
+    S: SynthesisSample,
+    O: FromSynthesized<S>,
+{
+    let mut s_vec = [S::default(); 32];
+    let mut d_vec = [S::default(); 32];
+
+    let synthesis_d = S::synthesis_window();
 
     assert!(in_samples.len() == 32 * n_frames);
 
@@ -245,13 +481,13 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
         dct32(&s_vec, &mut d_vec);
 
         for (d, s) in v_vec[48 - 15..48 + 0].iter_mut().rev().zip(&d_vec[1..16]) {
-            *d = -s;
+            *d = -*s;
         }
         for (d, s) in v_vec[48 + 1..48 + 16].iter_mut().zip(&d_vec[1..16]) {
-            *d = -s;
+            *d = -*s;
         }
         for (d, s) in v_vec[16 + 1..16 + 16].iter_mut().rev().zip(&d_vec[17..32]) {
-            *d = -s;
+            *d = -*s;
         }
         for (d, s) in v_vec[1..16].iter_mut().zip(&d_vec[17..32]) {
             *d = *s;
@@ -260,7 +496,7 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
         v_vec[0] = d_vec[16];
         v_vec[32] = -d_vec[16];
         v_vec[48] = -d_vec[0];
-        v_vec[16] = 0.0;
+        v_vec[16] = S::default();
 
         // Next, as per the specification, build a vector, u_vec, by iterating over the 16 slots in
         // v_vec, and copying the first 32 samples of EVEN numbered v_vec slots, and the last 32
@@ -306,7 +542,7 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
         //
         // Using this method, there is no reason to build u_vec and cache locality is greatly
         // improved.
-        let mut o_vec = [0f32; 32];
+        let mut o_vec = [S::default(); 32];
 
         for j in 0..8 {
             let v_start = state.v_front + (j << 1);
@@ -317,8 +553,8 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
             let k = j << 6;
 
             for i in 0..32 {
-                o_vec[i] += v0[i] * SYNTHESIS_D[k + i + 0];
-                o_vec[i] += v1[i] * SYNTHESIS_D[k + i + 32];
+                o_vec[i] += v0[i] * synthesis_d[k + i + 0];
+                o_vec[i] += v1[i] * synthesis_d[k + i + 32];
             }
         }
 
@@ -326,7 +562,7 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
         let offset = b << 5;
 
         for (o, s) in out[offset..offset + 32].iter_mut().zip(&o_vec) {
-            *o = s.clamp(-1.0, 1.0);
+            *o = O::from_synthesized(*s);
         }
 
         // Shift the v_vec FIFO. The value v_front is the index of the 64 sample slot in v_vec
@@ -348,56 +584,7 @@ pub fn synthesis(state: &mut SynthesisState, n_frames: usize, in_samples: &[f32]
 /// on Acoustics, Speech, and Signal Processing, vol. 32, no. 6, pp. 1243-1245, 1984.
 ///
 /// https://ieeexplore.ieee.org/document/1164443
-fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
-    // The following tables are pre-computed values of the the following equation:
-    //
-    // c[i] = 1.0 / [2.0 * cos((PI / N) * (2*i + 1))]    for i = 0..N/2
-    //
-    // where N = [32, 16, 8, 4, 2], for COS_16, COS8, COS_4, and COS_2, respectively.
-    const COS_16: [f32; 16] = [
-        0.500_602_998_235_196_3,  // i= 0
-        0.505_470_959_897_543_6,  // i= 1
-        0.515_447_309_922_624_6,  // i= 2
-        0.531_042_591_089_784_1,  // i= 3
-        0.553_103_896_034_444_5,  // i= 4
-        0.582_934_968_206_133_9,  // i= 5
-        0.622_504_123_035_664_8,  // i= 6
-        0.674_808_341_455_005_7,  // i= 7
-        0.744_536_271_002_298_6,  // i= 8
-        0.839_349_645_415_526_8,  // i= 9
-        0.972_568_237_861_960_8,  // i=10
-        1.169_439_933_432_884_7,  // i=11
-        1.484_164_616_314_166_2,  // i=12
-        2.057_781_009_953_410_8,  // i=13
-        3.407_608_418_468_719_0,  // i=14
-        10.190_008_123_548_032_9, // i=15
-    ];
-
-    const COS_8: [f32; 8] = [
-        0.502_419_286_188_155_7, // i=0
-        0.522_498_614_939_688_9, // i=1
-        0.566_944_034_816_357_7, // i=2
-        0.646_821_783_359_990_1, // i=3
-        0.788_154_623_451_250_2, // i=4
-        1.060_677_685_990_347_1, // i=5
-        1.722_447_098_238_334_2, // i=6
-        5.101_148_618_689_155_3, // i=7
-    ];
-
-    const COS_4: [f32; 4] = [
-        0.509_795_579_104_159_2, // i=0
-        0.601_344_886_935_045_3, // i=1
-        0.899_976_223_136_415_6, // i=2
-        2.562_915_447_741_505_5, // i=3
-    ];
-
-    const COS_2: [f32; 2] = [
-        0.541_196_100_146_197_0, // i=0
-        1.306_562_964_876_376_4, // i=1
-    ];
-
-    const COS_1: f32 = 0.707_106_781_186_547_5;
-
+fn dct32<S: SynthesisSample>(x: &[S; 32], y: &mut [S; 32]) {
     // 16-point DCT decomposition
     let mut t0 = [
         (x[0] + x[32 - 1]),
@@ -416,22 +603,22 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
         (x[13] + x[32 - 14]),
         (x[14] + x[32 - 15]),
         (x[15] + x[32 - 16]),
-        (x[0] - x[32 - 1]) * COS_16[0],
-        (x[1] - x[32 - 2]) * COS_16[1],
-        (x[2] - x[32 - 3]) * COS_16[2],
-        (x[3] - x[32 - 4]) * COS_16[3],
-        (x[4] - x[32 - 5]) * COS_16[4],
-        (x[5] - x[32 - 6]) * COS_16[5],
-        (x[6] - x[32 - 7]) * COS_16[6],
-        (x[7] - x[32 - 8]) * COS_16[7],
-        (x[8] - x[32 - 9]) * COS_16[8],
-        (x[9] - x[32 - 10]) * COS_16[9],
-        (x[10] - x[32 - 11]) * COS_16[10],
-        (x[11] - x[32 - 12]) * COS_16[11],
-        (x[12] - x[32 - 13]) * COS_16[12],
-        (x[13] - x[32 - 14]) * COS_16[13],
-        (x[14] - x[32 - 15]) * COS_16[14],
-        (x[15] - x[32 - 16]) * COS_16[15],
+        (x[0] - x[32 - 1]) * S::DCT32_COS_16[0],
+        (x[1] - x[32 - 2]) * S::DCT32_COS_16[1],
+        (x[2] - x[32 - 3]) * S::DCT32_COS_16[2],
+        (x[3] - x[32 - 4]) * S::DCT32_COS_16[3],
+        (x[4] - x[32 - 5]) * S::DCT32_COS_16[4],
+        (x[5] - x[32 - 6]) * S::DCT32_COS_16[5],
+        (x[6] - x[32 - 7]) * S::DCT32_COS_16[6],
+        (x[7] - x[32 - 8]) * S::DCT32_COS_16[7],
+        (x[8] - x[32 - 9]) * S::DCT32_COS_16[8],
+        (x[9] - x[32 - 10]) * S::DCT32_COS_16[9],
+        (x[10] - x[32 - 11]) * S::DCT32_COS_16[10],
+        (x[11] - x[32 - 12]) * S::DCT32_COS_16[11],
+        (x[12] - x[32 - 13]) * S::DCT32_COS_16[12],
+        (x[13] - x[32 - 14]) * S::DCT32_COS_16[13],
+        (x[14] - x[32 - 15]) * S::DCT32_COS_16[14],
+        (x[15] - x[32 - 16]) * S::DCT32_COS_16[15],
     ];
 
     // 16-point DCT decomposition of t0[0..16]
@@ -445,14 +632,14 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
             (t0[5] + t0[16 - 6]),
             (t0[6] + t0[16 - 7]),
             (t0[7] + t0[16 - 8]),
-            (t0[0] - t0[16 - 1]) * COS_8[0],
-            (t0[1] - t0[16 - 2]) * COS_8[1],
-            (t0[2] - t0[16 - 3]) * COS_8[2],
-            (t0[3] - t0[16 - 4]) * COS_8[3],
-            (t0[4] - t0[16 - 5]) * COS_8[4],
-            (t0[5] - t0[16 - 6]) * COS_8[5],
-            (t0[6] - t0[16 - 7]) * COS_8[6],
-            (t0[7] - t0[16 - 8]) * COS_8[7],
+            (t0[0] - t0[16 - 1]) * S::DCT32_COS_8[0],
+            (t0[1] - t0[16 - 2]) * S::DCT32_COS_8[1],
+            (t0[2] - t0[16 - 3]) * S::DCT32_COS_8[2],
+            (t0[3] - t0[16 - 4]) * S::DCT32_COS_8[3],
+            (t0[4] - t0[16 - 5]) * S::DCT32_COS_8[4],
+            (t0[5] - t0[16 - 6]) * S::DCT32_COS_8[5],
+            (t0[6] - t0[16 - 7]) * S::DCT32_COS_8[6],
+            (t0[7] - t0[16 - 8]) * S::DCT32_COS_8[7],
         ];
 
         // 8-point DCT decomposition of t1[0..8]
@@ -462,10 +649,10 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 (t1[1] + t1[8 - 2]),
                 (t1[2] + t1[8 - 3]),
                 (t1[3] + t1[8 - 4]),
-                (t1[0] - t1[8 - 1]) * COS_4[0],
-                (t1[1] - t1[8 - 2]) * COS_4[1],
-                (t1[2] - t1[8 - 3]) * COS_4[2],
-                (t1[3] - t1[8 - 4]) * COS_4[3],
+                (t1[0] - t1[8 - 1]) * S::DCT32_COS_4[0],
+                (t1[1] - t1[8 - 2]) * S::DCT32_COS_4[1],
+                (t1[2] - t1[8 - 3]) * S::DCT32_COS_4[2],
+                (t1[3] - t1[8 - 4]) * S::DCT32_COS_4[3],
             ];
 
             // 4-point DCT decomposition of t2[0..4]
@@ -473,13 +660,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[0] + t2[4 - 1]),
                     (t2[1] + t2[4 - 2]),
-                    (t2[0] - t2[4 - 1]) * COS_2[0],
-                    (t2[1] - t2[4 - 2]) * COS_2[1],
+                    (t2[0] - t2[4 - 1]) * S::DCT32_COS_2[0],
+                    (t2[1] - t2[4 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -487,7 +674,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -504,13 +691,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[4] + t2[8 - 1]),
                     (t2[5] + t2[8 - 2]),
-                    (t2[4] - t2[8 - 1]) * COS_2[0],
-                    (t2[5] - t2[8 - 2]) * COS_2[1],
+                    (t2[4] - t2[8 - 1]) * S::DCT32_COS_2[0],
+                    (t2[5] - t2[8 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -518,7 +705,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -547,10 +734,10 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 (t1[9] + t1[16 - 2]),
                 (t1[10] + t1[16 - 3]),
                 (t1[11] + t1[16 - 4]),
-                (t1[8] - t1[16 - 1]) * COS_4[0],
-                (t1[9] - t1[16 - 2]) * COS_4[1],
-                (t1[10] - t1[16 - 3]) * COS_4[2],
-                (t1[11] - t1[16 - 4]) * COS_4[3],
+                (t1[8] - t1[16 - 1]) * S::DCT32_COS_4[0],
+                (t1[9] - t1[16 - 2]) * S::DCT32_COS_4[1],
+                (t1[10] - t1[16 - 3]) * S::DCT32_COS_4[2],
+                (t1[11] - t1[16 - 4]) * S::DCT32_COS_4[3],
             ];
 
             // 4-point DCT decomposition of t2[0..4]
@@ -558,13 +745,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[0] + t2[4 - 1]),
                     (t2[1] + t2[4 - 2]),
-                    (t2[0] - t2[4 - 1]) * COS_2[0],
-                    (t2[1] - t2[4 - 2]) * COS_2[1],
+                    (t2[0] - t2[4 - 1]) * S::DCT32_COS_2[0],
+                    (t2[1] - t2[4 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -572,7 +759,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -589,13 +776,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[4] + t2[8 - 1]),
                     (t2[5] + t2[8 - 2]),
-                    (t2[4] - t2[8 - 1]) * COS_2[0],
-                    (t2[5] - t2[8 - 2]) * COS_2[1],
+                    (t2[4] - t2[8 - 1]) * S::DCT32_COS_2[0],
+                    (t2[5] - t2[8 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -603,7 +790,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -646,14 +833,14 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
             (t0[21] + t0[32 - 6]),
             (t0[22] + t0[32 - 7]),
             (t0[23] + t0[32 - 8]),
-            (t0[16] - t0[32 - 1]) * COS_8[0],
-            (t0[17] - t0[32 - 2]) * COS_8[1],
-            (t0[18] - t0[32 - 3]) * COS_8[2],
-            (t0[19] - t0[32 - 4]) * COS_8[3],
-            (t0[20] - t0[32 - 5]) * COS_8[4],
-            (t0[21] - t0[32 - 6]) * COS_8[5],
-            (t0[22] - t0[32 - 7]) * COS_8[6],
-            (t0[23] - t0[32 - 8]) * COS_8[7],
+            (t0[16] - t0[32 - 1]) * S::DCT32_COS_8[0],
+            (t0[17] - t0[32 - 2]) * S::DCT32_COS_8[1],
+            (t0[18] - t0[32 - 3]) * S::DCT32_COS_8[2],
+            (t0[19] - t0[32 - 4]) * S::DCT32_COS_8[3],
+            (t0[20] - t0[32 - 5]) * S::DCT32_COS_8[4],
+            (t0[21] - t0[32 - 6]) * S::DCT32_COS_8[5],
+            (t0[22] - t0[32 - 7]) * S::DCT32_COS_8[6],
+            (t0[23] - t0[32 - 8]) * S::DCT32_COS_8[7],
         ];
 
         // 8-point DCT decomposition of t1[0..8]
@@ -663,10 +850,10 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 (t1[1] + t1[8 - 2]),
                 (t1[2] + t1[8 - 3]),
                 (t1[3] + t1[8 - 4]),
-                (t1[0] - t1[8 - 1]) * COS_4[0],
-                (t1[1] - t1[8 - 2]) * COS_4[1],
-                (t1[2] - t1[8 - 3]) * COS_4[2],
-                (t1[3] - t1[8 - 4]) * COS_4[3],
+                (t1[0] - t1[8 - 1]) * S::DCT32_COS_4[0],
+                (t1[1] - t1[8 - 2]) * S::DCT32_COS_4[1],
+                (t1[2] - t1[8 - 3]) * S::DCT32_COS_4[2],
+                (t1[3] - t1[8 - 4]) * S::DCT32_COS_4[3],
             ];
 
             // 4-point DCT decomposition of t2[0..4]
@@ -674,13 +861,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[0] + t2[4 - 1]),
                     (t2[1] + t2[4 - 2]),
-                    (t2[0] - t2[4 - 1]) * COS_2[0],
-                    (t2[1] - t2[4 - 2]) * COS_2[1],
+                    (t2[0] - t2[4 - 1]) * S::DCT32_COS_2[0],
+                    (t2[1] - t2[4 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -688,7 +875,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -705,13 +892,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[4] + t2[8 - 1]),
                     (t2[5] + t2[8 - 2]),
-                    (t2[4] - t2[8 - 1]) * COS_2[0],
-                    (t2[5] - t2[8 - 2]) * COS_2[1],
+                    (t2[4] - t2[8 - 1]) * S::DCT32_COS_2[0],
+                    (t2[5] - t2[8 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -719,7 +906,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -748,10 +935,10 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 (t1[9] + t1[16 - 2]),
                 (t1[10] + t1[16 - 3]),
                 (t1[11] + t1[16 - 4]),
-                (t1[8] - t1[16 - 1]) * COS_4[0],
-                (t1[9] - t1[16 - 2]) * COS_4[1],
-                (t1[10] - t1[16 - 3]) * COS_4[2],
-                (t1[11] - t1[16 - 4]) * COS_4[3],
+                (t1[8] - t1[16 - 1]) * S::DCT32_COS_4[0],
+                (t1[9] - t1[16 - 2]) * S::DCT32_COS_4[1],
+                (t1[10] - t1[16 - 3]) * S::DCT32_COS_4[2],
+                (t1[11] - t1[16 - 4]) * S::DCT32_COS_4[3],
             ];
 
             // 4-point DCT decomposition of t2[0..4]
@@ -759,13 +946,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[0] + t2[4 - 1]),
                     (t2[1] + t2[4 - 2]),
-                    (t2[0] - t2[4 - 1]) * COS_2[0],
-                    (t2[1] - t2[4 - 2]) * COS_2[1],
+                    (t2[0] - t2[4 - 1]) * S::DCT32_COS_2[0],
+                    (t2[1] - t2[4 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -773,7 +960,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -790,13 +977,13 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
                 let mut t3 = [
                     (t2[4] + t2[8 - 1]),
                     (t2[5] + t2[8 - 2]),
-                    (t2[4] - t2[8 - 1]) * COS_2[0],
-                    (t2[5] - t2[8 - 2]) * COS_2[1],
+                    (t2[4] - t2[8 - 1]) * S::DCT32_COS_2[0],
+                    (t2[5] - t2[8 - 2]) * S::DCT32_COS_2[1],
                 ];
 
                 // 2-point DCT decomposition of t3[0..2]
                 {
-                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * COS_1];
+                    let t4 = [(t3[0] + t3[2 - 1]), (t3[0] - t3[2 - 1]) * S::DCT32_COS_1];
 
                     t3[0] = t4[0];
                     t3[1] = t4[1];
@@ -804,7 +991,7 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
                 // 2-point DCT decomposition of t3[2..4]
                 {
-                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * COS_1];
+                    let t4 = [(t3[2] + t3[4 - 1]), (t3[2] - t3[4 - 1]) * S::DCT32_COS_1];
 
                     t3[2 + 0] = t4[0];
                     t3[2 + 1] = t4[1];
@@ -848,8 +1035,22 @@ fn dct32(x: &[f32; 32], y: &mut [f32; 32]) {
 
 #[cfg(test)]
 mod tests {
-    use super::dct32;
+    #[cfg(feature = "fixed-point")]
+    use super::SynthesisSample;
+    use super::{dct32, downmix_to_mono, select_dual_mono, synthesis};
+    use super::{FromSynthesized, SynthesisState};
+    #[cfg(feature = "fixed-point")]
+    use crate::fixed::Fixed;
     use std::f64;
+    use symphonia_core::codecs::DualMonoPolicy;
+    use symphonia_core::conv::FromSample;
+
+    const TEST_VECTOR: [f32; 32] = [
+        0.1710, 0.1705, 0.3476, 0.1866, 0.4784, 0.6525, 0.2690, 0.9996, //
+        0.1864, 0.7277, 0.1163, 0.6620, 0.0911, 0.3225, 0.1126, 0.5344, //
+        0.7839, 0.9741, 0.8757, 0.5763, 0.5926, 0.2756, 0.1757, 0.6531, //
+        0.7101, 0.7376, 0.1924, 0.0351, 0.8044, 0.2409, 0.9347, 0.9417, //
+    ];
 
     fn dct32_analytical(x: &[f32; 32]) -> [f32; 32] {
         const PI_32: f64 = f64::consts::PI / 32.0;
@@ -868,13 +1069,6 @@ mod tests {
 
     #[test]
     fn verify_dct32() {
-        const TEST_VECTOR: [f32; 32] = [
-            0.1710, 0.1705, 0.3476, 0.1866, 0.4784, 0.6525, 0.2690, 0.9996, //
-            0.1864, 0.7277, 0.1163, 0.6620, 0.0911, 0.3225, 0.1126, 0.5344, //
-            0.7839, 0.9741, 0.8757, 0.5763, 0.5926, 0.2756, 0.1757, 0.6531, //
-            0.7101, 0.7376, 0.1924, 0.0351, 0.8044, 0.2409, 0.9347, 0.9417, //
-        ];
-
         let mut test_result = [0f32; 32];
         dct32(&TEST_VECTOR, &mut test_result);
 
@@ -883,4 +1077,101 @@ mod tests {
             assert!((actual_result[i] - test_result[i]).abs() < 0.00001);
         }
     }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_dct32_fixed() {
+        let mut input = [Fixed(0); 32];
+        for (fixed, float) in input.iter_mut().zip(&TEST_VECTOR) {
+            *fixed = Fixed::from_f64(f64::from(*float));
+        }
+
+        let mut test_result = [Fixed(0); 32];
+        dct32(&input, &mut test_result);
+
+        let actual_result = dct32_analytical(&TEST_VECTOR);
+        for i in 0..32 {
+            assert!((f64::from(actual_result[i]) - test_result[i].to_f64()).abs() < 0.00001);
+        }
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_synthesis_window_fixed() {
+        for (fixed, float) in Fixed::synthesis_window().iter().zip(f32::synthesis_window()) {
+            assert!((fixed.to_f64() - f64::from(*float)).abs() < 0.0000001);
+        }
+    }
+
+    #[test]
+    fn verify_downmix_to_mono() {
+        let mut left = [0f32; 32 * 18];
+        let mut right = [0f32; 32 * 18];
+
+        for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            *l = 0.05 * (TEST_VECTOR[i % 32] - 0.5);
+            *r = 0.05 * (0.5 - TEST_VECTOR[(i * 7) % 32]);
+        }
+
+        let synthesize = |samples: &[f32]| {
+            let mut out = [0f32; 32 * 18];
+            synthesis(&mut SynthesisState::default(), 18, samples, &mut out);
+            out
+        };
+
+        let expected_left = synthesize(&left);
+        let expected_right = synthesize(&right);
+
+        // Downmixing before synthesis must be equivalent to averaging after synthesis.
+        downmix_to_mono(&mut left, &right);
+
+        for ((&actual, &l), &r) in synthesize(&left).iter().zip(&expected_left).zip(&expected_right)
+        {
+            assert!((actual - 0.5 * (l + r)).abs() < 0.00001);
+        }
+    }
+
+    #[test]
+    fn verify_select_dual_mono() {
+        let select = |policy| {
+            let mut left = [0.25f32, -0.5];
+            let mut right = [0.75f32, 0.5];
+            select_dual_mono(&mut left, &mut right, policy);
+            (left, right)
+        };
+
+        assert_eq!(select(DualMonoPolicy::Both), ([0.25, -0.5], [0.75, 0.5]));
+        assert_eq!(select(DualMonoPolicy::Left), ([0.25, -0.5], [0.25, -0.5]));
+        assert_eq!(select(DualMonoPolicy::Right), ([0.75, 0.5], [0.75, 0.5]));
+        assert_eq!(select(DualMonoPolicy::Mix), ([0.5, 0.0], [0.5, 0.0]));
+    }
+
+    #[test]
+    fn verify_from_synthesized() {
+        // Synthesizing directly into integer samples must be identical to synthesizing into f32
+        // samples and then converting.
+        for i in -40_000..40_000 {
+            let sample = i as f32 / 32_768.0;
+            let clamped = f32::from_synthesized(sample);
+
+            assert_eq!(i16::from_synthesized(sample), i16::from_sample(clamped));
+            assert_eq!(i32::from_synthesized(sample), i32::from_sample(clamped));
+        }
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn verify_from_synthesized_fixed() {
+        assert_eq!(i16::from_synthesized(Fixed::from_f64(0.5)), 1 << 14);
+        assert_eq!(i16::from_synthesized(Fixed::from_f64(-0.5)), -(1 << 14));
+        assert_eq!(i16::from_synthesized(Fixed::from_f64(1.5)), i16::MAX);
+        assert_eq!(i16::from_synthesized(Fixed::from_f64(-1.5)), i16::MIN);
+        assert_eq!(i16::from_synthesized(Fixed(1 << 8)), 1);
+        assert_eq!(i16::from_synthesized(Fixed((1 << 8) - 1)), 0);
+
+        assert_eq!(i32::from_synthesized(Fixed::from_f64(0.5)), 1 << 30);
+        assert_eq!(i32::from_synthesized(Fixed::from_f64(-1.0)), i32::MIN);
+        assert_eq!(i32::from_synthesized(Fixed::from_f64(1.0)), i32::MAX);
+        assert_eq!(i32::from_synthesized(Fixed::from_f64(-2.0)), i32::MIN);
+    }
 }
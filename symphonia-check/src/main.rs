@@ -19,6 +19,7 @@ use std::process::{Command, Stdio};
 use symphonia::core::audio::{AudioBufferRef, SampleBuffer};
 use symphonia::core::codecs::{Decoder, DecoderOptions};
 use symphonia::core::errors::{Error, Result};
+use symphonia::core::formats::seek_accuracy::SeekVerifier;
 use symphonia::core::formats::{FormatOptions, FormatReader};
 use symphonia::core::io::{MediaSourceStream, ReadOnlySource};
 use symphonia::core::meta::MetadataOptions;
@@ -334,40 +335,131 @@ fn run_test(path: &str, opts: &TestOptions, result: &mut TestResult) -> Result<(
     run_check(&mut ref_inst, &mut tgt_inst, opts, result)
 }
 
+fn run_seek_test(path: &str, n_seeks: u64, gapless: bool) -> Result<bool> {
+    let fmt_opts = FormatOptions { enable_gapless: gapless, ..Default::default() };
+
+    let open = || {
+        let mss =
+            MediaSourceStream::new(Box::new(File::open(Path::new(path))?), Default::default());
+        DecoderInstance::try_open(mss, &fmt_opts)
+    };
+
+    // Get the duration of the track, and the name of its codec.
+    let (n_frames, codec) = {
+        let inst = open()?;
+        let params = inst.decoder.codec_params();
+
+        let codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map_or("unknown", |desc| desc.short_name);
+
+        (params.n_frames.unwrap_or(0), codec)
+    };
+
+    if n_frames == 0 {
+        return Err(Error::Unsupported("the duration of the track is unknown"));
+    }
+
+    // Seek to the middle of n_seeks evenly sized regions of the track.
+    let timestamps: Vec<u64> =
+        (0..n_seeks).map(|i| ((2 * i + 1) * n_frames) / (2 * n_seeks)).collect();
+
+    let results =
+        SeekVerifier::new(|| open().map(|inst| (inst.format, inst.decoder))).verify(&timestamps)?;
+
+    println!("Codec: {}", codec);
+    println!();
+    println!("  {:>12}  {:>12}  {:>8}  {:>12}", "Timestamp", "Seeked To", "Offset", "Max Delta");
+
+    for result in results.iter() {
+        let offset = result.offset.map_or("none".to_string(), |offset| offset.to_string());
+
+        println!(
+            "  {:>12}  {:>12}  {:>8}  {:>12.8}",
+            result.ts, result.actual_ts, offset, result.max_delta
+        );
+    }
+
+    let n_failed = results.iter().filter(|result| !result.is_exact()).count();
+
+    println!();
+    println!("  Failed/Total Seeks: {:>12}/{:>12}", n_failed, results.len());
+    println!();
+
+    Ok(n_failed == 0)
+}
+
 fn main() {
     pretty_env_logger::init();
 
-    let matches = clap::Command::new("Symphonia Check")
-        .version("1.0")
-        .author("Philip Deljanov <philip.deljanov@gmail.com>")
-        .about("Check Symphonia output with a reference decoding")
-        .arg(Arg::new("samples").long("samples").help("Print failures per sample"))
-        .arg(
-            Arg::new("stop-after-fail")
-                .long("first-fail")
-                .short('f')
-                .help("Stop testing after the first failed packet"),
-        )
-        .arg(Arg::new("quiet").long("quiet").short('q').help("Only print test results"))
-        .arg(
-            Arg::new("keep-going")
-                .long("keep-going")
-                .help("Continue after a decode error (may cause many failures)"),
-        )
-        .arg(
-            Arg::new("decoder")
-                .long("ref")
-                .takes_value(true)
-                .possible_values(["ffmpeg", "flac", "mpg123", "oggdec"])
-                .default_value("ffmpeg")
-                .help("Specify a particular decoder to be used as the reference"),
-        )
-        .arg(Arg::new("no-gapless").long("no-gapless").help("Disable gapless decoding"))
-        .arg(Arg::new("INPUT").help("The input file path").required(true).index(1))
-        .get_matches();
+    let matches =
+        clap::Command::new("Symphonia Check")
+            .version("1.0")
+            .author("Philip Deljanov <philip.deljanov@gmail.com>")
+            .about("Check Symphonia output with a reference decoding")
+            .arg(Arg::new("samples").long("samples").help("Print failures per sample"))
+            .arg(
+                Arg::new("stop-after-fail")
+                    .long("first-fail")
+                    .short('f')
+                    .help("Stop testing after the first failed packet"),
+            )
+            .arg(Arg::new("quiet").long("quiet").short('q').help("Only print test results"))
+            .arg(
+                Arg::new("keep-going")
+                    .long("keep-going")
+                    .help("Continue after a decode error (may cause many failures)"),
+            )
+            .arg(
+                Arg::new("decoder")
+                    .long("ref")
+                    .takes_value(true)
+                    .possible_values(["ffmpeg", "flac", "mpg123", "oggdec"])
+                    .default_value("ffmpeg")
+                    .help("Specify a particular decoder to be used as the reference"),
+            )
+            .arg(Arg::new("no-gapless").long("no-gapless").help("Disable gapless decoding"))
+            .arg(
+                Arg::new("seek").long("seek").takes_value(true).value_name("COUNT").help(
+                    "Instead of comparing with a reference, verify the accuracy of COUNT seeks",
+                ),
+            )
+            .arg(Arg::new("INPUT").help("The input file path").required(true).index(1))
+            .get_matches();
 
     let path = matches.value_of("INPUT").unwrap();
 
+    if let Some(n_seeks) = matches.value_of("seek") {
+        let n_seeks = match n_seeks.parse::<u64>() {
+            Ok(n_seeks) if n_seeks > 0 => n_seeks,
+            _ => {
+                eprintln!("Invalid number of seeks: {}", n_seeks);
+                std::process::exit(2);
+            }
+        };
+
+        println!("Input Path: {}", path);
+        println!();
+
+        let ret = match run_seek_test(path, n_seeks, !matches.is_present("no-gapless")) {
+            Ok(true) => {
+                println!("PASS");
+                0
+            }
+            Ok(false) => {
+                println!("FAIL");
+                1
+            }
+            Err(err) => {
+                eprintln!("Test interrupted by error: {}", err);
+                2
+            }
+        };
+        println!();
+
+        std::process::exit(ret);
+    }
+
     let ref_decoder = match matches.value_of("decoder").unwrap() {
         "ffmpeg" => RefDecoder::Ffmpeg,
         "flac" => RefDecoder::Flac,
@@ -13,7 +13,7 @@
 
 use symphonia_core::audio::{AudioBuffer, Signal};
 use symphonia_core::errors::{decode_error, Result};
-use symphonia_core::io::ReadBitsLtr;
+use symphonia_core::io::{BufReader, ReadBitsLtr, ReadBytes};
 
 use crate::aac::common::*;
 use crate::aac::dsp;
@@ -32,14 +32,14 @@ pub struct ChannelPair {
 }
 
 impl ChannelPair {
-    pub fn new(is_pair: bool, channel: usize, sbinfo: GASubbandInfo) -> Self {
+    pub fn new(is_pair: bool, channel: usize, sbinfo: GASubbandInfo, m4atype: M4AType) -> Self {
         Self {
             is_pair,
             channel,
             ms_mask_present: 0,
             ms_used: [[false; MAX_SFBS]; MAX_WINDOWS],
-            ics0: ics::Ics::new(sbinfo),
-            ics1: ics::Ics::new(sbinfo),
+            ics0: ics::Ics::new(sbinfo, m4atype),
+            ics1: ics::Ics::new(sbinfo, m4atype),
             lcg: Lcg::new(0x1f2e3d4c), // Use the same seed as ffmpeg for symphonia-check.
         }
     }
@@ -49,6 +49,20 @@ impl ChannelPair {
         self.ics1.reset();
     }
 
+    /// Appends the state of the channel pair to `buf`.
+    pub fn save_state(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.lcg.state().to_le_bytes());
+        self.ics0.save_state(buf);
+        self.ics1.save_state(buf);
+    }
+
+    /// Restores a state previously saved by `save_state` from `reader`.
+    pub fn restore_state(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        self.lcg = Lcg::new(reader.read_u32()?);
+        self.ics0.restore_state(reader)?;
+        self.ics1.restore_state(reader)
+    }
+
     pub fn decode_ga_sce<B: ReadBitsLtr>(&mut self, bs: &mut B, m4atype: M4AType) -> Result<()> {
         self.ics0.decode(bs, &mut self.lcg, m4atype, false)?;
         Ok(())
@@ -59,7 +73,13 @@ impl ChannelPair {
 
         if common_window {
             // Decode the common ICS info block into the first channel.
-            self.ics0.info.decode(bs)?;
+            self.ics0.info.decode(bs, m4atype)?;
+
+            // Copy the common ICS info decoded in the first channel to the second channel.
+            self.ics1.info.copy_from_common(&self.ics0.info);
+
+            // The second channel may have its own long-term prediction data.
+            self.ics1.info.decode_common_ltp(bs, m4atype)?;
 
             // Mid-side stereo mask decoding.
             self.ms_mask_present = bs.read_bits_leq32(2)? as u8;
@@ -88,9 +108,6 @@ impl ChannelPair {
                 3 => return decode_error("aac: invalid mid-side mask"),
                 _ => unreachable!(),
             }
-
-            // Copy the common ICS info decoded in the first channel to the second channel.
-            self.ics1.info.copy_from_common(&self.ics0.info);
         }
 
         self.ics0.decode(bs, &mut self.lcg, m4atype, common_window)?;
@@ -100,6 +117,8 @@ impl ChannelPair {
         if common_window {
             let bands = self.ics0.get_bands();
 
+            let win_len = bands[bands.len() - 1];
+
             let mut g = 0;
 
             for w in 0..self.ics0.info.num_windows {
@@ -108,8 +127,8 @@ impl ChannelPair {
                 }
 
                 for sfb in 0..self.ics0.info.max_sfb {
-                    let start = w * 128 + bands[sfb];
-                    let end = w * 128 + bands[sfb + 1];
+                    let start = w * win_len + bands[sfb];
+                    let end = w * win_len + bands[sfb + 1];
 
                     if self.ics1.is_intensity(g, sfb) {
                         // Intensity stereo
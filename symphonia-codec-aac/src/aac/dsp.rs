@@ -11,43 +11,163 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::dsp::mdct::Imdct;
+use symphonia_core::dsp::mdct;
 
 use crate::aac::common::*;
 use crate::aac::window::*;
 
-const SHORT_WIN_POINT0: usize = 512 - 64;
-const SHORT_WIN_POINT1: usize = 512 + 64;
+/// A direct implementation of the MDCT and IMDCT.
+///
+/// The IMDCT provided by Symphonia core requires a power-of-two transform size. However, 960 sample
+/// frames require transform sizes of 960 and 120. Additionally, long-term prediction requires a
+/// forward MDCT. Both are rarely used, so a direct O(N^2) transform is used in these cases.
+struct DirectMdct {
+    n: usize,
+    scale: f32,
+    /// A table of cos(pi * m / 4N) for m in 0..8N, a full period of the cosine.
+    cos: Box<[f32]>,
+}
+
+impl DirectMdct {
+    /// Instantiate a N-point MDCT and IMDCT with scaling.
+    fn new_scaled(n: usize, scale: f64) -> Self {
+        let period = 8 * n;
+        let pi_4n = std::f64::consts::PI / (4 * n) as f64;
+
+        let cos = (0..period).map(|m| (pi_4n * m as f64).cos() as f32).collect();
+
+        DirectMdct { n, scale: scale as f32, cos }
+    }
+
+    /// Computes the sum of `x[j] * cos(pi / 4N * (start + j * step))` over all elements of `x`.
+    #[inline(always)]
+    fn dot_cos(&self, x: &[f32], start: usize, step: usize) -> f32 {
+        let period = self.cos.len();
+
+        let mut idx = start % period;
+        let step = step % period;
+
+        let mut sum = 0.0;
+
+        for &x in x {
+            sum += x * self.cos[idx];
+
+            idx += step;
+
+            if idx >= period {
+                idx -= period;
+            }
+        }
+
+        sum
+    }
+
+    /// Performs the N-point IMDCT. The length of `spec` must be N, and the length of `out` must be
+    /// 2N.
+    fn imdct(&self, spec: &[f32], out: &mut [f32]) {
+        let n = self.n;
+        let n2 = n / 2;
+
+        assert_eq!(spec.len(), n);
+        assert_eq!(out.len(), 2 * n);
+
+        // Only the middle N output samples need to be computed. The first and last quarters of the
+        // output are reversed copies (with negation for the first quarter) of the second and third
+        // quarters, respectively.
+        //
+        // The argument of the cosine for output sample i, and spectral sample k, is
+        // (2i + 1 + N) * (2k + 1).
+        for i in n2..n2 + n {
+            let m = 2 * i + 1 + n;
+            out[i] = self.scale * self.dot_cos(spec, m, 2 * m);
+        }
+
+        for i in 0..n2 {
+            out[i] = -out[n - 1 - i];
+            out[2 * n - 1 - i] = out[n + i];
+        }
+    }
+
+    /// Performs the N-point MDCT. The length of `input` must be 2N, and the length of `spec` must
+    /// be N.
+    fn mdct(&self, input: &[f32], spec: &mut [f32]) {
+        assert_eq!(input.len(), 2 * self.n);
+        assert_eq!(spec.len(), self.n);
+
+        // The argument of the cosine for input sample i, and spectral sample k, is
+        // (2i + 1 + N) * (2k + 1).
+        for (k, s) in spec.iter_mut().enumerate() {
+            let m = 2 * k + 1;
+            *s = self.scale * self.dot_cos(input, m * (1 + self.n), 2 * m);
+        }
+    }
+}
+
+/// An IMDCT of any transform size supported by AAC.
+enum Imdct {
+    Fast(mdct::Imdct),
+    Direct(DirectMdct),
+}
+
+impl Imdct {
+    fn new_scaled(n: usize, scale: f64) -> Self {
+        if n.is_power_of_two() {
+            Imdct::Fast(mdct::Imdct::new_scaled(n, scale))
+        }
+        else {
+            Imdct::Direct(DirectMdct::new_scaled(n, scale))
+        }
+    }
+
+    fn imdct(&mut self, spec: &[f32], out: &mut [f32]) {
+        match self {
+            Imdct::Fast(imdct) => imdct.imdct(spec, out),
+            Imdct::Direct(imdct) => imdct.imdct(spec, out),
+        }
+    }
+}
 
 pub struct Dsp {
-    kbd_long_win: [f32; 1024],
-    kbd_short_win: [f32; 128],
-    sine_long_win: [f32; 1024],
-    sine_short_win: [f32; 128],
+    /// The number of samples per frame (1024 or 960).
+    long_len: usize,
+    /// The number of samples per short window (128 or 120).
+    short_len: usize,
+    kbd_long_win: Box<[f32]>,
+    kbd_short_win: Box<[f32]>,
+    sine_long_win: Box<[f32]>,
+    sine_short_win: Box<[f32]>,
     imdct_long: Imdct,
     imdct_short: Imdct,
+    mdct_ltp: Option<DirectMdct>,
     pcm_long: [f32; 2048],
     pcm_short: [f32; 1152],
 }
 
 impl Dsp {
-    pub fn new() -> Self {
-        let mut kbd_long_win: [f32; 1024] = [0.0; 1024];
-        let mut kbd_short_win: [f32; 128] = [0.0; 128];
-        generate_window(WindowType::KaiserBessel(4.0), 1.0, 1024, true, &mut kbd_long_win);
-        generate_window(WindowType::KaiserBessel(6.0), 1.0, 128, true, &mut kbd_short_win);
-        let mut sine_long_win: [f32; 1024] = [0.0; 1024];
-        let mut sine_short_win: [f32; 128] = [0.0; 128];
-        generate_window(WindowType::Sine, 1.0, 1024, true, &mut sine_long_win);
-        generate_window(WindowType::Sine, 1.0, 128, true, &mut sine_short_win);
+    pub fn new(long_len: usize) -> Self {
+        debug_assert!(long_len == 1024 || long_len == 960);
+
+        let short_len = long_len / 8;
+
+        let mut kbd_long_win = vec![0.0; long_len];
+        let mut kbd_short_win = vec![0.0; short_len];
+        generate_window(WindowType::KaiserBessel(4.0), 1.0, long_len, true, &mut kbd_long_win);
+        generate_window(WindowType::KaiserBessel(6.0), 1.0, short_len, true, &mut kbd_short_win);
+        let mut sine_long_win = vec![0.0; long_len];
+        let mut sine_short_win = vec![0.0; short_len];
+        generate_window(WindowType::Sine, 1.0, long_len, true, &mut sine_long_win);
+        generate_window(WindowType::Sine, 1.0, short_len, true, &mut sine_short_win);
 
         Self {
-            kbd_long_win,
-            kbd_short_win,
-            sine_long_win,
-            sine_short_win,
-            imdct_long: Imdct::new_scaled(1024, 1.0 / 2048.0),
-            imdct_short: Imdct::new_scaled(128, 1.0 / 256.0),
+            long_len,
+            short_len,
+            kbd_long_win: kbd_long_win.into_boxed_slice(),
+            kbd_short_win: kbd_short_win.into_boxed_slice(),
+            sine_long_win: sine_long_win.into_boxed_slice(),
+            sine_short_win: sine_short_win.into_boxed_slice(),
+            imdct_long: Imdct::new_scaled(long_len, 1.0 / (2 * long_len) as f64),
+            imdct_short: Imdct::new_scaled(short_len, 1.0 / (2 * short_len) as f64),
+            mdct_ltp: None,
             pcm_long: [0.0; 2048],
             pcm_short: [0.0; 1152],
         }
@@ -63,6 +183,13 @@ impl Dsp {
         prev_window_shape: bool,
         dst: &mut [f32],
     ) {
+        let long_len = self.long_len;
+        let short_len = self.short_len;
+
+        // The start and end of the short windows in the middle of a long window.
+        let short_win_point0 = (long_len - short_len) / 2;
+        let short_win_point1 = (long_len + short_len) / 2;
+
         let (long_win, short_win) = match window_shape {
             true => (&self.kbd_long_win, &self.kbd_short_win),
             false => (&self.sine_long_win, &self.sine_short_win),
@@ -73,29 +200,36 @@ impl Dsp {
             false => (&self.sine_long_win, &self.sine_short_win),
         };
 
+        let pcm_long = &mut self.pcm_long[..2 * long_len];
+        let pcm_short = &mut self.pcm_short[..9 * short_len];
+
         // Inverse MDCT
         if seq != EIGHT_SHORT_SEQUENCE {
-            self.imdct_long.imdct(coeffs, &mut self.pcm_long);
+            self.imdct_long.imdct(&coeffs[..long_len], pcm_long);
         }
         else {
-            for (ain, aout) in coeffs.chunks_exact(128).zip(self.pcm_long.chunks_exact_mut(256)) {
+            let ains = coeffs[..long_len].chunks_exact(short_len);
+
+            for (ain, aout) in ains.zip(pcm_long.chunks_exact_mut(2 * short_len)) {
                 self.imdct_short.imdct(ain, aout);
             }
 
             // Zero the eight short sequence buffer.
-            self.pcm_short.fill(0.0);
+            pcm_short.fill(0.0);
+
+            for (w, src) in pcm_long.chunks_exact(2 * short_len).enumerate() {
+                let dst = &mut pcm_short[w * short_len..(w + 2) * short_len];
 
-            for (w, src) in self.pcm_long.chunks_exact(256).enumerate() {
                 if w > 0 {
-                    for i in 0..128 {
-                        self.pcm_short[w * 128 + i] += src[i] * short_win[i];
-                        self.pcm_short[w * 128 + i + 128] += src[i + 128] * short_win[127 - i];
+                    for i in 0..short_len {
+                        dst[i] += src[i] * short_win[i];
+                        dst[i + short_len] += src[i + short_len] * short_win[short_len - 1 - i];
                     }
                 }
                 else {
-                    for i in 0..128 {
-                        self.pcm_short[i] = src[i] * prev_short_win[i];
-                        self.pcm_short[i + 128] = src[i + 128] * short_win[127 - i];
+                    for i in 0..short_len {
+                        dst[i] = src[i] * prev_short_win[i];
+                        dst[i + short_len] = src[i + short_len] * short_win[short_len - 1 - i];
                     }
                 }
             }
@@ -104,25 +238,25 @@ impl Dsp {
         // Output new audio samples.
         match seq {
             ONLY_LONG_SEQUENCE | LONG_START_SEQUENCE => {
-                for i in 0..1024 {
-                    dst[i] = delay[i] + (self.pcm_long[i] * prev_long_win[i]);
+                for i in 0..long_len {
+                    dst[i] = delay[i] + (pcm_long[i] * prev_long_win[i]);
                 }
             }
             EIGHT_SHORT_SEQUENCE => {
-                dst[..SHORT_WIN_POINT0].copy_from_slice(&delay[..SHORT_WIN_POINT0]);
+                dst[..short_win_point0].copy_from_slice(&delay[..short_win_point0]);
 
-                for i in SHORT_WIN_POINT0..1024 {
-                    dst[i] = delay[i] + self.pcm_short[i - SHORT_WIN_POINT0];
+                for i in short_win_point0..long_len {
+                    dst[i] = delay[i] + pcm_short[i - short_win_point0];
                 }
             }
             LONG_STOP_SEQUENCE => {
-                dst[..SHORT_WIN_POINT0].copy_from_slice(&delay[..SHORT_WIN_POINT0]);
+                dst[..short_win_point0].copy_from_slice(&delay[..short_win_point0]);
 
-                for i in SHORT_WIN_POINT0..SHORT_WIN_POINT1 {
-                    dst[i] = delay[i] + self.pcm_long[i] * prev_short_win[i - SHORT_WIN_POINT0];
+                for i in short_win_point0..short_win_point1 {
+                    dst[i] = delay[i] + pcm_long[i] * prev_short_win[i - short_win_point0];
                 }
-                for i in SHORT_WIN_POINT1..1024 {
-                    dst[i] = delay[i] + self.pcm_long[i];
+                for i in short_win_point1..long_len {
+                    dst[i] = delay[i] + pcm_long[i];
                 }
             }
             _ => unreachable!(),
@@ -131,29 +265,161 @@ impl Dsp {
         // Save delay for overlap.
         match seq {
             ONLY_LONG_SEQUENCE | LONG_STOP_SEQUENCE => {
-                for i in 0..1024 {
-                    delay[i] = self.pcm_long[i + 1024] * long_win[1023 - i];
+                for i in 0..long_len {
+                    delay[i] = pcm_long[i + long_len] * long_win[long_len - 1 - i];
                 }
             }
             EIGHT_SHORT_SEQUENCE => {
-                for i in 0..SHORT_WIN_POINT1 {
-                    // Last part is already windowed.
-                    delay[i] = self.pcm_short[i + 512 + 64];
-                }
-
-                delay[SHORT_WIN_POINT1..].fill(0.0);
+                // Last part is already windowed.
+                delay[..short_win_point1].copy_from_slice(&pcm_short[short_win_point1..]);
+                delay[short_win_point1..].fill(0.0);
             }
             LONG_START_SEQUENCE => {
-                delay[..SHORT_WIN_POINT0]
-                    .copy_from_slice(&self.pcm_long[1024..(SHORT_WIN_POINT0 + 1024)]);
+                delay[..short_win_point0]
+                    .copy_from_slice(&pcm_long[long_len..(short_win_point0 + long_len)]);
 
-                for i in SHORT_WIN_POINT0..SHORT_WIN_POINT1 {
-                    delay[i] = self.pcm_long[i + 1024] * short_win[127 - (i - SHORT_WIN_POINT0)];
+                for i in short_win_point0..short_win_point1 {
+                    delay[i] =
+                        pcm_long[i + long_len] * short_win[short_len - 1 - (i - short_win_point0)];
                 }
 
-                delay[SHORT_WIN_POINT1..].fill(0.0);
+                delay[short_win_point1..].fill(0.0);
             }
             _ => unreachable!(),
         };
     }
+
+    /// Window the predicted time-domain signal for long-term prediction, `pred_time`, as would be
+    /// done by the encoder for a long window sequence, and then transform it into the predicted
+    /// spectrum, `pred_spec`. Section 4.6.7.
+    pub fn ltp_mdct(
+        &mut self,
+        seq: u8,
+        window_shape: bool,
+        prev_window_shape: bool,
+        pred_time: &mut [f32],
+        pred_spec: &mut [f32; 1024],
+    ) {
+        let long_len = self.long_len;
+        let short_len = self.short_len;
+
+        let short_win_point0 = (long_len - short_len) / 2;
+        let short_win_point1 = (long_len + short_len) / 2;
+
+        debug_assert!(seq != EIGHT_SHORT_SEQUENCE);
+        debug_assert!(pred_time.len() == 2 * long_len);
+
+        let (long_win, short_win) = match window_shape {
+            true => (&self.kbd_long_win, &self.kbd_short_win),
+            false => (&self.sine_long_win, &self.sine_short_win),
+        };
+
+        let (prev_long_win, prev_short_win) = match prev_window_shape {
+            true => (&self.kbd_long_win, &self.kbd_short_win),
+            false => (&self.sine_long_win, &self.sine_short_win),
+        };
+
+        let (first, second) = pred_time.split_at_mut(long_len);
+
+        // Window the first half.
+        if seq != LONG_STOP_SEQUENCE {
+            for (s, &w) in first.iter_mut().zip(prev_long_win.iter()) {
+                *s *= w;
+            }
+        }
+        else {
+            first[..short_win_point0].fill(0.0);
+
+            for (s, &w) in
+                first[short_win_point0..short_win_point1].iter_mut().zip(prev_short_win.iter())
+            {
+                *s *= w;
+            }
+        }
+
+        // Window the second half.
+        if seq != LONG_START_SEQUENCE {
+            for (s, &w) in second.iter_mut().zip(long_win.iter().rev()) {
+                *s *= w;
+            }
+        }
+        else {
+            for (s, &w) in
+                second[short_win_point0..short_win_point1].iter_mut().zip(short_win.iter().rev())
+            {
+                *s *= w;
+            }
+
+            second[short_win_point1..].fill(0.0);
+        }
+
+        // The forward MDCT is scaled such that it is the inverse of the long IMDCT.
+        let mdct = self.mdct_ltp.get_or_insert_with(|| DirectMdct::new_scaled(long_len, 4.0));
+
+        mdct.mdct(pred_time, &mut pred_spec[..long_len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_direct_imdct() {
+        const N: usize = 64;
+
+        let spec: Vec<f32> = (0..N).map(|i| ((i * 7) % 13) as f32 - 6.0).collect();
+
+        let mut expected = [0f32; 2 * N];
+        let mut actual = [0f32; 2 * N];
+
+        mdct::Imdct::new_scaled(N, 1.0 / (2 * N) as f64).imdct(&spec, &mut expected);
+        DirectMdct::new_scaled(N, 1.0 / (2 * N) as f64).imdct(&spec, &mut actual);
+
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn verify_direct_mdct_inverse() {
+        // A 960 sample frame with a sine window. With princen-bradley windows, the windowed
+        // overlap-add of the IMDCT of the MDCT of a signal reconstructs that signal.
+        const N: usize = 960;
+
+        let mut win = [0f32; N];
+        generate_window(WindowType::Sine, 1.0, N, true, &mut win);
+
+        let signal: Vec<f32> = (0..3 * N).map(|i| (0.01 * i as f32).sin()).collect();
+
+        let mdct = DirectMdct::new_scaled(N, 4.0);
+        let imdct = DirectMdct::new_scaled(N, 1.0 / (2 * N) as f64);
+
+        let mut out = vec![0f32; 3 * N];
+
+        for block in 0..2 {
+            let mut input = signal[block * N..(block + 2) * N].to_vec();
+
+            for i in 0..N {
+                input[i] *= win[i];
+                input[2 * N - 1 - i] *= win[i];
+            }
+
+            let mut spec = [0f32; N];
+            mdct.mdct(&input, &mut spec);
+
+            let mut time = [0f32; 2 * N];
+            imdct.imdct(&spec, &mut time);
+
+            for i in 0..N {
+                out[block * N + i] += time[i] * win[i];
+                out[block * N + N + i] += time[N + i] * win[N - 1 - i];
+            }
+        }
+
+        // Only the middle block is fully reconstructed.
+        for i in N..2 * N {
+            assert!((out[i] - signal[i]).abs() < 0.001);
+        }
+    }
 }
@@ -11,45 +11,124 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::errors::Result;
 use symphonia_core::io::ReadBitsLtr;
 
+use crate::aac::dsp;
+use crate::aac::ics::tns::Tns;
+use crate::aac::ics::IcsInfo;
+
+/// The maximum number of long window scale factor bands that may use long-term prediction.
+const MAX_LTP_LONG_SFB: usize = 40;
+
+/// The long-term prediction coefficients. Table 4.153.
+const LTP_COEF: [f32; 8] =
+    [0.570829, 0.696616, 0.813004, 0.911304, 0.984900, 1.067894, 1.194601, 1.369533];
+
+/// Long-term prediction (LTP) data for a long window.
 #[derive(Clone, Copy)]
-pub struct LtpData {}
+pub struct LtpData {
+    lag: usize,
+    coef: f32,
+    long_used: [bool; MAX_LTP_LONG_SFB],
+}
 
 impl LtpData {
-    pub fn read<B: ReadBitsLtr>(bs: &mut B) -> Result<Option<Self>> {
-        let predictor_data_present = bs.read_bool()?;
+    /// Read the long-term prediction data from the bitstream if present.
+    pub fn read<B: ReadBitsLtr>(bs: &mut B, max_sfb: usize) -> Result<Option<Self>> {
+        let ltp_data_present = bs.read_bool()?;
 
-        if !predictor_data_present {
+        if !ltp_data_present {
             return Ok(None);
         }
 
-        /*
-        if is_main {
-            let predictor_reset                         = bs.read_bit()?;
-            if predictor_reset {
-                let predictor_reset_group_number        = bs.read_bits_leq32(5)?;
-            }
-            for sfb in 0..max_sfb.min(PRED_SFB_MAX) {
-                prediction_used[sfb]                    = bs.read_bit()?;
-            }
+        let lag = bs.read_bits_leq32(11)? as usize;
+        let coef = LTP_COEF[bs.read_bits_leq32(3)? as usize];
+
+        // Long-term prediction data is only read for long windows, therefore the short window
+        // syntax elements are never present.
+        let mut long_used = [false; MAX_LTP_LONG_SFB];
+
+        for used in long_used[..max_sfb.min(MAX_LTP_LONG_SFB)].iter_mut() {
+            *used = bs.read_bool()?;
         }
-        else {
-            let ltp_data_present                        = bs.read_bit()?;
-            if ltp_data_present {
-                //ltp data
+
+        Ok(Some(LtpData { lag, coef, long_used }))
+    }
+
+    /// Add the predicted spectrum to the spectral coefficients. The state, `ltp_state`, contains
+    /// the fully reconstructed time-domain samples of the last two frames followed by the
+    /// windowed overlap of the last frame. Section 4.6.7.
+    #[allow(clippy::too_many_arguments)]
+    pub fn predict(
+        &self,
+        dsp: &mut dsp::Dsp,
+        info: &IcsInfo,
+        tns: Option<&Tns>,
+        bands: &[usize],
+        rate_idx: usize,
+        ltp_state: &[f32],
+        coeffs: &mut [f32; 1024],
+    ) {
+        // Long-term prediction is only applicable to long windows.
+        if !info.long_win {
+            return;
+        }
+
+        let long_len = ltp_state.len() / 3;
+
+        let mut pred_time = [0.0; 2048];
+        let pred_time = &mut pred_time[..2 * long_len];
+
+        // The predicted time-domain signal is the state delayed by the lag. If the lag is less
+        // than the frame length, then the predicted signal is only partially known and the
+        // remainder is 0.
+        let end = if self.lag < long_len { self.lag + long_len } else { 2 * long_len };
+        let start = self.lag.saturating_sub(2 * long_len);
+
+        for i in start..end {
+            pred_time[i] = self.coef * ltp_state[i + 2 * long_len - self.lag];
+        }
+
+        let mut pred_spec = [0.0; 1024];
+
+        dsp.ltp_mdct(
+            info.window_sequence,
+            info.window_shape,
+            info.prev_window_shape,
+            pred_time,
+            &mut pred_spec,
+        );
+
+        // The encoder applies the TNS analysis filter after prediction, so it must also be applied
+        // to the predicted spectrum.
+        if let Some(tns) = tns {
+            tns.analyze(info, bands, rate_idx, &mut pred_spec);
+        }
+
+        let max_sfb = info.max_sfb.min(MAX_LTP_LONG_SFB);
+
+        for (sfb, &used) in self.long_used[..max_sfb].iter().enumerate() {
+            if !used {
+                continue;
             }
-            if common_window {
-                let ltp_data_present                    = bs.read_bit()?;
-                if ltp_data_present {
-                    //ltp data
-                }
+
+            let start = bands[sfb];
+            let end = bands[sfb + 1];
+
+            for (c, &p) in coeffs[start..end].iter_mut().zip(&pred_spec[start..end]) {
+                *c += p;
             }
         }
-        Ok(Some(Self { }))
-        */
-
-        unsupported_error("aac: predictor data")
     }
 }
+
+/// Update the long-term prediction state after a frame, `out`, has been synthesized. The overlap
+/// of the frame, `delay`, is appended after the output.
+pub fn update_state(ltp_state: &mut [f32], out: &[f32], delay: &[f32]) {
+    let long_len = ltp_state.len() / 3;
+
+    ltp_state.copy_within(long_len..2 * long_len, 0);
+    ltp_state[long_len..2 * long_len].copy_from_slice(&out[..long_len]);
+    ltp_state[2 * long_len..].copy_from_slice(&delay[..long_len]);
+}
@@ -11,9 +11,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::vlc::{Codebook, Entry8x16};
-use symphonia_core::io::ReadBitsLtr;
+use symphonia_core::io::{BufReader, ReadBitsLtr, ReadBytes};
+use symphonia_core::util::table::make_table;
 
 use crate::aac::codebooks;
 use crate::aac::common::*;
@@ -39,39 +40,25 @@ const NORMAL_SCALE_MIN: i16 = -100;
 
 lazy_static! {
     /// Pre-computed table of y = x^(4/3).
-    static ref POW43_TABLE: [f32; 8192] = {
-        let mut pow43 = [0f32; 8192];
-        for (i, pow43) in pow43.iter_mut().enumerate() {
-            *pow43 = f32::powf(i as f32, 4.0 / 3.0);
-        }
-        pow43
-    };
+    static ref POW43_TABLE: Box<[f32; 8192]> = make_table(|i| f32::powf(i as f32, 4.0 / 3.0));
 }
 
 lazy_static! {
     /// Pre-computed table of y = 2^(0.25 * (x - 156)) for decoding scale factors for normal bands.
     /// This table is indexed relative to -100, the minimum encoded scale factor value for normal
     /// bands. Therefore, an input of 0 corresponds to -100.
-    static ref NORMAL_SCF_TABLE: [f32; 256] = {
-        let mut table = [0f32; 256];
-        for (i, table) in table.iter_mut().enumerate() {
-            *table = 2.0f32.powf(0.25 * f32::from(i as i16 - 56 + NORMAL_SCALE_MIN))
-        }
-        table
-    };
+    static ref NORMAL_SCF_TABLE: Box<[f32; 256]> = make_table(|i| {
+        2.0f32.powf(0.25 * f32::from(i as i16 - 56 + NORMAL_SCALE_MIN))
+    });
 }
 
 lazy_static! {
     /// Pre-computed table of y = 0.5^(0.25 * (x - 155)) for decoding scale factors for intensity
     /// coded bands. This table is indexed relative to -155, the minimum encoded scale factor value
     /// for intensity coded bands. Therefore, an input of 0 corresponds to -155.
-    static ref INTENSITY_SCF_TABLE: [f32; 256] = {
-        let mut table = [0f32; 256];
-        for (i, table) in table.iter_mut().enumerate() {
-            *table = 0.5f32.powf(0.25 * f32::from(i as i16 + INTENSITY_SCALE_MIN));
-        }
-        table
-    };
+    static ref INTENSITY_SCF_TABLE: Box<[f32; 256]> = make_table(|i| {
+        0.5f32.powf(0.25 * f32::from(i as i16 + INTENSITY_SCALE_MIN))
+    });
 }
 
 #[derive(Clone)]
@@ -86,6 +73,7 @@ pub struct IcsInfo {
     pub num_windows: usize,
     pub max_sfb: usize,
     pub long_win: bool,
+    pub predictor_data_present: bool,
     pub ltp: Option<ltp::LtpData>,
 }
 
@@ -101,12 +89,13 @@ impl IcsInfo {
             num_windows: 0,
             window_groups: 0,
             max_sfb: 0,
+            predictor_data_present: false,
             ltp: None,
             long_win: true,
         }
     }
 
-    pub fn decode<B: ReadBitsLtr>(&mut self, bs: &mut B) -> Result<()> {
+    pub fn decode<B: ReadBitsLtr>(&mut self, bs: &mut B, m4atype: M4AType) -> Result<()> {
         self.prev_window_sequence = self.window_sequence;
         self.prev_window_shape = self.window_shape;
 
@@ -117,19 +106,17 @@ impl IcsInfo {
         self.window_sequence = bs.read_bits_leq32(2)? as u8;
 
         match self.prev_window_sequence {
-            ONLY_LONG_SEQUENCE | LONG_STOP_SEQUENCE => {
+            ONLY_LONG_SEQUENCE | LONG_STOP_SEQUENCE
                 if (self.window_sequence != ONLY_LONG_SEQUENCE)
-                    && (self.window_sequence != LONG_START_SEQUENCE)
-                {
-                    debug!("previous window is invalid");
-                }
+                    && (self.window_sequence != LONG_START_SEQUENCE) =>
+            {
+                debug!("previous window is invalid");
             }
-            LONG_START_SEQUENCE | EIGHT_SHORT_SEQUENCE => {
+            LONG_START_SEQUENCE | EIGHT_SHORT_SEQUENCE
                 if (self.window_sequence != EIGHT_SHORT_SEQUENCE)
-                    && (self.window_sequence != LONG_STOP_SEQUENCE)
-                {
-                    debug!("previous window is invalid");
-                }
+                    && (self.window_sequence != LONG_STOP_SEQUENCE) =>
+            {
+                debug!("previous window is invalid");
             }
             _ => {}
         };
@@ -150,16 +137,45 @@ impl IcsInfo {
                     self.window_groups += 1;
                 }
             }
+
+            self.predictor_data_present = false;
+            self.ltp = None;
         }
         else {
             self.long_win = true;
             self.num_windows = 1;
             self.max_sfb = bs.read_bits_leq32(6)? as usize;
-            self.ltp = ltp::LtpData::read(bs)?;
+            self.predictor_data_present = bs.read_bool()?;
+            self.ltp = None;
+
+            if self.predictor_data_present {
+                // Only long-term prediction is supported.
+                if m4atype != M4AType::Ltp {
+                    return unsupported_error("aac: predictor data");
+                }
+
+                self.ltp = ltp::LtpData::read(bs, self.max_sfb)?;
+            }
         }
         Ok(())
     }
 
+    /// Read the long-term prediction data for the second channel of a channel pair with a common
+    /// window. The ICS info of the first channel must have already been copied to this one.
+    pub fn decode_common_ltp<B: ReadBitsLtr>(
+        &mut self,
+        bs: &mut B,
+        m4atype: M4AType,
+    ) -> Result<()> {
+        self.ltp = None;
+
+        if m4atype == M4AType::Ltp && self.predictor_data_present {
+            self.ltp = ltp::LtpData::read(bs, self.max_sfb)?;
+        }
+
+        Ok(())
+    }
+
     pub fn copy_from_common(&mut self, other: &IcsInfo) {
         // Maintain the previous window sequence and shape.
         let prev_window_sequence = self.window_sequence;
@@ -204,10 +220,17 @@ pub struct Ics {
     sbinfo: GASubbandInfo,
     pub coeffs: [f32; 1024],
     delay: [f32; 1024],
+    ltp_state: Option<Box<[f32]>>,
 }
 
 impl Ics {
-    pub fn new(sbinfo: GASubbandInfo) -> Self {
+    pub fn new(sbinfo: GASubbandInfo, m4atype: M4AType) -> Self {
+        // The long-term prediction state is the last two frames and the overlap of the last frame.
+        let ltp_state = match m4atype {
+            M4AType::Ltp => Some(vec![0.0; 3 * sbinfo.long_win_len()].into_boxed_slice()),
+            _ => None,
+        };
+
         Self {
             global_gain: 0,
             info: IcsInfo::new(),
@@ -222,12 +245,51 @@ impl Ics {
             sbinfo,
             coeffs: [0.0; 1024],
             delay: [0.0; 1024],
+            ltp_state,
         }
     }
 
     pub fn reset(&mut self) {
         self.info = IcsInfo::new();
         self.delay = [0.0; 1024];
+
+        if let Some(ltp_state) = &mut self.ltp_state {
+            ltp_state.fill(0.0);
+        }
+    }
+
+    /// Appends the state carried from one frame to the next to `buf`. The state consists of the
+    /// window of the last frame, the overlap, and the long-term prediction state.
+    pub fn save_state(&self, buf: &mut Vec<u8>) {
+        buf.push(self.info.window_sequence);
+        buf.push(u8::from(self.info.window_shape));
+
+        let ltp_state = self.ltp_state.iter().flat_map(|ltp_state| ltp_state.iter());
+
+        for sample in self.delay.iter().chain(ltp_state) {
+            buf.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    /// Restores a state previously saved by `save_state` from `reader`.
+    pub fn restore_state(&mut self, reader: &mut BufReader<'_>) -> Result<()> {
+        let window_sequence = reader.read_u8()?;
+        let window_shape = reader.read_u8()?;
+
+        if window_sequence > LONG_STOP_SEQUENCE || window_shape > 1 {
+            return decode_error("aac: invalid decoder state window");
+        }
+
+        self.info.window_sequence = window_sequence;
+        self.info.window_shape = window_shape == 1;
+
+        let ltp_state = self.ltp_state.iter_mut().flat_map(|ltp_state| ltp_state.iter_mut());
+
+        for sample in self.delay.iter_mut().chain(ltp_state) {
+            *sample = reader.read_f32()?;
+        }
+
+        Ok(())
     }
 
     fn decode_section_data<B: ReadBitsLtr>(&mut self, bs: &mut B) -> Result<()> {
@@ -357,6 +419,8 @@ impl Ics {
 
         let bands = self.get_bands();
 
+        let win_len = bands[bands.len() - 1];
+
         for g in 0..self.info.window_groups {
             let cur_w = self.info.get_group_start(g);
             let next_w = self.info.get_group_start(g + 1);
@@ -368,7 +432,7 @@ impl Ics {
                 let scale = self.scales[g][sfb];
 
                 for w in cur_w..next_w {
-                    let dst = &mut self.coeffs[start + w * 128..end + w * 128];
+                    let dst = &mut self.coeffs[start + w * win_len..end + w * win_len];
 
                     // Derived from ISO/IEC-14496-3 Table 4.151.
                     match cb_idx {
@@ -407,9 +471,12 @@ impl Ics {
 
         // If a common window is used, a common ICS info was decoded previously.
         if !common_window {
-            self.info.decode(bs)?;
+            self.info.decode(bs, m4atype)?;
         }
 
+        // The maximum scale factor band must be within the scale factor band table.
+        validate!(self.info.max_sfb < self.get_bands().len());
+
         self.decode_section_data(bs)?;
 
         self.decode_scale_factor_data(bs)?;
@@ -441,6 +508,18 @@ impl Ics {
             pulse.synth(bands, &self.scales, &mut self.coeffs);
         }
 
+        if let (Some(ltp), Some(ltp_state)) = (&self.info.ltp, &self.ltp_state) {
+            ltp.predict(
+                dsp,
+                &self.info,
+                self.tns.as_ref(),
+                bands,
+                rate_idx,
+                ltp_state,
+                &mut self.coeffs,
+            );
+        }
+
         if let Some(tns) = &self.tns {
             tns.synth(&self.info, bands, rate_idx, &mut self.coeffs);
         }
@@ -453,6 +532,10 @@ impl Ics {
             self.info.prev_window_shape,
             dst,
         );
+
+        if let Some(ltp_state) = &mut self.ltp_state {
+            ltp::update_state(ltp_state, dst, &self.delay);
+        }
     }
 }
 
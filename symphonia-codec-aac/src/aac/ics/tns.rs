@@ -146,12 +146,38 @@ impl Tns {
         Ok(Some(Self { n_filt, coeffs }))
     }
 
+    /// Apply the TNS synthesis (all-pole) filter to the spectral coefficients. This is the decoding
+    /// step.
     pub fn synth(
         &self,
         info: &IcsInfo,
         bands: &[usize],
         rate_idx: usize,
         coeffs: &mut [f32; 1024],
+    ) {
+        self.filter(info, bands, rate_idx, coeffs, false);
+    }
+
+    /// Apply the TNS analysis (all-zero) filter to the spectral coefficients. This is the inverse
+    /// of the synthesis filter and is only required to process the predicted spectrum of long-term
+    /// prediction.
+    pub fn analyze(
+        &self,
+        info: &IcsInfo,
+        bands: &[usize],
+        rate_idx: usize,
+        coeffs: &mut [f32; 1024],
+    ) {
+        self.filter(info, bands, rate_idx, coeffs, true);
+    }
+
+    fn filter(
+        &self,
+        info: &IcsInfo,
+        bands: &[usize],
+        rate_idx: usize,
+        coeffs: &mut [f32; 1024],
+        is_analysis: bool,
     ) {
         let tns_max_bands = (if info.long_win {
             TNS_MAX_LONG_BANDS[rate_idx]
@@ -161,14 +187,16 @@ impl Tns {
         })
         .min(info.max_sfb);
 
+        // The number of spectral coefficients per window.
+        let win_len = bands[bands.len() - 1];
+
         for w in 0..info.num_windows {
             let mut bottom = bands.len() - 1;
 
             for f in 0..self.n_filt[w] {
                 let top = bottom;
 
-                bottom =
-                    if top > self.coeffs[w][f].length { top - self.coeffs[w][f].length } else { 0 };
+                bottom = top.saturating_sub(self.coeffs[w][f].length);
 
                 let order = self.coeffs[w][f].order;
 
@@ -176,12 +204,31 @@ impl Tns {
                     continue;
                 }
 
-                let start = w * 128 + bands[bottom.min(tns_max_bands)];
-                let end = w * 128 + bands[top.min(tns_max_bands)];
+                let start = w * win_len + bands[bottom.min(tns_max_bands)];
+                let end = w * win_len + bands[top.min(tns_max_bands)];
 
                 let lpc = &self.coeffs[w][f].coef;
 
-                if !self.coeffs[w][f].direction {
+                if is_analysis {
+                    // The analysis filter uses the unfiltered coefficients as input. Process the
+                    // coefficients in the opposite order of the filter direction such that the
+                    // input coefficients are not overwritten before they are used.
+                    if !self.coeffs[w][f].direction {
+                        for i in (start..end).rev() {
+                            for j in 0..order.min(i - start) {
+                                coeffs[i] += coeffs[i - j - 1] * lpc[j];
+                            }
+                        }
+                    }
+                    else {
+                        for i in start..end {
+                            for j in 0..order.min(end - 1 - i) {
+                                coeffs[i] += coeffs[i + j + 1] * lpc[j];
+                            }
+                        }
+                    }
+                }
+                else if !self.coeffs[w][f].direction {
                     for (m, i) in (start..end).enumerate() {
                         for j in 0..order.min(m) {
                             coeffs[i] -= coeffs[i - j - 1] * lpc[j];
@@ -12,11 +12,12 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{state::DecoderState, Decoder, DecoderOptions, FinalizeResult};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_AAC};
-use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
-use symphonia_core::io::{BitReaderLtr, FiniteBitStream, ReadBitsLtr};
+use symphonia_core::io::{BitReaderLtr, BufReader, FiniteBitStream, FiniteStream};
+use symphonia_core::io::{ReadBitsLtr, ReadBytes};
 use symphonia_core::support_codec;
 use symphonia_core::units::Duration;
 
@@ -116,6 +117,7 @@ impl M4AInfo {
             M4AType::Main
             | M4AType::Lc
             | M4AType::Ssr
+            | M4AType::Ltp
             | M4AType::Scalable
             | M4AType::TwinVQ
             | M4AType::ER_AAC_LC
@@ -289,8 +291,9 @@ impl std::fmt::Display for M4AInfo {
 
 /// Advanced Audio Coding (AAC) decoder.
 ///
-/// Implements a decoder for Advanced Audio Decoding Low-Complexity (AAC-LC) as defined in
-/// ISO/IEC 13818-7 and ISO/IEC 14496-3.
+/// Implements a decoder for Advanced Audio Decoding Low-Complexity (AAC-LC), and Long Term
+/// Prediction (AAC-LTP), with 1024 or 960 sample frames, as defined in ISO/IEC 13818-7 and ISO/IEC
+/// 14496-3.
 pub struct AacDecoder {
     // info: NACodecInfoRef,
     m4ainfo: M4AInfo,
@@ -304,7 +307,7 @@ pub struct AacDecoder {
 impl AacDecoder {
     fn set_pair(&mut self, pair_no: usize, channel: usize, pair: bool) -> Result<()> {
         if self.pairs.len() <= pair_no {
-            self.pairs.push(cpe::ChannelPair::new(pair, channel, self.sbinfo));
+            self.pairs.push(cpe::ChannelPair::new(pair, channel, self.sbinfo, self.m4ainfo.otype));
         }
         else {
             validate!(self.pairs[pair_no].channel == channel);
@@ -399,6 +402,40 @@ impl AacDecoder {
     //     }
     // }
 
+    /// Restores the channel pairs from a state previously saved by `save_state`.
+    fn restore_pairs(&self, reader: &mut BufReader<'_>) -> Result<Vec<cpe::ChannelPair>> {
+        let n_pairs = usize::from(reader.read_u8()?);
+
+        let mut pairs = Vec::with_capacity(n_pairs);
+        let mut channel = 0;
+
+        for _ in 0..n_pairs {
+            let is_pair = match reader.read_u8()? {
+                0 => false,
+                1 => true,
+                _ => return decode_error("aac: invalid decoder state channel pair"),
+            };
+
+            // The channel pairs are created in the order of their channels.
+            if usize::from(reader.read_u8()?) != channel {
+                return decode_error("aac: invalid decoder state channel pair");
+            }
+
+            let mut pair = cpe::ChannelPair::new(is_pair, channel, self.sbinfo, self.m4ainfo.otype);
+            pair.restore_state(reader)?;
+
+            pairs.push(pair);
+
+            channel += if is_pair { 2 } else { 1 };
+
+            if channel > self.m4ainfo.channels {
+                return decode_error("aac: invalid decoder state channel pair");
+            }
+        }
+
+        Ok(pairs)
+    }
+
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
         // Clear the audio output buffer.
         self.buf.clear();
@@ -408,7 +445,7 @@ impl AacDecoder {
 
         // Choose decode step based on the object type.
         match self.m4ainfo.otype {
-            M4AType::Lc => self.decode_ga(&mut bs)?,
+            M4AType::Lc | M4AType::Ltp => self.decode_ga(&mut bs)?,
             _ => return unsupported_error("aac: object type"),
         }
 
@@ -453,7 +490,7 @@ impl Decoder for AacDecoder {
 
         //print!("edata:"); for s in edata.iter() { print!(" {:02X}", *s);}println!("");
 
-        if (m4ainfo.otype != M4AType::Lc) || (m4ainfo.channels > 2) || (m4ainfo.samples != 1024) {
+        if !matches!(m4ainfo.otype, M4AType::Lc | M4AType::Ltp) || (m4ainfo.channels > 2) {
             return unsupported_error("aac: aac too complex");
         }
 
@@ -461,12 +498,13 @@ impl Decoder for AacDecoder {
 
         let duration = m4ainfo.samples as Duration;
         let srate = m4ainfo.srate;
+        let short_frame = m4ainfo.samples == 960;
 
         Ok(AacDecoder {
             m4ainfo,
             pairs: Vec::new(),
-            dsp: dsp::Dsp::new(),
-            sbinfo: GASubbandInfo::find(srate),
+            dsp: dsp::Dsp::new(duration as usize),
+            sbinfo: GASubbandInfo::find(srate, short_frame),
             params: params.clone(),
             buf: AudioBuffer::new(duration, spec),
         })
@@ -478,6 +516,38 @@ impl Decoder for AacDecoder {
         }
     }
 
+    fn save_state(&self) -> Option<DecoderState> {
+        // The channel pairs are created as they are first decoded, so save their layout with their
+        // state.
+        let mut data = vec![self.pairs.len() as u8];
+
+        for pair in self.pairs.iter() {
+            data.push(u8::from(pair.is_pair));
+            data.push(pair.channel as u8);
+            pair.save_state(&mut data);
+        }
+
+        Some(DecoderState::new(CODEC_TYPE_AAC, data.into_boxed_slice()))
+    }
+
+    fn restore_state(&mut self, state: &DecoderState) -> Result<()> {
+        if state.codec != CODEC_TYPE_AAC {
+            return decode_error("aac: decoder state is for a different codec");
+        }
+
+        let mut reader = BufReader::new(&state.data);
+
+        // Restore into new channel pairs so that the current state is unchanged on error.
+        let pairs = self.restore_pairs(&mut reader)?;
+
+        if reader.bytes_available() != 0 {
+            return decode_error("aac: invalid decoder state length");
+        }
+
+        self.pairs = pairs;
+        Ok(())
+    }
+
     fn supported_codecs() -> &'static [CodecDescriptor] {
         &[support_codec!(CODEC_TYPE_AAC, "aac", "Advanced Audio Coding")]
     }
@@ -487,9 +557,15 @@ impl Decoder for AacDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
         if let Err(e) = self.decode_inner(packet) {
             self.buf.clear();
-            Err(e)
+            Err(e.into_packet_error())
         }
         else {
             Ok(self.buf.as_audio_buffer_ref())
@@ -504,3 +580,256 @@ impl Decoder for AacDecoder {
         self.buf.as_audio_buffer_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::audio::{AudioBufferRef, Signal};
+    use symphonia_core::codecs::state::DecoderState;
+    use symphonia_core::codecs::{CodecParameters, Decoder, DecoderOptions};
+    use symphonia_core::codecs::{CODEC_TYPE_AAC, CODEC_TYPE_MP3};
+    use symphonia_core::formats::Packet;
+
+    use super::AacDecoder;
+
+    /// The AAC-LC object type.
+    const LC: u8 = 2;
+    /// The AAC-LTP object type.
+    const LTP: u8 = 4;
+
+    /// Instantiates an AAC decoder for 44.1 kHz mono audio of object type `otype` with frames of
+    /// `frame_len` (1024 or 960) samples.
+    fn make_decoder(otype: u8, frame_len: usize) -> AacDecoder {
+        // The audio specific config, the frame length flag is the sixth bit of the second byte.
+        let short_frame = if frame_len == 960 { 0x04 } else { 0x00 };
+
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_extra_data(Box::new([otype << 3 | 0x02, 0x08 | short_frame]));
+
+        AacDecoder::try_new(&params, &DecoderOptions::default()).unwrap()
+    }
+
+    /// Packs bit fields, given as (value, length) pairs, into bytes.
+    fn pack_bits(fields: &[(u32, u32)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut n_bits = 0;
+
+        for &(value, len) in fields {
+            for i in (0..len).rev() {
+                if n_bits % 8 == 0 {
+                    buf.push(0);
+                }
+
+                if (value >> i) & 1 != 0 {
+                    *buf.last_mut().unwrap() |= 0x80 >> (n_bits % 8);
+                }
+
+                n_bits += 1;
+            }
+        }
+
+        buf
+    }
+
+    /// Builds a raw data block of a single channel element with an only long window sequence, a
+    /// sine window shape, and one scale factor band. If `tone` is true, the band is coded with
+    /// codebook 1, and spectral coefficient 1 is 1.0. Otherwise, the band is zero. If `ltp` is
+    /// provided, the band is predicted with the long-term prediction lag and coefficient index.
+    fn make_frame(tone: bool, ltp: Option<(u32, u32)>) -> Vec<u8> {
+        // The element ID and tag, the global gain, and the ICS info up to max_sfb.
+        let mut fields = vec![(0, 3), (0, 4), (100, 8), (0, 1), (0, 2), (0, 1), (1, 6)];
+
+        // Predictor data, and long-term prediction data for the band.
+        match ltp {
+            Some((lag, coef)) => {
+                fields.extend_from_slice(&[(1, 1), (1, 1), (lag, 11), (coef, 3), (1, 1)])
+            }
+            None => fields.push((0, 1)),
+        }
+
+        if tone {
+            // The section data, a scale factor equal to the global gain, no pulse, TNS, or gain
+            // control data, and the quad (0, 1, 0, 0).
+            fields.extend_from_slice(&[(1, 4), (1, 5), (0, 1), (0, 3), (0x13, 5)]);
+        }
+        else {
+            // The section data, and no pulse, TNS, or gain control data.
+            fields.extend_from_slice(&[(0, 4), (1, 5), (0, 3)]);
+        }
+
+        // The end element.
+        fields.push((7, 3));
+
+        pack_bits(&fields)
+    }
+
+    /// Decodes `frames`, and returns the decoded audio of each frame.
+    fn decode(decoder: &mut AacDecoder, frames: &[Vec<u8>]) -> Vec<Vec<f32>> {
+        frames
+            .iter()
+            .map(|frame| match decoder.decode(&Packet::new_from_slice(0, 0, 0, frame)).unwrap() {
+                AudioBufferRef::F32(buf) => buf.chan(0).to_vec(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// Asserts that `actual` is equal to `expected` within a tolerance relative to the peak of
+    /// `expected`.
+    fn assert_approx_eq(actual: &[f32], expected: &[f32]) {
+        let peak = expected.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+        assert!(peak > 0.0);
+        assert_eq!(actual.len(), expected.len());
+
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() <= 1e-4 * peak, "{} != {}", a, e);
+        }
+    }
+
+    #[test]
+    fn verify_decode_frame_len() {
+        use std::f64::consts::PI;
+
+        for &frame_len in &[1024, 960] {
+            let mut decoder = make_decoder(LC, frame_len);
+
+            let audio = decode(&mut decoder, &[make_frame(true, None), make_frame(false, None)]);
+
+            // The frame with the tone outputs the first half of the windowed IMDCT of spectral
+            // coefficient 1, and the next frame outputs the second half. Section 4.6.11.3.
+            let n = 2 * frame_len;
+            let n0 = (n as f64 / 2.0 + 1.0) / 2.0;
+
+            let expected: Vec<f32> = (0..n)
+                .map(|i| {
+                    let window = (PI * (i as f64 + 0.5) / n as f64).sin();
+                    let basis = (2.0 * PI / n as f64 * (i as f64 + n0) * 1.5).cos();
+                    (window * basis) as f32
+                })
+                .collect();
+
+            // The gain of the IMDCT is not important, only the shape of the output.
+            let gain = audio[0].iter().zip(&expected).map(|(a, e)| a * e).sum::<f32>()
+                / expected[..frame_len].iter().map(|e| e * e).sum::<f32>();
+
+            let expected: Vec<f32> = expected.iter().map(|e| gain * e).collect();
+
+            assert_approx_eq(&audio[0], &expected[..frame_len]);
+            assert_approx_eq(&audio[1], &expected[frame_len..]);
+        }
+    }
+
+    #[test]
+    fn verify_decode_ltp() {
+        const LTP_COEF: [f32; 8] =
+            [0.570829, 0.696616, 0.813004, 0.911304, 0.984900, 1.067894, 1.194601, 1.369533];
+
+        for &frame_len in &[1024, 960] {
+            // The reference decodes a tone followed by a zero frame.
+            let mut decoder = make_decoder(LTP, frame_len);
+
+            let expected = decode(&mut decoder, &[make_frame(true, None), make_frame(false, None)]);
+
+            for (coef, &ltp_coef) in LTP_COEF.iter().enumerate() {
+                let frames = [
+                    make_frame(true, None),
+                    make_frame(false, Some((frame_len as u32, coef as u32))),
+                ];
+
+                let mut decoder = make_decoder(LTP, frame_len);
+                let audio = decode(&mut decoder, &frames);
+
+                // With a lag of one frame, the predicted signal is the windowed IMDCT output of the
+                // tone. The MDCT of it, windowed again, is the spectrum of the tone. Therefore, the
+                // prediction adds the tone, scaled by the coefficient, to the zero frame.
+                let predicted: Vec<f32> = expected[1]
+                    .iter()
+                    .zip(&expected[0])
+                    .map(|(overlap, tone)| overlap + ltp_coef * tone)
+                    .collect();
+
+                assert_eq!(audio[0], expected[0]);
+                assert_approx_eq(&audio[1], &predicted);
+            }
+        }
+    }
+
+    /// Builds the state of a mono decoder. The overlap of the channel is `overlap`, and all other
+    /// state is 0.
+    fn make_state(overlap: &[f32], ltp_len: usize) -> Vec<u8> {
+        // One single channel element at channel 0, and the noise generator state.
+        let mut data = vec![1, 0, 0];
+        data.extend_from_slice(&0x1f2e3d4cu32.to_le_bytes());
+
+        // The first channel, with an only long window sequence, and a sine window shape.
+        data.extend_from_slice(&[0, 0]);
+
+        for sample in overlap.iter().chain(std::iter::repeat(&0.0).take(ltp_len)) {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        // The unused second channel.
+        data.extend_from_slice(&[0, 0]);
+        data.resize(data.len() + 4 * (1024 + ltp_len), 0);
+
+        data
+    }
+
+    #[test]
+    fn verify_save_restore_state() {
+        let mut decoder = make_decoder(LC, 1024);
+
+        // No channels have been decoded yet.
+        assert_eq!(decoder.save_state().unwrap().data[..], [0]);
+
+        let overlap: Vec<f32> = (0..1024).map(|i| i as f32 / 1024.0).collect();
+
+        let state = DecoderState::new(CODEC_TYPE_AAC, make_state(&overlap, 0).into_boxed_slice());
+
+        decoder.restore_state(&state).unwrap();
+        assert_eq!(decoder.save_state().unwrap(), state);
+
+        // A single channel element with a zeroed spectrum outputs the restored overlap.
+        let packet = Packet::new_from_slice(0, 0, 1024, &[0x00, 0xc8, 0x00, 0x07]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => assert_eq!(buf.chan(0), &overlap[..]),
+            _ => unreachable!(),
+        }
+
+        // The long-term prediction state is saved for AAC-LTP.
+        let mut ltp_decoder = make_decoder(LTP, 1024);
+
+        let ltp_state = DecoderState::new(CODEC_TYPE_AAC, make_state(&overlap, 3 * 1024).into());
+
+        assert!(ltp_decoder.restore_state(&state).is_err());
+        ltp_decoder.restore_state(&ltp_state).unwrap();
+        assert_eq!(ltp_decoder.save_state().unwrap(), ltp_state);
+
+        // States for another codec, with invalid channels, or with missing or extra data, are
+        // rejected without changing the state of the decoder.
+        let current = decoder.save_state().unwrap();
+
+        let other = DecoderState { codec: CODEC_TYPE_MP3, ..state.clone() };
+        assert!(decoder.restore_state(&other).is_err());
+
+        let mut data = state.data.to_vec();
+        data[1] = 1;
+        assert!(decoder.restore_state(&DecoderState::new(CODEC_TYPE_AAC, data.into())).is_err());
+
+        let mut data = state.data.to_vec();
+        data[2] = 1;
+        assert!(decoder.restore_state(&DecoderState::new(CODEC_TYPE_AAC, data.into())).is_err());
+
+        let truncated =
+            DecoderState::new(CODEC_TYPE_AAC, state.data[..state.data.len() - 1].into());
+        assert!(decoder.restore_state(&truncated).is_err());
+
+        let extended = DecoderState::new(CODEC_TYPE_AAC, [&state.data[..], &[0]].concat().into());
+        assert!(decoder.restore_state(&extended).is_err());
+
+        assert_eq!(decoder.save_state().unwrap(), current);
+    }
+}
@@ -47,8 +47,8 @@ impl QueryDescriptor for AdtsReader {
         )]
     }
 
-    fn score(_context: &[u8]) -> u8 {
-        255
+    fn score(context: &[u8]) -> u8 {
+        score_adts_frames(context)
     }
 }
 
@@ -285,12 +285,7 @@ fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<
         source.ensure_seekback_buffer(MAX_LEN as usize);
         let mut scoped_stream = ScopedStream::new(&mut source, MAX_LEN);
 
-        loop {
-            let header = match AdtsHeader::read(&mut scoped_stream) {
-                Ok(header) => header,
-                _ => break,
-            };
-
+        while let Ok(header) = AdtsHeader::read(&mut scoped_stream) {
             if scoped_stream.ignore_bytes(header.frame_len as u64).is_err() {
                 break;
             }
@@ -339,3 +334,82 @@ fn approximate_frame_count(mut source: &mut MediaSourceStream) -> Result<Option<
         _ => Ok(Some(total_len / (n_bytes as u64 / parsed_n_frames) * SAMPLES_PER_AAC_PACKET)),
     }
 }
+
+/// The number of consecutive frames with consistent parameters required for a full probe score.
+const PROBE_SCORE_FRAMES: usize = 3;
+
+/// Scores a probe context buffer by the number of consecutive, consistent, ADTS frames it contains.
+///
+/// A frame that is followed by garbage, or by a frame with different parameters, is rejected with a
+/// score of 0. If the context ends before enough frames could be checked, the score is reduced
+/// proportionally.
+fn score_adts_frames(context: &[u8]) -> u8 {
+    let read_header_at = |pos: usize| -> Option<AdtsHeader> {
+        // The header must start exactly at the given position.
+        if context.get(pos..pos + 2)? != [0xff, 0xf1] {
+            return None;
+        }
+
+        AdtsHeader::read(&mut BufReader::new(&context[pos..])).ok()
+    };
+
+    let first = match read_header_at(0) {
+        Some(header) => header,
+        None => return 0,
+    };
+
+    let mut pos = AdtsHeader::SIZE + first.frame_len;
+    let mut n_frames = 1;
+
+    while n_frames < PROBE_SCORE_FRAMES && pos + AdtsHeader::SIZE <= context.len() {
+        match read_header_at(pos) {
+            Some(header)
+                if header.profile == first.profile
+                    && header.sample_rate == first.sample_rate
+                    && header.channels == first.channels =>
+            {
+                pos += AdtsHeader::SIZE + header.frame_len;
+                n_frames += 1;
+            }
+            _ => return 0,
+        }
+    }
+
+    ((255 * n_frames) / PROBE_SCORE_FRAMES) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AAC-LC, 44.1 kHz, stereo, 16 byte ADTS frame without CRC.
+    const FRAME: [u8; 7] = [0xff, 0xf1, 0x50, 0x80, 0x02, 0x1f, 0xfc];
+
+    fn push_frame(buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&FRAME);
+        buf.resize(buf.len() + 9, 0);
+    }
+
+    #[test]
+    fn verify_probe_score() {
+        let mut buf = Vec::new();
+
+        push_frame(&mut buf);
+        push_frame(&mut buf);
+        push_frame(&mut buf);
+
+        // Three consistent frames.
+        assert_eq!(score_adts_frames(&buf), 255);
+
+        // The context ends after one frame.
+        assert_eq!(score_adts_frames(&buf[..20]), 85);
+
+        // The second frame has a different sample rate.
+        buf[16 + 2] = 0x4c;
+        assert_eq!(score_adts_frames(&buf), 0);
+
+        // The first frame is followed by junk.
+        buf[16..18].copy_from_slice(&[0xff, 0xfb]);
+        assert_eq!(score_adts_frames(&buf), 0);
+    }
+}
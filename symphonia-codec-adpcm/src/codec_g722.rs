@@ -0,0 +1,253 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ITU-T G.722 sub-band ADPCM decoder. Only the 64 kbit/s mode, where each byte codes a 6-bit
+//! lower sub-band and a 2-bit higher sub-band sample, is supported.
+
+// Justification: The predictor update follows the structure of the specification.
+#![allow(clippy::needless_range_loop)]
+
+use crate::common::from_i16_shift;
+
+const WL: [i32; 8] = [-60, -30, 58, 172, 334, 538, 1198, 3042];
+
+const RL42: [usize; 16] = [0, 7, 6, 5, 4, 3, 2, 1, 7, 6, 5, 4, 3, 2, 1, 0];
+
+#[rustfmt::skip]
+const ILB: [i32; 32] = [
+    2048, 2093, 2139, 2186, 2233, 2282, 2332, 2383,
+    2435, 2489, 2543, 2599, 2656, 2714, 2774, 2834,
+    2896, 2960, 3025, 3091, 3158, 3228, 3298, 3371,
+    3444, 3520, 3597, 3676, 3756, 3838, 3922, 4008,
+];
+
+const WH: [i32; 3] = [0, -214, 798];
+
+const RH2: [usize; 4] = [2, 1, 2, 1];
+
+const QM2: [i32; 4] = [-7408, -1616, 7408, 1616];
+
+#[rustfmt::skip]
+const QM4: [i32; 16] = [
+    0, -20456, -12896, -8968, -6288, -4240, -2584, -1200,
+    20456, 12896, 8968, 6288, 4240, 2584, 1200, 0,
+];
+
+#[rustfmt::skip]
+const QM6: [i32; 64] = [
+    -136, -136, -136, -136, -24808, -21904, -19008, -16704,
+    -14984, -13512, -12280, -11192, -10232, -9360, -8576, -7856,
+    -7192, -6576, -6000, -5456, -4944, -4464, -4008, -3576,
+    -3168, -2776, -2400, -2032, -1688, -1360, -1040, -728,
+    24808, 21904, 19008, 16704, 14984, 13512, 12280, 11192,
+    10232, 9360, 8576, 7856, 7192, 6576, 6000, 5456,
+    4944, 4464, 4008, 3576, 3168, 2776, 2400, 2032,
+    1688, 1360, 1040, 728, 432, 136, -432, -136,
+];
+
+/// The coefficients of the receive quadrature mirror filter.
+const QMF_COEFFS: [i32; 12] = [3, -11, 12, 32, -210, 951, 3876, -805, 362, -156, 53, -11];
+
+#[inline(always)]
+fn saturate(x: i32) -> i32 {
+    x.clamp(i32::from(i16::MIN), i32::from(i16::MAX))
+}
+
+/// The adaptive predictor state of one sub-band.
+#[derive(Clone, Default)]
+struct Band {
+    /// The predicted signal.
+    s: i32,
+    /// The pole section of the predicted signal.
+    sp: i32,
+    /// The zero section of the predicted signal.
+    sz: i32,
+    /// The reconstructed signal history.
+    r: [i32; 3],
+    /// The pole predictor coefficients.
+    a: [i32; 3],
+    /// The zero predictor coefficients.
+    b: [i32; 7],
+    /// The partially reconstructed signal history.
+    p: [i32; 3],
+    /// The quantized difference signal history.
+    d: [i32; 7],
+    /// The logarithmic quantizer scale factor.
+    nb: i32,
+    /// The quantizer scale factor.
+    det: i32,
+}
+
+impl Band {
+    fn new(det: i32) -> Self {
+        Band { det, ..Default::default() }
+    }
+
+    /// Updates the predictor with the quantized difference signal `d` (block 4).
+    fn update(&mut self, d: i32) {
+        let mut ap = [0; 3];
+        let mut bp = [0; 7];
+
+        // RECONS
+        self.d[0] = d;
+        self.r[0] = saturate(self.s + d);
+
+        // PARREC
+        self.p[0] = saturate(self.sz + d);
+
+        // UPPOL2
+        let sg0 = self.p[0] >> 15;
+        let sg1 = self.p[1] >> 15;
+        let sg2 = self.p[2] >> 15;
+
+        let wd1 = saturate(self.a[1] << 2);
+        let wd2 = if sg0 == sg1 { -wd1 } else { wd1 };
+        let wd2 = wd2.min(32767);
+        let wd3 = (wd2 >> 7) + if sg0 == sg2 { 128 } else { -128 } + ((self.a[2] * 32512) >> 15);
+
+        ap[2] = wd3.clamp(-12288, 12288);
+
+        // UPPOL1
+        let wd1 = if sg0 == sg1 { 192 } else { -192 };
+        let wd2 = (self.a[1] * 32640) >> 15;
+        let wd3 = saturate(15360 - ap[2]);
+
+        ap[1] = saturate(wd1 + wd2).clamp(-wd3, wd3);
+
+        // UPZERO
+        let wd1 = if d == 0 { 0 } else { 128 };
+        let sg0 = d >> 15;
+
+        for i in 1..7 {
+            let wd2 = if (self.d[i] >> 15) == sg0 { wd1 } else { -wd1 };
+            let wd3 = (self.b[i] * 32640) >> 15;
+            bp[i] = saturate(wd2 + wd3);
+        }
+
+        // DELAYA
+        for i in (1..7).rev() {
+            self.d[i] = self.d[i - 1];
+            self.b[i] = bp[i];
+        }
+
+        for i in (1..3).rev() {
+            self.r[i] = self.r[i - 1];
+            self.p[i] = self.p[i - 1];
+            self.a[i] = ap[i];
+        }
+
+        // FILTEP
+        let wd1 = (self.a[1] * saturate(self.r[1] + self.r[1])) >> 15;
+        let wd2 = (self.a[2] * saturate(self.r[2] + self.r[2])) >> 15;
+        self.sp = saturate(wd1 + wd2);
+
+        // FILTEZ
+        let mut sz = 0;
+        for i in (1..7).rev() {
+            sz += (self.b[i] * saturate(self.d[i] + self.d[i])) >> 15;
+        }
+        self.sz = saturate(sz);
+
+        // PREDIC
+        self.s = saturate(self.sp + self.sz);
+    }
+}
+
+/// The state of a G.722 decoder. Unlike the other ADPCM codecs, G.722 has no block preamble and
+/// its state carries over from one packet to the next.
+pub(crate) struct G722Decoder {
+    low: Band,
+    high: Band,
+    /// The receive QMF delay line.
+    x: [i32; 24],
+}
+
+impl G722Decoder {
+    pub(crate) fn new() -> Self {
+        G722Decoder { low: Band::new(32), high: Band::new(8), x: [0; 24] }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = G722Decoder::new();
+    }
+
+    /// Decodes each code byte in `data` into two samples in `buffer`.
+    pub(crate) fn decode(&mut self, data: &[u8], buffer: &mut [i32]) {
+        for (&code, out) in data.iter().zip(buffer.chunks_exact_mut(2)) {
+            let ilow = usize::from(code & 0x3f);
+            let ihigh = usize::from(code >> 6);
+
+            // Lower sub-band: inverse quantize with the 6-bit quantizer for the output, and with
+            // the truncated 4-bit quantizer for the predictor.
+            let rlow = (self.low.s + ((self.low.det * QM6[ilow]) >> 15)).clamp(-16384, 16383);
+
+            let ilow = ilow >> 2;
+            let dlow = (self.low.det * QM4[ilow]) >> 15;
+
+            self.low.nb = (((self.low.nb * 127) >> 7) + WL[RL42[ilow]]).clamp(0, 18432);
+            self.low.det = scale_factor(self.low.nb, 8);
+            self.low.update(dlow);
+
+            // Higher sub-band.
+            let dhigh = (self.high.det * QM2[ihigh]) >> 15;
+            let rhigh = (self.high.s + dhigh).clamp(-16384, 16383);
+
+            self.high.nb = (((self.high.nb * 127) >> 7) + WH[RH2[ihigh]]).clamp(0, 22528);
+            self.high.det = scale_factor(self.high.nb, 10);
+            self.high.update(dhigh);
+
+            // Recombine the sub-bands with the receive QMF.
+            self.x.copy_within(2.., 0);
+            self.x[22] = rlow + rhigh;
+            self.x[23] = rlow - rhigh;
+
+            let mut xout1 = 0;
+            let mut xout2 = 0;
+
+            for i in 0..12 {
+                xout2 += self.x[2 * i] * QMF_COEFFS[i];
+                xout1 += self.x[2 * i + 1] * QMF_COEFFS[11 - i];
+            }
+
+            out[0] = from_i16_shift!(saturate(xout1 >> 11));
+            out[1] = from_i16_shift!(saturate(xout2 >> 11));
+        }
+    }
+}
+
+/// Computes the linear quantizer scale factor from the logarithmic scale factor `nb`.
+fn scale_factor(nb: i32, shift: i32) -> i32 {
+    let wd1 = ILB[((nb >> 6) & 31) as usize];
+    let wd2 = shift - (nb >> 11);
+    let wd3 = if wd2 < 0 { wd1 << -wd2 } else { wd1 >> wd2 };
+    wd3 << 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::G722Decoder;
+
+    #[test]
+    fn verify_g722_decode() {
+        let data: Vec<u8> = (0..=255).cycle().take(1024).collect();
+
+        let mut decoder = G722Decoder::new();
+
+        let mut first = vec![0; 2 * data.len()];
+        decoder.decode(&data, &mut first);
+
+        // The decoder state must be fully cleared by a reset.
+        assert!(first.iter().any(|&s| s != 0));
+
+        decoder.reset();
+
+        let mut second = vec![0; 2 * data.len()];
+        decoder.decode(&data, &mut second);
+
+        assert_eq!(first, second);
+    }
+}
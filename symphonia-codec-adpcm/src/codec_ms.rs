@@ -130,10 +130,11 @@ pub(crate) fn decode_stereo<B: ReadBytes>(
     buffers[0][1] = from_i16_shift!(left_status.sample1);
     buffers[1][0] = from_i16_shift!(right_status.sample2);
     buffers[1][1] = from_i16_shift!(right_status.sample1);
-    for frame in 2..frames_per_block {
+    let [left, right] = buffers;
+    for (l, r) in left[2..frames_per_block].iter_mut().zip(&mut right[2..frames_per_block]) {
         let nibbles = stream.read_u8()?;
-        buffers[0][frame] = left_status.expand_nibble(nibbles, Nibble::Upper);
-        buffers[1][frame] = right_status.expand_nibble(nibbles, Nibble::Lower);
+        *l = left_status.expand_nibble(nibbles, Nibble::Upper);
+        *r = right_status.expand_nibble(nibbles, Nibble::Lower);
     }
     Ok(())
 }
@@ -19,22 +19,26 @@ use symphonia_core::support_codec;
 use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CodecType};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::codecs::{CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS};
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::codecs::{
+    CODEC_TYPE_ADPCM_G722, CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS,
+};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
 use symphonia_core::io::ReadBytes;
 
+mod codec_g722;
 mod codec_ima;
 mod codec_ms;
 mod common;
 
 fn is_supported_adpcm_codec(codec_type: CodecType) -> bool {
-    matches!(codec_type, CODEC_TYPE_ADPCM_MS | CODEC_TYPE_ADPCM_IMA_WAV)
+    matches!(codec_type, CODEC_TYPE_ADPCM_MS | CODEC_TYPE_ADPCM_IMA_WAV | CODEC_TYPE_ADPCM_G722)
 }
 
 enum InnerDecoder {
     AdpcmMs,
     AdpcmIma,
+    G722(Box<codec_g722::G722Decoder>),
 }
 
 impl InnerDecoder {
@@ -42,6 +46,7 @@ impl InnerDecoder {
         match *self {
             InnerDecoder::AdpcmMs => codec_ms::decode_mono,
             InnerDecoder::AdpcmIma => codec_ima::decode_mono,
+            InnerDecoder::G722(_) => unreachable!(),
         }
     }
 
@@ -51,6 +56,7 @@ impl InnerDecoder {
         match *self {
             InnerDecoder::AdpcmMs => codec_ms::decode_stereo,
             InnerDecoder::AdpcmIma => codec_ima::decode_stereo,
+            InnerDecoder::G722(_) => unreachable!(),
         }
     }
 }
@@ -64,6 +70,22 @@ pub struct AdpcmDecoder {
 
 impl AdpcmDecoder {
     fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        // G.722 is not block based, every byte of the packet codes two samples.
+        if let InnerDecoder::G722(decoder) = &mut self.inner_decoder {
+            let n_frames = 2 * packet.buf().len();
+
+            if n_frames > self.buf.capacity() {
+                return decode_error("adpcm (g722): packet is too large");
+            }
+
+            self.buf.clear();
+            self.buf.render_reserved(Some(n_frames));
+
+            decoder.decode(packet.buf(), self.buf.chan_mut(0));
+
+            return Ok(());
+        }
+
         let mut stream = packet.as_buf_reader();
 
         let frames_per_block = self.params.frames_per_block.unwrap() as usize;
@@ -137,6 +159,10 @@ impl Decoder for AdpcmDecoder {
         let inner_decoder = match params.codec {
             CODEC_TYPE_ADPCM_MS => InnerDecoder::AdpcmMs,
             CODEC_TYPE_ADPCM_IMA_WAV => InnerDecoder::AdpcmIma,
+            CODEC_TYPE_ADPCM_G722 if spec.channels.count() != 1 => {
+                return unsupported_error("adpcm (g722): only mono is supported")
+            }
+            CODEC_TYPE_ADPCM_G722 => InnerDecoder::G722(Box::new(codec_g722::G722Decoder::new())),
             _ => return unsupported_error("adpcm: codec is unsupported"),
         };
 
@@ -151,11 +177,15 @@ impl Decoder for AdpcmDecoder {
         &[
             support_codec!(CODEC_TYPE_ADPCM_MS, "adpcm_ms", "Microsoft ADPCM"),
             support_codec!(CODEC_TYPE_ADPCM_IMA_WAV, "adpcm_ima_wav", "ADPCM IMA WAV"),
+            support_codec!(CODEC_TYPE_ADPCM_G722, "adpcm_g722", "G.722 ADPCM"),
         ]
     }
 
     fn reset(&mut self) {
-        // No state is stored between packets, therefore do nothing.
+        // Only G.722 stores state between packets.
+        if let InnerDecoder::G722(decoder) = &mut self.inner_decoder {
+            decoder.reset();
+        }
     }
 
     fn codec_params(&self) -> &CodecParameters {
@@ -163,9 +193,15 @@ impl Decoder for AdpcmDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
         if let Err(e) = self.decode_inner(packet) {
             self.buf.clear();
-            Err(e)
+            Err(e.into_packet_error())
         }
         else {
             Ok(self.buf.as_audio_buffer_ref())
@@ -379,7 +379,7 @@ impl ElementChannel {
 
         // Decoding is performed on signed 32-bit numbers, however, the actual predicted samples
         // have a bit-width of `pred_bits`. Therefore, the top `32 - pred_bits` bits should be
-        // clipped.
+        // clipped. Like the reference decoder, all intermediate arithmetic wraps on overflow.
         let num_clip_bits = 32 - self.pred_bits;
 
         // An order of 31, or a mode of 15, are special cases where the predictor runs twice. The
@@ -414,12 +414,12 @@ impl ElementChannel {
                 .iter()
                 .rev()
                 .zip(&out[i - order..i])
-                .map(|(&coeff, &s)| coeff.wrapping_mul(s - past0))
+                .map(|(&coeff, &s)| coeff.wrapping_mul(s.wrapping_sub(past0)))
                 .fold(0i32, |sum, s| sum.wrapping_add(s));
 
             // Rewrite `1 << (self.shift - 1)` as `(1 << self.shift) >> 1` to prevent overflowing
             // when shift is 0.
-            let val = (sum + ((1 << self.shift) >> 1)) >> self.shift;
+            let val = sum.wrapping_add((1 << self.shift) >> 1) >> self.shift;
             out[i] = clip_msbs(out[i].wrapping_add(past0).wrapping_add(val), num_clip_bits);
 
             // Adjust the coefficients if the initial value of the residual was not 0.
@@ -431,12 +431,14 @@ impl ElementChannel {
                 if res > 0 {
                     // Positive residual case.
                     for (j, (coeff, &sample)) in iter {
-                        let val = past0 - sample;
+                        let val = past0.wrapping_sub(sample);
                         let sign = val.signum();
 
-                        *coeff -= sign;
+                        *coeff = coeff.wrapping_sub(sign);
 
-                        res -= (1 + j as i32) * ((sign * val) >> self.shift);
+                        res = res.wrapping_sub(
+                            (1 + j as i32).wrapping_mul(sign.wrapping_mul(val) >> self.shift),
+                        );
 
                         if res <= 0 {
                             break;
@@ -446,12 +448,14 @@ impl ElementChannel {
                 else {
                     // Negative residual case.
                     for (j, (coeff, &sample)) in iter {
-                        let val = past0 - sample;
+                        let val = past0.wrapping_sub(sample);
                         let sign = val.signum();
 
-                        *coeff += sign;
+                        *coeff = coeff.wrapping_add(sign);
 
-                        res -= (1 + j as i32) * ((-sign * val) >> self.shift);
+                        res = res.wrapping_sub(
+                            (1 + j as i32).wrapping_mul((-sign).wrapping_mul(val) >> self.shift),
+                        );
 
                         if res >= 0 {
                             break;
@@ -615,9 +619,15 @@ impl Decoder for AlacDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
         if let Err(e) = self.decode_inner(packet) {
             self.buf.clear();
-            Err(e)
+            Err(e.into_packet_error())
         }
         else {
             Ok(self.buf.as_audio_buffer_ref())
@@ -815,7 +825,53 @@ fn decorrelate_mid_side(out0: &mut [i32], out1: &mut [i32], weight: i32, shift:
     assert!(out0.len() == out1.len());
 
     for (s0, s1) in out0.iter_mut().zip(out1.iter_mut()) {
-        *s0 = *s0 + *s1 - ((*s1 * weight) >> shift);
-        *s1 = *s0 - *s1;
+        *s0 = s0.wrapping_add(*s1).wrapping_sub(s1.wrapping_mul(weight) >> shift);
+        *s1 = s0.wrapping_sub(*s1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decorrelate_mid_side, ElementChannel};
+
+    #[test]
+    fn verify_predict_extreme() {
+        // A 32-bit, high-order, predictor with extreme coefficients and samples. Intermediate values
+        // overflow, and must wrap like the reference decoder instead of panicking.
+        let mut lpc_coeffs = [0; 32];
+        lpc_coeffs[..8].copy_from_slice(&[i16::MAX.into(), i16::MIN.into(), 1, -1, 2, -2, 3, -3]);
+
+        let mut channel = ElementChannel {
+            pred_bits: 32,
+            kb: 14,
+            mb: 10,
+            mode: 0,
+            shift: 0,
+            pb_factor: 40,
+            lpc_order: 8,
+            lpc_coeffs,
+        };
+
+        let mut out: Vec<i32> = (0..64)
+            .map(|i| {
+                if i % 3 == 0 {
+                    i32::MAX
+                }
+                else {
+                    i32::MIN + i
+                }
+            })
+            .collect();
+
+        channel.predict(&mut out).unwrap();
+
+        // Mid-side decorrelation of extreme samples also wraps.
+        let mut out0 = vec![i32::MAX, i32::MIN, i32::MAX];
+        let mut out1 = vec![i32::MAX, i32::MAX, i32::MIN];
+
+        decorrelate_mid_side(&mut out0, &mut out1, 2, 1);
+
+        assert_eq!(out0, [-1, 0, -1]);
+        assert_eq!(out1, [i32::MIN, -i32::MAX, i32::MAX]);
     }
 }
@@ -0,0 +1,181 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::tables::QMF_WINDOW;
+
+/// The maximum number of gain control points in a QMF band.
+pub const MAX_GAIN_POINTS: usize = 7;
+
+/// The number of samples between gain control locations.
+const GAIN_LOC_SCALE: u32 = 3;
+
+/// The number of samples over which the gain is interpolated from one level to the next.
+const GAIN_LOC_SIZE: usize = 1 << GAIN_LOC_SCALE;
+
+/// The level code of unity gain.
+const GAIN_UNITY_LEVEL: usize = 4;
+
+/// The gain control points of a QMF band.
+#[derive(Copy, Clone, Default)]
+pub struct GainInfo {
+    pub num_points: usize,
+    /// The level code of each point.
+    pub lev: [usize; MAX_GAIN_POINTS],
+    /// The location code of each point.
+    pub loc: [usize; MAX_GAIN_POINTS],
+}
+
+/// Gets the gain of a level code.
+fn gain_level(lev: usize) -> f32 {
+    2.0f32.powi(GAIN_UNITY_LEVEL as i32 - lev as i32)
+}
+
+/// Gets the per-sample gain increment used to interpolate from level code `from` to `to`.
+fn gain_increment(from: usize, to: usize) -> f32 {
+    2.0f32.powf((from as f32 - to as f32) / GAIN_LOC_SIZE as f32)
+}
+
+/// Overlaps-and-adds the first half of the windowed IMDCT output `input` with `prev`, the second
+/// half of the previous IMDCT output, while applying the gain control points of the current frame,
+/// `now`. Each gain control point sets the gain up-to its location, after which the gain is
+/// interpolated to the gain of the next point. The input is scaled by the first gain of the next
+/// frame, `next`, to compensate for the gain the next frame will apply to it.
+///
+/// The second half of `input` is saved in `prev` for the next frame.
+pub fn gain_compensation(
+    input: &[f32],
+    prev: &mut [f32],
+    now: &GainInfo,
+    next: &GainInfo,
+    out: &mut [f32],
+) {
+    let n = prev.len();
+
+    let scale = if next.num_points > 0 { gain_level(next.lev[0]) } else { 1.0 };
+
+    let mut pos = 0;
+
+    for i in 0..now.num_points {
+        let last_pos = now.loc[i] << GAIN_LOC_SCALE;
+
+        let mut lev = gain_level(now.lev[i]);
+
+        let next_lev = if i + 1 < now.num_points { now.lev[i + 1] } else { GAIN_UNITY_LEVEL };
+        let inc = gain_increment(now.lev[i], next_lev);
+
+        // Apply a constant gain up-to the location of the point.
+        while pos < last_pos {
+            out[pos] = (input[pos] * scale + prev[pos]) * lev;
+            pos += 1;
+        }
+
+        // Interpolate to the gain of the next point.
+        while pos < last_pos + GAIN_LOC_SIZE {
+            out[pos] = (input[pos] * scale + prev[pos]) * lev;
+            lev *= inc;
+            pos += 1;
+        }
+    }
+
+    while pos < n {
+        out[pos] = input[pos] * scale + prev[pos];
+        pos += 1;
+    }
+
+    prev.copy_from_slice(&input[n..2 * n]);
+}
+
+/// The maximum number of samples per sub-band the inverse QMF may be given.
+const IQMF_MAX_LEN: usize = 512;
+
+/// The length of the inverse QMF delay line.
+const IQMF_DELAY_LEN: usize = 46;
+
+/// An inverse quadrature mirror filter that combines a low and high sub-band into one band of
+/// twice the sample rate.
+#[derive(Clone)]
+pub struct Iqmf {
+    delay: [f32; IQMF_DELAY_LEN],
+}
+
+impl Iqmf {
+    pub fn new() -> Self {
+        Iqmf { delay: [0.0; IQMF_DELAY_LEN] }
+    }
+
+    /// Synthesizes `2 * lo.len()` samples into `out` from the sub-bands `lo` and `hi`.
+    pub fn synthesize(&mut self, lo: &[f32], hi: &[f32], out: &mut [f32]) {
+        let n = lo.len();
+
+        assert!(n <= IQMF_MAX_LEN);
+        assert_eq!(hi.len(), n);
+
+        let mut temp = [0.0; IQMF_DELAY_LEN + 2 * IQMF_MAX_LEN];
+
+        temp[..IQMF_DELAY_LEN].copy_from_slice(&self.delay);
+
+        for ((&lo, &hi), temp) in lo.iter().zip(hi).zip(temp[IQMF_DELAY_LEN..].chunks_exact_mut(2))
+        {
+            temp[0] = lo + hi;
+            temp[1] = lo - hi;
+        }
+
+        for (i, out) in out[..2 * n].chunks_exact_mut(2).enumerate() {
+            let mut s0 = 0.0;
+            let mut s1 = 0.0;
+
+            for (temp, w) in temp[2 * i..].chunks_exact(2).zip(QMF_WINDOW.chunks_exact(2)) {
+                s0 += temp[0] * w[0];
+                s1 += temp[1] * w[1];
+            }
+
+            out[0] = s1;
+            out[1] = s0;
+        }
+
+        self.delay.copy_from_slice(&temp[2 * n..2 * n + IQMF_DELAY_LEN]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_gain_compensation() {
+        let input = [1.0; 512];
+        let mut prev = [0.0; 256];
+        let mut out = [0.0; 256];
+
+        // A single point at location 2 with level code 3 doubles the gain up-to sample 16, and
+        // then interpolates back to unity gain over 8 samples.
+        let now =
+            GainInfo { num_points: 1, lev: [3, 0, 0, 0, 0, 0, 0], loc: [2, 0, 0, 0, 0, 0, 0] };
+
+        gain_compensation(&input, &mut prev, &now, &GainInfo::default(), &mut out);
+
+        assert!(out[..16].iter().all(|&s| s == 2.0));
+        assert!((out[23] - 2.0f32.powf(1.0 / 8.0)).abs() < 1e-6);
+        assert!(out[24..].iter().all(|&s| s == 1.0));
+        assert!(prev.iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn verify_iqmf_dc() {
+        // A constant low sub-band must synthesize to a constant signal once the delay line is
+        // filled, since the prototype filter has unity gain at DC.
+        let mut iqmf = Iqmf::new();
+
+        let lo = [0.5; 256];
+        let hi = [0.0; 256];
+        let mut out = [0.0; 512];
+
+        iqmf.synthesize(&lo, &hi, &mut out);
+
+        assert!(out[48..].iter().all(|&s| (s - 0.5).abs() < 1e-3));
+    }
+}
@@ -0,0 +1,861 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![warn(rust_2018_idioms)]
+#![forbid(unsafe_code)]
+// The following lints are allowed in all Symphonia crates. Please see clippy.toml for their
+// justification.
+#![allow(clippy::comparison_chain)]
+#![allow(clippy::excessive_precision)]
+#![allow(clippy::identity_op)]
+#![allow(clippy::manual_range_contains)]
+
+use symphonia_core::support_codec;
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_ATRAC3};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::dsp::mdct::Imdct;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+
+mod dsp;
+mod tables;
+
+use dsp::{gain_compensation, GainInfo, Iqmf};
+use tables::*;
+
+/// The number of audio frames decoded from each ATRAC3 frame, per channel.
+const SAMPLES_PER_FRAME: usize = 1024;
+
+/// The number of spectral lines in each of the 4 QMF bands.
+const BAND_LEN: usize = 256;
+
+/// The maximum number of tonal components in a sound unit.
+const MAX_TONAL_COMPONENTS: usize = 64;
+
+/// The byte used to pad the start of the reversed second sound unit of a joint stereo frame.
+const JOINT_STEREO_SYNC: u8 = 0xf8;
+
+/// A tonal component is a short run of strong spectral lines that is coded separately from, and
+/// added to, the rest of the spectrum.
+#[derive(Copy, Clone, Default)]
+struct TonalComponent {
+    pos: usize,
+    num_coefs: usize,
+    coefs: [f32; 8],
+}
+
+/// The decoder state of one channel.
+struct ChannelUnit {
+    /// The gain control points of the previous and current frame. The current frame's points are
+    /// at index `1 - gain_switch`.
+    gain_blocks: [[GainInfo; 4]; 2],
+    gain_switch: usize,
+    components: Vec<TonalComponent>,
+    spectrum: [f32; SAMPLES_PER_FRAME],
+    /// The second half of the previous IMDCT output of each QMF band.
+    overlap: [f32; SAMPLES_PER_FRAME],
+    /// The time-domain output of each QMF band.
+    samples: [f32; SAMPLES_PER_FRAME],
+    iqmf: [Iqmf; 3],
+}
+
+impl ChannelUnit {
+    fn new() -> Self {
+        ChannelUnit {
+            gain_blocks: Default::default(),
+            gain_switch: 0,
+            components: Vec::with_capacity(MAX_TONAL_COMPONENTS),
+            spectrum: [0.0; SAMPLES_PER_FRAME],
+            overlap: [0.0; SAMPLES_PER_FRAME],
+            samples: [0.0; SAMPLES_PER_FRAME],
+            iqmf: [Iqmf::new(), Iqmf::new(), Iqmf::new()],
+        }
+    }
+
+    /// Decodes a sound unit, and synthesizes the time-domain samples of each QMF band.
+    fn decode<B: ReadBitsLtr>(
+        &mut self,
+        bs: &mut B,
+        imdct: &mut Imdct,
+        is_js_pair: bool,
+    ) -> Result<()> {
+        // The second sound unit of a joint stereo pair has a shorter id.
+        let is_valid_id =
+            if is_js_pair { bs.read_bits_leq32(2)? == 0x3 } else { bs.read_bits_leq32(6)? == 0x28 };
+
+        if !is_valid_id {
+            return decode_error("atrac3: invalid sound unit id");
+        }
+
+        // The number of coded QMF bands, minus 1.
+        let num_bands = bs.read_bits_leq32(2)? as usize;
+
+        read_gain_control(bs, &mut self.gain_blocks[1 - self.gain_switch], num_bands)?;
+
+        read_tonal_components(bs, &mut self.components, num_bands)?;
+
+        let num_subbands = read_spectrum(bs, &mut self.spectrum)?;
+
+        // Add the tonal components to the spectrum.
+        let mut last_tonal = None;
+
+        for c in self.components.iter() {
+            for (s, &coef) in self.spectrum[c.pos..c.pos + c.num_coefs].iter_mut().zip(&c.coefs) {
+                *s += coef;
+            }
+
+            last_tonal = last_tonal.max(Some(c.pos + c.num_coefs));
+        }
+
+        // The QMF bands after the band containing the last coded spectral line are silent.
+        let mut num_bands = (SUBBAND_TAB[num_subbands + 1] - 1) / BAND_LEN;
+
+        if let Some(last_tonal) = last_tonal {
+            num_bands = num_bands.max((last_tonal - 1) / BAND_LEN);
+        }
+
+        let (gain_now, gain_next) = {
+            let (a, b) = self.gain_blocks.split_at(1);
+            if self.gain_switch == 0 {
+                (&a[0], &b[0])
+            }
+            else {
+                (&b[0], &a[0])
+            }
+        };
+
+        let mut imdct_out = [0.0; 2 * BAND_LEN];
+
+        for band in 0..4 {
+            let range = band * BAND_LEN..(band + 1) * BAND_LEN;
+
+            if band <= num_bands {
+                let spec = &mut self.spectrum[range.clone()];
+
+                // The spectrum of the odd QMF bands is reversed.
+                if band & 1 == 1 {
+                    spec.reverse();
+                }
+
+                imdct.imdct(spec, &mut imdct_out);
+
+                for (s, &w) in imdct_out.iter_mut().zip(MDCT_WINDOW.iter()) {
+                    *s *= w;
+                }
+            }
+            else {
+                imdct_out.iter_mut().for_each(|s| *s = 0.0);
+            }
+
+            gain_compensation(
+                &imdct_out,
+                &mut self.overlap[range.clone()],
+                &gain_now[band],
+                &gain_next[band],
+                &mut self.samples[range],
+            );
+        }
+
+        self.gain_switch ^= 1;
+
+        Ok(())
+    }
+
+    /// Combines the 4 QMF bands into `out`.
+    fn synthesize(&mut self, out: &mut [f32]) {
+        let mut lo = [0.0; 2 * BAND_LEN];
+        let mut hi = [0.0; 2 * BAND_LEN];
+
+        let (band0, rest) = self.samples.split_at(BAND_LEN);
+        let (band1, rest) = rest.split_at(BAND_LEN);
+        let (band2, band3) = rest.split_at(BAND_LEN);
+
+        // The upper half-band is spectrally inverted, so the order of its sub-bands is swapped.
+        self.iqmf[0].synthesize(band0, band1, &mut lo);
+        self.iqmf[1].synthesize(band3, band2, &mut hi);
+        self.iqmf[2].synthesize(&lo, &hi, out);
+    }
+}
+
+/// Reads the gain control points of the first `num_bands + 1` QMF bands. The remaining bands have
+/// no gain control points.
+fn read_gain_control<B: ReadBitsLtr>(
+    bs: &mut B,
+    gains: &mut [GainInfo; 4],
+    num_bands: usize,
+) -> Result<()> {
+    for (band, gain) in gains.iter_mut().enumerate() {
+        if band > num_bands {
+            gain.num_points = 0;
+            continue;
+        }
+
+        gain.num_points = bs.read_bits_leq32(3)? as usize;
+
+        for i in 0..gain.num_points {
+            gain.lev[i] = bs.read_bits_leq32(4)? as usize;
+            gain.loc[i] = bs.read_bits_leq32(5)? as usize;
+
+            // The locations must be strictly increasing.
+            if i > 0 && gain.loc[i] <= gain.loc[i - 1] {
+                return decode_error("atrac3: invalid gain control location");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `mantissas.len()` quantized spectral coefficients using the quantizer `selector`.
+fn read_mantissas<B: ReadBitsLtr>(
+    bs: &mut B,
+    selector: usize,
+    is_clc: bool,
+    mantissas: &mut [i32],
+) -> Result<()> {
+    if is_clc {
+        let bits = CLC_LENGTH_TAB[selector];
+
+        if selector == 1 {
+            // Each 4-bit code is a pair of 2-bit mantissas.
+            for pair in mantissas.chunks_exact_mut(2) {
+                let code = bs.read_bits_leq32(bits)? as usize;
+                pair[0] = MANTISSA_CLC_TAB[code >> 2];
+                pair[1] = MANTISSA_CLC_TAB[code & 0x3];
+            }
+        }
+        else {
+            for m in mantissas.iter_mut() {
+                *m = bs.read_bits_leq32_signed(bits)?;
+            }
+        }
+    }
+    else {
+        let codebook = &SPECTRAL_CODEBOOKS[selector - 1];
+
+        if selector == 1 {
+            // Each code is a pair of mantissas.
+            for pair in mantissas.chunks_exact_mut(2) {
+                let (value, _) = bs.read_codebook(codebook)?;
+                let (m0, m1) = MANTISSA_VLC_TAB[usize::from(value)];
+                pair[0] = m0;
+                pair[1] = m1;
+            }
+        }
+        else {
+            // The codes are ordered 0, 1, -1, 2, -2, etc.
+            for m in mantissas.iter_mut() {
+                let (value, _) = bs.read_codebook(codebook)?;
+                let value = i32::from(value) + 1;
+                *m = if value & 1 == 1 { -(value >> 1) } else { value >> 1 };
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the tonal components of the first `num_bands + 1` QMF bands into `components`.
+fn read_tonal_components<B: ReadBitsLtr>(
+    bs: &mut B,
+    components: &mut Vec<TonalComponent>,
+    num_bands: usize,
+) -> Result<()> {
+    components.clear();
+
+    let num_groups = bs.read_bits_leq32(5)?;
+
+    if num_groups == 0 {
+        return Ok(());
+    }
+
+    let mode_selector = bs.read_bits_leq32(2)?;
+
+    if mode_selector == 2 {
+        return decode_error("atrac3: invalid tonal component coding mode");
+    }
+
+    let mut is_clc = mode_selector & 1 == 1;
+
+    for _ in 0..num_groups {
+        let mut band_flags = [false; 4];
+
+        for flag in band_flags[..=num_bands].iter_mut() {
+            *flag = bs.read_bool()?;
+        }
+
+        let values_per_component = bs.read_bits_leq32(3)? as usize + 1;
+
+        let selector = bs.read_bits_leq32(3)? as usize;
+
+        if selector <= 1 {
+            return decode_error("atrac3: invalid tonal component quantizer");
+        }
+
+        if mode_selector == 3 {
+            is_clc = bs.read_bool()?;
+        }
+
+        // Each QMF band is divided into 4 groups of 64 spectral lines.
+        for group in 0..4 * (num_bands + 1) {
+            if !band_flags[group >> 2] {
+                continue;
+            }
+
+            let num_coded = bs.read_bits_leq32(3)?;
+
+            for _ in 0..num_coded {
+                if components.len() >= MAX_TONAL_COMPONENTS {
+                    return decode_error("atrac3: too many tonal components");
+                }
+
+                let sf_index = bs.read_bits_leq32(6)? as usize;
+
+                let pos = 64 * group + bs.read_bits_leq32(6)? as usize;
+                let num_coefs = values_per_component.min(SAMPLES_PER_FRAME - pos);
+
+                let mut mantissas = [0; 8];
+                read_mantissas(bs, selector, is_clc, &mut mantissas[..num_coefs])?;
+
+                let scale = SF_TABLE[sf_index] * INV_MAX_QUANT[selector];
+
+                let mut component = TonalComponent { pos, num_coefs, ..Default::default() };
+
+                for (coef, &m) in component.coefs.iter_mut().zip(&mantissas[..num_coefs]) {
+                    *coef = m as f32 * scale;
+                }
+
+                components.push(component);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the spectrum into `spectrum`, and returns the index of the last coded subband.
+fn read_spectrum<B: ReadBitsLtr>(
+    bs: &mut B,
+    spectrum: &mut [f32; SAMPLES_PER_FRAME],
+) -> Result<usize> {
+    let num_subbands = bs.read_bits_leq32(5)? as usize;
+    let is_clc = bs.read_bool()?;
+
+    let mut selectors = [0; 32];
+    let mut sf_indices = [0; 32];
+
+    for selector in selectors[..=num_subbands].iter_mut() {
+        *selector = bs.read_bits_leq32(3)? as usize;
+    }
+
+    for (sf_index, &selector) in sf_indices.iter_mut().zip(&selectors[..=num_subbands]) {
+        if selector != 0 {
+            *sf_index = bs.read_bits_leq32(6)? as usize;
+        }
+    }
+
+    let mut mantissas = [0; 128];
+
+    for i in 0..=num_subbands {
+        let subband = &mut spectrum[SUBBAND_TAB[i]..SUBBAND_TAB[i + 1]];
+
+        if selectors[i] != 0 {
+            let mantissas = &mut mantissas[..subband.len()];
+
+            read_mantissas(bs, selectors[i], is_clc, mantissas)?;
+
+            let scale = SF_TABLE[sf_indices[i]] * INV_MAX_QUANT[selectors[i]];
+
+            for (s, &m) in subband.iter_mut().zip(mantissas.iter()) {
+                *s = m as f32 * scale;
+            }
+        }
+        else {
+            subband.iter_mut().for_each(|s| *s = 0.0);
+        }
+    }
+
+    spectrum[SUBBAND_TAB[num_subbands + 1]..].iter_mut().for_each(|s| *s = 0.0);
+
+    Ok(num_subbands)
+}
+
+/// Interpolates from `a` to `b` over 8 samples.
+#[inline(always)]
+fn interpolate(a: f32, b: f32, i: usize) -> f32 {
+    a + i as f32 * 0.125 * (b - a)
+}
+
+/// Undoes the stereo matrixing of each QMF band of a joint stereo pair. If the matrix of a band
+/// changed since the previous frame, the coefficients are interpolated over the first 8 samples.
+fn reverse_matrixing(su0: &mut [f32], su1: &mut [f32], prev: &[usize; 4], now: &[usize; 4]) {
+    for (band, (su0, su1)) in
+        su0.chunks_exact_mut(BAND_LEN).zip(su1.chunks_exact_mut(BAND_LEN)).enumerate()
+    {
+        let mut start = 0;
+
+        if prev[band] != now[band] {
+            let (l0, r0) = MATRIX_COEFFS[prev[band]];
+            let (l1, r1) = MATRIX_COEFFS[now[band]];
+
+            for i in 0..8 {
+                let c0 = su0[i];
+                let c1 = c0 * interpolate(l0, l1, i) + su1[i] * interpolate(r0, r1, i);
+                su0[i] = c1;
+                su1[i] = 2.0 * c0 - c1;
+            }
+
+            start = 8;
+        }
+
+        for (s0, s1) in su0[start..].iter_mut().zip(su1[start..].iter_mut()) {
+            let (c0, c1) = (*s0, *s1);
+
+            match now[band] {
+                0 => {
+                    *s0 = 2.0 * c1;
+                    *s1 = 2.0 * (c0 - c1);
+                }
+                1 => {
+                    *s0 = 2.0 * (c0 + c1);
+                    *s1 = -2.0 * c1;
+                }
+                _ => {
+                    *s0 = c0 + c1;
+                    *s1 = c0 - c1;
+                }
+            }
+        }
+    }
+}
+
+/// Gets the channel weights for a weighting index and swap flag.
+fn channel_weights(index: usize, swap: usize) -> (f32, f32) {
+    if index == 7 {
+        (1.0, 1.0)
+    }
+    else {
+        let w0 = index as f32 / 7.0;
+        let w1 = (2.0 - w0 * w0).sqrt();
+
+        if swap != 0 {
+            (w1, w0)
+        }
+        else {
+            (w0, w1)
+        }
+    }
+}
+
+/// Applies the channel weighting of a joint stereo pair to all but the lowest QMF band.
+fn channel_weighting(su0: &mut [f32], su1: &mut [f32], delay: &[usize; 6]) {
+    if delay[1] == 7 && delay[3] == 7 {
+        return;
+    }
+
+    let w_prev = channel_weights(delay[1], delay[0]);
+    let w_now = channel_weights(delay[3], delay[2]);
+
+    for (su0, su1) in su0.chunks_exact_mut(BAND_LEN).zip(su1.chunks_exact_mut(BAND_LEN)).skip(1) {
+        for i in 0..8 {
+            su0[i] *= interpolate(w_prev.0, w_prev.1, i);
+            su1[i] *= interpolate(w_now.0, w_now.1, i);
+        }
+
+        for (s0, s1) in su0[8..].iter_mut().zip(su1[8..].iter_mut()) {
+            *s0 *= w_now.0;
+            *s1 *= w_now.1;
+        }
+    }
+}
+
+/// The joint stereo state of a channel pair.
+struct JointStereo {
+    /// The channel weighting parameters of the previous, current, and next frames.
+    weighting_delay: [usize; 6],
+    matrix_prev: [usize; 4],
+    matrix_now: [usize; 4],
+    matrix_next: [usize; 4],
+}
+
+impl JointStereo {
+    fn new() -> Self {
+        JointStereo {
+            weighting_delay: [0, 7, 0, 7, 0, 7],
+            matrix_prev: [3; 4],
+            matrix_now: [3; 4],
+            matrix_next: [3; 4],
+        }
+    }
+
+    /// Reads the joint stereo parameters of the next frame.
+    fn read<B: ReadBitsLtr>(&mut self, bs: &mut B) -> Result<()> {
+        self.weighting_delay.copy_within(2.., 0);
+        self.weighting_delay[4] = bs.read_bit()? as usize;
+        self.weighting_delay[5] = bs.read_bits_leq32(3)? as usize;
+
+        self.matrix_prev = self.matrix_now;
+        self.matrix_now = self.matrix_next;
+
+        for index in self.matrix_next.iter_mut() {
+            *index = bs.read_bits_leq32(2)? as usize;
+        }
+
+        Ok(())
+    }
+}
+
+/// Adaptive TRansform Acoustic Coding 3 (ATRAC3) decoder.
+pub struct Atrac3Decoder {
+    params: CodecParameters,
+    is_joint_stereo: bool,
+    units: Vec<ChannelUnit>,
+    joint_stereo: Vec<JointStereo>,
+    imdct: Imdct,
+    buf: AudioBuffer<f32>,
+}
+
+impl Atrac3Decoder {
+    fn decode_joint_stereo(&mut self, data: &[u8]) -> Result<()> {
+        let block_len = data.len() / self.joint_stereo.len();
+
+        let mut reversed = vec![0; block_len];
+
+        for (pair, block) in data.chunks_exact(block_len).enumerate() {
+            let (unit0, unit1) = self.units[2 * pair..2 * pair + 2].split_at_mut(1);
+            let (unit0, unit1) = (&mut unit0[0], &mut unit1[0]);
+
+            unit0.decode(&mut BitReaderLtr::new(block), &mut self.imdct, false)?;
+
+            // The second sound unit is coded backwards from the end of the block, and is padded
+            // at its start with sync bytes.
+            reversed.copy_from_slice(block);
+            reversed.reverse();
+
+            let start = match reversed.iter().position(|&b| b != JOINT_STEREO_SYNC) {
+                Some(start) if start + 4 <= block_len => start,
+                _ => return decode_error("atrac3: missing second sound unit"),
+            };
+
+            let mut bs = BitReaderLtr::new(&reversed[start..]);
+
+            let js = &mut self.joint_stereo[pair];
+            js.read(&mut bs)?;
+
+            unit1.decode(&mut bs, &mut self.imdct, true)?;
+
+            reverse_matrixing(
+                &mut unit0.samples,
+                &mut unit1.samples,
+                &js.matrix_prev,
+                &js.matrix_now,
+            );
+
+            channel_weighting(&mut unit0.samples, &mut unit1.samples, &js.weighting_delay);
+        }
+
+        Ok(())
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let data = packet.buf();
+
+        let n_channels = self.units.len();
+
+        if data.is_empty() || data.len() % n_channels != 0 {
+            return decode_error("atrac3: invalid frame length");
+        }
+
+        if self.is_joint_stereo {
+            self.decode_joint_stereo(data)?;
+        }
+        else {
+            let block_len = data.len() / n_channels;
+
+            for (unit, block) in self.units.iter_mut().zip(data.chunks_exact(block_len)) {
+                unit.decode(&mut BitReaderLtr::new(block), &mut self.imdct, false)?;
+            }
+        }
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(SAMPLES_PER_FRAME));
+
+        for (ch, unit) in self.units.iter_mut().enumerate() {
+            unit.synthesize(self.buf.chan_mut(ch));
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for Atrac3Decoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_ATRAC3 {
+            return unsupported_error("atrac3: invalid codec type");
+        }
+
+        let rate = match params.sample_rate {
+            Some(rate) => rate,
+            _ => return unsupported_error("atrac3: sample rate is required"),
+        };
+
+        let spec = if let Some(channels) = params.channels {
+            SignalSpec::new(rate, channels)
+        }
+        else if let Some(layout) = params.channel_layout {
+            SignalSpec::new_with_layout(rate, layout)
+        }
+        else {
+            return unsupported_error("atrac3: channels or channel_layout is required");
+        };
+
+        let n_channels = spec.channels.count();
+
+        // The coding mode is stored in the WAVE format extra data. The extra data of other
+        // containers (e.g., RealMedia) is not supported.
+        let is_joint_stereo = match params.extra_data.as_deref() {
+            Some(extra_data) if extra_data.len() == 14 => {
+                u16::from_le_bytes([extra_data[6], extra_data[7]]) != 0
+            }
+            _ => return unsupported_error("atrac3: missing or unsupported extra data"),
+        };
+
+        if n_channels == 0 || (is_joint_stereo && n_channels % 2 != 0) {
+            return unsupported_error("atrac3: unsupported number of channels");
+        }
+
+        let joint_stereo = if is_joint_stereo {
+            (0..n_channels / 2).map(|_| JointStereo::new()).collect()
+        }
+        else {
+            Vec::new()
+        };
+
+        Ok(Atrac3Decoder {
+            params: params.clone(),
+            is_joint_stereo,
+            units: (0..n_channels).map(|_| ChannelUnit::new()).collect(),
+            joint_stereo,
+            imdct: Imdct::new_scaled(BAND_LEN, -1.0 / 32768.0),
+            buf: AudioBuffer::new(SAMPLES_PER_FRAME as u64, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_ATRAC3, "atrac3", "Adaptive TRansform Acoustic Coding 3")]
+    }
+
+    fn reset(&mut self) {
+        for unit in self.units.iter_mut() {
+            *unit = ChannelUnit::new();
+        }
+
+        for js in self.joint_stereo.iter_mut() {
+            *js = JointStereo::new();
+        }
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e.into_packet_error())
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use symphonia_core::audio::Channels;
+
+    fn decoder(is_joint_stereo: bool) -> Atrac3Decoder {
+        let mut extra_data = [0; 14];
+        extra_data[0] = 1;
+        extra_data[6] = is_joint_stereo as u8;
+        extra_data[8] = is_joint_stereo as u8;
+        extra_data[10] = 1;
+
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_ATRAC3)
+            .with_sample_rate(44_100)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+            .with_extra_data(Box::new(extra_data));
+
+        Atrac3Decoder::try_new(&params, &Default::default()).unwrap()
+    }
+
+    /// Packs the bit fields `fields`, given as (value, width) pairs, into a zero padded buffer of
+    /// `len` bytes.
+    fn pack(fields: &[(u32, u32)], len: usize) -> Vec<u8> {
+        let mut buf = vec![0; len];
+        let mut pos = 0;
+
+        for &(value, width) in fields {
+            for i in (0..width).rev() {
+                buf[pos / 8] |= (((value >> i) & 1) as u8) << (7 - pos % 8);
+                pos += 1;
+            }
+        }
+
+        buf
+    }
+
+    /// The fields of a silent sound unit following the sound unit id: 1 coded QMF band, no gain
+    /// control points, no tonal components, 1 uncoded subband, and VLC coding.
+    const SILENT_SOUND_UNIT: [(u32, u32); 6] = [(0, 2), (0, 3), (0, 5), (0, 5), (0, 1), (0, 3)];
+
+    #[test]
+    fn verify_codebooks() {
+        // The shortest code of every codebook is a single 0 bit, or a run of 0 bits.
+        for codebook in SPECTRAL_CODEBOOKS.iter() {
+            let (value, len) = BitReaderLtr::new(&[0; 4]).read_codebook(codebook).unwrap();
+            assert_eq!(value, 0);
+            assert!(len <= 3);
+        }
+
+        // Codebook 7 maps its 4-bit codes to +/-31.
+        let mut mantissas = [0; 2];
+        read_mantissas(&mut BitReaderLtr::new(&[0x23]), 7, false, &mut mantissas).unwrap();
+        assert_eq!(mantissas, [31, -31]);
+    }
+
+    #[test]
+    fn verify_window() {
+        // The synthesis window must perfectly reconstruct a signal analyzed with the ATRAC3
+        // encoder's analysis window.
+        for i in 0..256 {
+            let j = 255 - i;
+
+            let wi = (((i as f64 + 0.5) / 256.0 - 0.5) * std::f64::consts::PI).sin() + 1.0;
+            let wj = (((j as f64 + 0.5) / 256.0 - 0.5) * std::f64::consts::PI).sin() + 1.0;
+
+            let sum = f64::from(MDCT_WINDOW[i]) * wi + f64::from(MDCT_WINDOW[j]) * wj;
+            assert!((sum - 2.0).abs() < 1e-5);
+
+            assert_eq!(MDCT_WINDOW[i], MDCT_WINDOW[511 - i]);
+        }
+    }
+
+    #[test]
+    fn verify_single_mode_silence() {
+        let mut decoder = decoder(false);
+
+        let su = [&[(0x28, 6)], &SILENT_SOUND_UNIT[..]].concat();
+
+        let mut frame = pack(&su, 192);
+        frame.extend(pack(&su, 192));
+
+        let packet = Packet::new_from_slice(0, 0, 0, &frame);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => {
+                assert_eq!(buf.frames(), SAMPLES_PER_FRAME);
+                assert!(buf.chan(0).iter().chain(buf.chan(1)).all(|&s| s == 0.0));
+            }
+            _ => unreachable!(),
+        }
+
+        // A partial frame, or an invalid sound unit id, is an error.
+        assert!(decoder.decode(&Packet::new_from_slice(0, 0, 0, &frame[..383])).is_err());
+        assert!(decoder.decode(&Packet::new_from_slice(0, 0, 0, &[0; 384])).is_err());
+    }
+
+    #[test]
+    fn verify_single_mode_spectral_line() {
+        let mut decoder = decoder(false);
+
+        // A single spectral line in the first subband, coded with the 6-bit CLC quantizer.
+        let mut su = vec![(0x28, 6), (0, 2), (0, 3), (0, 5), (0, 5), (1, 1), (7, 3), (60, 6)];
+        su.extend([0, 0, 31, 0, 0, 0, 0, 0].iter().map(|&m| (m, 6)));
+
+        let mut frame = pack(&su, 192);
+        frame.extend(pack(&su, 192));
+
+        let packet = Packet::new_from_slice(0, 0, 0, &frame);
+
+        let mut prev = Vec::new();
+
+        for _ in 0..3 {
+            match decoder.decode(&packet).unwrap() {
+                AudioBufferRef::F32(buf) => {
+                    // Both channels are identical and within a reasonable range.
+                    assert_eq!(buf.chan(0), buf.chan(1));
+                    assert!(buf.chan(0).iter().any(|&s| s.abs() > 0.1));
+                    assert!(buf.chan(0).iter().all(|&s| s.abs() < 2.0));
+
+                    // Once the overlap and filter delays are filled, a repeated frame decodes to
+                    // the same samples.
+                    prev = buf.chan(0).to_vec();
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => assert_eq!(buf.chan(0), &prev[..]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn verify_joint_stereo_silence() {
+        let mut decoder = decoder(true);
+
+        // The second sound unit is preceded by the joint stereo parameters of the next frame: no
+        // channel weighting, and a matrix selector of 3 for each QMF band. It is then stored
+        // reversed at the end of the frame, and padded with sync bytes.
+        let js = [(0, 1), (7, 3), (3, 2), (3, 2), (3, 2), (3, 2)];
+        let su1 = [&js[..], &[(0x3, 2)], &SILENT_SOUND_UNIT[..]].concat();
+
+        let mut su1 = pack(&su1, 190);
+        su1.splice(0..0, [JOINT_STEREO_SYNC; 2].iter().copied());
+        su1.reverse();
+
+        let mut frame = pack(&[&[(0x28, 6)], &SILENT_SOUND_UNIT[..]].concat(), 192);
+        frame.extend(su1);
+
+        let packet = Packet::new_from_slice(0, 0, 0, &frame);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => {
+                assert_eq!(buf.frames(), SAMPLES_PER_FRAME);
+                assert!(buf.chan(0).iter().chain(buf.chan(1)).all(|&s| s == 0.0));
+            }
+            _ => unreachable!(),
+        }
+
+        // A second sound unit of only sync bytes is an error.
+        frame[192..].iter_mut().for_each(|b| *b = JOINT_STEREO_SYNC);
+        assert!(decoder.decode(&Packet::new_from_slice(0, 0, 0, &frame)).is_err());
+    }
+}
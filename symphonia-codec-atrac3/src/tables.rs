@@ -0,0 +1,158 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::io::vlc::{BitOrder, Codebook, CodebookBuilder, Entry8x8};
+use symphonia_core::util::table::make_table;
+
+use lazy_static::lazy_static;
+
+/// The first spectral line of each subband, and the end of the last subband.
+#[rustfmt::skip]
+pub const SUBBAND_TAB: [usize; 33] = [
+    0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 288, 320,
+    352, 384, 416, 448, 480, 512, 576, 640, 704, 768, 896, 1024,
+];
+
+/// The bit length of each constant length coded mantissa, indexed by the quantizer selector.
+pub const CLC_LENGTH_TAB: [u32; 8] = [0, 4, 3, 3, 4, 4, 5, 6];
+
+/// The inverse of the maximum quantized value, indexed by the quantizer selector.
+#[rustfmt::skip]
+pub const INV_MAX_QUANT: [f32; 8] = [
+    0.0, 1.0 / 1.5, 1.0 / 2.5, 1.0 / 3.5, 1.0 / 4.5, 1.0 / 7.5, 1.0 / 15.5, 1.0 / 31.5,
+];
+
+/// The mantissa pairs of the 2-bit constant length coded mantissas.
+pub const MANTISSA_CLC_TAB: [i32; 4] = [0, 1, -2, -1];
+
+/// The mantissa pairs of the first variable length codebook.
+#[rustfmt::skip]
+pub const MANTISSA_VLC_TAB: [(i32, i32); 9] = [
+    (0, 0), (0, 1), (0, -1), (1, 0), (-1, 0), (1, 1), (1, -1), (-1, 1), (-1, -1),
+];
+
+/// The stereo matrixing coefficients (left, right) for each matrix selector.
+pub const MATRIX_COEFFS: [(f32, f32); 4] = [(0.0, 2.0), (2.0, 2.0), (0.0, 0.0), (1.0, 1.0)];
+
+/// The first half of the symmetric 48-tap QMF prototype filter.
+#[rustfmt::skip]
+pub const QMF_48TAP_HALF: [f32; 24] = [
+    -0.00001461907, -0.00009205479, -0.000056157569, 0.00030117269,
+    0.0002422519, -0.00085293897, -0.0005205574, 0.0020340169,
+    0.00078333891, -0.0042153862, -0.00075614988, 0.0078402944,
+    -0.000061169922, -0.01344162, 0.0024626821, 0.021736089,
+    -0.007801671, -0.034090221, 0.01880949, 0.054326009,
+    -0.043596379, -0.099384367, 0.13207909, 0.46424159,
+];
+
+const HUFF_CODES_1: [u32; 9] = [0x0, 0x4, 0x5, 0xc, 0xd, 0x1c, 0x1d, 0x1e, 0x1f];
+const HUFF_LENS_1: [u8; 9] = [1, 3, 3, 4, 4, 5, 5, 5, 5];
+
+const HUFF_CODES_2: [u32; 5] = [0x0, 0x4, 0x5, 0x6, 0x7];
+const HUFF_LENS_2: [u8; 5] = [1, 3, 3, 3, 3];
+
+const HUFF_CODES_3: [u32; 7] = [0x0, 0x4, 0x5, 0xc, 0xd, 0xe, 0xf];
+const HUFF_LENS_3: [u8; 7] = [1, 3, 3, 4, 4, 4, 4];
+
+const HUFF_CODES_4: [u32; 9] = [0x0, 0x4, 0x5, 0xc, 0xd, 0x1c, 0x1d, 0x1e, 0x1f];
+const HUFF_LENS_4: [u8; 9] = [1, 3, 3, 4, 4, 5, 5, 5, 5];
+
+#[rustfmt::skip]
+const HUFF_CODES_5: [u32; 15] = [
+    0x0, 0x2, 0x3, 0x8, 0x9, 0xa, 0xb, 0x1c, 0x1d, 0x3c, 0x3d, 0x3e, 0x3f, 0xc, 0xd,
+];
+const HUFF_LENS_5: [u8; 15] = [2, 3, 3, 4, 4, 4, 4, 5, 5, 6, 6, 6, 6, 4, 4];
+
+#[rustfmt::skip]
+const HUFF_CODES_6: [u32; 31] = [
+    0x0, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x34, 0x35, 0x36,
+    0x37, 0x38, 0x39, 0x3a, 0x3b, 0x78, 0x79, 0x7a, 0x7b, 0x7c, 0x7d, 0x7e, 0x7f, 0x8, 0x9,
+];
+#[rustfmt::skip]
+const HUFF_LENS_6: [u8; 31] = [
+    3, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 6, 7, 7, 7, 7, 7, 7, 7, 7, 4, 4,
+];
+
+#[rustfmt::skip]
+const HUFF_CODES_7: [u32; 63] = [
+    0x0, 0x8, 0x9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf, 0x10, 0x11, 0x24, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x68, 0x69, 0x6a, 0x6b,
+    0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0xec, 0xed, 0xee, 0xef, 0xf0,
+    0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+    0x2, 0x3,
+];
+#[rustfmt::skip]
+const HUFF_LENS_7: [u8; 63] = [
+    3, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 4,
+    4,
+];
+
+/// Generate a codebook that maps each code to its index in `codes`.
+fn make_codebook(codes: &[u32], lens: &[u8]) -> Codebook<Entry8x8> {
+    let values: Vec<u8> = (0..codes.len() as u8).collect();
+
+    let mut builder = CodebookBuilder::new(BitOrder::Verbatim);
+
+    // Read in 8-bit blocks.
+    builder.bits_per_read(8);
+
+    builder.make(codes, lens, &values).unwrap()
+}
+
+lazy_static! {
+    /// The spectral coefficient codebooks, indexed by the quantizer selector minus 1.
+    pub static ref SPECTRAL_CODEBOOKS: [Codebook<Entry8x8>; 7] = [
+        make_codebook(&HUFF_CODES_1, &HUFF_LENS_1),
+        make_codebook(&HUFF_CODES_2, &HUFF_LENS_2),
+        make_codebook(&HUFF_CODES_3, &HUFF_LENS_3),
+        make_codebook(&HUFF_CODES_4, &HUFF_LENS_4),
+        make_codebook(&HUFF_CODES_5, &HUFF_LENS_5),
+        make_codebook(&HUFF_CODES_6, &HUFF_LENS_6),
+        make_codebook(&HUFF_CODES_7, &HUFF_LENS_7),
+    ];
+}
+
+lazy_static! {
+    /// The scale factors, `2^((i - 15) / 3)`.
+    pub static ref SF_TABLE: Box<[f32; 64]> =
+        make_table(|i| 2.0f64.powf((i as f64 - 15.0) / 3.0) as f32);
+}
+
+lazy_static! {
+    /// The IMDCT window. The window is normalized such that overlapping windows sum to 1.
+    pub static ref MDCT_WINDOW: [f32; 512] = {
+        let mut window = [0.0; 512];
+
+        for i in 0..128 {
+            let j = 255 - i;
+
+            let wi = (((i as f64 + 0.5) / 256.0 - 0.5) * std::f64::consts::PI).sin() + 1.0;
+            let wj = (((j as f64 + 0.5) / 256.0 - 0.5) * std::f64::consts::PI).sin() + 1.0;
+            let w = 0.5 * (wi * wi + wj * wj);
+
+            window[i] = (wi / w) as f32;
+            window[511 - i] = (wi / w) as f32;
+            window[j] = (wj / w) as f32;
+            window[511 - j] = (wj / w) as f32;
+        }
+
+        window
+    };
+}
+
+lazy_static! {
+    /// The symmetric 48-tap QMF prototype filter.
+    pub static ref QMF_WINDOW: [f32; 48] = {
+        let mut window = [0.0; 48];
+        for (i, &tap) in QMF_48TAP_HALF.iter().enumerate() {
+            window[i] = 2.0 * tap;
+            window[47 - i] = 2.0 * tap;
+        }
+        window
+    };
+}
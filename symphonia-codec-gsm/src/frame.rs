@@ -0,0 +1,68 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+
+/// The number of samples in a GSM frame.
+pub const FRAME_LEN: usize = 160;
+
+/// The number of samples in a GSM sub-frame.
+pub const SUB_FRAME_LEN: usize = 40;
+
+/// The bit widths of the 8 coded log-area ratios.
+const LARC_BITS: [u32; 8] = [6, 6, 5, 5, 4, 4, 3, 3];
+
+/// The coded parameters of one of the 4 sub-frames of a GSM frame.
+#[derive(Default)]
+pub struct SubFrame {
+    /// The coded long-term predictor lag.
+    pub nc: u16,
+    /// The coded long-term predictor gain.
+    pub bc: u16,
+    /// The RPE grid selection.
+    pub mc: u16,
+    /// The coded block amplitude.
+    pub xmaxc: u16,
+    /// The coded RPE pulses.
+    pub xmc: [u16; 13],
+}
+
+/// The coded parameters of a GSM frame.
+#[derive(Default)]
+pub struct Frame {
+    /// The coded log-area ratios.
+    pub larc: [u16; 8],
+    pub sub_frames: [SubFrame; 4],
+}
+
+impl Frame {
+    /// Reads the 260 bits of the frame parameters using `read_bits`. The bit order of the frame
+    /// depends on the variant of GSM, therefore `read_bits` abstracts over it.
+    pub fn read<F>(mut read_bits: F) -> Result<Frame>
+    where
+        F: FnMut(u32) -> Result<u16>,
+    {
+        let mut frame: Frame = Default::default();
+
+        for (larc, &bits) in frame.larc.iter_mut().zip(LARC_BITS.iter()) {
+            *larc = read_bits(bits)?;
+        }
+
+        for sub_frame in frame.sub_frames.iter_mut() {
+            sub_frame.nc = read_bits(7)?;
+            sub_frame.bc = read_bits(2)?;
+            sub_frame.mc = read_bits(2)?;
+            sub_frame.xmaxc = read_bits(6)?;
+
+            for xmc in sub_frame.xmc.iter_mut() {
+                *xmc = read_bits(3)?;
+            }
+        }
+
+        Ok(frame)
+    }
+}
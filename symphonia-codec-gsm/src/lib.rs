@@ -0,0 +1,237 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![warn(rust_2018_idioms)]
+#![forbid(unsafe_code)]
+// The following lints are allowed in all Symphonia crates. Please see clippy.toml for their
+// justification.
+#![allow(clippy::comparison_chain)]
+#![allow(clippy::excessive_precision)]
+#![allow(clippy::identity_op)]
+#![allow(clippy::manual_range_contains)]
+
+use symphonia_core::support_codec;
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::codecs::{CODEC_TYPE_GSM, CODEC_TYPE_GSM_MS};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BitReaderLtr, BitReaderRtl, ReadBitsLtr, ReadBitsRtl};
+
+mod frame;
+mod synthesis;
+
+use frame::{Frame, FRAME_LEN};
+use synthesis::Synthesizer;
+
+/// The length in bytes of a standard GSM frame.
+const GSM_FRAME_SIZE: usize = 33;
+
+/// The length in bytes of a Microsoft GSM block. A block packs two frames without padding.
+const GSM_MS_BLOCK_SIZE: usize = 65;
+
+/// The magic number in the upper nibble of the first byte of a standard GSM frame.
+const GSM_MAGIC: u32 = 0xd;
+
+/// GSM 06.10 Full Rate decoder.
+pub struct GsmDecoder {
+    params: CodecParameters,
+    synth: Synthesizer,
+    buf: AudioBuffer<i16>,
+}
+
+impl GsmDecoder {
+    fn decode_gsm(&mut self, data: &[u8]) -> Result<()> {
+        for (block, out) in
+            data.chunks_exact(GSM_FRAME_SIZE).zip(self.buf.chan_mut(0).chunks_exact_mut(FRAME_LEN))
+        {
+            let mut bs = BitReaderLtr::new(block);
+
+            if bs.read_bits_leq32(4)? != GSM_MAGIC {
+                return decode_error("gsm: invalid frame magic");
+            }
+
+            let frame = Frame::read(|bits| Ok(bs.read_bits_leq32(bits)? as u16))?;
+
+            self.synth.synthesize(&frame, out);
+        }
+        Ok(())
+    }
+
+    fn decode_gsm_ms(&mut self, data: &[u8]) -> Result<()> {
+        for (block, out) in data
+            .chunks_exact(GSM_MS_BLOCK_SIZE)
+            .zip(self.buf.chan_mut(0).chunks_exact_mut(2 * FRAME_LEN))
+        {
+            // Both frames are packed least-significant bit first, and the second frame begins in
+            // the middle of a byte.
+            let mut bs = BitReaderRtl::new(block);
+
+            for out in out.chunks_exact_mut(FRAME_LEN) {
+                let frame = Frame::read(|bits| Ok(bs.read_bits_leq32(bits)? as u16))?;
+
+                self.synth.synthesize(&frame, out);
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let (block_size, frames_per_block) = match self.params.codec {
+            CODEC_TYPE_GSM => (GSM_FRAME_SIZE, FRAME_LEN),
+            _ => (GSM_MS_BLOCK_SIZE, 2 * FRAME_LEN),
+        };
+
+        let data = packet.buf();
+
+        if data.len() % block_size != 0 {
+            return decode_error("gsm: packet is not a whole number of blocks");
+        }
+
+        let n_frames = frames_per_block * (data.len() / block_size);
+
+        if n_frames > self.buf.capacity() {
+            return decode_error("gsm: packet is too large");
+        }
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(n_frames));
+
+        match self.params.codec {
+            CODEC_TYPE_GSM => self.decode_gsm(data),
+            _ => self.decode_gsm_ms(data),
+        }
+    }
+}
+
+impl Decoder for GsmDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let frames_per_block = match params.codec {
+            CODEC_TYPE_GSM => FRAME_LEN,
+            CODEC_TYPE_GSM_MS => 2 * FRAME_LEN,
+            _ => return unsupported_error("gsm: invalid codec type"),
+        };
+
+        // GSM is narrowband speech, the sample rate is 8 kHz unless specified otherwise.
+        let rate = params.sample_rate.unwrap_or(8000);
+
+        let spec = if let Some(channels) = params.channels {
+            SignalSpec::new(rate, channels)
+        }
+        else if let Some(layout) = params.channel_layout {
+            SignalSpec::new_with_layout(rate, layout)
+        }
+        else {
+            return unsupported_error("gsm: channels or channel_layout is required");
+        };
+
+        if spec.channels.count() != 1 {
+            return unsupported_error("gsm: only mono is supported");
+        }
+
+        // If the maximum number of frames per packet is unknown, assume one block per packet.
+        let frames = params.max_frames_per_packet.unwrap_or(frames_per_block as u64);
+
+        Ok(GsmDecoder {
+            params: params.clone(),
+            synth: Synthesizer::new(),
+            buf: AudioBuffer::new(frames, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[
+            support_codec!(CODEC_TYPE_GSM, "gsm", "GSM 06.10 Full Rate"),
+            support_codec!(CODEC_TYPE_GSM_MS, "gsm_ms", "GSM 06.10 Full Rate (Microsoft)"),
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.synth = Synthesizer::new();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        // An empty packet contains no audio.
+        if packet.buf().is_empty() {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e.into_packet_error())
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use symphonia_core::audio::Channels;
+    use symphonia_core::codecs::{CodecParameters, CodecType};
+
+    fn decode(codec: CodecType, data: &[u8]) -> Result<Vec<i16>> {
+        let mut params = CodecParameters::new();
+        params.for_codec(codec).with_sample_rate(8000).with_channels(Channels::FRONT_LEFT);
+
+        let mut decoder = GsmDecoder::try_new(&params, &Default::default())?;
+
+        let packet = Packet::new_from_slice(0, 0, 0, data);
+
+        match decoder.decode(&packet)? {
+            AudioBufferRef::S16(buf) => Ok(buf.chan(0).to_vec()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn verify_gsm_frame_decode() {
+        // Frames with arbitrary parameters must decode without overflowing.
+        let mut frame: Vec<u8> = (0..GSM_FRAME_SIZE as u8).map(|i| i.wrapping_mul(97)).collect();
+        frame[0] = 0xdf;
+
+        let samples = decode(CODEC_TYPE_GSM, &frame).unwrap();
+        assert_eq!(samples.len(), FRAME_LEN);
+        assert!(samples.iter().any(|&s| s != 0));
+
+        // Invalid magic, or a partial frame, is an error.
+        assert!(decode(CODEC_TYPE_GSM, &[0; GSM_FRAME_SIZE]).is_err());
+        assert!(decode(CODEC_TYPE_GSM, &frame[..GSM_FRAME_SIZE - 1]).is_err());
+
+        // An empty packet decodes to no audio.
+        assert!(decode(CODEC_TYPE_GSM, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn verify_gsm_ms_block_decode() {
+        let block: Vec<u8> = (0..GSM_MS_BLOCK_SIZE as u8).map(|i| i.wrapping_mul(151)).collect();
+
+        let samples = decode(CODEC_TYPE_GSM_MS, &block).unwrap();
+        assert_eq!(samples.len(), 2 * FRAME_LEN);
+
+        // Decoding is deterministic.
+        assert_eq!(decode(CODEC_TYPE_GSM_MS, &block).unwrap(), samples);
+    }
+}
@@ -0,0 +1,277 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The GSM 06.10 decoder is specified in terms of 16-bit fixed-point arithmetic. The output must
+//! be bit-exact, therefore the saturating and rounding behaviour of the basic operators of the
+//! specification is reproduced exactly.
+
+use crate::frame::{Frame, FRAME_LEN, SUB_FRAME_LEN};
+
+/// The normalized inverse mantissas used by the APCM inverse quantizer.
+const FAC: [i16; 8] = [18431, 20479, 22527, 24575, 26623, 28671, 30719, 32767];
+
+/// The quantization levels of the long-term predictor gain.
+const QLB: [i16; 4] = [3277, 11469, 21299, 32767];
+
+/// The decoding constants of the log-area ratios.
+const LAR_B: [i16; 8] = [0, 0, 2048, -2560, 94, -1792, -341, -1144];
+const LAR_MIC: [i16; 8] = [-32, -32, -16, -16, -8, -8, -4, -4];
+const LAR_INVA: [i16; 8] = [13107, 13107, 13107, 13107, 19223, 17476, 31454, 29708];
+
+/// The length of the reconstructed long-term residual history.
+const HISTORY_LEN: usize = 120;
+
+#[inline(always)]
+fn add(a: i16, b: i16) -> i16 {
+    a.saturating_add(b)
+}
+
+#[inline(always)]
+fn sub(a: i16, b: i16) -> i16 {
+    a.saturating_sub(b)
+}
+
+/// Multiplication with rounding.
+#[inline(always)]
+fn mult_r(a: i16, b: i16) -> i16 {
+    if a == i16::MIN && b == i16::MIN {
+        i16::MAX
+    }
+    else {
+        ((i32::from(a) * i32::from(b) + 16384) >> 15) as i16
+    }
+}
+
+/// Arithmetic shift right by `n`, where `n` may be negative.
+fn asr(a: i16, n: i16) -> i16 {
+    if n >= 16 {
+        -((a < 0) as i16)
+    }
+    else if n <= -16 {
+        0
+    }
+    else if n < 0 {
+        a << -n
+    }
+    else {
+        a >> n
+    }
+}
+
+/// Arithmetic shift left by `n`, where `n` may be negative.
+fn asl(a: i16, n: i16) -> i16 {
+    if n >= 16 {
+        0
+    }
+    else if n <= -16 {
+        -((a < 0) as i16)
+    }
+    else if n < 0 {
+        asr(a, -n)
+    }
+    else {
+        a << n
+    }
+}
+
+/// Decodes the RPE sequence of a sub-frame into the reconstructed long-term residual `erp`.
+fn rpe_decode(xmaxc: u16, mc: u16, xmc: &[u16; 13], erp: &mut [i16; SUB_FRAME_LEN]) {
+    // Compute the exponent and mantissa of the block amplitude.
+    let xmaxc = xmaxc as i16;
+
+    let mut exp = if xmaxc > 15 { (xmaxc >> 3) - 1 } else { 0 };
+    let mut mant = xmaxc - (exp << 3);
+
+    if mant == 0 {
+        exp = -4;
+        mant = 7;
+    }
+    else {
+        while mant <= 7 {
+            mant = mant << 1 | 1;
+            exp -= 1;
+        }
+        mant -= 8;
+    }
+
+    // APCM inverse quantization and RPE grid positioning.
+    let temp1 = FAC[mant as usize];
+    let temp2 = sub(6, exp);
+    let temp3 = asl(1, sub(temp2, 1));
+
+    erp.iter_mut().for_each(|e| *e = 0);
+
+    for (i, &xmc) in xmc.iter().enumerate() {
+        // Restore the sign of the pulse.
+        let temp = ((xmc as i16) << 1) - 7;
+        let temp = mult_r(temp1, temp << 12);
+        erp[usize::from(mc) + 3 * i] = asr(add(temp, temp3), temp2);
+    }
+}
+
+/// Converts the interpolated log-area ratios into reflection coefficients, in place.
+fn lar_to_rp(lar: &mut [i16; 8]) {
+    for lar in lar.iter_mut() {
+        let temp = if *lar == i16::MIN { i16::MAX } else { lar.abs() };
+
+        let rp = if temp < 11059 {
+            temp << 1
+        }
+        else if temp < 20070 {
+            temp + 11059
+        }
+        else {
+            add(temp >> 2, 26112)
+        };
+
+        *lar = if *lar < 0 { -rp } else { rp };
+    }
+}
+
+/// The state of a GSM 06.10 decoder.
+pub struct Synthesizer {
+    /// The reconstructed long-term residual. The first `HISTORY_LEN` samples are the history used
+    /// by the long-term predictor, the rest is the current sub-frame.
+    dp: [i16; HISTORY_LEN + SUB_FRAME_LEN],
+    /// The log-area ratios of the previous and current frame.
+    larpp: [[i16; 8]; 2],
+    /// The index into `larpp` of the log-area ratios of the current frame.
+    j: usize,
+    /// The previous long-term predictor lag.
+    nrp: i16,
+    /// The short-term synthesis filter state.
+    v: [i16; 9],
+    /// The de-emphasis filter state.
+    msr: i16,
+}
+
+impl Synthesizer {
+    pub fn new() -> Self {
+        Synthesizer {
+            dp: [0; HISTORY_LEN + SUB_FRAME_LEN],
+            larpp: [[0; 8]; 2],
+            j: 0,
+            nrp: 40,
+            v: [0; 9],
+            msr: 0,
+        }
+    }
+
+    /// Decodes `frame` into 160 samples in `out`.
+    pub fn synthesize(&mut self, frame: &Frame, out: &mut [i16]) {
+        let mut wt = [0; FRAME_LEN];
+        let mut erp = [0; SUB_FRAME_LEN];
+
+        for (sub_frame, wt) in frame.sub_frames.iter().zip(wt.chunks_exact_mut(SUB_FRAME_LEN)) {
+            rpe_decode(sub_frame.xmaxc, sub_frame.mc, &sub_frame.xmc, &mut erp);
+
+            self.long_term_synthesis(sub_frame.nc, sub_frame.bc, &erp);
+
+            wt.copy_from_slice(&self.dp[HISTORY_LEN..]);
+
+            // Shift the history.
+            self.dp.copy_within(SUB_FRAME_LEN.., 0);
+        }
+
+        self.short_term_synthesis(&frame.larc, &wt, out);
+
+        // De-emphasis, truncation, and upscaling.
+        for s in out[..FRAME_LEN].iter_mut() {
+            self.msr = add(*s, mult_r(self.msr, 28180));
+            *s = add(self.msr, self.msr) & !0x7;
+        }
+    }
+
+    fn long_term_synthesis(&mut self, ncr: u16, bcr: u16, erp: &[i16; SUB_FRAME_LEN]) {
+        // Use the previous lag if the current lag is out of range.
+        let nr = if ncr < 40 || ncr > 120 { self.nrp } else { ncr as i16 };
+        self.nrp = nr;
+
+        let brp = QLB[usize::from(bcr)];
+
+        for (k, &erp) in erp.iter().enumerate() {
+            let i = HISTORY_LEN + k;
+            let drpp = mult_r(brp, self.dp[i - nr as usize]);
+            self.dp[i] = add(erp, drpp);
+        }
+    }
+
+    fn short_term_synthesis(&mut self, larc: &[u16; 8], wt: &[i16; FRAME_LEN], out: &mut [i16]) {
+        let prev = self.j;
+        self.j ^= 1;
+
+        // Decode the coded log-area ratios.
+        for i in 0..8 {
+            let temp = add(larc[i] as i16, LAR_MIC[i]) << 10;
+            let temp = mult_r(LAR_INVA[i], sub(temp, LAR_B[i] << 1));
+            self.larpp[self.j][i] = add(temp, temp);
+        }
+
+        let larpp_j = self.larpp[self.j];
+        let larpp_j_1 = self.larpp[prev];
+
+        let mut larp = [0; 8];
+
+        // The reflection coefficients are interpolated between the previous and current frame
+        // over the first 40 samples.
+        for i in 0..8 {
+            larp[i] = add(larpp_j_1[i] >> 2, larpp_j[i] >> 2);
+            larp[i] = add(larp[i], larpp_j_1[i] >> 1);
+        }
+        lar_to_rp(&mut larp);
+        self.short_term_filter(&larp, &wt[0..13], &mut out[0..13]);
+
+        for i in 0..8 {
+            larp[i] = add(larpp_j_1[i] >> 1, larpp_j[i] >> 1);
+        }
+        lar_to_rp(&mut larp);
+        self.short_term_filter(&larp, &wt[13..27], &mut out[13..27]);
+
+        for i in 0..8 {
+            larp[i] = add(larpp_j_1[i] >> 2, larpp_j[i] >> 2);
+            larp[i] = add(larp[i], larpp_j[i] >> 1);
+        }
+        lar_to_rp(&mut larp);
+        self.short_term_filter(&larp, &wt[27..40], &mut out[27..40]);
+
+        larp = larpp_j;
+        lar_to_rp(&mut larp);
+        self.short_term_filter(&larp, &wt[40..], &mut out[40..FRAME_LEN]);
+    }
+
+    fn short_term_filter(&mut self, rrp: &[i16; 8], wt: &[i16], out: &mut [i16]) {
+        for (&wt, out) in wt.iter().zip(out.iter_mut()) {
+            let mut sri = wt;
+
+            for i in (0..8).rev() {
+                sri = sub(sri, mult_r(rrp[i], self.v[i]));
+                self.v[i + 1] = add(self.v[i], mult_r(rrp[i], sri));
+            }
+
+            self.v[0] = sri;
+            *out = sri;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add, asl, asr, mult_r, sub};
+
+    #[test]
+    fn verify_basic_operators() {
+        assert_eq!(mult_r(i16::MIN, i16::MIN), i16::MAX);
+        assert_eq!(mult_r(16384, 16384), 8192);
+        assert_eq!(mult_r(-1, 16384), 0);
+        assert_eq!(asr(-5, 16), -1);
+        assert_eq!(asr(5, -2), 20);
+        assert_eq!(asl(1, -1), 0);
+        assert_eq!(asl(1, 3), 8);
+        assert_eq!(add(i16::MAX, 1), i16::MAX);
+        assert_eq!(sub(i16::MIN, 1), i16::MIN);
+    }
+}
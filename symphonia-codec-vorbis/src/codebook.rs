@@ -40,10 +40,28 @@ fn lookup1_values(entries: u32, dimensions: u16) -> u32 {
     // (value ^ dimensions) <= entries
     // [(value ^ dimensions) ^ (1 / dimensions)] = lower[entries ^ (1 / dimensions)]
     // value = lower[entries ^ (1 / dimensions)]
-    let value = (entries as f32).powf(1.0f32 / f32::from(dimensions)).floor() as u32;
+    //
+    // Start with a floating-point estimate and then correct it using integer arithmetic since the
+    // estimate may be off-by-one due to rounding. Large dimensions (which some old encoders did
+    // produce) can overflow the integer power, so treat an overflow as exceeding the entries.
+    debug_assert!(dimensions > 0);
+
+    let dimensions = u32::from(dimensions);
+
+    let pow_leq_entries = |value: u32| match value.checked_pow(dimensions) {
+        Some(pow) => pow <= entries,
+        None => false,
+    };
+
+    let mut value = (entries as f64).powf(1.0 / f64::from(dimensions)).floor() as u32;
 
-    assert!(value.pow(u32::from(dimensions)) <= entries);
-    assert!((value + 1).pow(u32::from(dimensions)) > entries);
+    while value > 0 && !pow_leq_entries(value) {
+        value -= 1;
+    }
+
+    while pow_leq_entries(value + 1) {
+        value += 1;
+    }
 
     value
 }
@@ -97,16 +115,15 @@ fn unpack_vq_lookup_type2(
         vq_lookup.chunks_exact_mut(codebook_dimensions as usize).enumerate()
     {
         let mut last = 0.0;
-        let mut multiplicand_offset = lookup_offset * codebook_dimensions as usize;
+        let multiplicand_offset = lookup_offset * codebook_dimensions as usize;
+        let multiplicands = &multiplicands[multiplicand_offset..];
 
-        for value in value_vector.iter_mut() {
-            *value = f32::from(multiplicands[multiplicand_offset]) * delta_value + min_value + last;
+        for (value, &multiplicand) in value_vector.iter_mut().zip(multiplicands) {
+            *value = f32::from(multiplicand) * delta_value + min_value + last;
 
             if sequence_p {
                 last = *value;
             }
-
-            multiplicand_offset += 1;
         }
     }
 
@@ -303,6 +320,11 @@ impl VorbisCodebook {
         let vq_vec = match lookup_type & 0xf {
             0 => None,
             1 | 2 => {
+                // A VQ codebook must have at least one dimension.
+                if codebook_dimensions == 0 {
+                    return decode_error("vorbis: vq codebook has no dimensions");
+                }
+
                 let min_value = float32_unpack(bs.read_bits_leq32(32)?);
                 let delta_value = float32_unpack(bs.read_bits_leq32(32)?);
                 let value_bits = bs.read_bits_leq32(4)? + 1;
@@ -311,7 +333,10 @@ impl VorbisCodebook {
                 // Lookup type is either 1 or 2 as per outer match.
                 let lookup_values = match lookup_type {
                     1 => lookup1_values(codebook_entries, codebook_dimensions),
-                    2 => codebook_entries * u32::from(codebook_dimensions),
+                    2 => match codebook_entries.checked_mul(u32::from(codebook_dimensions)) {
+                        Some(lookup_values) => lookup_values,
+                        None => return decode_error("vorbis: too many vq lookup values"),
+                    },
                     _ => unreachable!(),
                 };
 
@@ -395,7 +420,46 @@ impl VorbisCodebook {
 
 #[cfg(test)]
 mod tests {
-    use super::{ilog, lookup1_values, synthesize_codewords};
+    use super::{ilog, lookup1_values, synthesize_codewords, VorbisCodebook};
+    use symphonia_core::io::{BitReaderRtl, BitWriterRtl, WriteBitsRtl};
+
+    /// Writes the setup of a codebook with 2 entries, each 1 bit long, and a VQ lookup table of
+    /// the given type where every multiplicand is 1.
+    fn write_codebook(dimensions: u16, lookup_type: u32) -> Vec<u8> {
+        let mut bw = BitWriterRtl::new();
+
+        bw.write_bits_leq32(0x564342, 24).unwrap();
+        bw.write_bits_leq32(u32::from(dimensions), 16).unwrap();
+        bw.write_bits_leq32(2, 24).unwrap();
+
+        // Not length ordered, not sparse, and 2 codewords of length 1.
+        bw.write_bool(false).unwrap();
+        bw.write_bool(false).unwrap();
+        bw.write_bits_leq32(0, 5).unwrap();
+        bw.write_bits_leq32(0, 5).unwrap();
+
+        bw.write_bits_leq32(lookup_type, 4).unwrap();
+
+        if lookup_type != 0 {
+            // A minimum value of 0, a delta value of 1, 1 bit per value, and no sequence.
+            bw.write_bits_leq32(0, 32).unwrap();
+            bw.write_bits_leq32(0x6280_0001, 32).unwrap();
+            bw.write_bits_leq32(0, 4).unwrap();
+            bw.write_bool(false).unwrap();
+
+            let lookup_values = match lookup_type {
+                _ if dimensions == 0 => 0,
+                1 => lookup1_values(2, dimensions),
+                _ => 2 * u32::from(dimensions),
+            };
+
+            for _ in 0..lookup_values {
+                bw.write_bit(1).unwrap();
+            }
+        }
+
+        bw.into_inner()
+    }
 
     #[test]
     fn verify_ilog() {
@@ -423,6 +487,23 @@ mod tests {
     fn verify_lookup1_values() {
         assert_eq!(lookup1_values(1, 1), naive_lookup1_values(1, 1));
         assert_eq!(lookup1_values(361, 2), naive_lookup1_values(361, 2));
+
+        // Exhaustively check small codebooks since the floating-point estimate is most likely to be
+        // off-by-one for perfect powers.
+        for dimensions in 1..=8 {
+            for entries in 1..=4096 {
+                assert_eq!(
+                    lookup1_values(entries, dimensions),
+                    naive_lookup1_values(entries, dimensions)
+                );
+            }
+        }
+
+        // Large dimensions must not overflow.
+        assert_eq!(lookup1_values(1, 65535), 1);
+        assert_eq!(lookup1_values(0xff_ffff, 65535), 1);
+        assert_eq!(lookup1_values(0xff_ffff, 24), 1);
+        assert_eq!(lookup1_values(0xff_ffff, 23), 2);
     }
 
     #[test]
@@ -432,4 +513,26 @@ mod tests {
         let codewords = synthesize_codewords(CODEWORD_LENGTHS).unwrap();
         assert_eq!(&codewords, EXPECTED_CODEWORDS);
     }
+
+    #[test]
+    fn verify_codebook_old_stream_setups() {
+        // A VQ codebook without any dimensions is rejected instead of producing empty VQ vectors.
+        for lookup_type in 1..=2 {
+            let buf = write_codebook(0, lookup_type);
+            assert!(VorbisCodebook::read(&mut BitReaderRtl::new(&buf)).is_err());
+        }
+
+        // A scalar codebook does not need any dimensions.
+        let buf = write_codebook(0, 0);
+        assert!(VorbisCodebook::read(&mut BitReaderRtl::new(&buf)).is_ok());
+
+        // A VQ codebook with a very large number of dimensions, as produced by some old encoders,
+        // must not overflow while computing the number of lookup values.
+        let buf = write_codebook(u16::MAX, 1);
+        let codebook = VorbisCodebook::read(&mut BitReaderRtl::new(&buf)).unwrap();
+
+        let vq = codebook.read_vq(&mut BitReaderRtl::new(&[0])).unwrap();
+        assert_eq!(vq.len(), usize::from(u16::MAX));
+        assert!(vq.iter().all(|&v| v == 1.0));
+    }
 }
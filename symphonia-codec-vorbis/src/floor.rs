@@ -166,6 +166,16 @@ impl Floor0 {
         let floor0_order = bs.read_bits_leq32(8)? as u8;
         let floor0_rate = bs.read_bits_leq32(16)? as u16;
         let floor0_bark_map_size = bs.read_bits_leq32(16)? as u16;
+
+        // The Bark-scale map is undefined for a rate or map size of 0.
+        if floor0_rate == 0 {
+            return decode_error("vorbis: floor0, invalid rate");
+        }
+
+        if floor0_bark_map_size == 0 {
+            return decode_error("vorbis: floor0, invalid bark map size");
+        }
+
         let floor0_amplitude_bits = bs.read_bits_leq32(6)? as u8;
         let floor0_amplitude_offset = bs.read_bits_leq32(8)? as u8;
         let floor0_number_of_books = bs.read_bits_leq32(4)? as u8 + 1;
@@ -219,6 +229,12 @@ impl Floor for Floor0 {
             let floor_book_idx_bits = ilog(u32::from(self.setup.floor0_number_of_books));
             let floor_book_idx = io_try_or_ret!(bs.read_bits_leq32(floor_book_idx_bits)) as usize;
 
+            // The index may exceed the number of books in the list. Like the reference decoder,
+            // treat the floor as unused for this packet rather than failing.
+            if floor_book_idx >= usize::from(self.setup.floor0_number_of_books) {
+                return Ok(());
+            }
+
             // Get the actual codebook index from the floor's codebook list.
             let codebook_idx = self.setup.floor0_book_list[floor_book_idx] as usize;
 
@@ -820,3 +836,60 @@ fn render_line(x0: u32, y0: i32, x1: u32, y1: i32, n: usize, v: &mut [f32]) {
         *v = FLOOR1_INVERSE_DB_TABLE[y as usize];
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Floor0;
+    use symphonia_core::io::{BitReaderRtl, BitWriterRtl, WriteBitsRtl};
+
+    /// Writes the setup of a floor0 with 3 books.
+    fn write_floor0_setup(rate: u32, bark_map_size: u32) -> Vec<u8> {
+        let mut bw = BitWriterRtl::new();
+
+        // Order, rate, and Bark map size.
+        bw.write_bits_leq32(8, 8).unwrap();
+        bw.write_bits_leq32(rate, 16).unwrap();
+        bw.write_bits_leq32(bark_map_size, 16).unwrap();
+
+        // Amplitude bits, amplitude offset, and 3 books that are all codebook 0.
+        bw.write_bits_leq32(4, 6).unwrap();
+        bw.write_bits_leq32(100, 8).unwrap();
+        bw.write_bits_leq32(2, 4).unwrap();
+
+        for _ in 0..3 {
+            bw.write_bits_leq32(0, 8).unwrap();
+        }
+
+        bw.into_inner()
+    }
+
+    #[test]
+    fn verify_floor0_old_stream_setups() {
+        let read = |rate, bark_map_size| {
+            let buf = write_floor0_setup(rate, bark_map_size);
+            Floor0::try_read(&mut BitReaderRtl::new(&buf), 8, 11, 1)
+        };
+
+        assert!(read(44_100, 256).is_ok());
+
+        // The Bark map is undefined for a rate or map size of 0.
+        assert!(read(0, 256).is_err());
+        assert!(read(44_100, 0).is_err());
+    }
+
+    #[test]
+    fn verify_floor0_book_out_of_range() {
+        let buf = write_floor0_setup(44_100, 256);
+        let mut floor = Floor0::try_read(&mut BitReaderRtl::new(&buf), 8, 11, 1).unwrap();
+
+        // A non-zero amplitude followed by the book index 3, which is past the end of the book
+        // list. The floor is unused for the packet, instead of failing.
+        let mut bw = BitWriterRtl::new();
+        bw.write_bits_leq32(5, 4).unwrap();
+        bw.write_bits_leq32(3, 2).unwrap();
+        let packet = bw.into_inner();
+
+        floor.read_channel(&mut BitReaderRtl::new(&packet), &[]).unwrap();
+        assert!(floor.is_unused());
+    }
+}
@@ -17,9 +17,10 @@ use std::vec::Vec;
 use arrayvec::ArrayVec;
 use bitflags::bitflags;
 
+use crate::conv::dither::{Dither, MaybeDither};
 use crate::conv::{ConvertibleSample, FromSample, IntoSample};
 use crate::errors::Result;
-use crate::sample::{i24, u24, Sample};
+use crate::sample::{i24, u24, Sample, SampleFormat};
 use crate::units::Duration;
 
 /// The maximum number of audio plane slices `AudioPlanes` or `AudioPlanesMut` will store on the
@@ -340,7 +341,7 @@ impl<S: Sample> AudioBuffer<S> {
     /// Note: This is not a cheap operation for audio buffers with > 8 channels. It is advisable
     /// that this call is only used when operating on large batches of frames. Generally speaking,
     /// it is almost always better to use `chan()` to selectively choose the plane to read instead.
-    pub fn planes(&self) -> AudioPlanes<S> {
+    pub fn planes(&self) -> AudioPlanes<'_, S> {
         // Fill the audio planes structure with references to the written portion of each audio
         // plane.
         let mut planes = AudioPlanes::new(self.spec.channels);
@@ -358,7 +359,7 @@ impl<S: Sample> AudioBuffer<S> {
     /// that this call is only used when modifying large batches of frames. Generally speaking,
     /// it is almost always better to use `render()`, `fill()`, `chan_mut()`, and `chan_pair_mut()`
     /// to modify the buffer instead.
-    pub fn planes_mut(&mut self) -> AudioPlanesMut<S> {
+    pub fn planes_mut(&mut self) -> AudioPlanesMut<'_, S> {
         // Fill the audio planes structure with references to the written portion of each audio
         // plane.
         let mut planes = AudioPlanesMut::new(self.spec.channels);
@@ -446,6 +447,25 @@ impl<'a> AudioBufferRef<'a> {
         impl_audio_buffer_ref_func!(self, buf, buf.frames())
     }
 
+    /// Gets the sample format of the buffer.
+    pub fn sample_format(&self) -> SampleFormat {
+        match self {
+            AudioBufferRef::U8(_) => SampleFormat::U8,
+            AudioBufferRef::U16(_) => SampleFormat::U16,
+            AudioBufferRef::U24(_) => SampleFormat::U24,
+            AudioBufferRef::U32(_) => SampleFormat::U32,
+            AudioBufferRef::S8(_) => SampleFormat::S8,
+            AudioBufferRef::S16(_) => SampleFormat::S16,
+            AudioBufferRef::S24(_) => SampleFormat::S24,
+            AudioBufferRef::S32(_) => SampleFormat::S32,
+            AudioBufferRef::F32(_) => SampleFormat::F32,
+            AudioBufferRef::F64(_) => SampleFormat::F64,
+        }
+    }
+
+    /// Converts the contents of the buffer into an equivalent destination `AudioBuffer` of any
+    /// type. The destination buffer must have at least the same capacity and the same signal
+    /// specification as this buffer.
     pub fn convert<T>(&self, dest: &mut AudioBuffer<T>)
     where
         T: Sample
@@ -463,22 +483,34 @@ impl<'a> AudioBufferRef<'a> {
         impl_audio_buffer_ref_func!(self, buf, buf.convert(dest))
     }
 
+    /// Makes an equivalent, but empty, `AudioBuffer` of any type.
     pub fn make_equivalent<E: Sample>(&self) -> AudioBuffer<E> {
         impl_audio_buffer_ref_func!(self, buf, buf.make_equivalent::<E>())
     }
+
+    /// Makes a new `AudioBuffer` of the sample format `T` containing a converted copy of the
+    /// contents of the buffer.
+    ///
+    /// To avoid an allocation for every decoded packet, prefer using [`AudioBufferRef::convert`]
+    /// with a buffer from [`AudioBufferRef::make_equivalent`] that is kept between packets.
+    pub fn to_typed<T: ConvertibleSample>(&self) -> AudioBuffer<T> {
+        let mut dest = self.make_equivalent::<T>();
+        self.convert(&mut dest);
+        dest
+    }
 }
 
 /// `AsAudioBufferRef` is a trait implemented for `AudioBuffer`s that may be referenced in an
 /// `AudioBufferRef`.
 pub trait AsAudioBufferRef {
     /// Get an `AudioBufferRef` reference.
-    fn as_audio_buffer_ref(&self) -> AudioBufferRef;
+    fn as_audio_buffer_ref(&self) -> AudioBufferRef<'_>;
 }
 
 macro_rules! impl_as_audio_buffer_ref {
     ($fmt:ty, $ref:path) => {
         impl AsAudioBufferRef for AudioBuffer<$fmt> {
-            fn as_audio_buffer_ref(&self) -> AudioBufferRef {
+            fn as_audio_buffer_ref(&self) -> AudioBufferRef<'_> {
                 $ref(Cow::Borrowed(self))
             }
         }
@@ -849,12 +881,76 @@ impl<S: Sample> SampleBuffer<S> {
         // of samples that will be copied from the source buffer.
         assert!(self.capacity() >= n_samples);
 
-        // Interleave the source buffer channels into the sample buffer.
-        for ch in 0..n_channels {
-            let ch_slice = src.chan(ch);
+        // The destination buffer slice.
+        let dst_buf = &mut self.buf[..n_samples];
 
-            for (dst, src) in self.buf[ch..].iter_mut().step_by(n_channels).zip(ch_slice) {
-                *dst = (*src).into_sample();
+        // Interleave the source buffer channels into the sample buffer. Provide slightly optimized
+        // interleave algorithms for Mono and Stereo buffers.
+        match n_channels {
+            // No channels, do nothing.
+            0 => (),
+            // Mono
+            1 => {
+                for (&s, d) in src.chan(0).iter().zip(dst_buf) {
+                    *d = s.into_sample();
+                }
+            }
+            // Stereo
+            2 => {
+                let l_buf = src.chan(0);
+                let r_buf = src.chan(1);
+
+                for ((&l, &r), d) in l_buf.iter().zip(r_buf).zip(dst_buf.chunks_exact_mut(2)) {
+                    d[0] = l.into_sample();
+                    d[1] = r.into_sample();
+                }
+            }
+            // 3+ channels
+            _ => {
+                for ch in 0..n_channels {
+                    let src_ch = src.chan(ch);
+                    let dst_ch_iter = dst_buf[ch..].iter_mut().step_by(n_channels);
+
+                    for (&s, d) in src_ch.iter().zip(dst_ch_iter) {
+                        *d = s.into_sample();
+                    }
+                }
+            }
+        }
+
+        // Commit the written samples.
+        self.n_written = n_samples;
+    }
+
+    /// Copies all audio samples from a source `AudioBuffer` into the `SampleBuffer` in interleaved
+    /// channel order, dithering each sample before it is converted. The two buffers must be
+    /// equivalent.
+    ///
+    /// One dither must be provided for each channel. The dithers should be kept and used for the
+    /// next copy such that their state carries over from one buffer to the next. Samples are only
+    /// dithered if the conversion from `F` to `S` reduces the resolution of the samples.
+    pub fn copy_interleaved_dithered<F, D>(&mut self, src: &AudioBuffer<F>, dithers: &mut [D])
+    where
+        F: MaybeDither<S> + IntoSample<S>,
+        D: Dither<F, S>,
+    {
+        let n_channels = src.spec.channels.count();
+        let n_samples = src.n_frames * n_channels;
+
+        // Ensure that the capacity of the sample buffer is greater than or equal to the number
+        // of samples that will be copied from the source buffer.
+        assert!(self.capacity() >= n_samples);
+        assert!(dithers.len() >= n_channels, "a dither must be provided for each channel");
+
+        // The destination buffer slice.
+        let dst_buf = &mut self.buf[..n_samples];
+
+        for (ch, dither) in dithers[..n_channels].iter_mut().enumerate() {
+            let src_ch = src.chan(ch);
+            let dst_ch_iter = dst_buf[ch..].iter_mut().step_by(n_channels);
+
+            for (&s, d) in src_ch.iter().zip(dst_ch_iter) {
+                *d = s.maybe_dither(dither).into_sample();
             }
         }
 
@@ -991,20 +1087,55 @@ impl RawSample for f64 {
     }
 }
 
+/// `ByteOrder` is the order of the bytes of a multi-byte sample.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// The least-significant byte is first.
+    LittleEndian,
+    /// The most-significant byte is first.
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// Gets the byte order of the target platform.
+    pub fn native() -> ByteOrder {
+        if cfg!(target_endian = "big") {
+            ByteOrder::BigEndian
+        }
+        else {
+            ByteOrder::LittleEndian
+        }
+    }
+}
+
 /// A `RawSampleBuffer`, is a byte-oriented sample buffer. All samples copied to this buffer are
 /// converted into their packed data-type and stored as a stream of bytes. `RawSampleBuffer` is
 /// mean't for safely importing and exporting sample data to and from Symphonia as raw bytes.
+///
+/// By default, samples are stored in the native byte order of the platform. Use
+/// [`RawSampleBuffer::new_with_byte_order`] to store samples in a specific byte order instead.
 pub struct RawSampleBuffer<S: Sample + RawSample> {
     buf: Box<[S::RawType]>,
     n_written: usize,
+    byte_order: ByteOrder,
     // Might take your heart.
     sample_format: PhantomData<S>,
 }
 
 impl<S: Sample + RawSample> RawSampleBuffer<S> {
     /// Instantiate a new `RawSampleBuffer` using the specified signal specification and of the given
-    /// duration.
+    /// duration. Samples are stored in the native byte order.
     pub fn new(duration: Duration, spec: SignalSpec) -> RawSampleBuffer<S> {
+        Self::new_with_byte_order(duration, spec, ByteOrder::native())
+    }
+
+    /// Instantiate a new `RawSampleBuffer` using the specified signal specification and of the given
+    /// duration. Samples are stored in the given byte order.
+    pub fn new_with_byte_order(
+        duration: Duration,
+        spec: SignalSpec,
+        byte_order: ByteOrder,
+    ) -> RawSampleBuffer<S> {
         // The number of channels * duration cannot exceed u64::MAX.
         assert!(duration <= u64::MAX / spec.channels.count() as u64, "duration too large");
 
@@ -1022,7 +1153,12 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         // Allocate enough memory for all the samples and fill the buffer with silence.
         let buf = vec![S::MID.into_raw_sample(); n_samples as usize].into_boxed_slice();
 
-        RawSampleBuffer { buf, n_written: 0, sample_format: PhantomData }
+        RawSampleBuffer { buf, n_written: 0, byte_order, sample_format: PhantomData }
+    }
+
+    /// Gets the byte order of the samples.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
     }
 
     /// Gets the number of written samples.
@@ -1053,6 +1189,18 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         bytemuck::cast_slice(&self.buf[..self.n_written])
     }
 
+    /// Commits `n_samples` written samples, and swaps the bytes of each if they are not to be
+    /// stored in the native byte order.
+    fn commit(&mut self, n_samples: usize) {
+        if self.byte_order != ByteOrder::native() {
+            for sample in self.buf[..n_samples].iter_mut() {
+                bytemuck::bytes_of_mut(sample).reverse();
+            }
+        }
+
+        self.n_written = n_samples;
+    }
+
     /// Copies all audio data from the source `AudioBufferRef` in planar channel order into the
     /// `RawSampleBuffer`. The two buffers must be equivalent.
     pub fn copy_planar_ref(&mut self, src: AudioBufferRef)
@@ -1097,7 +1245,7 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
             }
         }
 
-        self.n_written = n_samples;
+        self.commit(n_samples);
     }
 
     /// Copies all audio data from the source `AudioBuffer` to the `RawSampleBuffer` in planar order.
@@ -1120,7 +1268,7 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
             }
         }
 
-        self.n_written = n_samples;
+        self.commit(n_samples);
     }
 
     /// Copies all audio data from the source `AudioBufferRef` in interleaved channel order into the
@@ -1194,7 +1342,7 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
             }
         }
 
-        self.n_written = n_samples;
+        self.commit(n_samples);
     }
 
     /// Copies all audio data from the source `AudioBuffer` to the `RawSampleBuffer` in interleaved
@@ -1244,6 +1392,187 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
             }
         }
 
-        self.n_written = n_samples;
+        self.commit(n_samples);
+    }
+
+    /// Copies all audio samples from a source `AudioBuffer` into the `RawSampleBuffer` in
+    /// interleaved channel order, dithering each sample before it is converted. The two buffers
+    /// must be equivalent.
+    ///
+    /// One dither must be provided for each channel. The dithers should be kept and used for the
+    /// next copy such that their state carries over from one buffer to the next. Samples are only
+    /// dithered if the conversion from `F` to `S` reduces the resolution of the samples.
+    pub fn copy_interleaved_dithered<F, D>(&mut self, src: &AudioBuffer<F>, dithers: &mut [D])
+    where
+        F: MaybeDither<S> + IntoSample<S>,
+        D: Dither<F, S>,
+    {
+        let n_channels = src.spec.channels.count();
+        let n_samples = src.n_frames * n_channels;
+
+        // Ensure that the capacity of the sample buffer is greater than or equal to the number
+        // of samples that will be copied from the source buffer.
+        assert!(self.capacity() >= n_samples);
+        assert!(dithers.len() >= n_channels, "a dither must be provided for each channel");
+
+        // The destination buffer slice.
+        let dst_buf = &mut self.buf[..n_samples];
+
+        for (ch, dither) in dithers[..n_channels].iter_mut().enumerate() {
+            let src_ch = src.chan(ch);
+            let dst_ch_iter = dst_buf[ch..].iter_mut().step_by(n_channels);
+
+            for (&s, d) in src_ch.iter().zip(dst_ch_iter) {
+                *d = IntoSample::<S>::into_sample(s.maybe_dither(dither)).into_raw_sample();
+            }
+        }
+
+        self.commit(n_samples);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsAudioBufferRef, SampleBuffer, Signal, SignalSpec};
+    use super::{AudioBuffer, AudioBufferRef, ByteOrder, Channels, RawSampleBuffer};
+    use crate::conv::dither::Triangular;
+    use crate::sample::{i24, SampleFormat};
+    use std::borrow::Cow;
+
+    /// Gets a buffer where the samples of channel `c` are `c`, `c + 0.1`, `c + 0.2`, etc.
+    fn make_buffer(channels: Channels, n_frames: usize) -> AudioBuffer<f32> {
+        let mut buf = AudioBuffer::<f32>::new(n_frames as u64, SignalSpec::new(8_000, channels));
+        buf.render_reserved(Some(n_frames));
+
+        for c in 0..channels.count() {
+            for (i, s) in buf.chan_mut(c).iter_mut().enumerate() {
+                *s = c as f32 + 0.1 * i as f32;
+            }
+        }
+
+        buf
+    }
+
+    #[test]
+    fn verify_sample_buffer_interleaved() {
+        let three = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+
+        for &channels in
+            &[Channels::FRONT_LEFT, Channels::FRONT_LEFT | Channels::FRONT_RIGHT, three]
+        {
+            let n_channels = channels.count();
+            let src = make_buffer(channels, 4);
+
+            let mut buf = SampleBuffer::<f32>::new(4, *src.spec());
+            buf.copy_interleaved_ref(AudioBufferRef::F32(Cow::Borrowed(&src)));
+
+            assert_eq!(buf.len(), 4 * n_channels);
+
+            for (i, frame) in buf.samples().chunks_exact(n_channels).enumerate() {
+                for (c, &s) in frame.iter().enumerate() {
+                    assert_eq!(s, src.chan(c)[i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_sample_buffer_planar() {
+        let src = make_buffer(Channels::FRONT_LEFT | Channels::FRONT_RIGHT, 3);
+
+        // Convert to signed 16-bit samples while copying.
+        let mut buf = SampleBuffer::<i16>::new(3, *src.spec());
+        buf.copy_planar_typed(&src);
+
+        assert_eq!(buf.samples(), &[0, 3276, 6553, 32767, 32767, 32767]);
+
+        buf.clear();
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn verify_raw_sample_buffer_byte_order() {
+        let mut src = AudioBuffer::<i32>::new(2, SignalSpec::new(8_000, Channels::FRONT_LEFT));
+        src.render_reserved(Some(2));
+        src.chan_mut(0).copy_from_slice(&[0x0102_0304, -2]);
+
+        let spec = *src.spec();
+
+        // Packed 24-bit samples.
+        let mut le = RawSampleBuffer::<i24>::new_with_byte_order(2, spec, ByteOrder::LittleEndian);
+        le.copy_interleaved_typed(&src);
+        assert_eq!(le.as_bytes(), &[0x03, 0x02, 0x01, 0xff, 0xff, 0xff]);
+
+        let mut be = RawSampleBuffer::<i24>::new_with_byte_order(2, spec, ByteOrder::BigEndian);
+        be.copy_planar_typed(&src);
+        assert_eq!(be.as_bytes(), &[0x01, 0x02, 0x03, 0xff, 0xff, 0xff]);
+
+        let mut be = RawSampleBuffer::<i32>::new_with_byte_order(2, spec, ByteOrder::BigEndian);
+        be.copy_interleaved(&src);
+        assert_eq!(be.as_bytes(), &[0x01, 0x02, 0x03, 0x04, 0xff, 0xff, 0xff, 0xfe]);
+    }
+
+    #[test]
+    fn verify_audio_buffer_ref() {
+        let channels = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+
+        let src = make_buffer(channels, 4);
+        let buf_ref = src.as_audio_buffer_ref();
+
+        assert_eq!(buf_ref.sample_format(), SampleFormat::F32);
+        assert_eq!(buf_ref.frames(), 4);
+        assert_eq!(buf_ref.spec(), src.spec());
+
+        // Convert to a concrete sample format chosen by the caller.
+        let converted = buf_ref.to_typed::<i16>();
+
+        assert_eq!(converted.spec(), src.spec());
+        assert_eq!(converted.frames(), 4);
+        assert_eq!(converted.chan(0), &[0, 3276, 6553, 9830]);
+        assert_eq!(converted.chan(1), &[32767, 32767, 32767, 32767]);
+
+        // Converting into a kept buffer produces the same samples.
+        let mut dest = buf_ref.make_equivalent::<i16>();
+        buf_ref.convert(&mut dest);
+
+        assert_eq!(dest.chan(0), converted.chan(0));
+        assert_eq!(converted.as_audio_buffer_ref().sample_format(), SampleFormat::S16);
+    }
+
+    #[test]
+    fn verify_sample_buffer_dithered() {
+        let channels = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+
+        let mut src = make_buffer(channels, 64);
+        src.transform(|s| s / 64.0);
+
+        let spec = *src.spec();
+
+        let mut plain = SampleBuffer::<i16>::new(64, spec);
+        plain.copy_interleaved_typed(&src);
+
+        let mut dithers = [Triangular::new(), Triangular::new(), Triangular::new()];
+
+        let mut buf = SampleBuffer::<i16>::new(64, spec);
+        buf.copy_interleaved_dithered(&src, &mut dithers);
+
+        // Dithered samples are within 1 LSB of the undithered samples, but not all the same.
+        assert_eq!(buf.len(), plain.len());
+        assert!(buf.samples().iter().zip(plain.samples()).all(|(&a, &b)| (a - b).abs() <= 1));
+        assert_ne!(buf.samples(), plain.samples());
+
+        let mut raw = RawSampleBuffer::<i16>::new_with_byte_order(64, spec, ByteOrder::BigEndian);
+        raw.copy_interleaved_dithered(&src, &mut dithers);
+        assert_eq!(raw.len(), plain.len());
+
+        // Conversions that do not reduce resolution are never dithered.
+        let mut dithers = [Triangular::new(), Triangular::new(), Triangular::new()];
+
+        let mut buf = SampleBuffer::<f32>::new(64, spec);
+        buf.copy_interleaved_dithered(&src, &mut dithers);
+
+        let mut plain = SampleBuffer::<f32>::new(64, spec);
+        plain.copy_interleaved_typed(&src);
+        assert_eq!(buf.samples(), plain.samples());
     }
 }
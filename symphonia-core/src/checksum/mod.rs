@@ -12,8 +12,10 @@ mod crc16;
 mod crc32;
 mod crc8;
 mod md5;
+mod pcm;
 
 pub use crc16::{Crc16Ansi, Crc16AnsiLe};
 pub use crc32::Crc32;
 pub use crc8::Crc8Ccitt;
 pub use md5::Md5;
+pub use pcm::PcmMd5;
@@ -8,27 +8,35 @@
 use std::mem;
 use std::vec::Vec;
 
-use symphonia_core::audio::{AudioBuffer, Signal};
-use symphonia_core::checksum::Md5;
-use symphonia_core::io::Monitor;
-
-/// `Validator` computes the MD5 checksum of an audio stream taking into account the peculiarities
-/// of FLAC's MD5 validation scheme.
+use crate::audio::{AudioBuffer, Signal};
+use crate::io::Monitor;
+
+use super::Md5;
+
+/// `PcmMd5` computes the MD5 checksum of decoded audio as interleaved, little-endian, PCM samples.
+///
+/// This is the checksum of the decoded audio stored by lossless codecs, such as FLAC, and may be
+/// used by a decoder to verify its output. Decoders should only update the checksum when
+/// verification was requested with [`DecoderOptions::verify`], and compare the checksum to the
+/// expected [`VerificationCheck::Md5`] when finalized.
+///
+/// [`DecoderOptions::verify`]: crate::codecs::DecoderOptions::verify
+/// [`VerificationCheck::Md5`]: crate::codecs::VerificationCheck::Md5
 #[derive(Default)]
-pub struct Validator {
+pub struct PcmMd5 {
     state: Md5,
     buf: Vec<u8>,
 }
 
-impl Validator {
-    /// Processes the audio buffer and updates the state of the validator.
+impl PcmMd5 {
+    /// Processes the audio buffer, containing samples of `bps` bits per sample, and updates the
+    /// checksum.
     pub fn update(&mut self, buf: &AudioBuffer<i32>, bps: u32) {
         // The MD5 checksum is calculated on a buffer containing interleaved audio samples of the
-        // correct sample width. While FLAC can encode and decode samples of arbitrary bit widths,
-        // the samples in the buffer must be a multiple of 8-bits.
+        // correct sample width. While a codec can encode and decode samples of arbitrary bit
+        // widths, the samples in the buffer must be a multiple of 8-bits.
         //
-        // Additionally, Symphonia's AudioBuffer's are in planar format, and the FLAC decoder works
-        // internally on signed 32-bit samples exclusively.
+        // Additionally, Symphonia's AudioBuffer's are in planar format.
         //
         // Therefore, to compute the checksum, the audio buffer samples must truncated to the
         // correct bit-width, interlaced, and converted to a little-endian byte buffer. The byte
@@ -69,7 +77,7 @@ impl Validator {
         self.state.process_buf_bytes(buf_slice);
     }
 
-    /// Get the checksum.
+    /// Get the checksum of all the audio processed so far.
     pub fn md5(&mut self) -> [u8; 16] {
         self.state.md5()
     }
@@ -121,3 +129,40 @@ macro_rules! copy_as {
 copy_as!(copy_as_i8, i8);
 copy_as!(copy_as_i16, i16);
 copy_as!(copy_as_i32, i32);
+
+#[cfg(test)]
+mod tests {
+    use super::PcmMd5;
+    use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+    use crate::checksum::Md5;
+    use crate::io::Monitor;
+
+    /// Gets the MD5 checksum of the bytes.
+    fn md5(bytes: &[u8]) -> [u8; 16] {
+        let mut md5 = Md5::default();
+        md5.process_buf_bytes(bytes);
+        md5.md5()
+    }
+
+    #[test]
+    fn verify_pcm_md5() {
+        let spec = SignalSpec::new(44_100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut buf = AudioBuffer::<i32>::new(2, spec);
+        buf.render_reserved(Some(2));
+        buf.chan_mut(0).copy_from_slice(&[1, 0x123456]);
+        buf.chan_mut(1).copy_from_slice(&[-1, -2]);
+
+        // 16-bit samples are truncated, and interleaved.
+        let mut pcm = PcmMd5::default();
+        pcm.update(&buf, 16);
+        assert_eq!(pcm.md5(), md5(&[0x01, 0x00, 0xff, 0xff, 0x56, 0x34, 0xfe, 0xff]));
+
+        // 20-bit samples are rounded up to 24-bit samples.
+        let mut pcm = PcmMd5::default();
+        pcm.update(&buf, 20);
+
+        let expected = [0x01, 0x00, 0x00, 0xff, 0xff, 0xff, 0x56, 0x34, 0x12, 0xfe, 0xff, 0xff];
+        assert_eq!(pcm.md5(), md5(&expected));
+    }
+}
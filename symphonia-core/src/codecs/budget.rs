@@ -0,0 +1,138 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `budget` module provides a per-packet work budget for decoders.
+//!
+//! The amount of work required to decode a packet is usually bounded by the codec, but some codecs
+//! have loops whose iteration count is controlled by the (untrusted) bitstream. When decoding media
+//! from an untrusted source, such as in a server, a crafted packet could then consume an excessive
+//! amount of CPU time. A [`DecodeBudget`] caps the number of iterations, and the time, a decoder may
+//! spend on a single packet, as configured by
+//! [`DecoderOptions`](super::DecoderOptions).
+
+use std::time::{Duration, Instant};
+
+use crate::errors::{limit_error, Result};
+
+use super::DecoderOptions;
+
+/// A `DecodeBudget` tracks the work done by a decoder while decoding a single packet.
+///
+/// A decoder calls [`DecodeBudget::begin`] before decoding each packet, and then
+/// [`DecodeBudget::consume`] as it progresses. Since the elapsed time is checked on every call to
+/// `consume`, decoders should consume iterations in batches (e.g., once per channel), and not once
+/// per iteration.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeBudget {
+    /// The maximum number of iterations per packet, if limited.
+    max_iterations: Option<usize>,
+    /// The maximum time to spend decoding a packet, if limited.
+    max_time: Option<Duration>,
+    /// The number of iterations remaining for the current packet.
+    remaining: usize,
+    /// The instant after which decoding the current packet must be aborted.
+    deadline: Option<Instant>,
+}
+
+impl DecodeBudget {
+    /// Instantiates a new `DecodeBudget` from the limits in `options`. `default_iterations` is the
+    /// decoder specific default for the number of iterations per packet.
+    pub fn new(options: &DecoderOptions, default_iterations: usize) -> Self {
+        Self::with_limits(
+            options.limit_decode_iterations.limit_or_default(default_iterations),
+            options.limit_decode_time,
+        )
+    }
+
+    /// Instantiates a new `DecodeBudget` with explicit limits. `None` does not impose a limit.
+    pub fn with_limits(max_iterations: Option<usize>, max_time: Option<Duration>) -> Self {
+        DecodeBudget { max_iterations, max_time, remaining: 0, deadline: None }
+    }
+
+    /// Returns true if neither the number of iterations, nor the time, is limited.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_iterations.is_none() && self.max_time.is_none()
+    }
+
+    /// Resets the budget for a new packet.
+    pub fn begin(&mut self) {
+        self.remaining = self.max_iterations.unwrap_or(usize::MAX);
+        self.deadline = self.max_time.map(|max_time| Instant::now() + max_time);
+    }
+
+    /// Consumes `n` iterations of the budget of the current packet. Returns a `LimitError` if the
+    /// iterations, or time, of the budget are exhausted.
+    pub fn consume(&mut self, n: usize) -> Result<()> {
+        if n > self.remaining {
+            return limit_error("core: decode iteration limit exceeded");
+        }
+
+        self.remaining -= n;
+
+        if let Some(deadline) = self.deadline {
+            if Instant::now() > deadline {
+                return limit_error("core: decode time limit exceeded");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::DecodeBudget;
+    use crate::codecs::DecoderOptions;
+    use crate::errors::Error;
+    use crate::meta::Limit;
+
+    #[test]
+    fn verify_decode_budget_iterations() {
+        let options =
+            DecoderOptions { limit_decode_iterations: Limit::Maximum(10), ..Default::default() };
+
+        let mut budget = DecodeBudget::new(&options, 1000);
+
+        budget.begin();
+        assert!(budget.consume(4).is_ok());
+        assert!(budget.consume(6).is_ok());
+        assert!(matches!(budget.consume(1), Err(Error::LimitError(_))));
+
+        // The budget is replenished for every packet.
+        budget.begin();
+        assert!(budget.consume(10).is_ok());
+
+        // The decoder's default, or no limit.
+        let mut budget = DecodeBudget::new(&Default::default(), 1000);
+        budget.begin();
+        assert!(budget.consume(1000).is_ok());
+        assert!(budget.consume(1).is_err());
+
+        let options = DecoderOptions { limit_decode_iterations: Limit::None, ..Default::default() };
+
+        let mut budget = DecodeBudget::new(&options, 1000);
+        assert!(budget.is_unlimited());
+        budget.begin();
+        assert!(budget.consume(usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn verify_decode_budget_time() {
+        let mut budget = DecodeBudget::with_limits(None, Some(Duration::from_millis(0)));
+
+        budget.begin();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(matches!(budget.consume(0), Err(Error::LimitError(_))));
+
+        let mut budget = DecodeBudget::with_limits(None, Some(Duration::from_secs(3600)));
+
+        budget.begin();
+        assert!(budget.consume(1).is_ok());
+    }
+}
@@ -0,0 +1,344 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `ids` module provides a central registry of codec names, and of the container-specific
+//! identifiers that map to each `CodecType`.
+//!
+//! Container-specific identifiers that require additional information to resolve to a single
+//! `CodecType`, such as the PCM format tags of WAVE whose codec also depends on the bit depth, are
+//! not included.
+
+use super::*;
+
+/// `CodecInfo` provides human-readable names for a `CodecType`.
+#[derive(Copy, Clone, Debug)]
+pub struct CodecInfo {
+    /// The codec type.
+    pub codec: CodecType,
+    /// A short ASCII-only string identifying the codec.
+    pub short_name: &'static str,
+    /// A longer, more descriptive, string identifying the codec.
+    pub long_name: &'static str,
+}
+
+macro_rules! codec_info {
+    ($codec:expr, $short_name:expr, $long_name:expr) => {
+        CodecInfo { codec: $codec, short_name: $short_name, long_name: $long_name }
+    };
+}
+
+/// Names for all codec types defined by Symphonia.
+const CODEC_INFO: &[CodecInfo] = &[
+    codec_info!(CODEC_TYPE_NULL, "null", "Null"),
+    // Uncompressed PCM audio codecs.
+    codec_info!(CODEC_TYPE_PCM_S32LE, "pcm_s32le", "PCM Signed 32-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S32LE_PLANAR,
+        "pcm_s32le_planar",
+        "PCM Signed 32-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S32BE, "pcm_s32be", "PCM Signed 32-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S32BE_PLANAR,
+        "pcm_s32be_planar",
+        "PCM Signed 32-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S24LE, "pcm_s24le", "PCM Signed 24-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S24LE_PLANAR,
+        "pcm_s24le_planar",
+        "PCM Signed 24-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S24BE, "pcm_s24be", "PCM Signed 24-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S24BE_PLANAR,
+        "pcm_s24be_planar",
+        "PCM Signed 24-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S16LE, "pcm_s16le", "PCM Signed 16-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S16LE_PLANAR,
+        "pcm_s16le_planar",
+        "PCM Signed 16-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S16BE, "pcm_s16be", "PCM Signed 16-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_S16BE_PLANAR,
+        "pcm_s16be_planar",
+        "PCM Signed 16-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_S8, "pcm_s8", "PCM Signed 8-bit Interleaved"),
+    codec_info!(CODEC_TYPE_PCM_S8_PLANAR, "pcm_s8_planar", "PCM Signed 8-bit Planar"),
+    codec_info!(CODEC_TYPE_PCM_U32LE, "pcm_u32le", "PCM Unsigned 32-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U32LE_PLANAR,
+        "pcm_u32le_planar",
+        "PCM Unsigned 32-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U32BE, "pcm_u32be", "PCM Unsigned 32-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U32BE_PLANAR,
+        "pcm_u32be_planar",
+        "PCM Unsigned 32-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U24LE, "pcm_u24le", "PCM Unsigned 24-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U24LE_PLANAR,
+        "pcm_u24le_planar",
+        "PCM Unsigned 24-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U24BE, "pcm_u24be", "PCM Unsigned 24-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U24BE_PLANAR,
+        "pcm_u24be_planar",
+        "PCM Unsigned 24-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U16LE, "pcm_u16le", "PCM Unsigned 16-bit Little-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U16LE_PLANAR,
+        "pcm_u16le_planar",
+        "PCM Unsigned 16-bit Little-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U16BE, "pcm_u16be", "PCM Unsigned 16-bit Big-Endian Interleaved"),
+    codec_info!(
+        CODEC_TYPE_PCM_U16BE_PLANAR,
+        "pcm_u16be_planar",
+        "PCM Unsigned 16-bit Big-Endian Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_U8, "pcm_u8", "PCM Unsigned 8-bit Interleaved"),
+    codec_info!(CODEC_TYPE_PCM_U8_PLANAR, "pcm_u8_planar", "PCM Unsigned 8-bit Planar"),
+    codec_info!(
+        CODEC_TYPE_PCM_F32LE,
+        "pcm_f32le",
+        "PCM 32-bit Little-Endian Floating Point Interleaved"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F32LE_PLANAR,
+        "pcm_f32le_planar",
+        "PCM 32-bit Little-Endian Floating Point Planar"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F32BE,
+        "pcm_f32be",
+        "PCM 32-bit Big-Endian Floating Point Interleaved"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F32BE_PLANAR,
+        "pcm_f32be_planar",
+        "PCM 32-bit Big-Endian Floating Point Planar"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F64LE,
+        "pcm_f64le",
+        "PCM 64-bit Little-Endian Floating Point Interleaved"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F64LE_PLANAR,
+        "pcm_f64le_planar",
+        "PCM 64-bit Little-Endian Floating Point Planar"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F64BE,
+        "pcm_f64be",
+        "PCM 64-bit Big-Endian Floating Point Interleaved"
+    ),
+    codec_info!(
+        CODEC_TYPE_PCM_F64BE_PLANAR,
+        "pcm_f64be_planar",
+        "PCM 64-bit Big-Endian Floating Point Planar"
+    ),
+    codec_info!(CODEC_TYPE_PCM_ALAW, "pcm_alaw", "PCM A-law (G.711)"),
+    codec_info!(CODEC_TYPE_PCM_MULAW, "pcm_mulaw", "PCM Mu-law (G.711)"),
+    // ADPCM audio codecs.
+    codec_info!(CODEC_TYPE_ADPCM_G722, "adpcm_g722", "G.722 ADPCM"),
+    codec_info!(CODEC_TYPE_ADPCM_G726, "adpcm_g726", "G.726 ADPCM"),
+    codec_info!(CODEC_TYPE_ADPCM_G726LE, "adpcm_g726le", "G.726 ADPCM Little-Endian"),
+    codec_info!(CODEC_TYPE_ADPCM_MS, "adpcm_ms", "Microsoft ADPCM"),
+    codec_info!(CODEC_TYPE_ADPCM_IMA_WAV, "adpcm_ima_wav", "IMA ADPCM WAV"),
+    codec_info!(CODEC_TYPE_ADPCM_IMA_QT, "adpcm_ima_qt", "IMA ADPCM QuickTime"),
+    // Compressed lossy audio codecs.
+    codec_info!(CODEC_TYPE_VORBIS, "vorbis", "Vorbis"),
+    codec_info!(CODEC_TYPE_MP1, "mp1", "MPEG Audio Layer 1"),
+    codec_info!(CODEC_TYPE_MP2, "mp2", "MPEG Audio Layer 2"),
+    codec_info!(CODEC_TYPE_MP3, "mp3", "MPEG Audio Layer 3"),
+    codec_info!(CODEC_TYPE_AAC, "aac", "Advanced Audio Coding"),
+    codec_info!(CODEC_TYPE_OPUS, "opus", "Opus"),
+    codec_info!(CODEC_TYPE_SPEEX, "speex", "Speex"),
+    codec_info!(CODEC_TYPE_MUSEPACK, "musepack", "Musepack"),
+    codec_info!(CODEC_TYPE_ATRAC1, "atrac1", "Adaptive Transform Acoustic Coding"),
+    codec_info!(CODEC_TYPE_ATRAC3, "atrac3", "Adaptive Transform Acoustic Coding 3"),
+    codec_info!(CODEC_TYPE_ATRAC3PLUS, "atrac3plus", "Adaptive Transform Acoustic Coding 3+"),
+    codec_info!(CODEC_TYPE_ATRAC9, "atrac9", "Adaptive Transform Acoustic Coding 9"),
+    codec_info!(CODEC_TYPE_EAC3, "eac3", "Dolby Digital (AC-3, E-AC-3)"),
+    codec_info!(CODEC_TYPE_AC4, "ac4", "Dolby AC-4"),
+    codec_info!(CODEC_TYPE_DCA, "dca", "DTS Coherent Acoustics"),
+    codec_info!(CODEC_TYPE_WMA, "wma", "Windows Media Audio"),
+    codec_info!(CODEC_TYPE_AMR_NB, "amr_nb", "Adaptive Multi-Rate Narrowband"),
+    codec_info!(CODEC_TYPE_AMR_WB, "amr_wb", "Adaptive Multi-Rate Wideband"),
+    codec_info!(CODEC_TYPE_GSM, "gsm", "GSM 06.10 Full Rate"),
+    codec_info!(CODEC_TYPE_GSM_MS, "gsm_ms", "GSM 06.10 Full Rate (Microsoft)"),
+    // Compressed lossless audio codecs.
+    codec_info!(CODEC_TYPE_FLAC, "flac", "Free Lossless Audio Codec"),
+    codec_info!(CODEC_TYPE_WAVPACK, "wavpack", "WavPack"),
+    codec_info!(CODEC_TYPE_MONKEYS_AUDIO, "ape", "Monkey's Audio"),
+    codec_info!(CODEC_TYPE_ALAC, "alac", "Apple Lossless Audio Codec"),
+    codec_info!(CODEC_TYPE_TTA, "tta", "True Audio"),
+];
+
+/// WAVE format tags. The definition of these format identifiers can be found in mmreg.h of the
+/// Microsoft Windows Platform SDK.
+const WAVE_FORMAT_TAGS: &[(u16, CodecType)] = &[
+    (0x0002, CODEC_TYPE_ADPCM_MS),
+    (0x0006, CODEC_TYPE_PCM_ALAW),
+    (0x0007, CODEC_TYPE_PCM_MULAW),
+    (0x0011, CODEC_TYPE_ADPCM_IMA_WAV),
+    (0x0031, CODEC_TYPE_GSM_MS),
+    (0x0050, CODEC_TYPE_MP2),
+    (0x0055, CODEC_TYPE_MP3),
+    (0x0064, CODEC_TYPE_ADPCM_G726),
+    (0x0065, CODEC_TYPE_ADPCM_G722),
+    (0x00ff, CODEC_TYPE_AAC),
+    (0x0160, CODEC_TYPE_WMA),
+    (0x0161, CODEC_TYPE_WMA),
+    (0x0162, CODEC_TYPE_WMA),
+    (0x0163, CODEC_TYPE_WMA),
+    (0x0270, CODEC_TYPE_ATRAC3),
+    (0x1610, CODEC_TYPE_AAC),
+    (0x2000, CODEC_TYPE_EAC3),
+    (0x2001, CODEC_TYPE_DCA),
+    (0x5756, CODEC_TYPE_WAVPACK),
+    (0xf1ac, CODEC_TYPE_FLAC),
+];
+
+/// ISO/IEC 14496-12 (MP4) and QuickTime sample entry FourCCs. The endianness of the QuickTime
+/// `in24`, `in32`, `fl32`, and `fl64` sample entries is signalled separately, therefore they are
+/// not included.
+const MP4_FOURCCS: &[(&[u8; 4], CodecType)] = &[
+    (b"mp4a", CODEC_TYPE_AAC),
+    (b".mp3", CODEC_TYPE_MP3),
+    (b"ac-3", CODEC_TYPE_EAC3),
+    (b"ec-3", CODEC_TYPE_EAC3),
+    (b"ac-4", CODEC_TYPE_AC4),
+    (b"dtsc", CODEC_TYPE_DCA),
+    (b"dtsh", CODEC_TYPE_DCA),
+    (b"dtsl", CODEC_TYPE_DCA),
+    (b"dtse", CODEC_TYPE_DCA),
+    (b"Opus", CODEC_TYPE_OPUS),
+    (b"samr", CODEC_TYPE_AMR_NB),
+    (b"sawb", CODEC_TYPE_AMR_WB),
+    (b"agsm", CODEC_TYPE_GSM),
+    (b"fLaC", CODEC_TYPE_FLAC),
+    (b"alac", CODEC_TYPE_ALAC),
+    (b"alaw", CODEC_TYPE_PCM_ALAW),
+    (b"ulaw", CODEC_TYPE_PCM_MULAW),
+    (b"ima4", CODEC_TYPE_ADPCM_IMA_QT),
+    (b"raw ", CODEC_TYPE_PCM_U8),
+    (b"sowt", CODEC_TYPE_PCM_S16LE),
+    (b"twos", CODEC_TYPE_PCM_S16BE),
+];
+
+/// Matroska (MKV) and WebM codec IDs.
+const MKV_CODEC_IDS: &[(&str, CodecType)] = &[
+    ("A_MPEG/L1", CODEC_TYPE_MP1),
+    ("A_MPEG/L2", CODEC_TYPE_MP2),
+    ("A_MPEG/L3", CODEC_TYPE_MP3),
+    ("A_FLAC", CODEC_TYPE_FLAC),
+    ("A_OPUS", CODEC_TYPE_OPUS),
+    ("A_VORBIS", CODEC_TYPE_VORBIS),
+    ("A_AAC", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG2/MAIN", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG2/LC", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG2/LC/SBR", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG2/SSR", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG4/MAIN", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG4/LC", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG4/LC/SBR", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG4/SSR", CODEC_TYPE_AAC),
+    ("A_AAC/MPEG4/LTP", CODEC_TYPE_AAC),
+    ("A_AC3", CODEC_TYPE_EAC3),
+    ("A_EAC3", CODEC_TYPE_EAC3),
+    ("A_DTS", CODEC_TYPE_DCA),
+    ("A_ALAC", CODEC_TYPE_ALAC),
+    ("A_TTA1", CODEC_TYPE_TTA),
+    ("A_WAVPACK4", CODEC_TYPE_WAVPACK),
+    ("A_ATRAC/AT1", CODEC_TYPE_ATRAC1),
+];
+
+/// ISO/IEC 13818-1 (MPEG transport stream) stream types.
+const TS_STREAM_TYPES: &[(u8, CodecType)] = &[
+    // MPEG-1 and MPEG-2 audio may be any layer, the layer is stated in each frame header.
+    (0x03, CODEC_TYPE_MP3),
+    (0x04, CODEC_TYPE_MP3),
+    // ADTS and LATM/LOAS.
+    (0x0f, CODEC_TYPE_AAC),
+    (0x11, CODEC_TYPE_AAC),
+    // ATSC AC-3 and E-AC-3.
+    (0x81, CODEC_TYPE_EAC3),
+    (0x87, CODEC_TYPE_EAC3),
+];
+
+/// Gets the names of a codec type, if known.
+pub fn codec_info(codec: CodecType) -> Option<&'static CodecInfo> {
+    CODEC_INFO.iter().find(|info| info.codec == codec)
+}
+
+/// Gets the codec type for a WAVE format tag.
+pub fn from_wave_format_tag(tag: u16) -> Option<CodecType> {
+    WAVE_FORMAT_TAGS.iter().find(|(id, _)| *id == tag).map(|(_, codec)| *codec)
+}
+
+/// Gets the codec type for a MP4 sample entry FourCC.
+pub fn from_mp4_fourcc(fourcc: [u8; 4]) -> Option<CodecType> {
+    MP4_FOURCCS.iter().find(|(id, _)| **id == fourcc).map(|(_, codec)| *codec)
+}
+
+/// Gets the codec type for a Matroska codec ID.
+pub fn from_mkv_codec_id(codec_id: &str) -> Option<CodecType> {
+    MKV_CODEC_IDS.iter().find(|(id, _)| *id == codec_id).map(|(_, codec)| *codec)
+}
+
+/// Gets the codec type for a MPEG transport stream stream type.
+pub fn from_ts_stream_type(stream_type: u8) -> Option<CodecType> {
+    TS_STREAM_TYPES.iter().find(|(id, _)| *id == stream_type).map(|(_, codec)| *codec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_codec_info() {
+        // Every codec type must only be named once.
+        for (i, info) in CODEC_INFO.iter().enumerate() {
+            assert!(CODEC_INFO[i + 1..].iter().all(|other| other.codec != info.codec));
+        }
+
+        // Every codec type that is mapped from a container identifier should be named.
+        let mapped = WAVE_FORMAT_TAGS
+            .iter()
+            .map(|(_, codec)| codec)
+            .chain(MP4_FOURCCS.iter().map(|(_, codec)| codec))
+            .chain(MKV_CODEC_IDS.iter().map(|(_, codec)| codec))
+            .chain(TS_STREAM_TYPES.iter().map(|(_, codec)| codec));
+
+        for codec in mapped {
+            assert!(codec_info(*codec).is_some());
+        }
+
+        assert_eq!(format!("{}", CODEC_TYPE_EAC3), "Dolby Digital (AC-3, E-AC-3)");
+        assert_eq!(format!("{}", decl_codec_type(b"abc")), "0x8b30d000");
+    }
+
+    #[test]
+    fn verify_container_ids() {
+        assert_eq!(from_wave_format_tag(0x2000), Some(CODEC_TYPE_EAC3));
+        assert_eq!(from_wave_format_tag(0x0001), None);
+        assert_eq!(from_mp4_fourcc(*b"fLaC"), Some(CODEC_TYPE_FLAC));
+        assert_eq!(from_mkv_codec_id("A_AAC/MPEG4/LC"), Some(CODEC_TYPE_AAC));
+        assert_eq!(from_ts_stream_type(0x81), Some(CODEC_TYPE_EAC3));
+    }
+}
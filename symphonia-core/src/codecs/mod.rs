@@ -11,13 +11,25 @@
 use std::collections::HashMap;
 use std::default::Default;
 use std::fmt;
+use std::time::Duration;
 
 use crate::audio::{AudioBufferRef, Channels, Layout};
-use crate::errors::{unsupported_error, Result};
+use crate::dsp::emphasis::Emphasis;
+use crate::errors::{decode_error, unsupported_error, Result};
 use crate::formats::Packet;
+use crate::meta::Limit;
 use crate::sample::SampleFormat;
 use crate::units::TimeBase;
 
+use state::DecoderState;
+use transform::{ChannelTransformHook, TransformedDecoder};
+
+pub mod budget;
+pub mod ids;
+pub mod state;
+pub mod stats;
+pub mod transform;
+
 /// A `CodecType` is a unique identifier used to identify a specific codec.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct CodecType(u32);
@@ -75,7 +87,11 @@ pub const fn decl_codec_type(cc: &[u8]) -> CodecType {
 
 impl fmt::Display for CodecType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:#x}", self.0)
+        // Prefer the descriptive name of the codec, if one is known.
+        match ids::codec_info(*self) {
+            Some(info) => f.write_str(info.long_name),
+            None => write!(f, "{:#x}", self.0),
+        }
     }
 }
 
@@ -213,6 +229,14 @@ pub const CODEC_TYPE_AC4: CodecType = CodecType(0x100d);
 pub const CODEC_TYPE_DCA: CodecType = CodecType(0x100e);
 /// Windows Media Audio
 pub const CODEC_TYPE_WMA: CodecType = CodecType(0x100f);
+/// Adaptive Multi-Rate Narrowband (AMR-NB)
+pub const CODEC_TYPE_AMR_NB: CodecType = CodecType(0x1010);
+/// Adaptive Multi-Rate Wideband (AMR-WB)
+pub const CODEC_TYPE_AMR_WB: CodecType = CodecType(0x1011);
+/// GSM 06.10 Full Rate
+pub const CODEC_TYPE_GSM: CodecType = CodecType(0x1012);
+/// GSM 06.10 Full Rate, Microsoft variant (WAV49)
+pub const CODEC_TYPE_GSM_MS: CodecType = CodecType(0x1013);
 
 // Compressed lossless audio codecs
 //---------------------------------
@@ -245,6 +269,11 @@ pub enum VerificationCheck {
 
 /// Codec parameters stored in a container format's headers and metadata may be passed to a codec
 /// using the `CodecParameters` structure.
+///
+/// Applications that do not use a format reader, for example, to decode an elementary stream
+/// received over their own network protocol, may build `CodecParameters` manually using the
+/// `for_codec` and `with_*` functions, check them with [`CodecParameters::validate`], and then
+/// instantiate a decoder with them directly.
 #[derive(Clone, Debug)]
 pub struct CodecParameters {
     /// The codec type.
@@ -284,6 +313,14 @@ pub struct CodecParameters {
     /// The channel layout.
     pub channel_layout: Option<Layout>,
 
+    /// The two channels of the stream are independent mono programs (e.g., a bilingual broadcast)
+    /// instead of a stereo pair. See [`DualMonoPolicy`].
+    pub is_dual_mono: bool,
+
+    /// The pre-emphasis applied to the audio before encoding, if any. The decoded audio must be
+    /// filtered with a matching [`DeEmphasis`](crate::dsp::emphasis::DeEmphasis) filter.
+    pub emphasis: Option<Emphasis>,
+
     /// The number of leading frames inserted by the encoder that should be skipped during playback.
     pub delay: Option<u32>,
 
@@ -320,6 +357,8 @@ impl CodecParameters {
             bits_per_coded_sample: None,
             channels: None,
             channel_layout: None,
+            is_dual_mono: false,
+            emphasis: None,
             delay: None,
             padding: None,
             max_frames_per_packet: None,
@@ -390,6 +429,18 @@ impl CodecParameters {
         self
     }
 
+    /// Specify if the two channels of the stream are independent mono programs.
+    pub fn with_dual_mono(&mut self, is_dual_mono: bool) -> &mut Self {
+        self.is_dual_mono = is_dual_mono;
+        self
+    }
+
+    /// Provide the pre-emphasis applied to the audio.
+    pub fn with_emphasis(&mut self, emphasis: Emphasis) -> &mut Self {
+        self.emphasis = Some(emphasis);
+        self
+    }
+
     /// Provide the number of delay frames.
     pub fn with_delay(&mut self, delay: u32) -> &mut Self {
         self.delay = Some(delay);
@@ -431,6 +482,49 @@ impl CodecParameters {
         self.verification_check = Some(code);
         self
     }
+
+    /// Checks that the parameters are consistent. Returns an error describing the first invalid
+    /// parameter found.
+    ///
+    /// Only the consistency of the parameters is checked. Whether the parameters are supported by
+    /// a particular codec is only known once a decoder is instantiated with them.
+    pub fn validate(&self) -> Result<()> {
+        if self.codec == CODEC_TYPE_NULL {
+            return decode_error("core (codecs): codec type is not set");
+        }
+
+        if self.sample_rate == Some(0) {
+            return decode_error("core (codecs): sample rate is 0");
+        }
+
+        if self.time_base.map_or(false, |tb| tb.numer == 0 || tb.denom == 0) {
+            return decode_error("core (codecs): time base is 0");
+        }
+
+        if let Some(channels) = self.channels {
+            if channels.is_empty() {
+                return decode_error("core (codecs): channels are empty");
+            }
+
+            if self.channel_layout.map_or(false, |layout| layout.into_channels() != channels) {
+                return decode_error("core (codecs): channel layout does not match channels");
+            }
+        }
+
+        if self.bits_per_sample.map_or(false, |bps| bps == 0 || bps > 64) {
+            return decode_error("core (codecs): bits per sample is out of range");
+        }
+
+        if self.bits_per_coded_sample.map_or(false, |bps| bps == 0 || bps > 64) {
+            return decode_error("core (codecs): bits per coded sample is out of range");
+        }
+
+        if self.max_frames_per_packet == Some(0) || self.frames_per_block == Some(0) {
+            return decode_error("core (codecs): packets or blocks contain 0 frames");
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for CodecParameters {
@@ -448,11 +542,91 @@ pub struct FinalizeResult {
     pub verify_ok: Option<bool>,
 }
 
+/// `DecodeWarningKind` categorizes a [`DecodeWarning`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeWarningKind {
+    /// The bitstream was malformed, but could still be decoded.
+    MalformedBitstream,
+    /// Data that the packet depends on was unavailable. For example, if decoding started in the
+    /// middle of a stream.
+    MissingData,
+    /// A part of the packet could not be decoded, and was concealed instead.
+    Concealed,
+}
+
+/// A `DecodeWarning` describes a non-fatal problem that a decoder encountered while decoding a
+/// packet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeWarning {
+    /// The kind of warning.
+    pub kind: DecodeWarningKind,
+    /// A description of the warning.
+    pub message: &'static str,
+    /// The offset, in bits, from the start of the packet to the data that caused the warning, if
+    /// known.
+    pub offset: Option<u64>,
+}
+
+impl DecodeWarning {
+    /// Instantiates a new `DecodeWarning` of `kind` described by `message`. The offset of the data
+    /// that caused the warning is unknown, see [`DecodeWarning::at`] to set it.
+    pub fn new(kind: DecodeWarningKind, message: &'static str) -> Self {
+        DecodeWarning { kind, message, offset: None }
+    }
+
+    /// Sets the offset, in bits, from the start of the packet to the data that caused the warning.
+    pub fn at(self, offset: u64) -> Self {
+        DecodeWarning { offset: Some(offset), ..self }
+    }
+}
+
+/// `DualMonoPolicy` selects how a decoder outputs a dual mono program, wherein each of the two
+/// channels carries an independent mono program (e.g., the same broadcast in two languages).
+///
+/// Regardless of the policy, the audio is output with both channels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DualMonoPolicy {
+    /// Output the first program on the left channel, and the second program on the right channel.
+    Both,
+    /// Output the first program on both channels.
+    Left,
+    /// Output the second program on both channels.
+    Right,
+    /// Output the average of both programs on both channels.
+    Mix,
+}
+
+impl Default for DualMonoPolicy {
+    fn default() -> Self {
+        DualMonoPolicy::Both
+    }
+}
+
 /// `DecoderOptions` is a common set of options that all decoders use.
 #[derive(Copy, Clone, Debug, Default)]
 pub struct DecoderOptions {
     /// The decoded audio should be verified if possible during the decode process.
     pub verify: bool,
+    /// Decoders should return an error if a packet violates the codec's specification, instead of
+    /// ignoring or concealing the violation. Warnings describing each violation, including its
+    /// offset if known, are available from `Decoder::last_warnings` after the error is returned.
+    ///
+    /// This is useful to validate the output of an encoder. By default, decoders are lenient.
+    pub strict: bool,
+    /// Decoders that support it should downmix multi-channel audio to a single channel. This is
+    /// cheaper than downmixing the decoded audio since the decoder may skip some processing for
+    /// the other channels.
+    pub downmix_to_mono: bool,
+    /// The policy of decoders that support dual mono programs for outputting them.
+    pub dual_mono: DualMonoPolicy,
+    /// The maximum number of iterations of the bitstream controlled loops (e.g., Huffman symbols
+    /// decoded) a decoder may perform per packet. A packet exceeding the limit is rejected with a
+    /// `LimitError`. The default limit is decoder specific, and never rejects a valid packet. See
+    /// the [`budget`] module for details.
+    pub limit_decode_iterations: Limit,
+    /// The maximum time a decoder may spend decoding a single packet. A packet exceeding the limit
+    /// is rejected with a `LimitError`. By default, the time is not limited.
+    pub limit_decode_time: Option<Duration>,
 }
 
 /// A `Decoder` implements a codec's decode algorithm. It consumes `Packet`s and produces
@@ -489,8 +663,16 @@ pub trait Decoder: Send + Sync {
     /// consumers of the decoded audio data should expect the duration and `SignalSpec` of the
     /// decoded audio buffer to change. All other errors are unrecoverable.
     ///
+    /// Decoders should return a `DecodeError` for a truncated packet so that the error is not
+    /// mistaken for the end of the stream (see
+    /// [`Error::into_packet_error`](crate::errors::Error::into_packet_error)).
+    ///
+    /// A packet with an empty payload, such as an Opus DTX packet or the padding of a container,
+    /// contains no audio and is not an error. It decodes to an audio buffer with zero frames. The
+    /// timestamp and duration of the packet remain those assigned by the `FormatReader`.
+    ///
     /// Implementors of decoders *must* `clear` the internal buffer if an error occurs.
-    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef>;
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>>;
 
     /// Optionally, obtain post-decode information such as the verification status.
     fn finalize(&mut self) -> FinalizeResult;
@@ -500,7 +682,33 @@ pub trait Decoder: Send + Sync {
     /// After a successful call to `decode`, this will contain the audio content of the last decoded
     /// `Packet`. If the last call to `decode` resulted in an error, then implementors *must* ensure
     /// the returned audio buffer has zero length.
-    fn last_decoded(&self) -> AudioBufferRef;
+    fn last_decoded(&self) -> AudioBufferRef<'_>;
+
+    /// Gets the warnings raised while decoding the last `Packet`.
+    ///
+    /// Warnings describe non-fatal problems, such as malformed data that was concealed, and are
+    /// cleared on every call to `decode`. Decoders that do not report warnings always return an
+    /// empty slice.
+    fn last_warnings(&self) -> &[DecodeWarning] {
+        &[]
+    }
+
+    /// Saves the minimal state of the decoder required to resume decoding, possibly in another
+    /// process, exactly where it left off. See the [`state`] module for details.
+    ///
+    /// Returns `None` if the decoder does not support saving its state.
+    fn save_state(&self) -> Option<DecoderState> {
+        None
+    }
+
+    /// Restores a state previously saved by `save_state` on a decoder instantiated with the same
+    /// `CodecParameters`. The next packet decoded should be the packet following the last packet
+    /// decoded before the state was saved.
+    ///
+    /// If an error is returned, the state of the decoder is undefined, and it should be reset.
+    fn restore_state(&mut self, _state: &DecoderState) -> Result<()> {
+        unsupported_error("core (codecs): decoder does not support restoring state")
+    }
 }
 
 /// A `CodecDescriptor` stores a description of a single logical codec. Common information such as
@@ -536,6 +744,11 @@ impl CodecRegistry {
         self.codecs.get(&codec)
     }
 
+    /// Gets the `CodecDescriptor`s of all registered codecs, in no particular order.
+    pub fn codecs(&self) -> impl Iterator<Item = &CodecDescriptor> {
+        self.codecs.values()
+    }
+
     /// Registers all codecs supported by `Decoder`. If a supported codec was previously registered
     /// by another `Decoder` it will be replaced within the registry.
     pub fn register_all<D: Decoder>(&mut self) {
@@ -566,6 +779,18 @@ impl CodecRegistry {
             unsupported_error("core (codec):unsupported codec")
         }
     }
+
+    /// Like `make`, but the returned `Decoder` applies the channel transform instantiated by
+    /// `hook` to all decoded audio. See the [`transform`] module for details.
+    pub fn make_with_transform(
+        &self,
+        params: &CodecParameters,
+        options: &DecoderOptions,
+        hook: &ChannelTransformHook,
+    ) -> Result<Box<dyn Decoder>> {
+        let decoder = self.make(params, options)?;
+        Ok(Box::new(TransformedDecoder::new(decoder, hook.make())))
+    }
 }
 
 impl Default for CodecRegistry {
@@ -586,3 +811,37 @@ macro_rules! support_codec {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CodecParameters, CODEC_TYPE_FLAC};
+    use crate::audio::{Channels, Layout};
+    use crate::units::TimeBase;
+
+    #[test]
+    fn verify_codec_parameters_validate() {
+        let mut params = CodecParameters::new();
+
+        // The codec type is required.
+        assert!(params.validate().is_err());
+
+        params
+            .for_codec(CODEC_TYPE_FLAC)
+            .with_sample_rate(48_000)
+            .with_time_base(TimeBase::new(1, 48_000))
+            .with_channels(Layout::Stereo.into_channels())
+            .with_channel_layout(Layout::Stereo)
+            .with_bits_per_sample(24)
+            .with_delay(100)
+            .with_padding(200)
+            .with_extra_data(Box::new([0; 34]));
+
+        assert!(params.validate().is_ok());
+
+        assert!(params.clone().with_sample_rate(0).validate().is_err());
+        assert!(params.clone().with_channels(Channels::FRONT_LEFT).validate().is_err());
+        assert!(params.clone().with_channels(Channels::empty()).validate().is_err());
+        assert!(params.clone().with_bits_per_sample(65).validate().is_err());
+        assert!(params.clone().with_max_frames_per_packet(0).validate().is_err());
+    }
+}
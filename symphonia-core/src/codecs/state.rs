@@ -0,0 +1,102 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `state` module provides a serializable snapshot of the state of a `Decoder`.
+//!
+//! Many decoders carry state from one packet to the next (e.g., overlap-add buffers, or the MP3 bit
+//! reservoir). Normally, resuming decoding in the middle of a stream requires decoding from a
+//! previous sync point to rebuild that state. Instead, a [`DecoderState`] saved with
+//! [`Decoder::save_state`](super::Decoder::save_state) may be persisted, and later restored with
+//! [`Decoder::restore_state`](super::Decoder::restore_state), possibly in another process, to
+//! resume decoding exactly where it left off.
+
+use crate::errors::{decode_error, Result};
+use crate::io::{BufReader, FiniteStream, ReadBytes};
+
+use super::CodecType;
+
+/// The magic bytes at the start of a serialized `DecoderState`.
+const STATE_MAGIC: [u8; 4] = *b"SyDS";
+
+/// The version of the serialized `DecoderState` format.
+const STATE_VERSION: u8 = 1;
+
+/// A `DecoderState` is a snapshot of the minimal state of a `Decoder` required to resume decoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecoderState {
+    /// The codec of the decoder that saved the state.
+    pub codec: CodecType,
+    /// Decoder specific state data. The layout of the data is private to the decoder.
+    pub data: Box<[u8]>,
+}
+
+impl DecoderState {
+    /// Instantiate a new `DecoderState` for a decoder of `codec`.
+    pub fn new(codec: CodecType, data: Box<[u8]>) -> Self {
+        DecoderState { codec, data }
+    }
+
+    /// Serializes the state into a compact binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(13 + self.data.len());
+
+        buf.extend_from_slice(&STATE_MAGIC);
+        buf.push(STATE_VERSION);
+        buf.extend_from_slice(&self.codec.0.to_le_bytes());
+        buf.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&self.data);
+
+        buf
+    }
+
+    /// Deserializes a state previously serialized with [`DecoderState::to_bytes`].
+    pub fn from_bytes(buf: &[u8]) -> Result<DecoderState> {
+        let mut reader = BufReader::new(buf);
+
+        let mut magic = [0; 4];
+        reader.read_buf_exact(&mut magic)?;
+
+        if magic != STATE_MAGIC {
+            return decode_error("core (state): invalid decoder state magic");
+        }
+
+        if reader.read_u8()? != STATE_VERSION {
+            return decode_error("core (state): unsupported decoder state version");
+        }
+
+        let codec = CodecType(reader.read_u32()?);
+
+        let len = reader.read_u32()? as usize;
+
+        if len != reader.bytes_available() as usize {
+            return decode_error("core (state): invalid decoder state length");
+        }
+
+        let data = reader.read_boxed_slice_exact(len)?;
+
+        Ok(DecoderState { codec, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecoderState;
+    use crate::codecs::CODEC_TYPE_MP3;
+
+    #[test]
+    fn verify_decoder_state_round_trip() {
+        let state = DecoderState::new(CODEC_TYPE_MP3, vec![3, 1, 4, 1, 5].into_boxed_slice());
+
+        let bytes = state.to_bytes();
+        assert_eq!(DecoderState::from_bytes(&bytes).unwrap(), state);
+
+        // Truncated or corrupt states are rejected.
+        assert!(DecoderState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(DecoderState::from_bytes(&[&bytes[..], &[0]].concat()).is_err());
+        assert!(DecoderState::from_bytes(b"SyDX\x01\x00\x00\x00\x00\x00\x00\x00\x00").is_err());
+    }
+}
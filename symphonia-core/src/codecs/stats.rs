@@ -0,0 +1,201 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `stats` module provides a summary of a decoded stream.
+//!
+//! A decode loop records the result of decoding each packet in a [`StreamStats`]. When the stream
+//! ends, [`StreamStats::summary`] provides the total number of frames, the decoded duration, the
+//! number of errors and warnings, the average bitrate, and the true-peak of the stream.
+
+use crate::audio::{AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use crate::dsp::limiter::TruePeakDetector;
+use crate::formats::Packet;
+use crate::units::Time;
+
+use super::DecodeWarning;
+
+/// A `StreamSummary` summarizes a decoded stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StreamSummary {
+    /// The number of packets decoded.
+    pub n_packets: u64,
+    /// The total number of decoded frames.
+    pub n_frames: u64,
+    /// The total duration of the decoded audio.
+    pub duration: Time,
+    /// The number of packets that failed to decode.
+    pub n_errors: u64,
+    /// The number of warnings raised while decoding.
+    pub n_warnings: u64,
+    /// The average bitrate of the decoded packets in bits per second, if any audio was decoded.
+    pub avg_bitrate: Option<u64>,
+    /// The true-peak of the decoded audio as a linear amplitude.
+    pub true_peak: f32,
+}
+
+impl StreamSummary {
+    /// Gets the true-peak of the decoded audio in dBTP.
+    pub fn true_peak_db(&self) -> f32 {
+        20.0 * self.true_peak.log10()
+    }
+}
+
+/// `StreamStats` accumulates the statistics of a stream as it is decoded.
+#[derive(Default)]
+pub struct StreamStats {
+    summary: StreamSummary,
+    /// The total length of the decoded packets in bytes.
+    n_bytes: u64,
+    /// The duration, in seconds, of the audio decoded before the last change of sample rate.
+    seconds: f64,
+    /// The sample rate of the audio, and the number of frames decoded at that sample rate.
+    rate: u32,
+    rate_frames: u64,
+    /// The true-peak detectors of each channel.
+    detectors: Vec<TruePeakDetector>,
+    /// The decoded audio converted to `f32` for true-peak detection.
+    scratch: Option<AudioBuffer<f32>>,
+}
+
+impl StreamStats {
+    /// Instantiates a new, empty, `StreamStats`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records that `packet` was decoded to `decoded`.
+    pub fn record_decoded(&mut self, packet: &Packet, decoded: &AudioBufferRef<'_>) {
+        let spec = *decoded.spec();
+
+        self.summary.n_packets += 1;
+        self.summary.n_frames += decoded.frames() as u64;
+        self.n_bytes += packet.buf().len() as u64;
+
+        // An empty buffer may not have a valid signal specification.
+        if decoded.frames() == 0 {
+            return;
+        }
+
+        // Frames are counted per sample rate so that the duration does not accumulate rounding
+        // errors.
+        if spec.rate != self.rate {
+            self.seconds = self.duration_secs();
+            self.rate = spec.rate;
+            self.rate_frames = 0;
+        }
+
+        self.rate_frames += decoded.frames() as u64;
+
+        self.update_true_peak(&spec, decoded);
+    }
+
+    /// Records the warnings raised while decoding a packet.
+    pub fn record_warnings(&mut self, warnings: &[DecodeWarning]) {
+        self.summary.n_warnings += warnings.len() as u64;
+    }
+
+    /// Records that a packet failed to decode.
+    pub fn record_error(&mut self) {
+        self.summary.n_errors += 1;
+    }
+
+    /// Gets the summary of the stream decoded so far.
+    pub fn summary(&self) -> StreamSummary {
+        let seconds = self.duration_secs();
+
+        let avg_bitrate = if seconds > 0.0 {
+            Some((8.0 * self.n_bytes as f64 / seconds).round() as u64)
+        }
+        else {
+            None
+        };
+
+        StreamSummary { duration: Time::from(seconds), avg_bitrate, ..self.summary }
+    }
+
+    /// Gets the total duration of the decoded audio in seconds.
+    fn duration_secs(&self) -> f64 {
+        if self.rate > 0 {
+            self.seconds + self.rate_frames as f64 / f64::from(self.rate)
+        }
+        else {
+            self.seconds
+        }
+    }
+
+    fn update_true_peak(&mut self, spec: &SignalSpec, decoded: &AudioBufferRef<'_>) {
+        let n_channels = spec.channels.count();
+
+        // The scratch buffer, and detectors, are replaced if the signal specification changes, or
+        // the buffer grows.
+        let scratch = match &mut self.scratch {
+            Some(scratch) if scratch.spec() == spec && scratch.capacity() >= decoded.capacity() => {
+                scratch
+            }
+            scratch => {
+                self.detectors = vec![Default::default(); n_channels];
+                scratch.insert(decoded.make_equivalent())
+            }
+        };
+
+        decoded.convert(scratch);
+
+        for (c, detector) in self.detectors.iter_mut().enumerate() {
+            for &sample in scratch.chan(c) {
+                let peak = detector.push(sample).max(sample.abs());
+                self.summary.true_peak = self.summary.true_peak.max(peak);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::StreamStats;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, Channels, Signal, SignalSpec};
+    use crate::codecs::{DecodeWarning, DecodeWarningKind};
+    use crate::formats::Packet;
+
+    #[test]
+    fn verify_stream_stats() {
+        let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT);
+
+        // A quarter sample rate sine wave where every sample is 3 dB below the true-peak.
+        let mut buf = AudioBuffer::<i16>::new(800, spec);
+        buf.render_reserved(Some(800));
+
+        for (i, s) in buf.chan_mut(0).iter_mut().enumerate() {
+            *s = (16384.0 * (0.5 * PI * i as f32 + 0.25 * PI).sin()) as i16;
+        }
+
+        let packet = Packet::new_from_slice(0, 0, 800, &[0; 100]);
+
+        let mut stats = StreamStats::new();
+
+        for _ in 0..10 {
+            stats.record_decoded(&packet, &buf.as_audio_buffer_ref());
+        }
+
+        stats.record_warnings(&[DecodeWarning::new(DecodeWarningKind::Concealed, "test")]);
+        stats.record_error();
+
+        let summary = stats.summary();
+
+        assert_eq!(summary.n_packets, 10);
+        assert_eq!(summary.n_frames, 8000);
+        assert_eq!(summary.duration.seconds, 1);
+        assert!(summary.duration.frac < 1e-9);
+        assert_eq!(summary.n_errors, 1);
+        assert_eq!(summary.n_warnings, 1);
+        // 1000 bytes in 1 second.
+        assert_eq!(summary.avg_bitrate, Some(8000));
+        // A true-peak of -6 dBTP, not the -9 dBFS sample peak.
+        assert!((summary.true_peak_db() + 6.02).abs() < 0.2);
+    }
+}
@@ -0,0 +1,321 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `transform` module provides a hook to transform the channels of decoded audio before it is
+//! returned by a `Decoder`.
+//!
+//! A [`ChannelTransform`] may be used to downmix, upmix, or otherwise remap the channels of every
+//! decoded audio buffer (e.g., a binaural downmix or a mono sum). To apply a transform to all
+//! decoders instantiated by a [`CodecRegistry`](super::CodecRegistry), provide a
+//! [`ChannelTransformHook`] to
+//! [`CodecRegistry::make_with_transform`](super::CodecRegistry::make_with_transform). Decoders
+//! that are instantiated directly may be wrapped with a [`TransformedDecoder`] instead.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use crate::errors::{decode_error, unsupported_error, Result};
+use crate::formats::Packet;
+use crate::units::Duration;
+
+use super::state::DecoderState;
+use super::FinalizeResult;
+use super::{CodecDescriptor, CodecParameters, DecodeWarning, Decoder, DecoderOptions};
+
+/// A `ChannelTransform` transforms the channels of decoded audio.
+pub trait ChannelTransform: Send + Sync {
+    /// Gets the signal specification of the transformed audio given the signal specification of
+    /// the decoded audio.
+    fn output_spec(&self, input: &SignalSpec) -> Result<SignalSpec>;
+
+    /// Transforms the decoded audio in `input` into `output`.
+    ///
+    /// `output` has the signal specification returned by `output_spec`, and already contains the
+    /// same number of frames as `input`. The contents of those frames are undefined and must be
+    /// overwritten.
+    fn transform(&mut self, input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>);
+
+    /// Resets any state maintained by the transform. Called whenever the decoder is reset.
+    fn reset(&mut self) {}
+}
+
+/// A `ChannelTransformHook` instantiates a new `ChannelTransform` for each decoder.
+///
+/// Since a transform may maintain state between decoded audio buffers, each decoder requires its
+/// own instance of the transform.
+#[derive(Clone)]
+pub struct ChannelTransformHook {
+    make: Arc<dyn Fn() -> Box<dyn ChannelTransform> + Send + Sync>,
+}
+
+impl ChannelTransformHook {
+    /// Instantiates a new `ChannelTransformHook` that calls `make` to instantiate the
+    /// `ChannelTransform` of each decoder.
+    pub fn new<F>(make: F) -> Self
+    where
+        F: Fn() -> Box<dyn ChannelTransform> + Send + Sync + 'static,
+    {
+        ChannelTransformHook { make: Arc::new(make) }
+    }
+
+    /// Instantiates a new `ChannelTransform`.
+    pub fn make(&self) -> Box<dyn ChannelTransform> {
+        (self.make)()
+    }
+}
+
+impl fmt::Debug for ChannelTransformHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelTransformHook").finish()
+    }
+}
+
+/// A `TransformedDecoder` wraps a `Decoder` and applies a `ChannelTransform` to every decoded
+/// audio buffer.
+///
+/// The decoded audio is always output as `f32` samples.
+pub struct TransformedDecoder {
+    inner: Box<dyn Decoder>,
+    transform: Box<dyn ChannelTransform>,
+    input: AudioBuffer<f32>,
+    output: AudioBuffer<f32>,
+}
+
+impl TransformedDecoder {
+    /// Instantiates a new `TransformedDecoder` that applies `transform` to the audio decoded by
+    /// `inner`.
+    pub fn new(inner: Box<dyn Decoder>, transform: Box<dyn ChannelTransform>) -> Self {
+        TransformedDecoder {
+            inner,
+            transform,
+            input: AudioBuffer::unused(),
+            output: AudioBuffer::unused(),
+        }
+    }
+
+    /// Gets a reference to the wrapped decoder.
+    pub fn inner(&self) -> &dyn Decoder {
+        self.inner.as_ref()
+    }
+
+    /// Consumes the `TransformedDecoder` and returns the wrapped decoder.
+    pub fn into_inner(self) -> Box<dyn Decoder> {
+        self.inner
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let decoded = self.inner.decode(packet)?;
+
+        // An empty buffer may not have a valid signal specification, and is not transformed.
+        if decoded.frames() == 0 {
+            self.output.clear();
+            return Ok(());
+        }
+
+        let spec = *decoded.spec();
+
+        // Reallocate the buffers if the signal specification changed, or the decoded buffer
+        // outgrew them.
+        if self.input.is_unused()
+            || self.input.spec() != &spec
+            || self.input.capacity() < decoded.capacity()
+        {
+            let out_spec = self.transform.output_spec(&spec)?;
+
+            if out_spec.channels.count() == 0 {
+                return decode_error("core (transform): channel transform output has no channels");
+            }
+
+            self.input = decoded.make_equivalent::<f32>();
+            self.output = AudioBuffer::new(self.input.capacity() as Duration, out_spec);
+        }
+
+        decoded.convert(&mut self.input);
+
+        self.output.clear();
+        self.output.render_reserved(Some(self.input.frames()));
+
+        self.transform.transform(&self.input, &mut self.output);
+
+        Ok(())
+    }
+}
+
+impl Decoder for TransformedDecoder {
+    fn try_new(_: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+        unsupported_error("core (transform): use TransformedDecoder::new to wrap a decoder")
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[]
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.transform.reset();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        self.inner.codec_params()
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.output.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.output.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        self.inner.finalize()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.output.as_audio_buffer_ref()
+    }
+
+    fn last_warnings(&self) -> &[DecodeWarning] {
+        self.inner.last_warnings()
+    }
+
+    fn save_state(&self) -> Option<DecoderState> {
+        self.inner.save_state()
+    }
+
+    fn restore_state(&mut self, state: &DecoderState) -> Result<()> {
+        self.transform.reset();
+        self.inner.restore_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelTransform, ChannelTransformHook};
+    use crate::audio::SignalSpec;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+    use crate::codecs::{CodecDescriptor, CodecParameters, CodecRegistry, Decoder};
+    use crate::codecs::{DecoderOptions, FinalizeResult, CODEC_TYPE_PCM_S16LE};
+    use crate::errors::Result;
+    use crate::formats::Packet;
+    use crate::support_codec;
+
+    /// A decoder that outputs a stereo frame of `i16` samples for every byte in a packet, with the
+    /// left and right channels set to +/- the value of the byte.
+    struct TestDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<i16>,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+            Ok(TestDecoder { params: params.clone(), buf: AudioBuffer::new(16, spec) })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_S16LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            self.buf.clear();
+            self.buf.render_reserved(Some(packet.buf().len()));
+
+            let (l, r) = self.buf.chan_pair_mut(0, 1);
+
+            for ((l, r), &byte) in l.iter_mut().zip(r.iter_mut()).zip(packet.buf()) {
+                *l = i16::from(byte) << 8;
+                *r = -(i16::from(byte) << 7);
+            }
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    /// Sums stereo to mono, and counts the number of resets.
+    struct MonoSum {
+        n_resets: usize,
+    }
+
+    impl ChannelTransform for MonoSum {
+        fn output_spec(&self, input: &SignalSpec) -> Result<SignalSpec> {
+            Ok(SignalSpec::new(input.rate, Channels::FRONT_CENTRE))
+        }
+
+        fn transform(&mut self, input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>) {
+            for (i, out) in output.chan_mut(0).iter_mut().enumerate() {
+                *out = 0.5 * (input.chan(0)[i] + input.chan(1)[i]) + self.n_resets as f32;
+            }
+        }
+
+        fn reset(&mut self) {
+            self.n_resets += 1;
+        }
+    }
+
+    #[test]
+    fn verify_channel_transform_hook() {
+        let mut registry = CodecRegistry::new();
+        registry.register_all::<TestDecoder>();
+
+        let mut params = CodecParameters::new();
+        params.for_codec(CODEC_TYPE_PCM_S16LE);
+
+        let hook = ChannelTransformHook::new(|| Box::new(MonoSum { n_resets: 0 }));
+
+        let mut decoder =
+            registry.make_with_transform(&params, &Default::default(), &hook).unwrap();
+
+        let packet = Packet::new_from_slice(0, 0, 3, &[0, 32, 64]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => {
+                assert_eq!(buf.spec().channels, Channels::FRONT_CENTRE);
+                assert_eq!(buf.chan(0), &[0.0, 0.0625, 0.125]);
+            }
+            _ => panic!("expected f32 samples"),
+        }
+
+        // Resets are forwarded to the transform.
+        decoder.reset();
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => assert_eq!(buf.chan(0), &[1.0, 1.0625, 1.125]),
+            _ => panic!("expected f32 samples"),
+        }
+
+        assert_eq!(decoder.last_decoded().frames(), 3);
+
+        // An empty packet decodes to an empty buffer.
+        let empty = Packet::new_from_slice(0, 3, 0, &[]);
+
+        assert_eq!(decoder.decode(&empty).unwrap().frames(), 0);
+        assert_eq!(decoder.last_decoded().frames(), 0);
+
+        // Without a hook the decoder is not wrapped.
+        let mut decoder = registry.make(&params, &Default::default()).unwrap();
+
+        assert!(matches!(decoder.decode(&packet).unwrap(), AudioBufferRef::S16(_)));
+    }
+}
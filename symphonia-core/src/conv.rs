@@ -18,6 +18,8 @@ pub mod dither {
     //! Dithering is only applied on lossy conversions. Therefore the `dither` module will only
     //! apply a dither to the following down-conversions:
     //!
+    //! * `f64` to { `i24`, `u24`, `i16`, `u16`, `i8`, `u8` }
+    //! * `f32` to { `i16`, `u16`, `i8`, `u8` }
     //! * { `i32`, `u32` } to { `i24`, `u24`, `i16`, `u16`, `i8`, `u8` }
     //! * { `i24`, `u24` } to { `i16`, `u16`, `i8`, `u8` }
     //! * { `i16`, `u16` } to { `i8`, `u8` }
@@ -25,7 +27,7 @@ pub mod dither {
     //! Multiple dithering algorithms are provided, each drawing noise from a different probability
     //! distribution. In addition to different distributions, a dithering algorithm may also shape
     //! the noise such that the bulk of the noise is placed in an inaudible frequency range.
-    use super::FromSample;
+    use super::{FromSample, IntoSample};
     use crate::sample::Sample;
     use crate::sample::{i24, u24};
     use std::marker::PhantomData;
@@ -69,7 +71,7 @@ pub mod dither {
 
             #[inline(always)]
             fn rotl(x: u32, k: u32) -> u32 {
-                (x << k) | (x >> (32 - k))
+                x.rotate_left(k)
             }
 
             #[inline]
@@ -261,6 +263,66 @@ pub mod dither {
         }
     }
 
+    /// `Shaped` implements a dither using a triangular distribution of noise, and shapes the
+    /// quantization noise with first-order error feedback.
+    ///
+    /// The quantization error of each sample is subtracted from the next sample. This moves the
+    /// bulk of the noise towards higher frequencies, where the ear is less sensitive, at the cost of
+    /// a greater total amount of noise. Since the error of the previous sample is fed back, a
+    /// `Shaped` dither should only be used to dither the samples of a single channel in order.
+    pub struct Shaped<F: Sample, T: Sample> {
+        prng: prng::Xoshiro128pp,
+        /// The quantization error of the previous sample.
+        error: f64,
+        from_type: PhantomData<F>,
+        to_type: PhantomData<T>,
+    }
+
+    impl<F: Sample, T: Sample> Shaped<F, T> {
+        pub fn new() -> Self {
+            Shaped {
+                prng: prng::Xoshiro128pp::new(0xb2c1_01f4_425b_987e),
+                error: 0.0,
+                from_type: PhantomData,
+                to_type: PhantomData,
+            }
+        }
+    }
+
+    impl<F: Sample, T: Sample> Dither<F, T> for Shaped<F, T>
+    where
+        RandomNoise: AddNoise<F>,
+        F: FromSample<f64> + IntoSample<T> + IntoSample<f64>,
+        T: IntoSample<f64>,
+    {
+        fn dither(&mut self, sample: F) -> F {
+            debug_assert!(F::EFF_BITS > T::EFF_BITS);
+
+            let dither_bits = 32 - T::EFF_BITS;
+
+            // Subtract the quantization error of the previous sample.
+            let shaped: f64 = IntoSample::<f64>::into_sample(sample) - self.error;
+
+            let tpdf = (self.prng.next() as i32 >> 1) + (self.prng.next() as i32 >> 1);
+
+            let noise = RandomNoise::from(tpdf, dither_bits);
+            let dithered = noise.add_noise(F::from_sample(shaped));
+
+            // The quantization error is the difference between the quantized sample and the
+            // shaped sample.
+            let quantized: T = dithered.into_sample();
+            self.error = quantized.into_sample() - shaped;
+
+            dithered
+        }
+    }
+
+    impl<F: Sample, T: Sample> Default for Shaped<F, T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     /// Enumeration of dither algorithms.
     pub enum DitherType {
         /// No dithering.
@@ -269,6 +331,8 @@ pub mod dither {
         Rectangular,
         /// Apply triangular dithering. See `Triangular` for more details.
         Triangular,
+        /// Apply triangular dithering with noise shaping. See `Shaped` for more details.
+        Shaped,
     }
 
     /// `MaybeDither` conditionally applies a dither to a sample depending on the source and
@@ -314,8 +378,8 @@ pub mod dither {
     dither_maybe!(u8, i16);
     dither_maybe!(u8, i24);
     dither_maybe!(u8, i32);
-    dither_never!(u8, f32);
-    dither_never!(u8, f64);
+    dither_maybe!(u8, f32);
+    dither_maybe!(u8, f64);
 
     // Dither table for conversions to u16
     dither_never!(u16, u8);
@@ -326,8 +390,8 @@ pub mod dither {
     dither_never!(u16, i16);
     dither_maybe!(u16, i24);
     dither_maybe!(u16, i32);
-    dither_never!(u16, f32);
-    dither_never!(u16, f64);
+    dither_maybe!(u16, f32);
+    dither_maybe!(u16, f64);
 
     // Dither table for conversions to u24
     dither_never!(u24, u8);
@@ -339,7 +403,7 @@ pub mod dither {
     dither_never!(u24, i24);
     dither_maybe!(u24, i32);
     dither_never!(u24, f32);
-    dither_never!(u24, f64);
+    dither_maybe!(u24, f64);
 
     // Dither table for conversions to u32
     dither_never!(u32, u8);
@@ -362,8 +426,8 @@ pub mod dither {
     dither_maybe!(i8, i16);
     dither_maybe!(i8, i24);
     dither_maybe!(i8, i32);
-    dither_never!(i8, f32);
-    dither_never!(i8, f64);
+    dither_maybe!(i8, f32);
+    dither_maybe!(i8, f64);
 
     // Dither table for conversions to i16
     dither_never!(i16, u8);
@@ -374,8 +438,8 @@ pub mod dither {
     dither_never!(i16, i16);
     dither_maybe!(i16, i24);
     dither_maybe!(i16, i32);
-    dither_never!(i16, f32);
-    dither_never!(i16, f64);
+    dither_maybe!(i16, f32);
+    dither_maybe!(i16, f64);
 
     // Dither table for conversions to i24
     dither_never!(i24, u8);
@@ -387,7 +451,7 @@ pub mod dither {
     dither_never!(i24, i24);
     dither_maybe!(i24, i32);
     dither_never!(i24, f32);
-    dither_never!(i24, f64);
+    dither_maybe!(i24, f64);
 
     // Dither table for conversions to i32
     dither_never!(i32, u8);
@@ -677,9 +741,41 @@ impl<S> ConvertibleSample for S where
 
 #[cfg(test)]
 mod tests {
+    use super::dither::{Dither, Shaped, Triangular};
     use super::FromSample;
     use crate::sample::{i24, u24, Sample};
-    use std::{i16, i32, i8, u16, u32, u8};
+
+    #[test]
+    fn verify_dither_triangular() {
+        let mut dither = Triangular::<f32, i16>::new();
+
+        // A sample a third of the way between two 16-bit steps.
+        let sample = 100.3 / 32768.0;
+
+        let quantized: Vec<i16> =
+            (0..1000).map(|_| i16::from_sample(dither.dither(sample))).collect();
+
+        // The dither is at most 1 LSB of the destination in either direction.
+        assert!(quantized.iter().all(|&s| (99..=101).contains(&s)));
+        // Without dither, every sample would be quantized to 100.
+        assert!(quantized.iter().any(|&s| s != 100));
+    }
+
+    #[test]
+    fn verify_dither_shaped() {
+        let mut dither = Shaped::<f32, i16>::new();
+
+        let sample = 100.3 / 32768.0;
+
+        let quantized: Vec<i16> =
+            (0..1000).map(|_| i16::from_sample(dither.dither(sample))).collect();
+
+        assert!(quantized.iter().all(|&s| (98..=103).contains(&s)));
+
+        // The error feedback keeps the average of the quantized samples close to the source.
+        let mean = quantized.iter().map(|&s| f64::from(s)).sum::<f64>() / 1000.0;
+        assert!((mean - 100.3).abs() < 0.05, "mean = {}", mean);
+    }
 
     #[test]
     fn verify_u8_from_sample() {
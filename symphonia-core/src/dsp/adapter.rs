@@ -0,0 +1,245 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `adapter` module provides a channel adapter that presents audio with a constant set of
+//! channels.
+//!
+//! The channels of a stream may change mid-stream. For example, a chained Ogg web radio stream may
+//! switch between mono and stereo programmes. A [`ChannelAdapter`] upmixes or downmixes the
+//! decoded audio to a fixed set of channels such that the consumer, such as an audio output, never
+//! needs to be reconfigured.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::audio::{AudioBuffer, Channels, SignalSpec};
+use crate::codecs::transform::ChannelTransform;
+use crate::errors::{unsupported_error, Result};
+
+use super::mixer::mix;
+
+/// Channels on the left side of the sound stage.
+pub(super) fn left_channels() -> Channels {
+    Channels::FRONT_LEFT
+        | Channels::REAR_LEFT
+        | Channels::FRONT_LEFT_CENTRE
+        | Channels::SIDE_LEFT
+        | Channels::TOP_FRONT_LEFT
+        | Channels::TOP_REAR_LEFT
+        | Channels::REAR_LEFT_CENTRE
+        | Channels::FRONT_LEFT_WIDE
+        | Channels::FRONT_LEFT_HIGH
+}
+
+/// Channels on the right side of the sound stage.
+pub(super) fn right_channels() -> Channels {
+    Channels::FRONT_RIGHT
+        | Channels::REAR_RIGHT
+        | Channels::FRONT_RIGHT_CENTRE
+        | Channels::SIDE_RIGHT
+        | Channels::TOP_FRONT_RIGHT
+        | Channels::TOP_REAR_RIGHT
+        | Channels::REAR_RIGHT_CENTRE
+        | Channels::FRONT_RIGHT_WIDE
+        | Channels::FRONT_RIGHT_HIGH
+}
+
+/// Low-frequency effects channels.
+fn lfe_channels() -> Channels {
+    Channels::LFE1 | Channels::LFE2
+}
+
+/// Gets the gain of every input channel for every output channel. The gains of the first output
+/// channel are first, followed by the gains of the second output channel, and so on.
+///
+/// * A mono input is copied to the front-left and front-right channels, or the first channel, of
+///   the output.
+/// * A mono output is the average of all input channels other than the low-frequency effects
+///   channels.
+/// * Otherwise, an input channel that is also an output channel is copied. Other input channels
+///   are mixed into the front channel(s) on the same side of the sound stage with a gain of -3 dB.
+///   Low-frequency effects channels are only ever copied.
+///
+/// Output channels without a source are silent.
+fn mix_gains(input: Channels, output: Channels) -> Vec<f32> {
+    let n_in = input.count();
+    let n_out = output.count();
+
+    let index = |channels: Channels, channel: Channels| channels.iter().position(|c| c == channel);
+
+    let mut gains = vec![0.0; n_in * n_out];
+
+    let mut set = |o: usize, i: usize, gain: f32| gains[o * n_in + i] += gain;
+
+    if n_in == 1 {
+        let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+
+        if output.contains(front) {
+            set(index(output, Channels::FRONT_LEFT).unwrap(), 0, 1.0);
+            set(index(output, Channels::FRONT_RIGHT).unwrap(), 0, 1.0);
+        }
+        else {
+            set(0, 0, 1.0);
+        }
+    }
+    else if n_out == 1 {
+        let sources = input - lfe_channels();
+
+        for channel in sources.iter() {
+            set(0, index(input, channel).unwrap(), 1.0 / sources.count() as f32);
+        }
+    }
+    else {
+        for (i, channel) in input.iter().enumerate() {
+            if let Some(o) = index(output, channel) {
+                set(o, i, 1.0);
+                continue;
+            }
+
+            if lfe_channels().contains(channel) {
+                continue;
+            }
+
+            let left = index(output, Channels::FRONT_LEFT);
+            let right = index(output, Channels::FRONT_RIGHT);
+            let centre = index(output, Channels::FRONT_CENTRE);
+
+            // Prefer the front channel on the same side, otherwise the centre channel. Centre
+            // channels prefer the centre channel, otherwise both the left and right channels.
+            let targets = if left_channels().contains(channel) {
+                [left.or(centre), None]
+            }
+            else if right_channels().contains(channel) {
+                [right.or(centre), None]
+            }
+            else if centre.is_some() {
+                [centre, None]
+            }
+            else {
+                [left, right]
+            };
+
+            for &o in targets.iter().flatten() {
+                set(o, i, FRAC_1_SQRT_2);
+            }
+        }
+    }
+
+    gains
+}
+
+/// A `ChannelAdapter` upmixes or downmixes audio to a constant set of channels.
+pub struct ChannelAdapter {
+    channels: Channels,
+    /// The input channels the gains were computed for.
+    input: Channels,
+    gains: Vec<f32>,
+}
+
+impl ChannelAdapter {
+    /// Instantiates a new `ChannelAdapter` that outputs `channels`.
+    pub fn new(channels: Channels) -> Self {
+        assert!(channels.count() > 0, "channel adapter must output at least one channel");
+
+        ChannelAdapter { channels, input: Channels::empty(), gains: Vec::new() }
+    }
+}
+
+impl ChannelTransform for ChannelAdapter {
+    fn output_spec(&self, input: &SignalSpec) -> Result<SignalSpec> {
+        if input.channels.is_empty() {
+            return unsupported_error("core (adapter): input has no channels");
+        }
+
+        Ok(SignalSpec::new(input.rate, self.channels))
+    }
+
+    fn transform(&mut self, input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>) {
+        let channels = input.spec().channels;
+
+        if channels != self.input {
+            self.input = channels;
+            self.gains = mix_gains(channels, self.channels);
+        }
+
+        mix(&self.gains, input, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use super::{mix_gains, ChannelAdapter};
+    use crate::audio::{AudioBuffer, Channels, Layout, Signal, SignalSpec};
+    use crate::codecs::transform::ChannelTransform;
+
+    fn stereo() -> Channels {
+        Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+    }
+
+    #[test]
+    fn verify_mix_gains() {
+        let stereo = stereo();
+        let mono = Channels::FRONT_LEFT;
+        let five_one = Layout::FivePointOne.into_channels();
+
+        // Identity.
+        assert_eq!(mix_gains(stereo, stereo), [1.0, 0.0, 0.0, 1.0]);
+
+        // Mono to stereo, and stereo to mono.
+        assert_eq!(mix_gains(mono, stereo), [1.0, 1.0]);
+        assert_eq!(mix_gains(stereo, mono), [0.5, 0.5]);
+
+        // 5.1 to stereo. The channels of 5.1 are FL, FR, FC, LFE, RL, RR.
+        let h = FRAC_1_SQRT_2;
+        let expected = [1.0, 0.0, h, 0.0, h, 0.0, 0.0, 1.0, h, 0.0, 0.0, h];
+        assert_eq!(mix_gains(five_one, stereo), expected);
+
+        // 5.1 to mono excludes the LFE channel.
+        assert_eq!(mix_gains(five_one, mono), [0.2, 0.2, 0.2, 0.0, 0.2, 0.2]);
+
+        // Stereo to 5.1 leaves the other channels silent.
+        let expected = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        assert_eq!(mix_gains(stereo, five_one), expected);
+    }
+
+    #[test]
+    fn verify_channel_adapter() {
+        let stereo = stereo();
+
+        let mut adapter = ChannelAdapter::new(stereo);
+
+        // The output is always stereo, even as the input changes from mono to stereo.
+        for &channels in [Channels::FRONT_LEFT, stereo, Channels::FRONT_LEFT].iter() {
+            let spec = SignalSpec::new(8_000, channels);
+
+            let mut input = AudioBuffer::<f32>::new(4, spec);
+            input.render_reserved(Some(2));
+
+            for c in 0..channels.count() {
+                input.chan_mut(c).copy_from_slice(&[0.5, -0.5 * c as f32]);
+            }
+
+            let out_spec = adapter.output_spec(&spec).unwrap();
+            assert_eq!(out_spec, SignalSpec::new(8_000, stereo));
+
+            let mut output = AudioBuffer::<f32>::new(4, out_spec);
+            output.render_reserved(Some(2));
+
+            adapter.transform(&input, &mut output);
+
+            assert_eq!(output.chan(0), &[0.5, 0.0]);
+
+            if channels.count() == 1 {
+                assert_eq!(output.chan(1), &[0.5, 0.0]);
+            }
+            else {
+                assert_eq!(output.chan(1), &[0.5, -0.5]);
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `emphasis` module provides de-emphasis filters for audio that was pre-emphasized before
+//! encoding.
+//!
+//! Some encoders, and the Compact Disc, may boost the high frequencies of the audio before
+//! encoding to improve the signal-to-noise ratio. The boost must be undone after decoding with a
+//! matching de-emphasis filter, otherwise the audio will have a tilted frequency response.
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::dsp::processor::AudioProcessor;
+use crate::errors::Result;
+
+/// The pre-emphasis applied to audio before encoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Emphasis {
+    /// 50/15 µs pre-emphasis, as used by MPEG audio and the Compact Disc.
+    Fifty15,
+    /// CCITT J.17 pre-emphasis.
+    CcittJ17,
+}
+
+impl Emphasis {
+    /// Gets the time constants, in seconds, of the zero and the pole of the de-emphasis filter.
+    fn time_constants(&self) -> (f64, f64) {
+        match self {
+            Emphasis::Fifty15 => (15e-6, 50e-6),
+            // J.17 specifies an insertion loss of 10 log((75 + (w/3000)^2) / (1 + (w/3000)^2))
+            // dB, which is a pole at 3000 rad/s, and a zero at sqrt(75) * 3000 rad/s.
+            Emphasis::CcittJ17 => (1.0 / (75f64.sqrt() * 3000.0), 1.0 / 3000.0),
+        }
+    }
+}
+
+/// A `DeEmphasis` filter undoes the pre-emphasis applied to audio before encoding.
+///
+/// The filter is a first-order high-frequency shelving filter with unity gain at DC.
+pub struct DeEmphasis {
+    emphasis: Emphasis,
+    /// The filter coefficients `b0`, `b1`, and `a1`.
+    coeffs: [f32; 3],
+    /// The previous input and output sample of each channel.
+    state: Vec<[f32; 2]>,
+}
+
+impl DeEmphasis {
+    /// Instantiates a new `DeEmphasis` filter for the given pre-emphasis.
+    pub fn new(emphasis: Emphasis) -> Self {
+        DeEmphasis { emphasis, coeffs: [1.0, 0.0, 0.0], state: Vec::new() }
+    }
+
+    /// Gets the pre-emphasis the filter undoes.
+    pub fn emphasis(&self) -> Emphasis {
+        self.emphasis
+    }
+}
+
+impl AudioProcessor for DeEmphasis {
+    fn configure(&mut self, spec: &SignalSpec) -> Result<()> {
+        let (tz, tp) = self.emphasis.time_constants();
+
+        // Design the filter from the analog prototype H(s) = (1 + s*tz) / (1 + s*tp) using the
+        // bilinear transform.
+        let k = 2.0 * f64::from(spec.rate);
+        let norm = 1.0 / (1.0 + k * tp);
+
+        self.coeffs = [
+            ((1.0 + k * tz) * norm) as f32,
+            ((1.0 - k * tz) * norm) as f32,
+            ((1.0 - k * tp) * norm) as f32,
+        ];
+
+        self.state = vec![[0.0; 2]; spec.channels.count()];
+
+        Ok(())
+    }
+
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        let [b0, b1, a1] = self.coeffs;
+
+        for (c, state) in self.state.iter_mut().enumerate() {
+            let [mut x1, mut y1] = *state;
+
+            for s in buf.chan_mut(c) {
+                let y = b0 * *s + b1 * x1 - a1 * y1;
+                x1 = *s;
+                y1 = y;
+                *s = y;
+            }
+
+            *state = [x1, y1];
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.iter_mut().for_each(|state| *state = [0.0; 2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeEmphasis, Emphasis};
+    use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+    use crate::dsp::processor::AudioProcessor;
+    use std::f32::consts::PI;
+
+    /// Gets the gain, in dB, of the filter for a sinusoid of frequency `freq`.
+    fn gain(emphasis: Emphasis, freq: f32) -> f32 {
+        const RATE: u32 = 48_000;
+        const LEN: usize = 4 * RATE as usize;
+
+        let spec = SignalSpec::new(RATE, Channels::FRONT_LEFT);
+
+        let mut buf = AudioBuffer::<f32>::new(LEN as u64, spec);
+        buf.render_reserved(Some(LEN));
+
+        for (i, s) in buf.chan_mut(0).iter_mut().enumerate() {
+            *s = (2.0 * PI * freq * i as f32 / RATE as f32).cos();
+        }
+
+        // Measure the energy after the filter has settled.
+        let energy = |buf: &AudioBuffer<f32>| -> f32 {
+            buf.chan(0)[RATE as usize..].iter().map(|s| s * s).sum()
+        };
+
+        let input = energy(&buf);
+
+        let mut filter = DeEmphasis::new(emphasis);
+        filter.configure(&spec).unwrap();
+        filter.process(&mut buf);
+
+        10.0 * (energy(&buf) / input).log10()
+    }
+
+    #[test]
+    fn verify_de_emphasis() {
+        // Unity gain at DC for all filters.
+        assert!(gain(Emphasis::Fifty15, 0.0).abs() < 0.01);
+        assert!(gain(Emphasis::CcittJ17, 0.0).abs() < 0.01);
+
+        // The 50/15 µs filter attenuates high frequencies by up-to 10.5 dB.
+        assert!((gain(Emphasis::Fifty15, 1_000.0) + 0.37).abs() < 0.05);
+        assert!((gain(Emphasis::Fifty15, 10_000.0) + 8.17).abs() < 0.05);
+
+        // The J.17 filter attenuates high frequencies by up-to 18.75 dB.
+        assert!((gain(Emphasis::CcittJ17, 800.0) + 5.65).abs() < 0.05);
+        assert!((gain(Emphasis::CcittJ17, 20_000.0) + 18.73).abs() < 0.05);
+    }
+}
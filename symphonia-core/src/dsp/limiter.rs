@@ -0,0 +1,365 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `limiter` module provides a true-peak limiter.
+//!
+//! Applying a gain, such as ReplayGain, to quiet audio may boost the audio beyond full-scale. A
+//! limiter smoothly reduces the gain of the audio wherever it would exceed a ceiling. Since the
+//! peaks of the analog signal reconstructed by a digital-to-analog converter may exceed the
+//! sample values, a [`TruePeakLimiter`] detects the peaks of a 4x oversampled signal as described
+//! in ITU-R BS.1770-4, Annex 2.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::dsp::processor::AudioProcessor;
+use crate::errors::Result;
+
+/// The number of taps of each phase of the oversampling filter.
+const N_TAPS: usize = 12;
+
+/// The 4 phases of the 48-tap interpolating FIR filter used to oversample the signal by 4x, as
+/// specified in ITU-R BS.1770-4, Annex 2.
+///
+/// Phase `k` estimates the signal `k / 4` samples after the sample 6 samples before the latest
+/// sample.
+const PHASES: [[f32; N_TAPS]; 4] = [
+    [
+        0.0017089843750,
+        0.0109863281250,
+        -0.0196533203125,
+        0.0332031250000,
+        -0.0594482421875,
+        0.1373291015625,
+        0.9721679687500,
+        -0.1022949218750,
+        0.0476074218750,
+        -0.0266113281250,
+        0.0148925781250,
+        -0.0083007812500,
+    ],
+    [
+        -0.0291748046875,
+        0.0292968750000,
+        -0.0517578125000,
+        0.0891113281250,
+        -0.1665039062500,
+        0.4650878906250,
+        0.7797851562500,
+        -0.2003173828125,
+        0.1015625000000,
+        -0.0582275390625,
+        0.0330810546875,
+        -0.0189208984375,
+    ],
+    [
+        -0.0189208984375,
+        0.0330810546875,
+        -0.0582275390625,
+        0.1015625000000,
+        -0.2003173828125,
+        0.7797851562500,
+        0.4650878906250,
+        -0.1665039062500,
+        0.0891113281250,
+        -0.0517578125000,
+        0.0292968750000,
+        -0.0291748046875,
+    ],
+    [
+        -0.0083007812500,
+        0.0148925781250,
+        -0.0266113281250,
+        0.0476074218750,
+        -0.1022949218750,
+        0.9721679687500,
+        0.1373291015625,
+        -0.0594482421875,
+        0.0332031250000,
+        -0.0196533203125,
+        0.0109863281250,
+        0.0017089843750,
+    ],
+];
+
+/// The delay, in samples, between the latest sample and the samples whose true-peak is
+/// estimated by the oversampling filter.
+const DETECTOR_DELAY: usize = 6;
+
+/// The default time over which the gain is reduced ahead of a peak.
+const DEFAULT_LOOKAHEAD: Duration = Duration::from_micros(1500);
+
+/// The default time over which the gain recovers after a peak.
+const DEFAULT_RELEASE: Duration = Duration::from_millis(100);
+
+/// A `TruePeakDetector` estimates the true-peak of a single channel.
+#[derive(Clone, Default)]
+pub(crate) struct TruePeakDetector {
+    /// The last `N_TAPS` samples, newest first.
+    history: [f32; N_TAPS],
+}
+
+impl TruePeakDetector {
+    /// Pushes a sample, and returns the true-peak of the signal between the samples
+    /// `DETECTOR_DELAY` and `DETECTOR_DELAY - 1` samples before it.
+    pub(crate) fn push(&mut self, sample: f32) -> f32 {
+        self.history.copy_within(..N_TAPS - 1, 1);
+        self.history[0] = sample;
+
+        PHASES.iter().fold(0.0, |peak, taps| {
+            let value: f32 = taps.iter().zip(&self.history).map(|(&c, &s)| c * s).sum();
+            peak.max(value.abs())
+        })
+    }
+}
+
+/// A `TruePeakLimiter` prevents the true-peak of the audio from exceeding a ceiling.
+///
+/// All channels are limited by the same gain so that the stereo image is preserved. To reduce
+/// the gain smoothly ahead of a peak, the audio is delayed by [`TruePeakLimiter::latency`] frames.
+/// The first frames output by the limiter are therefore silent, and the last frames input to the
+/// limiter are never output.
+pub struct TruePeakLimiter {
+    /// The ceiling as a linear amplitude.
+    ceiling: f32,
+    lookahead: Duration,
+    release: Duration,
+    /// The number of frames over which the gain is reduced ahead of a peak.
+    n_lookahead: usize,
+    /// The release filter coefficient.
+    release_coeff: f32,
+    detectors: Vec<TruePeakDetector>,
+    /// The delay lines of each channel, and the position of the oldest frame in them.
+    delay: Vec<Vec<f32>>,
+    delay_pos: usize,
+    /// The required gain of recent frames, as (frame index, gain), in increasing order of gain.
+    min_window: VecDeque<(u64, f32)>,
+    /// The gain after the release filter.
+    release_gain: f32,
+    /// The released gain of the last `n_lookahead` frames, and their sum, for smoothing.
+    smooth_window: VecDeque<f32>,
+    smooth_sum: f64,
+    /// The index of the next frame.
+    frame: u64,
+}
+
+impl TruePeakLimiter {
+    /// Instantiates a new `TruePeakLimiter` with a ceiling of `ceiling_db` dBTP.
+    pub fn new(ceiling_db: f32) -> Self {
+        TruePeakLimiter {
+            ceiling: 10.0f32.powf(ceiling_db / 20.0),
+            lookahead: DEFAULT_LOOKAHEAD,
+            release: DEFAULT_RELEASE,
+            n_lookahead: 1,
+            release_coeff: 0.0,
+            detectors: Vec::new(),
+            delay: Vec::new(),
+            delay_pos: 0,
+            min_window: VecDeque::new(),
+            release_gain: 1.0,
+            smooth_window: VecDeque::new(),
+            smooth_sum: 0.0,
+            frame: 0,
+        }
+    }
+
+    /// Sets the time over which the gain is reduced ahead of a peak. Default: 1.5 ms.
+    ///
+    /// Takes effect the next time the limiter is configured.
+    pub fn set_lookahead(&mut self, lookahead: Duration) -> &mut Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    /// Sets the time over which the gain recovers after a peak. Default: 100 ms.
+    ///
+    /// Takes effect the next time the limiter is configured.
+    pub fn set_release(&mut self, release: Duration) -> &mut Self {
+        self.release = release;
+        self
+    }
+
+    /// Gets the number of frames the audio is delayed by.
+    pub fn latency(&self) -> usize {
+        self.n_lookahead + DETECTOR_DELAY - 1
+    }
+
+    /// Computes the gain of the next output frame from the required gain of the latest input
+    /// frame.
+    fn next_gain(&mut self, required: f32) -> f32 {
+        // Get the minimum required gain of the last `n_lookahead + 1` frames.
+        while self.min_window.back().map_or(false, |&(_, gain)| gain >= required) {
+            self.min_window.pop_back();
+        }
+
+        self.min_window.push_back((self.frame, required));
+
+        while self
+            .min_window
+            .front()
+            .map_or(false, |&(i, _)| i + (self.n_lookahead as u64) < self.frame)
+        {
+            self.min_window.pop_front();
+        }
+
+        let min_gain = self.min_window.front().map_or(1.0, |&(_, gain)| gain);
+
+        // The gain may be reduced instantly, but recovers slowly.
+        self.release_gain = min_gain.min(1.0 - (1.0 - self.release_gain) * self.release_coeff);
+
+        // Smooth the gain with a moving average. Since the minimum was taken over a window longer
+        // than the average, the averaged gain never exceeds the gain required at a peak.
+        self.smooth_sum += f64::from(self.release_gain);
+        self.smooth_window.push_back(self.release_gain);
+
+        if self.smooth_window.len() > self.n_lookahead {
+            self.smooth_sum -= f64::from(self.smooth_window.pop_front().unwrap());
+        }
+
+        self.frame += 1;
+
+        (self.smooth_sum / self.n_lookahead as f64) as f32
+    }
+}
+
+impl AudioProcessor for TruePeakLimiter {
+    fn configure(&mut self, spec: &SignalSpec) -> Result<()> {
+        let rate = f64::from(spec.rate);
+
+        self.n_lookahead = ((self.lookahead.as_secs_f64() * rate).round() as usize).max(1);
+        self.release_coeff = (-1.0 / (self.release.as_secs_f64() * rate)).exp() as f32;
+
+        let n_channels = spec.channels.count();
+
+        self.detectors = vec![Default::default(); n_channels];
+        self.delay = vec![vec![0.0; self.latency()]; n_channels];
+
+        self.reset();
+
+        Ok(())
+    }
+
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        let n_channels = self.detectors.len();
+        let latency = self.latency();
+
+        for i in 0..buf.frames() {
+            // Get the true-peak of the latest input frame across all channels.
+            let peak = (0..n_channels)
+                .map(|c| {
+                    let sample = buf.chan(c)[i];
+                    let peak = self.detectors[c].push(sample);
+                    // Include the sample the true-peak is estimated around.
+                    peak.max(self.detectors[c].history[DETECTOR_DELAY].abs())
+                })
+                .fold(0.0f32, f32::max);
+
+            let required = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+            let gain = self.next_gain(required);
+
+            // Exchange the latest input frame with the oldest delayed frame, and apply the gain.
+            for (c, delay) in self.delay.iter_mut().enumerate() {
+                let sample = &mut buf.chan_mut(c)[i];
+                let delayed = std::mem::replace(&mut delay[self.delay_pos], *sample);
+                *sample = gain * delayed;
+            }
+
+            self.delay_pos = (self.delay_pos + 1) % latency;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.detectors.iter_mut().for_each(|detector| *detector = Default::default());
+        self.delay.iter_mut().for_each(|delay| delay.iter_mut().for_each(|s| *s = 0.0));
+        self.delay_pos = 0;
+        self.min_window.clear();
+        self.release_gain = 1.0;
+        self.smooth_window.clear();
+        self.smooth_window.resize(self.n_lookahead, 1.0);
+        self.smooth_sum = self.n_lookahead as f64;
+        self.frame = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TruePeakDetector, TruePeakLimiter, N_TAPS};
+    use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+    use crate::dsp::processor::AudioProcessor;
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    const RATE: u32 = 48_000;
+
+    /// Generates a sine wave with a frequency of a quarter of the sample rate, and a phase such
+    /// that every sample is at 1/sqrt(2) of the true-peak.
+    fn quarter_rate_sine(amplitude: f32, len: usize) -> AudioBuffer<f32> {
+        let spec = SignalSpec::new(RATE, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut buf = AudioBuffer::<f32>::new(len as u64, spec);
+        buf.render_reserved(Some(len));
+
+        for c in 0..2 {
+            for (i, s) in buf.chan_mut(c).iter_mut().enumerate() {
+                *s = amplitude * (0.5 * PI * i as f32 + 0.25 * PI).sin();
+            }
+        }
+
+        buf
+    }
+
+    /// Gets the true-peak of `samples`, ignoring the start-up transient of the detector.
+    fn true_peak(samples: &[f32]) -> f32 {
+        let mut detector = TruePeakDetector::default();
+        samples.iter().map(|&s| detector.push(s)).skip(N_TAPS).fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn verify_true_peak_detector() {
+        let buf = quarter_rate_sine(1.0, 1024);
+
+        // The sample peak underestimates the true-peak by 3 dB.
+        let sample_peak = buf.chan(0).iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((sample_peak - FRAC_1_SQRT_2).abs() < 0.001);
+
+        assert!((true_peak(buf.chan(0)) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn verify_true_peak_limiter() {
+        let ceiling = 10.0f32.powf(-1.0 / 20.0);
+
+        let mut limiter = TruePeakLimiter::new(-1.0);
+        limiter
+            .configure(&SignalSpec::new(RATE, Channels::FRONT_LEFT | Channels::FRONT_RIGHT))
+            .unwrap();
+
+        let latency = limiter.latency();
+
+        // A signal below the ceiling is only delayed.
+        let mut buf = quarter_rate_sine(0.5, 4096);
+        let input = buf.clone();
+
+        limiter.process(&mut buf);
+
+        assert!(buf.chan(0)[..latency].iter().all(|&s| s == 0.0));
+        assert_eq!(&buf.chan(1)[latency..], &input.chan(1)[..4096 - latency]);
+
+        // The sample peak of a signal with a true-peak of 0 dBTP is below the ceiling, but its
+        // true-peak is not. The true-peak of the output must not exceed the ceiling.
+        let mut buf = quarter_rate_sine(1.0, 4096);
+
+        limiter.reset();
+        limiter.process(&mut buf);
+
+        for c in 0..2 {
+            assert!(true_peak(buf.chan(c)) <= ceiling * 1.01);
+            assert!(true_peak(&buf.chan(c)[2048..]) >= ceiling * 0.97);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `mixer` module provides a matrix mixer that maps a set of input channels onto a set of
+//! output channels.
+//!
+//! A [`Mixer`] computes each output channel as the weighted sum of the input channels. The gains
+//! may be set individually to build any channel mapping, or taken from the standard
+//! ITU-R BS.775 downmix equations with [`Mixer::downmix`]. Since it changes the channels of the
+//! audio, a `Mixer` is a [`ChannelTransform`].
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+use crate::codecs::transform::ChannelTransform;
+use crate::errors::{unsupported_error, Result};
+
+use super::adapter::{left_channels, right_channels};
+
+/// Mixes the channels of `input` into the channels of `output` using `gains`. The gains of the
+/// first output channel are first, followed by the gains of the second output channel, and so on.
+pub(super) fn mix(gains: &[f32], input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>) {
+    let n_in = input.spec().channels.count();
+    let n_out = output.spec().channels.count();
+
+    for o in 0..n_out {
+        let out = output.chan_mut(o);
+
+        out.iter_mut().for_each(|s| *s = 0.0);
+
+        for (i, &gain) in gains[o * n_in..(o + 1) * n_in].iter().enumerate() {
+            if gain == 0.0 {
+                continue;
+            }
+
+            for (out, &s) in out.iter_mut().zip(input.chan(i)) {
+                *out += gain * s;
+            }
+        }
+    }
+}
+
+/// Gets the gains of the left and right output channels for an input channel when downmixing to
+/// stereo.
+fn stereo_downmix_gains(channel: Channels) -> [f32; 2] {
+    if channel == Channels::FRONT_LEFT {
+        [1.0, 0.0]
+    }
+    else if channel == Channels::FRONT_RIGHT {
+        [0.0, 1.0]
+    }
+    else if channel == Channels::FRONT_CENTRE {
+        [FRAC_1_SQRT_2, FRAC_1_SQRT_2]
+    }
+    else if Channels::LFE1.contains(channel) || Channels::LFE2.contains(channel) {
+        [0.0, 0.0]
+    }
+    else if left_channels().contains(channel) {
+        [FRAC_1_SQRT_2, 0.0]
+    }
+    else if right_channels().contains(channel) {
+        [0.0, FRAC_1_SQRT_2]
+    }
+    else {
+        // A centre channel other than the front-centre channel is a surround channel split
+        // equally between the left and right channels.
+        [0.5, 0.5]
+    }
+}
+
+/// A `Mixer` maps a set of input channels onto a set of output channels using a matrix of gains.
+#[derive(Clone, Debug)]
+pub struct Mixer {
+    input: Channels,
+    output: Channels,
+    gains: Vec<f32>,
+}
+
+impl Mixer {
+    /// Instantiates a new `Mixer` from `input` to `output` channels. All gains are initially 0,
+    /// such that every output channel is silent.
+    pub fn new(input: Channels, output: Channels) -> Self {
+        assert!(input.count() > 0, "mixer must have at least one input channel");
+        assert!(output.count() > 0, "mixer must output at least one channel");
+
+        Mixer { input, output, gains: vec![0.0; input.count() * output.count()] }
+    }
+
+    /// Instantiates a new `Mixer` that copies every input channel that is also an output channel.
+    /// Other input channels are dropped, and other output channels are silent.
+    pub fn new_mapping(input: Channels, output: Channels) -> Self {
+        let mut mixer = Mixer::new(input, output);
+
+        for channel in (input & output).iter() {
+            mixer.set_gain(channel, channel, 1.0);
+        }
+
+        mixer
+    }
+
+    /// Instantiates a new `Mixer` that downmixes `input` channels to stereo or mono `output`
+    /// channels using the ITU-R BS.775 downmix equations.
+    ///
+    /// For stereo, the front-centre channel is mixed into both the left and right channels at
+    /// -3 dB, and the surround channels are mixed into the channel on the same side at -3 dB.
+    /// Low-frequency effects channels are dropped. Mono is the sum of the stereo downmix at -3 dB.
+    ///
+    /// The downmix may exceed full-scale, see [`Mixer::normalize`].
+    pub fn downmix(input: Channels, output: Channels) -> Result<Self> {
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+
+        if output != stereo && output.count() != 1 {
+            return unsupported_error("core (mixer): can only downmix to stereo or mono");
+        }
+
+        let mut mixer = Mixer::new(input, output);
+
+        for channel in input.iter() {
+            let [l, r] = stereo_downmix_gains(channel);
+
+            if output == stereo {
+                mixer.set_gain(Channels::FRONT_LEFT, channel, l);
+                mixer.set_gain(Channels::FRONT_RIGHT, channel, r);
+            }
+            else {
+                mixer.set_gain(output, channel, FRAC_1_SQRT_2 * (l + r));
+            }
+        }
+
+        Ok(mixer)
+    }
+
+    /// Gets the input channels.
+    pub fn input(&self) -> Channels {
+        self.input
+    }
+
+    /// Gets the output channels.
+    pub fn output(&self) -> Channels {
+        self.output
+    }
+
+    /// Gets the index of the gain of `input` in `output`.
+    fn index(&self, output: Channels, input: Channels) -> usize {
+        assert!(output.count() == 1 && input.count() == 1, "gains are set per channel");
+
+        let o = self.output.iter().position(|c| c == output).expect("not an output channel");
+        let i = self.input.iter().position(|c| c == input).expect("not an input channel");
+
+        o * self.input.count() + i
+    }
+
+    /// Gets the gain of the `input` channel in the `output` channel.
+    pub fn gain(&self, output: Channels, input: Channels) -> f32 {
+        self.gains[self.index(output, input)]
+    }
+
+    /// Sets the gain of the `input` channel in the `output` channel.
+    ///
+    /// Panics if `output` or `input` is not exactly one of the output or input channels,
+    /// respectively.
+    pub fn set_gain(&mut self, output: Channels, input: Channels, gain: f32) {
+        let index = self.index(output, input);
+        self.gains[index] = gain;
+    }
+
+    /// Scales all gains such that no output channel can exceed full-scale. That is, the sum of the
+    /// magnitudes of the gains of each output channel is at most 1.
+    pub fn normalize(&mut self) {
+        let n_in = self.input.count();
+
+        let max = self
+            .gains
+            .chunks_exact(n_in)
+            .map(|gains| gains.iter().map(|g| g.abs()).sum::<f32>())
+            .fold(0.0, f32::max);
+
+        if max > 1.0 {
+            self.gains.iter_mut().for_each(|g| *g /= max);
+        }
+    }
+}
+
+impl ChannelTransform for Mixer {
+    fn output_spec(&self, input: &SignalSpec) -> Result<SignalSpec> {
+        if input.channels != self.input {
+            return unsupported_error("core (mixer): input channels do not match the mixer");
+        }
+
+        Ok(SignalSpec::new(input.rate, self.output))
+    }
+
+    fn transform(&mut self, input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>) {
+        mix(&self.gains, input, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use super::Mixer;
+    use crate::audio::{AudioBuffer, Channels, Layout, Signal, SignalSpec};
+    use crate::codecs::transform::ChannelTransform;
+
+    fn stereo() -> Channels {
+        Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+    }
+
+    #[test]
+    fn verify_mixer_downmix() {
+        let five_one = Layout::FivePointOne.into_channels();
+        let h = FRAC_1_SQRT_2;
+
+        // 5.1 to stereo. The channels of 5.1 are FL, FR, FC, LFE, RL, RR.
+        let mixer = Mixer::downmix(five_one, stereo()).unwrap();
+
+        let left: Vec<f32> = five_one.iter().map(|c| mixer.gain(Channels::FRONT_LEFT, c)).collect();
+        assert_eq!(left, [1.0, 0.0, h, 0.0, h, 0.0]);
+
+        let right: Vec<f32> =
+            five_one.iter().map(|c| mixer.gain(Channels::FRONT_RIGHT, c)).collect();
+        assert_eq!(right, [0.0, 1.0, h, 0.0, 0.0, h]);
+
+        // 5.1 to mono.
+        let mono = Channels::FRONT_CENTRE;
+        let mixer = Mixer::downmix(five_one, mono).unwrap();
+
+        let gains: Vec<f32> = five_one.iter().map(|c| mixer.gain(mono, c)).collect();
+        let expected = [h, h, 1.0, 0.0, 0.5, 0.5];
+
+        for (gain, expected) in gains.iter().zip(expected.iter()) {
+            assert!((gain - expected).abs() < 1e-6);
+        }
+
+        // Only stereo and mono downmixes are supported.
+        assert!(Mixer::downmix(stereo(), five_one).is_err());
+    }
+
+    #[test]
+    fn verify_mixer_normalize() {
+        let mut mixer = Mixer::downmix(Layout::FivePointOne.into_channels(), stereo()).unwrap();
+        mixer.normalize();
+
+        let sum = 1.0 + 2.0 * FRAC_1_SQRT_2;
+
+        assert!((mixer.gain(Channels::FRONT_LEFT, Channels::FRONT_LEFT) - 1.0 / sum).abs() < 1e-6);
+
+        // Gains that cannot exceed full-scale are unchanged.
+        let mut mixer = Mixer::new_mapping(stereo(), stereo());
+        mixer.normalize();
+
+        assert_eq!(mixer.gain(Channels::FRONT_LEFT, Channels::FRONT_LEFT), 1.0);
+    }
+
+    #[test]
+    fn verify_mixer_transform() {
+        let input = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+
+        // Swap the left and right channels, and drop the centre channel.
+        let mut mixer = Mixer::new(input, stereo());
+        mixer.set_gain(Channels::FRONT_LEFT, Channels::FRONT_RIGHT, 1.0);
+        mixer.set_gain(Channels::FRONT_RIGHT, Channels::FRONT_LEFT, 0.5);
+
+        let spec = SignalSpec::new(8_000, input);
+
+        let mut src = AudioBuffer::<f32>::new(2, spec);
+        src.render_reserved(Some(2));
+        src.chan_mut(0).copy_from_slice(&[0.1, 0.2]);
+        src.chan_mut(1).copy_from_slice(&[0.3, 0.4]);
+        src.chan_mut(2).copy_from_slice(&[0.5, 0.6]);
+
+        let out_spec = mixer.output_spec(&spec).unwrap();
+        assert_eq!(out_spec, SignalSpec::new(8_000, stereo()));
+
+        let mut dst = AudioBuffer::<f32>::new(2, out_spec);
+        dst.render_reserved(Some(2));
+
+        mixer.transform(&src, &mut dst);
+
+        assert_eq!(dst.chan(0), &[0.3, 0.4]);
+        assert_eq!(dst.chan(1), &[0.05, 0.1]);
+
+        // The input channels must match the mixer.
+        assert!(mixer.output_spec(&SignalSpec::new(8_000, stereo())).is_err());
+    }
+}
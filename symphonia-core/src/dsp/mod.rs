@@ -7,6 +7,23 @@
 
 //! The `dsp` module provides efficient implementations of common signal processing algorithms.
 
+pub mod adapter;
 pub mod complex;
+pub mod emphasis;
 pub mod fft;
+pub mod limiter;
 pub mod mdct;
+pub mod mixer;
+pub mod pan;
+pub mod processor;
+
+/// The SIMD instruction sets the DSP algorithms were built to use. An instruction set is only used
+/// if it is supported by the CPU at runtime.
+pub const SIMD_FEATURES: &[&str] = &[
+    #[cfg(feature = "opt-simd-sse")]
+    "sse",
+    #[cfg(feature = "opt-simd-avx")]
+    "avx",
+    #[cfg(feature = "opt-simd-neon")]
+    "neon",
+];
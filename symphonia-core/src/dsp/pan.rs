@@ -0,0 +1,163 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `pan` module provides constant-power panning and stereo balance.
+//!
+//! Positions range from -1.0 (fully left), through 0.0 (centre), to 1.0 (fully right). A
+//! [`Pan`] places a mono signal in the stereo field, and, since it changes the number of channels,
+//! is a [`ChannelTransform`]. A [`Balance`] adjusts the balance of a stereo signal in-place, and is
+//! an [`AudioProcessor`].
+
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+use crate::codecs::transform::ChannelTransform;
+use crate::dsp::processor::AudioProcessor;
+use crate::errors::{unsupported_error, Result};
+
+/// Gets the gains of the left and right channels for a signal at `position` using the
+/// constant-power (-3 dB) pan law.
+///
+/// The sum of the squares of the gains is always 1, so the perceived loudness of the signal does
+/// not change as it is moved. At the centre, each channel has a gain of -3 dB.
+pub fn pan_gains(position: f32) -> [f32; 2] {
+    let angle = (position.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    [angle.cos(), angle.sin()]
+}
+
+/// Gets the gains of the left and right channels of a stereo signal for a balance of `position`.
+///
+/// The gains follow the constant-power pan law, normalized such that both channels have unity
+/// gain at the centre. Neither channel is ever boosted: moving the balance only attenuates the
+/// opposite channel.
+pub fn balance_gains(position: f32) -> [f32; 2] {
+    let [l, r] = pan_gains(position);
+    [(SQRT_2 * l).min(1.0), (SQRT_2 * r).min(1.0)]
+}
+
+/// A `Pan` places a mono signal in the stereo field. Stereo signals have their balance adjusted
+/// instead.
+pub struct Pan {
+    gains: [f32; 2],
+    balance: [f32; 2],
+}
+
+impl Pan {
+    /// Instantiates a new `Pan` for the position `position`.
+    pub fn new(position: f32) -> Self {
+        Pan { gains: pan_gains(position), balance: balance_gains(position) }
+    }
+}
+
+impl ChannelTransform for Pan {
+    fn output_spec(&self, input: &SignalSpec) -> Result<SignalSpec> {
+        match input.channels.count() {
+            1 | 2 => Ok(SignalSpec::new(input.rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT)),
+            _ => unsupported_error("core (pan): only mono or stereo audio may be panned"),
+        }
+    }
+
+    fn transform(&mut self, input: &AudioBuffer<f32>, output: &mut AudioBuffer<f32>) {
+        let (gains, right) = match input.spec().channels.count() {
+            1 => (self.gains, 0),
+            _ => (self.balance, 1),
+        };
+
+        let (l, r) = output.chan_pair_mut(0, 1);
+
+        for (out, &s) in l.iter_mut().zip(input.chan(0)) {
+            *out = gains[0] * s;
+        }
+
+        for (out, &s) in r.iter_mut().zip(input.chan(right)) {
+            *out = gains[1] * s;
+        }
+    }
+}
+
+/// A `Balance` adjusts the balance of a stereo signal.
+pub struct Balance {
+    gains: [f32; 2],
+}
+
+impl Balance {
+    /// Instantiates a new `Balance` for the position `position`.
+    pub fn new(position: f32) -> Self {
+        Balance { gains: balance_gains(position) }
+    }
+}
+
+impl AudioProcessor for Balance {
+    fn configure(&mut self, spec: &SignalSpec) -> Result<()> {
+        if spec.channels.count() != 2 {
+            return unsupported_error("core (pan): balance requires stereo audio");
+        }
+
+        Ok(())
+    }
+
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        let [gl, gr] = self.gains;
+
+        let (l, r) = buf.chan_pair_mut(0, 1);
+
+        l.iter_mut().for_each(|s| *s *= gl);
+        r.iter_mut().for_each(|s| *s *= gr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{balance_gains, pan_gains, Pan};
+    use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+    use crate::codecs::transform::ChannelTransform;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    fn assert_gains(gains: [f32; 2], expected: [f32; 2]) {
+        assert!((gains[0] - expected[0]).abs() < 1e-6, "{:?} != {:?}", gains, expected);
+        assert!((gains[1] - expected[1]).abs() < 1e-6, "{:?} != {:?}", gains, expected);
+    }
+
+    #[test]
+    fn verify_pan_gains() {
+        assert_gains(pan_gains(-1.0), [1.0, 0.0]);
+        assert_gains(pan_gains(0.0), [FRAC_1_SQRT_2, FRAC_1_SQRT_2]);
+        assert_gains(pan_gains(1.0), [0.0, 1.0]);
+        assert_gains(pan_gains(2.0), [0.0, 1.0]);
+
+        // Constant power.
+        let [l, r] = pan_gains(0.3);
+        assert!((l * l + r * r - 1.0).abs() < 1e-6);
+
+        assert_gains(balance_gains(-1.0), [1.0, 0.0]);
+        assert_gains(balance_gains(0.0), [1.0, 1.0]);
+        assert_gains(balance_gains(1.0), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn verify_pan_mono() {
+        let mut input = AudioBuffer::<f32>::new(4, SignalSpec::new(44_100, Channels::FRONT_CENTRE));
+        input.render_reserved(Some(4));
+        input.chan_mut(0).copy_from_slice(&[1.0, -1.0, 0.5, 0.0]);
+
+        let mut pan = Pan::new(-0.5);
+
+        let spec = pan.output_spec(input.spec()).unwrap();
+        assert_eq!(spec.channels, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut output = AudioBuffer::<f32>::new(4, spec);
+        output.render_reserved(Some(4));
+
+        pan.transform(&input, &mut output);
+
+        let [gl, gr] = pan_gains(-0.5);
+
+        assert_eq!(output.chan(0), &[gl, -gl, 0.5 * gl, 0.0]);
+        assert_eq!(output.chan(1), &[gr, -gr, 0.5 * gr, 0.0]);
+        assert!(gl > gr);
+    }
+}
@@ -0,0 +1,209 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `processor` module provides a hook to insert audio processing (e.g., an equalizer,
+//! crossfeed, or a third-party effect) between decoding and output.
+//!
+//! An [`AudioProcessor`] processes audio in-place. Any number of processors may be added to a
+//! [`ProcessorChain`], which runs each processor, in order, on the audio passed to it. Unlike a
+//! [`ChannelTransform`](crate::codecs::transform::ChannelTransform), a processor may not change
+//! the number of channels or the sample rate of the audio.
+
+use std::fmt;
+use std::mem;
+
+use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use crate::errors::Result;
+
+/// An `AudioProcessor` processes audio in-place.
+pub trait AudioProcessor: Send {
+    /// Prepares the processor to process audio with the signal specification `spec`. Called
+    /// before the first audio buffer is processed, and whenever the signal specification changes.
+    ///
+    /// Returns an error if the processor does not support the signal specification.
+    fn configure(&mut self, _spec: &SignalSpec) -> Result<()> {
+        Ok(())
+    }
+
+    /// Processes the audio in `buf` in-place.
+    fn process(&mut self, buf: &mut AudioBuffer<f32>);
+
+    /// Resets any state maintained by the processor. Should be called whenever playback is
+    /// discontinuous (e.g., after a seek).
+    fn reset(&mut self) {}
+}
+
+/// A `ProcessorChain` runs a sequence of `AudioProcessor`s.
+pub struct ProcessorChain {
+    processors: Vec<Box<dyn AudioProcessor>>,
+    /// The signal specification the processors are configured for.
+    spec: Option<SignalSpec>,
+    /// A buffer for audio converted to `f32` samples.
+    buf: AudioBuffer<f32>,
+}
+
+impl ProcessorChain {
+    /// Instantiates a new, empty, `ProcessorChain`.
+    pub fn new() -> Self {
+        ProcessorChain { processors: Vec::new(), spec: None, buf: AudioBuffer::unused() }
+    }
+
+    /// Appends a processor to the end of the chain.
+    pub fn push(&mut self, processor: Box<dyn AudioProcessor>) -> &mut Self {
+        // The new processor must be configured before it processes any audio.
+        self.spec = None;
+        self.processors.push(processor);
+        self
+    }
+
+    /// Gets the number of processors in the chain.
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    /// Returns true if there are no processors in the chain.
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Resets all processors in the chain.
+    pub fn reset(&mut self) {
+        for processor in self.processors.iter_mut() {
+            processor.reset();
+        }
+    }
+
+    /// Runs all processors in the chain, in order, on the audio in `buf`.
+    pub fn process(&mut self, buf: &mut AudioBuffer<f32>) -> Result<()> {
+        if self.spec.as_ref() != Some(buf.spec()) {
+            for processor in self.processors.iter_mut() {
+                processor.configure(buf.spec())?;
+            }
+
+            self.spec = Some(*buf.spec());
+        }
+
+        for processor in self.processors.iter_mut() {
+            processor.process(buf);
+        }
+
+        Ok(())
+    }
+
+    /// Converts the audio in `input` to `f32` samples, runs all processors in the chain on it, and
+    /// returns a reference to the processed audio.
+    ///
+    /// This is useful for processing the audio returned by a `Decoder`.
+    pub fn process_ref(&mut self, input: AudioBufferRef<'_>) -> Result<AudioBufferRef<'_>> {
+        // An empty buffer may not have a valid signal specification, and is not processed.
+        if input.frames() == 0 {
+            self.buf.clear();
+            return Ok(self.buf.as_audio_buffer_ref());
+        }
+
+        // Reallocate the buffer if the signal specification or capacity changed.
+        if self.buf.is_unused()
+            || self.buf.spec() != input.spec()
+            || self.buf.capacity() != input.capacity()
+        {
+            self.buf = input.make_equivalent::<f32>();
+        }
+
+        input.convert(&mut self.buf);
+
+        let mut buf = mem::replace(&mut self.buf, AudioBuffer::unused());
+        let result = self.process(&mut buf);
+        self.buf = buf;
+
+        result?;
+
+        Ok(self.buf.as_audio_buffer_ref())
+    }
+}
+
+impl Default for ProcessorChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ProcessorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessorChain").field("len", &self.processors.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioProcessor, ProcessorChain};
+    use crate::audio::SignalSpec;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+    use crate::errors::{unsupported_error, Result};
+
+    /// Scales the audio, and counts the number of times it is configured and reset.
+    struct Scale {
+        gain: f32,
+        n_configures: usize,
+        n_resets: usize,
+    }
+
+    impl AudioProcessor for Scale {
+        fn configure(&mut self, spec: &SignalSpec) -> Result<()> {
+            if spec.rate != 8_000 {
+                return unsupported_error("scale: unsupported sample rate");
+            }
+            self.n_configures += 1;
+            Ok(())
+        }
+
+        fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+            let offset = (self.n_configures + 10 * self.n_resets - 1) as f32;
+            buf.transform(|s| s * self.gain + offset);
+        }
+
+        fn reset(&mut self) {
+            self.n_resets += 1;
+        }
+    }
+
+    fn scale(gain: f32) -> Box<dyn AudioProcessor> {
+        Box::new(Scale { gain, n_configures: 0, n_resets: 0 })
+    }
+
+    #[test]
+    fn verify_processor_chain() {
+        let mut chain = ProcessorChain::new();
+        assert!(chain.is_empty());
+
+        chain.push(scale(2.0)).push(scale(3.0));
+        assert_eq!(chain.len(), 2);
+
+        let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT);
+
+        let mut input = AudioBuffer::<i16>::new(4, spec);
+        input.render_reserved(Some(2));
+        input.chan_mut(0).copy_from_slice(&[0x2000, -0x4000]);
+
+        // Processors are run in order.
+        match chain.process_ref(input.as_audio_buffer_ref()).unwrap() {
+            AudioBufferRef::F32(buf) => assert_eq!(buf.chan(0), &[1.5, -3.0]),
+            _ => panic!("expected f32 samples"),
+        }
+
+        // Processors are reset, but not reconfigured for the same signal specification.
+        chain.reset();
+
+        match chain.process_ref(input.as_audio_buffer_ref()).unwrap() {
+            AudioBufferRef::F32(buf) => assert_eq!(buf.chan(0), &[41.5, 37.0]),
+            _ => panic!("expected f32 samples"),
+        }
+
+        // A processor may reject a signal specification.
+        let mut buf = AudioBuffer::<f32>::new(4, SignalSpec::new(16_000, Channels::FRONT_LEFT));
+        assert!(chain.process(&mut buf).is_err());
+    }
+}
@@ -54,6 +54,71 @@ pub enum Error {
     ResetRequired,
 }
 
+/// `ErrorKind` classifies an [`Error`] by how a consumer, such as a player, should react to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The end of the stream was reached.
+    EndOfStream,
+    /// The source stalled. For example, a network source timed out, or has no data available yet.
+    /// The operation may be retried later.
+    Interrupted,
+    /// The stream is corrupt. If a packet fails to decode with this kind of error, the packet may
+    /// be skipped.
+    Corrupt,
+    /// An unsupported container or codec feature was encountered.
+    Unsupported,
+    /// A default or user-defined limit was reached.
+    LimitReached,
+    /// The stream could not be seeked.
+    SeekFailed,
+    /// The demuxer or decoder needs to be reset before continuing.
+    ResetRequired,
+    /// Any other IO error. These errors are unrecoverable.
+    Io,
+}
+
+impl Error {
+    /// Gets the kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::IoError(ref err) => match err.kind() {
+                io::ErrorKind::UnexpectedEof => ErrorKind::EndOfStream,
+                io::ErrorKind::Interrupted
+                | io::ErrorKind::WouldBlock
+                | io::ErrorKind::TimedOut => ErrorKind::Interrupted,
+                io::ErrorKind::InvalidData => ErrorKind::Corrupt,
+                _ => ErrorKind::Io,
+            },
+            Error::DecodeError(_) => ErrorKind::Corrupt,
+            Error::SeekError(_) => ErrorKind::SeekFailed,
+            Error::Unsupported(_) => ErrorKind::Unsupported,
+            Error::LimitError(_) => ErrorKind::LimitReached,
+            Error::ResetRequired => ErrorKind::ResetRequired,
+        }
+    }
+
+    /// Returns true if the operation that returned the error may be retried.
+    pub fn is_retryable(&self) -> bool {
+        self.kind() == ErrorKind::Interrupted
+    }
+
+    /// Reclassifies an error returned while decoding a packet.
+    ///
+    /// A decoder only reads from the buffer of the packet. Therefore, an IO error, such as reading
+    /// past the end of the buffer, means the packet is truncated or malformed, rather than that
+    /// the stream ended. These errors are returned as decode errors. All other errors are
+    /// returned unchanged.
+    pub fn into_packet_error(self) -> Error {
+        match self {
+            Error::IoError(ref err) if err.kind() == io::ErrorKind::InvalidData => {
+                Error::DecodeError("core (codecs): packet is malformed")
+            }
+            Error::IoError(_) => Error::DecodeError("core (codecs): packet is truncated"),
+            err => err,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -127,3 +192,39 @@ pub fn reset_error<T>() -> Result<T> {
 pub fn end_of_stream_error<T>() -> Result<T> {
     Err(Error::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "end of stream")))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{end_of_stream_error, Error, ErrorKind, Result};
+
+    #[test]
+    fn verify_error_kind() {
+        let io_error = |kind| Error::IoError(io::Error::new(kind, "io error"));
+
+        let eos: Result<()> = end_of_stream_error();
+        assert_eq!(eos.unwrap_err().kind(), ErrorKind::EndOfStream);
+
+        assert!(io_error(io::ErrorKind::WouldBlock).is_retryable());
+        assert!(io_error(io::ErrorKind::TimedOut).is_retryable());
+        assert!(!io_error(io::ErrorKind::UnexpectedEof).is_retryable());
+
+        assert_eq!(io_error(io::ErrorKind::InvalidData).kind(), ErrorKind::Corrupt);
+        assert_eq!(io_error(io::ErrorKind::PermissionDenied).kind(), ErrorKind::Io);
+        assert_eq!(Error::DecodeError("corrupt").kind(), ErrorKind::Corrupt);
+        assert_eq!(Error::Unsupported("feature").kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn verify_into_packet_error() {
+        let err = Error::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "buffer underrun"));
+        assert_eq!(err.into_packet_error().kind(), ErrorKind::Corrupt);
+
+        let err = Error::IoError(io::Error::new(io::ErrorKind::Other, "end of bitstream"));
+        assert_eq!(err.into_packet_error().kind(), ErrorKind::Corrupt);
+
+        let err = Error::ResetRequired.into_packet_error();
+        assert_eq!(err.kind(), ErrorKind::ResetRequired);
+    }
+}
@@ -0,0 +1,246 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::checksum::{Crc16Ansi, Crc8Ccitt, Md5};
+use crate::errors::{unsupported_error, Result};
+use crate::io::Monitor;
+
+use super::{requantize, ExportSink};
+
+/// The number of frames in a FLAC block.
+const BLOCK_LEN: usize = 4096;
+
+/// A `FlacSink` writes exported audio to a FLAC file.
+///
+/// The audio is stored losslessly, but without compression (every subframe is a verbatim subframe).
+/// The file is intended to be re-encoded, or imported into an editor, and not to be distributed.
+pub struct FlacSink<W: Write + Seek> {
+    writer: W,
+    /// The position of the start of the FLAC file in the writer.
+    start_pos: u64,
+    bits_per_sample: u32,
+    rate: u32,
+    /// The samples of the current block for each channel.
+    block: Vec<Vec<i32>>,
+    /// The number of blocks written.
+    n_blocks: u32,
+    /// The number of frames written.
+    n_frames: u64,
+    /// The minimum and maximum length of an encoded block in bytes.
+    min_block_len: u32,
+    max_block_len: u32,
+    /// The MD5 checksum of the unencoded audio.
+    md5: Md5,
+    /// A buffer for an encoded block.
+    buf: Vec<u8>,
+}
+
+impl<W: Write + Seek> FlacSink<W> {
+    /// Instantiates a new `FlacSink` that writes samples with `bits_per_sample` bits, which must be
+    /// one of 8, 16, or 24, to `writer`.
+    pub fn new(writer: W, bits_per_sample: u32) -> Self {
+        assert!(matches!(bits_per_sample, 8 | 16 | 24), "bits per sample must be 8, 16, or 24");
+
+        FlacSink {
+            writer,
+            start_pos: 0,
+            bits_per_sample,
+            rate: 0,
+            block: Vec::new(),
+            n_blocks: 0,
+            n_frames: 0,
+            min_block_len: u32::MAX,
+            max_block_len: 0,
+            md5: Default::default(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Consumes the `FlacSink`, and returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes the stream marker, and the STREAMINFO metadata block.
+    fn write_header(&mut self) -> Result<()> {
+        let n_channels = self.block.len() as u64;
+
+        let (min_block_len, max_block_len) =
+            if self.n_blocks > 0 { (self.min_block_len, self.max_block_len) } else { (0, 0) };
+
+        let w = &mut self.writer;
+
+        w.write_all(b"fLaC")?;
+
+        // The STREAMINFO block is the last, and only, metadata block.
+        w.write_all(&[0x80, 0, 0, 34])?;
+        w.write_all(&(BLOCK_LEN as u16).to_be_bytes())?;
+        w.write_all(&(BLOCK_LEN as u16).to_be_bytes())?;
+        w.write_all(&min_block_len.to_be_bytes()[1..])?;
+        w.write_all(&max_block_len.to_be_bytes()[1..])?;
+
+        let info = (u64::from(self.rate) << 44)
+            | ((n_channels - 1) << 41)
+            | (u64::from(self.bits_per_sample - 1) << 36)
+            | self.n_frames;
+
+        w.write_all(&info.to_be_bytes())?;
+        w.write_all(&self.md5.md5())?;
+
+        Ok(())
+    }
+
+    /// Encodes and writes the current block.
+    fn write_block(&mut self) -> Result<()> {
+        let n_frames = self.block[0].len();
+
+        if n_frames == 0 {
+            return Ok(());
+        }
+
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+
+        // The sample size of the frame header.
+        let sample_size = match self.bits_per_sample {
+            8 => 0x1,
+            16 => 0x4,
+            _ => 0x6,
+        };
+
+        self.buf.clear();
+
+        // Frame header. The block size is stored at the end of the header, and the sample rate is
+        // stored in the STREAMINFO block. The channels are coded independently.
+        self.buf.extend_from_slice(&[0xff, 0xf8, 0x70]);
+        self.buf.push((((self.block.len() - 1) as u8) << 4) | (sample_size << 1));
+        push_utf8_u32(&mut self.buf, self.n_blocks);
+        self.buf.extend_from_slice(&((n_frames - 1) as u16).to_be_bytes());
+
+        let mut crc8 = Crc8Ccitt::new(0);
+        crc8.process_buf_bytes(&self.buf);
+        self.buf.push(crc8.crc());
+
+        // Verbatim subframes.
+        for samples in self.block.iter() {
+            self.buf.push(0x02);
+
+            for &sample in samples.iter() {
+                self.buf.extend_from_slice(&sample.to_be_bytes()[4 - bytes_per_sample..]);
+            }
+        }
+
+        let mut crc16 = Crc16Ansi::new(0);
+        crc16.process_buf_bytes(&self.buf);
+        self.buf.extend_from_slice(&crc16.crc().to_be_bytes());
+
+        // The MD5 checksum is of the interleaved little-endian samples.
+        for i in 0..n_frames {
+            for samples in self.block.iter() {
+                self.md5.process_buf_bytes(&samples[i].to_le_bytes()[..bytes_per_sample]);
+            }
+        }
+
+        self.writer.write_all(&self.buf)?;
+
+        self.min_block_len = self.min_block_len.min(self.buf.len() as u32);
+        self.max_block_len = self.max_block_len.max(self.buf.len() as u32);
+        self.n_blocks += 1;
+        self.n_frames += n_frames as u64;
+
+        self.block.iter_mut().for_each(|samples| samples.clear());
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> ExportSink for FlacSink<W> {
+    // `Seek::stream_position` requires a newer Rust than the minimum supported version.
+    #[allow(clippy::seek_from_current)]
+    fn begin(&mut self, spec: &SignalSpec) -> Result<()> {
+        let n_channels = spec.channels.count();
+
+        if n_channels == 0 || n_channels > 8 {
+            return unsupported_error("core (export): flac supports only 1 to 8 channels");
+        }
+
+        if spec.rate == 0 || spec.rate >= 1 << 20 {
+            return unsupported_error("core (export): flac does not support the sample rate");
+        }
+
+        self.rate = spec.rate;
+        self.block = vec![Vec::with_capacity(BLOCK_LEN); n_channels];
+        self.start_pos = self.writer.seek(SeekFrom::Current(0))?;
+
+        // The STREAMINFO block is rewritten when the export is finished.
+        self.write_header()
+    }
+
+    fn write(&mut self, buf: &AudioBuffer<i32>) -> Result<()> {
+        let bits_per_sample = self.bits_per_sample;
+        let mut offset = 0;
+
+        while offset < buf.frames() {
+            let len = (BLOCK_LEN - self.block[0].len()).min(buf.frames() - offset);
+
+            for (ch, samples) in self.block.iter_mut().enumerate() {
+                samples.extend(
+                    buf.chan(ch)[offset..offset + len]
+                        .iter()
+                        .map(|&s| requantize(s, bits_per_sample)),
+                );
+            }
+
+            offset += len;
+
+            if self.block[0].len() == BLOCK_LEN {
+                self.write_block()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_block()?;
+
+        self.writer.seek(SeekFrom::Start(self.start_pos))?;
+        self.write_header()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Pushes `value` to `buf` using the UTF-8 like variable-length coding used by FLAC frame headers.
+fn push_utf8_u32(buf: &mut Vec<u8>, value: u32) {
+    if value < 0x80 {
+        buf.push(value as u8);
+        return;
+    }
+
+    // The number of continuation bytes, each storing 6 bits.
+    let n_cont = match value {
+        0..=0x7ff => 1,
+        0x800..=0xffff => 2,
+        0x1_0000..=0x1f_ffff => 3,
+        0x20_0000..=0x3ff_ffff => 4,
+        _ => 5,
+    };
+
+    // The leading byte has a prefix of n_cont + 1 set bits.
+    let prefix = !(0xffu8 >> (n_cont + 1));
+
+    buf.push(prefix | (value >> (6 * n_cont)) as u8);
+
+    for i in (0..n_cont).rev() {
+        buf.push(0x80 | ((value >> (6 * i)) & 0x3f) as u8);
+    }
+}
@@ -0,0 +1,270 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `export` module provides frame-accurate export of a range of a track.
+//!
+//! Cutting a region out of a track requires seeking to a pre-roll position before the region,
+//! decoding, trimming the decoded audio to the exact boundaries of the region, and then encoding the
+//! result. [`export_range`] performs all of these steps, and writes the audio to an
+//! [`ExportSink`]. Sinks that write WAVE ([`WavSink`]) and FLAC ([`FlacSink`]) files are provided.
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::codecs::Decoder;
+use crate::errors::{seek_error, unsupported_error, Error, Result, SeekErrorKind};
+use crate::formats::looping::Looper;
+use crate::formats::{FormatReader, LoopRegion};
+
+mod flac;
+mod wav;
+
+pub use flac::FlacSink;
+pub use wav::WavSink;
+
+/// An `ExportSink` encodes and writes exported audio.
+///
+/// Samples are provided as full-scale signed 32-bit integers. The sink converts them to its own
+/// sample format.
+pub trait ExportSink {
+    /// Prepares the sink to write audio with the signal specification `spec`. Called once, before
+    /// the first audio buffer is written.
+    fn begin(&mut self, spec: &SignalSpec) -> Result<()>;
+
+    /// Writes the audio in `buf`.
+    fn write(&mut self, buf: &AudioBuffer<i32>) -> Result<()>;
+
+    /// Completes the export. No audio will be written after this call.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Exports the frames from `start_ts` up-to, but not including, `end_ts` of the default track of
+/// `reader` to `sink`, and returns the number of frames exported.
+///
+/// The reader is seeked to a pre-roll position before `start_ts` so that `decoder` converges before
+/// the start of the range, and the decoded audio is trimmed to exactly the frames of the range.
+/// `decoder` must be a decoder for the default track. If the range ends after the end of the track,
+/// the export ends with the track.
+///
+/// The signal specification of the track must not change within the range.
+pub fn export_range(
+    reader: &mut dyn FormatReader,
+    decoder: &mut dyn Decoder,
+    start_ts: u64,
+    end_ts: u64,
+    sink: &mut dyn ExportSink,
+) -> Result<u64> {
+    if start_ts >= end_ts {
+        return seek_error(SeekErrorKind::OutOfRange);
+    }
+
+    let track = match reader.default_track() {
+        Some(track) => track,
+        _ => return unsupported_error("core (export): no track to export"),
+    };
+
+    let mut looper = Looper::<i32>::new(track, LoopRegion::new(start_ts, end_ts, 1));
+
+    let mut spec = None;
+    let mut n_frames = 0;
+
+    loop {
+        let buf = match looper.next_buffer(reader, decoder) {
+            Ok(buf) => buf,
+            // The end of the range, or the track, was reached.
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        };
+
+        match spec {
+            None => {
+                sink.begin(buf.spec())?;
+                spec = Some(*buf.spec());
+            }
+            Some(spec) if spec != *buf.spec() => {
+                return unsupported_error("core (export): signal specification changed");
+            }
+            _ => (),
+        }
+
+        sink.write(buf)?;
+
+        n_frames += buf.frames() as u64;
+    }
+
+    // If no audio was decoded, then the range is outside the track.
+    if spec.is_none() {
+        return seek_error(SeekErrorKind::OutOfRange);
+    }
+
+    sink.finish()?;
+
+    Ok(n_frames)
+}
+
+/// Converts a full-scale 32-bit sample to a sample with `bits_per_sample` bits by truncation.
+#[inline(always)]
+fn requantize(sample: i32, bits_per_sample: u32) -> i32 {
+    sample >> (32 - bits_per_sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{export_range, WavSink};
+    use crate::audio::SignalSpec;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+    use crate::codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions};
+    use crate::codecs::{FinalizeResult, CODEC_TYPE_PCM_S32LE};
+    use crate::errors::{end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+    use crate::support_codec;
+
+    /// The number of frames in a test packet.
+    const PACKET_LEN: u64 = 10;
+
+    /// A reader of 10 packets, each containing `PACKET_LEN` frames, that can only seek to a packet
+    /// boundary.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        next_ts: u64,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+            let required_ts = match to {
+                SeekTo::TimeStamp { ts, .. } => ts,
+                _ => unreachable!(),
+            };
+
+            self.next_ts = required_ts - required_ts % PACKET_LEN;
+
+            Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_ts })
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            if self.next_ts >= 10 * PACKET_LEN {
+                return end_of_stream_error();
+            }
+
+            let packet = Packet::new_from_slice(0, self.next_ts, PACKET_LEN, &[]);
+            self.next_ts += PACKET_LEN;
+            Ok(packet)
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    /// A decoder that outputs the timestamp of each frame, scaled to the 16 most significant bits,
+    /// as its sample value.
+    struct TestDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<i32>,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT);
+            Ok(TestDecoder { params: params.clone(), buf: AudioBuffer::new(PACKET_LEN, spec) })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_S32LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            self.buf.clear();
+            self.buf.render_reserved(Some(packet.dur() as usize));
+
+            for (i, s) in self.buf.chan_mut(0).iter_mut().enumerate() {
+                *s = ((packet.ts() + i as u64) as i32) << 16;
+            }
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    /// Exports the range from `start_ts` to `end_ts` to a 16-bit WAVE file.
+    fn export_wav(start_ts: u64, end_ts: u64) -> Result<(u64, Vec<u8>)> {
+        let mut params = CodecParameters::new();
+        params.with_max_frames_per_packet(PACKET_LEN);
+
+        let mut reader = TestReader {
+            tracks: vec![Track::new(0, params.clone())],
+            metadata: Default::default(),
+            next_ts: 0,
+        };
+        let mut decoder = TestDecoder::try_new(&params, &Default::default()).unwrap();
+
+        let mut sink = WavSink::new(Cursor::new(Vec::new()), 16);
+
+        let n_frames = export_range(&mut reader, &mut decoder, start_ts, end_ts, &mut sink)?;
+
+        Ok((n_frames, sink.into_inner().into_inner()))
+    }
+
+    #[test]
+    fn verify_export_range_wav() {
+        let (n_frames, wav) = export_wav(23, 47).unwrap();
+        assert_eq!(n_frames, 24);
+
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav[4..8], (36u32 + 48).to_le_bytes());
+        assert_eq!(wav[22..24], 1u16.to_le_bytes());
+        assert_eq!(wav[24..28], 8_000u32.to_le_bytes());
+        assert_eq!(wav[34..36], 16u16.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[40..44], 48u32.to_le_bytes());
+
+        let samples: Vec<i16> =
+            wav[44..].chunks_exact(2).map(|s| i16::from_le_bytes([s[0], s[1]])).collect();
+
+        assert_eq!(samples, (23..47).collect::<Vec<_>>());
+
+        // The range ends after the end of the track.
+        assert_eq!(export_wav(95, 200).unwrap().0, 5);
+
+        // The range is empty, or outside the track.
+        assert!(export_wav(47, 47).is_err());
+        assert!(export_wav(100, 120).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::errors::{unsupported_error, Result};
+use crate::io::WriteBytes;
+
+use super::{requantize, ExportSink};
+
+/// The length of the RIFF header, and the "fmt " and "data" chunk headers, in bytes.
+const HEADER_LEN: u32 = 44;
+
+/// A `WavSink` writes exported audio to a WAVE file with integer PCM samples.
+pub struct WavSink<W: Write + Seek> {
+    writer: W,
+    /// The position of the start of the WAVE file in the writer.
+    start_pos: u64,
+    bits_per_sample: u32,
+    n_channels: usize,
+    /// The length of the data chunk in bytes.
+    data_len: u32,
+    /// A buffer of interleaved, encoded, samples.
+    buf: Vec<u8>,
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    /// Instantiates a new `WavSink` that writes samples with `bits_per_sample` bits, which must be
+    /// one of 8, 16, 24, or 32, to `writer`.
+    pub fn new(writer: W, bits_per_sample: u32) -> Self {
+        assert!(
+            matches!(bits_per_sample, 8 | 16 | 24 | 32),
+            "bits per sample must be 8, 16, 24, or 32"
+        );
+
+        WavSink {
+            writer,
+            start_pos: 0,
+            bits_per_sample,
+            n_channels: 0,
+            data_len: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Consumes the `WavSink`, and returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Seek> ExportSink for WavSink<W> {
+    // `Seek::stream_position` requires a newer Rust than the minimum supported version.
+    #[allow(clippy::seek_from_current)]
+    fn begin(&mut self, spec: &SignalSpec) -> Result<()> {
+        let n_channels = spec.channels.count() as u16;
+
+        if n_channels == 0 {
+            return unsupported_error("core (export): wav requires atleast one channel");
+        }
+
+        let bits_per_sample = self.bits_per_sample as u16;
+        let block_align = n_channels * (bits_per_sample / 8);
+
+        self.n_channels = usize::from(n_channels);
+        self.start_pos = self.writer.seek(SeekFrom::Current(0))?;
+
+        // The chunk lengths are updated when the export is finished.
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+
+        header.write_buf(b"RIFF")?;
+        header.write_u32(HEADER_LEN - 8)?;
+        header.write_buf(b"WAVE")?;
+        header.write_buf(b"fmt ")?;
+        header.write_u32(16)?;
+        // WAVE_FORMAT_PCM
+        header.write_u16(1)?;
+        header.write_u16(n_channels)?;
+        header.write_u32(spec.rate)?;
+        header.write_u32(spec.rate * u32::from(block_align))?;
+        header.write_u16(block_align)?;
+        header.write_u16(bits_per_sample)?;
+        header.write_buf(b"data")?;
+        header.write_u32(0)?;
+
+        self.writer.write_all(&header)?;
+
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &AudioBuffer<i32>) -> Result<()> {
+        self.buf.clear();
+
+        for i in 0..buf.frames() {
+            for ch in 0..self.n_channels {
+                let sample = requantize(buf.chan(ch)[i], self.bits_per_sample);
+
+                match self.bits_per_sample {
+                    // 8-bit samples are unsigned.
+                    8 => self.buf.write_u8((sample + 128) as u8)?,
+                    16 => self.buf.write_i16(sample as i16)?,
+                    24 => self.buf.write_i24(sample)?,
+                    _ => self.buf.write_i32(sample)?,
+                }
+            }
+        }
+
+        self.writer.write_all(&self.buf)?;
+
+        // The data chunk length saturates if the file grows beyond what a WAVE file can describe.
+        self.data_len = self.data_len.saturating_add(self.buf.len() as u32);
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // The data chunk must be padded to an even length.
+        let pad_len = self.data_len & 1;
+
+        if pad_len != 0 {
+            self.writer.write_all(&[0])?;
+        }
+
+        let riff_len = (HEADER_LEN - 8).saturating_add(self.data_len).saturating_add(pad_len);
+
+        let w = &mut self.writer;
+
+        w.seek(SeekFrom::Start(self.start_pos + 4))?;
+        w.write_all(&riff_len.to_le_bytes())?;
+        w.seek(SeekFrom::Start(self.start_pos + u64::from(HEADER_LEN) - 4))?;
+        w.write_all(&self.data_len.to_le_bytes())?;
+        w.seek(SeekFrom::End(0))?;
+        w.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `async_reader` module provides an asynchronous packet reading front-end.
+//!
+//! An [`AsyncFormatReader`] reads the bytes of a media asynchronously with an
+//! [`AsyncSource`], and demuxes them with a synchronous `FormatReader`.
+//! Since demuxing and decoding are not I/O bound, packets may then be decoded with a `Decoder` as
+//! usual without blocking the executor.
+//!
+//! Any asynchronous reader implementing `futures_io::AsyncRead` may be used. Readers of other
+//! runtimes, such as Tokio, may be adapted using their compatibility layers.
+//!
+//! A `FormatReader` is not transactional: if a read fails part way through a packet, the bytes
+//! of the packet read so far are lost. Therefore, the read-ahead length of the [`AsyncSource`]
+//! must exceed the largest packet, or any other structure, the format reader reads at once. If
+//! the format reader runs out of buffered bytes, the `AsyncFormatReader` fails with a
+//! `LimitError`, and can no longer be used.
+
+use std::io;
+
+use futures_io::AsyncRead;
+
+use crate::errors::{limit_error, Error, Result};
+use crate::formats::{FormatOptions, FormatReader, Packet};
+use crate::io::{AsyncSource, MediaSourceStream};
+use crate::meta::MetadataOptions;
+use crate::probe::{Hint, Probe, ProbedMetadata};
+
+/// `AsyncFormatReader` reads packets from an asynchronous source.
+pub struct AsyncFormatReader<R> {
+    source: AsyncSource<R>,
+    reader: Box<dyn FormatReader>,
+    is_failed: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncFormatReader<R> {
+    /// Probes the format of `source` and instantiates an `AsyncFormatReader` for it. The metadata
+    /// read while probing is returned alongside the reader.
+    pub async fn probe(
+        mut source: AsyncSource<R>,
+        probe: &Probe,
+        hint: &Hint,
+        format_opts: &FormatOptions,
+        metadata_opts: &MetadataOptions,
+    ) -> Result<(Self, ProbedMetadata)> {
+        source.fill().await?;
+
+        let mss = MediaSourceStream::new(source.media_source(), Default::default());
+
+        let probed = probe.format(hint, mss, format_opts, metadata_opts)?;
+
+        Ok((AsyncFormatReader { source, reader: probed.format, is_failed: false }, probed.metadata))
+    }
+
+    /// Instantiates an `AsyncFormatReader` that reads `source` with the format reader `F`.
+    pub async fn try_new<F>(mut source: AsyncSource<R>, format_opts: &FormatOptions) -> Result<Self>
+    where
+        F: FormatReader + 'static,
+    {
+        source.fill().await?;
+
+        let mss = MediaSourceStream::new(source.media_source(), Default::default());
+
+        let reader = Box::new(F::try_new(mss, format_opts)?);
+
+        Ok(AsyncFormatReader { source, reader, is_failed: false })
+    }
+
+    /// Gets a reference to the synchronous format reader, for example, to get its tracks.
+    pub fn format(&self) -> &dyn FormatReader {
+        self.reader.as_ref()
+    }
+
+    /// Gets a mutable reference to the synchronous format reader, for example, to read its
+    /// metadata.
+    ///
+    /// Calls that read from the source only read the bytes buffered by the last call to
+    /// [`AsyncFormatReader::next_packet`].
+    pub fn format_mut(&mut self) -> &mut dyn FormatReader {
+        self.reader.as_mut()
+    }
+
+    /// Reads the next packet.
+    ///
+    /// If the packet is larger than the read-ahead length of the source, a `LimitError` is
+    /// returned. Since part of the packet was consumed, the error is not retryable, and all
+    /// subsequent calls return the same error.
+    pub async fn next_packet(&mut self) -> Result<Packet> {
+        if self.is_failed {
+            return read_ahead_exceeded();
+        }
+
+        self.source.fill().await?;
+
+        match self.reader.next_packet() {
+            Err(Error::IoError(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                self.is_failed = true;
+                read_ahead_exceeded()
+            }
+            result => result,
+        }
+    }
+
+    /// Consumes the `AsyncFormatReader` and returns the synchronous format reader.
+    pub fn into_inner(self) -> Box<dyn FormatReader> {
+        self.reader
+    }
+}
+
+fn read_ahead_exceeded<T>() -> Result<T> {
+    limit_error("async reader: the read-ahead length was exceeded")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncFormatReader;
+    use crate::codecs::CodecParameters;
+    use crate::errors::{unsupported_error, Error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::async_source::tests::{block_on, SlowReader};
+    use crate::io::{AsyncSource, MediaSourceStream, ReadBytes};
+    use crate::meta::{Metadata, MetadataLog};
+
+    /// A reader of packets prefixed by their 1 byte length.
+    struct TestReader {
+        mss: MediaSourceStream,
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(mss: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            let tracks = vec![Track::new(0, CodecParameters::new())];
+            Ok(TestReader { mss, tracks, metadata: Default::default() })
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, _: SeekTo) -> Result<SeekedTo> {
+            unsupported_error("test")
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            let len = self.mss.read_byte()?;
+            let buf = self.mss.read_boxed_slice_exact(usize::from(len))?;
+            Ok(Packet::new_from_boxed_slice(0, 0, 1, buf))
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            self.mss
+        }
+    }
+
+    #[test]
+    fn verify_async_format_reader() {
+        let data = vec![2, 0xa, 0xb, 0, 3, 0xc, 0xd, 0xe];
+
+        let source = AsyncSource::new(SlowReader { data, pos: 0, is_pending: false }, 4);
+
+        let mut reader =
+            block_on(AsyncFormatReader::try_new::<TestReader>(source, &Default::default()))
+                .unwrap();
+
+        assert_eq!(reader.format().tracks().len(), 1);
+
+        let mut packets = Vec::new();
+
+        let err = loop {
+            match block_on(reader.next_packet()) {
+                Ok(packet) => packets.push(packet.buf().to_vec()),
+                Err(err) => break err,
+            }
+        };
+
+        assert_eq!(packets, [vec![0xa, 0xb], vec![], vec![0xc, 0xd, 0xe]]);
+        assert!(
+            matches!(err, Error::IoError(err) if err.kind() == std::io::ErrorKind::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn verify_async_format_reader_large_packet() {
+        // The second packet is larger than the read-ahead length.
+        let data = vec![2, 0xa, 0xb, 6, 1, 2, 3, 4, 5, 6, 1, 0xc];
+
+        let source = AsyncSource::new(SlowReader { data, pos: 0, is_pending: false }, 4);
+
+        let mut reader =
+            block_on(AsyncFormatReader::try_new::<TestReader>(source, &Default::default()))
+                .unwrap();
+
+        assert_eq!(block_on(reader.next_packet()).unwrap().buf(), &[0xa, 0xb]);
+
+        // The reader fails instead of returning a retryable error, since part of the packet was
+        // consumed.
+        match block_on(reader.next_packet()) {
+            Err(err) => assert!(matches!(err, Error::LimitError(_)) && !err.is_retryable()),
+            Ok(_) => panic!("expected an error"),
+        }
+
+        // It does not recover.
+        assert!(matches!(block_on(reader.next_packet()), Err(Error::LimitError(_))));
+    }
+}
@@ -0,0 +1,321 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `cache` module provides a serializable cache of the results of analyzing a media stream.
+//!
+//! Determining the exact duration of, or building a seek index for, some media streams (e.g., MP3
+//! files without a Xing/Info tag) requires scanning the entire stream. For large libraries this is
+//! expensive. An [`AnalysisCache`] stores the results of such scans, along with other per-file
+//! analysis results, so that applications may persist it alongside each file and provide it to
+//! the `FormatReader` when the file is reopened. When probing, the cache is provided via
+//! [`Hint::with_analysis_cache`](crate::probe::Hint::with_analysis_cache). Readers that can use a
+//! cache may also provide a constructor that accepts one (e.g.,
+//! `MpaReader::try_new_with_analysis_cache`).
+
+use crate::errors::{decode_error, Result};
+use crate::io::{BufReader, ReadBytes};
+use crate::meta::{Tag, Value};
+
+use super::util::SeekPoint;
+
+/// The magic bytes at the start of a serialized `AnalysisCache`.
+const CACHE_MAGIC: [u8; 4] = *b"SyAC";
+
+/// The version of the serialized `AnalysisCache` format.
+const CACHE_VERSION: u8 = 1;
+
+const FLAG_BYTE_LEN: u8 = 0x01;
+const FLAG_N_FRAMES: u8 = 0x02;
+const FLAG_DELAY: u8 = 0x04;
+const FLAG_PADDING: u8 = 0x08;
+const FLAG_LOUDNESS: u8 = 0x10;
+const FLAG_TAGS_HASH: u8 = 0x20;
+
+/// The results of a loudness analysis.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Loudness {
+    /// The integrated loudness in LUFS.
+    pub integrated_lufs: f32,
+    /// The peak absolute sample value, where 1.0 is full-scale.
+    pub peak: f32,
+}
+
+/// An `AnalysisCache` stores the results of analyzing the default track of a media stream.
+///
+/// A `FormatReader` may fill in some of these fields via
+/// [`FormatReader::analysis_cache`](super::FormatReader::analysis_cache). The remaining fields,
+/// such as `loudness`, are produced by the application.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnalysisCache {
+    /// The total length of the media source in bytes when the cache was created. Used to detect if
+    /// the media source was modified.
+    pub byte_len: Option<u64>,
+    /// The total number of frames in the track, including any encoder delay and padding.
+    pub n_frames: Option<u64>,
+    /// The encoder delay in frames.
+    pub delay: Option<u32>,
+    /// The encoder padding in frames.
+    pub padding: Option<u32>,
+    /// Seek points for the track. The interpretation of the byte offset of each point is specific
+    /// to the `FormatReader` that produced it.
+    pub seek_points: Vec<SeekPoint>,
+    /// The loudness of the track.
+    pub loudness: Option<Loudness>,
+    /// A hash of the tags of the media, see [`AnalysisCache::hash_tags`].
+    pub tags_hash: Option<u64>,
+}
+
+impl AnalysisCache {
+    /// Computes a stable hash of a list of tags.
+    ///
+    /// Only the key and the raw value of each tag are hashed. The hash does not depend on how a
+    /// value is formatted, and is stable across platforms and versions of Symphonia. It may
+    /// therefore be persisted to detect if the tags of a media file were changed.
+    pub fn hash_tags(tags: &[Tag]) -> u64 {
+        // 64-bit FNV-1a.
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+
+        let mut update = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        // Variable length fields are prefixed with their length so that the boundaries between
+        // fields are unambiguous.
+        for tag in tags {
+            update(&(tag.key.len() as u64).to_le_bytes());
+            update(tag.key.as_bytes());
+
+            match tag.value {
+                Value::Binary(ref buf) => {
+                    update(&[0]);
+                    update(&(buf.len() as u64).to_le_bytes());
+                    update(buf);
+                }
+                Value::Boolean(value) => update(&[1, u8::from(value)]),
+                Value::Flag => update(&[2]),
+                Value::Float(value) => {
+                    update(&[3]);
+                    update(&value.to_bits().to_le_bytes());
+                }
+                Value::SignedInt(value) => {
+                    update(&[4]);
+                    update(&value.to_le_bytes());
+                }
+                Value::String(ref value) => {
+                    update(&[5]);
+                    update(&(value.len() as u64).to_le_bytes());
+                    update(value.as_bytes());
+                }
+                Value::UnsignedInt(value) => {
+                    update(&[6]);
+                    update(&value.to_le_bytes());
+                }
+            }
+        }
+
+        hash
+    }
+
+    /// Returns true if the cache may be used for a media source of length `byte_len`.
+    ///
+    /// If either length is unknown, the cache is assumed to be valid.
+    pub fn is_valid_for(&self, byte_len: Option<u64>) -> bool {
+        match (self.byte_len, byte_len) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => true,
+        }
+    }
+
+    /// Serializes the cache into a compact binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(48 + 20 * self.seek_points.len());
+
+        let mut flags = 0;
+
+        if self.byte_len.is_some() {
+            flags |= FLAG_BYTE_LEN;
+        }
+        if self.n_frames.is_some() {
+            flags |= FLAG_N_FRAMES;
+        }
+        if self.delay.is_some() {
+            flags |= FLAG_DELAY;
+        }
+        if self.padding.is_some() {
+            flags |= FLAG_PADDING;
+        }
+        if self.loudness.is_some() {
+            flags |= FLAG_LOUDNESS;
+        }
+        if self.tags_hash.is_some() {
+            flags |= FLAG_TAGS_HASH;
+        }
+
+        buf.extend_from_slice(&CACHE_MAGIC);
+        buf.push(CACHE_VERSION);
+        buf.push(flags);
+
+        if let Some(byte_len) = self.byte_len {
+            buf.extend_from_slice(&byte_len.to_le_bytes());
+        }
+        if let Some(n_frames) = self.n_frames {
+            buf.extend_from_slice(&n_frames.to_le_bytes());
+        }
+        if let Some(delay) = self.delay {
+            buf.extend_from_slice(&delay.to_le_bytes());
+        }
+        if let Some(padding) = self.padding {
+            buf.extend_from_slice(&padding.to_le_bytes());
+        }
+        if let Some(loudness) = self.loudness {
+            buf.extend_from_slice(&loudness.integrated_lufs.to_le_bytes());
+            buf.extend_from_slice(&loudness.peak.to_le_bytes());
+        }
+        if let Some(tags_hash) = self.tags_hash {
+            buf.extend_from_slice(&tags_hash.to_le_bytes());
+        }
+
+        buf.extend_from_slice(&(self.seek_points.len() as u32).to_le_bytes());
+
+        for point in &self.seek_points {
+            buf.extend_from_slice(&point.frame_ts.to_le_bytes());
+            buf.extend_from_slice(&point.byte_offset.to_le_bytes());
+            buf.extend_from_slice(&point.n_frames.to_le_bytes());
+        }
+
+        buf
+    }
+
+    /// Deserializes a cache previously serialized with [`AnalysisCache::to_bytes`].
+    pub fn from_bytes(buf: &[u8]) -> Result<AnalysisCache> {
+        let mut reader = BufReader::new(buf);
+
+        let mut magic = [0; 4];
+        reader.read_buf_exact(&mut magic)?;
+
+        if magic != CACHE_MAGIC {
+            return decode_error("core (cache): invalid analysis cache magic");
+        }
+
+        if reader.read_u8()? != CACHE_VERSION {
+            return decode_error("core (cache): unsupported analysis cache version");
+        }
+
+        let flags = reader.read_u8()?;
+
+        let mut cache = AnalysisCache::default();
+
+        if flags & FLAG_BYTE_LEN != 0 {
+            cache.byte_len = Some(reader.read_u64()?);
+        }
+        if flags & FLAG_N_FRAMES != 0 {
+            cache.n_frames = Some(reader.read_u64()?);
+        }
+        if flags & FLAG_DELAY != 0 {
+            cache.delay = Some(reader.read_u32()?);
+        }
+        if flags & FLAG_PADDING != 0 {
+            cache.padding = Some(reader.read_u32()?);
+        }
+        if flags & FLAG_LOUDNESS != 0 {
+            let integrated_lufs = reader.read_f32()?;
+            let peak = reader.read_f32()?;
+            cache.loudness = Some(Loudness { integrated_lufs, peak });
+        }
+        if flags & FLAG_TAGS_HASH != 0 {
+            cache.tags_hash = Some(reader.read_u64()?);
+        }
+
+        let n_seek_points = reader.read_u32()? as usize;
+
+        // Each seek point is 20 bytes. Validate the count before allocating.
+        if n_seek_points > buf.len() / 20 {
+            return decode_error("core (cache): invalid seek point count");
+        }
+
+        cache.seek_points.reserve_exact(n_seek_points);
+
+        for _ in 0..n_seek_points {
+            let frame_ts = reader.read_u64()?;
+            let byte_offset = reader.read_u64()?;
+            let n_frames = reader.read_u32()?;
+            cache.seek_points.push(SeekPoint { frame_ts, byte_offset, n_frames });
+        }
+
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnalysisCache, Loudness};
+    use crate::formats::util::SeekPoint;
+    use crate::meta::{Tag, Value};
+
+    #[test]
+    fn verify_analysis_cache_round_trip() {
+        let cache = AnalysisCache {
+            byte_len: Some(12_345_678),
+            n_frames: Some(44_100 * 300),
+            delay: Some(576),
+            padding: None,
+            seek_points: vec![
+                SeekPoint { frame_ts: 0, byte_offset: 0, n_frames: 1152 },
+                SeekPoint { frame_ts: 882_000, byte_offset: 320_000, n_frames: 1152 },
+            ],
+            loudness: Some(Loudness { integrated_lufs: -14.5, peak: 0.98 }),
+            tags_hash: Some(0xdead_beef),
+        };
+
+        let bytes = cache.to_bytes();
+        assert_eq!(AnalysisCache::from_bytes(&bytes).unwrap(), cache);
+
+        // Empty caches are also valid.
+        let empty = AnalysisCache::default();
+        assert_eq!(AnalysisCache::from_bytes(&empty.to_bytes()).unwrap(), empty);
+
+        // Truncated or corrupt caches are rejected.
+        assert!(AnalysisCache::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(AnalysisCache::from_bytes(b"SyAX\x01\x00\x00\x00\x00\x00").is_err());
+    }
+
+    #[test]
+    fn verify_analysis_cache_validity() {
+        let cache = AnalysisCache { byte_len: Some(100), ..Default::default() };
+
+        assert!(cache.is_valid_for(Some(100)));
+        assert!(cache.is_valid_for(None));
+        assert!(!cache.is_valid_for(Some(101)));
+    }
+
+    #[test]
+    fn verify_hash_tags() {
+        let a = [Tag::new(None, "TITLE", Value::from("Song"))];
+        let b = [Tag::new(None, "TITLE", Value::from("Song 2"))];
+        let c = [Tag::new(None, "TITL", Value::from("ESong"))];
+
+        assert_eq!(AnalysisCache::hash_tags(&a), AnalysisCache::hash_tags(&a.clone()));
+        assert_ne!(AnalysisCache::hash_tags(&a), AnalysisCache::hash_tags(&b));
+        assert_ne!(AnalysisCache::hash_tags(&a), AnalysisCache::hash_tags(&c));
+        assert_eq!(AnalysisCache::hash_tags(&[]), 0xcbf2_9ce4_8422_2325);
+
+        // Values that format identically, but are of different types, do not collide.
+        let d = [Tag::new(None, "TRACK", Value::from("1"))];
+        let e = [Tag::new(None, "TRACK", Value::UnsignedInt(1))];
+
+        assert_ne!(AnalysisCache::hash_tags(&d), AnalysisCache::hash_tags(&e));
+
+        // The hash must never change since it may be persisted.
+        assert_eq!(AnalysisCache::hash_tags(&a), 0x7f34_1247_39c9_a31c);
+    }
+}
@@ -0,0 +1,164 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `chunks` module provides an extension point for chunks that are not recognized by a
+//! chunk-based `FormatReader` (e.g., RIFF WAVE or AIFF).
+//!
+//! By default, unrecognized chunks are skipped. Applications may instead consume proprietary chunks
+//! (e.g., DAW session data) by registering a [`ChunkHandler`] for a chunk ID, or by enabling raw
+//! chunk passthrough, in an [`UnknownChunkOptions`]. Chunk-based `FormatReader`s that support
+//! unknown chunks provide a `try_new_with_unknown_chunks` constructor that accepts these options.
+//! Readers instantiated by a [`Probe`](crate::probe::Probe) skip all unrecognized chunks.
+//! Passed through chunks are returned by
+//! [`FormatReader::unknown_chunks`](super::FormatReader::unknown_chunks).
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::errors::Result;
+
+/// A chunk that was not recognized by a `FormatReader`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawChunk {
+    /// The 4 byte chunk ID.
+    pub id: [u8; 4],
+    /// The position of the chunk header, in bytes, from the start of the media source.
+    pub pos: u64,
+    /// The chunk data, excluding the chunk header and any padding.
+    pub data: Box<[u8]>,
+}
+
+/// A `ChunkHandler` consumes chunks that are not recognized by a `FormatReader`.
+pub trait ChunkHandler: Send + Sync {
+    /// Handles an unrecognized chunk. If an error is returned, the `FormatReader` will return it.
+    fn handle(&self, chunk: &RawChunk) -> Result<()>;
+}
+
+impl<F> ChunkHandler for F
+where
+    F: Fn(&RawChunk) -> Result<()> + Send + Sync,
+{
+    fn handle(&self, chunk: &RawChunk) -> Result<()> {
+        self(chunk)
+    }
+}
+
+/// `UnknownChunkOptions` selects how a `FormatReader` treats chunks that it does not recognize.
+#[derive(Clone)]
+pub struct UnknownChunkOptions {
+    handlers: Vec<([u8; 4], Arc<dyn ChunkHandler>)>,
+    passthrough: bool,
+    max_len: u32,
+}
+
+impl UnknownChunkOptions {
+    /// The default maximum length of a chunk that will be read.
+    pub const DEFAULT_MAX_LEN: u32 = 16 * 1024 * 1024;
+
+    /// Instantiate a new `UnknownChunkOptions` that skips all unrecognized chunks.
+    pub fn new() -> Self {
+        UnknownChunkOptions {
+            handlers: Vec::new(),
+            passthrough: false,
+            max_len: UnknownChunkOptions::DEFAULT_MAX_LEN,
+        }
+    }
+
+    /// Register a handler for chunks with ID `id`. Multiple handlers may be registered for the
+    /// same ID, and will be called in the order they were registered.
+    pub fn register<H: ChunkHandler + 'static>(&mut self, id: [u8; 4], handler: H) -> &mut Self {
+        self.handlers.push((id, Arc::new(handler)));
+        self
+    }
+
+    /// If enabled, all unrecognized chunks are retained by the `FormatReader` and returned by
+    /// `FormatReader::unknown_chunks`.
+    pub fn passthrough(&mut self, enable: bool) -> &mut Self {
+        self.passthrough = enable;
+        self
+    }
+
+    /// Sets the maximum length of a chunk that will be read. Longer chunks are skipped. Since the
+    /// entire chunk is read into memory, this guards against excessive allocations.
+    pub fn max_len(&mut self, max_len: u32) -> &mut Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns true if unrecognized chunks are retained by the `FormatReader`.
+    pub fn is_passthrough(&self) -> bool {
+        self.passthrough
+    }
+
+    /// Returns true if a chunk with ID `id` and length `len` should be read instead of skipped.
+    pub fn wants(&self, id: [u8; 4], len: u32) -> bool {
+        len <= self.max_len
+            && (self.passthrough || self.handlers.iter().any(|(handled, _)| *handled == id))
+    }
+
+    /// Calls all handlers registered for the ID of `chunk`.
+    pub fn handle(&self, chunk: &RawChunk) -> Result<()> {
+        for (_, handler) in self.handlers.iter().filter(|(id, _)| *id == chunk.id) {
+            handler.handle(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for UnknownChunkOptions {
+    fn default() -> Self {
+        UnknownChunkOptions::new()
+    }
+}
+
+impl fmt::Debug for UnknownChunkOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<_> = self.handlers.iter().map(|(id, _)| String::from_utf8_lossy(id)).collect();
+
+        f.debug_struct("UnknownChunkOptions")
+            .field("handlers", &ids)
+            .field("passthrough", &self.passthrough)
+            .field("max_len", &self.max_len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{RawChunk, UnknownChunkOptions};
+
+    #[test]
+    fn verify_unknown_chunk_options() {
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let mut options = UnknownChunkOptions::new();
+
+        let counter = count.clone();
+        options.register(*b"DAW ", move |chunk: &RawChunk| {
+            counter.fetch_add(chunk.data.len(), Ordering::SeqCst);
+            Ok(())
+        });
+        options.max_len(8);
+
+        assert!(options.wants(*b"DAW ", 8));
+        assert!(!options.wants(*b"DAW ", 9));
+        assert!(!options.wants(*b"junk", 4));
+
+        options.passthrough(true);
+        assert!(options.wants(*b"junk", 4));
+
+        let chunk = RawChunk { id: *b"DAW ", pos: 0, data: vec![0; 3].into_boxed_slice() };
+
+        options.handle(&chunk).unwrap();
+        options.handle(&RawChunk { id: *b"junk", ..chunk.clone() }).unwrap();
+
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+}
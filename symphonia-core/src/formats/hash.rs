@@ -0,0 +1,205 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `hash` module provides hashing of packet payloads.
+//!
+//! The same audio stored in files with different metadata, or even different containers, has the
+//! same packet payloads. Hashing the payloads allows a deduplication or caching layer to identify
+//! identical audio content without decoding it.
+//!
+//! Readers instantiated by a [`Probe`](crate::probe::Probe) hash every packet if
+//! [`FormatOptions::enable_packet_hashes`] is set. Readers that are instantiated directly may be
+//! wrapped with a [`HashedReader`] instead.
+
+use std::collections::HashMap;
+
+use crate::checksum::Crc32;
+use crate::errors::{unsupported_error, Result};
+use crate::formats::chunks::RawChunk;
+use crate::formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track};
+use crate::io::{MediaSourceStream, Monitor};
+use crate::meta::Metadata;
+
+use super::cache::AnalysisCache;
+
+/// Computes the hash (CRC-32) of a packet payload.
+pub fn packet_hash(buf: &[u8]) -> u32 {
+    let mut crc = Crc32::new(0xffff_ffff);
+    crc.process_buf_bytes(buf);
+    crc.crc()
+}
+
+/// A `HashedReader` wraps a `FormatReader` and sets the hash of every packet it reads.
+///
+/// In addition to the hash of each packet, a rolling hash of the payloads of all packets read from
+/// each track is maintained. The rolling hash is restarted after a seek.
+pub struct HashedReader {
+    inner: Box<dyn FormatReader>,
+    /// The rolling hash of each track.
+    track_hashes: HashMap<u32, Crc32>,
+}
+
+impl HashedReader {
+    /// Instantiates a new `HashedReader` that hashes the packets read by `inner`.
+    pub fn new(inner: Box<dyn FormatReader>) -> Self {
+        HashedReader { inner, track_hashes: Default::default() }
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn inner(&self) -> &dyn FormatReader {
+        self.inner.as_ref()
+    }
+
+    /// Gets the rolling hash of the payloads of all packets read from the track with `track_id`
+    /// since the reader was instantiated, or last seeked.
+    pub fn track_hash(&self, track_id: u32) -> Option<u32> {
+        self.track_hashes.get(&track_id).map(|crc| crc.crc())
+    }
+}
+
+impl FormatReader for HashedReader {
+    fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+        unsupported_error("core (hash): use HashedReader::new to wrap a reader")
+    }
+
+    fn cues(&self) -> &[Cue] {
+        self.inner.cues()
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.inner.metadata()
+    }
+
+    fn seek(&mut self, mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        self.track_hashes.clear();
+        self.inner.seek(mode, to)
+    }
+
+    fn tracks(&self) -> &[Track] {
+        self.inner.tracks()
+    }
+
+    fn default_track(&self) -> Option<&Track> {
+        self.inner.default_track()
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let mut packet = self.inner.next_packet()?;
+
+        packet.hash = Some(packet_hash(packet.buf()));
+
+        self.track_hashes
+            .entry(packet.track_id())
+            .or_insert_with(|| Crc32::new(0xffff_ffff))
+            .process_buf_bytes(packet.buf());
+
+        Ok(packet)
+    }
+
+    fn analysis_cache(&self) -> Option<AnalysisCache> {
+        self.inner.analysis_cache()
+    }
+
+    fn unknown_chunks(&self) -> &[RawChunk] {
+        self.inner.unknown_chunks()
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{packet_hash, HashedReader};
+    use crate::codecs::CodecParameters;
+    use crate::errors::{end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+
+    /// A reader of packets with fixed payloads.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        payloads: Vec<&'static [u8]>,
+        next: usize,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, _: SeekTo) -> Result<SeekedTo> {
+            self.next = 0;
+            Ok(SeekedTo { track_id: 0, required_ts: 0, actual_ts: 0 })
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            match self.payloads.get(self.next) {
+                Some(payload) => {
+                    self.next += 1;
+                    Ok(Packet::new_from_slice(0, 0, 1, payload))
+                }
+                None => end_of_stream_error(),
+            }
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    #[test]
+    fn verify_packet_hash() {
+        // The CRC-32/MPEG-2 check value.
+        assert_eq!(packet_hash(b"123456789"), 0x0376_e6e7);
+    }
+
+    #[test]
+    fn verify_hashed_reader() {
+        let inner = TestReader {
+            tracks: vec![Track::new(0, CodecParameters::new())],
+            metadata: Default::default(),
+            payloads: vec![b"12345", b"6789"],
+            next: 0,
+        };
+
+        let mut reader = HashedReader::new(Box::new(inner));
+
+        assert_eq!(reader.track_hash(0), None);
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.hash, Some(packet_hash(b"12345")));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.hash, Some(packet_hash(b"6789")));
+
+        // The rolling hash is the hash of all payloads.
+        assert_eq!(reader.track_hash(0), Some(packet_hash(b"123456789")));
+
+        // A seek restarts the rolling hash.
+        reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 0, track_id: 0 }).unwrap();
+        assert_eq!(reader.track_hash(0), None);
+    }
+}
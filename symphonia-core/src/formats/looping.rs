@@ -0,0 +1,377 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `looping` module provides sample-accurate playback of a [`LoopRegion`].
+//!
+//! A `FormatReader` can only seek to a packet boundary, and a `Decoder` may require a number of
+//! packets after a reset before its output converges (e.g., to fill overlap-add buffers). A
+//! [`Looper`] hides these details: it seeks to a pre-roll position before the start of the loop,
+//! decodes and discards the pre-roll, and trims the decoded audio so that exactly the frames of the
+//! loop are returned on every pass.
+
+use crate::audio::{AudioBuffer, Signal};
+use crate::codecs::Decoder;
+use crate::conv::FromSample;
+use crate::errors::{end_of_stream_error, Error, Result};
+use crate::sample::{i24, u24, Sample};
+
+use super::{FormatReader, LoopRegion, SeekMode, SeekTo, Track};
+
+/// A `Looper` decodes a `LoopRegion` of a track repeatedly with sample-accurate boundaries.
+///
+/// The first and last buffers of each pass are trimmed to the loop boundaries, therefore the
+/// concatenation of all buffers of a pass is exactly the audio from the start of the loop up-to,
+/// but not including, the end of the loop.
+pub struct Looper<S: Sample> {
+    track_id: u32,
+    region: LoopRegion,
+    preroll: u64,
+    /// The number of completed passes of the loop.
+    num_plays: u32,
+    /// The number of frames returned in the current pass.
+    num_frames: u64,
+    /// If true, the reader must be seeked to the start of the loop before reading the next packet.
+    needs_seek: bool,
+    buf: AudioBuffer<S>,
+}
+
+impl<S> Looper<S>
+where
+    S: Sample
+        + FromSample<u8>
+        + FromSample<u16>
+        + FromSample<u24>
+        + FromSample<u32>
+        + FromSample<i8>
+        + FromSample<i16>
+        + FromSample<i24>
+        + FromSample<i32>
+        + FromSample<f32>
+        + FromSample<f64>,
+{
+    /// Instantiate a new `Looper` for `region` of `track`.
+    ///
+    /// The pre-roll defaults to the maximum number of frames per packet of the track, if known, which
+    /// is sufficient for most decoders to converge.
+    pub fn new(track: &Track, region: LoopRegion) -> Self {
+        assert!(region.start_ts < region.end_ts, "loop region is empty");
+
+        Looper {
+            track_id: track.id,
+            region,
+            preroll: track.codec_params.max_frames_per_packet.unwrap_or(0),
+            num_plays: 0,
+            num_frames: 0,
+            needs_seek: true,
+            buf: AudioBuffer::unused(),
+        }
+    }
+
+    /// Sets the number of frames decoded and discarded before the start of the loop after every
+    /// seek.
+    pub fn with_preroll(&mut self, n_frames: u64) -> &mut Self {
+        self.preroll = n_frames;
+        self
+    }
+
+    /// Gets the loop region.
+    pub fn region(&self) -> &LoopRegion {
+        &self.region
+    }
+
+    /// Gets the number of completed passes of the loop.
+    pub fn num_plays(&self) -> u32 {
+        self.num_plays
+    }
+
+    /// Restarts playback from the start of the loop on the next call to `next_buffer`, and resets
+    /// the play count.
+    pub fn restart(&mut self) {
+        self.num_plays = 0;
+        self.num_frames = 0;
+        self.needs_seek = true;
+    }
+
+    /// Reads and decodes packets from `reader` using `decoder` until audio within the loop is
+    /// decoded, and returns it. The reader and decoder are seeked and reset as required.
+    ///
+    /// Once the loop has been played the number of times specified by the region, or if the loop
+    /// contains no audio, an end-of-stream error is returned. Decode errors are returned to the
+    /// caller, but playback may be continued by calling `next_buffer` again.
+    pub fn next_buffer(
+        &mut self,
+        reader: &mut dyn FormatReader,
+        decoder: &mut dyn Decoder,
+    ) -> Result<&AudioBuffer<S>> {
+        loop {
+            if self.needs_seek {
+                if self.region.play_count > 0 && self.num_plays >= self.region.play_count {
+                    return end_of_stream_error();
+                }
+
+                let ts = self.region.start_ts.saturating_sub(self.preroll);
+
+                reader
+                    .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts, track_id: self.track_id })?;
+                decoder.reset();
+
+                self.needs_seek = false;
+            }
+
+            let packet = match reader.next_packet() {
+                Ok(packet) => packet,
+                // The end of the stream ends the loop if it is reached before the end of the loop.
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    self.finish_play()?;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            if packet.ts() >= self.region.end_ts {
+                self.finish_play()?;
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // The output of the pre-roll is discarded, therefore so are its errors.
+                Err(Error::DecodeError(_))
+                    if packet.ts() + packet.dur() <= self.region.start_ts =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err),
+            };
+
+            let start_ts = packet.ts();
+            let end_ts = start_ts + decoded.frames() as u64;
+
+            // Skip the pre-roll.
+            if end_ts <= self.region.start_ts {
+                continue;
+            }
+
+            if self.buf.capacity() < decoded.capacity() || self.buf.spec() != decoded.spec() {
+                self.buf = decoded.make_equivalent();
+            }
+
+            decoded.convert(&mut self.buf);
+
+            // Trim the frames outside the loop.
+            let trim_start = self.region.start_ts.saturating_sub(start_ts);
+            let trim_end = end_ts.saturating_sub(self.region.end_ts);
+
+            self.buf.trim(trim_start as usize, trim_end as usize);
+
+            self.num_frames += self.buf.frames() as u64;
+
+            if end_ts >= self.region.end_ts {
+                self.finish_play()?;
+            }
+
+            return Ok(&self.buf);
+        }
+    }
+
+    /// Completes the current pass of the loop.
+    fn finish_play(&mut self) -> Result<()> {
+        // If the loop contained no audio, then it is outside the stream, and would never end.
+        if self.num_frames == 0 {
+            return end_of_stream_error();
+        }
+
+        self.num_plays += 1;
+        self.num_frames = 0;
+        self.needs_seek = true;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::Looper;
+    use crate::audio::{
+        AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec,
+    };
+    use crate::codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions};
+    use crate::codecs::{FinalizeResult, CODEC_TYPE_PCM_S32LE};
+    use crate::errors::{end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, LoopRegion, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+    use crate::support_codec;
+
+    /// The number of frames in a test packet.
+    const PACKET_LEN: u64 = 10;
+
+    /// A reader of 10 packets, each containing `PACKET_LEN` frames, that can only seek to a packet
+    /// boundary.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        next_ts: u64,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+            let required_ts = match to {
+                SeekTo::TimeStamp { ts, .. } => ts,
+                _ => unreachable!(),
+            };
+
+            self.next_ts = required_ts - required_ts % PACKET_LEN;
+
+            Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_ts })
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            if self.next_ts >= 10 * PACKET_LEN {
+                return end_of_stream_error();
+            }
+
+            let packet = Packet::new_from_slice(0, self.next_ts, PACKET_LEN, &[]);
+            self.next_ts += PACKET_LEN;
+            Ok(packet)
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    /// A decoder that outputs the timestamp of each frame as its sample value. After a reset, the
+    /// first packet is decoded to garbage, as if the decoder had not yet converged.
+    struct TestDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<i32>,
+        is_primed: bool,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT);
+
+            Ok(TestDecoder {
+                params: params.clone(),
+                buf: AudioBuffer::new(PACKET_LEN, spec),
+                is_primed: false,
+            })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_S32LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {
+            self.is_primed = false;
+        }
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            self.buf.clear();
+            self.buf.render_reserved(Some(packet.dur() as usize));
+
+            for (i, s) in self.buf.chan_mut(0).iter_mut().enumerate() {
+                *s = if self.is_primed { (packet.ts() + i as u64) as i32 } else { -1 };
+            }
+
+            self.is_primed = true;
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    /// Plays `region` until the end, and returns the concatenation of all passes.
+    fn play(region: LoopRegion, preroll: Option<u64>) -> Vec<i32> {
+        let mut params = CodecParameters::new();
+        params.with_max_frames_per_packet(PACKET_LEN);
+
+        let track = Track::new(0, params.clone());
+
+        let mut reader =
+            TestReader { tracks: vec![track.clone()], metadata: Default::default(), next_ts: 0 };
+        let mut decoder = TestDecoder::try_new(&params, &Default::default()).unwrap();
+
+        let mut looper = Looper::<i32>::new(&track, region);
+
+        if let Some(preroll) = preroll {
+            looper.with_preroll(preroll);
+        }
+
+        let mut samples = Vec::new();
+
+        while let Ok(buf) = looper.next_buffer(&mut reader, &mut decoder) {
+            samples.extend_from_slice(buf.chan(0));
+        }
+
+        assert_eq!(looper.num_plays(), region.play_count);
+
+        samples
+    }
+
+    #[test]
+    fn verify_looper_boundaries() {
+        // The loop starts and ends in the middle of a packet.
+        let expected: Vec<i32> = (23..47).collect();
+        assert_eq!(play(LoopRegion::new(23, 47, 3), None), expected.repeat(3));
+
+        // The loop is within one packet.
+        let expected: Vec<i32> = (52..55).collect();
+        assert_eq!(play(LoopRegion::new(52, 55, 2), None), expected.repeat(2));
+
+        // The loop ends at, or after, the end of the stream.
+        let expected: Vec<i32> = (95..100).collect();
+        assert_eq!(play(LoopRegion::new(95, 100, 2), None), expected.repeat(2));
+        assert_eq!(play(LoopRegion::new(95, 200, 1), None), expected);
+
+        // Without pre-roll, the start of the loop is garbage.
+        let samples = play(LoopRegion::new(23, 47, 1), Some(0));
+        assert_eq!(samples[..7], [-1; 7]);
+        assert_eq!(samples[7..], (30..47).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn verify_looper_outside_stream() {
+        assert!(play(LoopRegion::new(100, 120, 0), None).is_empty());
+    }
+}
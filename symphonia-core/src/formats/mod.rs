@@ -11,15 +11,30 @@
 use crate::codecs::CodecParameters;
 use crate::errors::Result;
 use crate::io::{BufReader, MediaSourceStream};
-use crate::meta::{Metadata, Tag};
+use crate::meta::{Limit, Metadata, Tag};
 use crate::units::{Time, TimeStamp};
 
+#[cfg(feature = "async")]
+pub mod async_reader;
+pub mod cache;
+pub mod chunks;
+pub mod hash;
+pub mod looping;
+pub mod seek_accuracy;
+pub mod splice;
+pub mod sync;
+
+use cache::AnalysisCache;
+use chunks::RawChunk;
+
 pub mod prelude {
     //! The `formats` module prelude.
 
     pub use crate::units::{Duration, TimeBase, TimeStamp};
 
-    pub use super::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track};
+    pub use super::{
+        Cue, FormatOptions, FormatReader, LoopRegion, Packet, SeekMode, SeekTo, SeekedTo, Track,
+    };
 }
 
 /// `SeekTo` specifies a position to seek to.
@@ -88,6 +103,24 @@ pub struct FormatOptions {
     /// When enabled, this option will also alter the value and interpretation of timestamps and
     /// durations such that they are relative to the non-trimmed region.
     pub enable_gapless: bool,
+    /// If the exact duration of a track is not provided by the container, scan the entire stream
+    /// during instantiation to determine it. Default: `false`.
+    ///
+    /// Only the packetization of the stream is scanned, no packets are decoded. The seek index is
+    /// also built while scanning, as if `prebuild_seek_index` was enabled. Scanning trades a longer
+    /// instantiation time, which may be significant for large or slow media sources, for an exact
+    /// duration and faster accurate seeking.
+    pub enable_full_scan: bool,
+    /// The maximum number of bytes a `FormatReader` may skip while searching for the next packet
+    /// after losing synchronization (e.g., while skipping junk between MP3 frames). If exceeded,
+    /// a `LimitError` is returned. Default: `Limit::Default`, which is demuxer specific.
+    pub limit_resync_bytes: Limit,
+    /// Set the hash of every packet's payload. Default: `false`.
+    ///
+    /// Packet hashes allow identical audio content to be identified across files without decoding
+    /// it. Readers instantiated by a [`Probe`](crate::probe::Probe) apply this option. See the
+    /// [`hash`] module for details.
+    pub enable_packet_hashes: bool,
 }
 
 impl Default for FormatOptions {
@@ -96,6 +129,9 @@ impl Default for FormatOptions {
             prebuild_seek_index: false,
             seek_index_fill_rate: 20,
             enable_gapless: false,
+            enable_full_scan: false,
+            limit_resync_bytes: Limit::Default,
+            enable_packet_hashes: false,
         }
     }
 }
@@ -131,6 +167,36 @@ pub struct CuePoint {
     pub tags: Vec<Tag>,
 }
 
+/// A `LoopRegion` is a region of a track that is intended to be played repeatedly, such as the
+/// sustain loop of an instrument sample.
+///
+/// See the [`looping`] module for sample-accurate playback of a `LoopRegion`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LoopRegion {
+    /// The timestamp of the first frame of the loop.
+    pub start_ts: u64,
+    /// The timestamp of the frame following the last frame of the loop.
+    pub end_ts: u64,
+    /// The number of times the loop should be played, or 0 if the loop should be played
+    /// indefinitely.
+    pub play_count: u32,
+}
+
+impl LoopRegion {
+    /// Instantiate a new `LoopRegion` spanning the frames from `start_ts` up-to, but not including,
+    /// `end_ts`. Panics if the region is empty.
+    pub fn new(start_ts: u64, end_ts: u64, play_count: u32) -> Self {
+        assert!(start_ts < end_ts, "loop region is empty");
+
+        LoopRegion { start_ts, end_ts, play_count }
+    }
+
+    /// Gets the duration of the loop in frames.
+    pub fn duration(&self) -> u64 {
+        self.end_ts - self.start_ts
+    }
+}
+
 /// A `Track` is an independently coded media bitstream. A media format may contain multiple tracks
 /// in one container. Each of those tracks are represented by one `Track`.
 #[derive(Clone, Debug)]
@@ -141,11 +207,13 @@ pub struct Track {
     pub codec_params: CodecParameters,
     /// The language of the track. May be unknown.
     pub language: Option<String>,
+    /// The regions of the track that are intended to be looped, if any.
+    pub loops: Vec<LoopRegion>,
 }
 
 impl Track {
     pub fn new(id: u32, codec_params: CodecParameters) -> Self {
-        Track { id, codec_params, language: None }
+        Track { id, codec_params, language: None, loops: Vec::new() }
     }
 }
 
@@ -209,6 +277,25 @@ pub trait FormatReader: Send + Sync {
     /// re-created. All other errors are unrecoverable.
     fn next_packet(&mut self) -> Result<Packet>;
 
+    /// Gets the results of analyzing the default track so far, if supported by the
+    /// `FormatReader`.
+    ///
+    /// The returned cache may be persisted by the application, and provided to a `FormatReader`
+    /// constructor that accepts one when the same media is opened again, to avoid repeating
+    /// expensive scans. The contents of the cache may improve as more of the media is read.
+    fn analysis_cache(&self) -> Option<AnalysisCache> {
+        None
+    }
+
+    /// Gets the chunks that were not recognized by the `FormatReader`, if raw chunk passthrough was
+    /// enabled when the `FormatReader` was instantiated. See the [`chunks`] module for details.
+    ///
+    /// Only chunk-based formats support passthrough. Chunks are returned in the order they were
+    /// read.
+    fn unknown_chunks(&self) -> &[RawChunk] {
+        &[]
+    }
+
     /// Destroys the `FormatReader` and returns the underlying media source stream
     fn into_inner(self: Box<Self>) -> MediaSourceStream;
 }
@@ -238,17 +325,21 @@ pub struct Packet {
     pub trim_end: u32,
     /// The packet buffer.
     pub data: Box<[u8]>,
+    /// The hash of the packet buffer, if packet hashes are enabled.
+    ///
+    /// See the [`hash`] module for details.
+    pub hash: Option<u32>,
 }
 
 impl Packet {
     /// Create a new `Packet` from a slice.
     pub fn new_from_slice(track_id: u32, ts: u64, dur: u64, buf: &[u8]) -> Self {
-        Packet { track_id, ts, dur, trim_start: 0, trim_end: 0, data: Box::from(buf) }
+        Packet { track_id, ts, dur, trim_start: 0, trim_end: 0, data: Box::from(buf), hash: None }
     }
 
     /// Create a new `Packet` from a boxed slice.
     pub fn new_from_boxed_slice(track_id: u32, ts: u64, dur: u64, data: Box<[u8]>) -> Self {
-        Packet { track_id, ts, dur, trim_start: 0, trim_end: 0, data }
+        Packet { track_id, ts, dur, trim_start: 0, trim_end: 0, data, hash: None }
     }
 
     /// Create a new `Packet` with trimming information from a slice.
@@ -260,7 +351,7 @@ impl Packet {
         trim_end: u32,
         buf: &[u8],
     ) -> Self {
-        Packet { track_id, ts, dur, trim_start, trim_end, data: Box::from(buf) }
+        Packet { track_id, ts, dur, trim_start, trim_end, data: Box::from(buf), hash: None }
     }
 
     /// Create a new `Packet` with trimming information from a boxed slice.
@@ -272,7 +363,7 @@ impl Packet {
         trim_end: u32,
         data: Box<[u8]>,
     ) -> Self {
-        Packet { track_id, ts, dur, trim_start, trim_end, data }
+        Packet { track_id, ts, dur, trim_start, trim_end, data, hash: None }
     }
 
     /// The track identifier of the track this packet belongs to.
@@ -313,13 +404,18 @@ impl Packet {
         self.trim_end
     }
 
+    /// Get the hash of the packet buffer, if packet hashes are enabled.
+    pub fn hash(&self) -> Option<u32> {
+        self.hash
+    }
+
     /// Get an immutable slice to the packet buffer.
     pub fn buf(&self) -> &[u8] {
         &self.data
     }
 
     /// Get a `BufStream` to read the packet data buffer sequentially.
-    pub fn as_buf_reader(&self) -> BufReader {
+    pub fn as_buf_reader(&self) -> BufReader<'_> {
         BufReader::new(&self.data)
     }
 }
@@ -413,6 +509,11 @@ pub mod util {
             }
         }
 
+        /// Gets all `SeekPoint`s in the index, ordered by timestamp.
+        pub fn points(&self) -> &[SeekPoint] {
+            &self.points
+        }
+
         /// Search the index to find a bounded range of bytes wherein the specified frame timestamp
         /// will be contained. If the index is empty, this function simply returns a result
         /// indicating the entire stream should be searched manually.
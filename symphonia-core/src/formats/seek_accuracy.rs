@@ -0,0 +1,494 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `seek_accuracy` module provides a self-test of the seek accuracy of a format reader and
+//! decoder.
+//!
+//! A seek is accurate if the audio decoded after seeking to a timestamp is the audio decoded at
+//! that timestamp when decoding linearly from the start of the stream. A [`SeekVerifier`] first
+//! decodes a stream linearly, then seeks to each timestamp being verified, and reports the offset,
+//! in frames, between the audio decoded after the seek and the audio decoded linearly.
+
+use crate::audio::{AudioBuffer, Signal};
+use crate::codecs::Decoder;
+use crate::errors::{unsupported_error, Error, Result};
+
+use super::{FormatReader, SeekMode, SeekTo};
+
+/// The maximum absolute difference between a sample decoded after a seek and the sample decoded
+/// linearly for the samples to be considered equal. Around 2^-17 (-102.4dB).
+const MAX_SAMPLE_DELTA: f32 = 0.00001;
+
+/// The result of verifying a seek to a timestamp.
+#[derive(Clone, Debug)]
+pub struct SeekAccuracy {
+    /// The timestamp seeked to.
+    pub ts: u64,
+    /// The timestamp the format reader reported it seeked to.
+    pub actual_ts: u64,
+    /// The offset, in frames, of the audio decoded after the seek relative to the audio decoded
+    /// linearly. A positive offset indicates the audio is from later in the stream than its
+    /// timestamp. `None` if the audio did not match at any offset up-to the maximum offset.
+    pub offset: Option<i64>,
+    /// The maximum absolute difference between a sample decoded after the seek, and the sample
+    /// decoded linearly at the same timestamp.
+    pub max_delta: f32,
+}
+
+impl SeekAccuracy {
+    /// Returns true if the audio decoded after the seek is the audio decoded linearly.
+    pub fn is_exact(&self) -> bool {
+        self.offset == Some(0)
+    }
+}
+
+/// A span of decoded audio, one plane per channel.
+#[derive(Default)]
+struct Span {
+    /// The timestamp of the first frame.
+    start: u64,
+    planes: Vec<Vec<f32>>,
+}
+
+impl Span {
+    fn new(start: u64) -> Self {
+        Span { start, planes: Vec::new() }
+    }
+
+    fn frames(&self) -> usize {
+        self.planes.first().map_or(0, |plane| plane.len())
+    }
+
+    /// Appends the frames of `buf`, whose first frame has the timestamp `ts`, that fall within the
+    /// span when the span is at most `len` frames long.
+    fn append(&mut self, buf: &AudioBuffer<f32>, ts: u64, len: usize) {
+        let end = self.start + len as u64;
+        let buf_end = ts + buf.frames() as u64;
+
+        let first = self.start.max(ts).max(self.start + self.frames() as u64);
+        let last = end.min(buf_end);
+
+        if first >= last {
+            return;
+        }
+
+        if self.planes.is_empty() {
+            self.planes = vec![Vec::new(); buf.spec().channels.count()];
+        }
+
+        let range = (first - ts) as usize..(last - ts) as usize;
+
+        for (c, plane) in self.planes.iter_mut().enumerate() {
+            plane.extend_from_slice(&buf.chan(c)[range.clone()]);
+        }
+    }
+
+    /// Gets the maximum absolute difference between the samples of `other` and the samples of this
+    /// span offset by `offset` frames. Returns `None` if this span does not cover `other`. Stops
+    /// early once the difference exceeds `limit`.
+    fn max_delta(&self, other: &Span, offset: i64, limit: f32) -> Option<f32> {
+        let start = (other.start as i64 + offset).checked_sub(self.start as i64)?;
+
+        if start < 0 || start as usize + other.frames() > self.frames() {
+            return None;
+        }
+
+        let start = start as usize;
+        let mut max_delta = 0.0f32;
+
+        for (a, b) in other.planes.iter().zip(&self.planes) {
+            for (&a, &b) in a.iter().zip(&b[start..]) {
+                max_delta = max_delta.max((a - b).abs());
+
+                if max_delta > limit {
+                    return Some(max_delta);
+                }
+            }
+        }
+
+        Some(max_delta)
+    }
+}
+
+/// A decoding instance of a stream.
+struct Instance {
+    reader: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    buf: AudioBuffer<f32>,
+}
+
+impl Instance {
+    fn new(reader: Box<dyn FormatReader>, decoder: Box<dyn Decoder>) -> Result<Self> {
+        let track_id = match reader.default_track() {
+            Some(track) => track.id,
+            None => return unsupported_error("core (seek accuracy): no track to verify"),
+        };
+
+        Ok(Instance { reader, decoder, track_id, buf: AudioBuffer::unused() })
+    }
+
+    /// Decodes the next packet of the track. Returns the timestamp of the packet, or `None` at the
+    /// end of the stream.
+    fn decode_next(&mut self) -> Result<Option<u64>> {
+        loop {
+            let packet = match self.reader.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return Ok(None);
+                }
+                Err(err) => return Err(err),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(Error::DecodeError(_)) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if self.buf.capacity() < decoded.capacity() || self.buf.spec() != decoded.spec() {
+                self.buf = decoded.make_equivalent();
+            }
+
+            decoded.convert(&mut self.buf);
+
+            return Ok(Some(packet.ts()));
+        }
+    }
+}
+
+/// A `SeekVerifier` verifies the seek accuracy of a format reader and decoder.
+///
+/// The stream is opened twice using the provided function, once to decode the stream linearly, and
+/// once to seek. The function must return a format reader, and a decoder for its default track.
+pub struct SeekVerifier<F>
+where
+    F: FnMut() -> Result<(Box<dyn FormatReader>, Box<dyn Decoder>)>,
+{
+    open: F,
+    mode: SeekMode,
+    window: usize,
+    max_offset: usize,
+}
+
+impl<F> SeekVerifier<F>
+where
+    F: FnMut() -> Result<(Box<dyn FormatReader>, Box<dyn Decoder>)>,
+{
+    /// Instantiate a new `SeekVerifier` for the stream opened by `open`.
+    pub fn new(open: F) -> Self {
+        SeekVerifier { open, mode: SeekMode::Accurate, window: 1024, max_offset: 8192 }
+    }
+
+    /// Sets the seek mode to verify. The default is accurate seeking.
+    pub fn with_mode(&mut self, mode: SeekMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the number of frames compared after each seek. The default is 1024 frames.
+    pub fn with_window(&mut self, n_frames: usize) -> &mut Self {
+        self.window = n_frames;
+        self
+    }
+
+    /// Sets the maximum offset, in frames, that will be searched for. The default is 8192 frames.
+    pub fn with_max_offset(&mut self, n_frames: usize) -> &mut Self {
+        self.max_offset = n_frames;
+        self
+    }
+
+    /// Verifies a seek to each of the timestamps, in order, and returns the accuracy of each seek.
+    pub fn verify(&mut self, timestamps: &[u64]) -> Result<Vec<SeekAccuracy>> {
+        let linear = self.decode_linear(timestamps)?;
+
+        let (reader, decoder) = (self.open)()?;
+        let mut inst = Instance::new(reader, decoder)?;
+
+        let mut results = Vec::with_capacity(timestamps.len());
+
+        for (&ts, linear) in timestamps.iter().zip(&linear) {
+            let seeked =
+                inst.reader.seek(self.mode, SeekTo::TimeStamp { ts, track_id: inst.track_id })?;
+
+            inst.decoder.reset();
+
+            // Collect the audio decoded after the seek, by the timestamps given by the reader.
+            let mut span: Option<Span> = None;
+
+            while span.as_ref().map_or(0, |span| span.frames()) < self.window {
+                let packet_ts = match inst.decode_next()? {
+                    Some(packet_ts) => packet_ts,
+                    None => break,
+                };
+
+                let span = span.get_or_insert_with(|| Span::new(ts.max(packet_ts)));
+                span.append(&inst.buf, packet_ts, self.window);
+            }
+
+            let span = span.unwrap_or_default();
+
+            results.push(self.compare(ts, seeked.actual_ts, linear, &span));
+        }
+
+        Ok(results)
+    }
+
+    /// Decodes the stream linearly, and returns the audio surrounding each timestamp. The timestamp
+    /// of each frame is given by the number of frames decoded before it.
+    fn decode_linear(&mut self, timestamps: &[u64]) -> Result<Vec<Span>> {
+        let len = self.window + 2 * self.max_offset;
+
+        let mut spans: Vec<Span> = timestamps
+            .iter()
+            .map(|&ts| Span::new(ts.saturating_sub(self.max_offset as u64)))
+            .collect();
+
+        let end = spans.iter().map(|span| span.start + len as u64).max().unwrap_or(0);
+
+        let (reader, decoder) = (self.open)()?;
+        let mut inst = Instance::new(reader, decoder)?;
+
+        let mut pos = None;
+
+        while let Some(packet_ts) = inst.decode_next()? {
+            // Only the timestamp of the first packet is used.
+            let ts = *pos.get_or_insert(packet_ts);
+
+            for span in spans.iter_mut() {
+                span.append(&inst.buf, ts, len);
+            }
+
+            pos = Some(ts + inst.buf.frames() as u64);
+
+            if ts >= end {
+                break;
+            }
+        }
+
+        Ok(spans)
+    }
+
+    /// Compares the audio decoded after a seek to the audio decoded linearly.
+    fn compare(&self, ts: u64, actual_ts: u64, linear: &Span, seeked: &Span) -> SeekAccuracy {
+        let max_delta = linear.max_delta(seeked, 0, f32::INFINITY).unwrap_or(f32::INFINITY);
+
+        let mut offset = None;
+
+        // Search for the smallest offset at which the audio matches.
+        if seeked.frames() > 0 {
+            for i in 0..=2 * self.max_offset as i64 {
+                let candidate = if i % 2 == 0 { -i / 2 } else { (i + 1) / 2 };
+
+                let is_match = linear
+                    .max_delta(seeked, candidate, MAX_SAMPLE_DELTA)
+                    .map_or(false, |delta| delta <= MAX_SAMPLE_DELTA);
+
+                if is_match {
+                    offset = Some(candidate);
+                    break;
+                }
+            }
+        }
+
+        SeekAccuracy { ts, actual_ts, offset, max_delta }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::SeekVerifier;
+    use crate::audio::SignalSpec;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+    use crate::codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions};
+    use crate::codecs::{FinalizeResult, CODEC_TYPE_PCM_F32LE};
+    use crate::errors::{end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+    use crate::support_codec;
+
+    /// The number of frames in a test packet.
+    const PACKET_LEN: u64 = 100;
+
+    /// The number of packets in a test stream.
+    const N_PACKETS: u64 = 50;
+
+    /// Gets the value of the frame at `ts`. Every frame in a test stream has a distinct value.
+    fn frame_value(ts: u64) -> f32 {
+        ((ts * 7919) % 4096) as f32 / 4096.0
+    }
+
+    /// A reader whose packets contain the timestamp of their first frame. After a seek, the
+    /// timestamps of the packets are wrong by `error` frames.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        next: u64,
+        error: i64,
+        is_seeked: bool,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+            let ts = match to {
+                SeekTo::TimeStamp { ts, .. } => ts,
+                _ => unreachable!(),
+            };
+
+            self.next = ts / PACKET_LEN;
+            self.is_seeked = true;
+
+            let actual_ts = self.next * PACKET_LEN;
+
+            Ok(SeekedTo { track_id: 0, required_ts: ts, actual_ts })
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            if self.next == N_PACKETS {
+                return end_of_stream_error();
+            }
+
+            let ts = self.next * PACKET_LEN;
+            let error = if self.is_seeked { self.error } else { 0 };
+
+            self.next += 1;
+
+            let reported_ts = (ts as i64 + error) as u64;
+
+            Ok(Packet::new_from_slice(0, reported_ts, PACKET_LEN, &ts.to_le_bytes()))
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    /// A decoder that outputs the frame values of the timestamp contained in the packet.
+    struct TestDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<f32>,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(8_000, Channels::FRONT_LEFT);
+            Ok(TestDecoder { params: params.clone(), buf: AudioBuffer::new(PACKET_LEN, spec) })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_F32LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&packet.buf()[..8]);
+            let ts = u64::from_le_bytes(bytes);
+
+            self.buf.clear();
+            self.buf.render_reserved(Some(PACKET_LEN as usize));
+
+            for (i, s) in self.buf.chan_mut(0).iter_mut().enumerate() {
+                *s = frame_value(ts + i as u64);
+            }
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    fn open(error: i64) -> Result<(Box<dyn FormatReader>, Box<dyn Decoder>)> {
+        let params = CodecParameters::new();
+
+        let reader = TestReader {
+            tracks: vec![Track::new(0, params.clone())],
+            metadata: Default::default(),
+            next: 0,
+            error,
+            is_seeked: false,
+        };
+
+        let decoder = TestDecoder::try_new(&params, &Default::default())?;
+
+        Ok((Box::new(reader), Box::new(decoder)))
+    }
+
+    #[test]
+    fn verify_seek_accuracy_exact() {
+        let mut verifier = SeekVerifier::new(|| open(0));
+        verifier.with_window(64).with_max_offset(256);
+
+        // Seek forwards, backwards, mid-packet, and close to the end of the stream.
+        let results = verifier.verify(&[2000, 150, 0, 4990]).unwrap();
+
+        for result in results.iter() {
+            assert!(result.is_exact(), "{:?}", result);
+            assert_eq!(result.max_delta, 0.0);
+        }
+
+        assert_eq!(results[1].actual_ts, 100);
+    }
+
+    #[test]
+    fn verify_seek_accuracy_offset() {
+        // The audio after a seek is 3 frames earlier than its timestamp.
+        let mut verifier = SeekVerifier::new(|| open(3));
+        verifier.with_window(64).with_max_offset(256);
+
+        let results = verifier.verify(&[1000, 250]).unwrap();
+
+        for result in results.iter() {
+            assert_eq!(result.offset, Some(-3));
+            assert!(!result.is_exact());
+            assert!(result.max_delta > 0.0);
+        }
+
+        // The offset is out of range.
+        let mut verifier = SeekVerifier::new(|| open(-300));
+        verifier.with_window(64).with_max_offset(256);
+
+        let results = verifier.verify(&[1000]).unwrap();
+
+        assert_eq!(results[0].offset, None);
+    }
+}
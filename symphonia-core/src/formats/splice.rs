@@ -0,0 +1,548 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `splice` module provides splicing of one stream into another.
+//!
+//! A [`Splicer`] plays a stream up-to a splice point, and then switches to another stream, such as
+//! an advertisement or an announcement. The switch may be smoothed with a short crossfade. The
+//! timestamps of the second stream are rebased such that the output has a single, continuous,
+//! timeline.
+
+use std::f32::consts::FRAC_PI_2;
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::codecs::Decoder;
+use crate::errors::{end_of_stream_error, unsupported_error, Error, Result};
+
+use super::FormatReader;
+
+/// A `SpliceSource` is one of the streams of a `Splicer`.
+pub struct SpliceSource {
+    reader: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    spec: Option<SignalSpec>,
+    /// Decoded audio that was not yet emitted, one plane per channel.
+    pending: Vec<Vec<f32>>,
+    /// The timestamp of the next expected frame, or `None` if no audio was decoded yet.
+    next_ts: Option<u64>,
+    is_eos: bool,
+    scratch: AudioBuffer<f32>,
+}
+
+impl SpliceSource {
+    /// Instantiate a new `SpliceSource` that decodes the track `track_id` of `reader` using
+    /// `decoder`.
+    pub fn new(reader: Box<dyn FormatReader>, decoder: Box<dyn Decoder>, track_id: u32) -> Self {
+        SpliceSource {
+            reader,
+            decoder,
+            track_id,
+            spec: None,
+            pending: Vec::new(),
+            next_ts: None,
+            is_eos: false,
+            scratch: AudioBuffer::unused(),
+        }
+    }
+
+    /// Gets the track identifier of the source.
+    pub fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
+    /// Consumes the `SpliceSource` and returns the format reader and decoder. Any decoded audio
+    /// that was not emitted is discarded.
+    pub fn into_inner(self) -> (Box<dyn FormatReader>, Box<dyn Decoder>) {
+        (self.reader, self.decoder)
+    }
+
+    fn pending_len(&self) -> usize {
+        self.pending.first().map_or(0, |plane| plane.len())
+    }
+
+    /// Gets the timestamp of the first pending frame.
+    fn pending_ts(&self) -> u64 {
+        self.next_ts.unwrap_or(0) - self.pending_len() as u64
+    }
+
+    /// Decodes packets until audio is pending, or the source ends.
+    fn fill(&mut self) -> Result<()> {
+        while !self.is_eos && self.pending_len() == 0 {
+            self.decode_next()?;
+        }
+        Ok(())
+    }
+
+    /// Reads and decodes the next packet of the source into the pending audio.
+    fn decode_next(&mut self) -> Result<()> {
+        let packet = match self.reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.is_eos = true;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        if packet.track_id() != self.track_id {
+            return Ok(());
+        }
+
+        let decoded = match self.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // The timestamp of the next packet will reveal the gap left by the undecodable packet,
+            // and it will be filled with silence.
+            Err(Error::DecodeError(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let n_frames = decoded.frames();
+
+        if n_frames == 0 {
+            return Ok(());
+        }
+
+        match self.spec {
+            None => {
+                self.spec = Some(*decoded.spec());
+                self.pending = vec![Vec::new(); decoded.spec().channels.count()];
+            }
+            Some(spec) if spec != *decoded.spec() => {
+                return unsupported_error("core (splice): the signal specification changed");
+            }
+            _ => (),
+        }
+
+        if self.scratch.capacity() < decoded.capacity() || self.scratch.spec() != decoded.spec() {
+            self.scratch = decoded.make_equivalent();
+        }
+
+        decoded.convert(&mut self.scratch);
+
+        let ts = packet.ts();
+        let next_ts = self.next_ts.unwrap_or(ts);
+
+        // Fill a gap before the packet with silence.
+        if ts > next_ts {
+            let gap = (ts - next_ts) as usize;
+
+            for plane in self.pending.iter_mut() {
+                plane.resize(plane.len() + gap, 0.0);
+            }
+        }
+
+        // Drop frames that overlap audio already pending or emitted.
+        let skip = next_ts.saturating_sub(ts).min(n_frames as u64) as usize;
+
+        for (c, plane) in self.pending.iter_mut().enumerate() {
+            plane.extend_from_slice(&self.scratch.chan(c)[skip..]);
+        }
+
+        self.next_ts = Some(next_ts.max(ts + n_frames as u64));
+
+        Ok(())
+    }
+}
+
+/// A `Splicer` plays one stream up-to a splice point, and then switches to another stream.
+///
+/// The splice point is a timestamp of the first stream. Up-to the splice point, the audio and
+/// timestamps of the first stream are returned unaltered. From the splice point onwards, the audio
+/// of the second stream is returned, and its timestamps are rebased to continue from the splice
+/// point. If a crossfade is set, the first stream fades out as the second stream fades in over the
+/// length of the crossfade, starting at the splice point. If the first stream ends before the
+/// splice point, the switch happens when it ends.
+///
+/// Both streams must have the same signal specification, and their timestamps are assumed to be in
+/// units of frames.
+pub struct Splicer {
+    from: SpliceSource,
+    to: SpliceSource,
+    splice_ts: u64,
+    crossfade_len: u64,
+    /// The number of frames returned from the splice point onwards.
+    n_spliced: u64,
+    /// The timestamp of the last buffer returned.
+    ts: u64,
+    buf: AudioBuffer<f32>,
+}
+
+impl Splicer {
+    /// Instantiate a new `Splicer` that switches from the source `from` to the source `to` at the
+    /// timestamp `splice_ts` of `from`.
+    pub fn new(from: SpliceSource, to: SpliceSource, splice_ts: u64) -> Self {
+        Splicer {
+            from,
+            to,
+            splice_ts,
+            crossfade_len: 0,
+            n_spliced: 0,
+            ts: 0,
+            buf: AudioBuffer::unused(),
+        }
+    }
+
+    /// Sets the length of the crossfade, in frames, starting at the splice point. A length of 0,
+    /// the default, switches streams instantaneously. A short crossfade (e.g., 10 to 50 ms) avoids
+    /// an audible click at the splice point.
+    pub fn with_crossfade(&mut self, n_frames: u64) -> &mut Self {
+        self.crossfade_len = n_frames;
+        self
+    }
+
+    /// Gets the timestamp of the splice point.
+    pub fn splice_ts(&self) -> u64 {
+        self.splice_ts
+    }
+
+    /// Returns true once audio at, or after, the splice point has been returned.
+    pub fn is_spliced(&self) -> bool {
+        self.n_spliced > 0
+    }
+
+    /// Gets the timestamp of the first frame of the last buffer returned by `next_buffer`.
+    pub fn ts(&self) -> u64 {
+        self.ts
+    }
+
+    /// Consumes the `Splicer` and returns the sources. For example, to splice back to the first
+    /// stream once the second ends.
+    pub fn into_inner(self) -> (SpliceSource, SpliceSource) {
+        (self.from, self.to)
+    }
+
+    /// Decodes and returns the next buffer of spliced audio. Once the second stream ends, an
+    /// end-of-stream error is returned.
+    pub fn next_buffer(&mut self) -> Result<&AudioBuffer<f32>> {
+        if self.n_spliced == 0 {
+            self.from.fill()?;
+
+            if self.from.pending_len() > 0 {
+                let ts = self.from.pending_ts();
+                let n_frames =
+                    self.splice_ts.saturating_sub(ts).min(self.from.pending_len() as u64);
+
+                if n_frames > 0 {
+                    let spec = self.from.spec.unwrap();
+
+                    self.ts = ts;
+                    self.render(spec, n_frames as usize, |from, _, _| from);
+
+                    for plane in self.from.pending.iter_mut() {
+                        plane.drain(..n_frames as usize);
+                    }
+
+                    return Ok(&self.buf);
+                }
+            }
+            else {
+                // The first stream ended before the splice point, switch now.
+                self.splice_ts = self.from.next_ts.unwrap_or(0);
+            }
+        }
+
+        self.to.fill()?;
+
+        let ts = self.splice_ts + self.n_spliced;
+
+        if self.n_spliced < self.crossfade_len {
+            self.from.fill()?;
+
+            let spec = match (self.from.spec, self.to.spec) {
+                (Some(from), Some(to)) if from != to => {
+                    return unsupported_error(
+                        "core (splice): the signal specifications of the streams differ",
+                    )
+                }
+                (Some(spec), _) | (_, Some(spec)) => spec,
+                _ => return end_of_stream_error(),
+            };
+
+            // A stream that ended is silent for the remainder of the crossfade.
+            let n_frames = [&self.from, &self.to]
+                .iter()
+                .filter(|source| source.pending_len() > 0)
+                .map(|source| source.pending_len() as u64)
+                .fold(self.crossfade_len - self.n_spliced, u64::min);
+
+            if self.from.pending_len() == 0 && self.to.pending_len() == 0 {
+                return end_of_stream_error();
+            }
+
+            let start = self.n_spliced;
+            let len = self.crossfade_len as f32;
+
+            self.ts = ts;
+            self.render(spec, n_frames as usize, |from, to, i| {
+                let angle = FRAC_PI_2 * ((start + i as u64) as f32 + 0.5) / len;
+                from * angle.cos() + to * angle.sin()
+            });
+
+            for source in [&mut self.from, &mut self.to].iter_mut() {
+                let n_frames = (n_frames as usize).min(source.pending_len());
+
+                for plane in source.pending.iter_mut() {
+                    plane.drain(..n_frames);
+                }
+            }
+        }
+        else {
+            if self.to.pending_len() == 0 {
+                return end_of_stream_error();
+            }
+
+            if let Some(from) = self.from.spec {
+                if Some(from) != self.to.spec {
+                    return unsupported_error(
+                        "core (splice): the signal specifications of the streams differ",
+                    );
+                }
+            }
+
+            let n_frames = self.to.pending_len();
+
+            self.ts = ts;
+            self.render(self.to.spec.unwrap(), n_frames, |_, to, _| to);
+
+            for plane in self.to.pending.iter_mut() {
+                plane.clear();
+            }
+        }
+
+        Ok(&self.buf)
+    }
+
+    /// Renders `n_frames` frames into the output buffer by mixing the pending audio of the sources
+    /// with `mix`, and advances the number of frames spliced if past the splice point.
+    ///
+    /// The mixing function is given a sample of each source, and the index of the frame. Sources
+    /// without pending audio are silent.
+    fn render<F>(&mut self, spec: SignalSpec, n_frames: usize, mix: F)
+    where
+        F: Fn(f32, f32, usize) -> f32,
+    {
+        if self.buf.capacity() < n_frames || *self.buf.spec() != spec {
+            self.buf = AudioBuffer::new(n_frames as u64, spec);
+        }
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(n_frames));
+
+        for c in 0..spec.channels.count() {
+            let from = self.from.pending.get(c).map_or(&[][..], |plane| &plane[..]);
+            let to = self.to.pending.get(c).map_or(&[][..], |plane| &plane[..]);
+
+            for (i, out) in self.buf.chan_mut(c).iter_mut().enumerate() {
+                let from = from.get(i).copied().unwrap_or(0.0);
+                let to = to.get(i).copied().unwrap_or(0.0);
+
+                *out = mix(from, to, i);
+            }
+        }
+
+        if self.ts >= self.splice_ts {
+            self.n_spliced += n_frames as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{SpliceSource, Splicer};
+    use crate::audio::SignalSpec;
+    use crate::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal};
+    use crate::codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions};
+    use crate::codecs::{FinalizeResult, CODEC_TYPE_PCM_F32LE};
+    use crate::errors::{end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+    use crate::support_codec;
+
+    /// The number of frames in a test packet.
+    const PACKET_LEN: u64 = 10;
+
+    /// A reader of packets at the given timestamps, each containing `PACKET_LEN` frames.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        packets: Vec<u64>,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, _: SeekTo) -> Result<SeekedTo> {
+            unsupported_error("test")
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            if self.packets.is_empty() {
+                return end_of_stream_error();
+            }
+
+            Ok(Packet::new_from_slice(0, self.packets.remove(0), PACKET_LEN, &[]))
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    /// A decoder that outputs a constant sample value.
+    struct TestDecoder {
+        params: CodecParameters,
+        value: f32,
+        buf: AudioBuffer<f32>,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(params.sample_rate.unwrap(), Channels::FRONT_LEFT);
+            Ok(TestDecoder { params: params.clone(), value: 0.0, buf: AudioBuffer::new(16, spec) })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_F32LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            self.buf.clear();
+            self.buf.render_reserved(Some(packet.dur() as usize));
+
+            let value = self.value;
+            self.buf.chan_mut(0).iter_mut().for_each(|s| *s = value);
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    fn source(sample_rate: u32, packets: Vec<u64>, value: f32) -> SpliceSource {
+        let mut params = CodecParameters::new();
+        params.with_sample_rate(sample_rate);
+
+        let reader = TestReader {
+            tracks: vec![Track::new(0, params.clone())],
+            metadata: Default::default(),
+            packets,
+        };
+
+        let mut decoder = TestDecoder::try_new(&params, &Default::default()).unwrap();
+        decoder.value = value;
+
+        SpliceSource::new(Box::new(reader), Box::new(decoder), 0)
+    }
+
+    /// Decodes all buffers, and returns the timestamp of each buffer, and the concatenated audio.
+    fn splice_all(splicer: &mut Splicer) -> (Vec<u64>, Vec<f32>) {
+        let mut timestamps = Vec::new();
+        let mut samples = Vec::new();
+
+        while let Ok(buf) = splicer.next_buffer() {
+            samples.extend_from_slice(buf.chan(0));
+            timestamps.push(splicer.ts());
+        }
+
+        (timestamps, samples)
+    }
+
+    #[test]
+    fn verify_splice_cut() {
+        // Splice mid-packet, into a stream that starts at a non-zero timestamp.
+        let from = source(8_000, vec![100, 110, 120, 130], 1.0);
+        let to = source(8_000, vec![500, 510], 2.0);
+
+        let mut splicer = Splicer::new(from, to, 115);
+
+        let (timestamps, samples) = splice_all(&mut splicer);
+
+        // The timestamps of the second stream are rebased to the splice point.
+        assert_eq!(timestamps, [100, 110, 115, 125]);
+
+        assert_eq!(samples.len(), 35);
+        assert!(samples[..15].iter().all(|&s| s == 1.0));
+        assert!(samples[15..].iter().all(|&s| s == 2.0));
+    }
+
+    #[test]
+    fn verify_splice_crossfade() {
+        let from = source(8_000, vec![0, 10, 20, 30], 1.0);
+        let to = source(8_000, vec![0, 10, 20], -1.0);
+
+        let mut splicer = Splicer::new(from, to, 10);
+        splicer.with_crossfade(16);
+
+        let (timestamps, samples) = splice_all(&mut splicer);
+
+        assert_eq!(timestamps, [0, 10, 20, 26, 30]);
+        assert_eq!(samples.len(), 40);
+
+        // The crossfade is equal-power, and monotonically decreasing from the first stream to the
+        // second stream.
+        assert!(samples[..10].iter().all(|&s| s == 1.0));
+        assert!(samples[10..26].windows(2).all(|w| w[0] > w[1]));
+        assert!(samples[10] < 1.0 && samples[25] > -1.0);
+        assert!(samples[17].abs() < 0.1);
+        assert!(samples[26..].iter().all(|&s| s == -1.0));
+    }
+
+    #[test]
+    fn verify_splice_early_end() {
+        // The first stream ends before the splice point.
+        let from = source(8_000, vec![0, 10], 1.0);
+        let to = source(8_000, vec![0], 2.0);
+
+        let mut splicer = Splicer::new(from, to, 100);
+
+        let (timestamps, samples) = splice_all(&mut splicer);
+
+        assert_eq!(timestamps, [0, 10, 20]);
+        assert_eq!(samples.len(), 30);
+        assert_eq!(splicer.splice_ts(), 20);
+
+        // Streams with different signal specifications may not be spliced.
+        let from = source(8_000, vec![0], 1.0);
+        let to = source(16_000, vec![0], 2.0);
+
+        let mut splicer = Splicer::new(from, to, 5);
+
+        assert!(splicer.next_buffer().is_ok());
+        assert!(splicer.next_buffer().is_err());
+    }
+}
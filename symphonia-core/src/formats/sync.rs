@@ -0,0 +1,519 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `sync` module provides synchronized decoding of multiple streams.
+//!
+//! A [`SyncGroup`] drives several `FormatReader` and `Decoder` pairs in lockstep (e.g., the tracks
+//! of a multi-microphone recording split across files), and emits blocks of audio that are aligned
+//! by timestamp. Gaps in a stream, such as those caused by undecodable packets, are filled with
+//! silence so that a stream never falls out of alignment with the others.
+//!
+//! Streams recorded on different devices will slowly drift apart since no two clocks run at exactly
+//! the same rate. If the drift is known, a stream may be resampled to compensate for it. Streams
+//! with a sample rate that differs from that of the group are also resampled.
+
+use crate::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+use crate::codecs::Decoder;
+use crate::errors::{end_of_stream_error, unsupported_error, Error, Result};
+
+use super::FormatReader;
+
+/// A `SyncStream` is one stream of a `SyncGroup`.
+pub struct SyncStream {
+    reader: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    /// The ratio of the stream's sample rate to the group's sample rate.
+    rate_ratio: f64,
+    /// The clock drift of the stream in parts-per-million.
+    drift_ppm: f64,
+    channels: Channels,
+    /// Decoded audio that was not yet emitted, one plane per channel.
+    pending: Vec<Vec<f32>>,
+    /// The fractional position of the resampler in `pending`.
+    phase: f64,
+    /// The timestamp of the next expected frame. Negative if the stream was delayed.
+    next_ts: i64,
+    is_eos: bool,
+    scratch: AudioBuffer<f32>,
+    buf: AudioBuffer<f32>,
+}
+
+impl SyncStream {
+    /// Sets the position of the start of the stream relative to the start of the group, in frames
+    /// of the stream. A positive offset delays the stream, and a negative offset advances it.
+    pub fn with_offset(&mut self, n_frames: i64) -> &mut Self {
+        self.next_ts = -n_frames;
+        self
+    }
+
+    /// Sets the clock drift of the stream relative to the group in parts-per-million. A positive
+    /// drift indicates the stream's clock runs fast, and the stream will be resampled to compensate.
+    pub fn with_drift(&mut self, ppm: f64) -> &mut Self {
+        self.drift_ppm = ppm;
+        self
+    }
+
+    /// Gets the track identifier of the stream.
+    pub fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
+    /// Gets the last block of audio emitted for the stream.
+    pub fn buffer(&self) -> &AudioBuffer<f32> {
+        &self.buf
+    }
+
+    /// Gets the number of input frames consumed per output frame.
+    fn step(&self) -> f64 {
+        self.rate_ratio * (1.0 + self.drift_ppm * 1e-6)
+    }
+
+    /// Returns true if resampling is not required.
+    fn is_passthrough(&self) -> bool {
+        self.step() == 1.0
+    }
+
+    fn pending_len(&self) -> usize {
+        self.pending.first().map_or(0, |plane| plane.len())
+    }
+
+    /// Returns true if the stream ended, and all decoded audio was emitted.
+    fn is_exhausted(&self) -> bool {
+        self.is_eos && self.pending_len() as f64 <= self.phase
+    }
+
+    /// Gets the number of pending frames required to render a block of `block_len` frames.
+    fn required_len(&self, block_len: usize) -> usize {
+        if self.is_passthrough() {
+            block_len
+        }
+        else {
+            // Linear interpolation requires the frame following the last position.
+            (self.phase + (block_len - 1) as f64 * self.step()) as usize + 2
+        }
+    }
+
+    /// Reads and decodes the next packet of the stream into the pending audio.
+    fn decode_next(&mut self) -> Result<()> {
+        let packet = match self.reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.is_eos = true;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        if packet.track_id() != self.track_id {
+            return Ok(());
+        }
+
+        let decoded = match self.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // The timestamp of the next packet will reveal the gap left by the undecodable packet,
+            // and it will be filled with silence.
+            Err(Error::DecodeError(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let n_frames = decoded.frames();
+
+        if n_frames == 0 {
+            return Ok(());
+        }
+
+        let channels = decoded.spec().channels;
+
+        if self.pending.is_empty() {
+            self.channels = channels;
+            self.pending = vec![Vec::new(); channels.count()];
+        }
+        else if channels != self.channels {
+            return unsupported_error("core (sync): the channels of a stream changed");
+        }
+
+        if self.scratch.capacity() < decoded.capacity() || self.scratch.spec() != decoded.spec() {
+            self.scratch = decoded.make_equivalent();
+        }
+
+        decoded.convert(&mut self.scratch);
+
+        let ts = packet.ts() as i64;
+
+        // Fill a gap before the packet with silence.
+        if ts > self.next_ts {
+            let gap = (ts - self.next_ts) as usize;
+
+            for plane in self.pending.iter_mut() {
+                plane.resize(plane.len() + gap, 0.0);
+            }
+        }
+
+        // Drop frames that overlap audio already pending or emitted.
+        let skip = (self.next_ts - ts).clamp(0, n_frames as i64) as usize;
+
+        for (c, plane) in self.pending.iter_mut().enumerate() {
+            plane.extend_from_slice(&self.scratch.chan(c)[skip..]);
+        }
+
+        self.next_ts = self.next_ts.max(ts + n_frames as i64);
+
+        Ok(())
+    }
+
+    /// Renders the next block of `block_len` frames at `sample_rate` into the output buffer.
+    fn render(&mut self, sample_rate: u32, block_len: usize) {
+        let spec = SignalSpec::new(sample_rate, self.channels);
+
+        if self.buf.capacity() < block_len || *self.buf.spec() != spec {
+            self.buf = AudioBuffer::new(block_len as u64, spec);
+        }
+
+        self.buf.clear();
+        self.buf.render_silence(Some(block_len));
+
+        let pending_len = self.pending_len();
+
+        let consumed = if self.is_passthrough() {
+            let n_frames = block_len.min(pending_len);
+
+            for (c, plane) in self.pending.iter().enumerate() {
+                self.buf.chan_mut(c)[..n_frames].copy_from_slice(&plane[..n_frames]);
+            }
+
+            n_frames
+        }
+        else {
+            let step = self.step();
+
+            for (c, plane) in self.pending.iter().enumerate() {
+                let out = self.buf.chan_mut(c);
+
+                // Frames past the end of the pending audio are silent.
+                let get = |i: usize| plane.get(i).copied().unwrap_or(0.0);
+
+                for (k, out) in out.iter_mut().enumerate() {
+                    let pos = self.phase + k as f64 * step;
+                    let i = pos as usize;
+                    let frac = (pos - i as f64) as f32;
+
+                    *out = get(i) + (get(i + 1) - get(i)) * frac;
+                }
+            }
+
+            let end = self.phase + block_len as f64 * step;
+            let consumed = (end as usize).min(pending_len);
+
+            self.phase = end - consumed as f64;
+
+            consumed
+        };
+
+        for plane in self.pending.iter_mut() {
+            plane.drain(..consumed);
+        }
+    }
+}
+
+/// A `SyncGroup` decodes multiple streams in lockstep, and emits aligned blocks of audio.
+///
+/// The timestamps of a stream are assumed to be in units of frames.
+pub struct SyncGroup {
+    sample_rate: u32,
+    block_len: usize,
+    streams: Vec<SyncStream>,
+}
+
+impl SyncGroup {
+    /// Instantiate a new `SyncGroup` that emits blocks of `block_len` frames at `sample_rate`.
+    pub fn new(sample_rate: u32, block_len: usize) -> Self {
+        assert!(sample_rate > 0, "sample rate must be non-zero");
+        assert!(block_len > 0, "block length must be non-zero");
+
+        SyncGroup { sample_rate, block_len, streams: Vec::new() }
+    }
+
+    /// Adds a stream that decodes the track `track_id` of `reader` using `decoder`. Returns the
+    /// stream so that it may be configured.
+    pub fn add_stream(
+        &mut self,
+        reader: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+    ) -> &mut SyncStream {
+        let params = decoder.codec_params();
+
+        let rate = params.sample_rate.unwrap_or(self.sample_rate);
+        let channels = params.channels.unwrap_or(Channels::FRONT_LEFT);
+
+        self.streams.push(SyncStream {
+            reader,
+            decoder,
+            track_id,
+            rate_ratio: f64::from(rate) / f64::from(self.sample_rate),
+            drift_ppm: 0.0,
+            channels,
+            pending: Vec::new(),
+            phase: 0.0,
+            next_ts: 0,
+            is_eos: false,
+            scratch: AudioBuffer::unused(),
+            buf: AudioBuffer::unused(),
+        });
+
+        self.streams.last_mut().unwrap()
+    }
+
+    /// Gets the streams of the group.
+    pub fn streams(&self) -> &[SyncStream] {
+        &self.streams
+    }
+
+    /// Decodes the next block of every stream, and returns the streams. The buffer of each stream
+    /// contains exactly one block of audio that is aligned with the buffers of the other streams.
+    ///
+    /// Streams that ended before the others are padded with silence. Once all streams have ended,
+    /// an end-of-stream error is returned.
+    pub fn next_block(&mut self) -> Result<&[SyncStream]> {
+        if self.streams.iter().all(|stream| stream.is_exhausted()) {
+            return end_of_stream_error();
+        }
+
+        for stream in self.streams.iter_mut() {
+            while !stream.is_eos && stream.pending_len() < stream.required_len(self.block_len) {
+                stream.decode_next()?;
+            }
+
+            stream.render(self.sample_rate, self.block_len);
+        }
+
+        Ok(&self.streams)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::SyncGroup;
+    use crate::audio::{
+        AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec,
+    };
+    use crate::codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions};
+    use crate::codecs::{FinalizeResult, CODEC_TYPE_PCM_F32LE};
+    use crate::errors::{decode_error, end_of_stream_error, unsupported_error, Result};
+    use crate::formats::{Cue, FormatOptions, FormatReader, Packet};
+    use crate::formats::{SeekMode, SeekTo, SeekedTo, Track};
+    use crate::io::MediaSourceStream;
+    use crate::meta::{Metadata, MetadataLog};
+    use crate::support_codec;
+
+    /// The number of frames in a test packet.
+    const PACKET_LEN: u64 = 10;
+
+    /// A reader of packets at the given timestamps, each containing `PACKET_LEN` frames. A packet
+    /// is corrupt if it contains data.
+    struct TestReader {
+        tracks: Vec<Track>,
+        metadata: MetadataLog,
+        packets: Vec<(u64, bool)>,
+    }
+
+    impl FormatReader for TestReader {
+        fn try_new(_: MediaSourceStream, _: &FormatOptions) -> Result<Self> {
+            unsupported_error("test")
+        }
+
+        fn cues(&self) -> &[Cue] {
+            &[]
+        }
+
+        fn metadata(&mut self) -> Metadata<'_> {
+            self.metadata.metadata()
+        }
+
+        fn seek(&mut self, _: SeekMode, _: SeekTo) -> Result<SeekedTo> {
+            unsupported_error("test")
+        }
+
+        fn tracks(&self) -> &[Track] {
+            &self.tracks
+        }
+
+        fn next_packet(&mut self) -> Result<Packet> {
+            if self.packets.is_empty() {
+                return end_of_stream_error();
+            }
+
+            let (ts, is_corrupt) = self.packets.remove(0);
+
+            let data: &[u8] = if is_corrupt { &[0] } else { &[] };
+
+            Ok(Packet::new_from_slice(0, ts, PACKET_LEN, data))
+        }
+
+        fn into_inner(self: Box<Self>) -> MediaSourceStream {
+            MediaSourceStream::new(Box::new(Cursor::new(Vec::new())), Default::default())
+        }
+    }
+
+    /// A decoder that outputs the timestamp of each frame as its sample value, and fails to decode
+    /// corrupt packets.
+    struct TestDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<f32>,
+    }
+
+    impl Decoder for TestDecoder {
+        fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+            let spec = SignalSpec::new(params.sample_rate.unwrap(), Channels::FRONT_LEFT);
+            Ok(TestDecoder { params: params.clone(), buf: AudioBuffer::new(PACKET_LEN, spec) })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_PCM_F32LE, "test", "Test")]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            self.buf.clear();
+
+            if !packet.buf().is_empty() {
+                return decode_error("test: corrupt packet");
+            }
+
+            self.buf.render_reserved(Some(packet.dur() as usize));
+
+            for (i, s) in self.buf.chan_mut(0).iter_mut().enumerate() {
+                *s = (packet.ts() + i as u64) as f32;
+            }
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    fn add_stream<'a>(
+        group: &'a mut SyncGroup,
+        sample_rate: u32,
+        packets: Vec<u64>,
+        corrupt: &[u64],
+    ) -> &'a mut super::SyncStream {
+        let mut params = CodecParameters::new();
+        params.with_sample_rate(sample_rate);
+
+        let reader = TestReader {
+            tracks: vec![Track::new(0, params.clone())],
+            metadata: Default::default(),
+            packets: packets.into_iter().map(|ts| (ts, corrupt.contains(&ts))).collect(),
+        };
+        let decoder = TestDecoder::try_new(&params, &Default::default()).unwrap();
+
+        group.add_stream(Box::new(reader), Box::new(decoder), 0)
+    }
+
+    /// Decodes all blocks, and returns the concatenated output of each stream.
+    fn decode_all(group: &mut SyncGroup) -> Vec<Vec<f32>> {
+        let mut output = vec![Vec::new(); group.streams().len()];
+
+        while let Ok(streams) = group.next_block() {
+            for (output, stream) in output.iter_mut().zip(streams) {
+                assert_eq!(stream.buffer().frames(), 16);
+                output.extend_from_slice(stream.buffer().chan(0));
+            }
+        }
+
+        output
+    }
+
+    #[test]
+    fn verify_sync_alignment() {
+        let mut group = SyncGroup::new(8_000, 16);
+
+        // A complete stream.
+        add_stream(&mut group, 8_000, (0..6).map(|i| i * PACKET_LEN).collect(), &[]);
+        // A stream with a missing packet, an undecodable packet, and delayed by 5 frames.
+        add_stream(&mut group, 8_000, vec![0, 10, 30, 40], &[30]).with_offset(5);
+        // A stream advanced by 12 frames.
+        add_stream(&mut group, 8_000, (0..6).map(|i| i * PACKET_LEN).collect(), &[])
+            .with_offset(-12);
+
+        let output = decode_all(&mut group);
+
+        // The first stream has 60 frames, which is padded to 4 blocks.
+        let expected: Vec<f32> = (0..64)
+            .map(|i| {
+                if i < 60 {
+                    i as f32
+                }
+                else {
+                    0.0
+                }
+            })
+            .collect();
+        assert_eq!(output[0], expected);
+
+        let expected: Vec<f32> = (0..64)
+            .map(|i| {
+                if (5..25).contains(&i) {
+                    (i - 5) as f32
+                }
+                else {
+                    0.0
+                }
+            })
+            .collect();
+        assert_eq!(output[1][..45], expected[..45]);
+        assert_eq!(output[1][45..55], (40..50).map(|i| i as f32).collect::<Vec<_>>()[..]);
+
+        let expected: Vec<f32> = (0..64)
+            .map(|i| {
+                if i < 48 {
+                    (i + 12) as f32
+                }
+                else {
+                    0.0
+                }
+            })
+            .collect();
+        assert_eq!(output[2], expected);
+    }
+
+    #[test]
+    fn verify_sync_resampling() {
+        let mut group = SyncGroup::new(16_000, 16);
+
+        // A stream at half the sample rate of the group is interpolated.
+        add_stream(&mut group, 8_000, (0..4).map(|i| i * PACKET_LEN).collect(), &[]);
+        // A stream whose clock runs fast is slowed down.
+        add_stream(&mut group, 16_000, (0..8).map(|i| i * PACKET_LEN).collect(), &[])
+            .with_drift(1e5);
+
+        let output = decode_all(&mut group);
+
+        for (k, &s) in output[0][..78].iter().enumerate() {
+            assert!((s - k as f32 / 2.0).abs() < 1e-4);
+        }
+
+        for (k, &s) in output[1][..70].iter().enumerate() {
+            assert!((s - k as f32 * 1.1).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures_io::AsyncRead;
+
+use super::MediaSource;
+
+/// The number of bytes read from the inner reader at a time.
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// The bytes read from the inner reader that have not yet been consumed.
+#[derive(Default)]
+struct Shared {
+    buf: VecDeque<u8>,
+    is_eof: bool,
+}
+
+/// `AsyncSource` reads bytes from an asynchronous reader ahead of a synchronous `MediaSource`.
+///
+/// Format readers and decoders are synchronous. To use them in an asynchronous context without
+/// blocking the executor, `AsyncSource` reads bytes from the inner reader asynchronously, and
+/// buffers them. The synchronous end, obtained with [`AsyncSource::media_source`], only ever reads
+/// the buffered bytes.
+///
+/// Before calling into a format reader, call [`AsyncSource::fill`] to buffer up-to the read-ahead
+/// length. The read-ahead length should exceed the largest amount of data a format reader may read
+/// at once, such as the largest packet. If the synchronous end runs out of buffered bytes before
+/// the inner reader ends, the read returns an error of kind [`io::ErrorKind::WouldBlock`]. A
+/// `MediaSourceStream` rewinds the bytes consumed by a read that fails this way, such that the read
+/// may be retried (see [`Error::is_retryable`](crate::errors::Error::is_retryable)).
+///
+/// The synchronous end is not seekable.
+pub struct AsyncSource<R> {
+    inner: R,
+    shared: Arc<Mutex<Shared>>,
+    read_ahead: usize,
+    chunk: Box<[u8]>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncSource<R> {
+    /// Instantiate a new `AsyncSource` that reads from `inner`, and buffers up-to `read_ahead`
+    /// bytes when filled.
+    pub fn new(inner: R, read_ahead: usize) -> Self {
+        AsyncSource {
+            inner,
+            shared: Default::default(),
+            read_ahead,
+            chunk: vec![0; CHUNK_LEN].into_boxed_slice(),
+        }
+    }
+
+    /// Gets the synchronous end of the `AsyncSource`. Bytes read from the synchronous end are
+    /// consumed from the buffer.
+    pub fn media_source(&self) -> Box<dyn MediaSource> {
+        Box::new(BufferedSource { shared: Arc::clone(&self.shared) })
+    }
+
+    /// Gets the number of buffered bytes.
+    pub fn buffered_len(&self) -> usize {
+        self.shared.lock().unwrap().buf.len()
+    }
+
+    /// Returns true if the inner reader has ended.
+    pub fn is_eof(&self) -> bool {
+        self.shared.lock().unwrap().is_eof
+    }
+
+    /// Reads from the inner reader until the read-ahead length is buffered, or the inner reader
+    /// ends.
+    pub async fn fill(&mut self) -> io::Result<()> {
+        loop {
+            if self.is_eof() {
+                break;
+            }
+
+            let want = match self.read_ahead.checked_sub(self.buffered_len()) {
+                Some(want) if want > 0 => want.min(self.chunk.len()),
+                _ => break,
+            };
+
+            let read = ReadChunk { inner: &mut self.inner, buf: &mut self.chunk[..want] };
+
+            let len = match read.await {
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+
+            let mut shared = self.shared.lock().unwrap();
+
+            if len == 0 {
+                shared.is_eof = true;
+            }
+            else {
+                shared.buf.extend(&self.chunk[..len]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Consumes the `AsyncSource` and returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// A future that reads a chunk of bytes from an asynchronous reader.
+struct ReadChunk<'a, R> {
+    inner: &'a mut R,
+    buf: &'a mut [u8],
+}
+
+impl<'a, R: AsyncRead + Unpin> Future for ReadChunk<'a, R> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        Pin::new(&mut *this.inner).poll_read(cx, this.buf)
+    }
+}
+
+/// The synchronous end of an `AsyncSource`.
+struct BufferedSource {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Read for BufferedSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.buf.is_empty() && !shared.is_eof && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "async source: no bytes are buffered, the read-ahead length may be too short",
+            ));
+        }
+
+        let len = buf.len().min(shared.buf.len());
+
+        for (dst, src) in buf.iter_mut().zip(shared.buf.drain(..len)) {
+            *dst = src;
+        }
+
+        Ok(len)
+    }
+}
+
+impl Seek for BufferedSource {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "async source: not seekable"))
+    }
+}
+
+impl MediaSource for BufferedSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use futures_io::AsyncRead;
+
+    use super::AsyncSource;
+    use crate::io::{MediaSourceStream, ReadBytes};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Polls `future` to completion on the current thread.
+    pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut future = Box::pin(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// An asynchronous reader that returns at most 3 bytes per read, and is pending every other
+    /// poll.
+    pub(crate) struct SlowReader {
+        pub(crate) data: Vec<u8>,
+        pub(crate) pos: usize,
+        pub(crate) is_pending: bool,
+    }
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.is_pending = !self.is_pending;
+
+            if self.is_pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            let len = buf.len().min(3).min(self.data.len() - self.pos);
+            buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+            self.pos += len;
+
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    #[test]
+    fn verify_async_source() {
+        let reader = SlowReader { data: (0..20).collect(), pos: 0, is_pending: false };
+
+        let mut source = AsyncSource::new(reader, 8);
+        let mut mss = MediaSourceStream::new(source.media_source(), Default::default());
+
+        // Fill up-to the read-ahead length.
+        block_on(source.fill()).unwrap();
+        assert!(source.buffered_len() >= 8);
+        assert!(!source.is_eof());
+
+        let mut buf = [0; 8];
+        mss.read_buf_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        // Reading more than is buffered would block.
+        let err = mss.read_buf_exact(&mut [0; 16]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        // Fill, and read, the next 8 bytes.
+        block_on(source.fill()).unwrap();
+        mss.read_buf_exact(&mut buf).unwrap();
+        assert_eq!(buf, [8, 9, 10, 11, 12, 13, 14, 15]);
+
+        // The inner reader ends before the read-ahead length is buffered.
+        block_on(source.fill()).unwrap();
+        assert!(source.is_eof());
+        assert_eq!(source.buffered_len(), 4);
+
+        mss.read_buf_exact(&mut buf[..4]).unwrap();
+        assert_eq!(buf[..4], [16, 17, 18, 19]);
+
+        let err = mss.read_byte().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -15,6 +15,10 @@ fn end_of_bitstream_error<T>() -> io::Result<T> {
     Err(io::Error::new(io::ErrorKind::Other, "unexpected end of bitstream"))
 }
 
+fn start_of_bitstream_error<T>() -> io::Result<T> {
+    Err(io::Error::new(io::ErrorKind::InvalidInput, "rewind past the start of bitstream"))
+}
+
 pub mod vlc {
     //! The `vlc` module provides support for decoding variable-length codes (VLC).
 
@@ -256,6 +260,8 @@ pub mod vlc {
     /// description.
     pub struct CodebookBuilder {
         max_bits_per_block: u8,
+        /// The maximum number of bits of the first block, if different from `max_bits_per_block`.
+        first_bits_per_block: Option<u8>,
         bit_order: BitOrder,
         is_sparse: bool,
     }
@@ -268,7 +274,12 @@ pub mod vlc {
         /// codebook reads bits in an order different from the order of the provided codewords,
         /// then this option can be used to make them compatible.
         pub fn new(bit_order: BitOrder) -> Self {
-            CodebookBuilder { max_bits_per_block: 4, bit_order, is_sparse: false }
+            CodebookBuilder {
+                max_bits_per_block: 4,
+                first_bits_per_block: None,
+                bit_order,
+                is_sparse: false,
+            }
         }
 
         /// Instantiates a new `CodebookBuilder` for sparse codebooks.
@@ -279,7 +290,12 @@ pub mod vlc {
         ///
         /// Unused codewords are marked by having a length of 0.
         pub fn new_sparse(bit_order: BitOrder) -> Self {
-            CodebookBuilder { max_bits_per_block: 4, bit_order, is_sparse: true }
+            CodebookBuilder {
+                max_bits_per_block: 4,
+                first_bits_per_block: None,
+                bit_order,
+                is_sparse: true,
+            }
         }
 
         /// Specify the maximum number of bits that should be consumed from the source at a time.
@@ -293,6 +309,18 @@ pub mod vlc {
             self
         }
 
+        /// Specify the maximum number of bits that should be consumed from the source by the first
+        /// read of a codeword. A wide first read resolves most codewords with a single table
+        /// lookup at the cost of a larger table. This value must be within the range 1 <=
+        /// `max_bits_per_read` <= 16. Values outside of this range will cause this function to
+        /// panic. If not provided, the first read is limited by `bits_per_read`.
+        pub fn first_bits_per_read(&mut self, max_bits_per_read: u8) -> &mut Self {
+            assert!(max_bits_per_read <= 16);
+            assert!(max_bits_per_read > 0);
+            self.first_bits_per_block = Some(max_bits_per_read);
+            self
+        }
+
         fn generate_lut<E: CodebookEntry>(
             bit_order: BitOrder,
             is_sparse: bool,
@@ -444,7 +472,8 @@ pub mod vlc {
 
             // Only attempt to generate something if there are code words.
             if !code_words.is_empty() {
-                let prefix_mask = !(!0 << self.max_bits_per_block);
+                let first_bits_per_block =
+                    self.first_bits_per_block.unwrap_or(self.max_bits_per_block);
 
                 // Push a root block.
                 blocks.push(Default::default());
@@ -453,6 +482,7 @@ pub mod vlc {
                 for ((&code, &code_len), &value) in code_words.iter().zip(code_lens).zip(values) {
                     let mut parent_block_id = 0;
                     let mut len = code_len;
+                    let mut block_width = first_bits_per_block;
 
                     // A zero length codeword in a spare codebook is allowed, but not in a regular
                     // codebook.
@@ -465,10 +495,10 @@ pub mod vlc {
                         }
                     }
 
-                    while len > self.max_bits_per_block {
-                        len -= self.max_bits_per_block;
+                    while len > block_width {
+                        len -= block_width;
 
-                        let prefix = ((code >> len) & prefix_mask) as u16;
+                        let prefix = ((code >> len) & !(!0 << block_width)) as u16;
 
                         // Recurse down the tree.
                         if let Some(&block_id) = blocks[parent_block_id].nodes.get(&prefix) {
@@ -483,19 +513,22 @@ pub mod vlc {
                             block.nodes.insert(prefix, block_id);
 
                             // The parent's block width must accomodate the prefix of the child.
-                            // This is always max_bits_per_block bits.
-                            block.width = self.max_bits_per_block;
+                            // This is always the maximum width of the parent block.
+                            block.width = block_width;
 
                             // Append the new block.
                             blocks.push(Default::default());
 
                             parent_block_id = block_id;
                         }
+
+                        // All blocks but the first are limited to max_bits_per_block bits.
+                        block_width = self.max_bits_per_block;
                     }
 
-                    // The final chunk of code bits always has <= max_bits_per_block bits. Obtain
-                    // the final prefix.
-                    let prefix = code & (prefix_mask >> (self.max_bits_per_block - len));
+                    // The final chunk of code bits always has <= block_width bits. Obtain the final
+                    // prefix.
+                    let prefix = code & !(!0 << len);
 
                     let block = &mut blocks[parent_block_id];
 
@@ -518,6 +551,157 @@ pub mod vlc {
 
             Ok(Codebook { table, max_code_len: u32::from(max_code_len), init_block_len })
         }
+
+        /// Construct a canonical `Codebook` using only the lengths of the codewords of each value.
+        ///
+        /// Codewords are assigned in canonical order: shorter codewords before longer codewords,
+        /// and codewords of the same length in the order their values are provided. This is the
+        /// assignment used by DEFLATE and JPEG, amongst others.
+        ///
+        /// This function fails if the code lengths are over-subscribed, or do not form a complete
+        /// code. If the builder is for sparse codebooks, then an incomplete code is allowed and
+        /// values with a code length of 0 are unused.
+        ///
+        /// This function will panic if the number of code lengths and values differ.
+        pub fn make_canonical<E: CodebookEntry>(
+            &mut self,
+            code_lens: &[u8],
+            values: &[E::ValueType],
+        ) -> io::Result<Codebook<E>> {
+            assert!(code_lens.len() == values.len());
+
+            // Count the number of codewords of each length.
+            let mut counts = [0u32; 33];
+
+            for &len in code_lens {
+                if len > 32 {
+                    return codebook_error("core (io): codeword length exceeds 32 bits");
+                }
+
+                counts[usize::from(len)] += 1;
+            }
+
+            if counts[0] > 0 && !self.is_sparse {
+                return codebook_error("core (io): zero length codeword");
+            }
+
+            // Check that the code is not over-subscribed by counting the number of unused
+            // codewords at each length.
+            let mut n_unused = 1u64;
+
+            for &count in &counts[1..] {
+                n_unused <<= 1;
+
+                if u64::from(count) > n_unused {
+                    return codebook_error("core (io): codebook is over-subscribed");
+                }
+
+                n_unused -= u64::from(count);
+            }
+
+            let n_used = code_lens.len() - counts[0] as usize;
+
+            if n_unused > 0 && n_used > 0 && !self.is_sparse {
+                return codebook_error("core (io): codebook is incomplete");
+            }
+
+            // Get the first codeword of each length.
+            let mut next_code_word = [0u32; 33];
+
+            for len in 2..33 {
+                next_code_word[len] = (next_code_word[len - 1] + counts[len - 1]) << 1;
+            }
+
+            // Assign codewords to values in order.
+            let code_words: Vec<u32> = code_lens
+                .iter()
+                .map(|&len| match len {
+                    0 => 0,
+                    _ => {
+                        let code_word = next_code_word[usize::from(len)];
+                        next_code_word[usize::from(len)] += 1;
+                        code_word
+                    }
+                })
+                .collect();
+
+            self.make(&code_words, code_lens, values)
+        }
+
+        /// Construct a `Codebook` using the given codewords, their respective lengths, and values,
+        /// with the number of bits consumed by the first read chosen to minimize the expected
+        /// number of reads per codeword.
+        ///
+        /// The first read may consume up-to 16 bits, but the codebook's table may have no more
+        /// than `max_table_len` entries. Of the widths with the fewest expected reads, the one
+        /// with the smallest table is chosen. Subsequent reads are limited by `bits_per_read`. The
+        /// chosen width is retained by the builder.
+        ///
+        /// This function may fail for the same reasons as `make`.
+        pub fn make_optimal<E: CodebookEntry>(
+            &mut self,
+            code_words: &[u32],
+            code_lens: &[u8],
+            values: &[E::ValueType],
+            max_table_len: usize,
+        ) -> io::Result<Codebook<E>> {
+            let max_code_len = code_lens.iter().copied().max().unwrap_or(0).clamp(1, 16);
+
+            // The expected number of reads, the table length, and the width of the first read.
+            let mut best: Option<(f64, usize, u8)> = None;
+
+            for first_bits in 1..=max_code_len {
+                self.first_bits_per_block = Some(first_bits);
+
+                let table_len = match self.make::<E>(code_words, code_lens, values) {
+                    Ok(codebook) if codebook.table.len() <= max_table_len => codebook.table.len(),
+                    _ => continue,
+                };
+
+                let reads = expected_reads(code_lens, first_bits, self.max_bits_per_block);
+
+                let is_better = match best {
+                    Some((best_reads, best_table_len, _)) => {
+                        reads < best_reads || (reads <= best_reads && table_len < best_table_len)
+                    }
+                    None => true,
+                };
+
+                if is_better {
+                    best = Some((reads, table_len, first_bits));
+                }
+            }
+
+            // If no width fits, fallback to the width of subsequent reads.
+            self.first_bits_per_block = best.map(|(_, _, first_bits)| first_bits);
+
+            self.make(code_words, code_lens, values)
+        }
+    }
+
+    /// Gets the expected number of reads required to decode a codeword, if the first read consumes
+    /// up-to `first_bits` bits, and subsequent reads up-to `bits` bits. A codeword of length `N` is
+    /// assumed to occur with a probability of `2^-N`, as it would in an optimal prefix code.
+    fn expected_reads(code_lens: &[u8], first_bits: u8, bits: u8) -> f64 {
+        let mut reads = 0.0;
+        let mut total = 0.0;
+
+        for &len in code_lens.iter().filter(|&&len| len > 0) {
+            let prob = 0.5f64.powi(i32::from(len));
+
+            let n_reads =
+                if len <= first_bits { 1 } else { 1 + (len - first_bits + bits - 1) / bits };
+
+            reads += prob * f64::from(n_reads);
+            total += prob;
+        }
+
+        if total > 0.0 {
+            reads / total
+        }
+        else {
+            0.0
+        }
     }
 }
 
@@ -557,6 +741,22 @@ mod private {
         /// Consume `num` bits from the source.
         fn consume_bits(&mut self, num: u32);
     }
+
+    pub trait StoreBitsLtr {
+        /// Store the lower `num` bits, where `num` <= 32, of `bits` after the stored bits.
+        fn store_bits(&mut self, bits: u32, num: u32) -> io::Result<()>;
+
+        /// Get the number of stored bits that do not yet form a whole byte.
+        fn num_bits_stored(&self) -> u32;
+    }
+
+    pub trait StoreBitsRtl {
+        /// Store the lower `num` bits, where `num` <= 32, of `bits` after the stored bits.
+        fn store_bits(&mut self, bits: u32, num: u32) -> io::Result<()>;
+
+        /// Get the number of stored bits that do not yet form a whole byte.
+        fn num_bits_stored(&self) -> u32;
+    }
 }
 
 /// A `FiniteBitStream` is a bit stream that has a known length in bits.
@@ -565,6 +765,26 @@ pub trait FiniteBitStream {
     fn bits_left(&self) -> u64;
 }
 
+/// `SeekBits` is implemented by bit readers that may be repositioned. This allows a codec to save
+/// and restore the position of the reader, or to undo a read past the end of a field.
+pub trait SeekBits {
+    /// Gets the number of bits read from the start of the bitstream.
+    fn bit_pos(&self) -> u64;
+
+    /// Seeks to the bit at `pos` bits from the start of the bitstream. Seeking to the end of the
+    /// bitstream is allowed, but seeking past it returns an error.
+    fn seek_bits(&mut self, pos: u64) -> io::Result<()>;
+
+    /// Rewinds the bitstream by `num_bits` bits such that they will be read again. Returns an error
+    /// if the bitstream would be rewound past its start.
+    fn rewind_bits(&mut self, num_bits: u64) -> io::Result<()> {
+        match self.bit_pos().checked_sub(num_bits) {
+            Some(pos) => self.seek_bits(pos),
+            None => start_of_bitstream_error(),
+        }
+    }
+}
+
 /// `ReadBitsLtr` reads bits from most-significant to least-significant.
 pub trait ReadBitsLtr: private::FetchBitsLtr {
     /// Discards any saved bits and resets the `BitStream` to prepare it for a byte-aligned read.
@@ -892,6 +1112,12 @@ pub trait ReadBitsLtr: private::FetchBitsLtr {
 ///
 /// Stated another way, if N-bits are read from a `BitReaderLtr` then bit 0, the first bit read,
 /// is the most-significant bit, and bit N-1, the last bit read, is the least-significant.
+///
+/// To minimize the number of reads from the source, bits are read from the source 64-bits at a
+/// time into a cache. Therefore, a `BitStreamLtr` may read up-to 8 bytes past the last bit
+/// consumed. These bytes are lost when the `BitStreamLtr` is dropped. If the source will be read
+/// after the bitstream, then the bitstream should be read in multiples of 64-bits, or a
+/// [`BitReaderLtr`] should be used on a buffer instead.
 pub struct BitStreamLtr<'a, B: ReadBytes> {
     reader: &'a mut B,
     bits: u64,
@@ -906,16 +1132,36 @@ impl<'a, B: ReadBytes> BitStreamLtr<'a, B> {
 }
 
 impl<'a, B: ReadBytes> private::FetchBitsLtr for BitStreamLtr<'a, B> {
-    #[inline(always)]
-    fn fetch_bits(&mut self) -> io::Result<()> {
-        self.bits = u64::from(self.reader.read_u8()?) << 56;
-        self.n_bits_left = u8::BITS;
+    #[inline]
+    fn fetch_bits_partial(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; std::mem::size_of::<u64>()];
+
+        let len = (u64::BITS - self.n_bits_left) as usize >> 3;
+
+        // Bits that were already fetched may still be consumed if the source ended, therefore an
+        // end-of-stream error is not an error here.
+        let read_len = match self.reader.read_buf(&mut buf[..len]) {
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => 0,
+            Err(err) => return Err(err),
+        };
+
+        self.bits |= u64::from_be_bytes(buf) >> self.n_bits_left;
+        self.n_bits_left += (read_len as u32) << 3;
+
         Ok(())
     }
 
-    #[inline(always)]
-    fn fetch_bits_partial(&mut self) -> io::Result<()> {
-        todo!()
+    fn fetch_bits(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; std::mem::size_of::<u64>()];
+
+        // Read a whole 64-bit word, or as many bytes as are left in the source.
+        let read_len = self.reader.read_buf(&mut buf)?;
+
+        self.bits = u64::from_be_bytes(buf);
+        self.n_bits_left = (read_len as u32) << 3;
+
+        Ok(())
     }
 
     #[inline(always)]
@@ -942,6 +1188,8 @@ impl<'a, B: ReadBytes> ReadBitsLtr for BitStreamLtr<'a, B> {}
 /// Stated another way, if N-bits are read from a `BitReaderLtr` then bit 0, the first bit read,
 /// is the most-significant bit, and bit N-1, the last bit read, is the least-significant.
 pub struct BitReaderLtr<'a> {
+    /// The entire buffer, used to seek.
+    origin: &'a [u8],
     buf: &'a [u8],
     bits: u64,
     n_bits_left: u32,
@@ -950,7 +1198,7 @@ pub struct BitReaderLtr<'a> {
 impl<'a> BitReaderLtr<'a> {
     /// Instantiate a new `BitReaderLtr` with the given buffer.
     pub fn new(buf: &'a [u8]) -> Self {
-        BitReaderLtr { buf, bits: 0, n_bits_left: 0 }
+        BitReaderLtr { origin: buf, buf, bits: 0, n_bits_left: 0 }
     }
 }
 
@@ -1015,6 +1263,25 @@ impl<'a> FiniteBitStream for BitReaderLtr<'a> {
     }
 }
 
+impl<'a> SeekBits for BitReaderLtr<'a> {
+    fn bit_pos(&self) -> u64 {
+        8 * (self.origin.len() - self.buf.len()) as u64 - u64::from(self.n_bits_left)
+    }
+
+    fn seek_bits(&mut self, pos: u64) -> io::Result<()> {
+        if pos > 8 * self.origin.len() as u64 {
+            return end_of_bitstream_error();
+        }
+
+        // Discard the saved bits, and restart reading at the byte containing the bit.
+        self.buf = &self.origin[(pos >> 3) as usize..];
+        self.bits = 0;
+        self.n_bits_left = 0;
+
+        self.ignore_bits((pos & 0x7) as u32)
+    }
+}
+
 /// `ReadBitsRtl` reads bits from least-significant to most-significant.
 pub trait ReadBitsRtl: private::FetchBitsRtl {
     /// Discards any saved bits and resets the `BitStream` to prepare it for a byte-aligned read.
@@ -1391,6 +1658,8 @@ impl<'a, B: ReadBytes> ReadBitsRtl for BitStreamRtl<'a, B> {}
 /// Stated another way, if N-bits are read from a `BitReaderRtl` then bit 0, the first bit read,
 /// is the least-significant bit, and bit N-1, the last bit read, is the most-significant.
 pub struct BitReaderRtl<'a> {
+    /// The entire buffer, used to seek.
+    origin: &'a [u8],
     buf: &'a [u8],
     bits: u64,
     n_bits_left: u32,
@@ -1399,7 +1668,7 @@ pub struct BitReaderRtl<'a> {
 impl<'a> BitReaderRtl<'a> {
     /// Instantiate a new `BitReaderRtl` with the given buffer.
     pub fn new(buf: &'a [u8]) -> Self {
-        BitReaderRtl { buf, bits: 0, n_bits_left: 0 }
+        BitReaderRtl { origin: buf, buf, bits: 0, n_bits_left: 0 }
     }
 }
 
@@ -1464,11 +1733,307 @@ impl<'a> FiniteBitStream for BitReaderRtl<'a> {
     }
 }
 
+impl<'a> SeekBits for BitReaderRtl<'a> {
+    fn bit_pos(&self) -> u64 {
+        8 * (self.origin.len() - self.buf.len()) as u64 - u64::from(self.n_bits_left)
+    }
+
+    fn seek_bits(&mut self, pos: u64) -> io::Result<()> {
+        if pos > 8 * self.origin.len() as u64 {
+            return end_of_bitstream_error();
+        }
+
+        // Discard the saved bits, and restart reading at the byte containing the bit.
+        self.buf = &self.origin[(pos >> 3) as usize..];
+        self.bits = 0;
+        self.n_bits_left = 0;
+
+        self.ignore_bits((pos & 0x7) as u32)
+    }
+}
+
+/// Gets a mask of the lower `num` bits, where `num` <= 32.
+#[inline(always)]
+fn mask_leq32(num: u32) -> u32 {
+    ((1u64 << num) - 1) as u32
+}
+
+/// `WriteBitsLtr` writes bits from most-significant to least-significant.
+pub trait WriteBitsLtr: private::StoreBitsLtr {
+    /// Writes 0 bits until the bitstream is byte-aligned.
+    #[inline(always)]
+    fn realign(&mut self) -> io::Result<()> {
+        let pad = (8 - self.num_bits_stored()) & 0x7;
+        self.store_bits(0, pad)
+    }
+
+    /// Writes a single bit, 1 if `bit` is true, or 0 otherwise.
+    #[inline(always)]
+    fn write_bool(&mut self, bit: bool) -> io::Result<()> {
+        self.store_bits(u32::from(bit), 1)
+    }
+
+    /// Writes the least-significant bit of `bit`.
+    #[inline(always)]
+    fn write_bit(&mut self, bit: u32) -> io::Result<()> {
+        self.store_bits(bit & 1, 1)
+    }
+
+    /// Writes the lower `bit_width` bits, up to 32-bits, of `bits`.
+    #[inline(always)]
+    fn write_bits_leq32(&mut self, bits: u32, bit_width: u32) -> io::Result<()> {
+        debug_assert!(bit_width <= u32::BITS);
+        self.store_bits(bits & mask_leq32(bit_width), bit_width)
+    }
+
+    /// Writes `value` as a signed two's complement integer of `bit_width` bits, up to 32-bits.
+    #[inline(always)]
+    fn write_bits_leq32_signed(&mut self, value: i32, bit_width: u32) -> io::Result<()> {
+        self.write_bits_leq32(value as u32, bit_width)
+    }
+
+    /// Writes the lower `bit_width` bits, up to 64-bits, of `bits`.
+    #[inline(always)]
+    fn write_bits_leq64(&mut self, bits: u64, bit_width: u32) -> io::Result<()> {
+        debug_assert!(bit_width <= u64::BITS);
+
+        // Write the upper part first.
+        if bit_width > u32::BITS {
+            self.write_bits_leq32((bits >> u32::BITS) as u32, bit_width - u32::BITS)?;
+            self.write_bits_leq32(bits as u32, u32::BITS)
+        }
+        else {
+            self.write_bits_leq32(bits as u32, bit_width)
+        }
+    }
+
+    /// Writes `value` as a signed two's complement integer of `bit_width` bits, up to 64-bits.
+    #[inline(always)]
+    fn write_bits_leq64_signed(&mut self, value: i64, bit_width: u32) -> io::Result<()> {
+        self.write_bits_leq64(value as u64, bit_width)
+    }
+
+    /// Writes `num` as a unary zeros encoded integer: `num` 0 bits followed by a 1 bit.
+    #[inline(always)]
+    fn write_unary_zeros(&mut self, mut num: u32) -> io::Result<()> {
+        while num >= u32::BITS {
+            self.store_bits(0, u32::BITS)?;
+            num -= u32::BITS;
+        }
+
+        self.store_bits(1, num + 1)
+    }
+
+    /// Writes `num` as a unary ones encoded integer: `num` 1 bits followed by a 0 bit.
+    #[inline(always)]
+    fn write_unary_ones(&mut self, mut num: u32) -> io::Result<()> {
+        while num >= u32::BITS {
+            self.store_bits(u32::MAX, u32::BITS)?;
+            num -= u32::BITS;
+        }
+
+        self.store_bits(mask_leq32(num + 1) & !1, num + 1)
+    }
+}
+
+/// `BitWriterLtr` writes bits from most-significant to least-significant into a `Vec<u8>`.
+///
+/// Stated another way, if N-bits are written to a `BitWriterLtr` then bit 0, the first bit
+/// written, is the most-significant bit, and bit N-1, the last bit written, is the
+/// least-significant.
+#[derive(Default)]
+pub struct BitWriterLtr {
+    buf: Vec<u8>,
+    bits: u64,
+    n_bits: u32,
+}
+
+impl BitWriterLtr {
+    /// Instantiate a new, empty, `BitWriterLtr`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Gets the number of bits written.
+    pub fn bit_pos(&self) -> u64 {
+        8 * self.buf.len() as u64 + u64::from(self.n_bits)
+    }
+
+    /// Consumes the `BitWriterLtr` and returns the written bytes. If the bitstream is not
+    /// byte-aligned, the last byte is padded with 0 bits.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        // Writing to a vector never fails.
+        self.realign().unwrap();
+        self.buf
+    }
+}
+
+impl private::StoreBitsLtr for BitWriterLtr {
+    #[inline(always)]
+    fn store_bits(&mut self, bits: u32, num: u32) -> io::Result<()> {
+        // There are always less than 8 stored bits, therefore up-to 32 more bits always fit.
+        self.bits = (self.bits << num) | u64::from(bits);
+        self.n_bits += num;
+
+        while self.n_bits >= 8 {
+            self.n_bits -= 8;
+            self.buf.push((self.bits >> self.n_bits) as u8);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn num_bits_stored(&self) -> u32 {
+        self.n_bits
+    }
+}
+
+impl WriteBitsLtr for BitWriterLtr {}
+
+/// `WriteBitsRtl` writes bits from least-significant to most-significant.
+pub trait WriteBitsRtl: private::StoreBitsRtl {
+    /// Writes 0 bits until the bitstream is byte-aligned.
+    #[inline(always)]
+    fn realign(&mut self) -> io::Result<()> {
+        let pad = (8 - self.num_bits_stored()) & 0x7;
+        self.store_bits(0, pad)
+    }
+
+    /// Writes a single bit, 1 if `bit` is true, or 0 otherwise.
+    #[inline(always)]
+    fn write_bool(&mut self, bit: bool) -> io::Result<()> {
+        self.store_bits(u32::from(bit), 1)
+    }
+
+    /// Writes the least-significant bit of `bit`.
+    #[inline(always)]
+    fn write_bit(&mut self, bit: u32) -> io::Result<()> {
+        self.store_bits(bit & 1, 1)
+    }
+
+    /// Writes the lower `bit_width` bits, up to 32-bits, of `bits`.
+    #[inline(always)]
+    fn write_bits_leq32(&mut self, bits: u32, bit_width: u32) -> io::Result<()> {
+        debug_assert!(bit_width <= u32::BITS);
+        self.store_bits(bits & mask_leq32(bit_width), bit_width)
+    }
+
+    /// Writes `value` as a signed two's complement integer of `bit_width` bits, up to 32-bits.
+    #[inline(always)]
+    fn write_bits_leq32_signed(&mut self, value: i32, bit_width: u32) -> io::Result<()> {
+        self.write_bits_leq32(value as u32, bit_width)
+    }
+
+    /// Writes the lower `bit_width` bits, up to 64-bits, of `bits`.
+    #[inline(always)]
+    fn write_bits_leq64(&mut self, bits: u64, bit_width: u32) -> io::Result<()> {
+        debug_assert!(bit_width <= u64::BITS);
+
+        // Write the lower part first.
+        if bit_width > u32::BITS {
+            self.write_bits_leq32(bits as u32, u32::BITS)?;
+            self.write_bits_leq32((bits >> u32::BITS) as u32, bit_width - u32::BITS)
+        }
+        else {
+            self.write_bits_leq32(bits as u32, bit_width)
+        }
+    }
+
+    /// Writes `value` as a signed two's complement integer of `bit_width` bits, up to 64-bits.
+    #[inline(always)]
+    fn write_bits_leq64_signed(&mut self, value: i64, bit_width: u32) -> io::Result<()> {
+        self.write_bits_leq64(value as u64, bit_width)
+    }
+
+    /// Writes `num` as a unary zeros encoded integer: `num` 0 bits followed by a 1 bit.
+    #[inline(always)]
+    fn write_unary_zeros(&mut self, mut num: u32) -> io::Result<()> {
+        while num >= u32::BITS {
+            self.store_bits(0, u32::BITS)?;
+            num -= u32::BITS;
+        }
+
+        self.store_bits(1 << num, num + 1)
+    }
+
+    /// Writes `num` as a unary ones encoded integer: `num` 1 bits followed by a 0 bit.
+    #[inline(always)]
+    fn write_unary_ones(&mut self, mut num: u32) -> io::Result<()> {
+        while num >= u32::BITS {
+            self.store_bits(u32::MAX, u32::BITS)?;
+            num -= u32::BITS;
+        }
+
+        self.store_bits(mask_leq32(num), num + 1)
+    }
+}
+
+/// `BitWriterRtl` writes bits from least-significant to most-significant into a `Vec<u8>`.
+///
+/// Stated another way, if N-bits are written to a `BitWriterRtl` then bit 0, the first bit
+/// written, is the least-significant bit, and bit N-1, the last bit written, is the
+/// most-significant.
+#[derive(Default)]
+pub struct BitWriterRtl {
+    buf: Vec<u8>,
+    bits: u64,
+    n_bits: u32,
+}
+
+impl BitWriterRtl {
+    /// Instantiate a new, empty, `BitWriterRtl`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Gets the number of bits written.
+    pub fn bit_pos(&self) -> u64 {
+        8 * self.buf.len() as u64 + u64::from(self.n_bits)
+    }
+
+    /// Consumes the `BitWriterRtl` and returns the written bytes. If the bitstream is not
+    /// byte-aligned, the last byte is padded with 0 bits.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        // Writing to a vector never fails.
+        self.realign().unwrap();
+        self.buf
+    }
+}
+
+impl private::StoreBitsRtl for BitWriterRtl {
+    #[inline(always)]
+    fn store_bits(&mut self, bits: u32, num: u32) -> io::Result<()> {
+        // There are always less than 8 stored bits, therefore up-to 32 more bits always fit.
+        self.bits |= u64::from(bits) << self.n_bits;
+        self.n_bits += num;
+
+        while self.n_bits >= 8 {
+            self.buf.push(self.bits as u8);
+            self.bits >>= 8;
+            self.n_bits -= 8;
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn num_bits_stored(&self) -> u32 {
+        self.n_bits
+    }
+}
+
+impl WriteBitsRtl for BitWriterRtl {}
+
 #[cfg(test)]
 mod tests {
     use super::vlc::{BitOrder, Codebook, CodebookBuilder, Entry8x8};
-    use super::{BitReaderLtr, ReadBitsLtr};
-    use super::{BitReaderRtl, ReadBitsRtl};
+    use super::{BitReaderLtr, BitStreamLtr, ReadBitsLtr};
+    use super::{BitReaderRtl, BitStreamRtl, ReadBitsRtl};
+    use super::{BitWriterLtr, WriteBitsLtr};
+    use super::{BitWriterRtl, WriteBitsRtl};
+    use super::{FiniteBitStream, SeekBits};
+    use crate::io::{BufReader, ReadBytes};
 
     #[test]
     #[allow(clippy::bool_assert_comparison)]
@@ -1638,6 +2203,57 @@ mod tests {
         assert!(bs.read_bits_leq64(9).is_err());
     }
 
+    #[test]
+    fn verify_bitstreamltr_read_bits_signed() {
+        let mut bs = BitReaderLtr::new(&[
+            0b1011_0100,
+            0x80,
+            0x00,
+            0x00,
+            0x00,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+        ]);
+
+        // Widths of 0 and 1, and positive and negative values.
+        assert_eq!(bs.read_bits_leq32_signed(0).unwrap(), 0);
+        assert_eq!(bs.read_bits_leq32_signed(1).unwrap(), -1);
+        assert_eq!(bs.read_bits_leq32_signed(2).unwrap(), 1);
+        assert_eq!(bs.read_bits_leq32_signed(3).unwrap(), -3);
+        assert_eq!(bs.read_bits_leq64_signed(2).unwrap(), 0);
+
+        // Full width reads.
+        assert_eq!(bs.read_bits_leq32_signed(32).unwrap(), i32::MIN);
+        assert_eq!(bs.read_bits_leq64_signed(64).unwrap(), -1);
+    }
+
+    #[test]
+    fn verify_bitstreamltr_read_bits_leq64_unaligned() {
+        let buf = [0x0f, 0xed, 0xcb, 0xa9, 0x87, 0x65, 0x43, 0x21, 0x0f];
+
+        // A 64-bit read that is not byte-aligned spans 9 bytes.
+        let mut bs = BitReaderLtr::new(&buf);
+
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0x0);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0xfedcba9876543210);
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0xf);
+
+        // The same read from a stream that fetches one byte at a time.
+        let mut reader = BufReader::new(&buf);
+        let mut bs = BitStreamLtr::new(&mut reader);
+
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0x0);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0xfedcba9876543210);
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0xf);
+        assert!(bs.read_bits_leq64(1).is_err());
+    }
+
     #[test]
     fn verify_bitstreamltr_read_unary_zeros() {
         // General tests
@@ -1758,6 +2374,18 @@ mod tests {
     }
 
     fn generate_codebook(bit_order: BitOrder) -> (Codebook<Entry8x8>, Vec<u8>, &'static str) {
+        generate_codebook_with(bit_order, |builder, code_words, code_lens, values| {
+            builder.make(code_words, code_lens, values).unwrap()
+        })
+    }
+
+    fn generate_codebook_with<F>(
+        bit_order: BitOrder,
+        make: F,
+    ) -> (Codebook<Entry8x8>, Vec<u8>, &'static str)
+    where
+        F: Fn(&mut CodebookBuilder, &[u32], &[u8], &[u8]) -> Codebook<Entry8x8>,
+    {
         // Codewords in MSb bit-order.
         #[rustfmt::skip]
         const CODE_WORDS: [u32; 25] = [
@@ -1816,7 +2444,7 @@ mod tests {
 
         // Construct a codebook using the tables above.
         let mut builder = CodebookBuilder::new(bit_order);
-        let codebook = builder.make::<Entry8x8>(&CODE_WORDS, &CODE_LENS, &VALUES).unwrap();
+        let codebook = make(&mut builder, &CODE_WORDS, &CODE_LENS, &VALUES);
 
         (codebook, data, TEXT)
     }
@@ -1828,11 +2456,97 @@ mod tests {
         let mut bs = BitReaderLtr::new(&buf);
 
         let decoded: Vec<u8> =
-            (0..text.len()).into_iter().map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
+            (0..text.len()).map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
+
+        assert_eq!(text, std::str::from_utf8(&decoded).unwrap());
+
+        // The same codewords from a stream.
+        let mut reader = BufReader::new(&buf);
+        let mut bs = BitStreamLtr::new(&mut reader);
+
+        let decoded: Vec<u8> =
+            (0..text.len()).map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
 
         assert_eq!(text, std::str::from_utf8(&decoded).unwrap());
     }
 
+    #[test]
+    fn verify_bitstreamltr_stream_read_ahead() {
+        let buf: Vec<u8> = (0..20).collect();
+
+        let mut reader = BufReader::new(&buf);
+
+        // Bits are read from the source 64-bits at a time.
+        let mut bs = BitStreamLtr::new(&mut reader);
+        assert_eq!(bs.read_bits_leq32(4).unwrap(), 0x0);
+        assert_eq!(bs.read_bits_leq32(12).unwrap(), 0x001);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0x0203_0405_0607_0809);
+        assert_eq!(reader.pos(), 16);
+
+        // If the source ends, the remaining bytes are read.
+        let mut bs = BitStreamLtr::new(&mut reader);
+        assert_eq!(bs.read_bits_leq32(32).unwrap(), 0x1011_1213);
+        assert!(bs.read_bit().is_err());
+    }
+
+    #[test]
+    fn verify_codebook_make_optimal() {
+        // The table has 129 entries if all codewords are decoded with one read, or 45 entries for 5
+        // bits, and 37 entries for 4 bits, in the first read. If no width fits, the width of the
+        // subsequent reads (4 bits) is used.
+        for &(max_table_len, expected_table_len) in &[(1024, 129), (64, 45), (40, 37), (0, 37)] {
+            let (codebook, buf, text) = generate_codebook_with(
+                BitOrder::Verbatim,
+                |builder, code_words, code_lens, values| {
+                    builder.make_optimal(code_words, code_lens, values, max_table_len).unwrap()
+                },
+            );
+
+            assert_eq!(codebook.table.len(), expected_table_len);
+
+            let mut bs = BitReaderLtr::new(&buf);
+
+            let decoded: Vec<u8> =
+                (0..text.len()).map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
+
+            assert_eq!(text, std::str::from_utf8(&decoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn verify_codebook_make_canonical() {
+        // The canonical code of the DEFLATE specification (RFC 1951 section 3.2.2): F=00, A=010,
+        // B=011, C=100, D=101, E=110, G=1110, H=1111.
+        const CODE_LENS: [u8; 8] = [3, 3, 3, 3, 3, 2, 4, 4];
+        const VALUES: [u8; 8] = [b'A', b'B', b'C', b'D', b'E', b'F', b'G', b'H'];
+
+        let mut builder = CodebookBuilder::new(BitOrder::Verbatim);
+        let codebook = builder.make_canonical::<Entry8x8>(&CODE_LENS, &VALUES).unwrap();
+
+        // 00 010 011 100 101 110 1110 1111
+        let mut bs = BitReaderLtr::new(&[0x13, 0x97, 0x77, 0x80]);
+
+        let decoded: Vec<u8> = (0..8).map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
+
+        assert_eq!(decoded, b"FABCDEGH");
+
+        // Over-subscribed, and incomplete, codes.
+        assert!(builder.make_canonical::<Entry8x8>(&[1, 1, 1], &[0, 1, 2]).is_err());
+        assert!(builder.make_canonical::<Entry8x8>(&[1, 2], &[0, 1]).is_err());
+        assert!(builder.make_canonical::<Entry8x8>(&[1, 0, 1], &[0, 1, 2]).is_err());
+
+        // A sparse codebook may be incomplete, and have unused values.
+        let mut builder = CodebookBuilder::new_sparse(BitOrder::Verbatim);
+        let codebook = builder.make_canonical::<Entry8x8>(&[1, 0, 2], &[0, 1, 2]).unwrap();
+
+        // 0 10 0
+        let mut bs = BitReaderLtr::new(&[0x40]);
+
+        assert_eq!(bs.read_codebook(&codebook).unwrap().0, 0);
+        assert_eq!(bs.read_codebook(&codebook).unwrap().0, 2);
+        assert_eq!(bs.read_codebook(&codebook).unwrap().0, 0);
+    }
+
     // BitStreamRtl
 
     #[test]
@@ -2007,6 +2721,27 @@ mod tests {
         assert!(bs.read_bits_leq64(9).is_err());
     }
 
+    #[test]
+    fn verify_bitstreamrtl_read_bits_leq64_unaligned() {
+        let buf = [0xf0, 0xde, 0xbc, 0x9a, 0x78, 0x56, 0x34, 0x12, 0xf0];
+
+        // A 64-bit read that is not byte-aligned spans 9 bytes.
+        let mut bs = BitReaderRtl::new(&buf);
+
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0x0);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0x0123456789abcdef);
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0xf);
+
+        // The same read from a stream that fetches one byte at a time.
+        let mut reader = BufReader::new(&buf);
+        let mut bs = BitStreamRtl::new(&mut reader);
+
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0x0);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0x0123456789abcdef);
+        assert_eq!(bs.read_bits_leq64(4).unwrap(), 0xf);
+        assert!(bs.read_bits_leq64(1).is_err());
+    }
+
     #[test]
     fn verify_bitstreamrtl_read_unary_zeros() {
         // General tests
@@ -2124,8 +2859,156 @@ mod tests {
         let mut bs = BitReaderRtl::new(&buf);
 
         let decoded: Vec<u8> =
-            (0..text.len()).into_iter().map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
+            (0..text.len()).map(|_| bs.read_codebook(&codebook).unwrap().0).collect();
 
         assert_eq!(text, std::str::from_utf8(&decoded).unwrap());
     }
+
+    #[test]
+    fn verify_bitstreamltr_seek_bits() {
+        let mut bs = BitReaderLtr::new(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x0f]);
+
+        assert_eq!(bs.read_bits_leq32(12).unwrap(), 0x123);
+        assert_eq!(bs.bit_pos(), 12);
+
+        // Undo a read.
+        bs.rewind_bits(8).unwrap();
+        assert_eq!(bs.bit_pos(), 4);
+        assert_eq!(bs.read_bits_leq32(12).unwrap(), 0x234);
+
+        // Save and restore the position across a refill of the bit cache.
+        let pos = bs.bit_pos();
+        assert_eq!(bs.read_bits_leq64(56).unwrap(), 0x56_789a_bcde_f00f);
+        bs.seek_bits(pos).unwrap();
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x56);
+
+        // Seek to the end, and past it.
+        bs.seek_bits(72).unwrap();
+        assert_eq!(bs.bits_left(), 0);
+        assert!(bs.read_bit().is_err());
+        assert!(bs.seek_bits(73).is_err());
+        assert!(bs.rewind_bits(73).is_err());
+
+        bs.seek_bits(0).unwrap();
+        assert_eq!(bs.bits_left(), 72);
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x12);
+    }
+
+    #[test]
+    fn verify_bitstreamrtl_seek_bits() {
+        let mut bs = BitReaderRtl::new(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x0f]);
+
+        assert_eq!(bs.read_bits_leq32(12).unwrap(), 0x412);
+        assert_eq!(bs.bit_pos(), 12);
+
+        // Undo a read.
+        bs.rewind_bits(8).unwrap();
+        assert_eq!(bs.bit_pos(), 4);
+        assert_eq!(bs.read_bits_leq32(12).unwrap(), 0x341);
+
+        // Save and restore the position across a refill of the bit cache.
+        let pos = bs.bit_pos();
+        assert_eq!(bs.read_bits_leq64(56).unwrap(), 0x0f_f0de_bc9a_7856);
+        bs.seek_bits(pos).unwrap();
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x56);
+
+        // Seek to the end, and past it.
+        bs.seek_bits(72).unwrap();
+        assert_eq!(bs.bits_left(), 0);
+        assert!(bs.read_bit().is_err());
+        assert!(bs.seek_bits(73).is_err());
+        assert!(bs.rewind_bits(73).is_err());
+
+        bs.seek_bits(0).unwrap();
+        assert_eq!(bs.bits_left(), 72);
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x12);
+    }
+
+    #[test]
+    fn verify_bitwriterltr() {
+        let mut bw = BitWriterLtr::new();
+
+        bw.write_bool(true).unwrap();
+        bw.write_bits_leq32(0b101, 3).unwrap();
+        bw.write_unary_zeros(2).unwrap();
+        bw.write_bits_leq32(0xabc, 12).unwrap();
+        assert_eq!(bw.bit_pos(), 19);
+
+        assert_eq!(bw.into_inner(), [0xd3, 0x57, 0x80]);
+
+        // Values written are read back.
+        let mut bw = BitWriterLtr::new();
+
+        bw.write_bit(0).unwrap();
+        bw.write_bits_leq32(0xffff_ffff, 32).unwrap();
+        bw.write_bits_leq32_signed(-5, 7).unwrap();
+        bw.write_bits_leq64(0x0123_4567_89ab_cdef, 64).unwrap();
+        bw.write_bits_leq64_signed(-0x1_2345_6789, 37).unwrap();
+        bw.write_bits_leq32(0, 0).unwrap();
+        bw.write_unary_zeros(70).unwrap();
+        bw.write_unary_ones(33).unwrap();
+        bw.write_unary_ones(0).unwrap();
+        bw.realign().unwrap();
+        assert_eq!(bw.bit_pos() % 8, 0);
+        bw.write_bits_leq32(0x5a, 8).unwrap();
+
+        let buf = bw.into_inner();
+        let mut bs = BitReaderLtr::new(&buf);
+
+        assert_eq!(bs.read_bit().unwrap(), 0);
+        assert_eq!(bs.read_bits_leq32(32).unwrap(), 0xffff_ffff);
+        assert_eq!(bs.read_bits_leq32_signed(7).unwrap(), -5);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0x0123_4567_89ab_cdef);
+        assert_eq!(bs.read_bits_leq64_signed(37).unwrap(), -0x1_2345_6789);
+        assert_eq!(bs.read_unary_zeros().unwrap(), 70);
+        assert_eq!(bs.read_unary_ones().unwrap(), 33);
+        assert_eq!(bs.read_unary_ones().unwrap(), 0);
+        bs.realign();
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x5a);
+        assert_eq!(bs.bits_left(), 0);
+    }
+
+    #[test]
+    fn verify_bitwriterrtl() {
+        let mut bw = BitWriterRtl::new();
+
+        bw.write_bool(true).unwrap();
+        bw.write_bits_leq32(0b101, 3).unwrap();
+        bw.write_unary_zeros(2).unwrap();
+        bw.write_bits_leq32(0xabc, 12).unwrap();
+        assert_eq!(bw.bit_pos(), 19);
+
+        assert_eq!(bw.into_inner(), [0x4b, 0x5e, 0x05]);
+
+        // Values written are read back.
+        let mut bw = BitWriterRtl::new();
+
+        bw.write_bit(0).unwrap();
+        bw.write_bits_leq32(0xffff_ffff, 32).unwrap();
+        bw.write_bits_leq32_signed(-5, 7).unwrap();
+        bw.write_bits_leq64(0x0123_4567_89ab_cdef, 64).unwrap();
+        bw.write_bits_leq64_signed(-0x1_2345_6789, 37).unwrap();
+        bw.write_bits_leq32(0, 0).unwrap();
+        bw.write_unary_zeros(70).unwrap();
+        bw.write_unary_ones(33).unwrap();
+        bw.write_unary_ones(0).unwrap();
+        bw.realign().unwrap();
+        assert_eq!(bw.bit_pos() % 8, 0);
+        bw.write_bits_leq32(0x5a, 8).unwrap();
+
+        let buf = bw.into_inner();
+        let mut bs = BitReaderRtl::new(&buf);
+
+        assert_eq!(bs.read_bit().unwrap(), 0);
+        assert_eq!(bs.read_bits_leq32(32).unwrap(), 0xffff_ffff);
+        assert_eq!(bs.read_bits_leq32_signed(7).unwrap(), -5);
+        assert_eq!(bs.read_bits_leq64(64).unwrap(), 0x0123_4567_89ab_cdef);
+        assert_eq!(bs.read_bits_leq64_signed(37).unwrap(), -0x1_2345_6789);
+        assert_eq!(bs.read_unary_zeros().unwrap(), 70);
+        assert_eq!(bs.read_unary_ones().unwrap(), 33);
+        assert_eq!(bs.read_unary_ones().unwrap(), 0);
+        bs.realign();
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0x5a);
+        assert_eq!(bs.bits_left(), 0);
+    }
 }
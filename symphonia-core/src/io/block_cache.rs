@@ -0,0 +1,410 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+use super::MediaSource;
+
+/// A `BlockCache` stores fixed-length blocks of bytes fetched from a [`MediaSource`], indexed by
+/// the position of the block in the source divided by the block length.
+///
+/// Applications may implement this trait to provide their own cache backend, for example, to
+/// persist downloaded blocks to disk, or to share a cache between sources.
+pub trait BlockCache: Send + Sync {
+    /// Gets the length in bytes of a block.
+    fn block_len(&self) -> usize;
+
+    /// Gets the block with the given index, if it is cached. The last block of a source may be
+    /// shorter than the block length.
+    fn get(&mut self, index: u64) -> Option<Arc<[u8]>>;
+
+    /// Inserts the block with the given index into the cache. The cache may discard the block, or
+    /// evict other blocks, at any time.
+    fn insert(&mut self, index: u64, block: Arc<[u8]>);
+
+    /// Removes all blocks from the cache.
+    fn clear(&mut self);
+}
+
+/// A `LruBlockCache` is an in-memory [`BlockCache`] that evicts the least-recently used block
+/// once it holds the maximum number of blocks.
+pub struct LruBlockCache {
+    block_len: usize,
+    max_blocks: usize,
+    /// The cached blocks, and the time each was last used.
+    blocks: HashMap<u64, (Arc<[u8]>, u64)>,
+    clock: u64,
+}
+
+impl LruBlockCache {
+    /// Instantiate a new `LruBlockCache` of at most `max_blocks` blocks of `block_len` bytes each.
+    pub fn new(block_len: usize, max_blocks: usize) -> Self {
+        assert!(block_len > 0, "block length must be greater than 0");
+
+        LruBlockCache { block_len, max_blocks, blocks: HashMap::new(), clock: 0 }
+    }
+
+    /// Gets the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true if no blocks are cached.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl BlockCache for LruBlockCache {
+    fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    fn get(&mut self, index: u64) -> Option<Arc<[u8]>> {
+        let now = self.tick();
+
+        self.blocks.get_mut(&index).map(|(block, last_used)| {
+            *last_used = now;
+            block.clone()
+        })
+    }
+
+    fn insert(&mut self, index: u64, block: Arc<[u8]>) {
+        if self.max_blocks == 0 {
+            return;
+        }
+
+        if self.blocks.len() >= self.max_blocks && !self.blocks.contains_key(&index) {
+            let lru = self.blocks.iter().min_by_key(|(_, (_, last_used))| *last_used);
+
+            if let Some(&lru) = lru.map(|(index, _)| index) {
+                self.blocks.remove(&lru);
+            }
+        }
+
+        let now = self.tick();
+        self.blocks.insert(index, (block, now));
+    }
+
+    fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+/// A `CachedSource` is a [`MediaSource`] that caches the blocks of bytes read from an inner
+/// `MediaSource` in a [`BlockCache`].
+///
+/// Seeking a `CachedSource` is lazy. The inner source is only seeked when a block that is not
+/// cached must be fetched. Therefore, seeking within already fetched regions of a remote source,
+/// such as an HTTP stream, does not refetch them. If the inner source is not seekable, blocks are
+/// only ever fetched in order.
+pub struct CachedSource<S: MediaSource> {
+    inner: S,
+    cache: Box<dyn BlockCache>,
+    /// The position of the inner source.
+    inner_pos: u64,
+    /// The position of the `CachedSource`.
+    pos: u64,
+    /// The block at the current position.
+    block: Option<(u64, Arc<[u8]>)>,
+}
+
+impl<S: MediaSource> CachedSource<S> {
+    /// The default block length of 64 kB.
+    const DEFAULT_BLOCK_LEN: usize = 64 * 1024;
+
+    /// The default number of cached blocks, for a maximum of 16 MB.
+    const DEFAULT_MAX_BLOCKS: usize = 256;
+
+    /// Instantiate a new `CachedSource` that caches `inner` in an [`LruBlockCache`] of up-to 16 MB.
+    pub fn new(inner: S) -> Self {
+        let cache = LruBlockCache::new(Self::DEFAULT_BLOCK_LEN, Self::DEFAULT_MAX_BLOCKS);
+        Self::new_with_cache(inner, Box::new(cache))
+    }
+
+    /// Instantiate a new `CachedSource` that caches `inner` in the provided [`BlockCache`].
+    ///
+    /// The inner source must be positioned at its start.
+    pub fn new_with_cache(inner: S, cache: Box<dyn BlockCache>) -> Self {
+        assert!(cache.block_len() > 0, "block length must be greater than 0");
+
+        CachedSource { inner, cache, inner_pos: 0, pos: 0, block: None }
+    }
+
+    /// Gets a reference to the inner source.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Gets a reference to the cache.
+    pub fn cache(&self) -> &dyn BlockCache {
+        self.cache.as_ref()
+    }
+
+    /// Unwraps this `CachedSource`, returning the inner source.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Reads a whole block, or until the end of the inner source, at the current position of the
+    /// inner source.
+    fn read_block(&mut self) -> io::Result<Arc<[u8]>> {
+        let mut block = vec![0; self.cache.block_len()];
+        let mut len = 0;
+
+        while len < block.len() {
+            match self.inner.read(&mut block[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        block.truncate(len);
+        self.inner_pos += len as u64;
+
+        Ok(Arc::from(block))
+    }
+
+    /// Fetches the block with the given index from the inner source, and caches it.
+    fn fetch(&mut self, index: u64) -> io::Result<Arc<[u8]>> {
+        let block_len = self.cache.block_len() as u64;
+        let start = index * block_len;
+
+        if self.inner_pos != start {
+            if self.inner.is_seekable() {
+                self.inner_pos = self.inner.seek(SeekFrom::Start(start))?;
+            }
+            else if self.inner_pos > start {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "source does not support seeking backwards",
+                ));
+            }
+        }
+
+        // If the inner source is not seekable, fetch, and cache, every block up-to the block.
+        loop {
+            let fetched = self.inner_pos / block_len;
+            let block = self.read_block()?;
+
+            let is_end = block.len() < block_len as usize;
+
+            self.cache.insert(fetched, block.clone());
+
+            if fetched == index {
+                return Ok(block);
+            }
+            else if is_end {
+                // The inner source ended before the block.
+                return Ok(Arc::from(Vec::new()));
+            }
+        }
+    }
+}
+
+impl<S: MediaSource> Read for CachedSource<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let block_len = self.cache.block_len() as u64;
+        let index = self.pos / block_len;
+        let offset = (self.pos % block_len) as usize;
+
+        let block = match &self.block {
+            Some((current, block)) if *current == index => block.clone(),
+            _ => {
+                let block = match self.cache.get(index) {
+                    Some(block) => block,
+                    None => self.fetch(index)?,
+                };
+
+                self.block = Some((index, block.clone()));
+                block
+            }
+        };
+
+        // The position is at, or past, the end of the inner source.
+        if offset >= block.len() {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(block.len() - offset);
+        buf[..len].copy_from_slice(&block[offset..offset + len]);
+
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<S: MediaSource> Seek for CachedSource<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(delta) if delta < 0 => self.pos.checked_sub(delta.unsigned_abs()),
+            SeekFrom::Current(delta) => self.pos.checked_add(delta as u64),
+            SeekFrom::End(delta) => match self.inner.byte_len() {
+                Some(len) if delta < 0 => len.checked_sub(delta.unsigned_abs()),
+                Some(len) => len.checked_add(delta as u64),
+                None => {
+                    return Err(io::Error::new(io::ErrorKind::Other, "source length is unknown"))
+                }
+            },
+        };
+
+        self.pos = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position")
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+impl<S: MediaSource> MediaSource for CachedSource<S> {
+    fn is_seekable(&self) -> bool {
+        self.inner.is_seekable()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.inner.byte_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+    use std::sync::{Arc, Mutex};
+
+    use super::{BlockCache, CachedSource, LruBlockCache};
+    use crate::io::{MediaSource, ReadOnlySource};
+
+    /// A source that counts the bytes read from it.
+    struct CountingSource {
+        inner: Cursor<Vec<u8>>,
+        n_read: Arc<Mutex<usize>>,
+    }
+
+    impl Read for CountingSource {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.inner.read(buf)?;
+            *self.n_read.lock().unwrap() += len;
+            Ok(len)
+        }
+    }
+
+    impl Seek for CountingSource {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl MediaSource for CountingSource {
+        fn is_seekable(&self) -> bool {
+            true
+        }
+
+        fn byte_len(&self) -> Option<u64> {
+            Some(self.inner.get_ref().len() as u64)
+        }
+    }
+
+    fn data() -> Vec<u8> {
+        (0..1000).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn verify_lru_block_cache() {
+        let mut cache = LruBlockCache::new(4, 2);
+
+        cache.insert(0, Arc::from(&[0u8; 4][..]));
+        cache.insert(1, Arc::from(&[1u8; 4][..]));
+
+        // Using block 0 makes block 1 the least-recently used block.
+        assert!(cache.get(0).is_some());
+
+        cache.insert(2, Arc::from(&[2u8; 4][..]));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(1).is_none());
+        assert_eq!(&*cache.get(0).unwrap(), &[0; 4]);
+        assert_eq!(&*cache.get(2).unwrap(), &[2; 4]);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn verify_cached_source() {
+        let n_read = Arc::new(Mutex::new(0));
+        let inner = CountingSource { inner: Cursor::new(data()), n_read: n_read.clone() };
+
+        let mut source = CachedSource::new_with_cache(inner, Box::new(LruBlockCache::new(64, 32)));
+
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, data());
+        assert_eq!(*n_read.lock().unwrap(), 1000);
+
+        // Seeks within fetched regions do not refetch.
+        let mut buf = [0; 100];
+        source.seek(SeekFrom::Start(130)).unwrap();
+        source.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data()[130..230]);
+
+        source.seek(SeekFrom::End(-10)).unwrap();
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &data()[990..]);
+
+        assert_eq!(*n_read.lock().unwrap(), 1000);
+
+        // Evicted blocks are refetched.
+        source.cache.clear();
+        source.block = None;
+
+        let mut buf = [0; 10];
+        source.seek(SeekFrom::Start(500)).unwrap();
+        source.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data()[500..510]);
+
+        assert_eq!(*n_read.lock().unwrap(), 1064);
+    }
+
+    #[test]
+    fn verify_cached_source_non_seekable() {
+        let inner = ReadOnlySource::new(Cursor::new(data()));
+
+        let mut source = CachedSource::new_with_cache(inner, Box::new(LruBlockCache::new(64, 32)));
+
+        // Forward seeks fetch every block in order.
+        let mut buf = [0; 10];
+        source.seek(SeekFrom::Start(300)).unwrap();
+        source.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data()[300..310]);
+
+        // Backward seeks are served from the cache.
+        source.seek(SeekFrom::Start(5)).unwrap();
+        source.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data()[5..15]);
+
+        // Reads past the end.
+        source.seek(SeekFrom::Start(2000)).unwrap();
+        assert_eq!(source.read(&mut buf).unwrap(), 0);
+    }
+}
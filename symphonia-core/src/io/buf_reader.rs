@@ -90,6 +90,27 @@ impl<'a> BufReader<'a> {
         self.pos = self.buf.len();
         &self.buf[pos..]
     }
+
+    /// Returns a reference to the next `len` bytes in the buffer without advancing the stream.
+    pub fn peek_buf_bytes_ref(&self, len: usize) -> io::Result<&'a [u8]> {
+        if self.pos + len > self.buf.len() {
+            return underrun_error();
+        }
+        Ok(&self.buf[self.pos..self.pos + len])
+    }
+
+    /// Returns a reference to the remaining bytes in the buffer without advancing the stream.
+    pub fn peek_buf_bytes_available_ref(&self) -> &'a [u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Returns the next byte in the buffer without advancing the stream.
+    pub fn peek_byte(&self) -> io::Result<u8> {
+        match self.buf.get(self.pos) {
+            Some(&byte) => Ok(byte),
+            None => underrun_error(),
+        }
+    }
 }
 
 impl<'a> ReadBytes for BufReader<'a> {
@@ -143,6 +164,10 @@ impl<'a> ReadBytes for BufReader<'a> {
     }
 
     fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !buf.is_empty() && self.pos >= self.buf.len() {
+            return underrun_error();
+        }
+
         let len = cmp::min(self.buf.len() - self.pos, buf.len());
         buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
         self.pos += len;
@@ -206,3 +231,33 @@ impl<'a> FiniteStream for BufReader<'a> {
         (self.buf.len() - self.pos) as u64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BufReader;
+    use crate::io::ReadBytes;
+
+    #[test]
+    fn verify_buf_reader_peek() {
+        let buf = [0, 1, 2, 3, 4, 5];
+
+        let mut reader = BufReader::new(&buf);
+
+        // Peeking does not advance the stream.
+        assert_eq!(reader.peek_byte().unwrap(), 0);
+        assert_eq!(reader.peek_buf_bytes_ref(4).unwrap(), &[0, 1, 2, 3]);
+        assert_eq!(reader.read_buf_bytes_ref(2).unwrap(), &[0, 1]);
+
+        assert_eq!(reader.peek_buf_bytes_available_ref(), &[2, 3, 4, 5]);
+        assert!(reader.peek_buf_bytes_ref(5).is_err());
+        assert_eq!(reader.pos(), 2);
+
+        // The returned slices borrow the underlying buffer, not the reader.
+        let peeked = reader.peek_buf_bytes_ref(2).unwrap();
+        reader.ignore_bytes(4).unwrap();
+        assert_eq!(peeked, &[2, 3]);
+
+        assert!(reader.peek_byte().is_err());
+        assert!(reader.peek_buf_bytes_available_ref().is_empty());
+    }
+}
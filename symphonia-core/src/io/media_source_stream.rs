@@ -49,6 +49,10 @@ impl Default for MediaSourceStreamOptions {
 /// length buffer cache. By default, the buffer caches allows backtracking by up-to the minimum of
 /// either `buffer_len - 32kB` or the total number of bytes read since instantiation or the last
 /// buffer cache invalidation. Note that regular a `seek()` will invalidate the buffer cache.
+///
+/// Third, if the inner reader is not seekable, such as a pipe or standard input, `seek()` is
+/// emulated. Seeks within the buffer cache do not invalidate it, forward seeks consume bytes, and
+/// backward seeks beyond the buffer cache return an error.
 pub struct MediaSourceStream {
     /// The source reader.
     inner: Box<dyn MediaSource>,
@@ -147,6 +151,34 @@ impl MediaSourceStream {
         Ok(())
     }
 
+    /// Rewinds the `len` bytes consumed by a read that failed with `err`, if the source stalled,
+    /// such that the read may be retried from the same position. If the consumed bytes are no
+    /// longer buffered, the read cannot be retried, and a non-retryable error is returned instead.
+    fn rewind_stalled_read(&mut self, len: usize, err: io::Error) -> io::Error {
+        let is_stalled = matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut);
+
+        if is_stalled && len > 0 {
+            if len > self.read_buffer_len() {
+                return io::Error::new(io::ErrorKind::Other, "source stalled during a large read");
+            }
+            self.seek_buffered_rev(len);
+        }
+
+        err
+    }
+
+    /// Reads bytes one at a time until `bytes` is filled. If the read fails, the bytes read so far
+    /// are rewound.
+    fn read_bytes_slow(&mut self, bytes: &mut [u8]) -> io::Result<()> {
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            match self.read_byte() {
+                Ok(value) => *byte = value,
+                Err(err) => return Err(self.rewind_stalled_read(i, err)),
+            }
+        }
+        Ok(())
+    }
+
     /// Advances the read position by `len` bytes, taking into account wrap-around.
     #[inline(always)]
     fn consume(&mut self, len: usize) {
@@ -191,8 +223,15 @@ impl io::Read for MediaSourceStream {
         let read_len = buf.len();
 
         while !buf.is_empty() {
-            // Refill the the buffer cache if required.
-            self.fetch()?;
+            // Refill the the buffer cache if required. If the refill fails after some bytes were
+            // read, return those bytes so that they are not lost if the read is retried. The error
+            // will be returned by the next read.
+            if let Err(err) = self.fetch() {
+                if buf.len() < read_len {
+                    break;
+                }
+                return Err(err);
+            }
 
             // Consume bytes from the readable portion of the buffer cache and copy them into the
             // remaining portion of the caller's buffer.
@@ -213,8 +252,49 @@ impl io::Read for MediaSourceStream {
     }
 }
 
+impl MediaSourceStream {
+    /// Emulates a seek on a non-seekable source. Seeks within the buffer cache are served from the
+    /// buffer cache, and forward seeks beyond it are performed by consuming bytes. Seeking
+    /// backwards beyond the buffer cache, or relative to the end of the stream, is not possible.
+    fn seek_forward_only(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let cur_pos = self.pos();
+
+        let target = match pos {
+            io::SeekFrom::Start(pos) => Some(pos),
+            io::SeekFrom::Current(delta) if delta < 0 => cur_pos.checked_sub(delta.unsigned_abs()),
+            io::SeekFrom::Current(delta) => cur_pos.checked_add(delta as u64),
+            io::SeekFrom::End(_) => None,
+        };
+
+        let target = match target {
+            Some(target) if target >= cur_pos - self.read_buffer_len() as u64 => target,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "source does not support seeking backwards",
+                ))
+            }
+        };
+
+        if target <= cur_pos + self.unread_buffer_len() as u64 {
+            self.seek_buffered(target);
+        }
+        else {
+            self.ignore_bytes(target - cur_pos)?;
+        }
+
+        Ok(self.pos())
+    }
+}
+
 impl io::Seek for MediaSourceStream {
     fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        // A non-seekable source can only be read forward, but seeks within the buffer cache, or
+        // forward seeks, may still be emulated.
+        if !self.is_seekable() {
+            return self.seek_forward_only(pos);
+        }
+
         // The current position of the underlying reader is ahead of the current position of the
         // MediaSourceStream by how ever many bytes have not been read from the read-ahead buffer
         // yet. When seeking from the current position adjust the position delta to offset that
@@ -260,9 +340,7 @@ impl ReadBytes for MediaSourceStream {
             self.consume(2);
         }
         else {
-            for byte in bytes.iter_mut() {
-                *byte = self.read_byte()?;
-            }
+            self.read_bytes_slow(&mut bytes)?;
         };
 
         Ok(bytes)
@@ -278,9 +356,7 @@ impl ReadBytes for MediaSourceStream {
             self.consume(3);
         }
         else {
-            for byte in bytes.iter_mut() {
-                *byte = self.read_byte()?;
-            }
+            self.read_bytes_slow(&mut bytes)?;
         };
         Ok(bytes)
     }
@@ -295,16 +371,25 @@ impl ReadBytes for MediaSourceStream {
             self.consume(4);
         }
         else {
-            for byte in bytes.iter_mut() {
-                *byte = self.read_byte()?;
-            }
+            self.read_bytes_slow(&mut bytes)?;
         };
         Ok(bytes)
     }
 
     fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        // Implemented via io::Read trait.
-        let read = self.read(buf)?;
+        let mut read = 0;
+
+        // Implemented via io::Read trait. The inner source may return less data than requested
+        // even if it has not reached the end of the stream, so keep reading until the buffer is
+        // filled, or no more data can be read.
+        while read < buf.len() {
+            match self.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(count) => read += count,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.rewind_stalled_read(read, e)),
+            }
+        }
 
         // Unlike the io::Read trait, ByteStream returns an end-of-stream error when no more data
         // can be read. If a non-zero read is requested, and 0 bytes are read, return an
@@ -318,6 +403,8 @@ impl ReadBytes for MediaSourceStream {
     }
 
     fn read_buf_exact(&mut self, mut buf: &mut [u8]) -> io::Result<()> {
+        let len = buf.len();
+
         while !buf.is_empty() {
             match self.read(buf) {
                 Ok(0) => break,
@@ -325,7 +412,7 @@ impl ReadBytes for MediaSourceStream {
                     buf = &mut buf[count..];
                 }
                 Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
-                Err(e) => return Err(e),
+                Err(e) => return Err(self.rewind_stalled_read(len - buf.len(), e)),
             }
         }
 
@@ -468,7 +555,40 @@ impl SeekBuffered for MediaSourceStream {
 #[cfg(test)]
 mod tests {
     use super::{MediaSourceStream, ReadBytes, SeekBuffered};
-    use std::io::{Cursor, Read};
+    use crate::io::ReadOnlySource;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+    /// A reader that returns at most 7 bytes per read, like a slow network stream.
+    struct ChunkedReader {
+        inner: Cursor<Box<[u8]>>,
+    }
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(7);
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    /// A reader that returns at most 7 bytes on every third read, and a would-block error on all
+    /// other reads, like a network stream that stalls.
+    struct StallingReader {
+        inner: Cursor<Box<[u8]>>,
+        n_reads: usize,
+    }
+
+    impl Read for StallingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.n_reads += 1;
+
+            if self.n_reads % 3 != 0 {
+                return Err(io::Error::new(io::ErrorKind::WouldBlock, "stalled"));
+            }
+
+            let len = buf.len().min(7);
+            self.inner.read(&mut buf[..len])
+        }
+    }
 
     /// Generate a random vector of bytes of the specified length using a PRNG.
     fn generate_random_bytes(len: usize) -> Box<[u8]> {
@@ -531,6 +651,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn verify_mss_read_buf() {
+        let data = generate_random_bytes(1000);
+
+        let ms = ChunkedReader { inner: Cursor::new(data.clone()) };
+        let mut mss = MediaSourceStream::new(Box::new(ReadOnlySource::new(ms)), Default::default());
+
+        // Short reads from the source do not result in short reads from the stream.
+        let mut buf = [0; 600];
+        assert_eq!(ReadBytes::read_buf(&mut mss, &mut buf).unwrap(), 600);
+        assert_eq!(&buf[..], &data[..600]);
+
+        // A short read indicates the end of the stream.
+        assert_eq!(ReadBytes::read_buf(&mut mss, &mut buf).unwrap(), 400);
+        assert_eq!(&buf[..400], &data[600..]);
+
+        // Reading at the end of the stream is an error, unless the buffer is empty.
+        assert_eq!(ReadBytes::read_buf(&mut mss, &mut []).unwrap(), 0);
+        assert_eq!(
+            ReadBytes::read_buf(&mut mss, &mut buf).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
     #[test]
     fn verify_mss_read_to_end() {
         let data = generate_random_bytes(5 * 96 * 1024);
@@ -572,6 +716,100 @@ mod tests {
         assert_eq!(upper, mss.read_byte().unwrap());
     }
 
+    #[test]
+    fn verify_mss_read_stalled() {
+        let data = generate_random_bytes(1000);
+
+        let ms = StallingReader { inner: Cursor::new(data.clone()), n_reads: 0 };
+        let mut mss = MediaSourceStream::new(Box::new(ReadOnlySource::new(ms)), Default::default());
+
+        // Bytes read before the source stalls are returned, and are not lost when retrying.
+        let mut out = Vec::new();
+        let mut buf = [0; 20];
+
+        loop {
+            match mss.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => out.extend_from_slice(&buf[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        assert_eq!(&out[..], &data[..]);
+    }
+
+    #[test]
+    fn verify_mss_read_buf_exact_stalled() {
+        let data = generate_random_bytes(1000);
+
+        let ms = StallingReader { inner: Cursor::new(data.clone()), n_reads: 0 };
+        let mut mss = MediaSourceStream::new(Box::new(ReadOnlySource::new(ms)), Default::default());
+
+        // A read that stalls after partially filling the buffer rewinds the bytes it consumed, such
+        // that retrying it reads the same bytes.
+        let mut out = Vec::new();
+        let mut buf = [0; 20];
+
+        while out.len() < data.len() {
+            match ReadBytes::read_buf_exact(&mut mss, &mut buf) {
+                Ok(()) => out.extend_from_slice(&buf),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        assert_eq!(&out[..], &data[..]);
+
+        // The same applies to multi-byte reads.
+        let ms = StallingReader { inner: Cursor::new(data.clone()), n_reads: 0 };
+        let mut mss = MediaSourceStream::new(Box::new(ReadOnlySource::new(ms)), Default::default());
+
+        let mut out = Vec::new();
+
+        while out.len() < data.len() {
+            match mss.read_quad_bytes() {
+                Ok(quad) => out.extend_from_slice(&quad),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        assert_eq!(&out[..], &data[..]);
+    }
+
+    #[test]
+    fn verify_mss_seek_non_seekable() {
+        let data = generate_random_bytes(256 * 1024);
+
+        let ms = ReadOnlySource::new(Cursor::new(data.clone()));
+        let mut mss = MediaSourceStream::new(Box::new(ms), Default::default());
+
+        mss.ignore_bytes(1000).unwrap();
+
+        // Seeks backwards within the buffer cache.
+        assert_eq!(mss.seek(SeekFrom::Start(10)).unwrap(), 10);
+        assert_eq!(mss.read_byte().unwrap(), data[10]);
+
+        assert_eq!(mss.seek(SeekFrom::Current(-11)).unwrap(), 0);
+        assert_eq!(mss.read_byte().unwrap(), data[0]);
+
+        // Seeks forwards beyond the buffer cache.
+        assert_eq!(mss.seek(SeekFrom::Start(200_000)).unwrap(), 200_000);
+        assert_eq!(mss.read_byte().unwrap(), data[200_000]);
+
+        assert_eq!(mss.seek(SeekFrom::Current(999)).unwrap(), 201_000);
+        assert_eq!(mss.read_byte().unwrap(), data[201_000]);
+
+        // Seeks backwards beyond the buffer cache, or from the end, are not possible.
+        assert!(mss.seek(SeekFrom::Start(0)).is_err());
+        assert!(mss.seek(SeekFrom::End(0)).is_err());
+        assert_eq!(mss.pos(), 201_001);
+
+        // Seeks past the end of the stream.
+        assert!(mss.seek(SeekFrom::Start(300_000)).is_err());
+    }
+
     #[test]
     fn verify_reading_be() {
         let data = generate_random_bytes(1024 * 1024);
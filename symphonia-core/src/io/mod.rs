@@ -22,13 +22,19 @@
 use std::io;
 use std::mem;
 
+#[cfg(feature = "async")]
+pub(crate) mod async_source;
 mod bit;
+mod block_cache;
 mod buf_reader;
 mod media_source_stream;
 mod monitor_stream;
 mod scoped_stream;
 
+#[cfg(feature = "async")]
+pub use async_source::AsyncSource;
 pub use bit::*;
+pub use block_cache::{BlockCache, CachedSource, LruBlockCache};
 pub use buf_reader::BufReader;
 pub use media_source_stream::{MediaSourceStream, MediaSourceStreamOptions};
 pub use monitor_stream::{Monitor, MonitorStream};
@@ -74,6 +80,12 @@ impl MediaSource for std::fs::File {
     }
 }
 
+/// Any in-memory buffer wrapped in an [`std::io::Cursor`] is a seekable `MediaSource`.
+///
+/// This includes memory-mapped files. Mapping a file is `unsafe`, and therefore left to the
+/// application, but the resulting map (e.g., `memmap2::Mmap`) may be used as a `MediaSource` with
+/// `Cursor::new(mmap)`. Seeking within a memory-mapped file is free, which benefits random access
+/// to large files.
 impl<T: std::convert::AsRef<[u8]> + Send + Sync> MediaSource for io::Cursor<T> {
     /// Always returns true since a `io::Cursor<u8>` is always seekable.
     fn is_seekable(&self) -> bool {
@@ -142,6 +154,9 @@ impl<R: io::Read> io::Seek for ReadOnlySource<R> {
 
 /// `ReadBytes` provides methods to read bytes and interpret them as little- or big-endian
 /// unsigned integers or floating-point values of standard widths.
+///
+/// Methods without an endianness in their name, such as `read_u24`, read little-endian values.
+/// Methods prefixed with `read_be_`, such as `read_be_u24`, read big-endian values.
 pub trait ReadBytes {
     /// Reads a single byte from the stream and returns it or an error.
     fn read_byte(&mut self) -> io::Result<u8>;
@@ -155,10 +170,21 @@ pub trait ReadBytes {
     /// Reads four bytes from the stream and returns them in read-order or an error.
     fn read_quad_bytes(&mut self) -> io::Result<[u8; 4]>;
 
-    /// Reads up-to the number of bytes required to fill buf or returns an error.
+    /// Reads bytes into `buf` until it is filled, or the end of the stream is reached, and returns
+    /// the number of bytes read.
+    ///
+    /// Unlike `std::io::Read::read`, a short read is never returned just because the underlying
+    /// source delivered fewer bytes than requested. Therefore, if the number of bytes read is less
+    /// than the length of `buf`, then the end of the stream was reached. If the end of the stream
+    /// was reached before any bytes could be read, and `buf` is not empty, an end-of-stream error
+    /// is returned instead.
     fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize>;
 
-    /// Reads exactly the number of bytes required to fill be provided buffer or returns an error.
+    /// Reads exactly the number of bytes required to fill `buf` or returns an error.
+    ///
+    /// If the end of the stream is reached before `buf` is filled, an end-of-stream error is
+    /// returned. In this case, the contents of `buf` and the number of bytes consumed from the
+    /// stream are unspecified. Use `read_buf` if a partial read is acceptable.
     fn read_buf_exact(&mut self, buf: &mut [u8]) -> io::Result<()>;
 
     /// Reads a single unsigned byte from the stream and returns it or an error.
@@ -329,8 +355,84 @@ pub trait ReadBytes {
         Ok(f64::from_be_bytes(buf))
     }
 
-    /// Reads up-to the number of bytes requested, and returns a boxed slice of the data or an
-    /// error.
+    /// Reads a big-endian variable-length quantity (VLQ) of at most `max_len` bytes from the
+    /// stream. Each byte contributes its lower 7 bits to the value, and the most-significant bit
+    /// of each byte, except the last, is set. Used by MP4 descriptor lengths, and CAF.
+    fn read_vlq(&mut self, max_len: u32) -> io::Result<u64> {
+        let mut value = 0u64;
+
+        for _ in 0..max_len {
+            let byte = self.read_byte()?;
+
+            if value.leading_zeros() < 7 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "vlq overflows 64 bits"));
+            }
+
+            value = (value << 7) | u64::from(byte & 0x7f);
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::InvalidData, "vlq is unterminated"))
+    }
+
+    /// Reads an EBML variable-size integer (RFC 8794) from the stream, and returns its value and
+    /// its length in bytes. The number of leading zero bits of the first byte gives the number of
+    /// bytes that follow it. The length marker bit is removed from the value. Used by Matroska and
+    /// WebM.
+    fn read_ebml_vint(&mut self) -> io::Result<(u64, u32)> {
+        let byte = self.read_byte()?;
+
+        if byte == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "ebml vint is too long"));
+        }
+
+        let width = byte.leading_zeros();
+
+        // Clear the length marker bit.
+        let mut value = u64::from(byte ^ (0x80 >> width));
+
+        for _ in 0..width {
+            value = (value << 8) | u64::from(self.read_byte()?);
+        }
+
+        Ok((value, width + 1))
+    }
+
+    /// Reads an unsigned integer coded like a UTF-8 character from the stream. The coding is
+    /// extended to sequences of up-to 7 bytes for a maximum value of 36 bits. Used by FLAC frame
+    /// headers. Returns `None` if the first byte is not a valid UTF-8 leading byte.
+    fn read_utf8_u64(&mut self) -> io::Result<Option<u64>> {
+        let mut value = u64::from(self.read_byte()?);
+
+        // The number of leading 1 bits of the first byte gives the length of the sequence. Using
+        // ranges, determine the mask of the data bits of the first byte.
+        let mask: u8 = match value {
+            0x00..=0x7f => return Ok(Some(value)),
+            0xc0..=0xdf => 0x1f,
+            0xe0..=0xef => 0x0f,
+            0xf0..=0xf7 => 0x07,
+            0xf8..=0xfb => 0x03,
+            0xfc..=0xfd => 0x01,
+            0xfe => 0x00,
+            _ => return Ok(None),
+        };
+
+        value &= u64::from(mask);
+
+        // Each subsequent byte is prefixed with 0b10, therefore only 6 bits of each are data. The
+        // number of subsequent bytes is the number of leading zeros of the mask minus 2.
+        for _ in 2..mask.leading_zeros() {
+            value = (value << 6) | u64::from(self.read_byte()? & 0x3f);
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Reads the number of bytes requested, or until the end of the stream is reached, and returns
+    /// a boxed slice of the data or an error. See `read_buf` for details.
     fn read_boxed_slice(&mut self, len: usize) -> io::Result<Box<[u8]>> {
         let mut buf = vec![0u8; len];
         let actual_len = self.read_buf(&mut buf)?;
@@ -369,7 +471,7 @@ pub trait ReadBytes {
     fn pos(&self) -> u64;
 }
 
-impl<'b, R: ReadBytes> ReadBytes for &'b mut R {
+impl<R: ReadBytes> ReadBytes for &mut R {
     #[inline(always)]
     fn read_byte(&mut self) -> io::Result<u8> {
         (*self).read_byte()
@@ -421,7 +523,213 @@ impl<'b, R: ReadBytes> ReadBytes for &'b mut R {
     }
 }
 
-impl<'b, S: SeekBuffered> SeekBuffered for &'b mut S {
+/// `WriteBytes` provides methods to write little- or big-endian unsigned integers or
+/// floating-point values of standard widths as bytes. It is the counterpart of [`ReadBytes`].
+pub trait WriteBytes {
+    /// Writes a single byte to the stream or returns an error.
+    fn write_byte(&mut self, byte: u8) -> io::Result<()>;
+
+    /// Writes all the bytes of `buf` to the stream or returns an error.
+    fn write_buf(&mut self, buf: &[u8]) -> io::Result<()>;
+
+    /// Writes a single unsigned byte to the stream or returns an error.
+    #[inline(always)]
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_byte(value)
+    }
+
+    /// Writes a single signed byte to the stream or returns an error.
+    #[inline(always)]
+    fn write_i8(&mut self, value: i8) -> io::Result<()> {
+        self.write_byte(value as u8)
+    }
+
+    /// Writes `value` to the stream as an unsigned 16-bit little-endian integer or returns an
+    /// error.
+    #[inline(always)]
+    fn write_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as an unsigned 16-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 16-bit little-endian integer or returns an error.
+    #[inline(always)]
+    fn write_i16(&mut self, value: i16) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 16-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_i16(&mut self, value: i16) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes the lower 24 bits of `value` to the stream as an unsigned 24-bit little-endian
+    /// integer or returns an error.
+    #[inline(always)]
+    fn write_u24(&mut self, value: u32) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes()[..3])
+    }
+
+    /// Writes the lower 24 bits of `value` to the stream as an unsigned 24-bit big-endian integer
+    /// or returns an error.
+    #[inline(always)]
+    fn write_be_u24(&mut self, value: u32) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes()[1..])
+    }
+
+    /// Writes the lower 24 bits of `value` to the stream as a signed 24-bit little-endian integer
+    /// or returns an error.
+    #[inline(always)]
+    fn write_i24(&mut self, value: i32) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes()[..3])
+    }
+
+    /// Writes the lower 24 bits of `value` to the stream as a signed 24-bit big-endian integer or
+    /// returns an error.
+    #[inline(always)]
+    fn write_be_i24(&mut self, value: i32) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes()[1..])
+    }
+
+    /// Writes `value` to the stream as an unsigned 32-bit little-endian integer or returns an
+    /// error.
+    #[inline(always)]
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as an unsigned 32-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 32-bit little-endian integer or returns an error.
+    #[inline(always)]
+    fn write_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 32-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as an unsigned 64-bit little-endian integer or returns an
+    /// error.
+    #[inline(always)]
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as an unsigned 64-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 64-bit little-endian integer or returns an error.
+    #[inline(always)]
+    fn write_i64(&mut self, value: i64) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as a signed 64-bit big-endian integer or returns an error.
+    #[inline(always)]
+    fn write_be_i64(&mut self, value: i64) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as a 32-bit little-endian IEEE-754 floating-point value or
+    /// returns an error.
+    #[inline(always)]
+    fn write_f32(&mut self, value: f32) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as a 32-bit big-endian IEEE-754 floating-point value or returns
+    /// an error.
+    #[inline(always)]
+    fn write_be_f32(&mut self, value: f32) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `value` to the stream as a 64-bit little-endian IEEE-754 floating-point value or
+    /// returns an error.
+    #[inline(always)]
+    fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        self.write_buf(&value.to_le_bytes())
+    }
+
+    /// Writes `value` to the stream as a 64-bit big-endian IEEE-754 floating-point value or returns
+    /// an error.
+    #[inline(always)]
+    fn write_be_f64(&mut self, value: f64) -> io::Result<()> {
+        self.write_buf(&value.to_be_bytes())
+    }
+
+    /// Writes `count` zero bytes to the stream or returns an error.
+    fn write_zeros(&mut self, mut count: u64) -> io::Result<()> {
+        let zeros = [0; 64];
+
+        while count > 0 {
+            let len = count.min(zeros.len() as u64) as usize;
+            self.write_buf(&zeros[..len])?;
+            count -= len as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the position of the stream.
+    fn pos(&self) -> u64;
+}
+
+impl<W: WriteBytes> WriteBytes for &mut W {
+    #[inline(always)]
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        (*self).write_byte(byte)
+    }
+
+    #[inline(always)]
+    fn write_buf(&mut self, buf: &[u8]) -> io::Result<()> {
+        (*self).write_buf(buf)
+    }
+
+    #[inline(always)]
+    fn pos(&self) -> u64 {
+        (**self).pos()
+    }
+}
+
+/// Writing to a vector appends to it. The position is the length of the vector.
+impl WriteBytes for Vec<u8> {
+    #[inline(always)]
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.push(byte);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn write_buf(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn pos(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<S: SeekBuffered> SeekBuffered for &mut S {
     fn ensure_seekback_buffer(&mut self, len: usize) {
         (*self).ensure_seekback_buffer(len)
     }
@@ -480,7 +788,7 @@ pub trait SeekBuffered {
     }
 }
 
-impl<'b, F: FiniteStream> FiniteStream for &'b mut F {
+impl<F: FiniteStream> FiniteStream for &mut F {
     fn byte_len(&self) -> u64 {
         (**self).byte_len()
     }
@@ -505,3 +813,109 @@ pub trait FiniteStream {
     /// Returns the number of bytes available for reading.
     fn bytes_available(&self) -> u64;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BufReader, ReadBytes, WriteBytes};
+
+    #[test]
+    fn verify_read_vlq() {
+        let mut reader = BufReader::new(&[0x7f, 0x81, 0x00, 0xff, 0x7f, 0x81, 0x80, 0x00]);
+
+        assert_eq!(reader.read_vlq(4).unwrap(), 127);
+        assert_eq!(reader.read_vlq(4).unwrap(), 128);
+        assert_eq!(reader.read_vlq(4).unwrap(), 16383);
+        assert_eq!(reader.read_vlq(4).unwrap(), 16384);
+
+        // Unterminated.
+        assert!(BufReader::new(&[0x80, 0x80, 0x80, 0x80, 0x00]).read_vlq(4).is_err());
+
+        // Overflow.
+        assert!(BufReader::new(&[0xff; 10]).read_vlq(10).is_err());
+    }
+
+    #[test]
+    fn verify_read_ebml_vint() {
+        let mut reader = BufReader::new(&[0x82, 0x40, 0x02, 0x10, 0x00, 0x00, 0x02, 0xff, 0x00]);
+
+        assert_eq!(reader.read_ebml_vint().unwrap(), (0x02, 1));
+        assert_eq!(reader.read_ebml_vint().unwrap(), (0x02, 2));
+        assert_eq!(reader.read_ebml_vint().unwrap(), (0x02, 4));
+        assert_eq!(reader.read_ebml_vint().unwrap(), (0x7f, 1));
+        assert!(reader.read_ebml_vint().is_err());
+    }
+
+    #[test]
+    fn verify_read_utf8_u64() {
+        let mut reader = BufReader::new(&[
+            0x24, 0xc2, 0xa2, 0xe0, 0xa4, 0xb9, 0xe2, 0x82, //
+            0xac, 0xf0, 0x90, 0x8d, 0x88, 0xff, 0x80, 0xbf, //
+        ]);
+
+        assert_eq!(reader.read_utf8_u64().unwrap(), Some(36));
+        assert_eq!(reader.read_utf8_u64().unwrap(), Some(162));
+        assert_eq!(reader.read_utf8_u64().unwrap(), Some(2361));
+        assert_eq!(reader.read_utf8_u64().unwrap(), Some(8364));
+        assert_eq!(reader.read_utf8_u64().unwrap(), Some(66376));
+        assert_eq!(reader.read_utf8_u64().unwrap(), None);
+        assert_eq!(reader.read_utf8_u64().unwrap(), None);
+        assert_eq!(reader.read_utf8_u64().unwrap(), None);
+    }
+
+    #[test]
+    fn verify_read_24_bit() {
+        let mut reader = BufReader::new(&[
+            0x56, 0x34, 0x12, 0x12, 0x34, 0x56, 0xff, 0xff, 0x7f, 0x80, 0x00, 0x00, 0x00, 0x00,
+            0x80, 0xff, 0xff, 0xff,
+        ]);
+
+        assert_eq!(reader.read_u24().unwrap(), 0x123456);
+        assert_eq!(reader.read_be_u24().unwrap(), 0x123456);
+
+        // The largest and smallest signed values.
+        assert_eq!(reader.read_i24().unwrap(), 0x7fffff);
+        assert_eq!(reader.read_be_i24().unwrap(), -0x800000);
+        assert_eq!(reader.read_i24().unwrap(), -0x800000);
+        assert_eq!(reader.read_be_i24().unwrap(), -1);
+
+        assert!(reader.read_u24().is_err());
+    }
+
+    #[test]
+    fn verify_write_bytes() {
+        let mut buf = Vec::new();
+
+        buf.write_u8(0xfe).unwrap();
+        buf.write_i8(-2).unwrap();
+        buf.write_be_u16(0x1234).unwrap();
+        buf.write_i16(-0x1234).unwrap();
+        buf.write_u24(0x123456).unwrap();
+        buf.write_be_i24(-0x123456).unwrap();
+        buf.write_be_u32(0x1234_5678).unwrap();
+        buf.write_i32(-0x1234_5678).unwrap();
+        buf.write_u64(0x1234_5678_9abc_def0).unwrap();
+        buf.write_be_i64(-0x1234_5678_9abc_def0).unwrap();
+        buf.write_f32(1.5).unwrap();
+        buf.write_be_f64(-2.25).unwrap();
+        buf.write_zeros(100).unwrap();
+
+        assert_eq!(buf.pos(), 1 + 1 + 2 + 2 + 3 + 3 + 4 + 4 + 8 + 8 + 4 + 8 + 100);
+
+        // Every value reads back.
+        let mut reader = BufReader::new(&buf);
+
+        assert_eq!(reader.read_u8().unwrap(), 0xfe);
+        assert_eq!(reader.read_i8().unwrap(), -2);
+        assert_eq!(reader.read_be_u16().unwrap(), 0x1234);
+        assert_eq!(reader.read_i16().unwrap(), -0x1234);
+        assert_eq!(reader.read_u24().unwrap(), 0x123456);
+        assert_eq!(reader.read_be_i24().unwrap(), -0x123456);
+        assert_eq!(reader.read_be_u32().unwrap(), 0x1234_5678);
+        assert_eq!(reader.read_i32().unwrap(), -0x1234_5678);
+        assert_eq!(reader.read_u64().unwrap(), 0x1234_5678_9abc_def0);
+        assert_eq!(reader.read_be_i64().unwrap(), -0x1234_5678_9abc_def0);
+        assert_eq!(reader.read_f32().unwrap(), 1.5);
+        assert_eq!(reader.read_be_f64().unwrap(), -2.25);
+        assert_eq!(reader.read_boxed_slice_exact(100).unwrap(), vec![0; 100].into_boxed_slice());
+    }
+}
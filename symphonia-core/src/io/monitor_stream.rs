@@ -12,14 +12,17 @@ use super::ReadBytes;
 /// A `Monitor` provides a common interface to examine the operations observed be
 /// a [`MonitorStream`].
 pub trait Monitor {
+    /// Processes a single byte.
     fn process_byte(&mut self, byte: u8);
 
+    /// Processes two bytes.
     #[inline(always)]
     fn process_double_bytes(&mut self, buf: [u8; 2]) {
         self.process_byte(buf[0]);
         self.process_byte(buf[1]);
     }
 
+    /// Processes three bytes.
     #[inline(always)]
     fn process_triple_bytes(&mut self, buf: [u8; 3]) {
         self.process_byte(buf[0]);
@@ -27,6 +30,7 @@ pub trait Monitor {
         self.process_byte(buf[2]);
     }
 
+    /// Processes four bytes.
     #[inline(always)]
     fn process_quad_bytes(&mut self, buf: [u8; 4]) {
         self.process_byte(buf[0]);
@@ -35,11 +39,16 @@ pub trait Monitor {
         self.process_byte(buf[3]);
     }
 
+    /// Processes a buffer of bytes.
     fn process_buf_bytes(&mut self, buf: &[u8]);
 }
 
 /// A `MonitorStream` is a passive stream that observes all operations performed on the inner
 /// stream and forwards an immutable reference of the result to a [`Monitor`].
+///
+/// Every byte consumed from the inner stream, including ignored bytes, is observed by the monitor.
+/// For example, a `MonitorStream` with a CRC monitor transparently checksums everything read
+/// through it.
 pub struct MonitorStream<B: ReadBytes, M: Monitor> {
     inner: B,
     monitor: M,
@@ -123,8 +132,17 @@ impl<B: ReadBytes, M: Monitor> ReadBytes for MonitorStream<B, M> {
         Ok(result)
     }
 
-    fn ignore_bytes(&mut self, count: u64) -> io::Result<()> {
-        self.inner.ignore_bytes(count)
+    fn ignore_bytes(&mut self, mut count: u64) -> io::Result<()> {
+        // Ignored bytes must still be observed by the monitor, so read them instead.
+        let mut buf = [0; 256];
+
+        while count > 0 {
+            let len = count.min(buf.len() as u64) as usize;
+            self.read_buf_exact(&mut buf[..len])?;
+            count -= len as u64;
+        }
+
+        Ok(())
     }
 
     #[inline(always)]
@@ -132,3 +150,44 @@ impl<B: ReadBytes, M: Monitor> ReadBytes for MonitorStream<B, M> {
         self.inner.pos()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Monitor, MonitorStream};
+    use crate::checksum::{Crc16Ansi, Crc32, Crc8Ccitt};
+    use crate::io::{BufReader, ReadBytes};
+
+    /// Reads `buf` through a `MonitorStream` using every type of read.
+    fn read_monitored<M: Monitor>(buf: &[u8], monitor: M) -> MonitorStream<BufReader<'_>, M> {
+        let mut reader = MonitorStream::new(BufReader::new(buf), monitor);
+
+        reader.read_byte().unwrap();
+        reader.read_double_bytes().unwrap();
+        reader.read_triple_bytes().unwrap();
+        reader.read_quad_bytes().unwrap();
+        reader.ignore_bytes(300).unwrap();
+        reader.read_buf_exact(&mut [0; 5]).unwrap();
+
+        assert_eq!(reader.pos(), 315);
+
+        reader
+    }
+
+    #[test]
+    fn verify_monitor_stream() {
+        let buf: Vec<u8> = (0..315).map(|i| (i * 7) as u8).collect();
+
+        // The monitor observes every byte read, including ignored bytes.
+        let mut crc8 = Crc8Ccitt::new(0);
+        crc8.process_buf_bytes(&buf);
+        assert_eq!(read_monitored(&buf, Crc8Ccitt::new(0)).monitor().crc(), crc8.crc());
+
+        let mut crc16 = Crc16Ansi::new(0);
+        crc16.process_buf_bytes(&buf);
+        assert_eq!(read_monitored(&buf, Crc16Ansi::new(0)).monitor().crc(), crc16.crc());
+
+        let mut crc32 = Crc32::new(0);
+        crc32.process_buf_bytes(&buf);
+        assert_eq!(read_monitored(&buf, Crc32::new(0)).monitor().crc(), crc32.crc());
+    }
+}
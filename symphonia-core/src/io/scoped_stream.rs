@@ -16,6 +16,10 @@ fn out_of_bounds_error<T>() -> io::Result<T> {
 }
 
 /// A `ScopedStream` restricts the number of bytes that may be read to an upper limit.
+///
+/// A `ScopedStream` is typically used to bound the reads of a chunk, atom, or element of a
+/// container to its length. Reads beyond the end of the scope return an out of bounds error, and
+/// the unread remainder of the scope may be skipped with [`ScopedStream::ignore`].
 pub struct ScopedStream<B: ReadBytes> {
     inner: B,
     start: u64,
@@ -42,7 +46,10 @@ impl<B: ReadBytes> ScopedStream<B> {
 
     /// Ignores the remainder of the `ScopedStream`.
     pub fn ignore(&mut self) -> io::Result<()> {
-        self.inner.ignore_bytes(self.len - self.read)
+        let remaining = self.len - self.read;
+        self.inner.ignore_bytes(remaining)?;
+        self.read = self.len;
+        Ok(())
     }
 
     /// Convert the `ScopedStream` to the inner stream.
@@ -75,8 +82,9 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        let value = self.inner.read_byte()?;
         self.read += 1;
-        self.inner.read_byte()
+        Ok(value)
     }
 
     #[inline(always)]
@@ -85,8 +93,9 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        let value = self.inner.read_double_bytes()?;
         self.read += 2;
-        self.inner.read_double_bytes()
+        Ok(value)
     }
 
     #[inline(always)]
@@ -95,8 +104,9 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        let value = self.inner.read_triple_bytes()?;
         self.read += 3;
-        self.inner.read_triple_bytes()
+        Ok(value)
     }
 
     #[inline(always)]
@@ -105,13 +115,19 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        let value = self.inner.read_quad_bytes()?;
         self.read += 4;
-        self.inner.read_quad_bytes()
+        Ok(value)
     }
 
     fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         // Limit read_buf() to the remainder of the scoped bytes if buf has a greater length.
         let scoped_len = cmp::min(self.len - self.read, buf.len() as u64) as usize;
+
+        // The end of the scope is the end of the stream.
+        if !buf.is_empty() && scoped_len == 0 {
+            return out_of_bounds_error();
+        }
         let result = self.inner.read_buf(&mut buf[0..scoped_len])?;
         self.read += result as u64;
         Ok(result)
@@ -122,8 +138,9 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        self.inner.read_buf_exact(buf)?;
         self.read += buf.len() as u64;
-        self.inner.read_buf_exact(buf)
+        Ok(())
     }
 
     #[inline(always)]
@@ -133,11 +150,14 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
         align: usize,
         buf: &'a mut [u8],
     ) -> io::Result<&'a mut [u8]> {
-        if self.len - self.read < buf.len() as u64 {
+        // Limit the scan to the remainder of the scoped bytes if buf has a greater length.
+        let scoped_len = cmp::min(self.len - self.read, buf.len() as u64) as usize;
+
+        if !buf.is_empty() && scoped_len == 0 {
             return out_of_bounds_error();
         }
 
-        let result = self.inner.scan_bytes_aligned(pattern, align, buf)?;
+        let result = self.inner.scan_bytes_aligned(pattern, align, &mut buf[..scoped_len])?;
         self.read += result.len() as u64;
         Ok(result)
     }
@@ -148,8 +168,9 @@ impl<B: ReadBytes> ReadBytes for ScopedStream<B> {
             return out_of_bounds_error();
         }
 
+        self.inner.ignore_bytes(count)?;
         self.read += count;
-        self.inner.ignore_bytes(count)
+        Ok(())
     }
 
     #[inline(always)]
@@ -177,7 +198,9 @@ impl<B: ReadBytes + SeekBuffered> SeekBuffered for ScopedStream<B> {
     #[inline(always)]
     fn seek_buffered(&mut self, pos: u64) -> u64 {
         // Clamp the seekable position to within the bounds of the ScopedStream.
-        self.inner.seek_buffered(pos.clamp(self.start, self.start + self.len))
+        let pos = self.inner.seek_buffered(pos.clamp(self.start, self.start + self.len));
+        self.read = pos - self.start;
+        pos
     }
 
     #[inline(always)]
@@ -186,6 +209,90 @@ impl<B: ReadBytes + SeekBuffered> SeekBuffered for ScopedStream<B> {
         // within the bounds of the ScopedStream.
         let max_back = self.read.min(isize::MAX as u64) as isize;
         let max_forward = (self.len - self.read).min(isize::MAX as u64) as isize;
-        self.inner.seek_buffered_rel(delta.clamp(-max_back, max_forward))
+        let pos = self.inner.seek_buffered_rel(delta.clamp(-max_back, max_forward));
+        self.read = pos - self.start;
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScopedStream;
+    use std::io::Cursor;
+
+    use crate::io::{BufReader, FiniteStream, MediaSourceStream, ReadBytes, SeekBuffered};
+
+    const DATA: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    #[test]
+    fn verify_scoped_stream_bounds() {
+        let mut reader = BufReader::new(&DATA);
+        reader.read_byte().unwrap();
+
+        let mut scoped = ScopedStream::new(&mut reader, 6);
+
+        assert_eq!(scoped.read_double_bytes().unwrap(), [1, 2]);
+        assert_eq!(scoped.bytes_available(), 4);
+
+        // Reads beyond the scope fail, and do not consume any bytes.
+        assert!(scoped.read_buf_exact(&mut [0; 5]).is_err());
+        assert!(scoped.ignore_bytes(5).is_err());
+        assert_eq!(scoped.bytes_read(), 2);
+
+        // Reads are limited to the scope.
+        let mut buf = [0; 8];
+        assert_eq!(scoped.read_buf(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], &[3, 4, 5, 6]);
+        assert!(scoped.read_byte().is_err());
+
+        assert_eq!(reader.read_byte().unwrap(), 7);
+    }
+
+    #[test]
+    fn verify_scoped_stream_ignore() {
+        let mut reader = BufReader::new(&DATA);
+
+        let mut scoped = ScopedStream::new(&mut reader, 8);
+        scoped.read_quad_bytes().unwrap();
+
+        // Ignoring the remainder of the scope is idempotent.
+        scoped.ignore().unwrap();
+        scoped.ignore().unwrap();
+        assert_eq!(scoped.bytes_available(), 0);
+
+        assert_eq!(reader.read_byte().unwrap(), 8);
+    }
+
+    #[test]
+    fn verify_scoped_stream_scan() {
+        let mut reader = BufReader::new(&DATA);
+
+        let mut scoped = ScopedStream::new(&mut reader, 6);
+
+        // The scan is limited to the scope, even though the buffer is larger.
+        let mut buf = [0; 16];
+        assert_eq!(scoped.scan_bytes_aligned(&[10, 11], 1, &mut buf).unwrap().len(), 6);
+        assert_eq!(scoped.bytes_available(), 0);
+    }
+
+    #[test]
+    fn verify_scoped_stream_seek_buffered() {
+        let mut reader = MediaSourceStream::new(Box::new(Cursor::new(DATA)), Default::default());
+        reader.ignore_bytes(2).unwrap();
+
+        let mut scoped = ScopedStream::new(&mut reader, 8);
+        scoped.ignore_bytes(6).unwrap();
+
+        // Seeking backwards returns bytes to the scope.
+        assert_eq!(scoped.seek_buffered_rel(-4), 4);
+        assert_eq!(scoped.bytes_available(), 6);
+        assert_eq!(scoped.read_byte().unwrap(), 4);
+
+        // Seeks are clamped to the scope.
+        assert_eq!(scoped.seek_buffered(0), 2);
+        assert_eq!(scoped.bytes_read(), 0);
+
+        assert_eq!(scoped.seek_buffered(100), 10);
+        assert_eq!(scoped.bytes_available(), 0);
     }
 }
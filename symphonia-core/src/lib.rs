@@ -18,9 +18,11 @@ pub mod codecs;
 pub mod conv;
 pub mod dsp;
 pub mod errors;
+pub mod export;
 pub mod formats;
 pub mod io;
 pub mod meta;
+pub mod pacing;
 pub mod probe;
 pub mod sample;
 pub mod units;
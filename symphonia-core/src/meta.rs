@@ -23,7 +23,7 @@ use crate::io::MediaSourceStream;
 ///
 /// All limits can be defaulted to a reasonable value specific to the situation. These defaults will
 /// generally not break any normal streams.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Limit {
     /// Do not impose any limit.
     None,
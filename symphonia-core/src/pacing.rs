@@ -0,0 +1,225 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `pacing` module provides support for delivering decoded audio at a real-time rate.
+//!
+//! An audio device consumes audio at a real-time rate and thereby paces the decoder. A headless
+//! consumer, such as a streaming server, has no such device and must pace itself. A [`Pacer`]
+//! schedules the delivery of each audio buffer against a monotonic [`Clock`] based on the total
+//! duration of the audio delivered so far.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A `Clock` is a monotonic source of time.
+pub trait Clock {
+    /// Gets the time elapsed since an arbitrary, but fixed, epoch. The time must never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// A `MonotonicClock` is a high-resolution `Clock` backed by the monotonic clock of the operating
+/// system.
+#[derive(Copy, Clone, Debug)]
+pub struct MonotonicClock {
+    epoch: Instant,
+}
+
+impl MonotonicClock {
+    /// Instantiates a new `MonotonicClock` with an epoch of now.
+    pub fn new() -> Self {
+        MonotonicClock { epoch: Instant::now() }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// A `Pacer` schedules the delivery of audio buffers such that audio is delivered at a real-time
+/// rate.
+///
+/// For each audio buffer, call [`Pacer::wait`] to wait until the buffer is due, deliver the
+/// buffer, and then call [`Pacer::advance`] with the number of frames in the buffer. Since the
+/// schedule is derived from the total number of frames delivered, rather than from the time each
+/// buffer was delivered, timing errors do not accumulate.
+///
+/// The schedule starts when the first buffer is delivered.
+pub struct Pacer<C: Clock = MonotonicClock> {
+    clock: C,
+    /// The sample rate of the audio.
+    rate: u32,
+    /// The time at which the first frame was due, or `None` if no buffer has been delivered.
+    origin: Option<Duration>,
+    /// The number of frames delivered since `origin`.
+    frames: u64,
+    /// The amount of time a buffer may be delivered before it is due.
+    lead: Duration,
+}
+
+impl Pacer<MonotonicClock> {
+    /// Instantiates a new `Pacer` for audio with a sample rate of `rate` using a
+    /// `MonotonicClock`.
+    pub fn new(rate: u32) -> Self {
+        Pacer::with_clock(MonotonicClock::new(), rate)
+    }
+}
+
+impl<C: Clock> Pacer<C> {
+    /// Instantiates a new `Pacer` for audio with a sample rate of `rate` using the provided
+    /// clock.
+    pub fn with_clock(clock: C, rate: u32) -> Self {
+        assert!(rate > 0, "sample rate must be greater than 0");
+        Pacer { clock, rate, origin: None, frames: 0, lead: Duration::from_secs(0) }
+    }
+
+    /// Sets the amount of time a buffer may be delivered before it is due. A lead allows the
+    /// receiver of the audio to maintain a buffer of its own to absorb jitter. Default: 0.
+    pub fn set_lead(&mut self, lead: Duration) -> &mut Self {
+        self.lead = lead;
+        self
+    }
+
+    /// Changes the sample rate of the audio. Audio delivered before the change remains scheduled
+    /// at the old sample rate.
+    pub fn set_rate(&mut self, rate: u32) -> &mut Self {
+        assert!(rate > 0, "sample rate must be greater than 0");
+
+        if let Some(origin) = self.origin {
+            self.origin = Some(origin + self.position());
+            self.frames = 0;
+        }
+
+        self.rate = rate;
+        self
+    }
+
+    /// Gets the sample rate of the audio.
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Gets the total duration of the audio delivered since the schedule started.
+    pub fn position(&self) -> Duration {
+        let nanos = u128::from(self.frames) * 1_000_000_000 / u128::from(self.rate);
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Gets the amount of time until the next buffer is due. Returns zero if the next buffer is
+    /// already due.
+    pub fn delay(&self) -> Duration {
+        match self.origin {
+            Some(origin) => {
+                let due = (origin + self.position()).checked_sub(self.lead).unwrap_or_default();
+                due.checked_sub(self.clock.now()).unwrap_or_default()
+            }
+            _ => Duration::from_secs(0),
+        }
+    }
+
+    /// Gets the amount of time by which the delivery of the next buffer is late. A persistently
+    /// increasing lag indicates that the audio is being produced slower than real-time.
+    pub fn lag(&self) -> Duration {
+        match self.origin {
+            Some(origin) => {
+                self.clock.now().checked_sub(origin + self.position()).unwrap_or_default()
+            }
+            _ => Duration::from_secs(0),
+        }
+    }
+
+    /// Blocks the current thread until the next buffer is due.
+    pub fn wait(&self) {
+        let delay = self.delay();
+
+        if delay > Duration::from_secs(0) {
+            thread::sleep(delay);
+        }
+    }
+
+    /// Records that a buffer of `n_frames` frames was delivered. Starts the schedule if it was
+    /// not started.
+    pub fn advance(&mut self, n_frames: u64) {
+        if self.origin.is_none() {
+            self.origin = Some(self.clock.now());
+        }
+        self.frames += n_frames;
+    }
+
+    /// Restarts the schedule. The next buffer will be due immediately. Should be called whenever
+    /// the delivered audio is discontinuous (e.g., after a seek), or after a long stall, to
+    /// prevent the pacer from delivering audio faster than real-time to catch up.
+    pub fn reset(&mut self) {
+        self.origin = None;
+        self.frames = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, Pacer};
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    /// A clock that only advances when told to.
+    struct ManualClock<'a>(&'a Cell<Duration>);
+
+    impl Clock for ManualClock<'_> {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn verify_pacer() {
+        let now = Cell::new(ms(500));
+        let mut pacer = Pacer::with_clock(ManualClock(&now), 1_000);
+
+        // The first buffer is due immediately.
+        assert_eq!(pacer.delay(), ms(0));
+        pacer.advance(100);
+
+        // The next buffer is due after the duration of the first buffer.
+        assert_eq!(pacer.position(), ms(100));
+        assert_eq!(pacer.delay(), ms(100));
+
+        now.set(ms(560));
+        assert_eq!(pacer.delay(), ms(40));
+
+        // With a lead, the buffer is due earlier.
+        pacer.set_lead(ms(50));
+        assert_eq!(pacer.delay(), ms(0));
+        pacer.set_lead(ms(0));
+
+        // A late delivery does not shift the schedule.
+        now.set(ms(630));
+        assert_eq!(pacer.lag(), ms(30));
+        pacer.advance(100);
+        assert_eq!(pacer.delay(), ms(70));
+
+        // A change of sample rate keeps the audio delivered so far on schedule.
+        pacer.set_rate(2_000);
+        pacer.advance(200);
+        assert_eq!(pacer.delay(), ms(170));
+
+        // A reset restarts the schedule.
+        pacer.reset();
+        assert_eq!(pacer.delay(), ms(0));
+        assert_eq!(pacer.position(), ms(0));
+    }
+}
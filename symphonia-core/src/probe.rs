@@ -8,7 +8,11 @@
 //! The `probe` module provides methods and traits to support auto-detection of media formats from
 //! arbitrary media streams.
 
+use std::sync::Arc;
+
 use crate::errors::{unsupported_error, Result};
+use crate::formats::cache::AnalysisCache;
+use crate::formats::hash::HashedReader;
 use crate::formats::{FormatOptions, FormatReader};
 use crate::io::{MediaSourceStream, ReadBytes, SeekBuffered};
 use crate::meta::{Metadata, MetadataLog, MetadataOptions, MetadataReader};
@@ -84,12 +88,19 @@ mod bloom {
     }
 }
 
+/// Instantiation function for a `FormatReader` that can use an `AnalysisCache`.
+pub type InstantiateFormatWithCache =
+    fn(MediaSourceStream, &FormatOptions, Option<&AnalysisCache>) -> Result<Box<dyn FormatReader>>;
+
 /// `Instantiate` is an enumeration of instantiation functions used by `Descriptor` and `Probe` to
 /// instantiate metadata and container format readers.
 #[derive(Copy, Clone)]
 pub enum Instantiate {
     /// Instantiation function for a `FormatReader`.
     Format(fn(MediaSourceStream, &FormatOptions) -> Result<Box<dyn FormatReader>>),
+    /// Instantiation function for a `FormatReader` that can use the results of a previous
+    /// analysis of the media, see [`Hint::with_analysis_cache`].
+    FormatWithCache(InstantiateFormatWithCache),
     /// Instantiation function for a `MetadataReader`.
     Metadata(fn(&MetadataOptions) -> Box<dyn MetadataReader>),
 }
@@ -122,6 +133,10 @@ pub trait QueryDescriptor {
 
     /// Using the provided context buffer, score calculate and returns a value between 0 and 255
     /// indicating the confidence of the reader in decoding or parsing the source stream.
+    ///
+    /// The context buffer begins at the matched marker and contains up-to 8kB of the stream. A score
+    /// of 0 rejects the stream at this position, in which case the probe will continue searching.
+    /// If multiple readers match a marker, the reader with the highest score is selected.
     fn score(context: &[u8]) -> u8;
 }
 
@@ -136,12 +151,13 @@ pub trait QueryDescriptor {
 pub struct Hint {
     extension: Option<String>,
     mime_type: Option<String>,
+    analysis_cache: Option<Arc<AnalysisCache>>,
 }
 
 impl Hint {
     /// Instantiate an empty `Hint`.
     pub fn new() -> Self {
-        Hint { extension: None, mime_type: None }
+        Hint { extension: None, mime_type: None, analysis_cache: None }
     }
 
     /// Add a file extension `Hint`.
@@ -155,6 +171,15 @@ impl Hint {
         self.mime_type = Some(mime_type.to_owned());
         self
     }
+
+    /// Add the results of a previous analysis of the media.
+    ///
+    /// `FormatReader`s that support it use the cache, if it is still valid for the media, instead
+    /// of scanning the media again. See [`FormatReader::analysis_cache`].
+    pub fn with_analysis_cache(&mut self, cache: Arc<AnalysisCache>) -> &mut Self {
+        self.analysis_cache = Some(cache);
+        self
+    }
 }
 
 /// Metadata that came from the `metadata` field of [`ProbeResult`].
@@ -199,6 +224,27 @@ pub struct Probe {
 
 impl Probe {
     const PROBE_SEARCH_LIMIT: u64 = 1 * 1024 * 1024;
+    const SCORE_CONTEXT_LEN: usize = 8 * 1024;
+
+    /// Reads up-to `SCORE_CONTEXT_LEN` bytes starting at the marker for scoring, then re-aligns the
+    /// stream to where it was before the read.
+    ///
+    /// The stream must be positioned 16 bytes past the start of the marker.
+    fn read_score_context(mss: &mut MediaSourceStream) -> Result<Vec<u8>> {
+        mss.seek_buffered_rev(16);
+
+        let mut buf = vec![0; Probe::SCORE_CONTEXT_LEN];
+
+        // A short context is acceptable for scoring. Since the stream was rewound, at least the
+        // 16 bytes of the marker will be read.
+        let len = mss.read_buf(&mut buf)?;
+
+        buf.truncate(len);
+
+        mss.seek_buffered_rev(len - 16);
+
+        Ok(buf)
+    }
 
     /// Register all `Descriptor`s supported by the parameterized type.
     pub fn register_all<Q: QueryDescriptor>(&mut self) {
@@ -224,6 +270,12 @@ impl Probe {
         self.registered.push(*descriptor);
     }
 
+    /// Gets the `Descriptor`s of all registered container and metadata formats, in the order they
+    /// were registered.
+    pub fn descriptors(&self) -> &[Descriptor] {
+        &self.registered
+    }
+
     /// Searches the provided `MediaSourceStream` for metadata or a container format.
     pub fn next(&self, mss: &mut MediaSourceStream) -> Result<Instantiate> {
         let mut win = 0u16;
@@ -264,33 +316,61 @@ impl Probe {
                     context, init_pos, count,
                 );
 
-                // Search for registered markers in the 16-byte window.
+                // Search for registered markers in the 16-byte window. A marker may be shared by,
+                // or be a false positive for, several formats. Therefore, score every format with a
+                // matching marker and select the one with the highest score. Ties are broken in
+                // favour of the format that was registered first.
+                let mut score_context = None;
+                let mut best: Option<(u8, &Descriptor)> = None;
+
                 for registered in &self.registered {
-                    for marker in registered.markers {
-                        let len = marker.len();
+                    let is_match = registered
+                        .markers
+                        .iter()
+                        .any(|marker| context[0..marker.len()] == **marker);
 
-                        // If a match is found, return the instantiate.
-                        if context[0..len] == **marker {
-                            // Re-align the stream to the start of the marker.
-                            mss.seek_buffered_rev(16);
+                    if !is_match {
+                        continue;
+                    }
 
-                            // TODO: Implement scoring.
+                    // Read the scoring context starting at the marker once, on the first match.
+                    let score_context = match score_context {
+                        Some(ref buf) => buf,
+                        None => score_context.get_or_insert(Probe::read_score_context(mss)?),
+                    };
+
+                    let score = (registered.score)(score_context);
+
+                    debug!(
+                        "format {} scored {} for marker {:x?} @ {}+{} bytes.",
+                        registered.short_name,
+                        score,
+                        &context[0..2],
+                        init_pos,
+                        count,
+                    );
+
+                    if score > 0 && best.map_or(true, |(best_score, _)| score > best_score) {
+                        best = Some((score, registered));
+                    }
+                }
 
-                            debug!(
-                                "found the format marker {:x?} @ {}+{} bytes.",
-                                &context[0..len],
-                                init_pos,
-                                count,
-                            );
+                if let Some((_, registered)) = best {
+                    // Re-align the stream to the start of the marker.
+                    mss.seek_buffered_rev(16);
 
-                            return Ok(registered.inst);
-                        }
-                    }
+                    debug!(
+                        "found the format marker {:x?} @ {}+{} bytes.",
+                        &context, init_pos, count
+                    );
+
+                    return Ok(registered.inst);
                 }
 
                 // If no registered markers were matched, then the bloom filter returned a false
-                // positive. Re-align the stream to the end of the 2-byte window and continue the
-                // search.
+                // positive. Likewise, if all formats with a matching marker rejected the stream,
+                // the marker was likely random data. Re-align the stream to the end of the 2-byte
+                // window and continue the search.
                 mss.seek_buffered_rev(16 - 2);
             }
         }
@@ -311,7 +391,7 @@ impl Probe {
     /// container format is found.
     pub fn format(
         &self,
-        _hint: &Hint,
+        hint: &Hint,
         mut mss: MediaSourceStream,
         format_opts: &FormatOptions,
         metadata_opts: &MetadataOptions,
@@ -320,15 +400,11 @@ impl Probe {
 
         // Loop over all elements in the stream until a container format is found.
         loop {
-            match self.next(&mut mss)? {
-                // If a container format is found, return an instance to it's reader.
-                Instantiate::Format(fmt) => {
-                    let format = fmt(mss, format_opts)?;
-
-                    let metadata =
-                        if metadata.metadata().current().is_some() { Some(metadata) } else { None };
-
-                    return Ok(ProbeResult { format, metadata: ProbedMetadata { metadata } });
+            let mut format = match self.next(&mut mss)? {
+                // If a container format is found, instantiate it's reader.
+                Instantiate::Format(fmt) => fmt(mss, format_opts)?,
+                Instantiate::FormatWithCache(fmt) => {
+                    fmt(mss, format_opts, hint.analysis_cache.as_deref())?
                 }
                 // If metadata was found, instantiate the metadata reader, read the metadata, and
                 // push it onto the metadata log.
@@ -337,8 +413,18 @@ impl Probe {
                     metadata.push(reader.read_all(&mut mss)?);
 
                     debug!("chaining a metadata element.");
+                    continue;
                 }
+            };
+
+            if format_opts.enable_packet_hashes {
+                format = Box::new(HashedReader::new(format));
             }
+
+            let metadata =
+                if metadata.metadata().current().is_some() { Some(metadata) } else { None };
+
+            return Ok(ProbeResult { format, metadata: ProbedMetadata { metadata } });
         }
 
         // This function returns when either the end-of-stream is reached, an error occurs, or a
@@ -360,6 +446,17 @@ macro_rules! support_format {
             inst: Instantiate::Format(|source, opt| Ok(Box::new(Self::try_new(source, &opt)?))),
         }
     };
+    ($short_name:expr, $long_name:expr, $exts:expr, $mimes:expr, $markers:expr, $inst:expr) => {
+        Descriptor {
+            short_name: $short_name,
+            long_name: $long_name,
+            extensions: $exts,
+            mime_types: $mimes,
+            markers: $markers,
+            score: Self::score,
+            inst: $inst,
+        }
+    };
 }
 
 /// Convenience macro for declaring a probe `Descriptor` for a `MetadataReader`.
@@ -377,3 +474,84 @@ macro_rules! support_metadata {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::meta::MetadataRevision;
+
+    struct NullMetadataReader;
+
+    impl MetadataReader for NullMetadataReader {
+        fn new(_options: &MetadataOptions) -> Self {
+            NullMetadataReader
+        }
+
+        fn read_all(&mut self, _reader: &mut MediaSourceStream) -> Result<MetadataRevision> {
+            Ok(Default::default())
+        }
+    }
+
+    fn descriptor(score: fn(&[u8]) -> u8) -> Descriptor {
+        Descriptor {
+            short_name: "test",
+            long_name: "Test",
+            extensions: &[],
+            mime_types: &[],
+            markers: &[&[0xab, 0xcd]],
+            score,
+            inst: Instantiate::Metadata(|opt| Box::new(NullMetadataReader::new(opt))),
+        }
+    }
+
+    #[test]
+    fn verify_probe_skips_rejected_markers() {
+        let mut probe: Probe = Default::default();
+
+        // Only accept the marker if it is followed by a 1.
+        probe.register(&descriptor(|context| {
+            if context[2] == 1 {
+                255
+            }
+            else {
+                0
+            }
+        }));
+
+        let mut buf = vec![0; 64];
+        buf[10..13].copy_from_slice(&[0xab, 0xcd, 0x00]);
+        buf[40..43].copy_from_slice(&[0xab, 0xcd, 0x01]);
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        assert!(matches!(probe.next(&mut mss), Ok(Instantiate::Metadata(_))));
+        assert_eq!(mss.pos(), 40);
+    }
+
+    #[test]
+    fn verify_probe_rejects_all() {
+        let mut probe: Probe = Default::default();
+
+        probe.register(&descriptor(|_| 0));
+
+        let mut buf = vec![0; 64];
+        buf[10..12].copy_from_slice(&[0xab, 0xcd]);
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        assert!(probe.next(&mut mss).is_err());
+    }
+
+    #[test]
+    fn verify_probe_descriptors() {
+        let mut probe: Probe = Default::default();
+        assert!(probe.descriptors().is_empty());
+
+        probe.register(&descriptor(|_| 0));
+
+        let names: Vec<&str> = probe.descriptors().iter().map(|desc| desc.short_name).collect();
+        assert_eq!(names, ["test"]);
+    }
+}
@@ -12,7 +12,7 @@ use std::fmt;
 use crate::util::clamp::{clamp_f32, clamp_f64, clamp_i24, clamp_u24};
 
 /// SampleFormat describes the data encoding for an audio sample.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SampleFormat {
     /// Unsigned 8-bit integer.
     U8,
@@ -36,6 +36,32 @@ pub enum SampleFormat {
     F64,
 }
 
+impl SampleFormat {
+    /// Gets the number of bits of a sample of this format when packed.
+    pub fn bits_per_sample(&self) -> u32 {
+        match *self {
+            SampleFormat::U8 | SampleFormat::S8 => 8,
+            SampleFormat::U16 | SampleFormat::S16 => 16,
+            SampleFormat::U24 | SampleFormat::S24 => 24,
+            SampleFormat::U32 | SampleFormat::S32 | SampleFormat::F32 => 32,
+            SampleFormat::F64 => 64,
+        }
+    }
+
+    /// Returns true if samples of this format are floating point.
+    pub fn is_float(&self) -> bool {
+        matches!(*self, SampleFormat::F32 | SampleFormat::F64)
+    }
+
+    /// Returns true if samples of this format are signed. Floating point samples are signed.
+    pub fn is_signed(&self) -> bool {
+        !matches!(
+            *self,
+            SampleFormat::U8 | SampleFormat::U16 | SampleFormat::U24 | SampleFormat::U32
+        )
+    }
+}
+
 /// `Sample` provides a common interface for manipulating sample's regardless of the
 /// underlying data type. Additionally, `Sample` provides information regarding the
 /// format of underlying data types representing the sample when in memory, but also
@@ -575,3 +601,21 @@ impl core::ops::BitXor<u24> for u24 {
         u24(self.0 ^ other.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{i24, u24, Sample, SampleFormat};
+
+    #[test]
+    fn verify_sample_format() {
+        assert_eq!(i24::FORMAT.bits_per_sample(), 24);
+        assert!(i24::FORMAT.is_signed());
+        assert!(!u24::FORMAT.is_signed());
+        assert!(!u24::FORMAT.is_float());
+
+        assert_eq!(<f64 as Sample>::FORMAT.bits_per_sample(), 64);
+        assert!(<f32 as Sample>::FORMAT.is_float());
+        assert!(SampleFormat::F32.is_signed());
+        assert_eq!(SampleFormat::U8.bits_per_sample(), 8);
+    }
+}
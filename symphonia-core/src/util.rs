@@ -331,7 +331,6 @@ pub mod clamp {
     #[cfg(test)]
     mod tests {
         use super::*;
-        use std::{i16, i32, i64, i8, u16, u32, u64, u8};
 
         #[test]
         fn verify_clamp() {
@@ -375,3 +374,162 @@ pub mod clamp {
         }
     }
 }
+
+pub mod table {
+    //! Utilities for initializing lookup tables.
+    //!
+    //! Large tables built on the stack, such as `[f32; 8192]`, must first be zero-initialized and
+    //! then moved to their final location. Building the table directly on the heap avoids both the
+    //! redundant initialization, and the large stack allocation, without requiring unsafe code.
+
+    use std::convert::TryFrom;
+
+    /// Makes a boxed lookup table of `N` entries where the entry at index `i` is `f(i)`.
+    ///
+    /// Each entry is initialized exactly once. This is intended to be used to initialize a lazily
+    /// evaluated static table:
+    ///
+    /// ```ignore
+    /// lazy_static! {
+    ///     static ref POW43_TABLE: Box<[f32; 8192]> = make_table(|i| (i as f32).powf(4.0 / 3.0));
+    /// }
+    /// ```
+    pub fn make_table<T, F, const N: usize>(f: F) -> Box<[T; N]>
+    where
+        F: FnMut(usize) -> T,
+    {
+        let table: Box<[T]> = (0..N).map(f).collect();
+
+        match Box::try_from(table) {
+            Ok(table) => table,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::make_table;
+
+        #[test]
+        fn verify_make_table() {
+            let table: Box<[u32; 5]> = make_table(|i| (i * i) as u32);
+            assert_eq!(*table, [0, 1, 4, 9, 16]);
+
+            // Entries are generated in order.
+            let mut n = 0;
+            let table: Box<[usize; 3]> = make_table(|i| {
+                n += 1;
+                10 * i + n
+            });
+            assert_eq!(*table, [1, 12, 23]);
+
+            let empty: Box<[f32; 0]> = make_table(|i| i as f32);
+            assert!(empty.is_empty());
+        }
+    }
+}
+
+pub mod checked {
+    //! Utilities for checked arithmetic on untrusted values, such as sizes read from headers.
+    //!
+    //! Instead of panicking or wrapping on overflow, these functions return a decode error with the
+    //! provided description.
+
+    use crate::errors::{decode_error, Result};
+
+    /// An integer type that supports checked arithmetic.
+    pub trait CheckedInt: Copy {
+        fn checked_add_int(self, rhs: Self) -> Option<Self>;
+        fn checked_sub_int(self, rhs: Self) -> Option<Self>;
+        fn checked_mul_int(self, rhs: Self) -> Option<Self>;
+        fn checked_div_int(self, rhs: Self) -> Option<Self>;
+    }
+
+    macro_rules! impl_checked_int {
+        ($($t:ty),*) => {
+            $(
+                impl CheckedInt for $t {
+                    #[inline(always)]
+                    fn checked_add_int(self, rhs: Self) -> Option<Self> {
+                        self.checked_add(rhs)
+                    }
+
+                    #[inline(always)]
+                    fn checked_sub_int(self, rhs: Self) -> Option<Self> {
+                        self.checked_sub(rhs)
+                    }
+
+                    #[inline(always)]
+                    fn checked_mul_int(self, rhs: Self) -> Option<Self> {
+                        self.checked_mul(rhs)
+                    }
+
+                    #[inline(always)]
+                    fn checked_div_int(self, rhs: Self) -> Option<Self> {
+                        self.checked_div(rhs)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_checked_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+    /// Computes `a + b`, or returns a decode error with the description `desc` on overflow.
+    #[inline]
+    pub fn add<T: CheckedInt>(a: T, b: T, desc: &'static str) -> Result<T> {
+        a.checked_add_int(b).map_or_else(|| decode_error(desc), Ok)
+    }
+
+    /// Computes `a - b`, or returns a decode error with the description `desc` on underflow.
+    #[inline]
+    pub fn sub<T: CheckedInt>(a: T, b: T, desc: &'static str) -> Result<T> {
+        a.checked_sub_int(b).map_or_else(|| decode_error(desc), Ok)
+    }
+
+    /// Computes `a * b`, or returns a decode error with the description `desc` on overflow.
+    #[inline]
+    pub fn mul<T: CheckedInt>(a: T, b: T, desc: &'static str) -> Result<T> {
+        a.checked_mul_int(b).map_or_else(|| decode_error(desc), Ok)
+    }
+
+    /// Computes `a / b`, or returns a decode error with the description `desc` if `b` is 0.
+    #[inline]
+    pub fn div<T: CheckedInt>(a: T, b: T, desc: &'static str) -> Result<T> {
+        a.checked_div_int(b).map_or_else(|| decode_error(desc), Ok)
+    }
+
+    /// Converts a `u64` to a `usize`, or returns a decode error with the description `desc` if the
+    /// value does not fit.
+    #[inline]
+    pub fn to_usize(val: u64, desc: &'static str) -> Result<usize> {
+        if val > usize::MAX as u64 {
+            return decode_error(desc);
+        }
+        Ok(val as usize)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::errors::Error;
+
+        #[test]
+        fn verify_checked() {
+            assert_eq!(add(1u32, 2, "add").unwrap(), 3);
+            assert!(matches!(add(u32::MAX, 1, "add"), Err(Error::DecodeError("add"))));
+
+            assert_eq!(sub(24u64, 24, "sub").unwrap(), 0);
+            assert!(matches!(sub(8u64, 24, "sub"), Err(Error::DecodeError("sub"))));
+            assert!(matches!(sub(i32::MIN, 1, "sub"), Err(Error::DecodeError("sub"))));
+
+            assert_eq!(mul(3usize, 4, "mul").unwrap(), 12);
+            assert!(matches!(mul(u16::MAX, 2, "mul"), Err(Error::DecodeError("mul"))));
+
+            assert_eq!(div(12u8, 4, "div").unwrap(), 3);
+            assert!(matches!(div(12u8, 0, "div"), Err(Error::DecodeError("div"))));
+
+            assert_eq!(to_usize(42, "to_usize").unwrap(), 42);
+        }
+    }
+}
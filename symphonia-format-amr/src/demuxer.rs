@@ -0,0 +1,344 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CodecType, CODEC_TYPE_AMR_NB, CODEC_TYPE_AMR_WB};
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+/// The length of the speech data following the frame header, indexed by frame type, for AMR-NB
+/// (3GPP TS 26.101). Frame types 9 to 14 are reserved.
+#[rustfmt::skip]
+const AMR_NB_FRAME_LENS: [Option<u8>; 16] = [
+    Some(12), Some(13), Some(15), Some(17), Some(19), Some(20), Some(26), Some(31),
+    Some(5), None, None, None, None, None, None, Some(0),
+];
+
+/// The length of the speech data following the frame header, indexed by frame type, for AMR-WB
+/// (3GPP TS 26.201). Frame types 10 to 13 are reserved.
+#[rustfmt::skip]
+const AMR_WB_FRAME_LENS: [Option<u8>; 16] = [
+    Some(17), Some(23), Some(32), Some(36), Some(40), Some(46), Some(50), Some(58),
+    Some(60), Some(5), None, None, None, None, Some(0), Some(0),
+];
+
+/// The AMR variant of a stream.
+#[derive(Copy, Clone, Debug)]
+struct Variant {
+    codec: CodecType,
+    sample_rate: u32,
+    frame_lens: &'static [Option<u8>; 16],
+}
+
+impl Variant {
+    /// The number of audio frames in each AMR frame. Every AMR frame is 20 ms long.
+    fn frames_per_packet(&self) -> u64 {
+        u64::from(self.sample_rate / 50)
+    }
+}
+
+const AMR_NB: Variant =
+    Variant { codec: CODEC_TYPE_AMR_NB, sample_rate: 8_000, frame_lens: &AMR_NB_FRAME_LENS };
+
+const AMR_WB: Variant =
+    Variant { codec: CODEC_TYPE_AMR_WB, sample_rate: 16_000, frame_lens: &AMR_WB_FRAME_LENS };
+
+/// Reads the header of the next AMR frame, and returns it along with the total length of the frame
+/// in bytes, including the header.
+fn read_frame_header<B: ReadBytes>(reader: &mut B, variant: &Variant) -> Result<(u8, usize)> {
+    let header = reader.read_u8()?;
+
+    // The frame header consists of a padding bit, the 4-bit frame type, the frame quality
+    // indicator, and two more padding bits. The padding bits must be ignored.
+    match variant.frame_lens[usize::from((header >> 3) & 0xf)] {
+        Some(len) => Ok((header, 1 + usize::from(len))),
+        None => decode_error("amr: reserved frame type"),
+    }
+}
+
+/// Counts the number of frames from the current position to the end of the stream, and then
+/// returns to the current position. Since AMR frames are small, and the storage format has no
+/// index, this is the only way to get an exact duration.
+fn count_frames(source: &mut MediaSourceStream, variant: &Variant) -> Result<Option<u64>> {
+    if !source.is_seekable() {
+        return Ok(None);
+    }
+
+    let original_pos = source.pos();
+
+    let mut n_frames = 0;
+
+    while let Ok((_, len)) = read_frame_header(source, variant) {
+        if source.ignore_bytes(len as u64 - 1).is_err() {
+            break;
+        }
+
+        n_frames += 1;
+    }
+
+    source.seek(SeekFrom::Start(original_pos))?;
+
+    Ok(Some(n_frames))
+}
+
+/// Adaptive Multi-Rate (AMR) storage format reader.
+///
+/// `AmrReader` implements a demuxer for the single-channel AMR-NB (`#!AMR`) and AMR-WB
+/// (`#!AMR-WB`) storage formats defined in RFC 4867. Each packet is one AMR frame, including its
+/// frame header.
+pub struct AmrReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    variant: Variant,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for AmrReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "amr",
+            "Adaptive Multi-Rate (AMR)",
+            &["amr", "awb"],
+            &["audio/amr", "audio/amr-wb"],
+            &[b"#!AMR\n", b"#!AMR-WB\n"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for AmrReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let mut magic = [0; 5];
+        source.read_buf_exact(&mut magic)?;
+
+        if magic != *b"#!AMR" {
+            return unsupported_error("amr: missing amr magic");
+        }
+
+        // The magic is followed by the variant. Multi-channel streams are suffixed with "_MC1.0".
+        let variant = match source.read_u8()? {
+            b'\n' => AMR_NB,
+            b'-' => {
+                let mut wb = [0; 3];
+                source.read_buf_exact(&mut wb)?;
+
+                match &wb {
+                    b"WB\n" => AMR_WB,
+                    b"WB_" => {
+                        return unsupported_error("amr: multi-channel amr-wb is not supported")
+                    }
+                    _ => return unsupported_error("amr: missing amr magic"),
+                }
+            }
+            b'_' => return unsupported_error("amr: multi-channel amr is not supported"),
+            _ => return unsupported_error("amr: missing amr magic"),
+        };
+
+        let first_frame_pos = source.pos();
+
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(variant.codec)
+            .with_sample_rate(variant.sample_rate)
+            .with_time_base(TimeBase::new(1, variant.sample_rate))
+            .with_channels(Channels::FRONT_LEFT)
+            .with_max_frames_per_packet(variant.frames_per_packet());
+
+        if let Some(n_frames) = count_frames(&mut source, &variant)? {
+            params.with_n_frames(n_frames * variant.frames_per_packet());
+        }
+
+        Ok(AmrReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            variant,
+            first_frame_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let (header, len) = read_frame_header(&mut self.reader, &self.variant)?;
+
+        // The packet includes the frame header since it is required to decode the frame.
+        let mut buf = vec![0; len];
+        buf[0] = header;
+        self.reader.read_buf_exact(&mut buf[1..])?;
+
+        let ts = self.next_packet_ts;
+        let dur = self.variant.frames_per_packet();
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.variant.sample_rate).calc_timestamp(time)
+            }
+        };
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        debug!("seeking to ts={}", required_ts);
+
+        // The storage format has no index, therefore frames must be parsed from the first frame to
+        // find the frame containing the desired timestamp. If the desired timestamp is before the
+        // next frame, start over from the first frame.
+        if required_ts < self.next_packet_ts {
+            if !self.reader.is_seekable() {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.seek(SeekFrom::Start(self.first_frame_pos))?;
+            self.next_packet_ts = 0;
+        }
+
+        let dur = self.variant.frames_per_packet();
+
+        while self.next_packet_ts + dur <= required_ts {
+            let (_, len) = read_frame_header(&mut self.reader, &self.variant)?;
+
+            self.reader.ignore_bytes(len as u64 - 1)?;
+            self.next_packet_ts += dur;
+        }
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts - self.next_packet_ts
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::codecs::{CODEC_TYPE_AMR_NB, CODEC_TYPE_AMR_WB};
+    use symphonia_core::formats::{FormatReader, SeekMode, SeekTo};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::AmrReader;
+
+    fn open(buf: Vec<u8>) -> symphonia_core::errors::Result<AmrReader> {
+        let source = Box::new(Cursor::new(buf));
+        AmrReader::try_new(MediaSourceStream::new(source, Default::default()), &Default::default())
+    }
+
+    /// Appends an AMR frame of frame type `ft`, with `len` bytes of speech data, to `buf`.
+    fn push_frame(buf: &mut Vec<u8>, ft: u8, len: usize) {
+        buf.push(ft << 3 | 0x4);
+        buf.extend(std::iter::repeat(ft).take(len));
+    }
+
+    #[test]
+    fn verify_amr_nb_reader() {
+        // A 12.2 kbps frame, a SID frame, a no data frame, and another 12.2 kbps frame.
+        let mut buf = b"#!AMR\n".to_vec();
+        push_frame(&mut buf, 7, 31);
+        push_frame(&mut buf, 8, 5);
+        push_frame(&mut buf, 15, 0);
+        push_frame(&mut buf, 7, 31);
+
+        let mut reader = open(buf).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_AMR_NB);
+        assert_eq!(params.sample_rate, Some(8_000));
+        assert_eq!(params.n_frames, Some(4 * 160));
+
+        let packets: Vec<_> = (0..4).map(|_| reader.next_packet().unwrap()).collect();
+        assert_eq!(packets.iter().map(|p| p.buf().len()).collect::<Vec<_>>(), [32, 6, 1, 32]);
+        assert_eq!(packets.iter().map(|p| p.ts).collect::<Vec<_>>(), [0, 160, 320, 480]);
+        assert!(reader.next_packet().is_err());
+
+        // Seek backwards into the middle of the third frame.
+        let seeked = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 400, track_id: 0 });
+        assert_eq!(seeked.unwrap().actual_ts, 320);
+        assert_eq!(reader.next_packet().unwrap().buf(), &[15 << 3 | 0x4]);
+
+        assert!(reader
+            .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 640, track_id: 0 })
+            .is_err());
+    }
+
+    #[test]
+    fn verify_amr_wb_reader() {
+        let mut buf = b"#!AMR-WB\n".to_vec();
+        push_frame(&mut buf, 8, 60);
+
+        let mut reader = open(buf).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_AMR_WB);
+        assert_eq!(params.sample_rate, Some(16_000));
+        assert_eq!(params.n_frames, Some(320));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts, packet.dur, packet.buf().len()), (0, 320, 61));
+    }
+
+    #[test]
+    fn verify_amr_reader_rejects_invalid_streams() {
+        // Multi-channel streams are not supported.
+        assert!(open(b"#!AMR_MC1.0\n\0\0\0\x01".to_vec()).is_err());
+        assert!(open(b"#!AMR-WB_MC1.0\n\0\0\0\x01".to_vec()).is_err());
+        assert!(open(b"#!AMX\n".to_vec()).is_err());
+
+        // Reserved frame types are rejected.
+        let mut buf = b"#!AMR\n".to_vec();
+        push_frame(&mut buf, 12, 0);
+
+        let mut reader = open(buf).unwrap();
+        assert!(reader.next_packet().is_err());
+    }
+}
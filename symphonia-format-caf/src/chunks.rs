@@ -533,21 +533,13 @@ fn invalid_chunk_size_error<T>(chunk_type: &str, chunk_size: i64) -> Result<T> {
 }
 
 fn read_variable_length_integer(reader: &mut MediaSourceStream) -> Result<u64> {
-    let mut result = 0;
-
-    for _ in 0..9 {
-        let byte = reader.read_byte()?;
-
-        result |= (byte & 0x7f) as u64;
-
-        if byte & 0x80 == 0 {
-            return Ok(result);
+    match reader.read_vlq(9) {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+            decode_error("caf: invalid variable-length integer")
         }
-
-        result <<= 7;
+        Err(err) => Err(err.into()),
     }
-
-    decode_error("caf: unterminated variable-length integer")
 }
 
 #[cfg(test)]
@@ -94,10 +94,26 @@ impl FormatReader for CafReader {
                 }
 
                 let bytes_to_read = max_bytes_to_read.min(bytes_remaining);
-                let packet_duration = bytes_to_read / bytes_per_frame;
                 let packet_timestamp = data_pos / bytes_per_frame;
-                let buffer = self.reader.read_boxed_slice(bytes_to_read as usize)?;
-                Ok(Packet::new_from_boxed_slice(0, packet_timestamp, packet_duration, buffer))
+
+                // Less data than requested is only read if the stream ended, in which case any
+                // partial frame at the end is dropped.
+                let mut buffer = self.reader.read_boxed_slice(bytes_to_read as usize)?.into_vec();
+
+                let packet_duration = buffer.len() as u64 / bytes_per_frame;
+
+                if packet_duration == 0 {
+                    return end_of_stream_error();
+                }
+
+                buffer.truncate((packet_duration * bytes_per_frame) as usize);
+
+                Ok(Packet::new_from_boxed_slice(
+                    0,
+                    packet_timestamp,
+                    packet_duration,
+                    buffer.into_boxed_slice(),
+                ))
             }
             PacketInfo::Compressed { packets, ref mut current_packet_index } => {
                 if let Some(packet) = packets.get(*current_packet_index) {
@@ -0,0 +1,62 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::ReadBytes;
+
+use crate::atoms::{Atom, AtomHeader};
+
+/// AMR decoder specific configuration atom (3GPP TS 26.244).
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct DamrAtom {
+    /// Atom header.
+    header: AtomHeader,
+    /// The vendor of the encoder.
+    pub vendor: [u8; 4],
+    /// The version of the encoder.
+    pub decoder_version: u8,
+    /// A bitmask of the AMR modes (frame types) that may be present in the stream.
+    pub mode_set: u16,
+    /// The number of frames between mode changes, or 0 if unrestricted.
+    pub mode_change_period: u8,
+    /// The number of AMR frames in each sample.
+    pub frames_per_sample: u8,
+}
+
+impl Atom for DamrAtom {
+    fn header(&self) -> AtomHeader {
+        self.header
+    }
+
+    fn read<B: ReadBytes>(reader: &mut B, header: AtomHeader) -> Result<Self> {
+        // Unlike most codec-specific atoms, the damr atom is not a full atom, so it has no version
+        // or flags.
+        if header.data_len < 9 {
+            return decode_error("isomp4 (damr): invalid damr atom length");
+        }
+
+        let vendor = reader.read_quad_bytes()?;
+        let decoder_version = reader.read_u8()?;
+        let mode_set = reader.read_be_u16()?;
+        let mode_change_period = reader.read_u8()?;
+        let frames_per_sample = reader.read_u8()?;
+
+        if frames_per_sample == 0 {
+            return decode_error("isomp4 (damr): invalid frames per sample");
+        }
+
+        Ok(DamrAtom {
+            header,
+            vendor,
+            decoder_version,
+            mode_set,
+            mode_change_period,
+            frames_per_sample,
+        })
+    }
+}
@@ -7,6 +7,7 @@
 
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
+use symphonia_core::util::checked;
 
 use crate::atoms::{Atom, AtomHeader};
 use crate::fourcc::FourCc;
@@ -36,7 +37,7 @@ impl Atom for FtypAtom {
         let minor = reader.read_quad_bytes()?;
 
         // The remainder of the Ftyp atom contains the FourCCs of compatible brands.
-        let n_brands = (header.data_len - 8) / 4;
+        let n_brands = checked::sub(header.data_len, 8, "isomp4: invalid ftyp atom length")? / 4;
 
         let mut compatible = Vec::new();
 
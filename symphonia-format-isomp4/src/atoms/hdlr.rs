@@ -7,6 +7,7 @@
 
 use symphonia_core::errors::Result;
 use symphonia_core::io::ReadBytes;
+use symphonia_core::util::checked;
 
 use crate::{
     atoms::{Atom, AtomHeader},
@@ -72,7 +73,9 @@ impl Atom for HdlrAtom {
         reader.ignore_bytes(4 * 3)?;
 
         // Human readable UTF-8 string of the track type.
-        let buf = reader.read_boxed_slice_exact((header.data_len - 24) as usize)?;
+        let name_len = checked::sub(header.data_len, 24, "isomp4: invalid hdlr atom length")?;
+        let buf = reader
+            .read_boxed_slice_exact(checked::to_usize(name_len, "isomp4: hdlr name too long")?)?;
         let name = String::from_utf8_lossy(&buf).to_string();
 
         Ok(HdlrAtom { header, handler_type, name })
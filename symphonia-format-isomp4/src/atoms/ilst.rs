@@ -11,7 +11,7 @@ use symphonia_core::meta::{
     MetadataBuilder, MetadataRevision, StandardTagKey, StandardVisualKey, Tag,
 };
 use symphonia_core::meta::{Value, Visual};
-use symphonia_core::util::bits;
+use symphonia_core::util::{bits, checked};
 use symphonia_metadata::{id3v1, itunes};
 
 use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType};
@@ -507,8 +507,14 @@ impl Atom for MetaTagDataAtom {
 
         // The data payload is the remainder of the atom.
         // TODO: Apply a limit.
+        let data_len = checked::sub(
+            header.data_len,
+            AtomHeader::EXTRA_DATA_SIZE + 4,
+            "isomp4: invalid data atom length",
+        )?;
+
         let data = reader
-            .read_boxed_slice_exact((header.data_len - AtomHeader::EXTRA_DATA_SIZE - 4) as usize)?;
+            .read_boxed_slice_exact(checked::to_usize(data_len, "isomp4: data atom too long")?)?;
 
         Ok(MetaTagDataAtom { header, data, data_type })
     }
@@ -530,8 +536,16 @@ impl Atom for MetaTagNamespaceAtom {
     fn read<B: ReadBytes>(reader: &mut B, header: AtomHeader) -> Result<Self> {
         let (_, _) = AtomHeader::read_extra(reader)?;
 
-        let buf = reader
-            .read_boxed_slice_exact((header.data_len - AtomHeader::EXTRA_DATA_SIZE) as usize)?;
+        let value_len = checked::sub(
+            header.data_len,
+            AtomHeader::EXTRA_DATA_SIZE,
+            "isomp4: invalid mean or name atom length",
+        )?;
+
+        let buf = reader.read_boxed_slice_exact(checked::to_usize(
+            value_len,
+            "isomp4: mean or name atom too long",
+        )?)?;
 
         // Do a lossy conversion because metadata should not prevent the demuxer from working.
         let value = String::from_utf8_lossy(&buf).to_string();
@@ -11,6 +11,7 @@ use symphonia_core::io::ReadBytes;
 pub(crate) mod alac;
 pub(crate) mod co64;
 pub(crate) mod ctts;
+pub(crate) mod damr;
 pub(crate) mod edts;
 pub(crate) mod elst;
 pub(crate) mod esds;
@@ -52,6 +53,7 @@ pub use alac::AlacAtom;
 pub use co64::Co64Atom;
 #[allow(unused_imports)]
 pub use ctts::CttsAtom;
+pub use damr::DamrAtom;
 pub use edts::EdtsAtom;
 pub use elst::ElstAtom;
 pub use esds::EsdsAtom;
@@ -97,6 +99,9 @@ pub enum AtomType {
     ALaw,
     AlbumArtistTag,
     AlbumTag,
+    AmrDsConfig,
+    AmrNb,
+    AmrWb,
     ArtistLowerTag,
     ArtistTag,
     CategoryTag,
@@ -207,6 +212,7 @@ impl From<[u8; 4]> for AtomType {
             b"alaw" => AtomType::ALaw,
             b"co64" => AtomType::ChunkOffset64,
             b"ctts" => AtomType::CompositionTimeToSample,
+            b"damr" => AtomType::AmrDsConfig,
             b"data" => AtomType::MetaTagData,
             b"dfLa" => AtomType::FlacDsConfig,
             b"dOps" => AtomType::OpusDsConfig,
@@ -239,6 +245,8 @@ impl From<[u8; 4]> for AtomType {
             b"name" => AtomType::MetaTagName,
             b"Opus" => AtomType::Opus,
             b"raw " => AtomType::U8SampleEntry,
+            b"samr" => AtomType::AmrNb,
+            b"sawb" => AtomType::AmrWb,
             b"sidx" => AtomType::SegmentIndex,
             b"skip" => AtomType::Skip,
             b"smhd" => AtomType::SoundMediaHeader,
@@ -475,3 +483,94 @@ impl<B: ReadBytes> AtomIterator<B> {
         assert!(self.cur_atom.take().is_some());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::errors::Error;
+    use symphonia_core::io::BufReader;
+
+    use super::*;
+
+    /// Reads an atom of type `A` from `buf`, which must begin with the atom header.
+    fn read_atom<A: Atom>(buf: &[u8]) -> Result<A> {
+        let mut reader = BufReader::new(buf);
+        let header = AtomHeader::read(&mut reader)?;
+        A::read(&mut reader, header)
+    }
+
+    fn atom(atom_len: u32, atype: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&atom_len.to_be_bytes());
+        buf.extend_from_slice(atype);
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn verify_truncated_hdlr_is_rejected() {
+        let mut payload = vec![0; 8];
+        payload.extend_from_slice(b"soun");
+        payload.extend_from_slice(&[0; 12]);
+
+        // The atom is too short to contain the handler name.
+        let buf = atom(8 + 20, b"hdlr", &payload);
+
+        assert!(matches!(read_atom::<HdlrAtom>(&buf), Err(Error::DecodeError(_))));
+    }
+
+    #[test]
+    fn verify_truncated_ftyp_is_rejected() {
+        // The atom is too short to contain the minor version.
+        let buf = atom(8 + 4, b"ftyp", b"isom\0\0\0\0");
+
+        assert!(matches!(read_atom::<FtypAtom>(&buf), Err(Error::DecodeError(_))));
+    }
+
+    #[test]
+    fn verify_stsc_zero_first_chunk_is_rejected() {
+        let mut payload = vec![0; 4];
+        // One entry, with a first chunk of 0 (first chunk is 1-based).
+        payload.extend_from_slice(&1u32.to_be_bytes());
+        payload.extend_from_slice(&0u32.to_be_bytes());
+        payload.extend_from_slice(&1u32.to_be_bytes());
+        payload.extend_from_slice(&1u32.to_be_bytes());
+
+        let buf = atom(8 + payload.len() as u32, b"stsc", &payload);
+
+        assert!(matches!(read_atom::<StscAtom>(&buf), Err(Error::DecodeError(_))));
+    }
+
+    #[test]
+    fn verify_stsd_amr_sample_entry() {
+        use symphonia_core::audio::Channels;
+        use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AMR_NB};
+
+        // An AMR-NB damr atom with 5 frames per sample.
+        let damr = atom(8 + 9, b"damr", b"FFMP\0\x81\xff\0\x05");
+
+        // An AMR-NB audio sample entry. The channel count is always 2, even though AMR is mono.
+        let mut entry = vec![0; 6];
+        entry.extend_from_slice(&[0, 1, 0, 0]);
+        entry.extend_from_slice(&[0; 6]);
+        entry.extend_from_slice(&[0, 2, 0, 16, 0, 0, 0, 0]);
+        entry.extend_from_slice(&(8_000u32 << 16).to_be_bytes());
+        entry.extend_from_slice(&damr);
+
+        let samr = atom(8 + entry.len() as u32, b"samr", &entry);
+
+        let mut payload = vec![0; 4];
+        payload.extend_from_slice(&1u32.to_be_bytes());
+        payload.extend_from_slice(&samr);
+
+        let stsd =
+            read_atom::<StsdAtom>(&atom(8 + payload.len() as u32, b"stsd", &payload)).unwrap();
+
+        let mut params = CodecParameters::new();
+        stsd.fill_codec_params(&mut params);
+
+        assert_eq!(params.codec, CODEC_TYPE_AMR_NB);
+        assert_eq!(params.sample_rate, Some(8_000));
+        assert_eq!(params.channels, Some(Channels::FRONT_LEFT));
+        assert_eq!(params.max_frames_per_packet, Some(5 * 160));
+    }
+}
@@ -7,6 +7,7 @@
 
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
+use symphonia_core::util::checked;
 
 use crate::atoms::{Atom, AtomHeader};
 
@@ -71,7 +72,11 @@ impl Atom for StscAtom {
 
         for _ in 0..entry_count {
             entries.push(StscEntry {
-                first_chunk: reader.read_be_u32()? - 1,
+                first_chunk: checked::sub(
+                    reader.read_be_u32()?,
+                    1,
+                    "isomp4: stsc entry first chunk is 0",
+                )?,
                 first_sample: 0,
                 samples_per_chunk: reader.read_be_u32()?,
                 sample_desc_index: reader.read_be_u32()?,
@@ -93,8 +98,11 @@ impl Atom for StscAtom {
 
                 let n = entries[i + 1].first_chunk - entries[i].first_chunk;
 
-                entries[i + 1].first_sample =
-                    entries[i].first_sample + (n * entries[i].samples_per_chunk);
+                entries[i + 1].first_sample = checked::add(
+                    entries[i].first_sample,
+                    checked::mul(n, entries[i].samples_per_chunk, "isomp4: stsc sample overflow")?,
+                    "isomp4: stsc sample overflow",
+                )?;
             }
 
             // Validate that samples per chunk is > 0. Could the entry be ignored?
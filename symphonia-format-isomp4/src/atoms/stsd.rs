@@ -6,7 +6,8 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::audio::Channels;
-use symphonia_core::codecs::{CodecParameters, CodecType, CODEC_TYPE_MP3, CODEC_TYPE_NULL};
+use symphonia_core::codecs::{ids, CodecParameters, CodecType, CODEC_TYPE_MP3, CODEC_TYPE_NULL};
+use symphonia_core::codecs::{CODEC_TYPE_AMR_NB, CODEC_TYPE_AMR_WB};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F32LE};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_F64BE, CODEC_TYPE_PCM_F64LE};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S16LE};
@@ -19,7 +20,10 @@ use symphonia_core::codecs::{CODEC_TYPE_PCM_U32BE, CODEC_TYPE_PCM_U32LE};
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::ReadBytes;
 
-use crate::atoms::{AlacAtom, Atom, AtomHeader, AtomType, EsdsAtom, FlacAtom, OpusAtom, WaveAtom};
+use log::info;
+
+use crate::atoms::WaveAtom;
+use crate::atoms::{AlacAtom, Atom, AtomHeader, AtomType, DamrAtom, EsdsAtom, FlacAtom, OpusAtom};
 use crate::fp::FpU16;
 
 use super::AtomIterator;
@@ -59,6 +63,8 @@ impl Atom for StsdAtom {
             | AtomType::Flac
             | AtomType::Opus
             | AtomType::Mp3
+            | AtomType::AmrNb
+            | AtomType::AmrWb
             | AtomType::Lpcm
             | AtomType::QtWave
             | AtomType::ALaw
@@ -70,6 +76,13 @@ impl Atom for StsdAtom {
             | AtomType::S32SampleEntry
             | AtomType::F32SampleEntry
             | AtomType::F64SampleEntry => read_audio_sample_entry(reader, sample_entry_header)?,
+            AtomType::Other(fourcc) => {
+                // Potentially video, subtitles, etc., or an audio codec that is not supported.
+                if let Some(codec) = ids::from_mp4_fourcc(fourcc) {
+                    info!("isomp4: unsupported sample entry {:?} ({})", fourcc, codec);
+                }
+                SampleEntry::Other
+            }
             _ => {
                 // Potentially video, subtitles, etc.
                 SampleEntry::Other
@@ -105,6 +118,22 @@ impl StsdAtom {
                 Some(AudioCodecSpecific::Mp3) => {
                     codec_params.for_codec(CODEC_TYPE_MP3);
                 }
+                Some(AudioCodecSpecific::Amr(ref amr)) => {
+                    // AMR is always mono. The channel count of the sample entry is not used.
+                    codec_params.for_codec(amr.codec_type).with_channels(Channels::FRONT_LEFT);
+
+                    // Each AMR frame is 20 ms long, and a sample may contain multiple frames.
+                    if let Some(ref damr) = amr.damr {
+                        let frames_per_amr_frame = match amr.codec_type {
+                            CODEC_TYPE_AMR_WB => 320,
+                            _ => 160,
+                        };
+
+                        codec_params.with_max_frames_per_packet(
+                            u64::from(damr.frames_per_sample) * frames_per_amr_frame,
+                        );
+                    }
+                }
                 Some(AudioCodecSpecific::Pcm(ref pcm)) => {
                     // PCM codecs.
                     codec_params
@@ -129,6 +158,12 @@ pub struct Pcm {
     pub channels: Channels,
 }
 
+#[derive(Debug)]
+pub struct Amr {
+    pub codec_type: CodecType,
+    pub damr: Option<DamrAtom>,
+}
+
 #[derive(Debug)]
 pub enum AudioCodecSpecific {
     /// MPEG Elementary Stream descriptor.
@@ -141,6 +176,8 @@ pub enum AudioCodecSpecific {
     Opus(OpusAtom),
     /// MP3.
     Mp3,
+    /// Adaptive Multi-Rate (AMR) narrowband or wideband.
+    Amr(Amr),
     /// PCM codecs.
     Pcm(Pcm),
 }
@@ -183,6 +220,15 @@ fn pcm_codec_type(atype: AtomType) -> CodecType {
     }
 }
 
+/// Gets the AMR codec from the sample entry atom type.
+fn amr_codec_type(atype: AtomType) -> CodecType {
+    match atype {
+        AtomType::AmrNb => CODEC_TYPE_AMR_NB,
+        AtomType::AmrWb => CODEC_TYPE_AMR_WB,
+        _ => CODEC_TYPE_NULL,
+    }
+}
+
 /// Determines the number of bytes per PCM sample for a PCM codec type.
 fn bytes_per_pcm_sample(pcm_codec_type: CodecType) -> u32 {
     match pcm_codec_type {
@@ -469,6 +515,8 @@ fn read_audio_sample_entry<B: ReadBytes>(
 
     let mut iter = AtomIterator::new(reader, header);
 
+    let mut damr = None;
+
     while let Some(entry_header) = iter.next()? {
         match entry_header.atype {
             AtomType::Esds => {
@@ -503,6 +551,14 @@ fn read_audio_sample_entry<B: ReadBytes>(
 
                 codec_specific = Some(AudioCodecSpecific::Opus(iter.read_atom::<OpusAtom>()?));
             }
+            AtomType::AmrDsConfig => {
+                // AMR codec-specific atom.
+                if amr_codec_type(header.atype) == CODEC_TYPE_NULL || damr.is_some() {
+                    return decode_error("isomp4: invalid sample entry");
+                }
+
+                damr = Some(iter.read_atom::<DamrAtom>()?);
+            }
             AtomType::QtWave => {
                 // The QuickTime WAVE (aka. siDecompressionParam) atom may contain many different
                 // types of sub-atoms to store decoder parameters.
@@ -529,6 +585,18 @@ fn read_audio_sample_entry<B: ReadBytes>(
         codec_specific = Some(AudioCodecSpecific::Mp3);
     }
 
+    // An AMR sample entry should have a damr codec-specific atom, but it is not required to
+    // demux the stream.
+    let amr_codec_type = amr_codec_type(header.atype);
+
+    if amr_codec_type != CODEC_TYPE_NULL {
+        if codec_specific.is_some() {
+            return decode_error("isomp4: invalid sample entry");
+        }
+
+        codec_specific = Some(AudioCodecSpecific::Amr(Amr { codec_type: amr_codec_type, damr }));
+    }
+
     Ok(SampleEntry::Audio(AudioSampleEntry {
         num_channels,
         sample_size,
@@ -310,8 +310,8 @@ impl QueryDescriptor for IsoMp4Reader {
         &[support_format!(
             "isomp4",
             "ISO Base Media File Format",
-            &["mp4", "m4a", "m4p", "m4b", "m4r", "m4v", "mov"],
-            &["video/mp4", "audio/m4a"],
+            &["mp4", "m4a", "m4p", "m4b", "m4r", "m4v", "mov", "3gp", "3g2"],
+            &["video/mp4", "audio/m4a", "audio/3gpp", "audio/3gpp2"],
             &[b"ftyp"] // Top-level atoms
         )]
     }
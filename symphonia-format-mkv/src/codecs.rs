@@ -6,7 +6,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::codecs;
-use symphonia_core::codecs::CodecType;
+use symphonia_core::codecs::{ids, CodecType};
 
 use crate::segment::TrackElement;
 
@@ -14,15 +14,6 @@ pub(crate) fn codec_id_to_type(track: &TrackElement) -> Option<CodecType> {
     let bit_depth = track.audio.as_ref().and_then(|a| a.bit_depth);
 
     match track.codec_id.as_str() {
-        "A_MPEG/L1" => Some(codecs::CODEC_TYPE_MP1),
-        "A_MPEG/L2" => Some(codecs::CODEC_TYPE_MP2),
-        "A_MPEG/L3" => Some(codecs::CODEC_TYPE_MP3),
-        "A_FLAC" => Some(codecs::CODEC_TYPE_FLAC),
-        "A_OPUS" => Some(codecs::CODEC_TYPE_OPUS),
-        "A_VORBIS" => Some(codecs::CODEC_TYPE_VORBIS),
-        "A_AAC/MPEG2/MAIN" | "A_AAC/MPEG2/LC" | "A_AAC/MPEG2/LC/SBR" | "A_AAC/MPEG2/SSR"
-        | "A_AAC/MPEG4/MAIN" | "A_AAC/MPEG4/LC" | "A_AAC/MPEG4/LC/SBR" | "A_AAC/MPEG4/SSR"
-        | "A_AAC/MPEG4/LTP" | "A_AAC" => Some(codecs::CODEC_TYPE_AAC),
         "A_PCM/INT/BIG" => match bit_depth? {
             16 => Some(codecs::CODEC_TYPE_PCM_S16BE),
             24 => Some(codecs::CODEC_TYPE_PCM_S24BE),
@@ -40,9 +31,14 @@ pub(crate) fn codec_id_to_type(track: &TrackElement) -> Option<CodecType> {
             64 => Some(codecs::CODEC_TYPE_PCM_F64LE),
             _ => None,
         },
-        _ => {
-            log::info!("unknown codec: {}", &track.codec_id);
-            None
+        codec_id => {
+            let codec = ids::from_mkv_codec_id(codec_id);
+
+            if codec.is_none() {
+                log::info!("unknown codec: {}", codec_id);
+            }
+
+            codec
         }
     }
 }
@@ -505,6 +505,7 @@ impl FormatReader for MkvReader {
                 id: track_id,
                 codec_params: codec_params.clone(),
                 language: track.language,
+                loops: Vec::new(),
             });
 
             states.insert(
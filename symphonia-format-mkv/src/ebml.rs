@@ -79,24 +79,14 @@ pub(crate) fn read_signed_vint<R: ReadBytes>(mut reader: R) -> Result<i64> {
 /// Reads a single unsigned variable size integer (as in RFC8794) from the stream
 /// and returns both its value and length in octects, or an error.
 fn read_vint<R: ReadBytes>(mut reader: R) -> Result<(u64, u32)> {
-    let byte = reader.read_byte()?;
-    if byte == 0xFF {
-        // Special case: unknown size elements.
-        return Ok((u64::MAX, 1));
-    }
-
-    let vint_width = byte.leading_zeros();
-    let mut vint = u64::from(byte);
-    // Clear VINT_MARKER bit
-    vint ^= 1 << (7 - vint_width);
+    let (vint, width) = reader.read_ebml_vint()?;
 
-    // Read remaining octets
-    for _ in 0..vint_width {
-        let byte = reader.read_byte()?;
-        vint = (vint << 8) | u64::from(byte);
+    // Special case: unknown size elements.
+    if width == 1 && vint == 0x7f {
+        return Ok((u64::MAX, 1));
     }
 
-    Ok((vint, vint_width + 1))
+    Ok((vint, width))
 }
 
 #[cfg(test)]
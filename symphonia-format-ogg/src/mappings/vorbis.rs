@@ -445,6 +445,11 @@ pub fn skip_codebook(bs: &mut BitReaderRtl<'_>) -> Result<()> {
     match lookup_type & 0xf {
         0 => (),
         1 | 2 => {
+            // A VQ codebook must have at least one dimension.
+            if codebook_dimensions == 0 {
+                return decode_error("ogg (vorbis): vq codebook has no dimensions");
+            }
+
             let _min_value = bs.read_bits_leq32(32)?;
             let _delta_value = bs.read_bits_leq32(32)?;
             let value_bits = bs.read_bits_leq32(4)? + 1;
@@ -453,12 +458,18 @@ pub fn skip_codebook(bs: &mut BitReaderRtl<'_>) -> Result<()> {
             // Lookup type is either 1 or 2 as per outer match.
             let lookup_values = match lookup_type {
                 1 => lookup1_values(codebook_entries, codebook_dimensions),
-                2 => codebook_entries * u32::from(codebook_dimensions),
+                2 => match codebook_entries.checked_mul(u32::from(codebook_dimensions)) {
+                    Some(lookup_values) => lookup_values,
+                    None => return decode_error("ogg (vorbis): too many vq lookup values"),
+                },
                 _ => unreachable!(),
             };
 
             // Multiplicands
-            bs.ignore_bits(lookup_values * value_bits)?;
+            match lookup_values.checked_mul(value_bits) {
+                Some(bits) => bs.ignore_bits(bits)?,
+                None => return decode_error("ogg (vorbis): too many vq lookup values"),
+            }
         }
         _ => return decode_error("ogg (vorbis): invalid codeword lookup type"),
     }
@@ -686,10 +697,28 @@ fn lookup1_values(entries: u32, dimensions: u16) -> u32 {
     // (value ^ dimensions) <= entries
     // [(value ^ dimensions) ^ (1 / dimensions)] = lower[entries ^ (1 / dimensions)]
     // value = lower[entries ^ (1 / dimensions)]
-    let value = (entries as f32).powf(1.0f32 / f32::from(dimensions)).floor() as u32;
+    //
+    // Start with a floating-point estimate and then correct it using integer arithmetic since the
+    // estimate may be off-by-one due to rounding. Large dimensions (which some old encoders did
+    // produce) can overflow the integer power, so treat an overflow as exceeding the entries.
+    debug_assert!(dimensions > 0);
+
+    let dimensions = u32::from(dimensions);
+
+    let pow_leq_entries = |value: u32| match value.checked_pow(dimensions) {
+        Some(pow) => pow <= entries,
+        None => false,
+    };
+
+    let mut value = (entries as f64).powf(1.0 / f64::from(dimensions)).floor() as u32;
 
-    assert!(value.pow(u32::from(dimensions)) <= entries);
-    assert!((value + 1).pow(u32::from(dimensions)) > entries);
+    while value > 0 && !pow_leq_entries(value) {
+        value -= 1;
+    }
+
+    while pow_leq_entries(value + 1) {
+        value += 1;
+    }
 
     value
 }
@@ -285,12 +285,8 @@ impl PageReader {
     {
         loop {
             match self.try_next_page(reader) {
-                Ok(_) => {
-                    // Exit if a page with the specific serial is found.
-                    if self.header.serial == serial && !self.header.is_continuation {
-                        break;
-                    }
-                }
+                // Exit if a page with the specific serial is found.
+                Ok(_) if self.header.serial == serial && !self.header.is_continuation => break,
                 Err(Error::IoError(e)) => return Err(Error::from(e)),
                 _ => (),
             }
@@ -0,0 +1,361 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_ATRAC3, CODEC_TYPE_ATRAC3PLUS};
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+/// The length of the EA3 header.
+const EA3_HEADER_LEN: usize = 96;
+
+/// The length of the header of the ID3v2-like "ea3" tag preceding the EA3 header.
+const EA3_TAG_HEADER_LEN: usize = 10;
+
+/// The codec identifiers of the EA3 header.
+const OMA_CODEC_ID_ATRAC3: u8 = 0;
+const OMA_CODEC_ID_ATRAC3PLUS: u8 = 1;
+
+/// The sample rates, in units of 100 Hz, indexed by the sample rate index of the codec parameters.
+const OMA_SAMPLE_RATES: [u32; 5] = [320, 441, 480, 882, 960];
+
+/// Gets the channels of an ATRAC3plus channel configuration.
+fn atrac3plus_channels(id: u32) -> Option<Channels> {
+    let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+    let surround = front | Channels::LFE1 | Channels::REAR_LEFT | Channels::REAR_RIGHT;
+
+    let channels = match id {
+        1 => Channels::FRONT_LEFT,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3 => front,
+        4 => front | Channels::REAR_CENTRE,
+        5 => surround,
+        6 => surround | Channels::REAR_CENTRE,
+        7 => surround | Channels::SIDE_LEFT | Channels::SIDE_RIGHT,
+        _ => return None,
+    };
+
+    Some(channels)
+}
+
+/// Skips the ID3v2-like "ea3" tag at the start of the stream, if present.
+fn skip_ea3_tag(source: &mut MediaSourceStream) -> Result<()> {
+    let mut header = [0; EA3_TAG_HEADER_LEN];
+    source.read_buf_exact(&mut header[..3])?;
+
+    if header[..3] != *b"ea3" {
+        // There is no tag, rewind to the EA3 header.
+        source.seek_buffered_rev(3);
+        return Ok(());
+    }
+
+    source.read_buf_exact(&mut header[3..])?;
+
+    // The tag size is a 28-bit synchsafe integer, exactly as in ID3v2.
+    if header[6..10].iter().any(|&b| b & 0x80 != 0) {
+        return decode_error("oma: invalid ea3 tag size");
+    }
+
+    let size = header[6..10].iter().fold(0, |size, &b| (size << 7) | u64::from(b));
+
+    source.ignore_bytes(size)?;
+
+    Ok(())
+}
+
+/// OpenMG Audio (OMA) format reader.
+///
+/// `OmaReader` implements a demuxer for the OMA container used by Sony's SonicStage and the
+/// MiniDisc and Walkman devices it supports. Each packet is one ATRAC3 or ATRAC3plus frame.
+/// Encrypted streams are not supported.
+pub struct OmaReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    frame_len: u64,
+    frames_per_packet: u64,
+    first_frame_pos: u64,
+    next_packet_ts: u64,
+}
+
+impl QueryDescriptor for OmaReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "oma",
+            "OpenMG Audio",
+            &["oma", "omg", "aa3"],
+            &["audio/x-oma", "audio/x-sony-oma"],
+            &[b"ea3", b"EA3"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for OmaReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        skip_ea3_tag(&mut source)?;
+
+        let mut header = [0; EA3_HEADER_LEN];
+        source.read_buf_exact(&mut header)?;
+
+        if header[..3] != *b"EA3" || header[4] != 0 || usize::from(header[5]) != EA3_HEADER_LEN {
+            return unsupported_error("oma: missing ea3 header");
+        }
+
+        // The encryption id is 0xffff or 0xff80 for unencrypted streams.
+        let encryption_id = u16::from_be_bytes([header[6], header[7]]);
+
+        if encryption_id != 0xffff && encryption_id != 0xff80 {
+            return unsupported_error("oma: encrypted streams are not supported");
+        }
+
+        let codec_params = u32::from_be_bytes([0, header[33], header[34], header[35]]);
+
+        let sample_rate = match OMA_SAMPLE_RATES.get(((codec_params >> 13) & 0x7) as usize) {
+            Some(rate) => 100 * rate,
+            None => return decode_error("oma: invalid sample rate"),
+        };
+
+        let mut params = CodecParameters::new();
+
+        let (frame_len, frames_per_packet) = match header[32] {
+            OMA_CODEC_ID_ATRAC3 => {
+                let frame_len = 8 * (codec_params & 0x3ff);
+                let is_joint_stereo = (codec_params >> 17) & 0x1;
+
+                // ATRAC3 in OMA is always stereo. Synthesize the extra data of the WAVE format
+                // to signal the coding mode to the decoder.
+                let mut extra_data = vec![0; 14];
+                extra_data[0..2].copy_from_slice(&1u16.to_le_bytes());
+                extra_data[2..6].copy_from_slice(&sample_rate.to_le_bytes());
+                extra_data[6..8].copy_from_slice(&(is_joint_stereo as u16).to_le_bytes());
+                extra_data[8..10].copy_from_slice(&(is_joint_stereo as u16).to_le_bytes());
+                extra_data[10..12].copy_from_slice(&1u16.to_le_bytes());
+
+                params
+                    .for_codec(CODEC_TYPE_ATRAC3)
+                    .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+                    .with_extra_data(extra_data.into_boxed_slice());
+
+                (frame_len, 1024)
+            }
+            OMA_CODEC_ID_ATRAC3PLUS => {
+                // Every ATRAC3plus frame is preceded by an 8 byte header.
+                let frame_len = 8 * (codec_params & 0x3ff) + 8;
+
+                let channels = match atrac3plus_channels((codec_params >> 10) & 0x7) {
+                    Some(channels) => channels,
+                    None => return decode_error("oma: invalid atrac3plus channel configuration"),
+                };
+
+                params.for_codec(CODEC_TYPE_ATRAC3PLUS).with_channels(channels);
+
+                (frame_len, 2048)
+            }
+            _ => return unsupported_error("oma: unsupported codec"),
+        };
+
+        if frame_len == 0 {
+            return decode_error("oma: invalid frame length");
+        }
+
+        let frame_len = u64::from(frame_len);
+        let first_frame_pos = source.pos();
+
+        params
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_max_frames_per_packet(frames_per_packet);
+
+        if let Some(len) = source.byte_len() {
+            let n_packets = len.saturating_sub(first_frame_pos) / frame_len;
+            params.with_n_frames(n_packets * frames_per_packet);
+        }
+
+        Ok(OmaReader {
+            reader: source,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: Default::default(),
+            frame_len,
+            frames_per_packet,
+            first_frame_pos,
+            next_packet_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let buf = self.reader.read_boxed_slice_exact(self.frame_len as usize)?;
+
+        let ts = self.next_packet_ts;
+        let dur = self.frames_per_packet;
+
+        self.next_packet_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let required_ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => match params.time_base {
+                Some(tb) => tb.calc_timestamp(time),
+                None => return seek_error(SeekErrorKind::Unseekable),
+            },
+        };
+
+        if let Some(n_frames) = params.n_frames {
+            if required_ts >= n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        debug!("seeking to ts={}", required_ts);
+
+        // Every frame has the same length, therefore the frame containing the desired timestamp
+        // can be seeked to directly.
+        let packet = required_ts / self.frames_per_packet;
+        let pos = self.first_frame_pos + packet * self.frame_len;
+
+        if self.reader.is_seekable() {
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+        else if pos >= self.reader.pos() {
+            self.reader.ignore_bytes(pos - self.reader.pos())?;
+        }
+        else {
+            return seek_error(SeekErrorKind::ForwardOnly);
+        }
+
+        self.next_packet_ts = packet * self.frames_per_packet;
+
+        debug!(
+            "seeked to ts={} (delta={})",
+            self.next_packet_ts,
+            required_ts - self.next_packet_ts
+        );
+
+        Ok(SeekedTo { track_id: 0, required_ts, actual_ts: self.next_packet_ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::codecs::{CODEC_TYPE_ATRAC3, CODEC_TYPE_ATRAC3PLUS};
+    use symphonia_core::formats::{FormatReader, SeekMode, SeekTo};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::OmaReader;
+
+    fn open(buf: Vec<u8>) -> symphonia_core::errors::Result<OmaReader> {
+        let source = Box::new(Cursor::new(buf));
+        OmaReader::try_new(MediaSourceStream::new(source, Default::default()), &Default::default())
+    }
+
+    /// Returns an EA3 header for the codec `id` with the codec parameters `params`.
+    fn ea3_header(id: u8, params: u32) -> Vec<u8> {
+        let mut header = vec![0; 96];
+        header[..6].copy_from_slice(&[b'E', b'A', b'3', 1, 0, 96]);
+        header[6..8].copy_from_slice(&[0xff, 0xff]);
+        header[32] = id;
+        header[33..36].copy_from_slice(&params.to_be_bytes()[1..]);
+        header
+    }
+
+    #[test]
+    fn verify_atrac3_oma_reader() {
+        // An ea3 tag with a 16 byte body.
+        let mut buf = vec![b'e', b'a', b'3', 3, 0, 0, 0, 0, 0, 16];
+        buf.extend_from_slice(&[0; 16]);
+
+        // A 44.1 kHz, joint stereo, 384 byte frame, ATRAC3 stream with 3 frames.
+        buf.extend(ea3_header(0, 1 << 17 | 1 << 13 | 48));
+        buf.extend((0..3).flat_map(|i| std::iter::repeat(i).take(384)));
+
+        let mut reader = open(buf).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_ATRAC3);
+        assert_eq!(params.sample_rate, Some(44_100));
+        assert_eq!(params.n_frames, Some(3 * 1024));
+        assert_eq!(params.extra_data.as_deref().map(|e| e[6]), Some(1));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts, packet.dur, packet.buf().len()), (0, 1024, 384));
+
+        // Seek into the middle of the last frame.
+        let seeked = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 2500, track_id: 0 });
+        assert_eq!(seeked.unwrap().actual_ts, 2048);
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts, packet.buf()[0]), (2048, 2));
+        assert!(reader.next_packet().is_err());
+    }
+
+    #[test]
+    fn verify_atrac3plus_oma_reader() {
+        // A 48 kHz, stereo, 1024 byte frame, ATRAC3plus stream without an ea3 tag.
+        let mut buf = ea3_header(1, 2 << 13 | 2 << 10 | 127);
+        buf.extend_from_slice(&[0; 1024]);
+
+        let mut reader = open(buf).unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_ATRAC3PLUS);
+        assert_eq!(params.sample_rate, Some(48_000));
+        assert_eq!(params.channels.map(|c| c.count()), Some(2));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.dur, packet.buf().len()), (2048, 1024));
+    }
+
+    #[test]
+    fn verify_oma_reader_rejects_invalid_streams() {
+        // Encrypted streams are not supported.
+        let mut buf = ea3_header(0, 1 << 13 | 48);
+        buf[6..8].copy_from_slice(&[0x00, 0x01]);
+        assert!(open(buf).is_err());
+
+        // MP3, LPCM, and WMA streams are not supported.
+        assert!(open(ea3_header(3, 1 << 13 | 48)).is_err());
+
+        // Reserved sample rates are rejected.
+        assert!(open(ea3_header(0, 7 << 13 | 48)).is_err());
+    }
+}
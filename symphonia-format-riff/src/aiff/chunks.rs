@@ -130,6 +130,12 @@ impl CommonChunk {
             FormatData::Adpcm(_) => {
                 unsupported_error("aiff: packet info not implemented for format Adpcm")
             }
+            FormatData::Gsm(_) => {
+                unsupported_error("aiff: packet info not implemented for format Gsm")
+            }
+            FormatData::Atrac3(_) => {
+                unsupported_error("aiff: packet info not implemented for format Atrac3")
+            }
         }
     }
 }
@@ -148,10 +154,7 @@ impl ParseChunk for CommonChunk {
 
         let format_data = Self::read_pcm_fmt(sample_size as u16, n_channels as u16);
 
-        let format_data = match format_data {
-            Ok(data) => data,
-            Err(e) => return Err(e),
-        };
+        let format_data = format_data?;
 
         Ok(CommonChunk { n_channels, n_sample_frames, sample_size, sample_rate, format_data })
     }
@@ -192,6 +195,12 @@ impl fmt::Display for CommonChunk {
             FormatData::Adpcm(_) => {
                 writeln!(f, "\tformat_data: Adpcm DISPLAY UNSUPPORTED {{")?;
             }
+            FormatData::Gsm(_) => {
+                writeln!(f, "\tformat_data: Gsm DISPLAY UNSUPPORTED {{")?;
+            }
+            FormatData::Atrac3(_) => {
+                writeln!(f, "\tformat_data: Atrac3 DISPLAY UNSUPPORTED {{")?;
+            }
         };
 
         writeln!(f, "\t}}")?;
@@ -240,10 +249,7 @@ impl CommonChunkParser for ChunkParser<CommonChunk> {
             _ => return unsupported_error("aifc: Compression type not implemented"),
         };
 
-        let format_data = match format_data {
-            Ok(data) => data,
-            Err(e) => return Err(e),
-        };
+        let format_data = format_data?;
 
         Ok(CommonChunk { n_channels, n_sample_frames, sample_size, sample_rate, format_data })
     }
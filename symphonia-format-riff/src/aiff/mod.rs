@@ -10,6 +10,7 @@ use std::io::{Seek, SeekFrom};
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::errors::{seek_error, unsupported_error};
 use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::*;
 use symphonia_core::meta::{Metadata, MetadataLog};
@@ -42,6 +43,7 @@ pub struct AiffReader {
     packet_info: PacketInfo,
     data_start_pos: u64,
     data_end_pos: u64,
+    unknown_chunks: Vec<RawChunk>,
 }
 
 impl QueryDescriptor for AiffReader {
@@ -63,8 +65,16 @@ impl QueryDescriptor for AiffReader {
     }
 }
 
-impl FormatReader for AiffReader {
-    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+impl AiffReader {
+    /// Instantiates a `AiffReader` that treats chunks it does not recognize as selected by
+    /// `unknown_chunks`. The `FormatReader::try_new` constructor skips all unrecognized chunks.
+    ///
+    /// A `Probe` always uses `FormatReader::try_new`. Therefore, to handle unrecognized chunks, the
+    /// reader must be instantiated directly with this constructor.
+    pub fn try_new_with_unknown_chunks(
+        mut source: MediaSourceStream,
+        unknown_chunks: &UnknownChunkOptions,
+    ) -> Result<Self> {
         // The FORM marker should be present.
         let marker = source.read_quad_bytes()?;
         if marker != AIFF_STREAM_MARKER {
@@ -79,6 +89,8 @@ impl FormatReader for AiffReader {
 
         let mut riff_chunks = ChunksReader::<RiffAiffChunks>::new(riff_len, ByteOrder::BigEndian);
 
+        riff_chunks.with_unknown_chunks(unknown_chunks);
+
         let mut codec_params = CodecParameters::new();
         //TODO: Chunks such as marker contain metadata, get it.
         let metadata: MetadataLog = Default::default();
@@ -135,11 +147,18 @@ impl FormatReader for AiffReader {
                         packet_info,
                         data_start_pos,
                         data_end_pos,
+                        unknown_chunks: riff_chunks.take_raw_chunks(),
                     });
                 }
             }
         }
     }
+}
+
+impl FormatReader for AiffReader {
+    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        Self::try_new_with_unknown_chunks(source, &Default::default())
+    }
 
     fn next_packet(&mut self) -> Result<Packet> {
         next_packet(
@@ -163,6 +182,10 @@ impl FormatReader for AiffReader {
         &self.tracks
     }
 
+    fn unknown_chunks(&self) -> &[RawChunk] {
+        &self.unknown_chunks
+    }
+
     fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
         if self.tracks.is_empty() || self.packet_info.is_empty() {
             return seek_error(SeekErrorKind::Unseekable);
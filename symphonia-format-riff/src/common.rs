@@ -13,6 +13,7 @@ use symphonia_core::audio::Channels;
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::codecs::CodecType;
 use symphonia_core::errors::{decode_error, end_of_stream_error, Error, Result};
+use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::{MediaSourceStream, ReadBytes};
 
@@ -103,16 +104,38 @@ fn test_try_channel_count_to_mask() {
 /// `parse_tag` on T is called to return an object capable of parsing/reading that chunk or `None`.
 /// This makes reading the actual chunk data lazy in that the  chunk is not read until the object is
 /// consumed.
+///
+/// By default, unrecognized chunks are skipped. See `with_unknown_chunks` to consume them instead.
 pub struct ChunksReader<T: ParseChunkTag> {
     len: u32,
     byte_order: ByteOrder,
     consumed: u32,
+    unknown: Option<UnknownChunkOptions>,
+    raw_chunks: Vec<RawChunk>,
     phantom: PhantomData<T>,
 }
 
 impl<T: ParseChunkTag> ChunksReader<T> {
     pub fn new(len: u32, byte_order: ByteOrder) -> Self {
-        ChunksReader { len, byte_order, consumed: 0, phantom: PhantomData }
+        ChunksReader {
+            len,
+            byte_order,
+            consumed: 0,
+            unknown: None,
+            raw_chunks: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Treat unrecognized chunks as per `options` instead of skipping them.
+    pub fn with_unknown_chunks(&mut self, options: &UnknownChunkOptions) -> &mut Self {
+        self.unknown = Some(options.clone());
+        self
+    }
+
+    /// Takes the unrecognized chunks that were retained because passthrough was enabled.
+    pub fn take_raw_chunks(&mut self) -> Vec<RawChunk> {
+        std::mem::take(&mut self.raw_chunks)
     }
 
     pub fn next<B: ReadBytes>(&mut self, reader: &mut B) -> Result<Option<T>> {
@@ -130,6 +153,7 @@ impl<T: ParseChunkTag> ChunksReader<T> {
             }
 
             // Read tag and len, the chunk header.
+            let pos = reader.pos();
             let tag = reader.read_quad_bytes()?;
 
             let len = match self.byte_order {
@@ -160,16 +184,28 @@ impl<T: ParseChunkTag> ChunksReader<T> {
 
             match T::parse_tag(tag, len) {
                 Some(chunk) => return Ok(Some(chunk)),
-                None => {
-                    // As per the RIFF spec, unknown chunks are to be ignored.
-                    info!(
-                        "ignoring unknown chunk: tag={}, len={}.",
-                        String::from_utf8_lossy(&tag),
-                        len
-                    );
-
-                    reader.ignore_bytes(u64::from(len))?
-                }
+                None => match &self.unknown {
+                    Some(unknown) if unknown.wants(tag, len) => {
+                        let data = reader.read_boxed_slice_exact(len as usize)?;
+                        let chunk = RawChunk { id: tag, pos, data };
+
+                        unknown.handle(&chunk)?;
+
+                        if unknown.is_passthrough() {
+                            self.raw_chunks.push(chunk);
+                        }
+                    }
+                    _ => {
+                        // As per the RIFF spec, unknown chunks are to be ignored.
+                        info!(
+                            "ignoring unknown chunk: tag={}, len={}.",
+                            String::from_utf8_lossy(&tag),
+                            len
+                        );
+
+                        reader.ignore_bytes(u64::from(len))?
+                    }
+                },
             }
         }
     }
@@ -219,6 +255,8 @@ pub enum FormatData {
     Extensible(FormatExtensible),
     ALaw(FormatALaw),
     MuLaw(FormatMuLaw),
+    Gsm(FormatGsm),
+    Atrac3(FormatAtrac3),
 }
 
 pub struct FormatPcm {
@@ -239,6 +277,24 @@ pub struct FormatAdpcm {
     pub codec: CodecType,
 }
 
+pub struct FormatGsm {
+    /// The number of audio frames in each block.
+    pub frames_per_block: u16,
+    /// Channel bitmask.
+    pub channels: Channels,
+    /// Codec type.
+    pub codec: CodecType,
+}
+
+pub struct FormatAtrac3 {
+    /// Channel bitmask.
+    pub channels: Channels,
+    /// Codec type.
+    pub codec: CodecType,
+    /// Codec-specific extra data, required to decode the stream.
+    pub extra_data: Box<[u8]>,
+}
+
 pub struct FormatIeeeFloat {
     /// Channel bitmask.
     pub channels: Channels,
@@ -344,17 +400,27 @@ pub fn next_packet(
 
     let blocks_per_packet = num_blocks_left.min(packet_info.max_blocks_per_packet);
 
-    let dur = blocks_per_packet * packet_info.frames_per_block;
     let packet_len = blocks_per_packet * packet_info.block_size;
 
-    // Copy the frames.
-    let packet_buf = reader.read_boxed_slice(packet_len as usize)?;
+    // Copy the frames. Less data than requested is only read if the stream ended, in which case
+    // the data chunk was truncated and any partial block at the end is dropped.
+    let mut packet_buf = reader.read_boxed_slice(packet_len as usize)?.into_vec();
+
+    let blocks_read = packet_buf.len() as u64 / packet_info.block_size;
+
+    if blocks_read == 0 {
+        return end_of_stream_error();
+    }
+
+    packet_buf.truncate((blocks_read * packet_info.block_size) as usize);
+
+    let dur = blocks_read * packet_info.frames_per_block;
 
     // The packet timestamp is the position of the first byte of the first frame in the
     // packet relative to the start of the data chunk divided by the length per frame.
     let pts = packet_info.get_frames(pos - data_start_pos);
 
-    Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf))
+    Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf.into_boxed_slice()))
 }
 
 /// TODO: format here refers to format chunk in Wave terminology, but the data being handled here is generic - find a better name, or combine with append_data_params
@@ -392,6 +458,15 @@ pub fn append_format_params(
         FormatData::MuLaw(mulaw) => {
             codec_params.for_codec(mulaw.codec).with_channels(mulaw.channels);
         }
+        FormatData::Gsm(gsm) => {
+            codec_params.for_codec(gsm.codec).with_channels(gsm.channels);
+        }
+        FormatData::Atrac3(atrac3) => {
+            codec_params
+                .for_codec(atrac3.codec)
+                .with_channels(atrac3.channels)
+                .with_extra_data(atrac3.extra_data.clone());
+        }
     }
 }
 
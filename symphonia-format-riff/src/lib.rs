@@ -20,8 +20,12 @@ mod common;
 mod aiff;
 #[cfg(feature = "wav")]
 mod wave;
+#[cfg(feature = "w64")]
+mod wave64;
 
 #[cfg(feature = "aiff")]
 pub use aiff::AiffReader;
 #[cfg(feature = "wav")]
 pub use wave::WavReader;
+#[cfg(feature = "w64")]
+pub use wave64::Wave64Reader;
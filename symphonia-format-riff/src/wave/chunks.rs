@@ -9,21 +9,25 @@ use std::fmt;
 
 use symphonia_core::audio::Channels;
 use symphonia_core::codecs::CodecParameters;
-use symphonia_core::codecs::CodecType;
+use symphonia_core::codecs::{ids, CodecType};
 use symphonia_core::codecs::{
-    CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32LE,
-    CODEC_TYPE_PCM_F64LE, CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24LE,
-    CODEC_TYPE_PCM_S32LE, CODEC_TYPE_PCM_U8,
+    CODEC_TYPE_ADPCM_G722, CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS, CODEC_TYPE_ATRAC3,
+    CODEC_TYPE_GSM_MS, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32LE, CODEC_TYPE_PCM_F64LE,
+    CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24LE, CODEC_TYPE_PCM_S32LE,
+    CODEC_TYPE_PCM_U8,
 };
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::LoopRegion;
 use symphonia_core::io::{MediaSourceStream, ReadBytes};
 use symphonia_core::meta::{MetadataBuilder, MetadataRevision, Tag};
 use symphonia_metadata::riff;
 
+use log::info;
+
 use crate::common::{
     fix_channel_mask, try_channel_count_to_mask, ByteOrder, ChunkParser, ChunksReader, FormatALaw,
-    FormatAdpcm, FormatData, FormatExtensible, FormatIeeeFloat, FormatMuLaw, FormatPcm, NullChunks,
-    PacketInfo, ParseChunk, ParseChunkTag,
+    FormatAdpcm, FormatAtrac3, FormatData, FormatExtensible, FormatGsm, FormatIeeeFloat,
+    FormatMuLaw, FormatPcm, NullChunks, PacketInfo, ParseChunk, ParseChunkTag,
 };
 
 pub struct WaveFormatChunk {
@@ -126,6 +130,103 @@ impl WaveFormatChunk {
         Ok(FormatData::Adpcm(FormatAdpcm { bits_per_sample, channels, codec }))
     }
 
+    fn read_g722_fmt<B: ReadBytes>(
+        reader: &mut B,
+        bits_per_sample: u16,
+        n_channels: u16,
+        len: u32,
+    ) -> Result<FormatData> {
+        // Each byte codes two samples, however, encoders disagree on whether bits per sample
+        // should be 4 or 8 bits.
+        if bits_per_sample != 4 && bits_per_sample != 8 {
+            return decode_error("wav: bits per sample for fmt_g722 must be 4 or 8 bits");
+        }
+
+        // WaveFormatEx may be present, but no extra data is defined for G.722.
+        if len > 16 {
+            if len < 18 {
+                return decode_error("wav: malformed fmt_g722 chunk");
+            }
+
+            let extra_size = reader.read_u16()?;
+            reader.ignore_bytes(u64::from(extra_size))?;
+        }
+
+        let channels = try_channel_count_to_mask(n_channels)?;
+        Ok(FormatData::Adpcm(FormatAdpcm {
+            bits_per_sample,
+            channels,
+            codec: CODEC_TYPE_ADPCM_G722,
+        }))
+    }
+
+    fn read_gsm_fmt<B: ReadBytes>(
+        reader: &mut B,
+        n_channels: u16,
+        block_align: u16,
+        len: u32,
+    ) -> Result<FormatData> {
+        // WaveFormatEx with extension data length field present and the samples per block.
+        if len < 20 {
+            return decode_error("wav: malformed fmt_gsm chunk");
+        }
+
+        let extra_size = reader.read_u16()?;
+
+        if extra_size < 2 {
+            return decode_error("wav: malformed fmt_gsm chunk");
+        }
+
+        let frames_per_block = reader.read_u16()?;
+        reader.ignore_bytes(u64::from(extra_size - 2))?;
+
+        // Microsoft GSM 6.10 packs two 160 sample GSM frames into a 65 byte block.
+        if n_channels != 1 {
+            return unsupported_error("wav: fmt_gsm only supports mono");
+        }
+
+        if block_align != 65 || frames_per_block != 320 {
+            return decode_error("wav: invalid block size for fmt_gsm");
+        }
+
+        let channels = try_channel_count_to_mask(n_channels)?;
+        Ok(FormatData::Gsm(FormatGsm { frames_per_block, channels, codec: CODEC_TYPE_GSM_MS }))
+    }
+
+    fn read_atrac3_fmt<B: ReadBytes>(
+        reader: &mut B,
+        n_channels: u16,
+        block_align: u16,
+        len: u32,
+    ) -> Result<FormatData> {
+        // WaveFormatEx with 14 bytes of extra data containing the coding mode.
+        if len < 32 {
+            return decode_error("wav: malformed fmt_atrac3 chunk");
+        }
+
+        let extra_size = reader.read_u16()?;
+
+        if extra_size != 14 {
+            return decode_error("wav: extra data size not 14 bytes for fmt_atrac3 chunk");
+        }
+
+        let mut extra_data = vec![0; 14];
+        reader.read_buf_exact(&mut extra_data)?;
+
+        let channels = try_channel_count_to_mask(n_channels)?;
+
+        // Each block is one frame, shared equally between all channels.
+        if block_align == 0 || block_align % n_channels != 0 {
+            return decode_error("wav: invalid block size for fmt_atrac3");
+        }
+
+        Ok(FormatData::Atrac3(FormatAtrac3 {
+            channels,
+            codec: CODEC_TYPE_ATRAC3,
+            extra_data: extra_data.into_boxed_slice(),
+        }))
+    }
+
     fn read_ieee_fmt<B: ReadBytes>(
         reader: &mut B,
         bits_per_sample: u16,
@@ -360,6 +461,18 @@ impl WaveFormatChunk {
                     + 1) as u64;
                 PacketInfo::with_blocks(self.block_align, frames_per_block)
             }
+            FormatData::Adpcm(FormatAdpcm { codec, .. }) if codec == CODEC_TYPE_ADPCM_G722 => {
+                // Every byte codes two samples of a channel. Ignore the block alignment since
+                // encoders do not agree on its value.
+                PacketInfo::with_blocks(self.n_channels, 2)
+            }
+            FormatData::Gsm(FormatGsm { frames_per_block, .. }) => {
+                PacketInfo::with_blocks(self.block_align, u64::from(frames_per_block))
+            }
+            FormatData::Atrac3(_) => {
+                // Every block is a frame of 1024 samples per channel.
+                PacketInfo::with_blocks(self.block_align, 1024)
+            }
             _ => Ok(PacketInfo::without_blocks(self.block_align)),
         }
     }
@@ -388,6 +501,9 @@ impl ParseChunk for WaveFormatChunk {
         const WAVE_FORMAT_ALAW: u16 = 0x0006;
         const WAVE_FORMAT_MULAW: u16 = 0x0007;
         const WAVE_FORMAT_ADPCM_IMA: u16 = 0x0011;
+        const WAVE_FORMAT_GSM610: u16 = 0x0031;
+        const WAVE_FORMAT_G722_ADPCM: u16 = 0x0065;
+        const WAVE_FORMAT_SONY_SCX: u16 = 0x0270;
         const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
 
         let format_data = match format {
@@ -413,8 +529,22 @@ impl ParseChunk for WaveFormatChunk {
                 len,
                 CODEC_TYPE_ADPCM_IMA_WAV,
             ),
+            // The Microsoft GSM 6.10 Format
+            WAVE_FORMAT_GSM610 => Self::read_gsm_fmt(reader, n_channels, block_align, len),
+            // The G.722 ADPCM Format
+            WAVE_FORMAT_G722_ADPCM => Self::read_g722_fmt(reader, bits_per_sample, n_channels, len),
+            // The Sony ATRAC3 Format
+            WAVE_FORMAT_SONY_SCX => Self::read_atrac3_fmt(reader, n_channels, block_align, len),
             // Unsupported format.
-            _ => return unsupported_error("wav: unsupported wave format"),
+            _ => {
+                match ids::from_wave_format_tag(format) {
+                    Some(codec) => {
+                        info!("wav: unsupported wave format {:#06x} ({})", format, codec)
+                    }
+                    None => info!("wav: unsupported wave format {:#06x}", format),
+                }
+                return unsupported_error("wav: unsupported wave format");
+            }
         }?;
 
         Ok(WaveFormatChunk { n_channels, sample_rate, avg_bytes_per_sec, block_align, format_data })
@@ -465,6 +595,18 @@ impl fmt::Display for WaveFormatChunk {
                 writeln!(f, "\t\tchannels: {},", mulaw.channels)?;
                 writeln!(f, "\t\tcodec: {},", mulaw.codec)?;
             }
+            FormatData::Gsm(ref gsm) => {
+                writeln!(f, "\tformat_data: Gsm {{")?;
+                writeln!(f, "\t\tframes_per_block: {},", gsm.frames_per_block)?;
+                writeln!(f, "\t\tchannels: {},", gsm.channels)?;
+                writeln!(f, "\t\tcodec: {},", gsm.codec)?;
+            }
+            FormatData::Atrac3(ref atrac3) => {
+                writeln!(f, "\tformat_data: Atrac3 {{")?;
+                writeln!(f, "\t\tchannels: {},", atrac3.channels)?;
+                writeln!(f, "\t\tcodec: {},", atrac3.codec)?;
+                writeln!(f, "\t\textra_data: {:?},", &atrac3.extra_data)?;
+            }
         };
 
         writeln!(f, "\t}}")?;
@@ -496,6 +638,84 @@ impl fmt::Display for FactChunk {
     }
 }
 
+/// A sample loop of a sampler chunk.
+pub struct SampleLoop {
+    /// The loop type. 0 for a forward loop, 1 for an alternating (ping-pong) loop, and 2 for a
+    /// backward loop.
+    pub loop_type: u32,
+    /// The first frame of the loop.
+    pub start: u32,
+    /// The last frame of the loop.
+    pub end: u32,
+    /// The number of times the loop is played, or 0 for infinite.
+    pub play_count: u32,
+}
+
+pub struct SamplerChunk {
+    /// The MIDI note that is played at the original pitch.
+    pub midi_unity_note: u32,
+    pub loops: Vec<SampleLoop>,
+}
+
+impl ParseChunk for SamplerChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<Self> {
+        // The sampler chunk header is 36 bytes long, and each sample loop is 24 bytes long.
+        if len < 36 {
+            return decode_error("wav: malformed smpl chunk");
+        }
+
+        // Ignore the manufacturer, product, and sample period.
+        reader.ignore_bytes(12)?;
+
+        let midi_unity_note = reader.read_u32()?;
+
+        // Ignore the MIDI pitch fraction, SMPTE format, and SMPTE offset.
+        reader.ignore_bytes(12)?;
+
+        let n_loops = reader.read_u32()?;
+        let _sampler_data_len = reader.read_u32()?;
+
+        if u64::from(n_loops) * 24 > u64::from(len - 36) {
+            return decode_error("wav: malformed smpl chunk");
+        }
+
+        let mut loops = Vec::with_capacity(n_loops as usize);
+
+        for _ in 0..n_loops {
+            let _cue_point_id = reader.read_u32()?;
+            let loop_type = reader.read_u32()?;
+            let start = reader.read_u32()?;
+            let end = reader.read_u32()?;
+            let _fraction = reader.read_u32()?;
+            let play_count = reader.read_u32()?;
+
+            loops.push(SampleLoop { loop_type, start, end, play_count });
+        }
+
+        // Ignore the sampler specific data.
+        reader.ignore_bytes(u64::from(len - 36 - 24 * n_loops))?;
+
+        Ok(SamplerChunk { midi_unity_note, loops })
+    }
+}
+
+impl fmt::Display for SamplerChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SamplerChunk {{")?;
+        writeln!(f, "	midi_unity_note: {},", self.midi_unity_note)?;
+        writeln!(f, "	loops: [")?;
+        for lp in &self.loops {
+            writeln!(
+                f,
+                "		{{ loop_type: {}, start: {}, end: {}, play_count: {} }},",
+                lp.loop_type, lp.start, lp.end, lp.play_count
+            )?;
+        }
+        writeln!(f, "	]")?;
+        writeln!(f, "}}")
+    }
+}
+
 pub struct ListChunk {
     pub form: [u8; 4],
     pub len: u32,
@@ -556,6 +776,7 @@ pub enum RiffWaveChunks {
     Format(ChunkParser<WaveFormatChunk>),
     List(ChunkParser<ListChunk>),
     Fact(ChunkParser<FactChunk>),
+    Sampler(ChunkParser<SamplerChunk>),
     Data(ChunkParser<DataChunk>),
 }
 
@@ -571,6 +792,7 @@ impl ParseChunkTag for RiffWaveChunks {
             b"fmt " => parser!(RiffWaveChunks::Format, WaveFormatChunk, tag, len),
             b"LIST" => parser!(RiffWaveChunks::List, ListChunk, tag, len),
             b"fact" => parser!(RiffWaveChunks::Fact, FactChunk, tag, len),
+            b"smpl" => parser!(RiffWaveChunks::Sampler, SamplerChunk, tag, len),
             b"data" => parser!(RiffWaveChunks::Data, DataChunk, tag, len),
             _ => None,
         }
@@ -591,6 +813,22 @@ impl ParseChunkTag for RiffInfoListChunks {
     }
 }
 
+/// Gets the forward loops of a sampler chunk as loop regions. Other loop types are not supported.
+pub fn sampler_loops(smpl: &SamplerChunk) -> Vec<LoopRegion> {
+    smpl.loops
+        .iter()
+        .filter(|lp| {
+            let is_forward = lp.loop_type == 0;
+            if !is_forward {
+                info!("ignoring smpl loop of unsupported type {}", lp.loop_type);
+            }
+            is_forward && lp.start <= lp.end
+        })
+        // The end of a sample loop is inclusive.
+        .map(|lp| LoopRegion::new(u64::from(lp.start), u64::from(lp.end) + 1, lp.play_count))
+        .collect()
+}
+
 pub fn append_fact_params(codec_params: &mut CodecParameters, fact: &FactChunk) {
     codec_params.with_n_frames(u64::from(fact.n_frames));
 }
@@ -10,18 +10,20 @@ use std::io::{Seek, SeekFrom};
 use symphonia_core::codecs::CodecParameters;
 use symphonia_core::errors::{seek_error, unsupported_error};
 use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::*;
 use symphonia_core::meta::{Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_format;
 
-use log::{debug, error};
+use log::{debug, error, warn};
 
 use crate::common::{
-    append_data_params, append_format_params, next_packet, ByteOrder, ChunksReader, PacketInfo,
+    append_data_params, append_format_params, next_packet, ByteOrder, ChunkParser, ChunksReader,
+    PacketInfo,
 };
-mod chunks;
+pub(crate) mod chunks;
 use chunks::*;
 
 /// WAVE is actually a RIFF stream, with a "RIFF" ASCII stream marker.
@@ -40,6 +42,7 @@ pub struct WavReader {
     packet_info: PacketInfo,
     data_start_pos: u64,
     data_end_pos: u64,
+    unknown_chunks: Vec<RawChunk>,
 }
 
 impl QueryDescriptor for WavReader {
@@ -61,8 +64,16 @@ impl QueryDescriptor for WavReader {
     }
 }
 
-impl FormatReader for WavReader {
-    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+impl WavReader {
+    /// Instantiates a `WavReader` that treats chunks it does not recognize as selected by
+    /// `unknown_chunks`. The `FormatReader::try_new` constructor skips all unrecognized chunks.
+    ///
+    /// A `Probe` always uses `FormatReader::try_new`. Therefore, to handle unrecognized chunks, the
+    /// reader must be instantiated directly with this constructor.
+    pub fn try_new_with_unknown_chunks(
+        mut source: MediaSourceStream,
+        unknown_chunks: &UnknownChunkOptions,
+    ) -> Result<Self> {
         // The RIFF marker should be present.
         let marker = source.read_quad_bytes()?;
 
@@ -86,9 +97,12 @@ impl FormatReader for WavReader {
         let mut riff_chunks =
             ChunksReader::<RiffWaveChunks>::new(riff_len, ByteOrder::LittleEndian);
 
+        riff_chunks.with_unknown_chunks(unknown_chunks);
+
         let mut codec_params = CodecParameters::new();
         let mut metadata: MetadataLog = Default::default();
         let mut packet_info = PacketInfo::without_blocks(0);
+        let mut loops = Vec::new();
 
         loop {
             let chunk = riff_chunks.next(&mut source)?;
@@ -123,6 +137,11 @@ impl FormatReader for WavReader {
                     // Append Fact chunk fields to codec parameters.
                     append_fact_params(&mut codec_params, &fact);
                 }
+                RiffWaveChunks::Sampler(smpl) => {
+                    let smpl = smpl.parse(&mut source)?;
+
+                    loops.extend(sampler_loops(&smpl));
+                }
                 RiffWaveChunks::List(lst) => {
                     let list = lst.parse(&mut source)?;
 
@@ -143,20 +162,49 @@ impl FormatReader for WavReader {
                     // Append Data chunk fields to codec parameters.
                     append_data_params(&mut codec_params, data.len as u64, &packet_info);
 
+                    // Chunks, such as the sampler chunk, may follow the data chunk. They can only
+                    // be read if the stream is seekable, and the length of the data chunk is known.
+                    if source.is_seekable() && data.len != u32::MAX {
+                        source.seek(SeekFrom::Start(data_end_pos))?;
+
+                        let res = read_trailing_chunks(
+                            &mut source,
+                            &mut riff_chunks,
+                            &mut loops,
+                            &mut metadata,
+                        );
+
+                        if let Err(err) = res {
+                            warn!("failed to read chunks after the data chunk: {}", err);
+                        }
+
+                        source.seek(SeekFrom::Start(data_start_pos))?;
+                    }
+
+                    let mut track = Track::new(0, codec_params);
+                    track.loops = loops;
+
                     // Add a new track using the collected codec parameters.
                     return Ok(WavReader {
                         reader: source,
-                        tracks: vec![Track::new(0, codec_params)],
+                        tracks: vec![track],
                         cues: Vec::new(),
                         metadata,
                         packet_info,
                         data_start_pos,
                         data_end_pos,
+                        unknown_chunks: riff_chunks.take_raw_chunks(),
                     });
                 }
             }
         }
     }
+}
+
+impl FormatReader for WavReader {
+    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        Self::try_new_with_unknown_chunks(source, &Default::default())
+    }
 
     fn next_packet(&mut self) -> Result<Packet> {
         next_packet(
@@ -180,6 +228,10 @@ impl FormatReader for WavReader {
         &self.tracks
     }
 
+    fn unknown_chunks(&self) -> &[RawChunk] {
+        &self.unknown_chunks
+    }
+
     fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
         if self.tracks.is_empty() || self.packet_info.is_empty() {
             return seek_error(SeekErrorKind::Unseekable);
@@ -250,3 +302,240 @@ impl FormatReader for WavReader {
         self.reader
     }
 }
+
+/// Reads the chunks following the data chunk.
+fn read_trailing_chunks(
+    source: &mut MediaSourceStream,
+    riff_chunks: &mut ChunksReader<RiffWaveChunks>,
+    loops: &mut Vec<LoopRegion>,
+    metadata: &mut MetadataLog,
+) -> Result<()> {
+    while let Some(chunk) = riff_chunks.next(source)? {
+        match chunk {
+            RiffWaveChunks::Sampler(smpl) => {
+                let smpl = smpl.parse(source)?;
+
+                loops.extend(sampler_loops(&smpl));
+            }
+            RiffWaveChunks::List(lst) => {
+                let list = lst.parse(source)?;
+
+                match &list.form {
+                    b"INFO" => metadata.push(read_info_chunk(source, list.len)?),
+                    _ => list.skip(source)?,
+                }
+            }
+            // Any other chunks are out-of-place, and ignored.
+            RiffWaveChunks::Format(ChunkParser { len, .. })
+            | RiffWaveChunks::Fact(ChunkParser { len, .. })
+            | RiffWaveChunks::Data(ChunkParser { len, .. }) => {
+                source.ignore_bytes(u64::from(len))?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    use symphonia_core::codecs::{CODEC_TYPE_ATRAC3, CODEC_TYPE_GSM_MS};
+    use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
+    use symphonia_core::formats::{FormatReader, LoopRegion};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::WavReader;
+
+    /// Builds a 16-bit mono PCM WAVE file with a proprietary chunk between the format and data
+    /// chunks.
+    fn make_wave() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"RIFF");
+        buf.extend_from_slice(&52u32.to_le_bytes());
+        buf.extend_from_slice(b"WAVE");
+        buf.extend_from_slice(b"fmt ");
+        buf.extend_from_slice(&16u32.to_le_bytes());
+        buf.extend_from_slice(&[1, 0, 1, 0]);
+        buf.extend_from_slice(&8_000u32.to_le_bytes());
+        buf.extend_from_slice(&16_000u32.to_le_bytes());
+        buf.extend_from_slice(&[2, 0, 16, 0]);
+        buf.extend_from_slice(b"DAW ");
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(b"abc\0");
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&4u32.to_le_bytes());
+        buf.extend_from_slice(&[0, 1, 2, 3]);
+        buf
+    }
+
+    fn open(unknown_chunks: &UnknownChunkOptions) -> WavReader {
+        let source = Box::new(Cursor::new(make_wave()));
+        let mss = MediaSourceStream::new(source, Default::default());
+        WavReader::try_new_with_unknown_chunks(mss, unknown_chunks).unwrap()
+    }
+
+    #[test]
+    fn verify_unknown_chunks() {
+        // Unknown chunks are skipped by default.
+        let reader = open(&Default::default());
+        assert!(reader.unknown_chunks().is_empty());
+
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        let mut options = UnknownChunkOptions::default();
+
+        let sink = handled.clone();
+        options
+            .register(*b"DAW ", move |chunk: &RawChunk| {
+                sink.lock().unwrap().push(chunk.clone());
+                Ok(())
+            })
+            .passthrough(true);
+
+        let mut reader = open(&options);
+
+        let expected = RawChunk { id: *b"DAW ", pos: 36, data: b"abc".to_vec().into_boxed_slice() };
+
+        assert_eq!(reader.unknown_chunks(), &*handled.lock().unwrap());
+        assert_eq!(reader.unknown_chunks(), &[expected]);
+
+        // The padding byte was consumed, and the data chunk is intact.
+        assert_eq!(&*reader.next_packet().unwrap().data, &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn verify_smpl_loops() {
+        let mut buf = make_wave();
+
+        // Append a sampler chunk with a forward loop and a ping-pong loop after the data chunk.
+        buf.extend_from_slice(b"smpl");
+        buf.extend_from_slice(&84u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 12]);
+        buf.extend_from_slice(&60u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 12]);
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+
+        for &(loop_type, play_count) in &[(0u32, 3u32), (1, 0)] {
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&loop_type.to_le_bytes());
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&1u32.to_le_bytes());
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&play_count.to_le_bytes());
+        }
+
+        let riff_len = buf.len() as u32 - 8;
+        buf[4..8].copy_from_slice(&riff_len.to_le_bytes());
+
+        let source = Box::new(Cursor::new(buf));
+        let mut reader = WavReader::try_new(
+            MediaSourceStream::new(source, Default::default()),
+            &Default::default(),
+        )
+        .unwrap();
+
+        // Only the forward loop is supported, and the end of a sample loop is inclusive.
+        assert_eq!(reader.tracks()[0].loops, &[LoopRegion::new(0, 2, 3)]);
+
+        // The reader returned to the start of the data chunk.
+        assert_eq!(&*reader.next_packet().unwrap().data, &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn verify_gsm_fmt() {
+        // A Microsoft GSM 6.10 WAVE file with two 65 byte blocks of two 160 sample frames each.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"RIFF");
+        buf.extend_from_slice(&170u32.to_le_bytes());
+        buf.extend_from_slice(b"WAVE");
+        buf.extend_from_slice(b"fmt ");
+        buf.extend_from_slice(&20u32.to_le_bytes());
+        buf.extend_from_slice(&[0x31, 0, 1, 0]);
+        buf.extend_from_slice(&8_000u32.to_le_bytes());
+        buf.extend_from_slice(&1_625u32.to_le_bytes());
+        buf.extend_from_slice(&[65, 0, 0, 0, 2, 0]);
+        buf.extend_from_slice(&320u16.to_le_bytes());
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&130u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 130]);
+
+        let source = Box::new(Cursor::new(buf));
+        let mut reader = WavReader::try_new(
+            MediaSourceStream::new(source, Default::default()),
+            &Default::default(),
+        )
+        .unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_GSM_MS);
+        assert_eq!(params.frames_per_block, Some(320));
+        assert_eq!(params.n_frames, Some(640));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.data.len(), 130);
+        assert_eq!(packet.dur, 640);
+    }
+
+    #[test]
+    fn verify_atrac3_fmt() {
+        // A joint stereo ATRAC3 WAVE file with two 384 byte frames.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"RIFF");
+        buf.extend_from_slice(&820u32.to_le_bytes());
+        buf.extend_from_slice(b"WAVE");
+        buf.extend_from_slice(b"fmt ");
+        buf.extend_from_slice(&32u32.to_le_bytes());
+        buf.extend_from_slice(&[0x70, 0x02, 2, 0]);
+        buf.extend_from_slice(&44_100u32.to_le_bytes());
+        buf.extend_from_slice(&16_537u32.to_le_bytes());
+        buf.extend_from_slice(&[0x80, 0x01, 0, 0, 14, 0]);
+        buf.extend_from_slice(&[1, 0, 0, 0x10, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0]);
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&768u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 768]);
+
+        let source = Box::new(Cursor::new(buf));
+        let mut reader = WavReader::try_new(
+            MediaSourceStream::new(source, Default::default()),
+            &Default::default(),
+        )
+        .unwrap();
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_ATRAC3);
+        assert_eq!(params.n_frames, Some(2048));
+        assert_eq!(params.extra_data.as_deref().map(|e| e[6]), Some(1));
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.data.len(), 384);
+        assert_eq!(packet.dur, 1024);
+    }
+
+    #[test]
+    fn verify_truncated_data() {
+        let mut buf = make_wave();
+
+        // Claim 4 frames of audio data, but end the stream in the middle of the second.
+        let len = buf.len();
+        buf[len - 8..len - 4].copy_from_slice(&8u32.to_le_bytes());
+        buf.truncate(len - 1);
+
+        let source = Box::new(Cursor::new(buf));
+        let mut reader = WavReader::try_new(
+            MediaSourceStream::new(source, Default::default()),
+            &Default::default(),
+        )
+        .unwrap();
+
+        // Only the complete frame is returned.
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(&*packet.data, &[0, 1]);
+        assert_eq!(packet.dur, 1);
+
+        assert!(reader.next_packet().is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
+use symphonia_core::io::ReadBytes;
+
+use log::{debug, info};
+
+use crate::common::ChunkParser;
+use crate::wave::chunks::{FactChunk, ListChunk, WaveFormatChunk};
+
+/// The length of a Wave64 chunk header. The header consists of a 16 byte GUID followed by the
+/// 64-bit length of the chunk, including the header.
+pub const CHUNK_HEADER_LEN: u64 = 24;
+
+// These GUIDs identify the Wave64 chunks. Each GUID is stored in its little-endian byte form. The
+// GUIDs of chunks inherited from WAVE begin with the FourCC of the equivalent WAVE chunk.
+#[rustfmt::skip]
+pub const W64_RIFF_GUID: [u8; 16] = [
+    b'r', b'i', b'f', b'f', 0x2e, 0x91, 0xcf, 0x11,
+    0xa5, 0xd6, 0x28, 0xdb, 0x04, 0xc1, 0x00, 0x00,
+];
+#[rustfmt::skip]
+pub const W64_LIST_GUID: [u8; 16] = [
+    b'l', b'i', b's', b't', 0x2f, 0x91, 0xcf, 0x11,
+    0xa5, 0xd6, 0x28, 0xdb, 0x04, 0xc1, 0x00, 0x00,
+];
+#[rustfmt::skip]
+pub const W64_WAVE_GUID: [u8; 16] = [
+    b'w', b'a', b'v', b'e', 0xf3, 0xac, 0xd3, 0x11,
+    0x8c, 0xd1, 0x00, 0xc0, 0x4f, 0x8e, 0xdb, 0x8a,
+];
+#[rustfmt::skip]
+pub const W64_FMT_GUID: [u8; 16] = [
+    b'f', b'm', b't', b' ', 0xf3, 0xac, 0xd3, 0x11,
+    0x8c, 0xd1, 0x00, 0xc0, 0x4f, 0x8e, 0xdb, 0x8a,
+];
+#[rustfmt::skip]
+pub const W64_FACT_GUID: [u8; 16] = [
+    b'f', b'a', b'c', b't', 0xf3, 0xac, 0xd3, 0x11,
+    0x8c, 0xd1, 0x00, 0xc0, 0x4f, 0x8e, 0xdb, 0x8a,
+];
+#[rustfmt::skip]
+pub const W64_DATA_GUID: [u8; 16] = [
+    b'd', b'a', b't', b'a', 0xf3, 0xac, 0xd3, 0x11,
+    0x8c, 0xd1, 0x00, 0xc0, 0x4f, 0x8e, 0xdb, 0x8a,
+];
+
+pub struct Wave64DataChunk {
+    pub len: u64,
+}
+
+/// The Wave64 chunks. Except for the data chunk, the contents of each chunk is identical to the
+/// equivalent WAVE chunk, and is therefore parsed by the same parser.
+pub enum Wave64Chunks {
+    Format(ChunkParser<WaveFormatChunk>),
+    List(ChunkParser<ListChunk>),
+    Fact(ChunkParser<FactChunk>),
+    Data(Wave64DataChunk),
+}
+
+/// `Wave64ChunksReader` reads the chunks of a Wave64 stream. Unlike RIFF chunks, Wave64 chunks are
+/// identified by a GUID, have 64-bit lengths that include the chunk header, and are aligned to an
+/// 8-byte boundary.
+pub struct Wave64ChunksReader {
+    len: u64,
+    consumed: u64,
+    unknown: Option<UnknownChunkOptions>,
+    raw_chunks: Vec<RawChunk>,
+}
+
+impl Wave64ChunksReader {
+    pub fn new(len: u64) -> Self {
+        Wave64ChunksReader { len, consumed: 0, unknown: None, raw_chunks: Vec::new() }
+    }
+
+    /// Treat unrecognized chunks as per `options` instead of skipping them. Since `RawChunk` IDs
+    /// are 4 bytes, the ID of an unrecognized chunk is the first 4 bytes of its GUID.
+    pub fn with_unknown_chunks(&mut self, options: &UnknownChunkOptions) -> &mut Self {
+        self.unknown = Some(options.clone());
+        self
+    }
+
+    /// Takes the unrecognized chunks that were retained because passthrough was enabled.
+    pub fn take_raw_chunks(&mut self) -> Vec<RawChunk> {
+        std::mem::take(&mut self.raw_chunks)
+    }
+
+    pub fn next<B: ReadBytes>(&mut self, reader: &mut B) -> Result<Option<Wave64Chunks>> {
+        // Loop until a chunk is recognized and returned, or the end of stream is reached.
+        loop {
+            // Align to the next 8-byte boundary if not currently aligned.
+            let padding = self.consumed.wrapping_neg() & 0x7;
+
+            // Check if there are enough bytes for another chunk, if not, there are no more chunks.
+            if self.len - self.consumed < padding + CHUNK_HEADER_LEN {
+                return Ok(None);
+            }
+
+            reader.ignore_bytes(padding)?;
+            self.consumed += padding;
+
+            // Read the GUID and length, the chunk header.
+            let pos = reader.pos();
+
+            let mut guid = [0; 16];
+            reader.read_buf_exact(&mut guid)?;
+
+            let chunk_len = reader.read_u64()?;
+
+            self.consumed += CHUNK_HEADER_LEN;
+
+            // The chunk length includes the chunk header.
+            if chunk_len < CHUNK_HEADER_LEN {
+                return decode_error("w64: chunk length is less than the chunk header length");
+            }
+
+            let len = chunk_len - CHUNK_HEADER_LEN;
+
+            // Check if the reader has enough unread bytes to fully read the chunk.
+            //
+            // Warning: the formulation of this conditional is critical because len is untrusted
+            // input, it may overflow when if added to anything.
+            if self.len - self.consumed < len {
+                debug!("chunk length of {:x?} exceeds parent chunk length", &guid);
+                return decode_error("w64: chunk length exceeds parent chunk length");
+            }
+
+            // The length of the chunk has been validated, so "consume" the chunk.
+            self.consumed += len;
+
+            let tag = [guid[0], guid[1], guid[2], guid[3]];
+
+            // Except for data, the chunks are parsed by WAVE chunk parsers which only support
+            // 32-bit lengths. Such chunks never approach 4 GiB.
+            let short_len = if len <= u64::from(u32::MAX) { Some(len as u32) } else { None };
+
+            match (guid, short_len) {
+                (W64_DATA_GUID, _) => {
+                    return Ok(Some(Wave64Chunks::Data(Wave64DataChunk { len })));
+                }
+                (W64_FMT_GUID, Some(len)) => {
+                    return Ok(Some(Wave64Chunks::Format(ChunkParser::new(tag, len))));
+                }
+                (W64_LIST_GUID, Some(len)) => {
+                    return Ok(Some(Wave64Chunks::List(ChunkParser::new(tag, len))));
+                }
+                (W64_FACT_GUID, Some(len)) => {
+                    return Ok(Some(Wave64Chunks::Fact(ChunkParser::new(tag, len))));
+                }
+                (W64_FMT_GUID, None) | (W64_LIST_GUID, None) | (W64_FACT_GUID, None) => {
+                    return decode_error("w64: chunk is too long");
+                }
+                _ => match (&self.unknown, short_len) {
+                    (Some(unknown), Some(len)) if unknown.wants(tag, len) => {
+                        let data = reader.read_boxed_slice_exact(len as usize)?;
+                        let chunk = RawChunk { id: tag, pos, data };
+
+                        unknown.handle(&chunk)?;
+
+                        if unknown.is_passthrough() {
+                            self.raw_chunks.push(chunk);
+                        }
+                    }
+                    _ => {
+                        // Unknown chunks are to be ignored.
+                        info!("ignoring unknown chunk: guid={:x?}, len={}.", &guid, len);
+
+                        reader.ignore_bytes(len)?
+                    }
+                },
+            }
+        }
+    }
+}
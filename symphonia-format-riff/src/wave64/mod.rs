@@ -0,0 +1,360 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::codecs::CodecParameters;
+use symphonia_core::errors::{decode_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::chunks::{RawChunk, UnknownChunkOptions};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+use symphonia_core::support_format;
+
+use log::debug;
+
+use crate::common::{append_data_params, append_format_params, next_packet, PacketInfo};
+use crate::wave::chunks::{append_fact_params, read_info_chunk};
+mod chunks;
+use chunks::*;
+
+/// Sony Wave64 (W64) format reader.
+///
+/// `Wave64Reader` implements a demuxer for the Wave64 container format. Wave64 is a variant of
+/// WAVE that uses GUIDs to identify chunks and 64-bit chunk lengths, allowing for streams larger
+/// than 4 GiB.
+pub struct Wave64Reader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    packet_info: PacketInfo,
+    data_start_pos: u64,
+    data_end_pos: u64,
+    unknown_chunks: Vec<RawChunk>,
+}
+
+impl QueryDescriptor for Wave64Reader {
+    fn query() -> &'static [Descriptor] {
+        &[
+            // Wave64 RIFF GUID
+            support_format!("w64", "Sony Wave64", &["w64"], &["audio/x-w64"], &[&W64_RIFF_GUID]),
+        ]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl Wave64Reader {
+    /// Instantiates a `Wave64Reader` that treats chunks it does not recognize as selected by
+    /// `unknown_chunks`. The `FormatReader::try_new` constructor skips all unrecognized chunks.
+    ///
+    /// A `Probe` always uses `FormatReader::try_new`. Therefore, to handle unrecognized chunks, the
+    /// reader must be instantiated directly with this constructor.
+    pub fn try_new_with_unknown_chunks(
+        mut source: MediaSourceStream,
+        unknown_chunks: &UnknownChunkOptions,
+    ) -> Result<Self> {
+        // The RIFF GUID should be present.
+        let mut guid = [0; 16];
+        source.read_buf_exact(&mut guid)?;
+
+        if guid != W64_RIFF_GUID {
+            return unsupported_error("w64: missing riff guid");
+        }
+
+        // Like WAVE, a Wave64 file is one large RIFF chunk, with the actual meta and audio data as
+        // sub-chunks. However, the length of the RIFF chunk includes the chunk header.
+        let riff_len = source.read_u64()?;
+
+        source.read_buf_exact(&mut guid)?;
+
+        // The RIFF chunk contains WAVE data.
+        if guid != W64_WAVE_GUID {
+            return unsupported_error("w64: riff form is not wave");
+        }
+
+        // Exclude the chunk header, and the WAVE GUID, from the length of the RIFF chunk.
+        let riff_len = match riff_len.checked_sub(CHUNK_HEADER_LEN + 16) {
+            Some(len) => len,
+            None => return decode_error("w64: invalid riff chunk length"),
+        };
+
+        let mut riff_chunks = Wave64ChunksReader::new(riff_len);
+
+        riff_chunks.with_unknown_chunks(unknown_chunks);
+
+        let mut codec_params = CodecParameters::new();
+        let mut metadata: MetadataLog = Default::default();
+        let mut packet_info = PacketInfo::without_blocks(0);
+
+        loop {
+            let chunk = riff_chunks.next(&mut source)?;
+
+            // The last chunk should always be a data chunk, if it is not, then the stream is
+            // unsupported.
+            if chunk.is_none() {
+                return unsupported_error("w64: missing data chunk");
+            }
+
+            match chunk.unwrap() {
+                Wave64Chunks::Format(fmt) => {
+                    let format = fmt.parse(&mut source)?;
+
+                    // The Format chunk contains the block_align field and possible additional
+                    // information to handle packetization and seeking.
+                    packet_info = format.packet_info()?;
+                    codec_params
+                        .with_max_frames_per_packet(packet_info.get_max_frames_per_packet())
+                        .with_frames_per_block(packet_info.frames_per_block);
+
+                    // Append Format chunk fields to codec parameters.
+                    append_format_params(
+                        &mut codec_params,
+                        &format.format_data,
+                        format.sample_rate,
+                    );
+                }
+                Wave64Chunks::Fact(fct) => {
+                    let fact = fct.parse(&mut source)?;
+
+                    // Append Fact chunk fields to codec parameters.
+                    append_fact_params(&mut codec_params, &fact);
+                }
+                Wave64Chunks::List(lst) => {
+                    let list = lst.parse(&mut source)?;
+
+                    // Only Info lists are supported.
+                    match &list.form {
+                        b"INFO" => metadata.push(read_info_chunk(&mut source, list.len)?),
+                        _ => list.skip(&mut source)?,
+                    }
+                }
+                Wave64Chunks::Data(data) => {
+                    // Record the bounds of the data chunk.
+                    let data_start_pos = source.pos();
+                    let data_end_pos = data_start_pos + data.len;
+
+                    // Append Data chunk fields to codec parameters.
+                    append_data_params(&mut codec_params, data.len, &packet_info);
+
+                    // Add a new track using the collected codec parameters.
+                    return Ok(Wave64Reader {
+                        reader: source,
+                        tracks: vec![Track::new(0, codec_params)],
+                        cues: Vec::new(),
+                        metadata,
+                        packet_info,
+                        data_start_pos,
+                        data_end_pos,
+                        unknown_chunks: riff_chunks.take_raw_chunks(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl FormatReader for Wave64Reader {
+    fn try_new(source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        Self::try_new_with_unknown_chunks(source, &Default::default())
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        next_packet(
+            &mut self.reader,
+            &self.packet_info,
+            &self.tracks,
+            self.data_start_pos,
+            self.data_end_pos,
+        )
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn unknown_chunks(&self) -> &[RawChunk] {
+        &self.unknown_chunks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        if self.tracks.is_empty() || self.packet_info.is_empty() {
+            return seek_error(SeekErrorKind::Unseekable);
+        }
+
+        let params = &self.tracks[0].codec_params;
+
+        let ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        // If the total number of frames in the track is known, verify the desired frame timestamp
+        // does not exceed it.
+        if let Some(n_frames) = params.n_frames {
+            if ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        debug!("seeking to frame_ts={}", ts);
+
+        // Like WAVE, packetization is simulated. Seek to a packet boundary so that packets have the
+        // same timestamps regardless if the stream was seeked or not.
+        let actual_ts = self.packet_info.get_actual_ts(ts);
+
+        // Calculate the absolute byte offset of the desired audio frame.
+        let seek_pos = self.data_start_pos + (actual_ts * self.packet_info.block_size);
+
+        // If the reader supports seeking we can seek directly to the frame's offset wherever it may
+        // be.
+        if self.reader.is_seekable() {
+            self.reader.seek(SeekFrom::Start(seek_pos))?;
+        }
+        // If the reader does not support seeking, we can only emulate forward seeks by consuming
+        // bytes. If the reader has to seek backwards, return an error.
+        else {
+            let current_pos = self.reader.pos();
+            if seek_pos >= current_pos {
+                self.reader.ignore_bytes(seek_pos - current_pos)?;
+            }
+            else {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        debug!("seeked to packet_ts={} (delta={})", actual_ts, actual_ts as i64 - ts as i64);
+
+        Ok(SeekedTo { track_id: 0, actual_ts, required_ts: ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::codecs::CODEC_TYPE_PCM_S16LE;
+    use symphonia_core::formats::chunks::UnknownChunkOptions;
+    use symphonia_core::formats::{FormatReader, SeekMode, SeekTo};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::chunks::*;
+    use super::Wave64Reader;
+
+    fn push_chunk(buf: &mut Vec<u8>, guid: &[u8; 16], data: &[u8]) {
+        buf.extend_from_slice(guid);
+        buf.extend_from_slice(&(CHUNK_HEADER_LEN + data.len() as u64).to_le_bytes());
+        buf.extend_from_slice(data);
+
+        // Pad to the next 8-byte boundary.
+        while buf.len() % 8 != 0 {
+            buf.push(0);
+        }
+    }
+
+    /// Builds a 16-bit stereo PCM Wave64 file with an unknown chunk between the format and data
+    /// chunks.
+    fn make_wave64() -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&[1, 0, 2, 0]);
+        fmt.extend_from_slice(&44_100u32.to_le_bytes());
+        fmt.extend_from_slice(&176_400u32.to_le_bytes());
+        fmt.extend_from_slice(&[4, 0, 16, 0]);
+
+        let mut unknown = W64_DATA_GUID;
+        unknown[0..4].copy_from_slice(b"junk");
+
+        let samples: Vec<u8> = (0..40).collect();
+
+        let mut chunks = Vec::new();
+        push_chunk(&mut chunks, &W64_FMT_GUID, &fmt);
+        push_chunk(&mut chunks, &unknown, b"abc");
+        push_chunk(&mut chunks, &W64_DATA_GUID, &samples);
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&W64_RIFF_GUID);
+        buf.extend_from_slice(&(40 + chunks.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&W64_WAVE_GUID);
+        buf.extend_from_slice(&chunks);
+        buf
+    }
+
+    fn open(buf: Vec<u8>, unknown_chunks: &UnknownChunkOptions) -> Wave64Reader {
+        let source = Box::new(Cursor::new(buf));
+        let mss = MediaSourceStream::new(source, Default::default());
+        Wave64Reader::try_new_with_unknown_chunks(mss, unknown_chunks).unwrap()
+    }
+
+    #[test]
+    fn verify_wave64_reader() {
+        let mut options = UnknownChunkOptions::default();
+        options.passthrough(true);
+
+        let mut reader = open(make_wave64(), &options);
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_PCM_S16LE);
+        assert_eq!(params.sample_rate, Some(44_100));
+        assert_eq!(params.n_frames, Some(10));
+
+        // The unknown chunk is identified by the first 4 bytes of its GUID.
+        assert_eq!(reader.unknown_chunks().len(), 1);
+        assert_eq!(reader.unknown_chunks()[0].id, *b"junk");
+        assert_eq!(&*reader.unknown_chunks()[0].data, b"abc");
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.ts, 0);
+        assert_eq!(packet.dur, 10);
+        assert_eq!(&*packet.data, &(0..40).collect::<Vec<u8>>()[..]);
+        assert!(reader.next_packet().is_err());
+
+        let seeked = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 0, track_id: 0 });
+        assert_eq!(seeked.unwrap().actual_ts, 0);
+        assert_eq!(reader.next_packet().unwrap().dur, 10);
+    }
+
+    #[test]
+    fn verify_wave64_reader_rejects_invalid_lengths() {
+        let mut buf = make_wave64();
+
+        // The data chunk claims to be longer than the RIFF chunk.
+        let data_len_pos = buf.len() - 40 - 8;
+        buf[data_len_pos..data_len_pos + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        let source = Box::new(Cursor::new(buf));
+        let mss = MediaSourceStream::new(source, Default::default());
+        assert!(Wave64Reader::try_new(mss, &Default::default()).is_err());
+    }
+}
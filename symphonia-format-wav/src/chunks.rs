@@ -9,7 +9,7 @@ use std::fmt;
 use std::marker::PhantomData;
 
 use symphonia_core::audio::Channels;
-use symphonia_core::codecs::CodecType;
+use symphonia_core::codecs::{ids, CodecType};
 use symphonia_core::codecs::{
     CODEC_TYPE_ADPCM_IMA_WAV, CODEC_TYPE_ADPCM_MS, CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_F32LE,
     CODEC_TYPE_PCM_F64LE, CODEC_TYPE_PCM_MULAW, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24LE,
@@ -650,7 +650,15 @@ impl ParseChunk for WaveFormatChunk {
                 CODEC_TYPE_ADPCM_IMA_WAV,
             ),
             // Unsupported format.
-            _ => return unsupported_error("wav: unsupported wave format"),
+            _ => {
+                match ids::from_wave_format_tag(format) {
+                    Some(codec) => {
+                        info!("wav: unsupported wave format {:#06x} ({})", format, codec)
+                    }
+                    None => info!("wav: unsupported wave format {:#06x}", format),
+                }
+                return unsupported_error("wav: unsupported wave format");
+            }
         }?;
 
         Ok(WaveFormatChunk { n_channels, sample_rate, avg_bytes_per_sec, block_align, format_data })
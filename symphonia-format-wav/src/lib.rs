@@ -237,17 +237,27 @@ impl FormatReader for WavReader {
 
         let blocks_per_packet = num_blocks_left.min(self.packet_info.max_blocks_per_packet);
 
-        let dur = blocks_per_packet * self.packet_info.frames_per_block;
         let packet_len = blocks_per_packet * self.packet_info.block_size;
 
-        // Copy the frames.
-        let packet_buf = self.reader.read_boxed_slice(packet_len as usize)?;
+        // Copy the frames. Less data than requested is only read if the stream ended, in which
+        // case the data chunk was truncated and any partial block at the end is dropped.
+        let mut packet_buf = self.reader.read_boxed_slice(packet_len as usize)?.into_vec();
+
+        let blocks_read = packet_buf.len() as u64 / self.packet_info.block_size;
+
+        if blocks_read == 0 {
+            return end_of_stream_error();
+        }
+
+        packet_buf.truncate((blocks_read * self.packet_info.block_size) as usize);
+
+        let dur = blocks_read * self.packet_info.frames_per_block;
 
         // The packet timestamp is the position of the first byte of the first frame in the
         // packet relative to the start of the data chunk divided by the length per frame.
         let pts = self.packet_info.get_frames(pos - self.data_start_pos);
 
-        Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf))
+        Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf.into_boxed_slice()))
     }
 
     fn metadata(&mut self) -> Metadata<'_> {
@@ -0,0 +1,249 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! An APEv1 and APEv2 metadata reader.
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use log::warn;
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::ReadBytes;
+use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
+
+/// The length of an APE tag header, or footer.
+pub const APE_FOOTER_LEN: u64 = 32;
+
+/// The maximum length of an APE item key.
+const APE_MAX_KEY_LEN: usize = 255;
+
+lazy_static! {
+    static ref APE_ITEM_MAP: HashMap<&'static str, StandardTagKey> = {
+        let mut m = HashMap::new();
+        m.insert("album artist", StandardTagKey::AlbumArtist);
+        m.insert("album", StandardTagKey::Album);
+        m.insert("albumartist", StandardTagKey::AlbumArtist);
+        m.insert("arranger", StandardTagKey::Arranger);
+        m.insert("artist", StandardTagKey::Artist);
+        m.insert("bpm", StandardTagKey::Bpm);
+        m.insert("catalog", StandardTagKey::IdentCatalogNumber);
+        m.insert("comment", StandardTagKey::Comment);
+        m.insert("composer", StandardTagKey::Composer);
+        m.insert("conductor", StandardTagKey::Conductor);
+        m.insert("copyright", StandardTagKey::Copyright);
+        m.insert("disc", StandardTagKey::DiscNumber);
+        m.insert("encodedby", StandardTagKey::EncodedBy);
+        m.insert("genre", StandardTagKey::Genre);
+        m.insert("isrc", StandardTagKey::IdentIsrc);
+        m.insert("label", StandardTagKey::Label);
+        m.insert("language", StandardTagKey::Language);
+        m.insert("lyricist", StandardTagKey::Lyricist);
+        m.insert("lyrics", StandardTagKey::Lyrics);
+        m.insert("mixartist", StandardTagKey::Remixer);
+        m.insert("musicbrainz_albumartistid", StandardTagKey::MusicBrainzAlbumArtistId);
+        m.insert("musicbrainz_albumid", StandardTagKey::MusicBrainzAlbumId);
+        m.insert("musicbrainz_artistid", StandardTagKey::MusicBrainzArtistId);
+        m.insert("musicbrainz_trackid", StandardTagKey::MusicBrainzTrackId);
+        m.insert("publisher", StandardTagKey::Label);
+        m.insert("replaygain_album_gain", StandardTagKey::ReplayGainAlbumGain);
+        m.insert("replaygain_album_peak", StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("replaygain_track_gain", StandardTagKey::ReplayGainTrackGain);
+        m.insert("replaygain_track_peak", StandardTagKey::ReplayGainTrackPeak);
+        m.insert("subtitle", StandardTagKey::TrackSubtitle);
+        m.insert("title", StandardTagKey::TrackTitle);
+        m.insert("track", StandardTagKey::TrackNumber);
+        m.insert("year", StandardTagKey::Date);
+        m
+    };
+}
+
+/// An APE tag footer, or header.
+#[derive(Copy, Clone, Debug)]
+pub struct ApeFooter {
+    /// The version of the tag. 1000 for APEv1, and 2000 for APEv2.
+    pub version: u32,
+    /// The length of the tag items and the footer, in bytes. The header is not included.
+    pub size: u32,
+    /// The number of items in the tag.
+    pub num_items: u32,
+    /// The global flags of the tag.
+    pub flags: u32,
+}
+
+impl ApeFooter {
+    /// Reads an APE tag footer, or header.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ApeFooter> {
+        let mut preamble = [0; 8];
+        reader.read_buf_exact(&mut preamble)?;
+
+        if preamble != *b"APETAGEX" {
+            return unsupported_error("ape: not an ape tag");
+        }
+
+        let version = reader.read_u32()?;
+        let size = reader.read_u32()?;
+        let num_items = reader.read_u32()?;
+        let flags = reader.read_u32()?;
+
+        // Reserved.
+        reader.ignore_bytes(8)?;
+
+        if u64::from(size) < APE_FOOTER_LEN {
+            return decode_error("ape: invalid tag size");
+        }
+
+        Ok(ApeFooter { version, size, num_items, flags })
+    }
+
+    /// Returns true if the tag has a header.
+    pub fn has_header(&self) -> bool {
+        self.flags & 0x8000_0000 != 0
+    }
+
+    /// Gets the total length of the tag in bytes, including the header, if present.
+    pub fn tag_len(&self) -> u64 {
+        let header_len = if self.has_header() { APE_FOOTER_LEN } else { 0 };
+        u64::from(self.size) + header_len
+    }
+
+    /// Gets the length of the tag items in bytes.
+    pub fn items_len(&self) -> u64 {
+        u64::from(self.size) - APE_FOOTER_LEN
+    }
+}
+
+/// Reads the items of an APE tag described by `footer`. The reader must be positioned at the first
+/// item, and will consume exactly `footer.items_len()` bytes if successful.
+pub fn read_ape_items<B: ReadBytes>(
+    reader: &mut B,
+    footer: &ApeFooter,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    let items_len = footer.items_len();
+
+    let mut pos = 0;
+
+    for _ in 0..footer.num_items {
+        // The value length, and item flags.
+        let value_len = u64::from(reader.read_u32()?);
+        let flags = reader.read_u32()?;
+
+        // The key is a null-terminated ASCII string.
+        let mut key = Vec::new();
+
+        loop {
+            match reader.read_byte()? {
+                0 => break,
+                byte if byte >= 0x20 && byte <= 0x7e && key.len() < APE_MAX_KEY_LEN => {
+                    key.push(byte)
+                }
+                _ => return decode_error("ape: invalid item key"),
+            }
+        }
+
+        pos += 8 + key.len() as u64 + 1 + value_len;
+
+        if pos > items_len {
+            return decode_error("ape: item exceeds tag size");
+        }
+
+        let value = reader.read_boxed_slice_exact(value_len as usize)?;
+
+        // Keys are ASCII, so this cannot fail.
+        let key = String::from_utf8_lossy(&key);
+
+        let std_key = APE_ITEM_MAP.get(key.to_lowercase().as_str()).copied();
+
+        // The item type is stored in bits 1 and 2 of the flags.
+        match (flags >> 1) & 0x3 {
+            // UTF-8 text, or a locator to external information. A text item may contain a list of
+            // values separated by null characters. APEv1 items are always text.
+            0 | 2 => {
+                for value in String::from_utf8_lossy(&value).split('\0') {
+                    metadata.add_tag(Tag::new(std_key, &key, Value::from(value)));
+                }
+            }
+            1 => {
+                metadata.add_tag(Tag::new(std_key, &key, Value::Binary(value)));
+            }
+            _ => warn!("ape: skipping item '{}' with reserved type", key),
+        }
+    }
+
+    // Skip any remaining bytes before the footer.
+    reader.ignore_bytes(items_len - pos)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    fn push_item(buf: &mut Vec<u8>, flags: u32, key: &str, value: &[u8]) {
+        buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(key.as_bytes());
+        buf.push(0);
+        buf.extend_from_slice(value);
+    }
+
+    #[test]
+    fn verify_read_ape_tag() {
+        let mut items = Vec::new();
+        push_item(&mut items, 0, "Title", b"Song");
+        push_item(&mut items, 0, "Artist", b"One\0Two");
+        push_item(&mut items, 2, "Cover Art (Front)", &[1, 2, 3]);
+
+        let mut buf = items.clone();
+        buf.extend_from_slice(b"APETAGEX");
+        buf.extend_from_slice(&2000u32.to_le_bytes());
+        buf.extend_from_slice(&(items.len() as u32 + 32).to_le_bytes());
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 8]);
+
+        let footer = ApeFooter::read(&mut BufReader::new(&buf[items.len()..])).unwrap();
+
+        assert_eq!(footer.version, 2000);
+        assert_eq!(footer.num_items, 3);
+        assert!(!footer.has_header());
+        assert_eq!(footer.tag_len(), buf.len() as u64);
+
+        let mut reader = BufReader::new(&buf);
+        let mut builder = MetadataBuilder::new();
+
+        read_ape_items(&mut reader, &footer, &mut builder).unwrap();
+
+        assert_eq!(reader.pos(), items.len() as u64);
+
+        let rev = builder.metadata();
+        let tags = rev.tags();
+
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[0].value.to_string(), "Song");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Artist));
+        assert_eq!(tags[2].value.to_string(), "Two");
+        assert!(matches!(tags[3].value, Value::Binary(ref data) if data[..] == [1, 2, 3]));
+    }
+
+    #[test]
+    fn verify_read_ape_tag_overflow() {
+        let mut items = Vec::new();
+        push_item(&mut items, 0, "Title", b"Song");
+
+        // The tag size is too small to contain the item.
+        let footer = ApeFooter { version: 2000, size: 32 + 4, num_items: 1, flags: 0 };
+
+        let mut builder = MetadataBuilder::new();
+
+        assert!(read_ape_items(&mut BufReader::new(&items), &footer, &mut builder).is_err());
+    }
+}
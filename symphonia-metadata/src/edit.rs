@@ -0,0 +1,26 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Common support for editing and writing tags.
+
+use symphonia_core::errors::Result;
+
+/// A `TagEditor` is an in-memory copy of a tag that may be modified and then written back.
+///
+/// Keys are always the native keys of the tagging format. For example, an ID3v2 frame ID, or a
+/// Vorbis comment field name.
+pub trait TagEditor {
+    /// Replace all existing values of the tag with key `key` with `values`. Returns an error if
+    /// the key is invalid, or the tag cannot be set.
+    fn set(&mut self, key: &str, values: &[&str]) -> Result<()>;
+
+    /// Remove all values of the tag with key `key`. Returns true if any values were removed.
+    fn remove(&mut self, key: &str) -> bool;
+
+    /// Remove all tags.
+    fn clear(&mut self);
+}
@@ -0,0 +1,137 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! An ICY (SHOUTcast/Icecast) metadata block reader.
+//!
+//! An internet radio stream may interleave metadata blocks with its audio. The text of a block is
+//! a list of `Key='Value';` pairs (e.g., `StreamTitle='Artist - Title';`), padded with null bytes.
+
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+/// Returns true if `text` is the start of the next `Key='Value'` pair, or the end of the block.
+fn is_next_pair(text: &str) -> bool {
+    let text = text.trim_start();
+
+    if text.is_empty() {
+        return true;
+    }
+
+    match text.find("='") {
+        Some(key_end) => {
+            let key = &text[..key_end];
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Gets the length of the value at the start of `text`, excluding the closing quote.
+fn value_len(text: &str) -> usize {
+    // A value may contain quotes and semicolons, even together. Therefore, a value ends at the
+    // first quote and semicolon that is followed by the next pair, or the end of the block.
+    let mut from = 0;
+
+    while let Some(pos) = text[from..].find("';") {
+        let end = from + pos;
+
+        if is_next_pair(&text[end + 2..]) {
+            return end;
+        }
+
+        from = end + 2;
+    }
+
+    // Some servers omit the final semicolon, or even the closing quote.
+    let text = text.trim_end();
+    let text = text.strip_suffix(';').unwrap_or(text);
+    let text = text.strip_suffix('\'').unwrap_or(text);
+
+    text.len()
+}
+
+/// Parses the `Key='Value';` pairs of an ICY metadata block into tags.
+pub fn parse_icy_metadata(block: &[u8]) -> Vec<Tag> {
+    // Strip the null padding.
+    let end = block.iter().position(|&b| b == 0).unwrap_or(block.len());
+
+    // The text encoding is not specified. Most servers send UTF-8, but some send ISO-8859-1.
+    let text = match std::str::from_utf8(&block[..end]) {
+        Ok(text) => text.to_string(),
+        Err(_) => block[..end].iter().map(|&b| b as char).collect(),
+    };
+
+    let mut tags = Vec::new();
+    let mut rest = text.as_str();
+
+    while let Some(key_end) = rest.find("='") {
+        let key = rest[..key_end].trim();
+        rest = &rest[key_end + 2..];
+
+        let len = value_len(rest);
+        let value = &rest[..len];
+
+        rest = rest.get(len + 2..).unwrap_or("");
+
+        let std_key = match key {
+            "StreamTitle" => Some(StandardTagKey::TrackTitle),
+            "StreamUrl" => Some(StandardTagKey::Url),
+            _ => None,
+        };
+
+        tags.push(Tag::new(std_key, key, Value::from(value)));
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_icy_metadata;
+    use symphonia_core::meta::StandardTagKey;
+
+    fn values(block: &[u8]) -> Vec<(String, String)> {
+        parse_icy_metadata(block).iter().map(|t| (t.key.clone(), t.value.to_string())).collect()
+    }
+
+    #[test]
+    fn verify_parse_icy_metadata() {
+        let tags = parse_icy_metadata(b"StreamTitle='Art; Ist - It's';StreamUrl='';\0\0\0");
+
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[0].key, "StreamTitle");
+        assert_eq!(tags[0].value.to_string(), "Art; Ist - It's");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Url));
+        assert_eq!(tags[1].value.to_string(), "");
+
+        // An unterminated value, and ISO-8859-1 text.
+        let tags = parse_icy_metadata(b"StreamTitle='Caf\xe9'");
+
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].value.to_string(), "Café");
+    }
+
+    #[test]
+    fn verify_parse_icy_metadata_quotes() {
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+        // A value containing a quote followed by a semicolon.
+        assert_eq!(
+            values(b"StreamTitle='Rock 'n' Roll';'; Part 2';StreamUrl='x';"),
+            [pair("StreamTitle", "Rock 'n' Roll';'; Part 2"), pair("StreamUrl", "x")]
+        );
+
+        assert_eq!(values(b"StreamTitle='It';s';"), [pair("StreamTitle", "It';s")]);
+
+        // A value without a semicolon or closing quote.
+        assert_eq!(values(b"StreamTitle='A - B"), [pair("StreamTitle", "A - B")]);
+        assert_eq!(values(b"StreamTitle='A - B' \0\0"), [pair("StreamTitle", "A - B")]);
+
+        assert!(values(b"").is_empty());
+        assert!(values(b"\0\0\0\0").is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::Write;
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, FiniteStream, ReadBytes};
+
+use log::info;
+
+use super::frames::{scan_text, validate_frame_id, Encoding};
+use super::read_id3v2_header;
+use super::unsync::{decode_unsynchronisation, read_syncsafe_leq32};
+use crate::edit::TagEditor;
+
+/// The length of the tag header, frame header, and tag footer.
+const HEADER_LEN: usize = 10;
+
+/// The maximum value of a 28-bit syncsafe integer.
+const MAX_SYNCSAFE: usize = (1 << 28) - 1;
+
+/// A frame stored exactly as it was read.
+struct RawFrame {
+    id: [u8; 4],
+    flags: u16,
+    data: Box<[u8]>,
+}
+
+/// An editable ID3v2.3 or ID3v2.4 tag.
+///
+/// Frames that are not modified are preserved exactly, and the tag is written using the same major
+/// version it was read with. Only text frames may be set.
+pub struct Id3v2Editor {
+    major_version: u8,
+    frames: Vec<RawFrame>,
+}
+
+impl Id3v2Editor {
+    /// Instantiate an empty ID3v2.4 tag.
+    pub fn new() -> Self {
+        Id3v2Editor { major_version: 4, frames: Vec::new() }
+    }
+
+    /// Read an existing tag. The reader will be positioned after the tag if successful.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<Self> {
+        let header = read_id3v2_header(reader)?;
+
+        if header.major_version < 3 {
+            return unsupported_error("id3v2: editing ID3v2.2 tags is not supported");
+        }
+
+        let mut body = reader.read_boxed_slice_exact(header.size as usize)?;
+
+        if header.has_footer {
+            reader.ignore_bytes(HEADER_LEN as u64)?;
+        }
+
+        // Tag-wide unsynchronisation is removed since the tag is always written without it.
+        let body = if header.unsynchronisation && header.major_version < 4 {
+            decode_unsynchronisation(&mut body)
+        }
+        else {
+            &mut body[..]
+        };
+
+        let mut reader = BufReader::new(body);
+
+        // The extended header is not preserved. Its CRC would be invalid after an edit anyways.
+        if header.has_extended_header {
+            let len = match header.major_version {
+                3 => u64::from(reader.read_be_u32()?),
+                _ => u64::from(read_syncsafe_leq32(&mut reader, 28)?).saturating_sub(4),
+            };
+            reader.ignore_bytes(len)?;
+        }
+
+        let mut frames = Vec::new();
+
+        while reader.bytes_available() >= HEADER_LEN as u64 {
+            let id = reader.read_quad_bytes()?;
+
+            // The remainder of the tag is padding.
+            if !validate_frame_id(&id) {
+                break;
+            }
+
+            let size = match header.major_version {
+                3 => reader.read_be_u32()?,
+                _ => read_syncsafe_leq32(&mut reader, 28)?,
+            };
+            let flags = reader.read_be_u16()?;
+
+            if u64::from(size) > reader.bytes_available() {
+                return decode_error("id3v2: frame size exceeds tag size");
+            }
+
+            let data = reader.read_boxed_slice_exact(size as usize)?;
+
+            // Frames that must be discarded if the tag is altered and the frame is unknown.
+            let discard = match header.major_version {
+                3 => flags & 0x8000 != 0,
+                _ => flags & 0x4000 != 0,
+            };
+
+            if discard {
+                info!("discarding frame {} on tag alteration", String::from_utf8_lossy(&id));
+                continue;
+            }
+
+            frames.push(RawFrame { id, flags, data });
+        }
+
+        Ok(Id3v2Editor { major_version: header.major_version, frames })
+    }
+
+    /// Gets the major version of the tag.
+    pub fn major_version(&self) -> u8 {
+        self.major_version
+    }
+
+    /// Returns true if the tag contains no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Write the tag, followed by `padding` bytes of padding.
+    pub fn write<W: Write>(&self, writer: &mut W, padding: usize) -> Result<()> {
+        let mut body = Vec::new();
+
+        for frame in &self.frames {
+            body.extend_from_slice(&frame.id);
+
+            match self.major_version {
+                3 => body.extend_from_slice(&(frame.data.len() as u32).to_be_bytes()),
+                _ => body.extend_from_slice(&encode_syncsafe(frame.data.len())?),
+            }
+
+            body.extend_from_slice(&frame.flags.to_be_bytes());
+            body.extend_from_slice(&frame.data);
+        }
+
+        body.resize(body.len() + padding, 0);
+
+        writer.write_all(b"ID3")?;
+        writer.write_all(&[self.major_version, 0, 0])?;
+        writer.write_all(&encode_syncsafe(body.len())?)?;
+        writer.write_all(&body)?;
+
+        Ok(())
+    }
+
+    /// Returns true if the frame is identified by `key`.
+    fn is_match(&self, frame: &RawFrame, key: &Key<'_>) -> bool {
+        match *key {
+            Key::Frame(id) => frame.id == id,
+            Key::UserText(desc) => {
+                frame.id == *b"TXXX"
+                    && self.user_text_desc(frame).map_or(false, |d| d.eq_ignore_ascii_case(desc))
+            }
+        }
+    }
+
+    /// Gets the description of a TXXX frame.
+    fn user_text_desc(&self, frame: &RawFrame) -> Option<String> {
+        // Frames that are compressed, encrypted, unsynchronised, or grouped can't be inspected.
+        let format_flags = match self.major_version {
+            3 => 0x00e0,
+            _ => 0x004f,
+        };
+
+        if frame.flags & format_flags != 0 || frame.data.is_empty() {
+            return None;
+        }
+
+        let encoding = Encoding::parse(frame.data[0])?;
+
+        let mut reader = BufReader::new(&frame.data[1..]);
+        let len = frame.data.len() - 1;
+
+        scan_text(&mut reader, encoding, len).ok().map(|desc| desc.into_owned())
+    }
+
+    /// Encodes a list of strings using the preferred text encoding of the tag version. The
+    /// encoding byte is not included.
+    fn encode_text(&self, buf: &mut Vec<u8>, values: &[&str]) {
+        for (i, value) in values.iter().enumerate() {
+            match self.major_version {
+                // ID3v2.3 does not support UTF-8, so UTF-16 with a byte-order-mark is used.
+                3 => {
+                    if i > 0 {
+                        buf.extend_from_slice(&[0, 0]);
+                    }
+                    buf.extend_from_slice(&[0xff, 0xfe]);
+                    for unit in value.encode_utf16() {
+                        buf.extend_from_slice(&unit.to_le_bytes());
+                    }
+                }
+                _ => {
+                    if i > 0 {
+                        buf.push(0);
+                    }
+                    buf.extend_from_slice(value.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Gets the encoding byte of the preferred text encoding of the tag version.
+    fn text_encoding(&self) -> u8 {
+        match self.major_version {
+            3 => 1,
+            _ => 3,
+        }
+    }
+}
+
+impl Default for Id3v2Editor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TagEditor for Id3v2Editor {
+    fn set(&mut self, key: &str, values: &[&str]) -> Result<()> {
+        let parsed = Key::parse(key)?;
+
+        let mut data = vec![self.text_encoding()];
+
+        let id = match parsed {
+            Key::Frame(id) => {
+                // Only text frames have a common structure that can be written.
+                if id[0] != b'T' || id == *b"TXXX" {
+                    return unsupported_error("id3v2: only text frames may be set");
+                }
+                id
+            }
+            Key::UserText(desc) => {
+                self.encode_text(&mut data, &[desc]);
+                data.extend_from_slice(if self.major_version == 3 { &[0, 0] } else { &[0] });
+                *b"TXXX"
+            }
+        };
+
+        self.encode_text(&mut data, values);
+
+        let frame = RawFrame { id, flags: 0, data: data.into_boxed_slice() };
+
+        // Replace the first matching frame to preserve the frame order, and remove the rest.
+        let pos = self.frames.iter().position(|frame| self.is_match(frame, &parsed));
+
+        self.remove(key);
+
+        let pos = pos.unwrap_or(self.frames.len());
+        self.frames.insert(pos, frame);
+
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        let parsed = match Key::parse(key) {
+            Ok(parsed) => parsed,
+            _ => return false,
+        };
+
+        let len = self.frames.len();
+
+        let frames = std::mem::take(&mut self.frames);
+        self.frames = frames.into_iter().filter(|frame| !self.is_match(frame, &parsed)).collect();
+
+        self.frames.len() != len
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// A frame key. Either a frame ID, or a `TXXX:<description>` user-defined text frame.
+enum Key<'a> {
+    Frame([u8; 4]),
+    UserText(&'a str),
+}
+
+impl<'a> Key<'a> {
+    fn parse(key: &'a str) -> Result<Key<'a>> {
+        if let Some(desc) = key.strip_prefix("TXXX:") {
+            return Ok(Key::UserText(desc));
+        }
+
+        let key = key.as_bytes();
+
+        if key.len() != 4 || !validate_frame_id(key) {
+            return unsupported_error("id3v2: invalid frame id");
+        }
+
+        Ok(Key::Frame([key[0], key[1], key[2], key[3]]))
+    }
+}
+
+/// Encodes a 28-bit syncsafe integer.
+fn encode_syncsafe(value: usize) -> Result<[u8; 4]> {
+    if value > MAX_SYNCSAFE {
+        return unsupported_error("id3v2: tag or frame is too large");
+    }
+
+    Ok([
+        (value >> 21) as u8 & 0x7f,
+        (value >> 14) as u8 & 0x7f,
+        (value >> 7) as u8 & 0x7f,
+        value as u8 & 0x7f,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id3v2::read_id3v2;
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey};
+
+    fn read_tags(buf: &[u8]) -> Vec<(String, String)> {
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut BufReader::new(buf), &mut builder).unwrap();
+
+        let rev = builder.metadata();
+        rev.tags().iter().map(|tag| (tag.key.clone(), tag.value.to_string())).collect()
+    }
+
+    #[test]
+    fn verify_edit_id3v2p4() {
+        let mut editor = Id3v2Editor::new();
+
+        editor.set("TIT2", &["Title"]).unwrap();
+        editor.set("TPE1", &["One", "Two"]).unwrap();
+        editor.set("TXXX:REPLAYGAIN_TRACK_GAIN", &["-6.00 dB"]).unwrap();
+        assert!(editor.set("APIC", &["Picture"]).is_err());
+        assert!(editor.set("T!T2", &["Title"]).is_err());
+
+        let mut buf = Vec::new();
+        editor.write(&mut buf, 16).unwrap();
+
+        // Re-read the written tag, and then edit it.
+        let mut reader = BufReader::new(&buf);
+        let mut editor = Id3v2Editor::read(&mut reader).unwrap();
+        assert_eq!(reader.pos(), buf.len() as u64);
+
+        editor.set("TIT2", &["New Title"]).unwrap();
+        assert!(!editor.remove("txxx:replaygain_track_gain"));
+        assert!(editor.remove("TXXX:replaygain_track_gain"));
+        assert!(!editor.remove("TALB"));
+
+        let mut buf = Vec::new();
+        editor.write(&mut buf, 0).unwrap();
+
+        let tags = read_tags(&buf);
+
+        assert_eq!(
+            tags,
+            vec![
+                ("TIT2".to_string(), "New Title".to_string()),
+                ("TPE1".to_string(), "One".to_string()),
+                ("TPE1".to_string(), "Two".to_string()),
+            ]
+        );
+
+        editor.clear();
+        assert!(editor.is_empty());
+    }
+
+    #[test]
+    fn verify_edit_id3v2p3() {
+        // An ID3v2.3 tag containing an unsupported frame, and a TXXX frame.
+        let mut buf = b"ID3\x03\x00\x00\x00\x00\x00\x00".to_vec();
+        buf.extend_from_slice(b"XTST\x00\x00\x00\x04\x00\x00\x01\x02\x03\x04");
+        buf.extend_from_slice(b"TXXX\x00\x00\x00\x0b\x00\x00\x00MOOD\x00Happy");
+        buf[9] = (buf.len() - 10) as u8;
+
+        let mut editor = Id3v2Editor::read(&mut BufReader::new(&buf)).unwrap();
+        assert_eq!(editor.major_version(), 3);
+
+        editor.set("TXXX:mood", &["Sad"]).unwrap();
+        editor.set("TALB", &["Album"]).unwrap();
+
+        let mut out = Vec::new();
+        editor.write(&mut out, 0).unwrap();
+
+        // The unsupported frame is preserved exactly.
+        assert_eq!(&out[10..24], &buf[10..24]);
+
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut BufReader::new(&out), &mut builder).unwrap();
+
+        let rev = builder.metadata();
+        let tags = rev.tags();
+
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].key, "TXXX:mood");
+        assert_eq!(tags[0].value.to_string(), "Sad");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Album));
+        assert_eq!(tags[1].value.to_string(), "Album");
+    }
+}
@@ -32,7 +32,7 @@ use super::util;
 //   -   ----   ----    ----    ----------------    ------------------------------------------------
 //   S   v2.2   v2.3    v2.4    Std. Key            Description
 //   -   ----   ----    ----    ----------------    ------------------------------------------------
-//       CRA    AENC                                Audio encryption
+//   x   CRA    AENC                                Audio encryption
 //       CRM                                        Encrypted meta frame
 //   x   PIC    APIC                                Attached picture
 //                      ASPI                        Audio seek point index
@@ -57,7 +57,7 @@ use super::util;
 //       RVA    RVAD                                Relative volume adjustment
 //                      RVA2                        Relative volume adjustment (2)
 //       REV    RVRB                                Reverb
-//                      SEEK                        Seek frame
+//   x                  SEEK                        Seek frame
 //                      SIGN                        Signature frame
 //       SLT    SYLT                                Synchronized lyric/text
 //       STC    SYTC                                Synchronized tempo codes
@@ -159,6 +159,11 @@ pub enum FrameResult {
     Visual(Visual),
     /// A frame was parsed and yielded many `Tag`s.
     MultipleTags(Vec<Tag>),
+    /// A `SEEK` frame was parsed and yielded the minimum offset, in bytes, from the end of the
+    /// ID3v2 Tag to the next ID3v2 Tag.
+    Seek(u32),
+    /// An `AENC` frame was parsed and yielded the owner identifier of the audio encryption method.
+    AudioEncryption(String),
 }
 
 /// Makes a frame result for a frame containing invalid data.
@@ -252,8 +257,8 @@ lazy_static! {
     static ref FRAME_PARSERS:
         HashMap<&'static [u8; 4], (FrameParser, Option<StandardTagKey>)> = {
             let mut m = HashMap::new();
-            // m.insert(b"AENC", read_null_frame);
-            m.insert(b"APIC", (read_apic_frame as FrameParser, None));
+            m.insert(b"AENC", (read_aenc_frame as FrameParser, None));
+            m.insert(b"APIC", (read_apic_frame, None));
             // m.insert(b"ASPI", read_null_frame);
             m.insert(b"COMM", (read_comm_uslt_frame, Some(StandardTagKey::Comment)));
             // m.insert(b"COMR", read_null_frame);
@@ -276,7 +281,7 @@ lazy_static! {
             // m.insert(b"RVA2", read_null_frame);
             // m.insert(b"RVAD", read_null_frame);
             // m.insert(b"RVRB", read_null_frame);
-            // m.insert(b"SEEK", read_null_frame);
+            m.insert(b"SEEK", (read_seek_frame, None));
             // m.insert(b"SIGN", read_null_frame);
             // m.insert(b"SYLT", read_null_frame);
             // m.insert(b"SYTC", read_null_frame);
@@ -386,7 +391,7 @@ lazy_static! {
 }
 
 /// Validates that a frame id only contains the uppercase letters A-Z, and digits 0-9.
-fn validate_frame_id(id: &[u8]) -> bool {
+pub fn validate_frame_id(id: &[u8]) -> bool {
     // Only frame IDs with 3 or 4 characters are valid.
     if id.len() != 4 && id.len() != 3 {
         return false;
@@ -765,6 +770,29 @@ fn read_priv_frame(
     Ok(FrameResult::Tag(tag))
 }
 
+/// Reads an `AENC` (audio encryption) frame.
+fn read_aenc_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // Scan for a ISO-8859-1 owner identifier. The remainder of the frame describes the unencrypted
+    // preview, and the encryption method, neither of which are useful.
+    let owner = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    Ok(FrameResult::AudioEncryption(owner.into_owned()))
+}
+
+/// Reads a `SEEK` (seek) frame.
+fn read_seek_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The frame contains the minimum offset from the end of this tag to the next tag.
+    Ok(FrameResult::Seek(reader.read_be_u32()?))
+}
+
 /// Reads a `COMM` (comment) or `USLT` (unsynchronized comment) frame.
 fn read_comm_uslt_frame(
     reader: &mut BufReader<'_>,
@@ -913,7 +941,7 @@ fn read_apic_frame(
 
 /// Enumeration of valid encodings for text fields in ID3v2 tags
 #[derive(Copy, Clone, Debug)]
-enum Encoding {
+pub enum Encoding {
     /// ISO-8859-1 (aka Latin-1) characters in the range 0x20-0xFF.
     Iso8859_1,
     /// UTF-16 (or UCS-2) with a byte-order-mark (BOM). If the BOM is missing, big-endian encoding
@@ -926,7 +954,7 @@ enum Encoding {
 }
 
 impl Encoding {
-    fn parse(encoding: u8) -> Option<Encoding> {
+    pub fn parse(encoding: u8) -> Option<Encoding> {
         match encoding {
             // ISO-8859-1 terminated with 0x00.
             0 => Some(Encoding::Iso8859_1),
@@ -948,7 +976,7 @@ impl Encoding {
 /// the scanned string is valid UTF-8, or is equivalent to UTF-8, then no copies will occur. If a
 /// null terminator is not found, and `scan_len` is reached, or the stream is exhausted, all the
 /// scanned bytes up-to that point are interpreted as the string.
-fn scan_text<'a>(
+pub fn scan_text<'a>(
     reader: &'a mut BufReader<'_>,
     encoding: Encoding,
     scan_len: usize,
@@ -9,15 +9,22 @@
 
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::*;
-use symphonia_core::meta::{MetadataBuilder, MetadataOptions, MetadataReader, MetadataRevision};
+use symphonia_core::meta::{
+    MetadataBuilder, MetadataOptions, MetadataReader, MetadataRevision, Tag, Value,
+};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_metadata;
 
+use std::io::{Seek, SeekFrom};
+
 use log::{info, trace, warn};
 
+mod editor;
 mod frames;
 mod unsync;
 
+pub use editor::Id3v2Editor;
+
 use frames::*;
 use unsync::{read_syncsafe_leq32, UnsyncStream};
 
@@ -93,6 +100,17 @@ struct ExtendedHeader {
     restrictions: Option<Restrictions>,
 }
 
+/// Information signalled by an ID3v2 tag about the media it is attached to.
+#[derive(Clone, Debug, Default)]
+pub struct Id3v2TagInfo {
+    /// If the tag contained a `SEEK` frame, the minimum offset, in bytes, from the end of the tag to
+    /// the next ID3v2 tag. Used to split the tag across multiple regions of the media.
+    pub seek_offset: Option<u32>,
+    /// If the tag contained an `AENC` frame, the owner identifier of the audio encryption method.
+    /// The audio is encrypted, and cannot be decoded.
+    pub encryption_owner: Option<String>,
+}
+
 /// Read the header of an ID3v2 (verions 2.2+) tag.
 fn read_id3v2_header<B: ReadBytes>(reader: &mut B) -> Result<Header> {
     let marker = reader.read_triple_bytes()?;
@@ -276,6 +294,7 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
     reader: &mut B,
     header: &Header,
     metadata: &mut MetadataBuilder,
+    info: &mut Id3v2TagInfo,
 ) -> Result<()> {
     // If there is an extended header, read and parse it based on the major version of the tag.
     if header.has_extended_header {
@@ -319,6 +338,16 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
             FrameResult::Visual(visual) => {
                 metadata.add_visual(visual);
             }
+            // A seek frame points to the next tag.
+            FrameResult::Seek(offset) => {
+                info.seek_offset = Some(offset);
+            }
+            // An audio encryption frame was encountered, the audio is encrypted. Also add a tag so
+            // the encryption is visible to the user.
+            FrameResult::AudioEncryption(owner) => {
+                metadata.add_tag(Tag::new(None, "AENC", Value::from(owner.as_str())));
+                info.encryption_owner = Some(owner);
+            }
             // An unknown frame was encountered.
             FrameResult::UnsupportedFrame(ref id) => {
                 info!("unsupported frame {}", id);
@@ -339,6 +368,17 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
 }
 
 pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    read_id3v2_with_info(reader, metadata).map(|_| ())
+}
+
+/// Reads an ID3v2 tag like [`read_id3v2`], and additionally returns the information signalled by
+/// the tag about the media it is attached to.
+pub fn read_id3v2_with_info<B: ReadBytes>(
+    reader: &mut B,
+    metadata: &mut MetadataBuilder,
+) -> Result<Id3v2TagInfo> {
+    let mut info: Id3v2TagInfo = Default::default();
+
     // Read the (sorta) version agnostic tag header.
     let header = read_id3v2_header(reader)?;
 
@@ -347,7 +387,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     let mut scoped = if header.unsynchronisation && header.major_version < 4 {
         let mut unsync = UnsyncStream::new(ScopedStream::new(reader, u64::from(header.size)));
 
-        read_id3v2_body(&mut unsync, &header, metadata)?;
+        read_id3v2_body(&mut unsync, &header, metadata, &mut info)?;
 
         unsync.into_inner()
     }
@@ -356,7 +396,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     else {
         let mut scoped = ScopedStream::new(reader, u64::from(header.size));
 
-        read_id3v2_body(&mut scoped, &header, metadata)?;
+        read_id3v2_body(&mut scoped, &header, metadata, &mut info)?;
 
         scoped
     };
@@ -364,7 +404,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     // Ignore any remaining data in the tag.
     scoped.ignore()?;
 
-    Ok(())
+    Ok(info)
 }
 
 pub mod util {
@@ -397,6 +437,9 @@ pub mod util {
     }
 }
 
+/// The maximum number of linked tags that will be followed by SEEK frames.
+const MAX_SEEKS: usize = 8;
+
 pub struct Id3v2Reader;
 
 impl QueryDescriptor for Id3v2Reader {
@@ -416,7 +459,83 @@ impl MetadataReader for Id3v2Reader {
 
     fn read_all(&mut self, reader: &mut MediaSourceStream) -> Result<MetadataRevision> {
         let mut builder = MetadataBuilder::new();
-        read_id3v2(reader, &mut builder)?;
+
+        let mut info = read_id3v2_with_info(reader, &mut builder)?;
+
+        // If the tag is split across multiple regions of the stream, follow the SEEK frames to
+        // read the remaining parts of the tag, and then return to the end of the first part.
+        if info.seek_offset.is_some() && reader.is_seekable() {
+            let resume_pos = reader.pos();
+
+            for _ in 0..MAX_SEEKS {
+                let offset = match info.seek_offset.take() {
+                    Some(offset) => u64::from(offset),
+                    None => break,
+                };
+
+                let linked = reader
+                    .seek(SeekFrom::Current(offset as i64))
+                    .map_err(From::from)
+                    .and_then(|_| read_id3v2_with_info(reader, &mut builder));
+
+                match linked {
+                    Ok(linked) => {
+                        info.seek_offset = linked.seek_offset;
+                        info.encryption_owner = info.encryption_owner.or(linked.encryption_owner);
+                    }
+                    Err(err) => {
+                        warn!("skipping linked tag: {}", err);
+                        break;
+                    }
+                }
+            }
+
+            reader.seek(SeekFrom::Start(resume_pos))?;
+        }
+
+        // Encrypted audio would decode to noise.
+        if let Some(owner) = info.encryption_owner {
+            warn!("audio is encrypted by {}", owner);
+            return unsupported_error("id3v2: audio is encrypted");
+        }
+
         Ok(builder.metadata())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::read_id3v2_with_info;
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::MetadataBuilder;
+
+    #[test]
+    fn verify_seek_and_aenc_frames() {
+        // An ID3v2.4 tag with an audio encryption frame, and a seek frame.
+        let mut buf = b"ID3\x04\x00\x00\x00\x00\x00\x00".to_vec();
+        buf.extend_from_slice(b"AENC\x00\x00\x00\x0e\x00\x00owner.org\x00\x00\x00\x00\x00");
+        buf.extend_from_slice(b"SEEK\x00\x00\x00\x04\x00\x00\x00\x00\x12\x34");
+        buf[9] = (buf.len() - 10) as u8;
+
+        let mut builder = MetadataBuilder::new();
+        let info = read_id3v2_with_info(&mut BufReader::new(&buf), &mut builder).unwrap();
+
+        assert_eq!(info.seek_offset, Some(0x1234));
+        assert_eq!(info.encryption_owner.as_deref(), Some("owner.org"));
+
+        let rev = builder.metadata();
+        assert_eq!(rev.tags().len(), 1);
+        assert_eq!(rev.tags()[0].key, "AENC");
+        assert_eq!(rev.tags()[0].value.to_string(), "owner.org");
+
+        // A tag without either frame, only padding.
+        let mut buf = b"ID3\x04\x00\x00\x00\x00\x00\x0a".to_vec();
+        buf.resize(20, 0);
+
+        let mut builder = MetadataBuilder::new();
+        let info = read_id3v2_with_info(&mut BufReader::new(&buf), &mut builder).unwrap();
+
+        assert_eq!(info.seek_offset, None);
+        assert_eq!(info.encryption_owner, None);
+    }
+}
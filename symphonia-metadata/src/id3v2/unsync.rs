@@ -112,9 +112,18 @@ impl<B: ReadBytes + FiniteStream> ReadBytes for UnsyncStream<B> {
         Ok([self.read_byte()?, self.read_byte()?, self.read_byte()?, self.read_byte()?])
     }
 
-    fn read_buf(&mut self, _: &mut [u8]) -> io::Result<usize> {
-        // Not required.
-        unimplemented!();
+    fn read_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // The number of bytes that will be dropped is unknown ahead of time, therefore read
+        // byte-by-byte until the buffer is filled or the end of the stream is reached.
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = match self.read_byte() {
+                Ok(byte) => byte,
+                Err(err) if i > 0 && err.kind() == io::ErrorKind::UnexpectedEof => return Ok(i),
+                Err(err) => return Err(err),
+            };
+        }
+
+        Ok(buf.len())
     }
 
     fn read_buf_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
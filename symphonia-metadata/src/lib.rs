@@ -14,7 +14,10 @@
 #![allow(clippy::identity_op)]
 #![allow(clippy::manual_range_contains)]
 
+pub mod ape;
+pub mod edit;
 pub mod flac;
+pub mod icy;
 pub mod id3v1;
 pub mod id3v2;
 pub mod itunes;
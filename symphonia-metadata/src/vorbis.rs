@@ -12,10 +12,11 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 use log::warn;
 
-use symphonia_core::errors::Result;
+use symphonia_core::errors::{unsupported_error, Result};
 use symphonia_core::io::{BufReader, ReadBytes};
 use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
 
+use crate::edit::TagEditor;
 use crate::flac;
 
 lazy_static! {
@@ -182,6 +183,95 @@ pub fn read_comment_no_framing<B: ReadBytes>(
     Ok(())
 }
 
+/// An editable Vorbis COMMENT.
+///
+/// Comments that are not modified, and the vendor string, are preserved exactly. Field names are
+/// case-insensitive.
+pub struct VorbisCommentEditor {
+    vendor: String,
+    comments: Vec<String>,
+}
+
+impl VorbisCommentEditor {
+    /// Instantiate an empty comment with the given vendor string.
+    pub fn new(vendor: &str) -> Self {
+        VorbisCommentEditor { vendor: vendor.to_string(), comments: Vec::new() }
+    }
+
+    /// Read an existing comment without framing.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<Self> {
+        let vendor_length = reader.read_u32()?;
+        let vendor = reader.read_boxed_slice_exact(vendor_length as usize)?;
+
+        let n_comments = reader.read_u32()? as usize;
+
+        let mut comments = Vec::new();
+
+        for _ in 0..n_comments {
+            let comment_length = reader.read_u32()?;
+            let comment = reader.read_boxed_slice_exact(comment_length as usize)?;
+
+            comments.push(String::from_utf8_lossy(&comment).into_owned());
+        }
+
+        Ok(VorbisCommentEditor { vendor: String::from_utf8_lossy(&vendor).into_owned(), comments })
+    }
+
+    /// Write the comment without framing.
+    pub fn write<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&(self.vendor.len() as u32).to_le_bytes())?;
+        writer.write_all(self.vendor.as_bytes())?;
+        writer.write_all(&(self.comments.len() as u32).to_le_bytes())?;
+
+        for comment in &self.comments {
+            writer.write_all(&(comment.len() as u32).to_le_bytes())?;
+            writer.write_all(comment.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the comment has the field name `key`.
+    fn is_match(comment: &str, key: &str) -> bool {
+        match comment.split_once('=') {
+            Some((name, _)) => name.eq_ignore_ascii_case(key),
+            _ => false,
+        }
+    }
+}
+
+impl TagEditor for VorbisCommentEditor {
+    fn set(&mut self, key: &str, values: &[&str]) -> Result<()> {
+        // A field name may contain any printable ASCII character except for '='.
+        if key.is_empty() || !key.bytes().all(|b| b >= 0x20 && b <= 0x7d && b != b'=') {
+            return unsupported_error("vorbis: invalid field name");
+        }
+
+        // Replace the first matching comment to preserve the comment order, and remove the rest.
+        let pos = self.comments.iter().position(|comment| Self::is_match(comment, key));
+
+        self.remove(key);
+
+        let pos = pos.unwrap_or(self.comments.len());
+
+        for (i, value) in values.iter().enumerate() {
+            self.comments.insert(pos + i, format!("{}={}", key, value));
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        let len = self.comments.len();
+        self.comments.retain(|comment| !Self::is_match(comment, key));
+        self.comments.len() != len
+    }
+
+    fn clear(&mut self) {
+        self.comments.clear();
+    }
+}
+
 /// Decode a RFC4648 Base64 encoded string.
 fn base64_decode(encoded: &str) -> Option<Box<[u8]>> {
     // A sentinel value indicating that an invalid symbol was encountered.
@@ -273,7 +363,7 @@ fn base64_decode(encoded: &str) -> Option<Box<[u8]>> {
 
 #[cfg(test)]
 mod tests {
-    use super::base64_decode;
+    use super::*;
 
     #[test]
     fn verify_base64_decode() {
@@ -298,4 +388,34 @@ mod tests {
         assert_eq!(None, base64_decode("ab!c").as_deref());
         assert_eq!(None, base64_decode("ab=c").as_deref());
     }
+
+    #[test]
+    fn verify_edit_vorbis_comment() {
+        let mut editor = VorbisCommentEditor::new("vendor");
+        editor.set("TITLE", &["Title"]).unwrap();
+        editor.set("ARTIST", &["One", "Two"]).unwrap();
+        editor.set("ALBUM", &["Album"]).unwrap();
+        assert!(editor.set("A=B", &["Value"]).is_err());
+
+        let mut buf = Vec::new();
+        editor.write(&mut buf).unwrap();
+
+        let mut editor = VorbisCommentEditor::read(&mut BufReader::new(&buf)).unwrap();
+
+        editor.set("artist", &["Three"]).unwrap();
+        assert!(editor.remove("Album"));
+        assert!(!editor.remove("Album"));
+
+        let mut buf = Vec::new();
+        editor.write(&mut buf).unwrap();
+
+        let mut builder = MetadataBuilder::new();
+        read_comment_no_framing(&mut BufReader::new(&buf), &mut builder).unwrap();
+
+        let rev = builder.metadata();
+        let tags: Vec<(&str, String)> =
+            rev.tags().iter().map(|tag| (tag.key.as_str(), tag.value.to_string())).collect();
+
+        assert_eq!(tags, vec![("TITLE", "Title".to_string()), ("artist", "Three".to_string())]);
+    }
 }
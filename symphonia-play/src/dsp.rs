@@ -0,0 +1,129 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Built-in audio processors that may be applied to the decoded audio with the `--dsp` option.
+
+use std::f32::consts::PI;
+
+use symphonia::core::audio::{AudioBuffer, Signal, SignalSpec};
+use symphonia::core::dsp::limiter::TruePeakLimiter;
+use symphonia::core::dsp::pan::Balance;
+use symphonia::core::dsp::processor::AudioProcessor;
+use symphonia::core::errors::{unsupported_error, Result};
+
+/// The names and parameters of the built-in processors, for the help text.
+pub const PROCESSORS: &str = "gain=<dB>, crossfeed[=<dB>], limiter[=<dBTP>], balance=<position>";
+
+/// The default level, in dB, of the signal fed to the opposite channel by the crossfeed.
+const CROSSFEED_LEVEL: f32 = -6.0;
+
+/// The cutoff frequency, in Hz, of the low-pass filter applied to the crossfeed.
+const CROSSFEED_CUTOFF: f32 = 700.0;
+
+/// The default ceiling, in dBTP, of the limiter.
+const LIMITER_CEILING: f32 = -1.0;
+
+/// Parses a processor description of the form `<name>[=<parameter>]` and instantiates the
+/// processor.
+pub fn parse(desc: &str) -> std::result::Result<Box<dyn AudioProcessor>, String> {
+    let (name, param) = match desc.split_once('=') {
+        Some((name, param)) => (name, Some(param)),
+        None => (desc, None),
+    };
+
+    let level = |default: Option<f32>| match param {
+        Some(param) => param.parse::<f32>().map_err(|_| format!("invalid level for {}", name)),
+        None => default.ok_or_else(|| format!("{} requires a level", name)),
+    };
+
+    match name {
+        "gain" => Ok(Box::new(Gain::new(level(None)?))),
+        "crossfeed" => Ok(Box::new(Crossfeed::new(level(Some(CROSSFEED_LEVEL))?))),
+        "limiter" => Ok(Box::new(TruePeakLimiter::new(level(Some(LIMITER_CEILING))?))),
+        "balance" => Ok(Box::new(Balance::new(parse_position(param.unwrap_or(""))?))),
+        _ => Err(format!("unknown processor {}, expected one of: {}", name, PROCESSORS)),
+    }
+}
+
+/// Parses a stereo position from -1.0 (left) to 1.0 (right).
+pub fn parse_position(position: &str) -> std::result::Result<f32, String> {
+    match position.parse::<f32>() {
+        Ok(position) if (-1.0..=1.0).contains(&position) => Ok(position),
+        _ => Err("position must be between -1.0 (left) and 1.0 (right)".to_string()),
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Applies a constant gain to all channels.
+struct Gain {
+    gain: f32,
+}
+
+impl Gain {
+    fn new(db: f32) -> Self {
+        Gain { gain: db_to_linear(db) }
+    }
+}
+
+impl AudioProcessor for Gain {
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        let gain = self.gain;
+        buf.transform(|s| s * gain);
+    }
+}
+
+/// Feeds a low-passed copy of each channel of a stereo signal into the opposite channel to reduce
+/// the exaggerated stereo separation of headphones.
+struct Crossfeed {
+    /// The linear level of the crossfeed.
+    level: f32,
+    /// The coefficient of the one-pole low-pass filter.
+    coeff: f32,
+    /// The low-pass filter state of the left and right channels.
+    state: [f32; 2],
+}
+
+impl Crossfeed {
+    fn new(db: f32) -> Self {
+        Crossfeed { level: db_to_linear(db), coeff: 0.0, state: [0.0; 2] }
+    }
+}
+
+impl AudioProcessor for Crossfeed {
+    fn configure(&mut self, spec: &SignalSpec) -> Result<()> {
+        if spec.channels.count() != 2 {
+            return unsupported_error("crossfeed: only stereo audio is supported");
+        }
+
+        self.coeff = (-2.0 * PI * CROSSFEED_CUTOFF / spec.rate as f32).exp();
+        self.state = [0.0; 2];
+
+        Ok(())
+    }
+
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        // Normalize the output so that a mono signal is not louder after the crossfeed.
+        let norm = 1.0 / (1.0 + self.level);
+
+        let (l, r) = buf.chan_pair_mut(0, 1);
+
+        for (l, r) in l.iter_mut().zip(r.iter_mut()) {
+            self.state[0] = *l + self.coeff * (self.state[0] - *l);
+            self.state[1] = *r + self.coeff * (self.state[1] - *r);
+
+            *l = norm * (*l + self.level * self.state[1]);
+            *r = norm * (*r + self.level * self.state[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = [0.0; 2];
+    }
+}
@@ -0,0 +1,309 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `gaps` subcommand. Analyzes the transitions between consecutive tracks of a playlist to
+//! determine if gapless playback will be seamless.
+
+use std::fs::File;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{
+    CodecType, DecoderOptions, CODEC_TYPE_AAC, CODEC_TYPE_MP1, CODEC_TYPE_MP2, CODEC_TYPE_MP3,
+};
+use symphonia::core::errors::{decode_error, Error, Result};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::probe::Hint;
+
+use clap::{Arg, ArgMatches, Command};
+use log::warn;
+
+/// Samples with an absolute value less-than or equal to this threshold are considered silent.
+/// Around -90 dBFS.
+const SILENCE_THRESHOLD: f32 = 1.0 / 32768.0;
+
+/// A transition with a discontinuity larger than this may produce an audible click. Around -20
+/// dBFS.
+const CLICK_THRESHOLD: f32 = 0.1;
+
+/// A transition with more than this much silence, in seconds, is considered a gap.
+const GAP_THRESHOLD: f64 = 0.001;
+
+/// Codecs that always have an encoder delay and padding that must be signalled for gapless
+/// playback.
+const DELAYED_CODECS: &[CodecType] =
+    &[CODEC_TYPE_AAC, CODEC_TYPE_MP1, CODEC_TYPE_MP2, CODEC_TYPE_MP3];
+
+pub fn subcommand() -> Command<'static> {
+    Command::new("gaps")
+        .about("Check if the transitions between consecutive tracks are seamless")
+        .arg(
+            Arg::new("INPUT")
+                .help("The input file paths, in playlist order")
+                .required(true)
+                .min_values(2)
+                .index(1),
+        )
+}
+
+/// The properties of a track relevant to a gapless transition.
+struct TrackInfo {
+    codec: CodecType,
+    sample_rate: u32,
+    n_channels: usize,
+    delay: Option<u32>,
+    padding: Option<u32>,
+    /// The number of silent frames at the start of the track.
+    lead_silence: u64,
+    /// The number of silent frames at the end of the track.
+    trail_silence: u64,
+    /// The first frame of the track.
+    first: Vec<f32>,
+    /// The last two frames of the track.
+    last: [Vec<f32>; 2],
+}
+
+pub fn run(args: &ArgMatches) -> Result<i32> {
+    let paths: Vec<&Path> = args.values_of("INPUT").unwrap().map(Path::new).collect();
+
+    let mut tracks = Vec::with_capacity(paths.len());
+
+    for path in &paths {
+        tracks.push(analyze(path)?);
+    }
+
+    let mut n_problems = 0;
+
+    for (i, (a, b)) in tracks.iter().zip(&tracks[1..]).enumerate() {
+        println!("+ {} -> {}", paths[i].display(), paths[i + 1].display());
+
+        if !print_transition(a, b) {
+            n_problems += 1;
+        }
+
+        println!(":");
+        println!();
+    }
+
+    if n_problems > 0 {
+        println!("{} of {} transitions are not seamless", n_problems, tracks.len() - 1);
+        Ok(1)
+    }
+    else {
+        println!("all transitions are seamless");
+        Ok(0)
+    }
+}
+
+/// Prints the analysis of the transition from track `a` to track `b`, and returns true if it is
+/// seamless.
+fn print_transition(a: &TrackInfo, b: &TrackInfo) -> bool {
+    let mut is_seamless = true;
+
+    println!(
+        "|     Format:          {} Hz, {} ch -> {} Hz, {} ch",
+        a.sample_rate, a.n_channels, b.sample_rate, b.n_channels
+    );
+    println!(
+        "|     Gapless Info:    padding {} -> delay {}",
+        fmt_opt(a.padding, DELAYED_CODECS.contains(&a.codec)),
+        fmt_opt(b.delay, DELAYED_CODECS.contains(&b.codec)),
+    );
+
+    // The audio output must be reconfigured when the format changes.
+    if a.sample_rate != b.sample_rate || a.n_channels != b.n_channels {
+        println!("|     Result:          Gap (format change)");
+        return false;
+    }
+
+    // Without the encoder padding and delay, the padding and delay will be played as silence, or
+    // worse, partially decoded audio.
+    if DELAYED_CODECS.contains(&a.codec) && a.padding.is_none() {
+        println!("|     Warning:         First track has no encoder padding information");
+        is_seamless = false;
+    }
+    if DELAYED_CODECS.contains(&b.codec) && b.delay.is_none() {
+        println!("|     Warning:         Second track has no encoder delay information");
+        is_seamless = false;
+    }
+
+    let silence = a.trail_silence + b.lead_silence;
+    let silence_secs = silence as f64 / f64::from(a.sample_rate);
+
+    println!(
+        "|     Silence:         {} + {} frames ({:.3} ms)",
+        a.trail_silence,
+        b.lead_silence,
+        1000.0 * silence_secs
+    );
+
+    if silence_secs > GAP_THRESHOLD {
+        is_seamless = false;
+    }
+
+    // Estimate the size of the discontinuity at the transition by linearly extrapolating the
+    // last two frames of the first track, and comparing it to the first frame of the second
+    // track.
+    let step = a.last[0]
+        .iter()
+        .zip(&a.last[1])
+        .zip(&b.first)
+        .map(|((&prev, &last), &first)| (first - (2.0 * last - prev)).abs())
+        .fold(0.0f32, f32::max);
+
+    if step > 0.0 {
+        println!("|     Discontinuity:   {:.1} dBFS", 20.0 * step.log10());
+    }
+    else {
+        println!("|     Discontinuity:   None");
+    }
+
+    if step > CLICK_THRESHOLD {
+        is_seamless = false;
+    }
+
+    let result = if silence_secs > GAP_THRESHOLD {
+        "Gap (silence)"
+    }
+    else if step > CLICK_THRESHOLD {
+        "Possible click"
+    }
+    else if is_seamless {
+        "Seamless"
+    }
+    else {
+        "Not seamless (missing gapless information)"
+    };
+
+    println!("|     Result:          {}", result);
+
+    is_seamless
+}
+
+fn fmt_opt(value: Option<u32>, is_required: bool) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None if is_required => "unknown".to_string(),
+        None => "none".to_string(),
+    }
+}
+
+/// Decodes the default track of the file at `path` as it would be for gapless playback.
+fn analyze(path: &Path) -> Result<TrackInfo> {
+    let mut hint = Hint::new();
+
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let mss = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+
+    let format_opts = FormatOptions { enable_gapless: true, ..Default::default() };
+
+    let probed =
+        symphonia::default::get_probe().format(&hint, mss, &format_opts, &Default::default())?;
+
+    let mut reader = probed.format;
+
+    let track = match reader.default_track() {
+        Some(track) => track,
+        _ => return decode_error("gaps: no default track"),
+    };
+
+    let track_id = track.id;
+    let params = &track.codec_params;
+
+    let mut info = TrackInfo {
+        codec: params.codec,
+        sample_rate: params.sample_rate.unwrap_or(0),
+        n_channels: params.channels.map_or(0, |channels| channels.count()),
+        delay: params.delay,
+        padding: params.padding,
+        lead_silence: 0,
+        trail_silence: 0,
+        first: Vec::new(),
+        last: [Vec::new(), Vec::new()],
+    };
+
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut sample_buf = None;
+    let mut is_leading = true;
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(Error::DecodeError(err)) => {
+                warn!("decode error: {}", err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let spec = *decoded.spec();
+
+        // The actual format of the decoded audio takes precedence over the codec parameters.
+        info.sample_rate = spec.rate;
+        info.n_channels = spec.channels.count();
+
+        let buf = sample_buf
+            .get_or_insert_with(|| SampleBuffer::<f32>::new(decoded.capacity() as u64, spec));
+
+        if buf.capacity() < decoded.capacity() * info.n_channels {
+            *buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        }
+
+        buf.copy_interleaved_ref(decoded);
+
+        for frame in buf.samples().chunks_exact(info.n_channels) {
+            let is_silent = frame.iter().all(|s| s.abs() <= SILENCE_THRESHOLD);
+
+            if info.first.is_empty() {
+                info.first = frame.to_vec();
+            }
+
+            if is_leading {
+                if is_silent {
+                    info.lead_silence += 1;
+                }
+                else {
+                    is_leading = false;
+                }
+            }
+
+            if is_silent {
+                info.trail_silence += 1;
+            }
+            else {
+                info.trail_silence = 0;
+            }
+
+            info.last.swap(0, 1);
+            info.last[1].clear();
+            info.last[1].extend_from_slice(frame);
+        }
+    }
+
+    // A track shorter than two frames is treated as if it is preceeded by silence.
+    if info.last[0].is_empty() {
+        info.last[0] = vec![0.0; info.last[1].len()];
+    }
+
+    Ok(info)
+}
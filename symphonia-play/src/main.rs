@@ -15,10 +15,15 @@
 use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use lazy_static::lazy_static;
+use symphonia::core::codecs::stats::{StreamStats, StreamSummary};
+use symphonia::core::codecs::transform::ChannelTransformHook;
 use symphonia::core::codecs::{DecoderOptions, FinalizeResult, CODEC_TYPE_NULL};
+use symphonia::core::dsp::emphasis::DeEmphasis;
+use symphonia::core::dsp::pan::Pan;
+use symphonia::core::dsp::processor::ProcessorChain;
 use symphonia::core::errors::{Error, Result};
 use symphonia::core::formats::{Cue, FormatOptions, FormatReader, SeekMode, SeekTo, Track};
 use symphonia::core::io::{MediaSource, MediaSourceStream, ReadOnlySource};
@@ -29,7 +34,13 @@ use symphonia::core::units::{Time, TimeBase};
 use clap::{Arg, ArgMatches};
 use log::{error, info, warn};
 
+use output::OutputFormat;
+
+mod dsp;
+mod gaps;
 mod output;
+mod tag;
+mod watch;
 
 #[cfg(not(target_os = "linux"))]
 mod resampler;
@@ -46,6 +57,10 @@ fn main() {
         .version("1.0")
         .author("Philip Deljanov <philip.deljanov@gmail.com>")
         .about("Play audio with Symphonia")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(gaps::subcommand())
+        .subcommand(tag::subcommand())
         .arg(
             Arg::new("seek")
                 .long("seek")
@@ -105,21 +120,78 @@ fn main() {
         .arg(
             Arg::new("no-gapless").long("no-gapless").help("Disable gapless decoding and playback"),
         )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(OutputFormat::NAMES)
+                .help("The sample format to output audio in")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
+        .arg(
+            Arg::new("dsp")
+                .long("dsp")
+                .value_name("PROCESSOR")
+                .multiple_occurrences(true)
+                .validator(|desc| dsp::parse(desc).map(|_| ()))
+                .help(
+                    "Apply an audio processor to the decoded audio. May be repeated to apply \
+                    multiple processors in order. Processors: gain=<dB>, crossfeed[=<dB>], \
+                    limiter[=<dBTP>], balance=<position>",
+                )
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
+        .arg(
+            Arg::new("pan")
+                .long("pan")
+                .value_name("POSITION")
+                .allow_hyphen_values(true)
+                .validator(|position| dsp::parse_position(position).map(|_| ()))
+                .help(
+                    "Place mono audio in the stereo field, or set the balance of stereo audio, \
+                    from -1.0 (left) to 1.0 (right)",
+                )
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
+        .arg(
+            Arg::new("dump-wav")
+                .long("dump-wav")
+                .value_name("FILE")
+                .help("Write the decoded audio to a WAVE file instead of playing it")
+                .conflicts_with_all(&["decode-only", "probe-only", "verify-only"]),
+        )
         .arg(
             Arg::new("dump-visuals")
                 .long("dump-visuals")
                 .help("Dump all visuals to the current working directory"),
         )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .value_name("DIR")
+                .help(
+                    "Watch a directory, and play, decode, verify, or probe, every new file while \
+                    it is still being written",
+                )
+                .conflicts_with_all(&["INPUT", "seek", "seek-ts", "dump-wav"]),
+        )
         .arg(
             Arg::new("INPUT")
                 .help("The input file path, or - to use standard input")
-                .required(true)
+                .required_unless_present("watch")
                 .index(1),
         )
         .get_matches();
 
     // For any error, return an exit code -1. Otherwise return the exit code provided.
-    let code = match run(&args) {
+    let result = match args.subcommand() {
+        Some(("gaps", gaps_args)) => gaps::run(gaps_args),
+        Some(("tag", tag_args)) => tag::run(tag_args),
+        _ if args.is_present("watch") => watch::run(&args),
+        _ => run(&args),
+    };
+
+    let code = match result {
         Ok(code) => code,
         Err(err) => {
             error!("{}", err.to_string().to_lowercase());
@@ -133,26 +205,30 @@ fn main() {
 fn run(args: &ArgMatches) -> Result<i32> {
     let path = Path::new(args.value_of("INPUT").unwrap());
 
-    // Create a hint to help the format registry guess what format reader is appropriate.
-    let mut hint = Hint::new();
-
     // If the path string is '-' then read from standard input.
     let source = if path.as_os_str() == "-" {
         Box::new(ReadOnlySource::new(std::io::stdin())) as Box<dyn MediaSource>
     }
     else {
         // Othwerise, get a Path from the path string.
-
-        // Provide the file extension as a hint.
-        if let Some(extension) = path.extension() {
-            if let Some(extension_str) = extension.to_str() {
-                hint.with_extension(extension_str);
-            }
-        }
-
         Box::new(File::open(path)?)
     };
 
+    run_source(args, path, source)
+}
+
+/// Runs the selected operating mode on the media `source` read from `path`.
+fn run_source(args: &ArgMatches, path: &Path, source: Box<dyn MediaSource>) -> Result<i32> {
+    // Create a hint to help the format registry guess what format reader is appropriate.
+    let mut hint = Hint::new();
+
+    // Provide the file extension as a hint.
+    if let Some(extension) = path.extension() {
+        if let Some(extension_str) = extension.to_str() {
+            hint.with_extension(extension_str);
+        }
+    }
+
     // Create the media source stream using the boxed media source from above.
     let mss = MediaSourceStream::new(source, Default::default());
 
@@ -212,11 +288,52 @@ fn run(args: &ArgMatches) -> Result<i32> {
                 };
 
                 // Set the decoder options.
-                let decode_opts =
-                    DecoderOptions { verify: args.is_present("verify"), ..Default::default() };
+                let pan = args.value_of("pan").map(|position| position.parse::<f32>().unwrap());
+
+                let decode_opts = DecodeOptions {
+                    decoder: DecoderOptions {
+                        verify: args.is_present("verify"),
+                        ..Default::default()
+                    },
+                    channel_transform: pan.map(|position| {
+                        ChannelTransformHook::new(move || Box::new(Pan::new(position)))
+                    }),
+                };
+
+                // Set the output options.
+                let output_opts = OutputOptions {
+                    format: args.value_of("format").and_then(OutputFormat::from_name),
+                    dump_wav: args.value_of("dump-wav").map(PathBuf::from),
+                };
+
+                // Build the chain of audio processors in the order they were provided.
+                let mut processors = ProcessorChain::new();
+
+                // Undo the pre-emphasis of the selected track, if any, before any other processing.
+                let emphasis = track
+                    .and_then(|t| probed.format.tracks().get(t))
+                    .or_else(|| first_supported_track(probed.format.tracks()))
+                    .and_then(|track| track.codec_params.emphasis);
+
+                if let Some(emphasis) = emphasis {
+                    info!("applying {:?} de-emphasis", emphasis);
+                    processors.push(Box::new(DeEmphasis::new(emphasis)));
+                }
+
+                for desc in args.values_of("dsp").into_iter().flatten() {
+                    processors.push(dsp::parse(desc).unwrap());
+                }
 
                 // Play it!
-                play(probed.format, track, seek, &decode_opts, no_progress)
+                play(
+                    probed.format,
+                    track,
+                    seek,
+                    &decode_opts,
+                    &output_opts,
+                    &mut processors,
+                    no_progress,
+                )
             }
         }
         Err(err) => {
@@ -236,6 +353,8 @@ fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions)
     // Create a decoder for the track.
     let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, decode_opts)?;
 
+    let mut stats = StreamStats::new();
+
     // Decode all packets, ignoring all decode errors.
     let result = loop {
         let packet = match reader.next_packet() {
@@ -250,15 +369,22 @@ fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions)
 
         // Decode the packet into audio samples.
         match decoder.decode(&packet) {
-            Ok(_decoded) => continue,
-            Err(Error::DecodeError(err)) => warn!("decode error: {}", err),
+            Ok(decoded) => stats.record_decoded(&packet, &decoded),
+            Err(Error::DecodeError(err)) => {
+                warn!("decode error: {}", err);
+                stats.record_error();
+            }
             Err(err) => break Err(err),
         }
+
+        stats.record_warnings(decoder.last_warnings());
     };
 
     // Return if a fatal error occured.
     ignore_end_of_stream_error(result)?;
 
+    print_summary(&stats.summary());
+
     // Finalize the decoder and return the verification result if it's been enabled.
     do_verification(decoder.finalize())
 }
@@ -269,11 +395,27 @@ struct PlayTrackOptions {
     seek_ts: u64,
 }
 
+struct DecodeOptions {
+    /// The options to instantiate the decoder with.
+    decoder: DecoderOptions,
+    /// If set, the channel transform to apply to the decoded audio.
+    channel_transform: Option<ChannelTransformHook>,
+}
+
+struct OutputOptions {
+    /// The sample format to output audio in. If `None`, the output picks a format.
+    format: Option<OutputFormat>,
+    /// If set, write the audio to a WAVE file at this path instead of playing it.
+    dump_wav: Option<PathBuf>,
+}
+
 fn play(
     mut reader: Box<dyn FormatReader>,
     track_num: Option<usize>,
     seek: Option<SeekPosition>,
-    decode_opts: &DecoderOptions,
+    decode_opts: &DecodeOptions,
+    output_opts: &OutputOptions,
+    processors: &mut ProcessorChain,
     no_progress: bool,
 ) -> Result<i32> {
     // If the user provided a track number, select that track if it exists, otherwise, select the
@@ -326,7 +468,15 @@ fn play(
     let mut track_info = PlayTrackOptions { track_id, seek_ts };
 
     let result = loop {
-        match play_track(&mut reader, &mut audio_output, track_info, decode_opts, no_progress) {
+        match play_track(
+            &mut reader,
+            &mut audio_output,
+            track_info,
+            decode_opts,
+            output_opts,
+            processors,
+            no_progress,
+        ) {
             Err(Error::ResetRequired) => {
                 // The demuxer indicated that a reset is required. This is sometimes seen with
                 // streaming OGG (e.g., Icecast) wherein the entire contents of the container change
@@ -338,6 +488,9 @@ fn play(
                 // longer be valid or make sense.
                 let track_id = first_supported_track(reader.tracks()).unwrap().id;
                 track_info = PlayTrackOptions { track_id, seek_ts: 0 };
+
+                // The new track is not a continuation of the old track.
+                processors.reset();
             }
             res => break res,
         }
@@ -355,7 +508,9 @@ fn play_track(
     reader: &mut Box<dyn FormatReader>,
     audio_output: &mut Option<Box<dyn output::AudioOutput>>,
     play_opts: PlayTrackOptions,
-    decode_opts: &DecoderOptions,
+    decode_opts: &DecodeOptions,
+    output_opts: &OutputOptions,
+    processors: &mut ProcessorChain,
     no_progress: bool,
 ) -> Result<i32> {
     // Get the selected track using the track ID.
@@ -365,12 +520,21 @@ fn play_track(
     };
 
     // Create a decoder for the track.
-    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, decode_opts)?;
+    let codecs = symphonia::default::get_codecs();
+
+    let mut decoder = match &decode_opts.channel_transform {
+        Some(hook) => {
+            codecs.make_with_transform(&track.codec_params, &decode_opts.decoder, hook)?
+        }
+        None => codecs.make(&track.codec_params, &decode_opts.decoder)?,
+    };
 
     // Get the selected track's timebase and duration.
     let tb = track.codec_params.time_base;
     let dur = track.codec_params.n_frames.map(|frames| track.codec_params.start_ts + frames);
 
+    let mut stats = StreamStats::new();
+
     // Decode and play the packets belonging to the selected track.
     let result = loop {
         // Get the next packet from the format reader.
@@ -396,8 +560,22 @@ fn play_track(
         // Decode the packet into audio samples.
         match decoder.decode(&packet) {
             Ok(decoded) => {
-                // If the audio output is not open, try to open it.
-                if audio_output.is_none() {
+                stats.record_decoded(&packet, &decoded);
+
+                // Apply the audio processors, if any.
+                let decoded = if processors.is_empty() {
+                    decoded
+                }
+                else {
+                    match processors.process_ref(decoded) {
+                        Ok(processed) => processed,
+                        Err(err) => break Err(err),
+                    }
+                };
+
+                // If the audio output is not open, try to open it. An empty buffer may not have a
+                // valid signal specification, so wait for a buffer with audio.
+                if audio_output.is_none() && decoded.frames() > 0 {
                     // Get the audio buffer specification. This is a description of the decoded
                     // audio buffer's sample format and sample rate.
                     let spec = *decoded.spec();
@@ -407,8 +585,15 @@ fn play_track(
                     // decoder, but the length is not.
                     let duration = decoded.capacity() as u64;
 
-                    // Try to open the audio output.
-                    audio_output.replace(output::try_open(spec, duration).unwrap());
+                    // Try to open the audio output, or the WAVE file if dumping.
+                    let format = output_opts.format;
+
+                    let opened = match &output_opts.dump_wav {
+                        Some(path) => output::try_open_wav(path, spec, duration, format),
+                        None => output::try_open(spec, duration, format),
+                    };
+
+                    audio_output.replace(opened.unwrap());
                 }
                 else {
                     // TODO: Check the audio spec. and duration hasn't changed.
@@ -430,9 +615,12 @@ fn play_track(
                 // Decode errors are not fatal. Print the error message and try to decode the next
                 // packet as usual.
                 warn!("decode error: {}", err);
+                stats.record_error();
             }
             Err(err) => break Err(err),
         }
+
+        stats.record_warnings(decoder.last_warnings());
     };
 
     if !no_progress {
@@ -442,6 +630,8 @@ fn play_track(
     // Return if a fatal error occured.
     ignore_end_of_stream_error(result)?;
 
+    print_summary(&stats.summary());
+
     // Finalize the decoder and return the verification result if it's been enabled.
     do_verification(decoder.finalize())
 }
@@ -613,6 +803,9 @@ fn print_tracks(tracks: &[Track]) {
             if let Some(channel_layout) = params.channel_layout {
                 println!("|          Channel Layout:  {:?}", channel_layout);
             }
+            if let Some(emphasis) = params.emphasis {
+                println!("|          Emphasis:        {:?}", emphasis);
+            }
             if let Some(language) = &track.language {
                 println!("|          Language:        {}", language);
             }
@@ -770,9 +963,30 @@ fn print_tag_item(idx: usize, key: &str, value: &Value, indent: usize) -> String
     out
 }
 
+fn print_summary(summary: &StreamSummary) {
+    println!("+ Summary");
+    println!("|     Frames:          {}", summary.n_frames);
+    println!("|     Duration:        {}", fmt_duration(summary.duration));
+    println!("|     Errors:          {}", summary.n_errors);
+    println!("|     Warnings:        {}", summary.n_warnings);
+
+    if let Some(bitrate) = summary.avg_bitrate {
+        println!("|     Average Bitrate: {} kbps", (bitrate + 500) / 1000);
+    }
+
+    if summary.n_frames > 0 {
+        println!("|     True Peak:       {:.1} dBTP", summary.true_peak_db());
+    }
+
+    println!(":");
+    println!();
+}
+
 fn fmt_time(ts: u64, tb: TimeBase) -> String {
-    let time = tb.calc_time(ts);
+    fmt_duration(tb.calc_time(ts))
+}
 
+fn fmt_duration(time: Time) -> String {
     let hours = time.seconds / (60 * 60);
     let mins = (time.seconds % (60 * 60)) / 60;
     let secs = f64::from((time.seconds % 60) as u32) + time.frac;
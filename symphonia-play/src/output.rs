@@ -7,9 +7,13 @@
 
 //! Platform-dependant Audio Outputs
 
+use std::path::Path;
 use std::result;
 
-use symphonia::core::audio::{AudioBufferRef, SignalSpec};
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, RawSample, RawSampleBuffer, SignalSpec};
+use symphonia::core::conv::dither::{MaybeDither, Triangular};
+use symphonia::core::conv::{ConvertibleSample, IntoSample};
+use symphonia::core::sample::{i24, Sample, SampleFormat};
 use symphonia::core::units::Duration;
 
 pub trait AudioOutput {
@@ -17,6 +21,142 @@ pub trait AudioOutput {
     fn flush(&mut self);
 }
 
+/// The sample format decoded audio is converted to before it is output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Signed 16-bit integer.
+    S16,
+    /// Signed 24-bit integer.
+    S24,
+    /// Signed 32-bit integer.
+    S32,
+    /// 32-bit floating point.
+    F32,
+}
+
+impl OutputFormat {
+    /// The names of all output formats as accepted by `from_name`.
+    pub const NAMES: &'static [&'static str] = &["s16", "s24", "s32", "f32"];
+
+    /// Get an output format from its name.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "s16" => Some(OutputFormat::S16),
+            "s24" => Some(OutputFormat::S24),
+            "s32" => Some(OutputFormat::S32),
+            "f32" => Some(OutputFormat::F32),
+            _ => None,
+        }
+    }
+
+    /// The number of bits per sample.
+    pub fn bits_per_sample(&self) -> u32 {
+        match self {
+            OutputFormat::S16 => 16,
+            OutputFormat::S24 => 24,
+            OutputFormat::S32 | OutputFormat::F32 => 32,
+        }
+    }
+}
+
+/// Gets the number of bits of resolution of a sample of the given format.
+fn resolution(format: SampleFormat) -> u32 {
+    match format {
+        SampleFormat::F32 => 24,
+        SampleFormat::F64 => 53,
+        _ => format.bits_per_sample(),
+    }
+}
+
+/// Dithers decoded audio that has a greater resolution than the output sample format `S`.
+pub struct Ditherer<S: Sample> {
+    /// The decoded audio converted to `f64`.
+    buf: Option<AudioBuffer<f64>>,
+    /// The dither of each channel. The dithers are kept between buffers.
+    dithers: Vec<Triangular<f64, S>>,
+}
+
+impl<S> Ditherer<S>
+where
+    S: ConvertibleSample + RawSample,
+    f64: MaybeDither<S> + IntoSample<S>,
+{
+    fn new() -> Self {
+        Ditherer { buf: None, dithers: Vec::new() }
+    }
+
+    /// Convert and interleave the decoded audio into `out`, dithering it if it has a greater
+    /// resolution than the output sample format.
+    fn copy_interleaved_ref(&mut self, out: &mut RawSampleBuffer<S>, decoded: AudioBufferRef<'_>) {
+        if resolution(decoded.sample_format()) <= S::EFF_BITS {
+            out.copy_interleaved_ref(decoded);
+            return;
+        }
+
+        let buf = match &mut self.buf {
+            Some(buf) if buf.capacity() == decoded.capacity() && buf.spec() == decoded.spec() => {
+                buf
+            }
+            buf => buf.insert(decoded.make_equivalent()),
+        };
+
+        decoded.convert(buf);
+
+        let n_channels = decoded.spec().channels.count();
+
+        if self.dithers.len() != n_channels {
+            self.dithers = (0..n_channels).map(|_| Triangular::new()).collect();
+        }
+
+        out.copy_interleaved_dithered(buf, &mut self.dithers);
+    }
+}
+
+/// An interleaved byte buffer of samples in a given `OutputFormat`. Samples are converted into the
+/// output format using Symphonia's sample conversion routines. Decoded audio is dithered if the
+/// output format reduces its resolution.
+pub enum RawOutputBuffer {
+    S16(RawSampleBuffer<i16>, Ditherer<i16>),
+    S24(RawSampleBuffer<i24>, Ditherer<i24>),
+    S32(RawSampleBuffer<i32>),
+    F32(RawSampleBuffer<f32>),
+}
+
+impl RawOutputBuffer {
+    pub fn new(format: OutputFormat, duration: Duration, spec: SignalSpec) -> Self {
+        match format {
+            OutputFormat::S16 => {
+                RawOutputBuffer::S16(RawSampleBuffer::new(duration, spec), Ditherer::new())
+            }
+            OutputFormat::S24 => {
+                RawOutputBuffer::S24(RawSampleBuffer::new(duration, spec), Ditherer::new())
+            }
+            OutputFormat::S32 => RawOutputBuffer::S32(RawSampleBuffer::new(duration, spec)),
+            OutputFormat::F32 => RawOutputBuffer::F32(RawSampleBuffer::new(duration, spec)),
+        }
+    }
+
+    /// Convert and interleave the decoded audio into the buffer.
+    pub fn copy_interleaved_ref(&mut self, decoded: AudioBufferRef<'_>) {
+        match self {
+            RawOutputBuffer::S16(buf, ditherer) => ditherer.copy_interleaved_ref(buf, decoded),
+            RawOutputBuffer::S24(buf, ditherer) => ditherer.copy_interleaved_ref(buf, decoded),
+            RawOutputBuffer::S32(buf) => buf.copy_interleaved_ref(decoded),
+            RawOutputBuffer::F32(buf) => buf.copy_interleaved_ref(decoded),
+        }
+    }
+
+    /// Get the interleaved samples as native-endian bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            RawOutputBuffer::S16(buf, _) => buf.as_bytes(),
+            RawOutputBuffer::S24(buf, _) => buf.as_bytes(),
+            RawOutputBuffer::S32(buf) => buf.as_bytes(),
+            RawOutputBuffer::F32(buf) => buf.as_bytes(),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
@@ -30,7 +170,7 @@ pub type Result<T> = result::Result<T, AudioOutputError>;
 
 #[cfg(target_os = "linux")]
 mod pulseaudio {
-    use super::{AudioOutput, AudioOutputError, Result};
+    use super::{AudioOutput, AudioOutputError, OutputFormat, RawOutputBuffer, Result};
 
     use symphonia::core::audio::*;
     use symphonia::core::units::Duration;
@@ -42,18 +182,33 @@ mod pulseaudio {
 
     pub struct PulseAudioOutput {
         pa: psimple::Simple,
-        sample_buf: RawSampleBuffer<f32>,
+        sample_buf: RawOutputBuffer,
     }
 
     impl PulseAudioOutput {
-        pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-            // An interleaved buffer is required to send data to PulseAudio. Use a SampleBuffer to
-            // move data between Symphonia AudioBuffers and the byte buffers required by PulseAudio.
-            let sample_buf = RawSampleBuffer::<f32>::new(duration, spec);
+        pub fn try_open(
+            spec: SignalSpec,
+            duration: Duration,
+            format: Option<OutputFormat>,
+        ) -> Result<Box<dyn AudioOutput>> {
+            // PulseAudio supports all output formats, use 32-bit floating point by default.
+            let format = format.unwrap_or(OutputFormat::F32);
+
+            // An interleaved buffer is required to send data to PulseAudio. Use a RawOutputBuffer
+            // to move data between Symphonia AudioBuffers and the byte buffers required by
+            // PulseAudio.
+            let sample_buf = RawOutputBuffer::new(format, duration, spec);
+
+            let pa_format = match format {
+                OutputFormat::S16 => pulse::sample::Format::S16NE,
+                OutputFormat::S24 => pulse::sample::Format::S24NE,
+                OutputFormat::S32 => pulse::sample::Format::S32NE,
+                OutputFormat::F32 => pulse::sample::Format::FLOAT32NE,
+            };
 
             // Create a PulseAudio stream specification.
             let pa_spec = pulse::sample::Spec {
-                format: pulse::sample::Format::FLOAT32NE,
+                format: pa_format,
                 channels: spec.channels.count() as u8,
                 rate: spec.rate,
             };
@@ -169,7 +324,7 @@ mod pulseaudio {
 mod cpal {
     use crate::resampler::Resampler;
 
-    use super::{AudioOutput, AudioOutputError, Result};
+    use super::{AudioOutput, AudioOutputError, OutputFormat, Result};
 
     use symphonia::core::audio::{AudioBufferRef, RawSample, SampleBuffer, SignalSpec};
     use symphonia::core::conv::{ConvertibleSample, IntoSample};
@@ -178,7 +333,7 @@ mod cpal {
     use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
     use rb::*;
 
-    use log::{error, info};
+    use log::{error, info, warn};
 
     pub struct CpalAudioOutput;
 
@@ -192,7 +347,11 @@ mod cpal {
     impl AudioOutputSample for u16 {}
 
     impl CpalAudioOutput {
-        pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
+        pub fn try_open(
+            spec: SignalSpec,
+            duration: Duration,
+            format: Option<OutputFormat>,
+        ) -> Result<Box<dyn AudioOutput>> {
             // Get default host.
             let host = cpal::default_host();
 
@@ -213,8 +372,36 @@ mod cpal {
                 }
             };
 
+            // If an output format was requested, use the closest sample format supported by cpal.
+            let requested = match format {
+                Some(OutputFormat::S16) => Some(cpal::SampleFormat::I16),
+                Some(OutputFormat::F32) => Some(cpal::SampleFormat::F32),
+                Some(format) => {
+                    // cpal does not support 24- or 32-bit integer samples. 32-bit floating point
+                    // can represent 24-bit integer samples losslessly, so use that instead.
+                    warn!("{:?} output is not supported, using f32 instead", format);
+                    Some(cpal::SampleFormat::F32)
+                }
+                None => None,
+            };
+
+            // Only use the requested sample format if the device supports it. Otherwise, use the
+            // device's default sample format.
+            let sample_format = match requested {
+                Some(requested) if supports_sample_format(&device, requested) => requested,
+                Some(requested) => {
+                    warn!(
+                        "{:?} output is not supported by the device, using {:?} instead",
+                        requested,
+                        config.sample_format()
+                    );
+                    config.sample_format()
+                }
+                None => config.sample_format(),
+            };
+
             // Select proper playback routine based on sample format.
-            match config.sample_format() {
+            match sample_format {
                 cpal::SampleFormat::F32 => {
                     CpalAudioOutputImpl::<f32>::try_open(spec, duration, &device)
                 }
@@ -228,6 +415,17 @@ mod cpal {
         }
     }
 
+    /// Returns true if the device supports output streams with the sample format `format`.
+    fn supports_sample_format(device: &cpal::Device, format: cpal::SampleFormat) -> bool {
+        match device.supported_output_configs() {
+            Ok(mut configs) => configs.any(|config| config.sample_format() == format),
+            Err(err) => {
+                warn!("failed to get supported audio output device configs: {}", err);
+                false
+            }
+        }
+    }
+
     struct CpalAudioOutputImpl<T: AudioOutputSample>
     where
         T: AudioOutputSample,
@@ -357,12 +555,168 @@ mod cpal {
     }
 }
 
+mod wav {
+    use super::{AudioOutput, AudioOutputError, OutputFormat, RawOutputBuffer, Result};
+
+    use std::fs::File;
+    use std::io::{BufWriter, Seek, SeekFrom, Write};
+    use std::path::Path;
+
+    use symphonia::core::audio::*;
+    use symphonia::core::units::Duration;
+
+    use log::error;
+
+    /// The size of the RIFF header, and the "fmt " and "data" chunk headers, in bytes.
+    const HEADER_LEN: u32 = 44;
+
+    /// An audio output that writes a WAVE file.
+    pub struct WavOutput {
+        writer: BufWriter<File>,
+        sample_buf: RawOutputBuffer,
+        bytes_per_sample: usize,
+        data_len: u32,
+    }
+
+    impl WavOutput {
+        pub fn try_open(
+            path: &Path,
+            spec: SignalSpec,
+            duration: Duration,
+            format: Option<OutputFormat>,
+        ) -> Result<Box<dyn AudioOutput>> {
+            // Write 32-bit floating point samples by default.
+            let format = format.unwrap_or(OutputFormat::F32);
+
+            let file = match File::create(path) {
+                Ok(file) => file,
+                Err(err) => {
+                    error!("failed to create wav file: {}", err);
+                    return Err(AudioOutputError::OpenStreamError);
+                }
+            };
+
+            let mut output = WavOutput {
+                writer: BufWriter::new(file),
+                sample_buf: RawOutputBuffer::new(format, duration, spec),
+                bytes_per_sample: format.bits_per_sample() as usize / 8,
+                data_len: 0,
+            };
+
+            // Write the header with the chunk lengths set to 0. The lengths are updated when the
+            // output is flushed.
+            if let Err(err) = output.write_header(format, spec) {
+                error!("failed to write wav header: {}", err);
+                return Err(AudioOutputError::OpenStreamError);
+            }
+
+            Ok(Box::new(output))
+        }
+
+        fn write_header(&mut self, format: OutputFormat, spec: SignalSpec) -> std::io::Result<()> {
+            let n_channels = spec.channels.count() as u16;
+            let bits_per_sample = format.bits_per_sample() as u16;
+            let block_align = n_channels * (bits_per_sample / 8);
+
+            // WAVE_FORMAT_IEEE_FLOAT for floating point samples, and WAVE_FORMAT_PCM otherwise.
+            let format_tag: u16 = if format == OutputFormat::F32 { 0x0003 } else { 0x0001 };
+
+            let w = &mut self.writer;
+
+            w.write_all(b"RIFF")?;
+            w.write_all(&(HEADER_LEN - 8).to_le_bytes())?;
+            w.write_all(b"WAVE")?;
+            w.write_all(b"fmt ")?;
+            w.write_all(&16u32.to_le_bytes())?;
+            w.write_all(&format_tag.to_le_bytes())?;
+            w.write_all(&n_channels.to_le_bytes())?;
+            w.write_all(&spec.rate.to_le_bytes())?;
+            w.write_all(&(spec.rate * u32::from(block_align)).to_le_bytes())?;
+            w.write_all(&block_align.to_le_bytes())?;
+            w.write_all(&bits_per_sample.to_le_bytes())?;
+            w.write_all(b"data")?;
+            w.write_all(&0u32.to_le_bytes())?;
+            Ok(())
+        }
+
+        fn update_header(&mut self) -> std::io::Result<()> {
+            let w = &mut self.writer;
+
+            w.seek(SeekFrom::Start(4))?;
+            w.write_all(&(HEADER_LEN - 8 + self.data_len).to_le_bytes())?;
+            w.seek(SeekFrom::Start(u64::from(HEADER_LEN) - 4))?;
+            w.write_all(&self.data_len.to_le_bytes())?;
+            w.seek(SeekFrom::End(0))?;
+            w.flush()
+        }
+    }
+
+    impl AudioOutput for WavOutput {
+        fn write(&mut self, decoded: AudioBufferRef<'_>) -> Result<()> {
+            // Do nothing if there are no audio frames.
+            if decoded.frames() == 0 {
+                return Ok(());
+            }
+
+            // Convert and interleave samples from the audio buffer into the sample buffer.
+            self.sample_buf.copy_interleaved_ref(decoded);
+
+            let bytes = self.sample_buf.as_bytes();
+
+            // The sample buffer is native-endian, but WAVE files are always little-endian.
+            let result = if cfg!(target_endian = "big") {
+                let mut swapped = bytes.to_vec();
+                swapped.chunks_exact_mut(self.bytes_per_sample).for_each(|s| s.reverse());
+                self.writer.write_all(&swapped)
+            }
+            else {
+                self.writer.write_all(bytes)
+            };
+
+            if let Err(err) = result {
+                error!("wav file write error: {}", err);
+                return Err(AudioOutputError::StreamClosedError);
+            }
+
+            // The data chunk length saturates if the file grows beyond what a WAVE file can
+            // describe.
+            self.data_len = self.data_len.saturating_add(bytes.len() as u32);
+
+            Ok(())
+        }
+
+        fn flush(&mut self) {
+            if let Err(err) = self.update_header() {
+                error!("failed to update wav header: {}", err);
+            }
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
-pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-    pulseaudio::PulseAudioOutput::try_open(spec, duration)
+pub fn try_open(
+    spec: SignalSpec,
+    duration: Duration,
+    format: Option<OutputFormat>,
+) -> Result<Box<dyn AudioOutput>> {
+    pulseaudio::PulseAudioOutput::try_open(spec, duration, format)
 }
 
 #[cfg(not(target_os = "linux"))]
-pub fn try_open(spec: SignalSpec, duration: Duration) -> Result<Box<dyn AudioOutput>> {
-    cpal::CpalAudioOutput::try_open(spec, duration)
+pub fn try_open(
+    spec: SignalSpec,
+    duration: Duration,
+    format: Option<OutputFormat>,
+) -> Result<Box<dyn AudioOutput>> {
+    cpal::CpalAudioOutput::try_open(spec, duration, format)
+}
+
+/// Open an audio output that writes a WAVE file to `path` instead of playing the audio.
+pub fn try_open_wav(
+    path: &Path,
+    spec: SignalSpec,
+    duration: Duration,
+    format: Option<OutputFormat>,
+) -> Result<Box<dyn AudioOutput>> {
+    wav::WavOutput::try_open(path, spec, duration, format)
 }
@@ -0,0 +1,283 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `tag` subcommand. Edits the ID3v2 tag of MPEG audio files, or the Vorbis COMMENT of FLAC
+//! files, in-place.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use symphonia::core::errors::{unsupported_error, Result};
+use symphonia::core::io::{BufReader, MediaSourceStream, ReadBytes};
+use symphonia_metadata::edit::TagEditor;
+use symphonia_metadata::id3v2::Id3v2Editor;
+use symphonia_metadata::vorbis::VorbisCommentEditor;
+
+use clap::{Arg, ArgMatches, Command};
+use log::info;
+
+/// The amount of padding to add to a rewritten ID3v2 tag so that future edits may be smaller.
+const ID3V2_PADDING: usize = 1024;
+
+/// File extensions of MPEG audio files that may have an ID3v2 tag added.
+const MPEG_EXTENSIONS: &[&str] = &["aac", "mp1", "mp2", "mp3", "mpa"];
+
+/// The vendor string of a new Vorbis COMMENT.
+const VENDOR: &str = "Symphonia";
+
+/// The FLAC VORBIS_COMMENT metadata block type.
+const FLAC_VORBIS_COMMENT: u8 = 4;
+
+/// The maximum length of a FLAC metadata block.
+const FLAC_MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+pub fn subcommand() -> Command<'static> {
+    let file = Arg::new("FILE").help("The file to edit").required(true).index(1);
+
+    Command::new("tag")
+        .about("Edit the tags of a file in-place")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("set")
+                .about("Set a tag, replacing any existing values")
+                .arg(file.clone())
+                .arg(
+                    Arg::new("KEY")
+                        .help(
+                            "The tag key. Either an ID3v2 frame ID (e.g., TIT2 or TXXX:<desc>), \
+                            or a Vorbis comment field name (e.g., TITLE)",
+                        )
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::new("VALUE")
+                        .help("The values of the tag")
+                        .required(true)
+                        .multiple_values(true)
+                        .index(3),
+                ),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Remove all values of one or more tags")
+                .arg(file.clone())
+                .arg(
+                    Arg::new("KEY")
+                        .help("The tag keys to remove")
+                        .required(true)
+                        .multiple_values(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(Command::new("clear").about("Remove all tags").arg(file))
+}
+
+pub fn run(args: &ArgMatches) -> Result<i32> {
+    let (cmd, args) = args.subcommand().unwrap();
+
+    let path = Path::new(args.value_of("FILE").unwrap());
+
+    match cmd {
+        "set" => {
+            let key = args.value_of("KEY").unwrap();
+            let values: Vec<&str> = args.values_of("VALUE").unwrap().collect();
+
+            edit_file(path, |editor| editor.set(key, &values).map(|_| true))?;
+        }
+        "remove" => {
+            let keys: Vec<&str> = args.values_of("KEY").unwrap().collect();
+
+            edit_file(path, |editor| {
+                let mut changed = false;
+
+                for key in keys {
+                    if editor.remove(key) {
+                        changed = true;
+                    }
+                    else {
+                        info!("no tag with key {}", key);
+                    }
+                }
+
+                Ok(changed)
+            })?;
+        }
+        "clear" => {
+            edit_file(path, |editor| {
+                editor.clear();
+                Ok(true)
+            })?;
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(0)
+}
+
+/// Applies `edit` to the tag of the file at `path`. If `edit` returns true, the file is rewritten
+/// with the edited tag.
+fn edit_file<F>(path: &Path, edit: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn TagEditor) -> Result<bool>,
+{
+    let mut file = File::open(path)?;
+
+    let mut marker = [0; 4];
+    let len = file.read(&mut marker)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut source = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut tag = Vec::new();
+
+    // Write the edited tag to a buffer, and get the position of the data following the original
+    // tag.
+    let data_pos = if len == 4 && marker == *b"fLaC" {
+        match edit_flac(&mut source, &mut tag, edit)? {
+            Some(pos) => pos,
+            _ => return Ok(()),
+        }
+    }
+    else if len >= 3 && marker[..3] == *b"ID3" {
+        let mut editor = Id3v2Editor::read(&mut source)?;
+
+        if !edit(&mut editor)? {
+            return Ok(());
+        }
+
+        if !editor.is_empty() {
+            editor.write(&mut tag, ID3V2_PADDING)?;
+        }
+
+        source.pos()
+    }
+    else if is_mpeg_file(path) {
+        let mut editor = Id3v2Editor::new();
+
+        if !edit(&mut editor)? || editor.is_empty() {
+            return Ok(());
+        }
+
+        editor.write(&mut tag, ID3V2_PADDING)?;
+
+        0
+    }
+    else {
+        return unsupported_error("tag: unsupported file format");
+    };
+
+    rewrite_file(path, &tag, data_pos)
+}
+
+/// Edits the Vorbis COMMENT of a FLAC file, and writes all the metadata blocks. Returns the
+/// position of the audio data, or `None` if nothing was edited.
+fn edit_flac<F>(source: &mut MediaSourceStream, tag: &mut Vec<u8>, edit: F) -> Result<Option<u64>>
+where
+    F: FnOnce(&mut dyn TagEditor) -> Result<bool>,
+{
+    // Skip the stream marker.
+    source.ignore_bytes(4)?;
+
+    let mut blocks = Vec::new();
+
+    loop {
+        let header = source.read_u8()?;
+        let len = source.read_be_u24()?;
+
+        blocks.push((header & 0x7f, source.read_boxed_slice_exact(len as usize)?));
+
+        if header & 0x80 != 0 {
+            break;
+        }
+    }
+
+    let pos = blocks.iter().position(|(block_type, _)| *block_type == FLAC_VORBIS_COMMENT);
+
+    let mut editor = match pos {
+        Some(i) => VorbisCommentEditor::read(&mut BufReader::new(&blocks[i].1))?,
+        _ => VorbisCommentEditor::new(VENDOR),
+    };
+
+    if !edit(&mut editor)? {
+        return Ok(None);
+    }
+
+    let mut comment = Vec::new();
+    editor.write(&mut comment)?;
+
+    if comment.len() > FLAC_MAX_BLOCK_LEN {
+        return unsupported_error("tag: vorbis comment is too large");
+    }
+
+    match pos {
+        Some(i) => blocks[i].1 = comment.into_boxed_slice(),
+        // The STREAMINFO block must always be first.
+        _ => blocks.insert(1.min(blocks.len()), (FLAC_VORBIS_COMMENT, comment.into_boxed_slice())),
+    }
+
+    tag.extend_from_slice(b"fLaC");
+
+    for (i, (block_type, data)) in blocks.iter().enumerate() {
+        let is_last = i == blocks.len() - 1;
+
+        tag.push(if is_last { 0x80 | block_type } else { *block_type });
+        tag.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+        tag.extend_from_slice(data);
+    }
+
+    Ok(Some(source.pos()))
+}
+
+fn is_mpeg_file(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => MPEG_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+        _ => false,
+    }
+}
+
+/// Safely rewrites the file at `path` with `tag` replacing all data before `data_pos`.
+///
+/// The new file is written to a temporary file in the same directory, which then replaces the
+/// original file. If an error occurs, the original file is untouched.
+fn rewrite_file(path: &Path, tag: &[u8], data_pos: u64) -> Result<()> {
+    let temp_path = temp_path(path);
+
+    let mut temp = OpenOptions::new().write(true).create_new(true).open(&temp_path)?;
+
+    let result = (|| -> Result<()> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(data_pos))?;
+
+        temp.write_all(tag)?;
+        std::io::copy(&mut file, &mut temp)?;
+
+        temp.set_permissions(file.metadata()?.permissions())?;
+        temp.sync_all()?;
+
+        Ok(())
+    })();
+
+    drop(temp);
+
+    match result {
+        Ok(_) => Ok(fs::rename(&temp_path, path)?),
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(err)
+        }
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
@@ -0,0 +1,152 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Watch mode. Monitors a directory for new files, and processes each file while it is still
+//! being written, such as the output of a recorder.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use symphonia::core::errors::Result;
+use symphonia::core::io::MediaSource;
+
+use clap::ArgMatches;
+use log::{error, info};
+
+/// The interval at which the directory, and files being written, are polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A file that has not grown for this long is considered complete.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The number of bytes a new file must have before it is probed. Enough for the headers of most
+/// formats, and the first few packets.
+const READY_LEN: u64 = 64 * 1024;
+
+/// A `MediaSource` for a file that may still be written. Reads at the end of the file wait for
+/// the file to grow, and the file ends once it has not grown for `IDLE_TIMEOUT`. Since the final
+/// length is unknown, the source is not seekable.
+struct GrowingFile {
+    file: File,
+    last_growth: Instant,
+}
+
+impl GrowingFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(GrowingFile { file: File::open(path)?, last_growth: Instant::now() })
+    }
+}
+
+impl Read for GrowingFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let len = self.file.read(buf)?;
+
+            if len > 0 || buf.is_empty() {
+                self.last_growth = Instant::now();
+                return Ok(len);
+            }
+
+            if self.last_growth.elapsed() >= IDLE_TIMEOUT {
+                return Ok(0);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Seek for GrowingFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl MediaSource for GrowingFile {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Lists the regular, non-hidden, files in `dir`.
+fn list_files(dir: &Path) -> io::Result<HashSet<PathBuf>> {
+    let mut files = HashSet::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if !is_hidden && entry.file_type()?.is_file() {
+            files.insert(entry.path());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Waits until `path` has at least `READY_LEN` bytes, or it has stopped growing.
+fn wait_until_ready(path: &Path) -> io::Result<()> {
+    let mut len = fs::metadata(path)?.len();
+    let mut last_growth = Instant::now();
+
+    while len < READY_LEN && last_growth.elapsed() < IDLE_TIMEOUT {
+        thread::sleep(POLL_INTERVAL);
+
+        let new_len = fs::metadata(path)?.len();
+
+        if new_len > len {
+            len = new_len;
+            last_growth = Instant::now();
+        }
+    }
+
+    Ok(())
+}
+
+/// Watches the directory given by the `watch` argument, and runs the selected operating mode on
+/// every file that appears in it. Files present when watching starts are ignored. Never returns
+/// unless the directory can no longer be read.
+pub fn run(args: &ArgMatches) -> Result<i32> {
+    let dir = Path::new(args.value_of("watch").unwrap());
+
+    let mut seen = list_files(dir)?;
+
+    info!("watching {} for new files", dir.display());
+
+    loop {
+        let mut new_files: Vec<PathBuf> = list_files(dir)?.difference(&seen).cloned().collect();
+
+        // Process files in order of their names, which is usually the order they were recorded.
+        new_files.sort();
+
+        for path in new_files {
+            seen.insert(path.clone());
+
+            let result = wait_until_ready(&path)
+                .and_then(|_| GrowingFile::open(&path))
+                .map_err(From::from)
+                .and_then(|file| super::run_source(args, &path, Box::new(file)));
+
+            match result {
+                Ok(0) => (),
+                Ok(code) => error!("{}: failed with exit code {}", path.display(), code),
+                Err(err) => error!("{}: {}", path.display(), err.to_string().to_lowercase()),
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
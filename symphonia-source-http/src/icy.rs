@@ -0,0 +1,216 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ICY (SHOUTcast/Icecast) metadata support.
+//!
+//! A server that supports ICY metadata, and is asked for it with the `Icy-MetaData: 1` request
+//! header, responds with an `icy-metaint` header. The response body then interleaves a block of
+//! metadata after every `icy-metaint` bytes of audio. Each block starts with a byte giving the
+//! length of the block in units of 16 bytes, followed by the metadata, for example,
+//! `StreamTitle='Artist - Title';`, padded with zeros.
+
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+use symphonia_core::meta::StandardTagKey;
+use symphonia_metadata::icy::parse_icy_metadata;
+
+/// The ICY headers of a stream. These describe the station, rather than the current track.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IcyHeaders {
+    /// The name of the station (`icy-name`).
+    pub name: Option<String>,
+    /// The genre of the station (`icy-genre`).
+    pub genre: Option<String>,
+    /// The description of the station (`icy-description`).
+    pub description: Option<String>,
+    /// The homepage of the station (`icy-url`).
+    pub url: Option<String>,
+    /// The nominal bitrate of the stream in kilobits per second (`icy-br`).
+    pub bitrate: Option<u32>,
+}
+
+impl IcyHeaders {
+    /// Reads the ICY headers using `header` to get the value of a header by name. Returns `None`
+    /// if there are no ICY headers.
+    pub(crate) fn read<'a, F>(header: F) -> Option<IcyHeaders>
+    where
+        F: Fn(&str) -> Option<&'a str>,
+    {
+        let get = |name: &str| header(name).map(|value| value.trim().to_string());
+
+        let headers = IcyHeaders {
+            name: get("icy-name"),
+            genre: get("icy-genre"),
+            description: get("icy-description"),
+            url: get("icy-url"),
+            bitrate: header("icy-br").and_then(|br| br.trim().parse().ok()),
+        };
+
+        if headers == IcyHeaders::default() {
+            None
+        }
+        else {
+            Some(headers)
+        }
+    }
+}
+
+/// A `StreamTitle` is a shared handle to the title of the track currently playing on a stream,
+/// as given by the ICY metadata of the stream.
+///
+/// The handle may be cloned and kept after the source is handed to a format reader.
+#[derive(Clone, Debug, Default)]
+pub struct StreamTitle {
+    title: Arc<Mutex<Option<String>>>,
+}
+
+impl StreamTitle {
+    /// Gets the current title, if known.
+    pub fn get(&self) -> Option<String> {
+        self.title.lock().unwrap().clone()
+    }
+
+    fn set(&self, title: String) {
+        *self.title.lock().unwrap() = Some(title);
+    }
+}
+
+/// Parses the value of the `StreamTitle` field of an ICY metadata block.
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    parse_icy_metadata(block)
+        .into_iter()
+        .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+        .map(|tag| tag.value.to_string())
+}
+
+/// An `IcyReader` removes the interleaved ICY metadata from the response body of a stream, and
+/// updates the stream title.
+pub(crate) struct IcyReader<R: Read> {
+    inner: R,
+    metaint: usize,
+    /// The number of audio bytes until the next metadata block.
+    remaining: usize,
+    title: StreamTitle,
+}
+
+impl<R: Read> IcyReader<R> {
+    pub(crate) fn new(inner: R, metaint: usize, title: StreamTitle) -> Self {
+        IcyReader { inner, metaint, remaining: metaint, title }
+    }
+
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut len = [0];
+        self.inner.read_exact(&mut len)?;
+
+        let mut block = vec![0; 16 * usize::from(len[0])];
+        self.inner.read_exact(&mut block)?;
+
+        // An empty block, or a block without a title, indicates the title has not changed.
+        if let Some(title) = parse_stream_title(&block) {
+            self.title.set(title);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            match self.read_metadata() {
+                Ok(_) => self.remaining = self.metaint,
+                // The stream ended cleanly if it ended exactly before a metadata block.
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let len = buf.len().min(self.remaining);
+        let len = self.inner.read(&mut buf[..len])?;
+
+        self.remaining -= len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::{parse_stream_title, IcyHeaders, IcyReader, StreamTitle};
+
+    /// Builds a metadata block for `text`.
+    fn block(text: &str) -> Vec<u8> {
+        let len = (text.len() + 15) / 16;
+        let mut block = vec![len as u8];
+        block.extend_from_slice(text.as_bytes());
+        block.resize(1 + 16 * len, 0);
+        block
+    }
+
+    #[test]
+    fn verify_parse_stream_title() {
+        let title = parse_stream_title(b"StreamTitle='A - B';StreamUrl='';\0\0");
+        assert_eq!(title.as_deref(), Some("A - B"));
+
+        assert_eq!(parse_stream_title(b"StreamUrl='x';"), None);
+        assert_eq!(parse_stream_title(b""), None);
+
+        // ISO-8859-1 text, a title without a trailing semicolon, and a title containing a quote
+        // followed by a semicolon.
+        assert_eq!(parse_stream_title(b"StreamTitle='Caf\xe9';").as_deref(), Some("Café"));
+        assert_eq!(parse_stream_title(b"StreamTitle='A - B'\0\0").as_deref(), Some("A - B"));
+        assert_eq!(parse_stream_title(b"StreamTitle='It';s';").as_deref(), Some("It';s"));
+    }
+
+    #[test]
+    fn verify_icy_headers() {
+        let headers = IcyHeaders::read(|name| match name {
+            "icy-name" => Some("Radio "),
+            "icy-br" => Some("128"),
+            _ => None,
+        });
+
+        let expected =
+            IcyHeaders { name: Some("Radio".into()), bitrate: Some(128), ..Default::default() };
+
+        assert_eq!(headers, Some(expected));
+        assert_eq!(IcyHeaders::read(|_| None), None);
+    }
+
+    #[test]
+    fn verify_icy_reader() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[0, 1, 2, 3]);
+        body.extend(block("StreamTitle='First';"));
+        body.extend_from_slice(&[4, 5, 6, 7]);
+        // An empty block does not change the title.
+        body.push(0);
+        body.extend_from_slice(&[8, 9]);
+
+        let title = StreamTitle::default();
+
+        let mut reader = IcyReader::new(Cursor::new(body), 4, title.clone());
+
+        // Reads stop at metadata blocks.
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(title.get(), None);
+
+        let mut audio = buf[..4].to_vec();
+        reader.read_to_end(&mut audio).unwrap();
+
+        assert_eq!(audio, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(title.get().as_deref(), Some("First"));
+    }
+}
@@ -0,0 +1,487 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![warn(rust_2018_idioms)]
+#![forbid(unsafe_code)]
+// The following lints are allowed in all Symphonia crates. Please see clippy.toml for their
+// justification.
+#![allow(clippy::comparison_chain)]
+#![allow(clippy::excessive_precision)]
+#![allow(clippy::identity_op)]
+#![allow(clippy::manual_range_contains)]
+
+//! An HTTP(S) [`MediaSource`] for Project Symphonia.
+//!
+//! An [`HttpSource`] streams a remote file, or a web radio station, such that it may be handed
+//! straight to the probe or a format reader. If the server accepts range requests, the source is
+//! seekable. If the connection drops, the source reconnects, with a backoff, and resumes where it
+//! left off. For web radio, the source negotiates ICY metadata, removes it from the stream, and
+//! exposes the station headers and the current stream title.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::Duration;
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::MediaSource;
+
+use log::{debug, warn};
+
+mod icy;
+
+pub use icy::{IcyHeaders, StreamTitle};
+
+use icy::IcyReader;
+
+/// Options for an `HttpSource`.
+#[derive(Clone, Debug)]
+pub struct HttpSourceOptions {
+    /// Request ICY metadata from the server. Default: `true`.
+    pub icy_metadata: bool,
+    /// The maximum number of consecutive attempts to reconnect after the connection drops before
+    /// giving up. Attempts are consecutive until a reconnection delivers more bytes. Default: `5`.
+    pub max_retries: u32,
+    /// The delay before the first attempt to reconnect. The delay doubles after every failed
+    /// attempt. Default: 500 ms.
+    pub retry_delay: Duration,
+    /// The timeout for connecting, and for each read. Default: 10 s.
+    pub timeout: Duration,
+}
+
+impl Default for HttpSourceOptions {
+    fn default() -> Self {
+        HttpSourceOptions {
+            icy_metadata: true,
+            max_retries: 5,
+            retry_delay: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The body of a response.
+type Body = Box<dyn Read + Send + Sync>;
+
+/// Converts an HTTP client error into an I/O error.
+fn http_error(err: ureq::Error) -> io::Error {
+    match err {
+        ureq::Error::Transport(err) => io::Error::new(io::ErrorKind::Other, err.to_string()),
+        ureq::Error::Status(code, _) => {
+            io::Error::new(io::ErrorKind::Other, format!("http: server returned status {}", code))
+        }
+    }
+}
+
+/// Gets the total length of the resource from the value of a `Content-Range` header of the form
+/// `bytes <start>-<end>/<length>`.
+fn parse_content_range(value: &str) -> Option<u64> {
+    value.trim().strip_prefix("bytes ")?.rsplit('/').next()?.parse().ok()
+}
+
+/// Offsets `base` by `delta`, or returns `None` if the result is out-of-range.
+fn offset(base: u64, delta: i64) -> Option<u64> {
+    if delta >= 0 {
+        base.checked_add(delta as u64)
+    }
+    else {
+        base.checked_sub(delta.unsigned_abs())
+    }
+}
+
+/// An `HttpSource` is a `MediaSource` that streams a resource over HTTP(S).
+pub struct HttpSource {
+    agent: ureq::Agent,
+    url: String,
+    opts: HttpSourceOptions,
+    body: Option<Body>,
+    /// The position of the next byte of the resource.
+    pos: u64,
+    /// The length of the resource, if known.
+    len: Option<u64>,
+    accepts_ranges: bool,
+    icy_metaint: Option<usize>,
+    icy_headers: Option<IcyHeaders>,
+    title: StreamTitle,
+    /// The number of attempts to reconnect since bytes were last read.
+    n_retries: u32,
+    /// The delay before the next attempt to reconnect.
+    retry_delay: Duration,
+}
+
+impl HttpSource {
+    /// Opens the resource at `url`.
+    pub fn open(url: &str, opts: &HttpSourceOptions) -> Result<Self> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(opts.timeout)
+            .timeout_read(opts.timeout)
+            .build();
+
+        let mut source = HttpSource {
+            agent,
+            url: url.to_string(),
+            opts: opts.clone(),
+            body: None,
+            pos: 0,
+            len: None,
+            accepts_ranges: false,
+            icy_metaint: None,
+            icy_headers: None,
+            title: Default::default(),
+            n_retries: 0,
+            retry_delay: opts.retry_delay,
+        };
+
+        source.connect(0)?;
+
+        Ok(source)
+    }
+
+    /// Gets the ICY headers of the stream, if the server sent any.
+    pub fn icy_headers(&self) -> Option<&IcyHeaders> {
+        self.icy_headers.as_ref()
+    }
+
+    /// Gets a handle to the title of the track currently playing on the stream, as given by the
+    /// ICY metadata of the stream.
+    pub fn stream_title(&self) -> StreamTitle {
+        self.title.clone()
+    }
+
+    /// Sends a request for the resource starting at byte `pos`, and replaces the body with the
+    /// body of the response.
+    fn connect(&mut self, pos: u64) -> io::Result<()> {
+        self.body = None;
+
+        let mut request = self.agent.get(&self.url);
+
+        if pos > 0 {
+            request = request.set("Range", &format!("bytes={}-", pos));
+        }
+
+        // ICY metadata is only useful for live streams, and cannot be aligned to a range request.
+        if self.opts.icy_metadata && pos == 0 {
+            request = request.set("Icy-MetaData", "1");
+        }
+
+        let response = request.call().map_err(http_error)?;
+
+        if pos > 0 && response.status() != 206 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "http: server did not honour the range request",
+            ));
+        }
+
+        let content_len = response.header("Content-Length").and_then(|len| len.parse().ok());
+
+        if pos == 0 {
+            self.len = content_len;
+            self.accepts_ranges = response
+                .header("Accept-Ranges")
+                .map_or(false, |value| value.trim().eq_ignore_ascii_case("bytes"));
+            self.icy_metaint = response.header("icy-metaint").and_then(|n| n.trim().parse().ok());
+            self.icy_headers = IcyHeaders::read(|name| response.header(name));
+        }
+        else if let Some(len) = response.header("Content-Range").and_then(parse_content_range) {
+            self.len = Some(len);
+        }
+
+        debug!(
+            "http: connected to {} at byte {} (length={:?}, ranges={}, icy-metaint={:?})",
+            self.url, pos, self.len, self.accepts_ranges, self.icy_metaint
+        );
+
+        let body = response.into_reader();
+
+        self.body = Some(match self.icy_metaint {
+            Some(metaint) if metaint > 0 => {
+                Box::new(IcyReader::new(body, metaint, self.title.clone()))
+            }
+            _ => body,
+        });
+
+        self.pos = pos;
+
+        Ok(())
+    }
+
+    /// Returns true if the stream can be resumed after the connection drops. A resource of known
+    /// length can only be resumed with a range request. A live stream is resumed from wherever it
+    /// is now.
+    fn is_resumable(&self) -> bool {
+        self.is_seekable() || self.len.is_none()
+    }
+
+    /// Reconnects after the connection dropped with `err`, backing off between attempts.
+    ///
+    /// Attempts are counted until bytes are read again, such that a server that accepts, but then
+    /// immediately drops, every connection does not cause endless reconnects.
+    fn reconnect(&mut self, err: io::Error) -> io::Result<()> {
+        if !self.is_resumable() {
+            return Err(err);
+        }
+
+        // A live stream restarts at the beginning of a new response.
+        let pos = if self.is_seekable() { self.pos } else { 0 };
+
+        let mut last_err = err;
+
+        while self.n_retries < self.opts.max_retries {
+            self.n_retries += 1;
+
+            warn!(
+                "http: connection dropped ({}), reconnecting in {:?} (attempt {} of {})",
+                last_err, self.retry_delay, self.n_retries, self.opts.max_retries
+            );
+
+            thread::sleep(self.retry_delay);
+
+            self.retry_delay = self.retry_delay.checked_mul(2).unwrap_or(self.retry_delay);
+
+            match self.connect(pos) {
+                Ok(_) => return Ok(()),
+                Err(err) => last_err = err,
+            }
+        }
+
+        Err(last_err)
+    }
+}
+
+impl Read for HttpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let result = match self.body.as_mut() {
+                Some(body) => body.read(buf),
+                None => Err(io::Error::new(io::ErrorKind::NotConnected, "http: not connected")),
+            };
+
+            match result {
+                // A resource of known length ended early, or a live stream ended. A live stream
+                // never ends by itself, so the server must have closed the connection.
+                Ok(0) if self.len.map_or(true, |len| self.pos < len) => {
+                    self.reconnect(io::Error::from(io::ErrorKind::UnexpectedEof))?
+                }
+                Ok(len) => {
+                    if len > 0 {
+                        self.n_retries = 0;
+                        self.retry_delay = self.opts.retry_delay;
+                    }
+
+                    self.pos += len as u64;
+                    return Ok(len);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => self.reconnect(err)?,
+            }
+        }
+    }
+}
+
+impl Seek for HttpSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if !self.is_seekable() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "http: source is not seekable"));
+        }
+
+        let len = self.len.unwrap_or(0);
+
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(delta) => offset(self.pos, delta),
+            SeekFrom::End(delta) => offset(len, delta),
+        };
+
+        let target = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "http: seek to a negative position")
+        })?;
+
+        if target != self.pos || self.body.is_none() {
+            if target >= len {
+                // There are no bytes to request past the end of the resource.
+                self.body = Some(Box::new(io::empty()));
+                self.pos = target;
+            }
+            else {
+                self.connect(target)?;
+            }
+        }
+
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for HttpSource {
+    /// Returns true if the server accepts range requests, the length of the resource is known, and
+    /// the stream does not have interleaved ICY metadata.
+    fn is_seekable(&self) -> bool {
+        self.accepts_ranges && self.len.is_some() && self.icy_metaint.is_none()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use symphonia_core::io::MediaSource;
+
+    use super::{parse_content_range, HttpSource, HttpSourceOptions};
+
+    /// Serves `body` to `n_requests` requests with support for range requests. The first response
+    /// is truncated to `truncate` bytes of the body. Returns the URL of the server.
+    fn serve(body: Vec<u8>, n_requests: usize, truncate: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().take(n_requests).enumerate() {
+                let mut stream = stream.unwrap();
+
+                let mut start = 0;
+
+                for line in BufReader::new(&mut stream).lines() {
+                    let line = line.unwrap().to_lowercase();
+
+                    if line.is_empty() {
+                        break;
+                    }
+
+                    if let Some(range) = line.strip_prefix("range: bytes=") {
+                        start = range.trim_end_matches('-').parse().unwrap();
+                    }
+                }
+
+                let mut response = if start > 0 {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n",
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    )
+                }
+                else {
+                    "HTTP/1.1 200 OK\r\n".to_string()
+                };
+
+                response += &format!(
+                    "Accept-Ranges: bytes\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len() - start
+                );
+
+                let end = if i == 0 { truncate } else { body.len() };
+
+                stream.write_all(response.as_bytes()).unwrap();
+                stream.write_all(&body[start..end]).unwrap();
+            }
+        });
+
+        url
+    }
+
+    /// Serves a live stream, without a length, to `n_requests` requests. The response to request `i`
+    /// is `bodies[i]`, or an empty body if there is none. Returns the URL of the server.
+    fn serve_live(bodies: Vec<Vec<u8>>, n_requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/live", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().take(n_requests).enumerate() {
+                let mut stream = stream.unwrap();
+
+                for line in BufReader::new(&mut stream).lines() {
+                    if line.unwrap().is_empty() {
+                        break;
+                    }
+                }
+
+                stream.write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n").unwrap();
+                stream.write_all(bodies.get(i).map_or(&[], |body| &body[..])).unwrap();
+            }
+        });
+
+        url
+    }
+
+    fn options() -> HttpSourceOptions {
+        HttpSourceOptions { retry_delay: Duration::from_millis(1), ..Default::default() }
+    }
+
+    #[test]
+    fn verify_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 100-199/1000"), Some(1000));
+        assert_eq!(parse_content_range("bytes 100-199/*"), None);
+    }
+
+    #[test]
+    fn verify_http_source_seek() {
+        let body: Vec<u8> = (0..=255).collect();
+
+        let mut source = HttpSource::open(&serve(body.clone(), 2, body.len()), &options()).unwrap();
+
+        assert!(source.is_seekable());
+        assert_eq!(source.byte_len(), Some(256));
+
+        assert_eq!(source.seek(SeekFrom::End(-56)).unwrap(), 200);
+
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &body[200..]);
+    }
+
+    #[test]
+    fn verify_http_source_reconnect() {
+        let body: Vec<u8> = (0..=255).collect();
+
+        // The first connection drops after 100 bytes.
+        let mut source = HttpSource::open(&serve(body.clone(), 2, 100), &options()).unwrap();
+
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, body);
+    }
+
+    #[test]
+    fn verify_http_source_reconnect_live() {
+        // A live stream is resumed after the server closes the connection.
+        let url = serve_live(vec![vec![1, 2, 3], vec![4, 5, 6]], 2);
+        let mut source = HttpSource::open(&url, &options()).unwrap();
+
+        assert!(!source.is_seekable());
+        assert_eq!(source.byte_len(), None);
+
+        let mut buf = [0; 6];
+        source.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+
+        // Once the server is gone, the source gives up after the maximum number of attempts.
+        assert!(source.read(&mut buf).is_err());
+    }
+
+    #[test]
+    fn verify_http_source_reconnect_limit() {
+        // A server that accepts, but then immediately drops, every connection.
+        let url = serve_live(vec![vec![1]], 100);
+        let mut source = HttpSource::open(&url, &options()).unwrap();
+
+        let mut buf = [0; 4];
+        assert_eq!(source.read(&mut buf).unwrap(), 1);
+
+        // The attempts are counted across reconnects that deliver no bytes.
+        assert!(source.read(&mut buf).is_err());
+    }
+}
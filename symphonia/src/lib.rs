@@ -27,11 +27,14 @@
 //! | Format   | Feature Flag | Gapless* | Default |
 //! |----------|--------------|----------|---------|
 //! | AIFF     | `aiff`       | Yes      | No      |
+//! | AMR      | `amr`        | No       | No      |
 //! | CAF      | `caf`        | No       | No      |
 //! | ISO/MP4  | `isomp4`     | No       | No      |
 //! | MKV/WebM | `mkv`        | No       | Yes     |
 //! | OGG      | `ogg`        | Yes      | Yes     |
+//! | OMA      | `oma`        | No       | No      |
 //! | Wave     | `wav`        | Yes      | Yes     |
+//! | Wave64   | `w64`        | Yes      | No      |
 //!
 //! \* Gapless playback requires support from both the demuxer and decoder.
 //!
@@ -46,7 +49,9 @@
 //! | AAC-LC   | `aac`        | No      | No      |
 //! | ADPCM    | `adpcm`      | Yes     | Yes     |
 //! | ALAC     | `alac`       | Yes     | No      |
+//! | ATRAC3   | `atrac3`     | No      | No      |
 //! | FLAC     | `flac`       | Yes     | Yes     |
+//! | GSM 6.10 | `gsm`        | Yes     | No      |
 //! | MP1      | `mp1`, `mpa` | No      | No      |
 //! | MP2      | `mp2`, `mpa` | No      | No      |
 //! | MP3      | `mp3`, `mpa` | Yes     | No      |
@@ -80,6 +85,11 @@
 //!
 //! **Tip:** All SIMD optimizations can be enabled with the `opt-simd` feature flag.
 //!
+//! ## Async I/O
+//!
+//! Packets may be read from an asynchronous source, without blocking the executor, by enabling
+//! the `async` feature flag. See `core::formats::async_reader` for details.
+//!
 //! # Usage
 //!
 //! The following steps describe a basic usage of Symphonia:
@@ -152,6 +162,10 @@ pub mod default {
         pub use symphonia_codec_adpcm::AdpcmDecoder;
         #[cfg(feature = "alac")]
         pub use symphonia_codec_alac::AlacDecoder;
+        #[cfg(feature = "atrac3")]
+        pub use symphonia_codec_atrac3::Atrac3Decoder;
+        #[cfg(feature = "gsm")]
+        pub use symphonia_codec_gsm::GsmDecoder;
         #[cfg(feature = "pcm")]
         pub use symphonia_codec_pcm::PcmDecoder;
         #[cfg(feature = "vorbis")]
@@ -171,6 +185,8 @@ pub mod default {
         pub use symphonia_bundle_mp3::MpaReader;
         #[cfg(feature = "aac")]
         pub use symphonia_codec_aac::AdtsReader;
+        #[cfg(feature = "amr")]
+        pub use symphonia_format_amr::AmrReader;
         #[cfg(feature = "caf")]
         pub use symphonia_format_caf::CafReader;
         #[cfg(feature = "isomp4")]
@@ -179,10 +195,14 @@ pub mod default {
         pub use symphonia_format_mkv::MkvReader;
         #[cfg(feature = "ogg")]
         pub use symphonia_format_ogg::OggReader;
+        #[cfg(feature = "oma")]
+        pub use symphonia_format_oma::OmaReader;
         #[cfg(feature = "aiff")]
         pub use symphonia_format_riff::AiffReader;
         #[cfg(feature = "wav")]
         pub use symphonia_format_riff::WavReader;
+        #[cfg(feature = "w64")]
+        pub use symphonia_format_riff::Wave64Reader;
 
         #[deprecated = "use `default::formats::MpaReader` instead"]
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
@@ -194,6 +214,55 @@ pub mod default {
     use symphonia_core::codecs::CodecRegistry;
     use symphonia_core::probe::Probe;
 
+    /// The `feature` flags that select codecs and formats, which were enabled in the includer's
+    /// `Cargo.toml`.
+    ///
+    /// To enumerate the codecs and formats themselves, use the `CodecRegistry` returned by
+    /// [`get_codecs`] and the `Probe` returned by [`get_probe`]. The SIMD instruction sets enabled
+    /// by the `opt-simd` flags are listed by [`SIMD_FEATURES`](symphonia_core::dsp::SIMD_FEATURES).
+    pub const ENABLED_FEATURES: &[&str] = &[
+        #[cfg(feature = "aac")]
+        "aac",
+        #[cfg(feature = "adpcm")]
+        "adpcm",
+        #[cfg(feature = "alac")]
+        "alac",
+        #[cfg(feature = "amr")]
+        "amr",
+        #[cfg(feature = "atrac3")]
+        "atrac3",
+        #[cfg(feature = "flac")]
+        "flac",
+        #[cfg(feature = "caf")]
+        "caf",
+        #[cfg(feature = "gsm")]
+        "gsm",
+        #[cfg(feature = "isomp4")]
+        "isomp4",
+        #[cfg(feature = "mkv")]
+        "mkv",
+        #[cfg(feature = "mp1")]
+        "mp1",
+        #[cfg(feature = "mp2")]
+        "mp2",
+        #[cfg(feature = "mp3")]
+        "mp3",
+        #[cfg(feature = "oma")]
+        "oma",
+        #[cfg(feature = "ogg")]
+        "ogg",
+        #[cfg(feature = "pcm")]
+        "pcm",
+        #[cfg(feature = "aiff")]
+        "aiff",
+        #[cfg(feature = "vorbis")]
+        "vorbis",
+        #[cfg(feature = "wav")]
+        "wav",
+        #[cfg(feature = "w64")]
+        "w64",
+    ];
+
     lazy_static! {
         static ref CODEC_REGISTRY: CodecRegistry = {
             let mut registry = CodecRegistry::new();
@@ -245,9 +314,15 @@ pub mod default {
         #[cfg(feature = "alac")]
         registry.register_all::<codecs::AlacDecoder>();
 
+        #[cfg(feature = "atrac3")]
+        registry.register_all::<codecs::Atrac3Decoder>();
+
         #[cfg(feature = "flac")]
         registry.register_all::<codecs::FlacDecoder>();
 
+        #[cfg(feature = "gsm")]
+        registry.register_all::<codecs::GsmDecoder>();
+
         #[cfg(any(feature = "mp1", feature = "mp2", feature = "mp3"))]
         registry.register_all::<codecs::MpaDecoder>();
 
@@ -270,6 +345,9 @@ pub mod default {
         #[cfg(feature = "aac")]
         probe.register_all::<formats::AdtsReader>();
 
+        #[cfg(feature = "amr")]
+        probe.register_all::<formats::AmrReader>();
+
         #[cfg(feature = "caf")]
         probe.register_all::<formats::CafReader>();
 
@@ -288,9 +366,15 @@ pub mod default {
         #[cfg(feature = "wav")]
         probe.register_all::<formats::WavReader>();
 
+        #[cfg(feature = "w64")]
+        probe.register_all::<formats::Wave64Reader>();
+
         #[cfg(feature = "ogg")]
         probe.register_all::<formats::OggReader>();
 
+        #[cfg(feature = "oma")]
+        probe.register_all::<formats::OmaReader>();
+
         #[cfg(feature = "mkv")]
         probe.register_all::<formats::MkvReader>();
 
@@ -0,0 +1,342 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![warn(rust_2018_idioms)]
+#![forbid(unsafe_code)]
+// Justification: Fields on DecoderOptions and FormatOptions may change at any time, but xtask
+// doesn't want to be updated every time those fields change, therefore always fill in the
+// remaining fields with default values.
+#![allow(clippy::needless_update)]
+
+//! Development tasks for Project Symphonia. Run with `cargo xtask <COMMAND>`.
+//!
+//! The conformance fixtures listed in `fixtures/fixtures.txt` are fetched into `fixtures/cache`,
+//! and verified against their SHA-256 hashes. Each fixture is decoded, and the hash of the decoded audio
+//! is compared against the golden output recorded in `fixtures/golden.txt`. The golden outputs
+//! are only updated by an explicit `bless` of the fixtures whose output is expected to change.
+
+mod manifest;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::checksum::Md5;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSourceStream, Monitor};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use clap::Arg;
+use log::error;
+use sha2::{Digest, Sha256};
+
+use manifest::{Fixture, Golden};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The paths of the fixture files.
+struct Paths {
+    /// The fixtures directory.
+    root: PathBuf,
+    /// The fixture manifest.
+    fixtures: PathBuf,
+    /// The golden outputs.
+    golden: PathBuf,
+    /// The directory fixtures are fetched into.
+    cache: PathBuf,
+}
+
+impl Paths {
+    fn new() -> Self {
+        // The xtask crate is located in the root of the workspace.
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().join("fixtures");
+
+        Paths {
+            fixtures: root.join("fixtures.txt"),
+            golden: root.join("golden.txt"),
+            cache: root.join("cache"),
+            root,
+        }
+    }
+
+    fn fixture(&self, fixture: &Fixture) -> PathBuf {
+        self.cache.join(&fixture.name)
+    }
+}
+
+/// Formats a hash as a lowercase hexadecimal string.
+fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Computes the SHA-256 hash of the file at `path`.
+fn file_sha256(path: &Path) -> Result<String> {
+    Ok(hex(&Sha256::digest(&fs::read(path)?)))
+}
+
+/// Selects the fixtures named in `names`, or all fixtures if no names are given.
+fn select(fixtures: Vec<Fixture>, names: &[&str]) -> Result<Vec<Fixture>> {
+    if names.is_empty() {
+        return Ok(fixtures);
+    }
+
+    if let Some(name) = names.iter().find(|&&name| !fixtures.iter().any(|f| f.name == name)) {
+        return Err(format!("unknown fixture {}", name).into());
+    }
+
+    Ok(fixtures.into_iter().filter(|f| names.contains(&f.name.as_str())).collect())
+}
+
+/// Fetches a fixture, if it is not cached, and verifies its hash.
+fn fetch(paths: &Paths, fixture: &Fixture) -> Result<()> {
+    let path = paths.fixture(fixture);
+
+    if path.exists() && file_sha256(&path)? == fixture.sha256 {
+        return Ok(());
+    }
+
+    println!("fetching {} from {}", fixture.name, fixture.url);
+
+    fs::create_dir_all(&paths.cache)?;
+
+    // Download to a temporary file such that an interrupted download is never mistaken for a
+    // cached fixture.
+    let partial = path.with_extension("partial");
+
+    if fixture.url.contains("://") {
+        let status = Command::new("curl")
+            .args(["--fail", "--location", "--silent", "--show-error", "--output"])
+            .arg(&partial)
+            .arg(&fixture.url)
+            .status()
+            .map_err(|err| format!("failed to run curl: {}", err))?;
+
+        if !status.success() {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("failed to fetch {}", fixture.name).into());
+        }
+    }
+    else {
+        // The fixture is kept in the repository.
+        fs::copy(paths.root.join(&fixture.url), &partial)
+            .map_err(|err| format!("failed to copy {}: {}", fixture.name, err))?;
+    }
+
+    let sha256 = file_sha256(&partial)?;
+
+    if sha256 != fixture.sha256 {
+        fs::remove_file(&partial)?;
+        return Err(format!(
+            "{}: expected sha256 {}, got {}",
+            fixture.name, fixture.sha256, sha256
+        )
+        .into());
+    }
+
+    fs::rename(&partial, &path)?;
+
+    Ok(())
+}
+
+/// Decodes the default track of a fixture, and computes the golden output. The golden output is
+/// the MD5 hash of the decoded audio converted to interleaved, little-endian, signed 16-bit
+/// samples.
+fn decode(paths: &Paths, fixture: &Fixture) -> Result<Golden> {
+    let path = paths.fixture(fixture);
+
+    if !path.exists() {
+        return Err(format!("{} is not fetched, run `cargo xtask fetch`", fixture.name).into());
+    }
+
+    let mss = MediaSourceStream::new(Box::new(File::open(&path)?), Default::default());
+
+    let mut hint = Hint::new();
+
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let format_opts = FormatOptions { enable_gapless: true, ..Default::default() };
+
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, mss, &format_opts, &MetadataOptions::default())?
+        .format;
+
+    let track = format.default_track().ok_or("no default track")?;
+    let track_id = track.id;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions { verify: true, ..Default::default() })?;
+
+    let mut md5 = Md5::default();
+    let mut n_frames = 0;
+    let mut sample_buf = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        // Fixtures are expected to decode without errors.
+        let decoded = decoder.decode(&packet)?;
+
+        let decoded_frames = decoded.frames() as u64;
+
+        if decoded_frames == 0 {
+            continue;
+        }
+
+        let buf = sample_buf.get_or_insert_with(|| {
+            SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec())
+        });
+
+        buf.copy_interleaved_ref(decoded);
+
+        for sample in buf.samples() {
+            md5.process_buf_bytes(&sample.to_le_bytes());
+        }
+
+        n_frames += decoded_frames;
+    }
+
+    Ok(Golden { md5: hex(&md5.md5()), n_frames })
+}
+
+fn run_fetch(paths: &Paths, fixtures: &[Fixture]) -> Result<()> {
+    let mut n_failed = 0;
+
+    for fixture in fixtures {
+        if let Err(err) = fetch(paths, fixture) {
+            error!("{}", err);
+            n_failed += 1;
+        }
+    }
+
+    if n_failed > 0 {
+        return Err(format!("failed to fetch {} fixture(s)", n_failed).into());
+    }
+
+    println!("{} fixture(s) fetched", fixtures.len());
+    Ok(())
+}
+
+fn run_verify(paths: &Paths, fixtures: &[Fixture]) -> Result<()> {
+    let golden = manifest::read_golden(&paths.golden)?;
+
+    let mut n_failed = 0;
+
+    for fixture in fixtures {
+        let result = match (decode(paths, fixture), golden.get(&fixture.name)) {
+            (Ok(actual), Some(expected)) if &actual == expected => "PASS".to_string(),
+            (Ok(actual), Some(expected)) => format!(
+                "FAIL (expected {} frames with md5 {}, got {} frames with md5 {})",
+                expected.n_frames, expected.md5, actual.n_frames, actual.md5
+            ),
+            (Ok(_), None) => "FAIL (no golden output, run `cargo xtask bless`)".to_string(),
+            (Err(err), _) => format!("FAIL ({})", err),
+        };
+
+        if result != "PASS" {
+            n_failed += 1;
+        }
+
+        println!("{}: {}", fixture.name, result);
+    }
+
+    if n_failed > 0 {
+        return Err(format!("{} of {} fixture(s) failed", n_failed, fixtures.len()).into());
+    }
+
+    println!("{} fixture(s) passed", fixtures.len());
+    Ok(())
+}
+
+fn run_bless(paths: &Paths, fixtures: &[Fixture]) -> Result<()> {
+    let mut golden = manifest::read_golden(&paths.golden)?;
+
+    // Decode all fixtures before updating any golden output.
+    let mut blessed = BTreeMap::new();
+
+    for fixture in fixtures {
+        blessed.insert(fixture.name.clone(), decode(paths, fixture)?);
+    }
+
+    for (name, actual) in blessed {
+        match golden.insert(name.clone(), actual.clone()) {
+            Some(expected) if expected == actual => println!("{}: unchanged", name),
+            Some(_) => println!("{}: updated", name),
+            None => println!("{}: added", name),
+        }
+    }
+
+    manifest::write_golden(&paths.golden, &golden)
+}
+
+fn run() -> Result<()> {
+    let fixture_names = Arg::new("FIXTURE").multiple_occurrences(true).help("The fixture names");
+
+    let matches = clap::Command::new("xtask")
+        .about("Project Symphonia development tasks")
+        .subcommand_required(true)
+        .subcommand(
+            clap::Command::new("fetch")
+                .about("Fetch the fixtures, or the named fixtures, and verify their hashes")
+                .arg(fixture_names.clone()),
+        )
+        .subcommand(
+            clap::Command::new("verify")
+                .about(
+                    "Decode the fixtures, or the named fixtures, and compare to the golden outputs",
+                )
+                .arg(fixture_names.clone()),
+        )
+        .subcommand(
+            clap::Command::new("bless")
+                .about("Decode the named fixtures, and update their golden outputs")
+                .arg(fixture_names.required_unless_present("all"))
+                .arg(Arg::new("all").long("all").help("Update the golden outputs of all fixtures")),
+        )
+        .get_matches();
+
+    let paths = Paths::new();
+
+    let fixtures = manifest::parse_fixtures(&fs::read_to_string(&paths.fixtures)?)
+        .map_err(|err| format!("{}: {}", paths.fixtures.display(), err))?;
+
+    let (command, args) = matches.subcommand().unwrap();
+
+    let names: Vec<&str> = args.values_of("FIXTURE").map(|v| v.collect()).unwrap_or_default();
+
+    let fixtures = select(fixtures, &names)?;
+
+    match command {
+        "fetch" => run_fetch(&paths, &fixtures),
+        "verify" => run_verify(&paths, &fixtures),
+        "bless" => run_bless(&paths, &fixtures),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    pretty_env_logger::init();
+
+    if let Err(err) = run() {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
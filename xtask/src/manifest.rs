@@ -0,0 +1,162 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parsing and writing of the fixture manifest and golden output files.
+//!
+//! Both files are plain text with one entry per line, and fields separated by whitespace. Empty
+//! lines, and lines starting with `#`, are ignored.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::Result;
+
+/// A conformance fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    /// The name of the fixture, and the name of the file it is cached as.
+    pub name: String,
+    /// The SHA-256 hash of the fixture file as a lowercase hexadecimal string.
+    pub sha256: String,
+    /// The URL the fixture is fetched from, or the path of the fixture relative to the fixtures
+    /// directory if it is kept in the repository.
+    pub url: String,
+}
+
+/// The golden output of a fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Golden {
+    /// The MD5 hash of the decoded audio as a lowercase hexadecimal string.
+    pub md5: String,
+    /// The number of decoded frames.
+    pub n_frames: u64,
+}
+
+/// Iterates over the fields of every entry in `text`.
+fn entries(text: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| (n, line.split_whitespace().collect()))
+}
+
+/// Returns true if `hash` is a lowercase hexadecimal string of `len` bytes.
+fn is_hash(hash: &str, len: usize) -> bool {
+    hash.len() == 2 * len && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Parses a fixture manifest. Each entry has the form: `<name> <sha256> <url>`.
+pub fn parse_fixtures(text: &str) -> Result<Vec<Fixture>> {
+    let mut fixtures: Vec<Fixture> = Vec::new();
+
+    for (n, fields) in entries(text) {
+        let (name, sha256, url) = match fields[..] {
+            [name, sha256, url] => (name, sha256, url),
+            _ => return Err(format!("line {}: expected <name> <sha256> <url>", n).into()),
+        };
+
+        if name.contains(|c| c == '/' || c == '\\') {
+            return Err(format!("line {}: fixture name must not be a path", n).into());
+        }
+
+        if !is_hash(sha256, 32) {
+            return Err(format!("line {}: invalid sha256 hash", n).into());
+        }
+
+        if fixtures.iter().any(|fixture| fixture.name == name) {
+            return Err(format!("line {}: duplicate fixture {}", n, name).into());
+        }
+
+        fixtures.push(Fixture { name: name.into(), sha256: sha256.into(), url: url.into() });
+    }
+
+    Ok(fixtures)
+}
+
+/// Parses a golden output file. Each entry has the form: `<name> <md5> <frames>`.
+pub fn parse_golden(text: &str) -> Result<BTreeMap<String, Golden>> {
+    let mut golden = BTreeMap::new();
+
+    for (n, fields) in entries(text) {
+        let (name, md5, n_frames) = match fields[..] {
+            [name, md5, n_frames] => (name, md5, n_frames),
+            _ => return Err(format!("line {}: expected <name> <md5> <frames>", n).into()),
+        };
+
+        if !is_hash(md5, 16) {
+            return Err(format!("line {}: invalid md5 hash", n).into());
+        }
+
+        let n_frames =
+            n_frames.parse().map_err(|_| format!("line {}: invalid number of frames", n))?;
+
+        golden.insert(name.into(), Golden { md5: md5.into(), n_frames });
+    }
+
+    Ok(golden)
+}
+
+/// Reads the golden output file at `path`. A missing file has no entries.
+pub fn read_golden(path: &Path) -> Result<BTreeMap<String, Golden>> {
+    if !path.exists() {
+        return Ok(Default::default());
+    }
+
+    parse_golden(&fs::read_to_string(path)?)
+}
+
+/// Writes the golden output file at `path`, sorted by fixture name.
+pub fn write_golden(path: &Path, golden: &BTreeMap<String, Golden>) -> Result<()> {
+    let mut file = fs::File::create(path)?;
+
+    writeln!(file, "# Golden outputs of the conformance fixtures.")?;
+    writeln!(file, "# Generated by `cargo xtask bless`. Do not edit.")?;
+    writeln!(file, "#")?;
+    writeln!(file, "# <name> <md5 of the decoded audio> <frames>")?;
+
+    for (name, entry) in golden {
+        writeln!(file, "{} {} {}", name, entry.md5, entry.n_frames)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_fixtures, parse_golden, Golden};
+
+    const MD5: &str = "0123456789abcdef0123456789abcdef";
+    const SHA256: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn verify_parse_fixtures() {
+        let text = format!("# Comment\n\na.flac {} https://example.org/a.flac\n", SHA256);
+
+        let fixtures = parse_fixtures(&text).unwrap();
+        assert_eq!(fixtures.len(), 1);
+        assert_eq!(fixtures[0].name, "a.flac");
+        assert_eq!(fixtures[0].url, "https://example.org/a.flac");
+
+        // Missing fields, invalid hashes, paths, and duplicates are rejected.
+        assert!(parse_fixtures("a.flac https://example.org/a.flac").is_err());
+        assert!(parse_fixtures("a.flac 0123 https://example.org/a.flac").is_err());
+        assert!(parse_fixtures(&format!("a.flac {} https://example.org/a.flac", MD5)).is_err());
+        assert!(parse_fixtures(&format!("../a.flac {} https://example.org", SHA256)).is_err());
+        assert!(parse_fixtures(&format!("{}{}", text, text)).is_err());
+    }
+
+    #[test]
+    fn verify_parse_golden() {
+        let golden = parse_golden(&format!("a.flac {} 4096\n", MD5)).unwrap();
+        assert_eq!(golden["a.flac"], Golden { md5: MD5.into(), n_frames: 4096 });
+
+        assert!(parse_golden(&format!("a.flac {} many", MD5)).is_err());
+    }
+}